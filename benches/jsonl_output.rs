@@ -57,6 +57,8 @@ fn bench_jsonl_file_entry(c: &mut Criterion) {
                 is_dir: false,
                 is_symlink: false,
                 permissions: "rw-r--r--".to_string(),
+                git_status: None,
+                last_commit: None,
             };
             let _jsonl = record.to_jsonl().unwrap();
             black_box(&_jsonl);
@@ -0,0 +1,56 @@
+//! Benchmark for pattern detection on large texts
+//!
+//! Runs `PatternDetector::detect_patterns` over a log-like text on one
+//! thread and on the default rayon pool, to show the gain from scanning
+//! line-aligned chunks in parallel.
+//!
+//! The text size defaults to 8 MiB; set `AI_BENCH_PATTERN_MB` for other
+//! sizes, e.g. `AI_BENCH_PATTERN_MB=64 cargo bench --bench pattern_detection`.
+
+use ai_coreutils::ml_ops::PatternDetector;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn log_text(bytes: usize) -> String {
+    let mut text = String::with_capacity(bytes + 256);
+    let mut i = 0u64;
+    while text.len() < bytes {
+        let line = match i % 3 {
+            0 => format!(
+                "2026-01-02T03:04:{:02}Z INFO request from 10.0.{}.{} to https://api.example.com/v1/items/{}\n",
+                i % 60,
+                i % 256,
+                i % 200,
+                i
+            ),
+            1 => format!("user{}@example.com updated /srv/data/records/{}.json\n", i, i),
+            _ => format!("plain log line with nothing to find {}\n", i),
+        };
+        text.push_str(&line);
+        i += 1;
+    }
+    text
+}
+
+fn bench_detect_patterns(c: &mut Criterion) {
+    let megabytes = std::env::var("AI_BENCH_PATTERN_MB")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(8);
+    let text = log_text(megabytes << 20);
+    let detector = PatternDetector::new().unwrap();
+    let single = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+
+    let mut group = c.benchmark_group(format!("detect_patterns_{}mb", megabytes));
+    group.sample_size(10);
+
+    group.bench_function("one_thread", |b| {
+        b.iter(|| single.install(|| black_box(detector.detect_patterns(&text).len())))
+    });
+
+    group.bench_function("parallel", |b| b.iter(|| black_box(detector.detect_patterns(&text).len())));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_detect_patterns);
+criterion_main!(benches);
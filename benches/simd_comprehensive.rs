@@ -10,6 +10,9 @@ use std::time::Duration;
 // Test data sizes (small, medium, large, very large)
 const SIZES: &[usize] = &[64, 1024, 10_240, 102_400, 1_048_576];
 
+/// Copies large enough to take the non-temporal path and exceed the cache
+const LARGE_COPY_SIZES: &[usize] = &[64 * 1_048_576, 256 * 1_048_576];
+
 fn generate_test_data(size: usize) -> Vec<u8> {
     // Generate mixed ASCII and UTF-8 data
     let mut data = Vec::with_capacity(size);
@@ -127,7 +130,7 @@ fn bench_count_words_scalar(data: &[u8]) -> usize {
     let mut in_word = false;
 
     for &byte in data.iter() {
-        let is_whitespace = byte.is_ascii_whitespace();
+        let is_whitespace = is_space(byte);
         if is_whitespace {
             if in_word {
                 count += 1;
@@ -390,6 +393,29 @@ fn bench_memory_copy(c: &mut Criterion) {
         });
     }
 
+    // Each iteration moves hundreds of MB, so take fewer samples
+    group.sample_size(10);
+    for size in LARGE_COPY_SIZES {
+        let mem_ops = SimdMemoryOps::new();
+
+        group.throughput(Throughput::Bytes(*size as u64));
+        group.bench_with_input(BenchmarkId::new("scalar", size), size, |bencher, len| {
+            let mut dst = vec![0u8; *len];
+            let src = generate_test_data(*len);
+            bencher.iter(|| {
+                bench_memory_copy_scalar(&mut dst, &src);
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("simd", size), size, |bencher, len| {
+            let mut dst = vec![0u8; *len];
+            let src = generate_test_data(*len);
+            bencher.iter(|| {
+                let _ = mem_ops.copy(&mut dst, &src);
+            });
+        });
+    }
+
     group.finish();
 }
 
@@ -476,6 +502,47 @@ fn bench_multi_pattern_search(c: &mut Criterion) {
     group.finish();
 }
 
+// Printable Run Benchmarks
+
+fn bench_printable_runs_scalar(data: &[u8], min_len: usize) -> Vec<std::ops::Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for (i, &byte) in data.iter().enumerate() {
+        if !((0x20..0x7f).contains(&byte) || byte == b'\t') {
+            if i - start >= min_len {
+                runs.push(start..i);
+            }
+            start = i + 1;
+        }
+    }
+    if data.len() - start >= min_len {
+        runs.push(start..data.len());
+    }
+    runs
+}
+
+fn bench_printable_runs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("printable_runs");
+    group.measurement_time(Duration::from_secs(5));
+    group.sample_size(100);
+
+    for size in SIZES {
+        let data = generate_test_data(*size);
+        let scanner = SimdPrintableScanner::new();
+
+        group.throughput(Throughput::Bytes(*size as u64));
+        group.bench_with_input(BenchmarkId::new("scalar", size), &data, |b, data| {
+            b.iter(|| bench_printable_runs_scalar(black_box(data), 4));
+        });
+
+        group.bench_with_input(BenchmarkId::new("simd", size), &data, |b, data| {
+            b.iter(|| scanner.ascii_runs(black_box(data), 4));
+        });
+    }
+
+    group.finish();
+}
+
 // Comprehensive Benchmark Group
 
 fn bench_comprehensive_suite(c: &mut Criterion) {
@@ -490,6 +557,7 @@ fn bench_comprehensive_suite(c: &mut Criterion) {
     bench_memory_copy(c);
     bench_hash_computation(c);
     bench_multi_pattern_search(c);
+    bench_printable_runs(c);
 }
 
 criterion_group!(benches, bench_comprehensive_suite);
@@ -0,0 +1,75 @@
+//! Benchmark for the io_uring backend against the default tokio (epoll) path
+//!
+//! Run with `cargo bench --features io-uring --bench uring_vs_epoll`.
+
+use ai_coreutils::async_ops::async_read_file;
+use ai_coreutils::uring_ops;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use futures::stream::{self, StreamExt};
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+const CONCURRENCY: usize = 64;
+
+fn create_small_files(count: usize) -> (TempDir, Vec<PathBuf>) {
+    let temp_dir = TempDir::new().unwrap();
+    let paths = (0..count)
+        .map(|i| {
+            let path = temp_dir.path().join(format!("file_{}.txt", i));
+            std::fs::write(&path, format!("line {}\n", i).repeat(64)).unwrap();
+            path
+        })
+        .collect();
+    (temp_dir, paths)
+}
+
+fn bench_small_file_reads(c: &mut Criterion) {
+    let mut group = c.benchmark_group("small_file_reads");
+    let rt = tokio::runtime::Runtime::new().unwrap();
+
+    for count in [100, 1000].iter() {
+        let (_dir, paths) = create_small_files(*count);
+
+        group.bench_with_input(BenchmarkId::new("tokio", count), &paths, |b, paths| {
+            b.iter(|| {
+                let results: Vec<_> = rt.block_on(
+                    stream::iter(paths.iter())
+                        .map(|p| async_read_file(p))
+                        .buffer_unordered(CONCURRENCY)
+                        .collect(),
+                );
+                black_box(results);
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("io_uring", count), &paths, |b, paths| {
+            b.iter(|| black_box(uring_ops::read_files(paths, CONCURRENCY)));
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_stat(c: &mut Criterion) {
+    let mut group = c.benchmark_group("stat");
+
+    for count in [100, 1000].iter() {
+        let (_dir, paths) = create_small_files(*count);
+
+        group.bench_with_input(BenchmarkId::new("std", count), &paths, |b, paths| {
+            b.iter(|| {
+                let results: Vec<_> = paths.iter().map(std::fs::symlink_metadata).collect();
+                black_box(results);
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("io_uring", count), &paths, |b, paths| {
+            b.iter(|| black_box(uring_ops::stat_batch(paths)));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_small_file_reads, bench_stat);
+criterion_main!(benches);
@@ -0,0 +1,88 @@
+//! Benchmark for cached entry metadata during directory walks
+//!
+//! Compares classifying and statting each entry through `Path` helpers (one
+//! syscall per check, as ai-find used to) with `fs_utils::EntryInfo`, which
+//! reuses the directory listing's file type and stats each entry once.
+//!
+//! The tree size defaults to 10,000 files; set `AI_BENCH_WALK_FILES` for
+//! larger runs, e.g. `AI_BENCH_WALK_FILES=1000000 cargo bench --bench walk_metadata`.
+
+use ai_coreutils::fs_utils::{DirWalk, SymlinkPolicy, WalkEvent};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::path::Path;
+use tempfile::TempDir;
+use walkdir::WalkDir;
+
+const FILES_PER_DIR: usize = 1000;
+
+fn create_tree(count: usize) -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    for i in 0..count {
+        let dir = temp_dir.path().join(format!("d{}", i / FILES_PER_DIR));
+        if i % FILES_PER_DIR == 0 {
+            std::fs::create_dir(&dir).unwrap();
+        }
+        std::fs::write(dir.join(format!("f{}.txt", i)), b"x").unwrap();
+    }
+    temp_dir
+}
+
+/// Per-entry checks the way ai-find did before `EntryInfo`
+fn path_stats(path: &Path) -> u64 {
+    let mut total = 0;
+    if path.is_file() {
+        if let Ok(metadata) = std::fs::metadata(path) {
+            total += metadata.len();
+        }
+    }
+    if let Ok(metadata) = std::fs::metadata(path) {
+        total += metadata.len();
+    }
+    if path.is_file() || path.is_dir() || path.is_symlink() {
+        total += 1;
+    }
+    total
+}
+
+fn bench_walk_metadata(c: &mut Criterion) {
+    let count = std::env::var("AI_BENCH_WALK_FILES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10_000);
+    let tree = create_tree(count);
+
+    let mut group = c.benchmark_group(format!("walk_metadata_{}", count));
+    group.sample_size(10);
+
+    group.bench_function("path_stats", |b| {
+        b.iter(|| {
+            let total: u64 = WalkDir::new(tree.path())
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .map(|e| path_stats(e.path()))
+                .sum();
+            black_box(total);
+        });
+    });
+
+    group.bench_function("entry_info", |b| {
+        b.iter(|| {
+            let total: u64 = DirWalk::new(tree.path(), SymlinkPolicy::Never)
+                .filter_map(|e| match e {
+                    Ok(WalkEvent::Entry(entry)) => Some(entry),
+                    _ => None,
+                })
+                .map(|entry| {
+                    let kind = (entry.kind() != "unknown") as u64;
+                    entry.size().unwrap_or(0) + kind
+                })
+                .sum();
+            black_box(total);
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_walk_metadata);
+criterion_main!(benches);
@@ -0,0 +1,20 @@
+//! Build script
+//!
+//! With the `grpc` feature, generates the gRPC service from
+//! `proto/ai_coreutils.proto`. `protoc` is taken from `PROTOC` if set, or
+//! else the vendored binary is used.
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/ai_coreutils.proto");
+        println!("cargo:rerun-if-env-changed=PROTOC");
+        if std::env::var_os("PROTOC").is_none() {
+            let protoc = protoc_bin_vendored::protoc_bin_path().expect("no vendored protoc for this platform");
+            std::env::set_var("PROTOC", protoc);
+        }
+        tonic_build::compile_protos("proto/ai_coreutils.proto").expect("failed to compile proto/ai_coreutils.proto");
+    }
+}
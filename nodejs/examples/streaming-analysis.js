@@ -0,0 +1,42 @@
+/**
+ * AI-Coreutils Node.js Example: Streaming Analysis
+ *
+ * This example demonstrates analyzing a large file incrementally, acting on
+ * each chunk as soon as it is ready.
+ */
+
+const { analyzeStream } = require('ai-coreutils/stream');
+const fs = require('fs');
+
+async function main() {
+  // Create a test file
+  const testFile = 'streaming-example.log';
+  const lines = [];
+  for (let i = 0; i < 50000; i++) {
+    lines.push(`request ${i} from user${i % 100}@example.com via 10.0.${i % 256}.1`);
+  }
+  fs.writeFileSync(testFile, lines.join('\n') + '\n');
+
+  try {
+    let total = 0;
+    for await (const chunk of analyzeStream(testFile, 256 * 1024)) {
+      total += chunk.totalPatterns;
+      console.log(
+        `Bytes ${chunk.offset}-${chunk.offset + chunk.length} (from line ${chunk.firstLine}): ` +
+        `${chunk.totalPatterns} patterns, entropy ${chunk.statistics.entropy.toFixed(2)}`
+      );
+      if (chunk.matches.length > 0) {
+        const first = chunk.matches[0];
+        console.log(`  first: ${first.patternType} at byte ${first.start}: ${first.matchedText}`);
+      }
+    }
+    console.log(`Total patterns: ${total}`);
+  } finally {
+    fs.unlinkSync(testFile);
+  }
+}
+
+main().catch((err) => {
+  console.error(err);
+  process.exit(1);
+});
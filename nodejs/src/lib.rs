@@ -2,22 +2,79 @@
 //!
 //! This module provides JavaScript/TypeScript bindings for the core
 //! functionality of AI-Coreutils.
+//!
+//! Byte inputs are taken as `Uint8Array` (which includes `Buffer`) and read
+//! in place for the duration of the call, without copying; byte outputs are
+//! returned as `Buffer`s that take ownership of the Rust allocation. Sizes,
+//! offsets and counts are JS numbers, checked to fit
+//! `Number.MAX_SAFE_INTEGER` rather than truncated, so files over 4 GiB
+//! work. `AnalyzeStream` analyzes a file chunk by chunk on the libuv thread
+//! pool, for agents that process large files incrementally.
+//!
+//! Heavy methods (`analyzeContent`, `classify`, `findPattern`) also come as
+//! `...Async` variants that run on the libuv thread pool and return
+//! Promises, so the event loop stays free. They and `AnalyzeStream.next`
+//! take an optional `AbortSignal`: aborting work that has not started yet
+//! cancels it and rejects its Promise with an `AbortError`; work already
+//! running on a worker thread runs to completion.
 
+use napi::bindgen_prelude::{AbortSignal, AsyncTask, Buffer, Uint8Array};
+use napi::{Env, Task};
 use napi_derive::napi;
-use std::path::PathBuf;
-use std::str;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
 
 // Import from ai-coreutils library
 use ai_coreutils::memory::SafeMemoryAccess;
-use ai_coreutils::simd_ops::{SimdConfig, SimdPatternSearcher, SimdByteCounter, SimdTextProcessor, TextMetrics};
-use ai_coreutils::ml_ops::{PatternDetector, MlConfig, FileClassifier};
+use ai_coreutils::simd_ops::{SimdConfig, SimdTextProcessor};
+use ai_coreutils::ml_ops::{self, PatternDetector, FileClassifier};
+use ai_coreutils::fs_utils::{self, SymlinkPolicy};
+use ai_coreutils::hash::{self, HashAlgorithm, StreamingHasher as Hasher};
+use ai_coreutils::{checksum, snapshot, watch, AiCoreutilsError};
+
+/// Largest integer a JS number holds exactly (`Number.MAX_SAFE_INTEGER`)
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Default `AnalyzeStream` chunk size
+const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// A size, offset or count as a JS number, failing rather than losing precision
+fn js_number(n: u64) -> napi::Result<i64> {
+    if n > MAX_SAFE_INTEGER {
+        return Err(napi::Error::new(
+            napi::Status::GenericFailure,
+            format!("{} exceeds Number.MAX_SAFE_INTEGER", n),
+        ));
+    }
+    Ok(n as i64)
+}
+
+/// A size or offset argument, which must not be negative
+fn usize_arg(n: i64, name: &str) -> napi::Result<usize> {
+    usize::try_from(n).map_err(|_| {
+        napi::Error::new(napi::Status::InvalidArg, format!("{} must be a non-negative integer, got {}", name, n))
+    })
+}
 
 /// Safe memory access for files with SIMD operations
 #[napi(object)]
 pub struct TextMetrics {
-    pub lines: u32,
-    pub words: u32,
-    pub bytes: u32,
+    pub lines: i64,
+    pub words: i64,
+    pub bytes: i64,
+}
+
+impl TextMetrics {
+    fn new(lines: usize, words: usize, bytes: usize) -> napi::Result<Self> {
+        Ok(Self {
+            lines: js_number(lines as u64)?,
+            words: js_number(words as u64)?,
+            bytes: js_number(bytes as u64)?,
+        })
+    }
 }
 
 /// Pattern match result
@@ -25,31 +82,79 @@ pub struct TextMetrics {
 pub struct PatternMatch {
     pub pattern: String,
     pub matched_text: String,
-    pub start: u32,
-    pub end: u32,
+    pub start: i64,
+    pub end: i64,
     pub confidence: f64,
     pub pattern_type: String,
 }
 
+impl PatternMatch {
+    /// Convert a match, moving its offsets `base` bytes along
+    fn new(m: ml_ops::PatternMatch, base: u64) -> napi::Result<Self> {
+        Ok(Self {
+            start: js_number(base + m.start as u64)?,
+            end: js_number(base + m.end as u64)?,
+            pattern_type: format!("{:?}", m.pattern_type),
+            pattern: m.pattern,
+            matched_text: m.matched_text,
+            confidence: m.confidence,
+        })
+    }
+}
+
 /// Text statistics
 #[napi(object)]
 pub struct TextStatistics {
-    pub characters: u32,
-    pub bytes: u32,
-    pub lines: u32,
-    pub words: u32,
+    pub characters: i64,
+    pub bytes: i64,
+    pub lines: i64,
+    pub words: i64,
     pub avg_line_length: f64,
-    pub max_line_length: u32,
+    pub max_line_length: i64,
     pub whitespace_ratio: f64,
     pub entropy: f64,
 }
 
+impl TextStatistics {
+    fn new(statistics: ml_ops::TextStatistics) -> napi::Result<Self> {
+        Ok(Self {
+            characters: js_number(statistics.characters as u64)?,
+            bytes: js_number(statistics.bytes as u64)?,
+            lines: js_number(statistics.lines as u64)?,
+            words: js_number(statistics.words as u64)?,
+            avg_line_length: statistics.avg_line_length,
+            max_line_length: js_number(statistics.max_line_length as u64)?,
+            whitespace_ratio: statistics.whitespace_ratio,
+            entropy: statistics.entropy,
+        })
+    }
+}
+
 /// Content analysis result
 #[napi(object)]
 pub struct ContentAnalysis {
     pub path: String,
-    pub total_patterns: u32,
+    pub total_patterns: i64,
+    pub matches: Vec<PatternMatch>,
+    pub statistics: TextStatistics,
+    pub issues: Vec<String>,
+}
+
+/// Analysis of one chunk of a file, yielded by `AnalyzeStream`
+#[napi(object)]
+pub struct ContentChunk {
+    pub path: String,
+    /// Byte offset of the chunk in the file
+    pub offset: i64,
+    /// Length of the chunk in bytes
+    pub length: i64,
+    /// Line number (1-indexed) of the chunk's first line
+    pub first_line: i64,
+    pub total_patterns: i64,
+    /// Matches, with byte offsets from the start of the file (exact where
+    /// the chunk is valid UTF-8)
     pub matches: Vec<PatternMatch>,
+    /// Statistics of this chunk alone
     pub statistics: TextStatistics,
     pub issues: Vec<String>,
 }
@@ -69,7 +174,7 @@ pub struct FileClassification {
 /// Safe memory access wrapper
 #[napi]
 pub struct MemoryAccess {
-    inner: SafeMemoryAccess,
+    inner: Arc<SafeMemoryAccess>,
 }
 
 #[napi]
@@ -78,14 +183,14 @@ impl MemoryAccess {
     #[napi(constructor)]
     pub fn new(path: String) -> napi::Result<Self> {
         SafeMemoryAccess::new(&path)
-            .map(|inner| Self { inner })
+            .map(|inner| Self { inner: Arc::new(inner) })
             .map_err(|e| napi::Error::new(napi::Status::GenericFailure, e.to_string()))
     }
 
     /// Get the size of the memory-mapped region
     #[napi(getter)]
-    pub fn size(&self) -> u32 {
-        self.inner.size() as u32
+    pub fn size(&self) -> napi::Result<i64> {
+        js_number(self.inner.size() as u64)
     }
 
     /// Get a raw pointer to the memory (as number)
@@ -95,42 +200,50 @@ impl MemoryAccess {
     }
 
     /// Bounds-checked access to a slice of memory
+    ///
+    /// The slice is copied once, into a `Buffer` that owns it; the mapping
+    /// may be unmapped while JS still holds the result.
     #[napi]
-    pub fn get(&self, offset: u32, len: u32) -> Option<Vec<u8>> {
-        self.inner.get(offset as usize, len as usize)
-            .map(|data| data.to_vec())
+    pub fn get(&self, offset: i64, len: i64) -> napi::Result<Option<Buffer>> {
+        let (offset, len) = (usize_arg(offset, "offset")?, usize_arg(len, "length")?);
+        Ok(self.inner.get(offset, len).map(|data| Buffer::from(data.to_vec())))
     }
 
     /// Get a byte at the given offset
     #[napi]
-    pub fn get_byte(&self, offset: u32) -> Option<u32> {
-        self.inner.get_byte(offset as usize).map(|b| b as u32)
+    pub fn get_byte(&self, offset: i64) -> napi::Result<Option<u32>> {
+        Ok(self.inner.get_byte(usize_arg(offset, "offset")?).map(|b| b as u32))
     }
 
     /// Search for a pattern in the memory-mapped region
     #[napi]
-    pub fn find_pattern(&self, pattern: Vec<u8>) -> Vec<u32> {
-        self.inner.find_pattern(&pattern)
-            .into_iter()
-            .map(|offset| offset as u32)
-            .collect()
+    pub fn find_pattern(&self, pattern: Uint8Array) -> napi::Result<Vec<i64>> {
+        find_offsets(&self.inner, &pattern)
+    }
+
+    /// Search for a pattern on the libuv thread pool
+    ///
+    /// The pattern is copied, since JS may change it while the search runs.
+    #[napi]
+    pub fn find_pattern_async(&self, pattern: Uint8Array, signal: Option<AbortSignal>) -> AsyncTask<FindPattern> {
+        let task = FindPattern {
+            inner: Arc::clone(&self.inner),
+            pattern: pattern.to_vec(),
+        };
+        AsyncTask::with_optional_signal(task, signal)
     }
 
     /// Count occurrences of a byte
     #[napi]
-    pub fn count_byte(&self, byte: u32) -> u32 {
-        self.inner.count_byte(byte as u8) as u32
+    pub fn count_byte(&self, byte: u32) -> napi::Result<i64> {
+        js_number(self.inner.count_byte(byte as u8) as u64)
     }
 
     /// Count lines, words, and bytes
     #[napi]
-    pub fn count_text_metrics(&self) -> TextMetrics {
+    pub fn count_text_metrics(&self) -> napi::Result<TextMetrics> {
         let (lines, words, bytes) = self.inner.count_text_metrics();
-        TextMetrics {
-            lines: lines as u32,
-            words: words as u32,
-            bytes: bytes as u32,
-        }
+        TextMetrics::new(lines, words, bytes)
     }
 }
 
@@ -152,32 +265,54 @@ impl TextProcessor {
 
     /// Analyze text and return metrics
     #[napi]
-    pub fn analyze(&self, data: Vec<u8>) -> TextMetrics {
+    pub fn analyze(&self, data: Uint8Array) -> napi::Result<TextMetrics> {
         let metrics = self.inner.analyze(&data);
-        TextMetrics {
-            lines: metrics.lines as u32,
-            words: metrics.words as u32,
-            bytes: metrics.bytes as u32,
-        }
+        TextMetrics::new(metrics.lines, metrics.words, metrics.bytes)
     }
 
     /// Count lines in data
     #[napi]
-    pub fn count_lines(&self, data: Vec<u8>) -> u32 {
-        self.inner.analyze(&data).lines as u32
+    pub fn count_lines(&self, data: Uint8Array) -> napi::Result<i64> {
+        js_number(self.inner.analyze(&data).lines as u64)
     }
 
     /// Count words in data
     #[napi]
-    pub fn count_words(&self, data: Vec<u8>) -> u32 {
-        self.inner.analyze(&data).words as u32
+    pub fn count_words(&self, data: Uint8Array) -> napi::Result<i64> {
+        js_number(self.inner.analyze(&data).words as u64)
+    }
+}
+
+fn find_offsets(inner: &SafeMemoryAccess, pattern: &[u8]) -> napi::Result<Vec<i64>> {
+    inner.find_pattern(pattern)
+        .into_iter()
+        .map(|offset| js_number(offset as u64))
+        .collect()
+}
+
+/// Background task for `MemoryAccess.findPatternAsync`
+pub struct FindPattern {
+    inner: Arc<SafeMemoryAccess>,
+    pattern: Vec<u8>,
+}
+
+impl Task for FindPattern {
+    type Output = Vec<i64>;
+    type JsValue = Vec<i64>;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        find_offsets(&self.inner, &self.pattern)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
     }
 }
 
 /// Pattern detector
 #[napi]
 pub struct PatternDetectorWrapper {
-    inner: PatternDetector,
+    inner: Arc<PatternDetector>,
 }
 
 #[napi]
@@ -186,61 +321,201 @@ impl PatternDetectorWrapper {
     #[napi(constructor)]
     pub fn new() -> napi::Result<Self> {
         PatternDetector::new()
-            .map(|inner| Self { inner })
+            .map(|inner| Self { inner: Arc::new(inner) })
             .map_err(|e| napi::Error::new(napi::Status::GenericFailure, e.to_string()))
     }
 
     /// Detect all patterns in the given text
     #[napi]
-    pub fn detect_patterns(&self, text: String) -> Vec<PatternMatch> {
-        let matches = self.inner.detect_patterns(&text);
-        matches
+    pub fn detect_patterns(&self, text: String) -> napi::Result<Vec<PatternMatch>> {
+        self.inner
+            .detect_patterns(&text)
             .into_iter()
-            .map(|m| PatternMatch {
-                pattern: m.pattern,
-                matched_text: m.matched_text,
-                start: m.start as u32,
-                end: m.end as u32,
-                confidence: m.confidence,
-                pattern_type: format!("{:?}", m.pattern_type),
-            })
+            .map(|m| PatternMatch::new(m, 0))
             .collect()
     }
 
     /// Analyze content and return detailed results
     #[napi]
     pub fn analyze_content(&self, text: String, path: String) -> napi::Result<ContentAnalysis> {
-        let path_buf = PathBuf::from(&path);
-        let analysis = self.inner.analyze_content(&text, &path_buf)
+        analyze(&self.inner, &text, &path)
+    }
+
+    /// Analyze content on the libuv thread pool
+    #[napi]
+    pub fn analyze_content_async(
+        &self,
+        text: String,
+        path: String,
+        signal: Option<AbortSignal>,
+    ) -> AsyncTask<AnalyzeContent> {
+        let task = AnalyzeContent {
+            detector: Arc::clone(&self.inner),
+            text,
+            path,
+        };
+        AsyncTask::with_optional_signal(task, signal)
+    }
+}
+
+fn analyze(detector: &PatternDetector, text: &str, path: &str) -> napi::Result<ContentAnalysis> {
+    let path_buf = PathBuf::from(path);
+    let analysis = detector.analyze_content(text, &path_buf)
+        .map_err(|e| napi::Error::new(napi::Status::GenericFailure, e.to_string()))?;
+
+    Ok(ContentAnalysis {
+        path: analysis.path,
+        total_patterns: js_number(analysis.total_patterns as u64)?,
+        matches: analysis
+            .matches
+            .into_iter()
+            .map(|m| PatternMatch::new(m, 0))
+            .collect::<napi::Result<_>>()?,
+        statistics: TextStatistics::new(analysis.statistics)?,
+        issues: analysis.issues,
+    })
+}
+
+/// Background task for `PatternDetectorWrapper.analyzeContentAsync`
+pub struct AnalyzeContent {
+    detector: Arc<PatternDetector>,
+    text: String,
+    path: String,
+}
+
+impl Task for AnalyzeContent {
+    type Output = ContentAnalysis;
+    type JsValue = ContentAnalysis;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        analyze(&self.detector, &self.text, &self.path)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// Reader behind an `AnalyzeStream`
+struct ChunkReader {
+    path: PathBuf,
+    reader: BufReader<File>,
+    detector: PatternDetector,
+    chunk_size: usize,
+    offset: u64,
+    line: u64,
+}
+
+impl ChunkReader {
+    /// Analyze the next chunk, ending at a line boundary; `None` at the end
+    ///
+    /// Returns the chunk's byte offset, first line number and length with
+    /// its analysis.
+    fn next_chunk(&mut self) -> ai_coreutils::Result<Option<(u64, u64, u64, ml_ops::ContentAnalysis)>> {
+        let mut chunk = Vec::with_capacity(self.chunk_size);
+        (&mut self.reader)
+            .take(self.chunk_size as u64)
+            .read_to_end(&mut chunk)?;
+        if chunk.last().is_some_and(|&b| b != b'\n') {
+            // Finish the line, so no pattern is split between chunks
+            self.reader.read_until(b'\n', &mut chunk)?;
+        }
+        if chunk.is_empty() {
+            return Ok(None);
+        }
+
+        let analysis = self
+            .detector
+            .analyze_content(&String::from_utf8_lossy(&chunk), &self.path)?;
+        let (offset, line, length) = (self.offset, self.line + 1, chunk.len() as u64);
+        self.offset += length;
+        self.line += chunk.iter().filter(|&&b| b == b'\n').count() as u64;
+        Ok(Some((offset, line, length, analysis)))
+    }
+}
+
+/// Incremental analysis of a large file
+///
+/// Each `next()` reads about `chunkSize` bytes (extended to the end of the
+/// line) and analyzes them on the libuv thread pool, resolving to a
+/// `ContentChunk`, or `null` at the end of the file. `stream.js` wraps this
+/// in an async iterator.
+#[napi]
+pub struct AnalyzeStream {
+    reader: Arc<Mutex<ChunkReader>>,
+}
+
+#[napi]
+impl AnalyzeStream {
+    /// Open a file for chunked analysis (`chunkSize` defaults to 1 MiB)
+    #[napi(constructor)]
+    pub fn new(path: String, chunk_size: Option<i64>) -> napi::Result<Self> {
+        let chunk_size = match chunk_size {
+            Some(size) => usize_arg(size, "chunkSize")?.max(1),
+            None => DEFAULT_CHUNK_SIZE,
+        };
+        let file = File::open(&path)
+            .map_err(|e| napi::Error::new(napi::Status::GenericFailure, e.to_string()))?;
+        let detector = PatternDetector::new()
             .map_err(|e| napi::Error::new(napi::Status::GenericFailure, e.to_string()))?;
+        Ok(Self {
+            reader: Arc::new(Mutex::new(ChunkReader {
+                path: PathBuf::from(path),
+                reader: BufReader::new(file),
+                detector,
+                chunk_size,
+                offset: 0,
+                line: 0,
+            })),
+        })
+    }
+
+    /// Analyze the next chunk; resolves to `null` at the end of the file
+    #[napi]
+    pub fn next(&self, signal: Option<AbortSignal>) -> AsyncTask<NextChunk> {
+        let task = NextChunk {
+            reader: Arc::clone(&self.reader),
+        };
+        AsyncTask::with_optional_signal(task, signal)
+    }
+}
+
+/// Background task reading one `AnalyzeStream` chunk
+pub struct NextChunk {
+    reader: Arc<Mutex<ChunkReader>>,
+}
 
-        Ok(ContentAnalysis {
+impl Task for NextChunk {
+    type Output = Option<ContentChunk>;
+    type JsValue = Option<ContentChunk>;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let mut reader = self.reader.lock().unwrap_or_else(|e| e.into_inner());
+        let Some((offset, line, length, analysis)) = reader
+            .next_chunk()
+            .map_err(|e| napi::Error::new(napi::Status::GenericFailure, e.to_string()))?
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(ContentChunk {
             path: analysis.path,
-            total_patterns: analysis.total_patterns as u32,
+            offset: js_number(offset)?,
+            length: js_number(length)?,
+            first_line: js_number(line)?,
+            total_patterns: js_number(analysis.total_patterns as u64)?,
             matches: analysis
                 .matches
                 .into_iter()
-                .map(|m| PatternMatch {
-                    pattern: m.pattern,
-                    matched_text: m.matched_text,
-                    start: m.start as u32,
-                    end: m.end as u32,
-                    confidence: m.confidence,
-                    pattern_type: format!("{:?}", m.pattern_type),
-                })
-                .collect(),
-            statistics: TextStatistics {
-                characters: analysis.statistics.characters as u32,
-                bytes: analysis.statistics.bytes as u32,
-                lines: analysis.statistics.lines as u32,
-                words: analysis.statistics.words as u32,
-                avg_line_length: analysis.statistics.avg_line_length,
-                max_line_length: analysis.statistics.max_line_length as u32,
-                whitespace_ratio: analysis.statistics.whitespace_ratio,
-                entropy: analysis.statistics.entropy,
-            },
+                .map(|m| PatternMatch::new(m, offset))
+                .collect::<napi::Result<_>>()?,
+            statistics: TextStatistics::new(analysis.statistics)?,
             issues: analysis.issues,
-        })
+        }))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
     }
 }
 
@@ -258,20 +533,60 @@ impl FileClassifierWrapper {
 
     /// Classify a file based on its extension and content
     #[napi]
-    pub fn classify(&self, path: String, content: Vec<u8>) -> napi::Result<FileClassification> {
-        let path_buf = PathBuf::from(&path);
-        let classification = FileClassifier::classify(&path_buf, &content)
-            .map_err(|e| napi::Error::new(napi::Status::GenericFailure, e.to_string()))?;
+    pub fn classify(&self, path: String, content: Uint8Array) -> napi::Result<FileClassification> {
+        classify(&path, &content)
+    }
 
-        Ok(FileClassification {
-            path: classification.path,
-            file_type: classification.file_type,
-            confidence: classification.confidence,
-            encoding: classification.encoding,
-            mime_type: classification.mime_type,
-            is_binary: classification.is_binary,
-            language: classification.language,
-        })
+    /// Classify a file on the libuv thread pool
+    ///
+    /// The content is copied, since JS may change it while the task runs.
+    #[napi]
+    pub fn classify_async(
+        &self,
+        path: String,
+        content: Uint8Array,
+        signal: Option<AbortSignal>,
+    ) -> AsyncTask<Classify> {
+        let task = Classify {
+            path,
+            content: content.to_vec(),
+        };
+        AsyncTask::with_optional_signal(task, signal)
+    }
+}
+
+fn classify(path: &str, content: &[u8]) -> napi::Result<FileClassification> {
+    let path_buf = PathBuf::from(path);
+    let classification = FileClassifier::classify(&path_buf, content)
+        .map_err(|e| napi::Error::new(napi::Status::GenericFailure, e.to_string()))?;
+
+    Ok(FileClassification {
+        path: classification.path,
+        file_type: classification.file_type,
+        confidence: classification.confidence,
+        encoding: classification.encoding,
+        mime_type: classification.mime_type,
+        is_binary: classification.is_binary,
+        language: classification.language,
+    })
+}
+
+/// Background task for `FileClassifierWrapper.classifyAsync`
+pub struct Classify {
+    path: String,
+    content: Vec<u8>,
+}
+
+impl Task for Classify {
+    type Output = FileClassification;
+    type JsValue = FileClassification;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        classify(&self.path, &self.content)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
     }
 }
 
@@ -304,6 +619,451 @@ impl SimdConfigWrapper {
     }
 }
 
+/// Incremental digest, fed data in chunks
+#[napi]
+pub struct StreamingHasher {
+    inner: Hasher,
+}
+
+#[napi]
+impl StreamingHasher {
+    /// Start a digest (crc32, xxh64, xxh3 (default) or blake3 if built with it)
+    #[napi(constructor)]
+    pub fn new(algorithm: Option<String>) -> napi::Result<Self> {
+        Ok(Self {
+            inner: Hasher::new(parse_algorithm(algorithm)?),
+        })
+    }
+
+    /// Feed the next chunk of data
+    #[napi]
+    pub fn update(&mut self, data: Uint8Array) {
+        self.inner.update(&data);
+    }
+
+    /// Digest of everything fed so far
+    #[napi]
+    pub fn digest(&self) -> Buffer {
+        Buffer::from(self.inner.finalize())
+    }
+
+    /// Lowercase hex digest of everything fed so far
+    #[napi]
+    pub fn hex_digest(&self) -> String {
+        self.inner.finalize_hex()
+    }
+
+    /// Algorithm name
+    #[napi(getter)]
+    pub fn algorithm(&self) -> String {
+        self.inner.algorithm().as_str().to_string()
+    }
+
+    /// Number of bytes fed so far
+    #[napi(getter)]
+    pub fn bytes_hashed(&self) -> napi::Result<i64> {
+        js_number(self.inner.bytes_hashed())
+    }
+}
+
+fn parse_algorithm(algorithm: Option<String>) -> napi::Result<HashAlgorithm> {
+    match algorithm {
+        Some(name) => name
+            .parse()
+            .map_err(|e: String| napi::Error::new(napi::Status::InvalidArg, e)),
+        None => Ok(HashAlgorithm::default()),
+    }
+}
+
+fn generic_error(e: AiCoreutilsError) -> napi::Error {
+    napi::Error::new(napi::Status::GenericFailure, e.to_string())
+}
+
+/// A path that could not be read, with the reason
+#[napi(object)]
+pub struct PathError {
+    pub path: String,
+    pub message: String,
+}
+
+fn path_errors(errors: Vec<(PathBuf, AiCoreutilsError)>) -> Vec<PathError> {
+    errors
+        .into_iter()
+        .map(|(path, e)| PathError {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })
+        .collect()
+}
+
+/// Recorded contents of one file in a `Manifest`
+#[napi(object)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub digest: String,
+    pub size: i64,
+}
+
+impl ManifestEntry {
+    fn new(path: &Path, entry: checksum::Entry) -> napi::Result<Self> {
+        Ok(Self {
+            path: path.display().to_string(),
+            digest: entry.digest,
+            size: js_number(entry.size)?,
+        })
+    }
+}
+
+/// How a file differs from its manifest entry
+#[napi(object)]
+pub struct Drift {
+    pub path: String,
+    /// `added`, `changed` or `removed`
+    pub kind: String,
+    pub expected: Option<ManifestEntry>,
+    pub actual: Option<ManifestEntry>,
+}
+
+impl Drift {
+    fn new(drift: checksum::Drift) -> napi::Result<Self> {
+        let entry = |entry: Option<checksum::Entry>| entry.map(|e| ManifestEntry::new(&drift.path, e)).transpose();
+        Ok(Self {
+            path: drift.path.display().to_string(),
+            kind: drift.kind.as_str().to_string(),
+            expected: entry(drift.expected)?,
+            actual: entry(drift.actual)?,
+        })
+    }
+}
+
+/// Outcome of `Manifest.verify`
+#[napi(object)]
+pub struct VerifyResult {
+    pub drifts: Vec<Drift>,
+    pub errors: Vec<PathError>,
+}
+
+/// Digests of a set of files, as kept by ai-checksum
+#[napi]
+pub struct Manifest {
+    inner: checksum::Manifest,
+}
+
+#[napi]
+impl Manifest {
+    /// An empty manifest (xxh3 by default)
+    #[napi(constructor)]
+    pub fn new(algorithm: Option<String>) -> napi::Result<Self> {
+        Ok(Self {
+            inner: checksum::Manifest::new(parse_algorithm(algorithm)?),
+        })
+    }
+
+    /// Read a manifest file
+    #[napi(factory)]
+    pub fn load(path: String) -> napi::Result<Self> {
+        checksum::Manifest::load(Path::new(&path))
+            .map(|inner| Self { inner })
+            .map_err(generic_error)
+    }
+
+    /// Atomically write the manifest to a file
+    #[napi]
+    pub fn save(&self, path: String) -> napi::Result<()> {
+        self.inner.save(Path::new(&path)).map_err(generic_error)
+    }
+
+    /// Hash a file and record it
+    #[napi]
+    pub fn insert(&mut self, path: String) -> napi::Result<ManifestEntry> {
+        let entry = self.inner.insert(Path::new(&path)).map_err(generic_error)?.clone();
+        ManifestEntry::new(Path::new(&path), entry)
+    }
+
+    /// Re-hash a file after a change; `null` if its contents are as recorded
+    #[napi]
+    pub fn rehash(&mut self, path: String) -> napi::Result<Option<Drift>> {
+        self.inner
+            .rehash(Path::new(&path))
+            .map_err(generic_error)?
+            .map(Drift::new)
+            .transpose()
+    }
+
+    /// Compare with the files on disk, given every file that exists now
+    #[napi]
+    pub fn verify(&self, paths: Vec<String>) -> napi::Result<VerifyResult> {
+        let current: Vec<PathBuf> = paths.into_iter().map(PathBuf::from).collect();
+        let (drifts, errors) = self.inner.verify(&current);
+        Ok(VerifyResult {
+            drifts: drifts.into_iter().map(Drift::new).collect::<napi::Result<_>>()?,
+            errors: path_errors(errors),
+        })
+    }
+
+    /// Algorithm of every digest
+    #[napi(getter)]
+    pub fn algorithm(&self) -> String {
+        self.inner.algorithm.as_str().to_string()
+    }
+
+    /// Recorded entries, sorted by path
+    #[napi]
+    pub fn entries(&self) -> napi::Result<Vec<ManifestEntry>> {
+        self.inner
+            .files
+            .iter()
+            .map(|(path, entry)| ManifestEntry::new(path, entry.clone()))
+            .collect()
+    }
+}
+
+/// Recorded state of one file in a `TreeSnapshot`
+#[napi(object)]
+pub struct SnapshotEntry {
+    pub path: String,
+    pub size: i64,
+    /// Last modification time, RFC 3339
+    pub mtime: String,
+    pub digest: String,
+}
+
+impl SnapshotEntry {
+    fn new(path: &Path, entry: &snapshot::SnapshotEntry) -> napi::Result<Self> {
+        Ok(Self {
+            path: path.display().to_string(),
+            size: js_number(entry.size)?,
+            mtime: entry.mtime.to_rfc3339(),
+            digest: entry.digest.clone(),
+        })
+    }
+}
+
+/// A file that differs between two states of a tree
+#[napi(object)]
+pub struct SnapshotChange {
+    pub path: String,
+    /// `added`, `changed` or `removed`
+    pub kind: String,
+    pub before: Option<SnapshotEntry>,
+    pub after: Option<SnapshotEntry>,
+}
+
+impl SnapshotChange {
+    fn new(change: snapshot::Change) -> napi::Result<Self> {
+        let entry = |entry: &Option<snapshot::SnapshotEntry>| {
+            entry.as_ref().map(|e| SnapshotEntry::new(&change.path, e)).transpose()
+        };
+        Ok(Self {
+            path: change.path.display().to_string(),
+            kind: change.kind.as_str().to_string(),
+            before: entry(&change.before)?,
+            after: entry(&change.after)?,
+        })
+    }
+}
+
+/// Outcome of `TreeSnapshot.diffLive`
+#[napi(object)]
+pub struct LiveDiff {
+    pub changes: Vec<SnapshotChange>,
+    /// Files whose contents are as recorded
+    pub unchanged: i64,
+    /// Files hashed because they are new or their size or mtime changed
+    pub rehashed: i64,
+    pub errors: Vec<PathError>,
+}
+
+/// Size, mtime and digest of every file under a directory, as kept by ai-snapshot
+#[napi]
+pub struct TreeSnapshot {
+    inner: snapshot::TreeSnapshot,
+    errors: Vec<PathError>,
+}
+
+#[napi]
+impl TreeSnapshot {
+    /// Record every file under `root`, skipping the files in `exclude`
+    ///
+    /// Files that cannot be read are left out and listed in `errors`.
+    #[napi(factory)]
+    pub fn create(root: String, algorithm: Option<String>, exclude: Option<Vec<String>>) -> napi::Result<Self> {
+        let exclude: Vec<PathBuf> = exclude.unwrap_or_default().into_iter().map(PathBuf::from).collect();
+        let (inner, errors) = snapshot::TreeSnapshot::create(Path::new(&root), parse_algorithm(algorithm)?, &exclude)
+            .map_err(generic_error)?;
+        Ok(Self {
+            inner,
+            errors: path_errors(errors),
+        })
+    }
+
+    /// Read a snapshot file
+    #[napi(factory)]
+    pub fn load(path: String) -> napi::Result<Self> {
+        snapshot::TreeSnapshot::load(Path::new(&path))
+            .map(|inner| Self { inner, errors: Vec::new() })
+            .map_err(generic_error)
+    }
+
+    /// Atomically write the snapshot to a file
+    #[napi]
+    pub fn save(&self, path: String) -> napi::Result<()> {
+        self.inner.save(Path::new(&path)).map_err(generic_error)
+    }
+
+    /// Changes from this snapshot to a later one, sorted by path
+    #[napi]
+    pub fn diff(&self, later: &TreeSnapshot) -> napi::Result<Vec<SnapshotChange>> {
+        self.inner
+            .diff(&later.inner)
+            .map_err(generic_error)?
+            .into_iter()
+            .map(SnapshotChange::new)
+            .collect()
+    }
+
+    /// Changes from this snapshot to the tree as it is now
+    ///
+    /// `root` defaults to the directory the snapshot was taken of. Files
+    /// whose size and mtime are unchanged are trusted unless `rehashAll`.
+    #[napi]
+    pub fn diff_live(
+        &self,
+        root: Option<String>,
+        exclude: Option<Vec<String>>,
+        rehash_all: Option<bool>,
+    ) -> napi::Result<LiveDiff> {
+        let root = root.map(PathBuf::from).unwrap_or_else(|| self.inner.root.clone());
+        let exclude: Vec<PathBuf> = exclude.unwrap_or_default().into_iter().map(PathBuf::from).collect();
+        let diff = self
+            .inner
+            .diff_live(&root, &exclude, rehash_all.unwrap_or(false))
+            .map_err(generic_error)?;
+        Ok(LiveDiff {
+            changes: diff.changes.into_iter().map(SnapshotChange::new).collect::<napi::Result<_>>()?,
+            unchanged: js_number(diff.unchanged as u64)?,
+            rehashed: js_number(diff.rehashed as u64)?,
+            errors: path_errors(diff.errors),
+        })
+    }
+
+    /// Absolute path of the directory the snapshot was taken of
+    #[napi(getter)]
+    pub fn root(&self) -> String {
+        self.inner.root.display().to_string()
+    }
+
+    /// Algorithm of every digest
+    #[napi(getter)]
+    pub fn algorithm(&self) -> String {
+        self.inner.algorithm.as_str().to_string()
+    }
+
+    /// When the snapshot was taken, RFC 3339
+    #[napi(getter)]
+    pub fn created(&self) -> String {
+        self.inner.created.to_rfc3339()
+    }
+
+    /// Total size of the recorded files
+    #[napi(getter)]
+    pub fn bytes(&self) -> napi::Result<i64> {
+        js_number(self.inner.bytes())
+    }
+
+    /// Files that could not be read when the snapshot was created
+    #[napi(getter)]
+    pub fn errors(&self) -> Vec<PathError> {
+        self.errors
+            .iter()
+            .map(|e| PathError {
+                path: e.path.clone(),
+                message: e.message.clone(),
+            })
+            .collect()
+    }
+
+    /// Recorded entries, sorted by path
+    #[napi]
+    pub fn entries(&self) -> napi::Result<Vec<SnapshotEntry>> {
+        self.inner
+            .files
+            .iter()
+            .map(|(path, entry)| SnapshotEntry::new(path, entry))
+            .collect()
+    }
+}
+
+/// A change to one file between two `WatchSnapshot`s
+#[napi(object)]
+pub struct WatchEvent {
+    pub path: String,
+    /// `create`, `modify` or `delete`
+    pub kind: String,
+    /// Milliseconds since the Unix epoch when the change was seen
+    pub detected_at: f64,
+}
+
+/// Size, mtime and inode of every file under a set of paths, as polled by ai-watch
+#[napi]
+pub struct WatchSnapshot {
+    inner: watch::Snapshot,
+    errors: Vec<String>,
+}
+
+#[napi]
+impl WatchSnapshot {
+    /// Record every file under `paths`
+    ///
+    /// `followSymlinks` is `never`, `command-line` (default) or `always`.
+    /// Paths that do not exist contribute nothing; directories that cannot
+    /// be read are listed in `errors`.
+    #[napi(factory)]
+    pub fn take(paths: Vec<String>, follow_symlinks: Option<String>) -> napi::Result<Self> {
+        let policy = match follow_symlinks {
+            Some(name) => name
+                .parse()
+                .map_err(|e: String| napi::Error::new(napi::Status::InvalidArg, e))?,
+            None => SymlinkPolicy::default(),
+        };
+        let paths: Vec<PathBuf> = paths.into_iter().map(PathBuf::from).collect();
+        let (inner, errors) = watch::Snapshot::take(&paths, policy);
+        Ok(Self {
+            inner,
+            errors: errors.iter().map(ToString::to_string).collect(),
+        })
+    }
+
+    /// Changes from this snapshot to a newer one, sorted by path
+    #[napi]
+    pub fn diff(&self, newer: &WatchSnapshot) -> Vec<WatchEvent> {
+        self.inner
+            .diff(&newer.inner)
+            .into_iter()
+            .map(|event| WatchEvent {
+                path: event.path.display().to_string(),
+                kind: event.kind.as_str().to_string(),
+                detected_at: event
+                    .detected_at
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0.0, |d| d.as_millis() as f64),
+            })
+            .collect()
+    }
+
+    /// Number of files recorded
+    #[napi(getter)]
+    pub fn file_count(&self) -> napi::Result<i64> {
+        js_number(self.inner.len() as u64)
+    }
+
+    /// Directories that could not be read
+    #[napi(getter)]
+    pub fn errors(&self) -> Vec<String> {
+        self.errors.clone()
+    }
+}
+
 /// Utility functions for common operations
 #[napi]
 pub struct Utils;
@@ -312,19 +1072,69 @@ pub struct Utils;
 impl Utils {
     /// Count lines in a string
     #[napi]
-    pub fn count_lines(text: String) -> u32 {
-        text.lines().count() as u32
+    pub fn count_lines(text: String) -> napi::Result<i64> {
+        js_number(text.lines().count() as u64)
     }
 
     /// Count words in a string
     #[napi]
-    pub fn count_words(text: String) -> u32 {
-        text.split_whitespace().count() as u32
+    pub fn count_words(text: String) -> napi::Result<i64> {
+        js_number(text.split_whitespace().count() as u64)
+    }
+
+    /// Hex digest of a file, read in chunks so memory use stays bounded
+    #[napi]
+    pub fn hash_file(path: String, algorithm: Option<String>) -> napi::Result<String> {
+        let algorithm = parse_algorithm(algorithm)?;
+        std::fs::File::open(&path)
+            .map_err(ai_coreutils::AiCoreutilsError::Io)
+            .and_then(|file| hash::hash_reader(algorithm, file))
+            .map(|hasher| hasher.finalize_hex())
+            .map_err(|e| napi::Error::new(napi::Status::GenericFailure, e.to_string()))
+    }
+
+    /// Atomically replace a file's contents (temp file + fsync + rename)
+    #[napi]
+    pub fn atomic_write(path: String, data: Uint8Array) -> napi::Result<()> {
+        fs_utils::atomic_write(&PathBuf::from(&path), &data)
+            .map_err(|e| napi::Error::new(napi::Status::GenericFailure, e.to_string()))
+    }
+
+    /// Atomically overwrite a file, returning the backup path if one was made
+    #[napi]
+    pub fn safe_overwrite(path: String, data: Uint8Array) -> napi::Result<Option<String>> {
+        fs_utils::safe_overwrite(&PathBuf::from(&path), &data)
+            .map(|backup| backup.map(|p| p.display().to_string()))
+            .map_err(|e| napi::Error::new(napi::Status::GenericFailure, e.to_string()))
+    }
+
+    /// Collapse `.` and `..` components without touching the filesystem
+    #[napi]
+    pub fn normalize_path(path: String) -> String {
+        fs_utils::paths::normalize(Path::new(&path)).to_string_lossy().into_owned()
+    }
+
+    /// Remove a Windows `\\?\` prefix when the path means the same without it
+    #[napi]
+    pub fn strip_verbatim(path: String) -> String {
+        fs_utils::paths::strip_verbatim(Path::new(&path)).to_string_lossy().into_owned()
+    }
+
+    /// Expand a leading `~` to the home directory
+    #[napi]
+    pub fn expand_home(path: String) -> String {
+        fs_utils::paths::expand_home(Path::new(&path)).to_string_lossy().into_owned()
+    }
+
+    /// `path` relative to `base`, computed lexically; null if there is none
+    #[napi]
+    pub fn relative_path(path: String, base: String) -> Option<String> {
+        fs_utils::paths::relative_to(Path::new(&path), Path::new(&base)).map(|p| p.to_string_lossy().into_owned())
     }
 
     /// Check if content appears to be binary
     #[napi]
-    pub fn is_binary(content: Vec<u8>) -> bool {
+    pub fn is_binary(content: Uint8Array) -> bool {
         if content.is_empty() {
             return false;
         }
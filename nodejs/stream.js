@@ -0,0 +1,31 @@
+/**
+ * Async iteration over AnalyzeStream chunks
+ *
+ * `for await (const chunk of analyzeStream(path))` analyzes a file piece by
+ * piece on the libuv thread pool, so agents can act on early results before
+ * the whole file has been read.
+ */
+
+const { AnalyzeStream } = require('./index.js');
+
+/**
+ * Analyze a file chunk by chunk
+ *
+ * @param {string} path - File to analyze
+ * @param {number} [chunkSize] - Approximate bytes per chunk (default 1 MiB)
+ * @param {AbortSignal} [signal] - Stops the iteration with an AbortError
+ * @returns {AsyncGenerator<import('./index').ContentChunk>}
+ */
+async function* analyzeStream(path, chunkSize, signal) {
+  const stream = new AnalyzeStream(path, chunkSize);
+  for (;;) {
+    signal?.throwIfAborted();
+    const chunk = await stream.next(signal);
+    if (chunk === null) {
+      return;
+    }
+    yield chunk;
+  }
+}
+
+module.exports = { analyzeStream };
@@ -0,0 +1,139 @@
+/**
+ * Parity tests: the fixtures in tests/fixtures/parity/cases.json run through
+ * the Node.js bindings, as they do through the Rust library
+ * (tests/integration_tests.rs, mod parity) and the Python bindings
+ * (python/tests/test_parity.py).
+ *
+ * Run with `npm test` after `npm run build:debug`.
+ */
+
+const assert = require('node:assert/strict');
+const fs = require('node:fs');
+const os = require('node:os');
+const path = require('node:path');
+
+const {
+  Manifest,
+  PatternDetectorWrapper,
+  StreamingHasher,
+  TextProcessor,
+  TreeSnapshot,
+  WatchSnapshot,
+} = require('../index.js');
+
+const cases = JSON.parse(
+  fs.readFileSync(path.join(__dirname, '..', '..', 'tests', 'fixtures', 'parity', 'cases.json'), 'utf8')
+);
+
+/**
+ * Make the files under `root` exactly `files` (relative path to contents)
+ *
+ * Files that already have their contents are left alone, so their mtime
+ * does not change.
+ */
+function writeTree(root, files) {
+  for (const entry of fs.readdirSync(root, { recursive: true, withFileTypes: true })) {
+    const full = path.join(entry.parentPath ?? entry.path, entry.name);
+    const relative = path.relative(root, full).split(path.sep).join('/');
+    if (entry.isFile() && !(relative in files)) {
+      fs.rmSync(full);
+    }
+  }
+  return Object.entries(files).map(([relative, contents]) => {
+    const full = path.join(root, relative);
+    const current = fs.existsSync(full) ? fs.readFileSync(full, 'utf8') : null;
+    if (current !== contents) {
+      fs.mkdirSync(path.dirname(full), { recursive: true });
+      fs.writeFileSync(full, contents);
+    }
+    return full;
+  });
+}
+
+/** `p` relative to `root`, with forward slashes; relative paths are kept */
+function relative(root, p) {
+  return (path.isAbsolute(p) ? path.relative(root, p) : p).split(path.sep).join('/');
+}
+
+const tests = {
+  hash() {
+    for (const c of cases.hash) {
+      const hasher = new StreamingHasher(c.algorithm);
+      hasher.update(Buffer.from(c.input));
+      assert.equal(hasher.hexDigest(), c.hex, JSON.stringify(c));
+    }
+  },
+
+  textMetrics() {
+    const processor = new TextProcessor();
+    for (const c of cases.text_metrics) {
+      const metrics = processor.analyze(Buffer.from(c.input));
+      assert.deepEqual(
+        [metrics.lines, metrics.words, metrics.bytes],
+        [c.lines, c.words, c.bytes],
+        JSON.stringify(c)
+      );
+    }
+  },
+
+  patterns() {
+    const detector = new PatternDetectorWrapper();
+    for (const c of cases.patterns) {
+      const found = [...new Set(detector.detectPatterns(c.input).map((m) => m.patternType))].sort();
+      assert.deepEqual(found, c.types, JSON.stringify(c));
+    }
+  },
+
+  trees() {
+    for (const c of cases.trees) {
+      const root = fs.mkdtempSync(path.join(os.tmpdir(), 'ai-coreutils-parity-'));
+      try {
+        const before = writeTree(root, c.before);
+
+        const manifest = new Manifest();
+        for (const p of before) {
+          manifest.insert(p);
+        }
+        const snapshot = TreeSnapshot.create(root);
+        const watched = WatchSnapshot.take([root]);
+
+        const after = writeTree(root, c.after);
+        const later = TreeSnapshot.create(root);
+        const rewatched = WatchSnapshot.take([root]);
+
+        const { drifts, errors } = manifest.verify(after);
+        assert.deepEqual(errors, []);
+        assert.deepEqual(
+          drifts.map((d) => [relative(root, d.path), d.kind]),
+          c.changes,
+          `manifest: ${c.name}`
+        );
+        assert.deepEqual(
+          snapshot.diff(later).map((d) => [relative(root, d.path), d.kind]),
+          c.changes,
+          `snapshot: ${c.name}`
+        );
+        assert.deepEqual(
+          watched.diff(rewatched).map((e) => [relative(root, e.path), e.kind]),
+          c.events,
+          `watch: ${c.name}`
+        );
+      } finally {
+        fs.rmSync(root, { recursive: true, force: true });
+      }
+    }
+  },
+};
+
+let failed = 0;
+for (const [name, test] of Object.entries(tests)) {
+  try {
+    test();
+    console.log(`ok - ${name}`);
+  } catch (e) {
+    failed += 1;
+    console.log(`not ok - ${name}`);
+    console.error(e);
+  }
+}
+process.exitCode = failed ? 1 : 0;
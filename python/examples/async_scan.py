@@ -0,0 +1,60 @@
+"""
+AI-Coreutils Python Example: Async Scanning
+
+This example demonstrates scanning several files concurrently from asyncio
+without blocking the event loop.
+"""
+
+import asyncio
+import os
+import tempfile
+
+from ai_coreutils import read_async, grep_async, analyze_async
+
+async def heartbeat(stop):
+    # Keeps ticking while the scans run, showing the loop is not blocked
+    ticks = 0
+    while not stop.is_set():
+        ticks += 1
+        await asyncio.sleep(0.01)
+    print(f"Event loop ticked {ticks} times during the scans")
+
+async def main():
+    tmpdir = tempfile.mkdtemp()
+    paths = []
+    for i in range(4):
+        path = os.path.join(tmpdir, f"service{i}.log")
+        with open(path, "w") as f:
+            for n in range(10000):
+                level = "ERROR" if n % 997 == 0 else "INFO"
+                f.write(f"{level} request {n} from admin{i}@example.com\n")
+        paths.append(path)
+
+    try:
+        stop = asyncio.Event()
+        ticker = asyncio.create_task(heartbeat(stop))
+
+        # Grep every file at once
+        results = await asyncio.gather(*(grep_async(p, "error", ignore_case=True) for p in paths))
+        for path, matches in zip(paths, results):
+            print(f"{os.path.basename(path)}: {len(matches)} errors")
+            for m in matches[:2]:
+                print(f"  line {m.line_number}: {m.line}")
+
+        # Read and analyze one file
+        data = await read_async(paths[0])
+        print(f"Read {len(data)} bytes")
+        analysis = await analyze_async(paths[0])
+        stats = analysis.statistics().to_dict()
+        print(f"Found {analysis.total_patterns()} patterns, entropy {stats['entropy']:.2f}")
+
+        stop.set()
+        await ticker
+
+    finally:
+        for path in paths:
+            os.remove(path)
+        os.rmdir(tmpdir)
+
+if __name__ == "__main__":
+    asyncio.run(main())
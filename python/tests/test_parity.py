@@ -0,0 +1,40 @@
+"""
+Parity tests: the fixtures in tests/fixtures/parity/cases.json run through
+the Python bindings, as they do through the Rust library
+(tests/integration_tests.rs, mod parity) and the Node.js bindings
+(nodejs/test/test.js).
+"""
+
+import json
+from pathlib import Path
+
+import pytest
+
+from ai_coreutils import PatternDetector, SimdTextProcessor, StreamingHasher
+
+CASES = json.loads(
+    (Path(__file__).resolve().parents[2] / "tests" / "fixtures" / "parity" / "cases.json").read_text(encoding="utf-8")
+)
+
+
+@pytest.mark.parametrize("case", CASES["hash"], ids=lambda c: f"{c['algorithm']}:{c['input'][:16]!r}")
+def test_hash(case):
+    hasher = StreamingHasher(case["algorithm"])
+    hasher.update(case["input"].encode())
+    assert hasher.hexdigest() == case["hex"]
+
+
+@pytest.mark.parametrize("case", CASES["text_metrics"], ids=lambda c: repr(c["input"][:16]))
+def test_text_metrics(case):
+    metrics = SimdTextProcessor().analyze(case["input"].encode())
+    assert (metrics.lines(), metrics.words(), metrics.bytes()) == (case["lines"], case["words"], case["bytes"])
+
+
+@pytest.mark.parametrize("case", CASES["patterns"], ids=lambda c: repr(c["input"][:16]))
+def test_patterns(case):
+    found = {m.to_dict()["pattern_type"] for m in PatternDetector().detect_patterns(case["input"])}
+    assert sorted(found) == case["types"]
+
+
+def test_trees():
+    pytest.skip("manifests, snapshots and watching have no Python bindings yet")
@@ -0,0 +1,508 @@
+//! Archive members as paths (`archives` feature)
+//!
+//! `logs.zip!/2024/app.log` names the member `2024/app.log` of `logs.zip`,
+//! and `bundle.tar.gz!/inner.zip!/notes.txt` a member of an archive inside
+//! another. Zip (and jar), tar and gzip-compressed tar archives are read;
+//! the outer archive is recognised by its extension, and every `!/` that
+//! follows an archive name starts a member name.
+//!
+//! Members are decompressed into memory when read, never to disk.
+//! [`crate::memory::SafeMemoryAccess`] and the async file readers serve
+//! member paths this way, and the sandbox policy checks the archive file in
+//! their place.
+
+use crate::error::{AiCoreutilsError, Result};
+use flate2::read::GzDecoder;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Cursor, Read, Seek};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+
+/// Separates an archive from the name of one of its members
+pub const SEPARATOR: &str = "!/";
+
+/// Archives nested deeper than this are searched as plain files
+const MAX_DEPTH: usize = 8;
+
+/// Tar header and padding block size
+const BLOCK: u64 = 512;
+
+/// Most bytes reserved up front for a member, whatever size it claims
+const RESERVE: u64 = 16 * 1024 * 1024;
+
+/// Archive formats read by this module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `.zip` or `.jar`
+    Zip,
+    /// `.tar`
+    Tar,
+    /// `.tar.gz` or `.tgz`
+    TarGz,
+}
+
+impl Format {
+    /// Format named by the extension of `name`
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        if name.ends_with(".zip") || name.ends_with(".jar") {
+            Some(Format::Zip)
+        } else if name.ends_with(".tar") {
+            Some(Format::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Format::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether `path` is named like an archive this module reads
+pub fn is_archive(path: &Path) -> bool {
+    path.to_str().and_then(Format::from_name).is_some()
+}
+
+/// Whether `path` names a member of an archive
+pub fn is_member(path: &Path) -> bool {
+    MemberPath::parse(path).is_some()
+}
+
+/// A member path, split into the archive file and the member names leading
+/// to the member, outermost first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberPath {
+    /// Archive file on disk
+    pub archive: PathBuf,
+    /// Member of `archive`, then of that member, and so on
+    pub members: Vec<String>,
+}
+
+impl MemberPath {
+    /// Split `path`; `None` if it names no archive member
+    pub fn parse(path: &Path) -> Option<Self> {
+        let path = path.to_str()?;
+        let mut parts = Vec::new();
+        let mut start = 0;
+        let mut from = 0;
+        while let Some(found) = path[from..].find(SEPARATOR) {
+            let at = from + found;
+            if Format::from_name(&path[start..at]).is_some() {
+                parts.push(&path[start..at]);
+                start = at + SEPARATOR.len();
+            }
+            from = at + SEPARATOR.len();
+        }
+        if parts.is_empty() || start == path.len() {
+            return None;
+        }
+        parts.push(&path[start..]);
+        Some(Self {
+            archive: PathBuf::from(parts[0]),
+            members: parts[1..].iter().map(|s| s.to_string()).collect(),
+        })
+    }
+}
+
+impl fmt::Display for MemberPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.archive.display())?;
+        for member in &self.members {
+            write!(f, "{}{}", SEPARATOR, member)?;
+        }
+        Ok(())
+    }
+}
+
+/// Contents of the member `path` names
+///
+/// Resource limits and the sandbox policy are left to the caller.
+pub fn read(path: &Path) -> Result<Vec<u8>> {
+    let member = MemberPath::parse(path)
+        .ok_or_else(|| AiCoreutilsError::InvalidInput(format!("not an archive member: {}", path.display())))?;
+    let not_found = || AiCoreutilsError::PathNotFound(path.to_path_buf());
+
+    let file = File::open(&member.archive).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => not_found(),
+        _ => AiCoreutilsError::Io(e),
+    })?;
+    let mut format = Format::from_name(&member.archive.to_string_lossy()).ok_or_else(not_found)?;
+    let mut data = extract(BufReader::new(file), format, &member.members[0])?.ok_or_else(not_found)?;
+    for pair in member.members.windows(2) {
+        format = Format::from_name(&pair[0]).ok_or_else(not_found)?;
+        data = extract(Cursor::new(data), format, &pair[1])?.ok_or_else(not_found)?;
+    }
+    Ok(data)
+}
+
+/// Call `f` with the path and contents of every file in the archive at
+/// `path`, descending into archives inside it
+///
+/// A nested archive that cannot be read is passed to `f` as an error, and
+/// the walk goes on; failing to read `path` itself ends it.
+pub fn walk(path: &Path, mut f: impl FnMut(&Path, Result<Vec<u8>>) -> Result<()>) -> Result<()> {
+    let format = Format::from_name(&path.to_string_lossy())
+        .ok_or_else(|| AiCoreutilsError::InvalidInput(format!("not an archive: {}", path.display())))?;
+    let file = BufReader::new(File::open(path)?);
+    walk_reader(file, format, &path.display().to_string(), 0, &mut f)
+}
+
+/// Callback for [`walk`]: a member path and its contents
+type Visitor<'a> = dyn FnMut(&Path, Result<Vec<u8>>) -> Result<()> + 'a;
+
+fn walk_reader<R: Read + Seek>(
+    reader: R,
+    format: Format,
+    prefix: &str,
+    depth: usize,
+    f: &mut Visitor<'_>,
+) -> Result<()> {
+    visit(reader, format, |name, size, data| {
+        let member = format!("{}{}{}", prefix, SEPARATOR, name);
+        let mut bytes = Vec::with_capacity(size.min(RESERVE) as usize);
+        let read = data.read_to_end(&mut bytes).map(|_| bytes).map_err(AiCoreutilsError::Io);
+        match (read, Format::from_name(name)) {
+            (Ok(bytes), Some(inner)) if depth < MAX_DEPTH => {
+                if let Err(e) = walk_reader(Cursor::new(bytes), inner, &member, depth + 1, &mut *f) {
+                    f(Path::new(&member), Err(e))?;
+                }
+            }
+            (read, _) => f(Path::new(&member), read)?,
+        }
+        Ok(ControlFlow::Continue(()))
+    })
+}
+
+/// Contents of the member `name`, if the archive has it
+fn extract<R: Read + Seek>(reader: R, format: Format, name: &str) -> Result<Option<Vec<u8>>> {
+    let mut found = None;
+    visit(reader, format, |member, size, data| {
+        if member != name {
+            return Ok(ControlFlow::Continue(()));
+        }
+        let mut bytes = Vec::with_capacity(size.min(RESERVE) as usize);
+        data.read_to_end(&mut bytes)?;
+        found = Some(bytes);
+        Ok(ControlFlow::Break(()))
+    })?;
+    Ok(found)
+}
+
+/// Call `f` with the name, size and a reader of each file in the archive,
+/// in archive order, until it breaks
+///
+/// Directories, links and other special members are skipped, and a leading
+/// `./` is dropped from names.
+pub fn visit<R: Read + Seek>(
+    reader: R,
+    format: Format,
+    mut f: impl FnMut(&str, u64, &mut dyn Read) -> Result<ControlFlow<()>>,
+) -> Result<()> {
+    match format {
+        Format::Zip => {
+            let mut zip = zip::ZipArchive::new(reader).map_err(archive_error)?;
+            for index in 0..zip.len() {
+                let mut file = zip.by_index(index).map_err(archive_error)?;
+                if !file.is_file() {
+                    continue;
+                }
+                let name = normalize(file.name()).to_string();
+                let size = file.size();
+                if f(&name, size, &mut file)?.is_break() {
+                    break;
+                }
+            }
+            Ok(())
+        }
+        Format::Tar => visit_tar(reader, f),
+        Format::TarGz => visit_tar(GzDecoder::new(reader), f),
+    }
+}
+
+/// [`visit`] for tar streams: ustar, GNU long names and pax `path`/`size`
+fn visit_tar(
+    mut reader: impl Read,
+    mut f: impl FnMut(&str, u64, &mut dyn Read) -> Result<ControlFlow<()>>,
+) -> Result<()> {
+    let mut header = [0u8; BLOCK as usize];
+    // Overrides for the next member, from GNU long name and pax headers
+    let mut next_name: Option<String> = None;
+    let mut next_size: Option<u64> = None;
+
+    loop {
+        if !read_block(&mut reader, &mut header)? || header.iter().all(|&b| b == 0) {
+            return Ok(());
+        }
+        if !checksum_matches(&header) {
+            return Err(archive_error("invalid tar header checksum"));
+        }
+        let size = next_size.take().map_or_else(|| parse_number(&header[124..136]), Ok)?;
+        let padding = size.div_ceil(BLOCK) * BLOCK - size;
+
+        match header[156] {
+            b'L' => {
+                let data = read_exact_vec(&mut reader, size)?;
+                next_name = Some(field(&data).to_string());
+            }
+            b'x' => {
+                let data = read_exact_vec(&mut reader, size)?;
+                for (key, value) in pax_records(&data) {
+                    match key {
+                        "path" => next_name = Some(value.to_string()),
+                        "size" => next_size = value.parse().ok(),
+                        _ => {}
+                    }
+                }
+            }
+            kind => {
+                let name = next_name.take().unwrap_or_else(|| header_name(&header));
+                let mut data = (&mut reader).take(size);
+                let is_file = matches!(kind, b'0' | b'\0' | b'7') && !name.ends_with('/');
+                let flow = if is_file {
+                    f(normalize(&name), size, &mut data)?
+                } else {
+                    ControlFlow::Continue(())
+                };
+                if flow.is_break() {
+                    return Ok(());
+                }
+                // Skip whatever `f` left unread
+                io::copy(&mut data, &mut io::sink())?;
+                if data.limit() > 0 {
+                    return Err(archive_error("tar member ends early"));
+                }
+            }
+        }
+        io::copy(&mut (&mut reader).take(padding), &mut io::sink())?;
+    }
+}
+
+/// Fill `block`; `false` at a clean end of stream
+fn read_block(reader: &mut impl Read, block: &mut [u8]) -> Result<bool> {
+    let mut filled = 0;
+    while filled < block.len() {
+        match reader.read(&mut block[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(archive_error("tar header ends early")),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(true)
+}
+
+fn read_exact_vec(reader: &mut impl Read, size: u64) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader.take(size).read_to_end(&mut data)?;
+    if (data.len() as u64) < size {
+        return Err(archive_error("tar member ends early"));
+    }
+    Ok(data)
+}
+
+/// Whether the header's checksum field matches its bytes (counted with the
+/// field itself as spaces)
+fn checksum_matches(header: &[u8; BLOCK as usize]) -> bool {
+    let sum: u64 = header
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| if (148..156).contains(&i) { b' ' as u64 } else { b as u64 })
+        .sum();
+    parse_number(&header[148..156]).is_ok_and(|stored| stored == sum)
+}
+
+/// An octal field, or a base-256 one when the high bit of its first byte is set
+fn parse_number(bytes: &[u8]) -> Result<u64> {
+    if bytes.first().is_some_and(|&b| b & 0x80 != 0) {
+        return Ok(bytes[1..].iter().fold(u64::from(bytes[0] & 0x7f), |n, &b| (n << 8) | u64::from(b)));
+    }
+    let text = field(bytes).trim();
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).map_err(|_| archive_error(format!("invalid tar number: {:?}", text)))
+}
+
+/// Text of a NUL-padded field
+fn field(bytes: &[u8]) -> &str {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    std::str::from_utf8(&bytes[..end]).unwrap_or_default()
+}
+
+/// Member name from the ustar `prefix` and `name` fields
+fn header_name(header: &[u8; BLOCK as usize]) -> String {
+    let name = field(&header[0..100]);
+    let prefix = if &header[257..262] == b"ustar" { field(&header[345..500]) } else { "" };
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", prefix, name)
+    }
+}
+
+/// `key=value` pairs of pax extended header records (`<len> key=value\n`)
+fn pax_records(data: &[u8]) -> impl Iterator<Item = (&str, &str)> {
+    let mut rest = data;
+    std::iter::from_fn(move || {
+        let space = rest.iter().position(|&b| b == b' ')?;
+        let len: usize = std::str::from_utf8(&rest[..space]).ok()?.parse().ok()?;
+        let record = rest.get(space + 1..len)?;
+        rest = &rest[len..];
+        let record = std::str::from_utf8(record).ok()?.strip_suffix('\n')?;
+        record.split_once('=')
+    })
+}
+
+fn normalize(name: &str) -> &str {
+    name.trim_start_matches("./")
+}
+
+fn archive_error(e: impl fmt::Display) -> AiCoreutilsError {
+    AiCoreutilsError::Archive(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn zip_bytes(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        zip.add_directory("dir/", zip::write::SimpleFileOptions::default()).unwrap();
+        for (name, data) in files {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn tar_header(name: &str, size: u64, kind: u8) -> [u8; 512] {
+        let mut header = [0u8; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..107].copy_from_slice(b"0000644");
+        header[124..135].copy_from_slice(format!("{:011o}", size).as_bytes());
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        header[148..156].fill(b' ');
+        let sum: u64 = header.iter().map(|&b| b as u64).sum();
+        header[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+        header
+    }
+
+    fn tar_entry(tar: &mut Vec<u8>, name: &str, data: &[u8], kind: u8) {
+        tar.extend_from_slice(&tar_header(name, data.len() as u64, kind));
+        tar.extend_from_slice(data);
+        tar.resize(tar.len().div_ceil(512) * 512, 0);
+    }
+
+    fn tar_bytes(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut tar = Vec::new();
+        tar_entry(&mut tar, "./dir/", b"", b'5');
+        for (name, data) in files {
+            tar_entry(&mut tar, name, data, b'0');
+        }
+        tar.extend_from_slice(&[0u8; 1024]);
+        tar
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_parse_member_path() {
+        let member = MemberPath::parse(Path::new("data/logs.zip!/2024/app.log")).unwrap();
+        assert_eq!(member.archive, PathBuf::from("data/logs.zip"));
+        assert_eq!(member.members, vec!["2024/app.log"]);
+
+        let nested = MemberPath::parse(Path::new("b.tar.gz!/x!/inner.ZIP!/notes.txt")).unwrap();
+        assert_eq!(nested.archive, PathBuf::from("b.tar.gz"));
+        assert_eq!(nested.members, vec!["x!/inner.ZIP", "notes.txt"]);
+        assert_eq!(nested.to_string(), "b.tar.gz!/x!/inner.ZIP!/notes.txt");
+
+        assert!(MemberPath::parse(Path::new("notes.txt!/a")).is_none());
+        assert!(MemberPath::parse(Path::new("logs.zip")).is_none());
+        assert!(MemberPath::parse(Path::new("logs.zip!/")).is_none());
+        assert!(is_archive(Path::new("release.tgz")));
+        assert!(!is_archive(Path::new("release.gz")));
+    }
+
+    #[test]
+    fn test_read_members() {
+        let dir = TempDir::new().unwrap();
+        let inner = tar_bytes(&[("notes.txt", b"nested")]);
+        std::fs::write(dir.path().join("a.zip"), zip_bytes(&[("dir/a.txt", b"alpha"), ("inner.tar", &inner)])).unwrap();
+        std::fs::write(dir.path().join("b.tgz"), gzip(&tar_bytes(&[("./b.txt", b"beta")]))).unwrap();
+
+        let member = |s: &str| dir.path().join(s);
+        assert_eq!(read(&member("a.zip!/dir/a.txt")).unwrap(), b"alpha");
+        assert_eq!(read(&member("a.zip!/inner.tar!/notes.txt")).unwrap(), b"nested");
+        assert_eq!(read(&member("b.tgz!/b.txt")).unwrap(), b"beta");
+        assert!(matches!(read(&member("a.zip!/missing")), Err(AiCoreutilsError::PathNotFound(_))));
+        assert!(matches!(read(&member("none.zip!/a")), Err(AiCoreutilsError::PathNotFound(_))));
+    }
+
+    #[test]
+    fn test_tar_long_names() {
+        let long = format!("{}/file.txt", "d".repeat(120));
+        let mut tar = Vec::new();
+        tar_entry(&mut tar, "././@LongLink", format!("{}\0", long).as_bytes(), b'L');
+        tar_entry(&mut tar, "truncated", b"gnu", b'0');
+        let record = format!(" path=pax/{}\n", long);
+        let pax = format!("{}{}", record.len() + 3, record);
+        tar_entry(&mut tar, "PaxHeader", pax.as_bytes(), b'x');
+        tar_entry(&mut tar, "truncated", b"pax", b'0');
+        tar_entry(&mut tar, "link", b"", b'2');
+        tar.extend_from_slice(&[0u8; 1024]);
+
+        let mut seen = Vec::new();
+        visit(Cursor::new(tar), Format::Tar, |name, _, data| {
+            let mut text = String::new();
+            data.read_to_string(&mut text)?;
+            seen.push((name.to_string(), text));
+            Ok(ControlFlow::Continue(()))
+        })
+        .unwrap();
+        assert_eq!(
+            seen,
+            vec![(long.clone(), "gnu".to_string()), (format!("pax/{}", long), "pax".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_invalid_tar() {
+        let mut tar = tar_bytes(&[("a.txt", b"alpha")]);
+        tar[600] ^= 1;
+        assert!(matches!(
+            visit(Cursor::new(tar), Format::Tar, |_, _, _| Ok(ControlFlow::Continue(()))),
+            Err(AiCoreutilsError::Archive(_))
+        ));
+    }
+
+    #[test]
+    fn test_walk_nested() {
+        let dir = TempDir::new().unwrap();
+        let inner = zip_bytes(&[("c.txt", b"gamma")]);
+        let outer = tar_bytes(&[("a.txt", b"alpha"), ("sub/inner.zip", &inner), ("bad.zip", b"not a zip")]);
+        let path = dir.path().join("outer.tar");
+        std::fs::write(&path, outer).unwrap();
+
+        let mut seen = Vec::new();
+        walk(&path, |member, data| {
+            let member = member.strip_prefix(dir.path()).unwrap().display().to_string();
+            seen.push((member, data.map(|d| String::from_utf8(d).unwrap()).map_err(|e| e.to_string())));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(seen[0], ("outer.tar!/a.txt".to_string(), Ok("alpha".to_string())));
+        assert_eq!(seen[1], ("outer.tar!/sub/inner.zip!/c.txt".to_string(), Ok("gamma".to_string())));
+        assert_eq!(seen[2].0, "outer.tar!/bad.zip");
+        assert!(seen[2].1.as_ref().unwrap_err().contains("Archive error"));
+    }
+}
@@ -3,12 +3,24 @@
 //! This module provides async/await variants of file system operations
 //! for concurrent I/O processing and improved performance.
 
+use crate::audit;
+use crate::limits;
 use crate::error::{AiCoreutilsError, Result};
-use crate::jsonl;
+use crate::fs_utils::{self, CachePolicy, CopyMechanism, SymlinkLoop, SymlinkPolicy};
+use crate::hash::{HashAlgorithm, StreamingHasher};
+use crate::jsonl::{self, ProgressReporter, RecordData};
+use crate::policy::{self, Access};
+use crate::memory::{AccessPattern, MapOptions, SafeMemoryAccess};
 use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
+use std::future::Future;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
 use tokio::fs;
 use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::{Semaphore, SemaphorePermit};
 
 /// Configuration for async operations
 #[derive(Debug, Clone)]
@@ -19,6 +31,10 @@ pub struct AsyncConfig {
     pub buffer_size: usize,
     /// Enable progress reporting
     pub progress: bool,
+    /// Retry policy for transient I/O errors
+    pub retry: RetryPolicy,
+    /// Page cache policy for whole-file reads
+    pub cache_policy: CachePolicy,
 }
 
 impl Default for AsyncConfig {
@@ -27,12 +43,305 @@ impl Default for AsyncConfig {
             max_concurrent: 10,
             buffer_size: 8192,
             progress: false,
+            retry: RetryPolicy::default(),
+            cache_policy: CachePolicy::Normal,
         }
     }
 }
 
-/// Read a file asynchronously
+impl AsyncConfig {
+    /// Defaults with the concurrency and buffer size tuned to the machine
+    ///
+    /// `max_concurrent` is the command line's value if given; see
+    /// [`crate::tuning`] for where the others come from.
+    pub fn tuned(max_concurrent: Option<usize>) -> Self {
+        Self {
+            max_concurrent: crate::tuning::concurrency(max_concurrent),
+            buffer_size: crate::tuning::buffer_size(None),
+            ..Self::default()
+        }
+    }
+}
+
+/// Retry policy with exponential backoff for transient I/O errors
+///
+/// Only errors classified as transient by [`RetryPolicy::is_retryable`]
+/// (interrupted calls, timeouts, busy resources, stale network handles) are
+/// retried. Every retry emits a `retry_attempt` info record.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one (1 disables retries)
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    pub initial_backoff: Duration,
+    /// Upper bound for any single delay
+    pub max_backoff: Duration,
+    /// Backoff growth factor between attempts
+    pub multiplier: f64,
+    /// Randomize each delay between 50% and 100% of its nominal value
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Default backoff settings with the given number of retries
+    pub fn with_retries(retries: u32) -> Self {
+        Self {
+            max_attempts: retries.saturating_add(1),
+            ..Self::default()
+        }
+    }
+
+    /// Check whether an error is transient and worth retrying
+    pub fn is_retryable(error: &AiCoreutilsError) -> bool {
+        use std::io::ErrorKind;
+
+        match error {
+            AiCoreutilsError::Io(e) => matches!(
+                e.kind(),
+                ErrorKind::Interrupted
+                    | ErrorKind::WouldBlock
+                    | ErrorKind::TimedOut
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::NotConnected
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::ResourceBusy
+                    | ErrorKind::StaleNetworkFileHandle
+            ),
+            _ => false,
+        }
+    }
+
+    /// Delay before the given retry (1-based)
+    pub fn backoff_for(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(31) as i32;
+        let nominal = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        let capped = nominal.min(self.max_backoff.as_secs_f64());
+
+        let factor = if self.jitter {
+            0.5 + 0.5 * jitter_fraction()
+        } else {
+            1.0
+        };
+
+        Duration::from_secs_f64(capped * factor)
+    }
+
+    /// Run an async operation, retrying transient failures
+    pub async fn run<T, F, Fut>(&self, operation: &str, mut f: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match f().await {
+                Err(e) if attempt < self.max_attempts && Self::is_retryable(&e) => {
+                    let delay = self.backoff_for(attempt);
+                    self.report_retry(operation, attempt, delay, &e)?;
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Run a blocking operation, retrying transient failures
+    pub fn run_blocking<T, F>(&self, operation: &str, mut f: F) -> Result<T>
+    where
+        F: FnMut() -> Result<T>,
+    {
+        let mut attempt = 1;
+        loop {
+            match f() {
+                Err(e) if attempt < self.max_attempts && Self::is_retryable(&e) => {
+                    let delay = self.backoff_for(attempt);
+                    self.report_retry(operation, attempt, delay, &e)?;
+                    std::thread::sleep(delay);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn report_retry(
+        &self,
+        operation: &str,
+        attempt: u32,
+        delay: Duration,
+        error: &AiCoreutilsError,
+    ) -> Result<()> {
+        jsonl::output_data(RecordData::RetryAttempt {
+            operation: operation.to_string(),
+            attempt,
+            max_attempts: self.max_attempts,
+            delay_ms: delay.as_millis() as u64,
+            error: error.to_string(),
+        })
+    }
+}
+
+/// Cheap pseudo-random fraction in [0, 1) for backoff jitter
+fn jitter_fraction() -> f64 {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static STATE: AtomicU64 = AtomicU64::new(0);
+
+    let seed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    let mut x = STATE.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed) ^ seed;
+
+    // xorshift64*
+    x ^= x >> 12;
+    x ^= x << 25;
+    x ^= x >> 27;
+    let x = x.wrapping_mul(0x2545_F491_4F6C_DD1D);
+
+    (x >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Process-wide budget of file descriptors for async operations
+///
+/// `AsyncConfig::max_concurrent` only limits one call site; recursive tools
+/// running several of them at once can still exhaust `ulimit -n`. Every
+/// async operation in this module takes a permit per descriptor it opens,
+/// so the total stays within this budget no matter how many callers there
+/// are.
+static FD_SEMAPHORE: OnceLock<Semaphore> = OnceLock::new();
+static FD_BUDGET: AtomicUsize = AtomicUsize::new(0);
+
+/// Permit for descriptors taken from the process-wide budget
+pub type FdPermit = SemaphorePermit<'static>;
+
+/// Default descriptor budget: half the soft `RLIMIT_NOFILE`, within 16..=4096
+pub fn default_fd_budget() -> usize {
+    #[cfg(unix)]
+    {
+        let mut limit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+        // SAFETY: getrlimit only writes into `limit`
+        if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } == 0 {
+            let soft = usize::try_from(limit.rlim_cur).unwrap_or(usize::MAX);
+            return (soft / 2).clamp(16, 4096);
+        }
+    }
+    512
+}
+
+fn fd_semaphore() -> &'static Semaphore {
+    FD_SEMAPHORE.get_or_init(|| {
+        let budget = match FD_BUDGET.load(Ordering::Relaxed) {
+            0 => default_fd_budget(),
+            n => n,
+        };
+        FD_BUDGET.store(budget, Ordering::Relaxed);
+        Semaphore::new(budget)
+    })
+}
+
+/// Set the process-wide descriptor budget
+///
+/// Shrinking takes effect as permits are returned; descriptors already in
+/// use are not closed.
+pub fn set_fd_budget(budget: usize) {
+    let budget = budget.max(1);
+    if FD_SEMAPHORE.get().is_none() {
+        FD_BUDGET.store(budget, Ordering::Relaxed);
+        return;
+    }
+
+    let semaphore = fd_semaphore();
+    let current = FD_BUDGET.swap(budget, Ordering::Relaxed);
+    if budget > current {
+        semaphore.add_permits(budget - current);
+    } else {
+        let excess = current - budget;
+        let forgotten = semaphore.forget_permits(excess);
+        // Permits in use are forgotten when they come back
+        if forgotten < excess {
+            let semaphore_ref: &'static Semaphore = semaphore;
+            let pending = (excess - forgotten) as u32;
+            if let Ok(handle) = tokio::runtime::Handle::try_current() {
+                handle.spawn(async move {
+                    if let Ok(permits) = semaphore_ref.acquire_many(pending).await {
+                        permits.forget();
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Current process-wide descriptor budget
+pub fn fd_budget() -> usize {
+    fd_semaphore();
+    FD_BUDGET.load(Ordering::Relaxed)
+}
+
+/// Descriptors currently available in the process-wide budget
+pub fn fds_available() -> usize {
+    fd_semaphore().available_permits()
+}
+
+/// Wait for `count` descriptors from the process-wide budget
+///
+/// `count` is capped at the budget, so an operation needing more
+/// descriptors than the whole budget runs alone instead of waiting forever.
+pub async fn acquire_fds(count: u32) -> FdPermit {
+    let semaphore = fd_semaphore();
+    let budget = u32::try_from(FD_BUDGET.load(Ordering::Relaxed)).unwrap_or(u32::MAX);
+    semaphore
+        .acquire_many(count.min(budget))
+        .await
+        .expect("fd semaphore is never closed")
+}
+
+/// Read a file asynchronously, retrying transient errors with the default policy
 pub async fn async_read_file(path: &Path) -> Result<Vec<u8>> {
+    async_read_file_with_retry(path, &RetryPolicy::default()).await
+}
+
+/// Read a file asynchronously using the given retry policy
+pub async fn async_read_file_with_retry(path: &Path, retry: &RetryPolicy) -> Result<Vec<u8>> {
+    policy::check(path, Access::Read)?;
+    let operation = format!("read {}", path.display());
+    let data = retry.run(&operation, || read_file_once(path)).await?;
+    limits::read_file(path, data.len() as u64)?;
+    Ok(data)
+}
+
+async fn read_file_once(path: &Path) -> Result<Vec<u8>> {
+    let _permit = acquire_fds(1).await;
+    #[cfg(feature = "archives")]
+    if crate::archive::is_member(path) {
+        let path = path.to_path_buf();
+        return tokio::task::spawn_blocking(move || crate::archive::read(&path))
+            .await
+            .map_err(|e| AiCoreutilsError::Io(std::io::Error::other(e)))?;
+    }
     let mut file = fs::File::open(path)
         .await
         .map_err(AiCoreutilsError::Io)?;
@@ -44,9 +353,100 @@ pub async fn async_read_file(path: &Path) -> Result<Vec<u8>> {
         .await
         .map_err(AiCoreutilsError::Io)?;
 
+    #[cfg(feature = "compression")]
+    if crate::compress::sniff(&buffer).is_some() {
+        let path = path.to_path_buf();
+        return tokio::task::spawn_blocking(move || decompress(&path, buffer))
+            .await
+            .map_err(|e| AiCoreutilsError::Io(std::io::Error::other(e)))?;
+    }
+
     Ok(buffer)
 }
 
+/// The decoded contents of `path`, given `data` as read from disk
+#[cfg(feature = "compression")]
+fn decompress(path: &Path, data: Vec<u8>) -> Result<Vec<u8>> {
+    match crate::compress::sniff(&data) {
+        Some(compression) => crate::compress::decode(path, compression),
+        None => Ok(data),
+    }
+}
+
+/// Read a file using the given retry and page cache policies
+///
+/// Returns the contents together with the cache policy that was actually
+/// applied: `Direct` falls back to `Stream` on filesystems that reject
+/// `O_DIRECT`, and `Stream` falls back to `Normal` where `posix_fadvise`
+/// is unavailable.
+pub async fn async_read_file_with_policy(
+    path: &Path,
+    retry: &RetryPolicy,
+    policy: CachePolicy,
+) -> Result<(Vec<u8>, CachePolicy)> {
+    // Archive members are decompressed, not read through the page cache
+    #[cfg(feature = "archives")]
+    let policy = if crate::archive::is_member(path) { CachePolicy::Normal } else { policy };
+    if policy == CachePolicy::Normal {
+        let data = async_read_file_with_retry(path, retry).await?;
+        return Ok((data, CachePolicy::Normal));
+    }
+    policy::check(path, Access::Read)?;
+
+    let operation = format!("read {}", path.display());
+    let (data, applied) = retry
+        .run(&operation, || {
+            let path = path.to_path_buf();
+            async move {
+                let permit = acquire_fds(1).await;
+                tokio::task::spawn_blocking(move || {
+                    let _permit = permit;
+                    let (data, applied) = read_file_uncached(&path, policy)?;
+                    #[cfg(feature = "compression")]
+                    let data = decompress(&path, data)?;
+                    Ok((data, applied))
+                })
+                    .await
+                    .map_err(|e| AiCoreutilsError::Io(std::io::Error::other(e)))?
+            }
+        })
+        .await?;
+    limits::read_file(path, data.len() as u64)?;
+    Ok((data, applied))
+}
+
+fn read_file_uncached(path: &Path, policy: CachePolicy) -> Result<(Vec<u8>, CachePolicy)> {
+    use std::io::Read;
+
+    if policy == CachePolicy::Direct {
+        match fs_utils::read_direct(path) {
+            Ok(data) => return Ok((data, CachePolicy::Direct)),
+            // Only fall back when O_DIRECT itself is the problem
+            Err(e) if matches!(
+                e.kind(),
+                std::io::ErrorKind::NotFound | std::io::ErrorKind::PermissionDenied
+            ) => return Err(AiCoreutilsError::Io(e)),
+            Err(_) => {}
+        }
+    }
+
+    let mut file = std::fs::File::open(path).map_err(AiCoreutilsError::Io)?;
+    let applied = if fs_utils::advise_sequential(&file) {
+        CachePolicy::Stream
+    } else {
+        CachePolicy::Normal
+    };
+
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer).map_err(AiCoreutilsError::Io)?;
+
+    if applied == CachePolicy::Stream {
+        fs_utils::drop_cache(&file);
+    }
+
+    Ok((buffer, applied))
+}
+
 /// Read a file as text asynchronously
 pub async fn async_read_file_to_string(path: &Path) -> Result<String> {
     let contents = async_read_file(path).await?;
@@ -55,6 +455,8 @@ pub async fn async_read_file_to_string(path: &Path) -> Result<String> {
 
 /// Write data to a file asynchronously
 pub async fn async_write_file(path: &Path, data: &[u8]) -> Result<()> {
+    policy::check(path, Access::Write)?;
+    let _permit = acquire_fds(1).await;
     let mut file = fs::File::create(path)
         .await
         .map_err(AiCoreutilsError::Io)?;
@@ -62,11 +464,14 @@ pub async fn async_write_file(path: &Path, data: &[u8]) -> Result<()> {
         .await
         .map_err(AiCoreutilsError::Io)?;
     file.flush().await.map_err(AiCoreutilsError::Io)?;
+    audit::written(path, data.len() as u64);
     Ok(())
 }
 
 /// Append data to a file asynchronously
 pub async fn async_append_file(path: &Path, data: &[u8]) -> Result<()> {
+    policy::check(path, Access::Write)?;
+    let _permit = acquire_fds(1).await;
     let mut file = fs::OpenOptions::new()
         .append(true)
         .create(true)
@@ -77,68 +482,295 @@ pub async fn async_append_file(path: &Path, data: &[u8]) -> Result<()> {
         .await
         .map_err(AiCoreutilsError::Io)?;
     file.flush().await.map_err(AiCoreutilsError::Io)?;
+    audit::written(path, data.len() as u64);
     Ok(())
 }
 
 /// Read a file line by line asynchronously
+///
+/// Invalid UTF-8 is replaced with U+FFFD rather than aborting the read; use
+/// [`async_read_raw_lines`] to see the exact bytes.
 pub async fn async_read_lines<F>(path: &Path, mut callback: F) -> Result<()>
 where
     F: FnMut(usize, String) -> Result<()>,
 {
-    let file = fs::File::open(path)
-        .await
-        .map_err(AiCoreutilsError::Io)?;
-    let reader = BufReader::new(file);
-    let mut lines = reader.lines();
+    async_read_raw_lines(path, |line| callback(line.number, line.to_string_lossy().into_owned())).await
+}
 
-    let mut line_num = 0;
-    while let Some(line) = lines
-        .next_line()
-        .await
-        .map_err(AiCoreutilsError::Io)?
-    {
-        line_num += 1;
-        callback(line_num, line)?;
+/// Read a file line by line as raw bytes with exact byte offsets
+pub async fn async_read_raw_lines<F>(path: &Path, mut callback: F) -> Result<()>
+where
+    F: FnMut(RawLine<'_>) -> Result<()>,
+{
+    let mut reader = AsyncLineReader::open(path).await?;
+    while let Some(line) = reader.next_line().await? {
+        callback(line)?;
     }
 
     Ok(())
 }
 
+/// A line as raw bytes, with its exact position in the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawLine<'a> {
+    /// Line number (1-indexed)
+    pub number: usize,
+    /// Byte offset of the first byte of the line
+    pub offset: u64,
+    /// Line content without its terminator
+    pub content: &'a [u8],
+    /// Line terminator: `\n`, `\r\n`, or empty for an unterminated last line
+    pub terminator: &'a [u8],
+}
+
+impl<'a> RawLine<'a> {
+    fn split(number: usize, offset: u64, raw: &'a [u8]) -> Self {
+        let content_len = if raw.ends_with(b"\r\n") {
+            raw.len() - 2
+        } else if raw.ends_with(b"\n") {
+            raw.len() - 1
+        } else {
+            raw.len()
+        };
+
+        Self {
+            number,
+            offset,
+            content: &raw[..content_len],
+            terminator: &raw[content_len..],
+        }
+    }
+
+    /// Length in bytes including the terminator
+    pub fn byte_len(&self) -> u64 {
+        (self.content.len() + self.terminator.len()) as u64
+    }
+
+    /// Content as text, replacing invalid UTF-8 with U+FFFD
+    pub fn to_string_lossy(&self) -> std::borrow::Cow<'a, str> {
+        String::from_utf8_lossy(self.content)
+    }
+}
+
+/// Iterator over the lines of an in-memory buffer, see [`raw_lines`]
+#[derive(Debug, Clone)]
+pub struct RawLines<'a> {
+    data: &'a [u8],
+    pos: usize,
+    number: usize,
+}
+
+impl<'a> Iterator for RawLines<'a> {
+    type Item = RawLine<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.data.len() {
+            return None;
+        }
+
+        let rest = &self.data[self.pos..];
+        let end = rest.iter().position(|&b| b == b'\n').map_or(rest.len(), |i| i + 1);
+        let offset = self.pos as u64;
+        self.pos += end;
+        self.number += 1;
+
+        Some(RawLine::split(self.number, offset, &rest[..end]))
+    }
+}
+
+/// Split an in-memory buffer into raw lines
+pub fn raw_lines(data: &[u8]) -> RawLines<'_> {
+    RawLines { data, pos: 0, number: 0 }
+}
+
+/// Streaming line reader yielding raw byte lines
+///
+/// Unlike `AsyncBufReadExt::lines`, this never fails or drops data on
+/// invalid UTF-8 and keeps `\r\n` terminators distinguishable, so byte
+/// counts and offsets match the file exactly.
+pub struct AsyncLineReader<R> {
+    reader: BufReader<R>,
+    buffer: Vec<u8>,
+    offset: u64,
+    number: usize,
+    _permit: Option<FdPermit>,
+}
+
+impl AsyncLineReader<fs::File> {
+    /// Open a file for line reading
+    ///
+    /// The reader holds a descriptor from the process-wide budget until dropped.
+    pub async fn open(path: &Path) -> Result<Self> {
+        let permit = acquire_fds(1).await;
+        let file = fs::File::open(path).await.map_err(AiCoreutilsError::Io)?;
+        Ok(Self {
+            _permit: Some(permit),
+            ..Self::new(file)
+        })
+    }
+}
+
+impl<R: tokio::io::AsyncRead + Unpin> AsyncLineReader<R> {
+    /// Wrap an async reader
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            buffer: Vec::new(),
+            offset: 0,
+            number: 0,
+            _permit: None,
+        }
+    }
+
+    /// Read the next line, or `None` at end of input
+    pub async fn next_line(&mut self) -> Result<Option<RawLine<'_>>> {
+        self.buffer.clear();
+        let n = self
+            .reader
+            .read_until(b'\n', &mut self.buffer)
+            .await
+            .map_err(AiCoreutilsError::Io)?;
+        if n == 0 {
+            return Ok(None);
+        }
+
+        let offset = self.offset;
+        self.offset += n as u64;
+        self.number += 1;
+        Ok(Some(RawLine::split(self.number, offset, &self.buffer)))
+    }
+}
+
 /// Recursively walk a directory asynchronously
+///
+/// Symlinks below `dir` are not followed.
 pub async fn async_walk_dir(dir: &Path) -> Result<Vec<PathBuf>> {
-    let mut entries = Vec::new();
+    let (files, _) = async_walk_dir_with_policy(dir, SymlinkPolicy::CommandLine).await?;
+    Ok(files)
+}
+
+/// Recursively walk a directory, following symlinks according to `policy`
+///
+/// Returns the files found and the directories skipped because they were
+/// reached again through a symlink (cycles or duplicate links). Each
+/// directory is visited at most once, identified by device and inode.
+/// Entries the sandbox policy denies are left out, and subdirectories that
+/// cannot be read are skipped with a `warning` record.
+pub async fn async_walk_dir_with_policy(
+    dir: &Path,
+    policy: SymlinkPolicy,
+) -> Result<(Vec<PathBuf>, Vec<SymlinkLoop>)> {
+    policy::check(dir, Access::Read)?;
+    let is_link = fs::symlink_metadata(dir)
+        .await
+        .map_err(AiCoreutilsError::Io)?
+        .file_type()
+        .is_symlink();
+    if is_link && !policy.follows_at(0) {
+        return Ok((Vec::new(), Vec::new()));
+    }
+
+    let mut state = WalkState {
+        policy,
+        files: Vec::new(),
+        loops: Vec::new(),
+        visited: HashMap::new(),
+    };
+    if policy == SymlinkPolicy::Always {
+        state.first_visit(dir).await;
+    }
+
+    async_walk_dir_recursive(dir, 1, &mut state).await?;
 
-    async_walk_dir_recursive(dir, &mut entries).await?;
+    Ok((state.files, state.loops))
+}
 
-    Ok(entries)
+/// Accumulated results of an async directory walk
+struct WalkState {
+    policy: SymlinkPolicy,
+    files: Vec<PathBuf>,
+    loops: Vec<SymlinkLoop>,
+    visited: HashMap<(u64, u64), PathBuf>,
+}
+
+impl WalkState {
+    /// Record a directory; returns false (and logs a loop) if already visited
+    async fn first_visit(&mut self, dir: &Path) -> bool {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            if let Ok(metadata) = fs::metadata(dir).await {
+                let id = (metadata.dev(), metadata.ino());
+                if let Some(first) = self.visited.get(&id) {
+                    self.loops.push(SymlinkLoop {
+                        path: dir.to_path_buf(),
+                        target: first.clone(),
+                    });
+                    return false;
+                }
+                self.visited.insert(id, dir.to_path_buf());
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = dir;
+        true
+    }
 }
 
 /// Helper for recursive directory walking
 fn async_walk_dir_recursive<'a>(
     dir: &'a Path,
-    entries: &'a mut Vec<PathBuf>,
+    depth: usize,
+    state: &'a mut WalkState,
 ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<()>> + 'a + Send>> {
     Box::pin(async move {
-        let mut dir_entry = fs::read_dir(dir)
-            .await
-            .map_err(AiCoreutilsError::Io)?;
-
-        while let Some(entry) = dir_entry
-            .next_entry()
-            .await
-            .map_err(AiCoreutilsError::Io)?
+        // Read the whole directory and close it before descending, so a deep
+        // tree holds one descriptor at a time
+        let mut subdirs = Vec::new();
         {
-            let path = entry.path();
-            let file_type = entry
-                .file_type()
+            let _permit = acquire_fds(1).await;
+            let mut dir_entry = match fs::read_dir(dir).await {
+                Ok(dir_entry) => dir_entry,
+                // Unreadable subdirectories are skipped with a warning
+                Err(e) if depth > 1 => return fs_utils::warn_unreadable(dir, &e),
+                Err(e) => return Err(AiCoreutilsError::Io(e)),
+            };
+
+            while let Some(entry) = dir_entry
+                .next_entry()
                 .await
-                .map_err(AiCoreutilsError::Io)?;
+                .map_err(AiCoreutilsError::Io)?
+            {
+                let path = entry.path();
+                if policy::check(&path, Access::Read).is_err() {
+                    continue;
+                }
+                let mut file_type = entry
+                    .file_type()
+                    .await
+                    .map_err(AiCoreutilsError::Io)?;
+
+                if file_type.is_symlink() && state.policy.follows_at(depth) {
+                    // Dangling links are skipped
+                    match fs::metadata(&path).await {
+                        Ok(target) => file_type = target.file_type(),
+                        Err(_) => continue,
+                    }
+                }
+
+                if file_type.is_dir() {
+                    subdirs.push(path);
+                } else if file_type.is_file() {
+                    state.files.push(path);
+                }
+            }
+        }
 
-            if file_type.is_dir() {
-                async_walk_dir_recursive(&path, entries).await?;
-            } else if file_type.is_file() {
-                entries.push(path);
+        for subdir in subdirs {
+            if state.policy == SymlinkPolicy::Always && !state.first_visit(&subdir).await {
+                continue;
             }
+            async_walk_dir_recursive(&subdir, depth + 1, state).await?;
         }
 
         Ok(())
@@ -165,8 +797,10 @@ where
         }))?;
     }
 
+    let mut progress = ProgressReporter::new("Processing files", files.len() as u64);
+
     // Process files in batches
-    let results = stream::iter(files)
+    let mut results = stream::iter(files)
         .map(|file| {
             let process_fn = &process_fn;
             async move {
@@ -174,15 +808,17 @@ where
                 (file, result)
             }
         })
-        .buffer_unordered(config.max_concurrent)
-        .collect::<Vec<_>>()
-        .await;
+        .buffer_unordered(config.max_concurrent);
 
-    // Check results
+    // Check results as they complete
     let mut success_count = 0;
     let mut error_count = 0;
 
-    for (path, result) in results {
+    while let Some((path, result)) = results.next().await {
+        if config.progress {
+            progress.advance(1)?;
+        }
+
         match result {
             Ok(()) => success_count += 1,
             Err(e) => {
@@ -198,6 +834,7 @@ where
 
     // Report completion
     if config.progress {
+        progress.finish()?;
         jsonl::output_info(serde_json::json!({
             "operation": "async_process_complete",
             "success_count": success_count,
@@ -209,16 +846,124 @@ where
 }
 
 /// Copy a file asynchronously with progress
+///
+/// Transient failures restart the copy according to `config.retry`.
 pub async fn async_copy_file(src: &Path, dest: &Path, config: &AsyncConfig) -> Result<u64> {
-    let mut src_file = fs::File::open(src)
+    let operation = format!("copy {} -> {}", src.display(), dest.display());
+    let (copied, _) = config
+        .retry
+        .run(&operation, || copy_file_once(src, dest, config, None))
+        .await?;
+    Ok(copied)
+}
+
+/// Result of [`async_copy_file_hashed`]
+#[derive(Debug, Clone)]
+pub struct HashedCopy {
+    /// Bytes copied
+    pub bytes: u64,
+    /// Digest of the bytes copied
+    pub hasher: StreamingHasher,
+}
+
+/// Copy a file asynchronously, hashing the data as it is copied
+///
+/// The digest is computed from the same buffers that are written, so no
+/// second read pass is needed. Zero-copy mechanisms never expose the data,
+/// so this always copies through a buffer of `config.buffer_size` bytes.
+pub async fn async_copy_file_hashed(
+    src: &Path,
+    dest: &Path,
+    config: &AsyncConfig,
+    algorithm: HashAlgorithm,
+) -> Result<HashedCopy> {
+    let operation = format!("copy {} -> {}", src.display(), dest.display());
+    let (bytes, hasher) = config
+        .retry
+        .run(&operation, || copy_file_once(src, dest, config, Some(algorithm)))
+        .await?;
+    Ok(HashedCopy {
+        bytes,
+        hasher: hasher.expect("hashed copy returns a digest"),
+    })
+}
+
+async fn copy_file_once(
+    src: &Path,
+    dest: &Path,
+    config: &AsyncConfig,
+    algorithm: Option<HashAlgorithm>,
+) -> Result<(u64, Option<StreamingHasher>)> {
+    policy::check(src, Access::Read)?;
+    policy::check(dest, Access::Write)?;
+    let total_size = fs::metadata(src).await.map_err(AiCoreutilsError::Io)?.len();
+    let progress = ProgressReporter::new(format!("Copying {}", src.display()), total_size);
+
+    // Source and destination are open at the same time
+    let _permit = acquire_fds(2).await;
+
+    // Try a zero-copy mechanism first, unless the data must be hashed; it
+    // reports progress from the blocking thread
+    let (result, progress) = if algorithm.is_some() {
+        (Ok(None), progress)
+    } else {
+        let (src_owned, dest_owned, report) = (src.to_path_buf(), dest.to_path_buf(), config.progress);
+        tokio::task::spawn_blocking(move || {
+            let mut progress = progress;
+            let result = fs_utils::kernel_copy(&src_owned, &dest_owned, |n| {
+                if report {
+                    progress.advance(n)?;
+                }
+                Ok(())
+            });
+            (result, progress)
+        })
         .await
-        .map_err(AiCoreutilsError::Io)?;
+        .map_err(|e| AiCoreutilsError::Io(std::io::Error::other(e)))?
+    };
+
+    let mut hasher = algorithm.map(StreamingHasher::new);
+    let (copied, mechanism, mut progress) = match result? {
+        Some((copied, mechanism)) => (copied, mechanism, progress),
+        None => {
+            let (copied, progress) = buffered_copy(src, dest, config, progress, hasher.as_mut()).await?;
+            (copied, CopyMechanism::Buffered, progress)
+        }
+    };
 
-    let metadata = src_file
-        .metadata()
+    if config.progress {
+        progress.finish()?;
+        let mut record = serde_json::json!({
+            "operation": "copy_complete",
+            "source": src.display().to_string(),
+            "destination": dest.display().to_string(),
+            "bytes_copied": copied,
+            "mechanism": mechanism.as_str(),
+        });
+        if let Some(hasher) = &hasher {
+            record["algorithm"] = serde_json::json!(hasher.algorithm().as_str());
+            record["digest"] = serde_json::json!(hasher.finalize_hex());
+        }
+        jsonl::output_info(record)?;
+    }
+
+    audit::read(src, copied);
+    audit::written(dest, copied);
+    Ok((copied, hasher))
+}
+
+/// Copy through a userspace buffer of `config.buffer_size` bytes, feeding
+/// each buffer to `hasher` if given
+async fn buffered_copy(
+    src: &Path,
+    dest: &Path,
+    config: &AsyncConfig,
+    mut progress: ProgressReporter,
+    mut hasher: Option<&mut StreamingHasher>,
+) -> Result<(u64, ProgressReporter)> {
+    let mut src_file = fs::File::open(src)
         .await
         .map_err(AiCoreutilsError::Io)?;
-    let total_size = metadata.len();
 
     let mut dest_file = fs::File::create(dest)
         .await
@@ -242,10 +987,14 @@ pub async fn async_copy_file(src: &Path, dest: &Path, config: &AsyncConfig) -> R
             .await
             .map_err(AiCoreutilsError::Io)?;
 
+        if let Some(hasher) = hasher.as_deref_mut() {
+            hasher.update(&buffer[..n]);
+        }
+
         copied += n as u64;
 
-        if config.progress && copied.is_multiple_of(1024 * 1024) {
-            jsonl::output_progress(copied as usize, total_size as usize, "Copying file")?;
+        if config.progress {
+            progress.advance(n as u64)?;
         }
     }
 
@@ -254,65 +1003,35 @@ pub async fn async_copy_file(src: &Path, dest: &Path, config: &AsyncConfig) -> R
         .await
         .map_err(AiCoreutilsError::Io)?;
 
-    if config.progress {
-        jsonl::output_info(serde_json::json!({
-            "operation": "copy_complete",
-            "source": src.display().to_string(),
-            "destination": dest.display().to_string(),
-            "bytes_copied": copied,
-        }))?;
-    }
-
-    Ok(copied)
+    Ok((copied, progress))
 }
 
 /// Count lines, words, and bytes in a file asynchronously
+///
+/// Bytes are counted exactly (including `\r\n` terminators and invalid
+/// UTF-8); words are separated by ASCII whitespace, as `wc` does in the C
+/// locale.
 pub async fn async_wc(path: &Path) -> Result<WcCounts> {
-    let file = fs::File::open(path)
-        .await
-        .map_err(AiCoreutilsError::Io)?;
-    let reader = BufReader::new(file);
+    let mut reader = AsyncLineReader::open(path).await?;
 
     let mut lines = 0u64;
     let mut words = 0u64;
     let mut bytes = 0u64;
-    let mut in_word = false;
 
-    let mut line_reader = reader.lines();
-    while let Some(line) = line_reader
-        .next_line()
-        .await
-        .map_err(AiCoreutilsError::Io)?
-    {
+    while let Some(line) = reader.next_line().await? {
         lines += 1;
-        bytes += line.len() as u64 + 1; // +1 for newline
-
-        for c in line.chars() {
-            if c.is_whitespace() {
-                if in_word {
-                    words += 1;
-                    in_word = false;
-                }
-            } else {
-                in_word = true;
-            }
-        }
-
-        // Count word at end of line
-        if in_word {
-            words += 1;
-            in_word = false;
-        }
-    }
-
-    // Handle last word if file doesn't end with newline
-    if in_word {
-        words += 1;
+        bytes += line.byte_len();
+        words += count_words(line.content);
     }
 
     Ok(WcCounts { lines, words, bytes })
 }
 
+fn count_words(data: &[u8]) -> u64 {
+    let is_space = |b: &u8| matches!(b, b' ' | b'\t' | b'\n' | b'\r' | 0x0b | 0x0c);
+    data.split(is_space).filter(|w| !w.is_empty()).count() as u64
+}
+
 /// Word count results
 #[derive(Debug, Clone)]
 pub struct WcCounts {
@@ -324,42 +1043,284 @@ pub struct WcCounts {
     pub bytes: u64,
 }
 
+/// Bytes read per step by the streaming grep
+pub const GREP_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Longest line kept in full by the streaming grep
+///
+/// Longer lines are still searched end to end, in overlapping windows, but
+/// only their first `GREP_MAX_LINE` bytes are returned in [`GrepMatch::line`].
+pub const GREP_MAX_LINE: usize = 1024 * 1024;
+
 /// Search for a pattern in a file asynchronously
+///
+/// The file is streamed in [`GREP_CHUNK_SIZE`] chunks, so memory stays
+/// bounded even for gigabyte logs, and the task yields to the scheduler
+/// after every chunk.
 pub async fn async_grep_file(
     path: &Path,
     pattern: &str,
     case_insensitive: bool,
     invert_match: bool,
 ) -> Result<Vec<GrepMatch>> {
-    let contents = async_read_file_to_string(path).await?;
-    let search_pattern = if case_insensitive {
-        pattern.to_lowercase()
-    } else {
-        pattern.to_string()
-    };
+    let _permit = acquire_fds(1).await;
+    let mut file = fs::File::open(path).await.map_err(AiCoreutilsError::Io)?;
+    let mut scanner = LineScanner::new(path, pattern, case_insensitive, invert_match);
+    let mut chunk = vec![0u8; GREP_CHUNK_SIZE];
+
+    // Compressed files are searched as they are decoded
+    let n = file.read(&mut chunk).await.map_err(AiCoreutilsError::Io)?;
+    #[cfg(feature = "compression")]
+    if let Some(compression) = crate::compress::sniff(&chunk[..n]) {
+        drop(file);
+        return grep_decompressed(path, pattern, case_insensitive, invert_match, compression).await;
+    }
+    scanner.feed(&chunk[..n]);
 
-    let mut matches = Vec::new();
+    loop {
+        let n = file.read(&mut chunk).await.map_err(AiCoreutilsError::Io)?;
+        if n == 0 {
+            break;
+        }
+        scanner.feed(&chunk[..n]);
+        tokio::task::yield_now().await;
+    }
 
-    for (line_num, line) in contents.lines().enumerate() {
-        let search_line = if case_insensitive {
-            line.to_lowercase()
-        } else {
-            line.to_string()
-        };
+    Ok(scanner.finish())
+}
+
+/// Search a compressed file as it is decoded, on the blocking thread pool
+#[cfg(feature = "compression")]
+async fn grep_decompressed(
+    path: &Path,
+    pattern: &str,
+    case_insensitive: bool,
+    invert_match: bool,
+    compression: crate::compress::Compression,
+) -> Result<Vec<GrepMatch>> {
+    let path = path.to_path_buf();
+    let pattern = pattern.to_string();
+    tokio::task::spawn_blocking(move || {
+        use std::io::Read;
+        let mut decoder = crate::compress::Decoder::open(&path, compression)?;
+        let mut scanner = LineScanner::new(&path, &pattern, case_insensitive, invert_match);
+        let mut chunk = vec![0u8; GREP_CHUNK_SIZE];
+        loop {
+            let n = decoder.read(&mut chunk).map_err(|e| decoder.error(e))?;
+            if n == 0 {
+                break;
+            }
+            scanner.feed(&chunk[..n]);
+        }
+        Ok(scanner.finish())
+    })
+    .await
+    .map_err(|e| AiCoreutilsError::Io(std::io::Error::other(e)))?
+}
+
+/// Search for a pattern in a memory-mapped file on the blocking thread pool
+///
+/// Avoids copying the file into the process at all; the kernel pages it in
+/// on demand. Returns the cache policy applied to the mapping.
+pub async fn async_grep_file_mmap(
+    path: &Path,
+    pattern: &str,
+    case_insensitive: bool,
+    invert_match: bool,
+    policy: CachePolicy,
+) -> Result<(Vec<GrepMatch>, CachePolicy)> {
+    let path = path.to_path_buf();
+    let pattern = pattern.to_string();
+    let permit = acquire_fds(1).await;
+
+    tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let options = MapOptions::new().cache_policy(policy).access(AccessPattern::Sequential);
+        let access = SafeMemoryAccess::with_options(&path, &options)?;
+        let data = access.as_slice();
+        let mut scanner = LineScanner::new(&path, &pattern, case_insensitive, invert_match);
+        scanner.feed(data);
+        Ok((scanner.finish(), access.cache_policy()))
+    })
+    .await
+    .map_err(|e| AiCoreutilsError::Io(std::io::Error::other(e)))?
+}
+
+/// Search for a pattern in a file using `config`'s retry and cache policies
+///
+/// With the default cache policy the file is streamed; otherwise it is
+/// memory-mapped with the requested page cache hints (`Direct` is served
+/// as `Stream`, since O_DIRECT would need the whole file in memory). Also
+/// returns the cache policy that was applied.
+pub async fn async_grep_file_with_config(
+    path: &Path,
+    pattern: &str,
+    case_insensitive: bool,
+    invert_match: bool,
+    config: &AsyncConfig,
+) -> Result<(Vec<GrepMatch>, CachePolicy)> {
+    let operation = format!("grep {}", path.display());
+
+    if config.cache_policy == CachePolicy::Normal {
+        let matches = config
+            .retry
+            .run(&operation, || async_grep_file(path, pattern, case_insensitive, invert_match))
+            .await?;
+        return Ok((matches, CachePolicy::Normal));
+    }
+
+    config
+        .retry
+        .run(&operation, || {
+            async_grep_file_mmap(path, pattern, case_insensitive, invert_match, config.cache_policy)
+        })
+        .await
+}
 
-        let is_match = search_line.contains(&search_pattern);
-        let should_include = if invert_match { !is_match } else { is_match };
+/// Incremental line matcher fed with arbitrary chunks of a file
+///
+/// Lines split across chunks are reassembled. Each byte is searched once,
+/// as it arrives: a search starts just far enough back to catch a match
+/// straddling the previous chunk. Lines longer than [`GREP_MAX_LINE`] keep
+/// only that overlap once searched, so memory stays bounded.
+struct LineScanner<'a> {
+    path: &'a Path,
+    search_pattern: String,
+    case_insensitive: bool,
+    invert_match: bool,
+    overlap: usize,
+    matches: Vec<GrepMatch>,
+    /// Bytes of the current line not yet discarded
+    line: Vec<u8>,
+    /// First bytes of the current line once it has been truncated
+    preview: Option<Vec<u8>>,
+    /// Whether the current line has matched so far
+    matched: bool,
+    /// Bytes at the front of `line` already searched
+    scanned: usize,
+    line_offset: u64,
+    line_number: usize,
+    offset: u64,
+}
+
+impl<'a> LineScanner<'a> {
+    fn new(path: &'a Path, pattern: &str, case_insensitive: bool, invert_match: bool) -> Self {
+        Self {
+            path,
+            search_pattern: search_pattern(pattern, case_insensitive),
+            case_insensitive,
+            invert_match,
+            // Extra bytes so a UTF-8 sequence cut at the window edge survives
+            overlap: pattern.len() + 3,
+            matches: Vec::new(),
+            line: Vec::new(),
+            preview: None,
+            matched: false,
+            scanned: 0,
+            line_offset: 0,
+            line_number: 0,
+            offset: 0,
+        }
+    }
+
+    fn feed(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            match data.iter().position(|&b| b == b'\n') {
+                Some(i) => {
+                    self.push(&data[..=i]);
+                    self.end_line();
+                    data = &data[i + 1..];
+                }
+                None => {
+                    self.push(data);
+                    // A `\r` may yet turn out to start the terminator
+                    let end = self.line.len() - usize::from(self.line.ends_with(b"\r"));
+                    self.scan(end);
+                    data = &[];
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.line.extend_from_slice(bytes);
+        self.offset += bytes.len() as u64;
 
-        if should_include {
-            matches.push(GrepMatch {
-                line_number: line_num + 1,
-                line: line.to_string(),
-                path: path.to_path_buf(),
+        if self.line.len() > GREP_MAX_LINE + self.overlap {
+            self.scan(self.line.len());
+            if self.preview.is_none() {
+                self.preview = Some(self.line[..GREP_MAX_LINE].to_vec());
+            }
+            let keep = self.line.len() - self.overlap;
+            self.line.drain(..keep);
+            self.scanned -= keep;
+        }
+    }
+
+    /// Search `line[..end]`, starting `overlap` bytes before where the last
+    /// search ended; nothing is searched once the line has matched
+    fn scan(&mut self, end: usize) {
+        if !self.matched && end > self.scanned {
+            let start = self.scanned.saturating_sub(self.overlap);
+            self.matched = self.contains(&self.line[start..end]);
+        }
+        self.scanned = self.scanned.max(end);
+    }
+
+    fn end_line(&mut self) {
+        self.line_number += 1;
+
+        let content_len = RawLine::split(self.line_number, self.line_offset, &self.line).content.len();
+        self.scan(content_len);
+
+        let raw = std::mem::take(&mut self.line);
+        let line = RawLine::split(self.line_number, self.line_offset, &raw);
+        let is_match = self.matched;
+
+        if is_match != self.invert_match {
+            let text = match self.preview.take() {
+                Some(preview) => String::from_utf8_lossy(&preview).into_owned(),
+                None => line.to_string_lossy().into_owned(),
+            };
+            self.matches.push(GrepMatch {
+                line_number: line.number,
+                byte_offset: line.offset,
+                line: text,
+                path: self.path.to_path_buf(),
             });
         }
+
+        self.line = raw;
+        self.line.clear();
+        self.preview = None;
+        self.matched = false;
+        self.scanned = 0;
+        self.line_offset = self.offset;
+    }
+
+    fn contains(&self, bytes: &[u8]) -> bool {
+        let text = String::from_utf8_lossy(bytes);
+        if self.case_insensitive {
+            text.to_lowercase().contains(&self.search_pattern)
+        } else {
+            text.contains(&self.search_pattern)
+        }
+    }
+
+    fn finish(mut self) -> Vec<GrepMatch> {
+        if !self.line.is_empty() || self.preview.is_some() {
+            self.end_line();
+        }
+        self.matches
     }
+}
 
-    Ok(matches)
+fn search_pattern(pattern: &str, case_insensitive: bool) -> String {
+    if case_insensitive {
+        pattern.to_lowercase()
+    } else {
+        pattern.to_string()
+    }
 }
 
 /// Grep match result
@@ -367,6 +1328,8 @@ pub async fn async_grep_file(
 pub struct GrepMatch {
     /// Line number (1-indexed)
     pub line_number: usize,
+    /// Byte offset of the line in the file
+    pub byte_offset: u64,
     /// Matching line content
     pub line: String,
     /// Path to the file containing the match
@@ -390,6 +1353,166 @@ mod tests {
         assert_eq!(read_data, data);
     }
 
+    /// Held by tests that depend on, or change, the descriptor budget
+    static FD_BUDGET_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    #[tokio::test]
+    async fn test_fd_budget_permits() {
+        let _lock = FD_BUDGET_LOCK.lock().await;
+        assert!(fd_budget() >= 1);
+
+        let permit = acquire_fds(2).await;
+        assert_eq!(permit.num_permits(), 2);
+        drop(permit);
+
+        // A deep tree is walked while holding one directory open at a time
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut dir = temp_dir.path().to_path_buf();
+        for i in 0..40 {
+            dir.push(format!("d{}", i));
+        }
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("leaf.txt"), "x").unwrap();
+
+        let files = async_walk_dir(temp_dir.path()).await.unwrap();
+        assert_eq!(files, vec![dir.join("leaf.txt")]);
+    }
+
+    #[tokio::test]
+    async fn test_copy_with_budget_of_one() {
+        let _lock = FD_BUDGET_LOCK.lock().await;
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let src = temp_dir.path().join("src.txt");
+        let dest = temp_dir.path().join("dest.txt");
+        std::fs::write(&src, "copied with one descriptor to spare").unwrap();
+
+        let previous = fd_budget();
+        set_fd_budget(1);
+        let copy = tokio::time::timeout(
+            Duration::from_secs(10),
+            async_copy_file(&src, &dest, &AsyncConfig::default()),
+        )
+        .await;
+        set_fd_budget(previous);
+
+        assert_eq!(copy.expect("copy waited on the budget").unwrap(), std::fs::metadata(&src).unwrap().len());
+        assert_eq!(std::fs::read(&dest).unwrap(), std::fs::read(&src).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_async_walk_dir_symlink_loops() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::fs::write(root.join("a/b/file.txt"), "x").unwrap();
+        std::os::unix::fs::symlink(root.join("a"), root.join("a/b/up")).unwrap();
+        std::os::unix::fs::symlink(root.join("a/b/file.txt"), root.join("link.txt")).unwrap();
+
+        let files = async_walk_dir(root).await.unwrap();
+        assert_eq!(files, vec![root.join("a/b/file.txt")]);
+
+        let (files, loops) = async_walk_dir_with_policy(root, SymlinkPolicy::Always)
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].path, root.join("a/b/up"));
+        assert_eq!(loops[0].target, root.join("a"));
+    }
+
+    #[tokio::test]
+    async fn test_async_read_file_with_policy() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let data = vec![7u8; 10_000];
+        temp_file.write_all(&data).unwrap();
+
+        for policy in [CachePolicy::Normal, CachePolicy::Stream, CachePolicy::Direct] {
+            let (read_data, applied) =
+                async_read_file_with_policy(temp_file.path(), &RetryPolicy::none(), policy)
+                    .await
+                    .unwrap();
+            assert_eq!(read_data, data);
+            if policy == CachePolicy::Normal {
+                assert_eq!(applied, CachePolicy::Normal);
+            }
+        }
+    }
+
+    #[test]
+    fn test_retry_policy_classification() {
+        let transient = AiCoreutilsError::Io(std::io::Error::new(std::io::ErrorKind::TimedOut, "slow"));
+        let permanent = AiCoreutilsError::Io(std::io::Error::new(std::io::ErrorKind::NotFound, "gone"));
+
+        assert!(RetryPolicy::is_retryable(&transient));
+        assert!(!RetryPolicy::is_retryable(&permanent));
+        assert!(!RetryPolicy::is_retryable(&AiCoreutilsError::InvalidInput("x".into())));
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(300),
+            jitter: false,
+            ..RetryPolicy::default()
+        };
+
+        assert_eq!(policy.backoff_for(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff_for(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff_for(3), Duration::from_millis(300));
+
+        let jittered = RetryPolicy { jitter: true, ..policy };
+        let delay = jittered.backoff_for(1);
+        assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_retry_policy_run_blocking() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..RetryPolicy::with_retries(2)
+        };
+
+        let mut calls = 0;
+        let result = policy.run_blocking("test", || {
+            calls += 1;
+            if calls < 3 {
+                Err(AiCoreutilsError::Io(std::io::Error::new(std::io::ErrorKind::Interrupted, "eintr")))
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        // Permanent errors are returned immediately
+        let mut calls = 0;
+        let result: Result<()> = policy.run_blocking("test", || {
+            calls += 1;
+            Err(AiCoreutilsError::InvalidInput("bad".into()))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy_run_async_exhausts() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..RetryPolicy::with_retries(1)
+        };
+
+        let mut calls = 0;
+        let result: Result<()> = policy
+            .run("test", || {
+                calls += 1;
+                async { Err(AiCoreutilsError::Io(std::io::Error::new(std::io::ErrorKind::TimedOut, "slow"))) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 2);
+    }
+
     #[tokio::test]
     async fn test_async_read_lines() {
         let mut temp_file = NamedTempFile::new().unwrap();
@@ -421,6 +1544,149 @@ mod tests {
         assert_eq!(counts.words, 4);
     }
 
+    #[tokio::test]
+    async fn test_async_wc_exact_bytes() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"one two\r\n\xff\xfe bad\nlast").unwrap();
+
+        let counts = async_wc(temp_file.path()).await.unwrap();
+        assert_eq!(counts.lines, 3);
+        assert_eq!(counts.words, 5);
+        assert_eq!(counts.bytes, 20);
+    }
+
+    #[tokio::test]
+    async fn test_async_copy_file_hashed() {
+        use crate::hash::Digest;
+
+        let dir = tempfile::tempdir().unwrap();
+        let (src, dest) = (dir.path().join("src"), dir.path().join("dest"));
+        let data: Vec<u8> = (0..20_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&src, &data).unwrap();
+
+        // Small buffer so the digest spans many chunks
+        let config = AsyncConfig {
+            buffer_size: 1000,
+            ..AsyncConfig::default()
+        };
+        let copy = async_copy_file_hashed(&src, &dest, &config, HashAlgorithm::Xxh3)
+            .await
+            .unwrap();
+        assert_eq!(copy.bytes, data.len() as u64);
+        assert_eq!(copy.hasher.finalize(), HashAlgorithm::Xxh3.digest(&data));
+        assert_eq!(std::fs::read(&dest).unwrap(), data);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_async_copy_proc_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("status");
+
+        let copied = async_copy_file(Path::new("/proc/self/status"), &dest, &AsyncConfig::default())
+            .await
+            .unwrap();
+        assert!(copied > 0);
+        assert!(std::fs::read_to_string(&dest).unwrap().starts_with("Name:"));
+    }
+
+    #[tokio::test]
+    async fn test_async_line_reader_offsets() {
+        let data: &[u8] = b"a\r\nbc\n\xffd";
+        let mut reader = AsyncLineReader::new(data);
+
+        let first = reader.next_line().await.unwrap().unwrap();
+        assert_eq!((first.number, first.offset), (1, 0));
+        assert_eq!((first.content, first.terminator), (&b"a"[..], &b"\r\n"[..]));
+
+        let second = reader.next_line().await.unwrap().unwrap();
+        assert_eq!((second.offset, second.content), (3, &b"bc"[..]));
+
+        let third = reader.next_line().await.unwrap().unwrap();
+        assert_eq!((third.offset, third.content, third.terminator), (6, &b"\xffd"[..], &b""[..]));
+        assert!(reader.next_line().await.unwrap().is_none());
+
+        let sync: Vec<_> = raw_lines(data).map(|l| (l.number, l.offset, l.byte_len())).collect();
+        assert_eq!(sync, vec![(1, 0, 3), (2, 3, 3), (3, 6, 2)]);
+    }
+
+    #[tokio::test]
+    async fn test_async_grep_invalid_utf8() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"\xff header\nerror: disk full\n").unwrap();
+
+        let matches = async_grep_file(temp_file.path(), "error", false, false).await.unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].line_number, 2);
+        assert_eq!(matches[0].byte_offset, 9);
+    }
+
+    #[test]
+    fn test_line_scanner_long_lines_and_chunk_boundaries() {
+        let mut data = vec![b'a'; GREP_MAX_LINE * 2 + 500];
+        // Straddles the first window boundary
+        let pos = GREP_MAX_LINE + 10 - 3;
+        data[pos..pos + 6].copy_from_slice(b"needle");
+        data.extend_from_slice(b"\nshort needle\r\nnothing\n");
+
+        let path = Path::new("big.log");
+        for chunk_size in [4096, GREP_CHUNK_SIZE] {
+            let mut scanner = LineScanner::new(path, "NEEDLE", true, false);
+            for chunk in data.chunks(chunk_size) {
+                scanner.feed(chunk);
+            }
+            let matches = scanner.finish();
+
+            assert_eq!(matches.len(), 2);
+            assert_eq!(matches[0].line_number, 1);
+            assert_eq!(matches[0].line.len(), GREP_MAX_LINE);
+            assert_eq!(matches[1].line, "short needle");
+            assert_eq!(matches[1].byte_offset, (GREP_MAX_LINE * 2 + 501) as u64);
+        }
+    }
+
+    #[test]
+    fn test_line_scanner_byte_at_a_time() {
+        let mut scanner = LineScanner::new(Path::new("f"), "needle", false, false);
+        for b in b"x needle\ny nee".iter().chain(b"dle\n") {
+            scanner.feed(std::slice::from_ref(b));
+        }
+
+        let matches = scanner.finish();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[1].byte_offset, 9);
+        assert_eq!(matches[1].line, "y needle");
+    }
+
+    #[test]
+    fn test_line_scanner_chunk_ends_in_carriage_return() {
+        let mut scanner = LineScanner::new(Path::new("f"), "dle", false, false);
+        for chunk in [&b"nee"[..], b"dle\r", b"\nno\r", b"dle\n", b"dl\r", b"e\n"] {
+            scanner.feed(chunk);
+        }
+
+        let matches = scanner.finish();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].line, "needle");
+        assert_eq!(matches[1].line, "no\rdle");
+    }
+
+    #[tokio::test]
+    async fn test_async_grep_file_mmap_matches_streaming() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"alpha\nbeta\r\ngamma alpha").unwrap();
+
+        let streamed = async_grep_file(temp_file.path(), "alpha", false, false).await.unwrap();
+        let (mapped, _) =
+            async_grep_file_mmap(temp_file.path(), "alpha", false, false, CachePolicy::Normal)
+                .await
+                .unwrap();
+
+        let summary = |m: &[GrepMatch]| m.iter().map(|m| (m.line_number, m.byte_offset)).collect::<Vec<_>>();
+        assert_eq!(summary(&streamed), vec![(1, 0), (3, 12)]);
+        assert_eq!(summary(&streamed), summary(&mapped));
+    }
+
     #[tokio::test]
     async fn test_async_grep() {
         let mut temp_file = NamedTempFile::new().unwrap();
@@ -437,6 +1703,21 @@ mod tests {
         assert!(matches[0].line.contains("Hello"));
     }
 
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn test_async_grep_decompresses() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let text = "Hello world\nGoodbye\nHello there\n".repeat(1000);
+        temp_file.write_all(&zstd::encode_all(text.as_bytes(), 0).unwrap()).unwrap();
+
+        let matches = async_grep_file(temp_file.path(), "Goodbye", false, false)
+            .await
+            .unwrap();
+        assert_eq!(matches.len(), 1000);
+        assert_eq!(matches[999].line_number, 2999);
+        assert_eq!(matches[0].line, "Goodbye");
+    }
+
     #[tokio::test]
     async fn test_async_grep_case_insensitive() {
         let mut temp_file = NamedTempFile::new().unwrap();
@@ -0,0 +1,330 @@
+//! Audit log of utility invocations
+//!
+//! When `audit.path` or `AI_COREUTILS_AUDIT_LOG` is set, [`crate::config::init`]
+//! starts recording the invocation and every utility appends one JSONL line
+//! to the log as the process exits: its argv, working directory, session,
+//! start and end time, the files it touched and the bytes it read and wrote.
+//! A utility that panics writes the line from the panic hook, since release
+//! builds abort on panic without running exit handlers.
+//!
+//! Files touched are the paths checked by [`crate::policy::check`], which
+//! every utility calls before accessing a path. Bytes are counted by the
+//! library's I/O primitives; memory-mapped files count their full size.
+//! The log is opened for appending when recording starts and each line is
+//! written under an exclusive lock, so concurrent utilities never
+//! interleave records.
+
+use crate::error::{AiCoreutilsError, Result};
+use crate::jsonl;
+use crate::policy::Access;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock, TryLockError};
+use std::time::Instant;
+
+static INVOCATION: OnceLock<Mutex<Invocation>> = OnceLock::new();
+static FINISHED: AtomicBool = AtomicBool::new(false);
+
+/// What one file saw during an invocation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FileActivity {
+    /// Checked for reading
+    pub read: bool,
+    /// Checked for writing
+    pub write: bool,
+    /// Bytes read from it
+    pub bytes_read: u64,
+    /// Bytes written to it
+    pub bytes_written: u64,
+}
+
+/// One invocation being recorded
+#[derive(Debug)]
+pub struct Invocation {
+    log: File,
+    argv: Vec<String>,
+    cwd: PathBuf,
+    start: DateTime<Utc>,
+    started: Instant,
+    files: BTreeMap<PathBuf, FileActivity>,
+    panicked: bool,
+}
+
+impl Invocation {
+    /// Start recording an invocation with `argv`, appending to `log`
+    pub fn new(log: &Path, argv: Vec<String>) -> Result<Self> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log)
+            .map_err(|e| AiCoreutilsError::Config(format!("Cannot open audit log {}: {}", log.display(), e)))?;
+
+        Ok(Self {
+            log: file,
+            argv,
+            cwd: std::env::current_dir().unwrap_or_default(),
+            start: Utc::now(),
+            started: Instant::now(),
+            files: BTreeMap::new(),
+            panicked: false,
+        })
+    }
+
+    /// Note `access` to `path`
+    pub fn touched(&mut self, path: &Path, access: Access) {
+        let activity = self.file(path);
+        match access {
+            Access::Read => activity.read = true,
+            Access::Write => activity.write = true,
+        }
+    }
+
+    /// Add `bytes` read from `path`
+    pub fn read(&mut self, path: &Path, bytes: u64) {
+        self.file(path).bytes_read += bytes;
+    }
+
+    /// Add `bytes` written to `path`
+    pub fn written(&mut self, path: &Path, bytes: u64) {
+        self.file(path).bytes_written += bytes;
+    }
+
+    /// Note that the utility panicked
+    pub fn panicked(&mut self) {
+        self.panicked = true;
+    }
+
+    /// Files touched so far, by absolute path
+    pub fn files(&self) -> &BTreeMap<PathBuf, FileActivity> {
+        &self.files
+    }
+
+    /// The audit record for the invocation, ending now
+    pub fn record(&self) -> serde_json::Value {
+        let files: Vec<serde_json::Value> = self
+            .files
+            .iter()
+            .map(|(path, activity)| {
+                let mut entry = serde_json::json!({ "path": path.display().to_string() });
+                if let (Some(entry), Ok(serde_json::Value::Object(fields))) =
+                    (entry.as_object_mut(), serde_json::to_value(activity))
+                {
+                    entry.extend(fields);
+                }
+                entry
+            })
+            .collect();
+        let tool = self
+            .argv
+            .first()
+            .and_then(|arg0| Path::new(arg0).file_name())
+            .map(|name| name.to_string_lossy().into_owned());
+
+        serde_json::json!({
+            "type": "invocation",
+            "tool": tool,
+            "argv": self.argv,
+            "cwd": self.cwd.display().to_string(),
+            "pid": std::process::id(),
+            "dry_run": jsonl::is_dry_run(),
+            "session": jsonl::session(),
+            "start": self.start.to_rfc3339(),
+            "end": Utc::now().to_rfc3339(),
+            "duration_ms": self.started.elapsed().as_millis() as u64,
+            "panicked": self.panicked,
+            "files": files,
+            "bytes_read": self.files.values().map(|f| f.bytes_read).sum::<u64>(),
+            "bytes_written": self.files.values().map(|f| f.bytes_written).sum::<u64>(),
+        })
+    }
+
+    /// Append the record to the log as one line, under an exclusive lock
+    pub fn finish(&self) -> Result<()> {
+        let mut line = serde_json::to_vec(&self.record())?;
+        line.push(b'\n');
+
+        self.log.lock()?;
+        let written = (&self.log).write_all(&line);
+        let _ = self.log.unlock();
+        Ok(written?)
+    }
+
+    fn file(&mut self, path: &Path) -> &mut FileActivity {
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        self.files.entry(path).or_default()
+    }
+}
+
+/// Start recording this process's invocation into `log`
+///
+/// The record is appended when the process exits, whether `main` returns or
+/// a utility calls `std::process::exit`, or when it panics. Later calls do
+/// nothing.
+pub fn start(log: &Path) -> Result<()> {
+    if INVOCATION.get().is_some() {
+        return Ok(());
+    }
+
+    let argv = std::env::args_os()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    if INVOCATION.set(Mutex::new(Invocation::new(log, argv)?)).is_err() {
+        return Ok(());
+    }
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        previous(info);
+        if cfg!(panic = "abort") {
+            // Aborting skips the exit handler
+            finish(true);
+        } else if std::thread::current().name() == Some("main") {
+            // Unwinding out of `main` ends in exit, which writes the record
+            if let Some(Ok(mut invocation)) = INVOCATION.get().map(Mutex::try_lock) {
+                invocation.panicked();
+            }
+        }
+    }));
+
+    #[cfg(unix)]
+    // SAFETY: registers a handler that only touches process-wide statics
+    unsafe {
+        libc::atexit(finish_at_exit);
+    }
+    // Without atexit, the invocation is logged as it starts
+    #[cfg(not(unix))]
+    finish(false);
+    Ok(())
+}
+
+/// Note `access` to `path`
+pub fn touched(path: &Path, access: Access) {
+    with_invocation(|invocation| invocation.touched(path, access));
+}
+
+/// Add `bytes` read from `path`
+///
+/// Counted for [telemetry](crate::telemetry) whether or not a log is kept.
+pub fn read(path: &Path, bytes: u64) {
+    crate::telemetry::read(bytes);
+    with_invocation(|invocation| invocation.read(path, bytes));
+}
+
+/// Add `bytes` written to `path`
+///
+/// Counted for [telemetry](crate::telemetry) whether or not a log is kept.
+pub fn written(path: &Path, bytes: u64) {
+    crate::telemetry::written(bytes);
+    with_invocation(|invocation| invocation.written(path, bytes));
+}
+
+/// Write the record now, once; errors are reported on stderr, as stdout may
+/// be gone
+///
+/// Never waits for the record: the exiting or panicking thread may itself
+/// hold it, and another thread may never release it.
+fn finish(panicked: bool) {
+    let Some(invocation) = INVOCATION.get() else {
+        return;
+    };
+    if FINISHED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let mut invocation = match invocation.try_lock() {
+        Ok(invocation) => invocation,
+        // A panic elsewhere must not stop the record being written
+        Err(TryLockError::Poisoned(e)) => e.into_inner(),
+        Err(TryLockError::WouldBlock) => {
+            eprintln!("ai-coreutils: audit record not written: it was being updated as the process ended");
+            return;
+        }
+    };
+    if panicked {
+        invocation.panicked();
+    }
+    if let Err(e) = invocation.finish() {
+        eprintln!("ai-coreutils: failed to write audit log: {}", e);
+    }
+}
+
+#[cfg(unix)]
+extern "C" fn finish_at_exit() {
+    finish(false);
+}
+
+fn with_invocation(f: impl FnOnce(&mut Invocation)) {
+    if let Some(invocation) = INVOCATION.get() {
+        // A panic elsewhere must not stop the record being written
+        let mut invocation = invocation.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut invocation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_accumulates_per_file() {
+        let temp = TempDir::new().unwrap();
+        let log = temp.path().join("audit.jsonl");
+        let argv = vec!["/usr/bin/ai-cp".to_string(), "a".to_string(), "b".to_string()];
+        let mut invocation = Invocation::new(&log, argv).unwrap();
+
+        let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+        invocation.touched(&a, Access::Read);
+        invocation.touched(&b, Access::Write);
+        invocation.read(&a, 10);
+        invocation.read(&a, 5);
+        invocation.written(&b, 15);
+
+        assert_eq!(invocation.files().len(), 2);
+        assert_eq!(invocation.files()[&a].bytes_read, 15);
+
+        let record = invocation.record();
+        assert_eq!(record["tool"], "ai-cp");
+        assert_eq!(record["argv"][1], "a");
+        assert_eq!(record["bytes_read"], 15);
+        assert_eq!(record["bytes_written"], 15);
+        assert_eq!(record["files"][1]["path"], b.display().to_string());
+        assert_eq!(record["files"][1]["write"], true);
+        assert_eq!(record["files"][1]["read"], false);
+        assert_eq!(record["panicked"], false);
+
+        invocation.panicked();
+        assert_eq!(invocation.record()["panicked"], true);
+    }
+
+    #[test]
+    fn test_finish_appends_lines() {
+        let temp = TempDir::new().unwrap();
+        let log = temp.path().join("audit.jsonl");
+        fs::write(&log, "{\"earlier\":true}\n").unwrap();
+
+        for _ in 0..2 {
+            let invocation = Invocation::new(&log, vec!["ai-ls".to_string()]).unwrap();
+            invocation.finish().unwrap();
+        }
+
+        let contents = fs::read_to_string(&log).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let record: serde_json::Value = serde_json::from_str(lines[2]).unwrap();
+        assert_eq!(record["type"], "invocation");
+        assert!(record["files"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unopenable_log_is_an_error() {
+        let temp = TempDir::new().unwrap();
+        let log = temp.path().join("missing/audit.jsonl");
+        let err = Invocation::new(&log, Vec::new()).unwrap_err();
+        assert!(matches!(err, AiCoreutilsError::Config(_)));
+    }
+}
@@ -0,0 +1,589 @@
+//! Built-in benchmarks and the machine profile derived from them
+//!
+//! ai-bench runs four suites on the current machine: the SIMD kernels over
+//! an in-memory buffer ([`simd_suite`]), the async read-and-search pipeline
+//! of ai-grep over a synthetic corpus at increasing concurrency
+//! ([`grep_suite`]), file copies, zero-copy and through buffers of
+//! increasing size ([`copy_suite`]), and cold-cache scans of a memory-mapped
+//! file under each set of mapping hints ([`mmap_suite`]). Each timing is a
+//! [`Measurement`].
+//!
+//! A [`Profile`] sums the measurements up as the settings that did best on
+//! this machine: the smallest concurrency and buffer size within
+//! [`TOLERANCE`] of the fastest, since past that point more tasks and more
+//! memory buy almost nothing. Profiles are JSON, kept by default in
+//! `ai-coreutils/profile.json` under the platform data directory, where
+//! [`crate::tuning`] reads them.
+
+use crate::async_ops::async_read_file;
+use crate::error::{AiCoreutilsError, Result};
+use crate::fs_utils;
+use crate::grep::Matcher;
+use crate::memory::{AccessPattern, MapOptions, SafeMemoryAccess};
+use crate::simd_ops::{
+    SimdByteCounter, SimdConfig, SimdEntropyCalculator, SimdHasher, SimdPatternSearcher,
+    SimdWhitespaceDetector,
+};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Concurrency levels tried by the grep suite, up to four per CPU
+pub const CONCURRENCY_LEVELS: [usize; 7] = [1, 2, 4, 8, 16, 32, 64];
+
+/// Buffer sizes tried by the copy suite
+pub const BUFFER_SIZES: [usize; 6] = [4096, 8192, 64 * 1024, 256 * 1024, 1024 * 1024, 4 * 1024 * 1024];
+
+/// Fraction of the best throughput a smaller setting may fall short by and
+/// still be recommended
+pub const TOLERANCE: f64 = 0.1;
+
+/// Pattern the SIMD and grep suites search for; about one corpus line in 50
+/// holds it
+pub const PATTERN: &str = "ERROR";
+
+const MIB: f64 = 1024.0 * 1024.0;
+
+/// How much data the suites use and how long each timing runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchOptions {
+    /// Size of the buffer the SIMD kernels scan
+    pub data_size: usize,
+    /// Number of files in the grep corpus
+    pub corpus_files: usize,
+    /// Size of each corpus file
+    pub corpus_file_size: usize,
+    /// Size of the file the copy suite copies
+    pub copy_size: usize,
+    /// Size of the file the mmap suite scans
+    pub scan_size: usize,
+    /// Each timing repeats its operation for at least this long
+    pub min_time: Duration,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            data_size: 16 * 1024 * 1024,
+            corpus_files: 64,
+            corpus_file_size: 256 * 1024,
+            copy_size: 32 * 1024 * 1024,
+            scan_size: 64 * 1024 * 1024,
+            min_time: Duration::from_millis(200),
+        }
+    }
+}
+
+impl BenchOptions {
+    /// Small data and short timings: a rough profile in about a second
+    pub fn quick() -> Self {
+        Self {
+            data_size: 1024 * 1024,
+            corpus_files: 16,
+            corpus_file_size: 64 * 1024,
+            copy_size: 4 * 1024 * 1024,
+            scan_size: 4 * 1024 * 1024,
+            min_time: Duration::from_millis(20),
+        }
+    }
+}
+
+/// One timed operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Measurement {
+    /// Suite it belongs to: `simd`, `grep` or `copy`
+    pub suite: String,
+    /// Operation timed
+    pub name: String,
+    /// Settings it ran with, such as `concurrency` or `buffer_size`
+    pub params: serde_json::Value,
+    /// Bytes processed by one iteration
+    pub bytes: u64,
+    /// Iterations timed
+    pub iterations: u64,
+    /// Time they took
+    pub seconds: f64,
+}
+
+impl Measurement {
+    /// Throughput in MiB per second
+    pub fn mib_per_sec(&self) -> f64 {
+        if self.seconds > 0.0 {
+            (self.bytes * self.iterations) as f64 / MIB / self.seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// The `bench_result` record for this measurement
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "type": "bench_result",
+            "suite": self.suite,
+            "name": self.name,
+            "params": self.params,
+            "bytes": self.bytes,
+            "iterations": self.iterations,
+            "seconds": self.seconds,
+            "mib_per_sec": (self.mib_per_sec() * 100.0).round() / 100.0,
+        })
+    }
+
+    /// The value of the integer parameter `name`, if set
+    fn param(&self, name: &str) -> Option<usize> {
+        self.params.get(name)?.as_u64().map(|value| value as usize)
+    }
+}
+
+/// Time `op` until it has run for at least `min_time`, after one untimed
+/// warm-up run
+fn measure(suite: &str, name: &str, params: serde_json::Value, bytes: usize, min_time: Duration, mut op: impl FnMut()) -> Measurement {
+    op();
+    let started = Instant::now();
+    let mut iterations = 0;
+    while iterations == 0 || started.elapsed() < min_time {
+        op();
+        iterations += 1;
+    }
+    Measurement {
+        suite: suite.to_string(),
+        name: name.to_string(),
+        params,
+        bytes: bytes as u64,
+        iterations,
+        seconds: started.elapsed().as_secs_f64(),
+    }
+}
+
+/// `size` bytes of log-like text, the same on every run
+pub fn synthetic_text(size: usize) -> Vec<u8> {
+    const LEVELS: [&str; 4] = ["INFO", "DEBUG", "WARN", "INFO"];
+    let mut text = Vec::with_capacity(size + 128);
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut line = 0u64;
+    while text.len() < size {
+        // xorshift, so the content varies without a random number crate
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let level = if line % 50 == 49 { PATTERN } else { LEVELS[(state % 4) as usize] };
+        let entry = format!(
+            "2026-01-01T{:02}:{:02}:{:02}Z {} request={} path=/api/v1/items/{} status={} took={}ms\n",
+            line / 3600 % 24,
+            line / 60 % 60,
+            line % 60,
+            level,
+            state >> 40,
+            state % 10_000,
+            [200, 201, 304, 404, 500][(state >> 8) as usize % 5],
+            state >> 20 & 0x3ff,
+        );
+        text.extend_from_slice(entry.as_bytes());
+        line += 1;
+    }
+    text.truncate(size);
+    text
+}
+
+/// Time the SIMD kernels over `options.data_size` bytes of text
+///
+/// Byte counting, pattern search and word counting are also timed on the
+/// scalar path, so the gain of the instruction set in use shows.
+pub fn simd_suite(options: &BenchOptions) -> Vec<Measurement> {
+    let data = synthetic_text(options.data_size);
+    let simd = SimdConfig::detect();
+    let scalar = SimdConfig {
+        enabled: false,
+        vector_width: 1,
+    };
+    let path = json!({"path": simd.path()});
+    let (size, min_time) = (data.len(), options.min_time);
+    let mut measurements = Vec::new();
+
+    for config in [simd.clone(), scalar] {
+        let params = json!({"path": config.path()});
+        let counter = SimdByteCounter::with_config(config.clone());
+        measurements.push(measure("simd", "count_byte", params.clone(), size, min_time, || {
+            black_box(counter.count(black_box(&data), b'\n'));
+        }));
+        let searcher = SimdPatternSearcher::with_config(config.clone());
+        measurements.push(measure("simd", "find_all", params.clone(), size, min_time, || {
+            black_box(searcher.find_all(black_box(&data), PATTERN.as_bytes()));
+        }));
+        let whitespace = SimdWhitespaceDetector::with_config(config.clone());
+        measurements.push(measure("simd", "count_words", params, size, min_time, || {
+            black_box(whitespace.count_words(black_box(&data)));
+        }));
+        if !simd.enabled {
+            break;
+        }
+    }
+
+    let whitespace = SimdWhitespaceDetector::new();
+    measurements.push(measure("simd", "count_lines", path.clone(), size, min_time, || {
+        black_box(whitespace.count_lines(black_box(&data)));
+    }));
+    let hasher = SimdHasher::new();
+    measurements.push(measure("simd", "xxh3", path.clone(), size, min_time, || {
+        black_box(hasher.xxh3(black_box(&data)));
+    }));
+    measurements.push(measure("simd", "crc32", path.clone(), size, min_time, || {
+        black_box(hasher.crc32(black_box(&data)));
+    }));
+    let entropy = SimdEntropyCalculator::new();
+    measurements.push(measure("simd", "entropy", path, size, min_time, || {
+        black_box(entropy.calculate_entropy(black_box(&data)));
+    }));
+    measurements
+}
+
+/// Concurrency levels worth trying on a machine with `cpus` CPUs
+pub fn concurrency_levels(cpus: usize) -> Vec<usize> {
+    let max = (cpus * 4).max(8);
+    CONCURRENCY_LEVELS.into_iter().filter(|&level| level <= max).collect()
+}
+
+/// Time ai-grep's async pipeline over a corpus written to `dir`
+///
+/// Files are read `concurrency` at a time and searched as they arrive, as
+/// `ai-grep -a -j N` does. The corpus was just written, so it is read from
+/// the page cache; the timings show how many reads in flight keep the CPUs
+/// busy, not the speed of the disk.
+pub async fn grep_suite(options: &BenchOptions, dir: &Path, cpus: usize) -> Result<Vec<Measurement>> {
+    let text = synthetic_text(options.corpus_file_size);
+    let mut files = Vec::with_capacity(options.corpus_files);
+    for i in 0..options.corpus_files {
+        let path = dir.join(format!("corpus-{:04}.log", i));
+        fs::write(&path, &text)?;
+        files.push(path);
+    }
+    let matcher = Matcher::new(PATTERN, false, false);
+    let bytes = text.len() * files.len();
+
+    let mut measurements = Vec::new();
+    for concurrency in concurrency_levels(cpus) {
+        grep_files(&files, &matcher, concurrency).await?;
+        let started = Instant::now();
+        let mut iterations = 0;
+        while iterations == 0 || started.elapsed() < options.min_time {
+            black_box(grep_files(&files, &matcher, concurrency).await?);
+            iterations += 1;
+        }
+        measurements.push(Measurement {
+            suite: "grep".to_string(),
+            name: "async_grep".to_string(),
+            params: json!({"concurrency": concurrency, "files": files.len()}),
+            bytes: bytes as u64,
+            iterations,
+            seconds: started.elapsed().as_secs_f64(),
+        });
+    }
+    Ok(measurements)
+}
+
+/// Read `files` `concurrency` at a time and count the selected lines
+async fn grep_files(files: &[PathBuf], matcher: &Matcher, concurrency: usize) -> Result<u64> {
+    let mut reads = stream::iter(files).map(|file| async_read_file(file)).buffer_unordered(concurrency);
+    let mut selected = 0;
+    while let Some(data) = reads.next().await {
+        selected += matcher.matches(&data?).count() as u64;
+    }
+    Ok(selected)
+}
+
+/// Time copies of an `options.copy_size` file in `dir`
+///
+/// The zero-copy mechanism of the platform is timed if there is one, then
+/// async copies through a buffer of each of [`BUFFER_SIZES`], the way
+/// ai-cp copies when it has to see the data.
+pub async fn copy_suite(options: &BenchOptions, dir: &Path) -> Result<Vec<Measurement>> {
+    let src = dir.join("copy-source.bin");
+    let dest = dir.join("copy-dest.bin");
+    fs::write(&src, synthetic_text(options.copy_size))?;
+    let size = options.copy_size;
+    let mut measurements = Vec::new();
+
+    let (kernel_src, kernel_dest, min_time) = (src.clone(), dest.clone(), options.min_time);
+    let kernel = tokio::task::spawn_blocking(move || -> Result<Option<Measurement>> {
+        let Some((_, mechanism)) = fs_utils::kernel_copy(&kernel_src, &kernel_dest, |_| Ok(()))? else {
+            return Ok(None);
+        };
+        let mut failed = None;
+        let measurement = measure("copy", "kernel_copy", json!({"mechanism": mechanism.as_str()}), size, min_time, || {
+            if let Err(e) = fs_utils::kernel_copy(&kernel_src, &kernel_dest, |_| Ok(())) {
+                failed = Some(e);
+            }
+        });
+        match failed {
+            Some(e) => Err(e),
+            None => Ok(Some(measurement)),
+        }
+    })
+    .await
+    .map_err(|e| AiCoreutilsError::Io(std::io::Error::other(e)))??;
+    measurements.extend(kernel);
+
+    for buffer_size in BUFFER_SIZES {
+        buffered_copy(&src, &dest, buffer_size).await?;
+        let started = Instant::now();
+        let mut iterations = 0;
+        while iterations == 0 || started.elapsed() < options.min_time {
+            buffered_copy(&src, &dest, buffer_size).await?;
+            iterations += 1;
+        }
+        measurements.push(Measurement {
+            suite: "copy".to_string(),
+            name: "buffered_copy".to_string(),
+            params: json!({"buffer_size": buffer_size}),
+            bytes: size as u64,
+            iterations,
+            seconds: started.elapsed().as_secs_f64(),
+        });
+    }
+    Ok(measurements)
+}
+
+async fn buffered_copy(src: &Path, dest: &Path, buffer_size: usize) -> Result<()> {
+    let mut src = tokio::fs::File::open(src).await?;
+    let mut dest = tokio::fs::File::create(dest).await?;
+    let mut buffer = vec![0u8; buffer_size];
+    loop {
+        let n = src.read(&mut buffer).await?;
+        if n == 0 {
+            break;
+        }
+        dest.write_all(&buffer[..n]).await?;
+    }
+    dest.flush().await?;
+    Ok(())
+}
+
+/// Mapping hints the mmap suite compares
+fn scan_hints() -> Vec<(&'static str, MapOptions)> {
+    vec![
+        ("normal", MapOptions::new()),
+        ("sequential", MapOptions::new().access(AccessPattern::Sequential)),
+        ("random", MapOptions::new().access(AccessPattern::Random)),
+        ("will_need", MapOptions::new().access(AccessPattern::Sequential).will_need(true)),
+        ("populate", MapOptions::new().populate(true)),
+        ("huge_pages", MapOptions::new().access(AccessPattern::Sequential).huge_pages(true)),
+    ]
+}
+
+/// Time newline counts over an `options.scan_size` file in `dir`, mapped
+/// with each set of hints
+///
+/// The file's cached pages are dropped before every scan, so each timing
+/// includes reading it from storage, the case read-ahead hints are for.
+/// Only clean pages can be dropped and not every platform allows it;
+/// `cold` in the params says whether it was. Dropping the cache is not
+/// timed.
+pub fn mmap_suite(options: &BenchOptions, dir: &Path) -> Result<Vec<Measurement>> {
+    let path = dir.join("scan.log");
+    fs::write(&path, synthetic_text(options.scan_size))?;
+    let file = fs::File::open(&path)?;
+    // Written pages stay dirty, and so cached, until they reach the disk
+    file.sync_all()?;
+
+    let mut measurements = Vec::new();
+    for (name, hints) in scan_hints() {
+        let mut cold = true;
+        let mut applied = hints;
+        let mut elapsed = Duration::ZERO;
+        let mut iterations = 0;
+        while iterations == 0 || elapsed < options.min_time {
+            cold &= fs_utils::drop_cache(&file);
+            let started = Instant::now();
+            let access = SafeMemoryAccess::with_options(&path, &hints)?;
+            black_box(access.count_byte(b'\n'));
+            elapsed += started.elapsed();
+            applied = access.map_options();
+            iterations += 1;
+        }
+        measurements.push(Measurement {
+            suite: "mmap".to_string(),
+            name: "cold_scan".to_string(),
+            params: json!({"hints": name, "applied": applied == hints, "cold": cold}),
+            bytes: options.scan_size as u64,
+            iterations,
+            seconds: elapsed.as_secs_f64(),
+        });
+    }
+    Ok(measurements)
+}
+
+/// The smallest setting whose throughput is within [`TOLERANCE`] of the best
+pub fn smallest_near_best(candidates: impl IntoIterator<Item = (usize, f64)>) -> Option<usize> {
+    let candidates: Vec<(usize, f64)> = candidates.into_iter().collect();
+    let best = candidates.iter().map(|&(_, throughput)| throughput).fold(f64::NAN, f64::max);
+    candidates
+        .into_iter()
+        .filter(|&(_, throughput)| throughput >= best * (1.0 - TOLERANCE))
+        .map(|(setting, _)| setting)
+        .min()
+}
+
+/// What the benchmarks found about this machine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    /// Version of the library that measured it
+    pub version: String,
+    /// When it was measured
+    pub created: DateTime<Utc>,
+    /// CPUs available to the process
+    pub cpus: usize,
+    /// SIMD instruction set in use
+    pub simd: String,
+    /// Recommended number of concurrent file operations, if the grep suite ran
+    pub concurrency: Option<usize>,
+    /// Recommended I/O buffer size in bytes, if the copy suite ran
+    pub buffer_size: Option<usize>,
+    /// Every timing the recommendations are based on
+    pub measurements: Vec<Measurement>,
+}
+
+impl Profile {
+    /// The profile of `measurements` taken now on this machine
+    pub fn from_measurements(measurements: Vec<Measurement>) -> Self {
+        let throughputs = |suite: &str, param: &str| -> Vec<(usize, f64)> {
+            measurements
+                .iter()
+                .filter(|m| m.suite == suite)
+                .filter_map(|m| Some((m.param(param)?, m.mib_per_sec())))
+                .collect()
+        };
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            created: Utc::now(),
+            cpus: available_cpus(),
+            simd: SimdConfig::detect().path().to_string(),
+            concurrency: smallest_near_best(throughputs("grep", "concurrency")),
+            buffer_size: smallest_near_best(throughputs("copy", "buffer_size")),
+            measurements,
+        }
+    }
+
+    /// Where profiles are kept unless a path is given
+    pub fn default_path() -> Option<PathBuf> {
+        Some(dirs::data_local_dir()?.join("ai-coreutils").join("profile.json"))
+    }
+
+    /// Read a profile from `path`
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => AiCoreutilsError::PathNotFound(path.to_path_buf()),
+            _ => AiCoreutilsError::Io(e),
+        })?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Atomically write the profile to `path`, creating its directory
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut json = serde_json::to_vec_pretty(self)?;
+        json.push(b'\n');
+        fs_utils::atomic_write(path, &json)
+    }
+
+    /// The `bench_profile` record: the profile without its measurements
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "type": "bench_profile",
+            "version": self.version,
+            "created": self.created.to_rfc3339(),
+            "cpus": self.cpus,
+            "simd": self.simd,
+            "concurrency": self.concurrency,
+            "buffer_size": self.buffer_size,
+            "measurements": self.measurements.len(),
+        })
+    }
+}
+
+/// CPUs available to the process, at least 1
+pub fn available_cpus() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn measurement(suite: &str, params: serde_json::Value, seconds: f64) -> Measurement {
+        Measurement {
+            suite: suite.to_string(),
+            name: "op".to_string(),
+            params,
+            bytes: 1024 * 1024,
+            iterations: 10,
+            seconds,
+        }
+    }
+
+    #[test]
+    fn test_smallest_near_best() {
+        assert_eq!(smallest_near_best([(1, 100.0), (2, 190.0), (4, 200.0), (8, 195.0)]), Some(2));
+        assert_eq!(smallest_near_best([(1, 100.0), (2, 150.0), (4, 200.0)]), Some(4));
+        assert_eq!(smallest_near_best([]), None);
+    }
+
+    #[test]
+    fn test_profile_from_measurements_and_roundtrip() {
+        let profile = Profile::from_measurements(vec![
+            measurement("grep", json!({"concurrency": 1}), 1.0),
+            measurement("grep", json!({"concurrency": 4}), 0.25),
+            measurement("grep", json!({"concurrency": 8}), 0.24),
+            measurement("copy", json!({"buffer_size": 4096}), 1.0),
+            measurement("copy", json!({"buffer_size": 65536}), 0.5),
+            measurement("copy", json!({"mechanism": "copy_file_range"}), 0.1),
+        ]);
+        assert_eq!(profile.concurrency, Some(4));
+        assert_eq!(profile.buffer_size, Some(65536));
+        assert_eq!(profile.measurements[0].mib_per_sec(), 10.0);
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nested").join("profile.json");
+        profile.save(&path).unwrap();
+        assert_eq!(Profile::load(&path).unwrap(), profile);
+    }
+
+    #[test]
+    fn test_simd_suite_and_text() {
+        let text = synthetic_text(10_000);
+        assert_eq!(text, synthetic_text(10_000));
+        assert_eq!(text.len(), 10_000);
+        assert!(SimdPatternSearcher::new().find_first(&text, PATTERN.as_bytes()).is_some());
+
+        let options = BenchOptions {
+            data_size: 4096,
+            min_time: Duration::ZERO,
+            ..BenchOptions::quick()
+        };
+        let measurements = simd_suite(&options);
+        assert!(measurements.iter().any(|m| m.name == "find_all"));
+        assert!(measurements.iter().all(|m| m.bytes == 4096 && m.iterations >= 1));
+    }
+
+    #[test]
+    fn test_mmap_suite() {
+        let dir = TempDir::new().unwrap();
+        let options = BenchOptions {
+            scan_size: 64 * 1024,
+            min_time: Duration::ZERO,
+            ..BenchOptions::quick()
+        };
+        let measurements = mmap_suite(&options, dir.path()).unwrap();
+        assert_eq!(measurements.len(), scan_hints().len());
+        assert!(measurements.iter().all(|m| m.suite == "mmap" && m.bytes == 64 * 1024));
+        assert_eq!(measurements[1].params["hints"], "sequential");
+    }
+}
@@ -1,13 +1,37 @@
 //! AI-Analyze: Intelligent file analysis utility
 //!
 //! Provides AI-powered pattern detection, file classification, and content analysis.
-
+//!
+//! With the `compression` feature, gzip, zstd and xz files are analyzed
+//! decompressed unless `--no-decompress` is given.
+//!
+//! Classification and pattern analysis are cached by content and options
+//! (see [`ai_coreutils::result_cache`]), so a repeated run over unchanged
+//! files reads them back; `--no-cache` analyzes every file again.
+//! `--changed-since MANIFEST` compares a tree with an ai-snapshot manifest
+//! and analyzes only the files that changed, reusing the cached results of
+//! the rest by their recorded digest without reading them.
+
+use ai_coreutils::cli::{DecompressArgs, LimitArgs, LogArgs};
+use ai_coreutils::config;
 use ai_coreutils::error::Result;
-use ai_coreutils::jsonl;
-use ai_coreutils::ml_ops::{FileClassifier, MlConfig, PatternDetector};
+use ai_coreutils::checksum::DriftKind;
+use ai_coreutils::fs_utils::{self, DirWalk, SymlinkPolicy, WalkEvent};
+use ai_coreutils::hash::HashAlgorithm;
+use ai_coreutils::jsonl::{self, LogLevel, ProgressReporter, RecordData};
+use ai_coreutils::limits;
+use ai_coreutils::ml_ops::{
+    document_text, sqlite_info, ContentAnalysis, DocumentKind, FileClassification, FileClassifier, MlConfig,
+    OverlapPolicy, PatternDetector,
+};
+use ai_coreutils::policy::{self, Access};
+use ai_coreutils::result_cache::{CacheKey, ResultCache};
+use ai_coreutils::snapshot::{SnapshotEntry, TreeSnapshot};
 use clap::Parser;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// AI-powered file analysis utility with pattern detection and classification
 #[derive(Parser, Debug)]
@@ -29,7 +53,7 @@ struct Cli {
     #[arg(short = 's', long)]
     statistics: bool,
 
-    /// Detect specific pattern types (comma-separated: email,url,ip,phone,ssn,creditcard,uuid,date,hex,base64)
+    /// Detect specific pattern types (comma-separated: email,url,ip,phone,ssn,creditcard,uuid,date,hex,base64,stacktrace)
     #[arg(short = 't', long)]
     pattern_types: Option<String>,
 
@@ -41,6 +65,10 @@ struct Cli {
     #[arg(short = 'r', long)]
     recursive: bool,
 
+    /// When to follow symlinks in recursive analysis: never, command-line or always
+    #[arg(long, value_name = "WHEN", default_value = "always")]
+    follow_symlinks: SymlinkPolicy,
+
     /// Output results in JSONL format
     #[arg(short = 'j', long, default_value_t = true)]
     jsonl: bool,
@@ -48,10 +76,56 @@ struct Cli {
     /// Verbose output
     #[arg(short = 'v', long)]
     verbose: bool,
+
+    /// Raise the confidence of file path matches that exist on disk
+    #[arg(long)]
+    check_paths: bool,
+
+    /// How overlapping matches are resolved: all, longest or priority
+    #[arg(long, value_name = "POLICY", default_value = "longest")]
+    overlap: OverlapPolicy,
+
+    /// Window size in bytes for high-entropy string detection (0 disables it)
+    #[arg(long, value_name = "BYTES", default_value_t = 20)]
+    entropy_window: usize,
+
+    /// Analyze every file again instead of reusing cached results
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Only analyze files that differ from this ai-snapshot manifest, reusing earlier results for the rest
+    #[arg(long, value_name = "MANIFEST")]
+    changed_since: Option<PathBuf>,
+
+    /// With --changed-since, record the tree's current state in MANIFEST
+    #[arg(long, requires = "changed_since")]
+    update_manifest: bool,
+
+    #[command(flatten)]
+    decompress: DecompressArgs,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let settings = config::init()?;
+    cli.limits.apply()?;
+    cli.log.apply();
+    cli.decompress.apply();
+    policy::enforce(cli.files.iter().chain(&cli.changed_since), Access::Read)?;
+    if cli.update_manifest {
+        policy::enforce(&cli.changed_since, Access::Write)?;
+    }
+    #[cfg(feature = "remote")]
+    let cli = Cli {
+        files: ai_coreutils::remote::localize(&cli.files)?,
+        ..cli
+    };
 
     // Validate confidence threshold
     if cli.min_confidence < 0.0 || cli.min_confidence > 1.0 {
@@ -68,25 +142,45 @@ fn main() -> Result<()> {
         detect_patterns: cli.patterns,
         min_confidence: cli.min_confidence,
         max_samples: 10000,
+        check_paths: cli.check_paths,
+        overlap: cli.overlap,
+        entropy_window: cli.entropy_window,
     };
 
-    let detector = PatternDetector::with_config(config)?;
+    let mut detector = PatternDetector::with_config(config)?;
+
+    // Register pattern packs from the shared configuration
+    for (pack, patterns) in &settings.pattern_packs {
+        for (index, pattern) in patterns.iter().enumerate() {
+            detector.add_custom_pattern(&format!("{}:{}", pack, index), pattern)?;
+        }
+    }
+
+    // Path checks depend on the filesystem, not just the content
+    let cache = if cli.no_cache || cli.check_paths { None } else { ResultCache::from_config() };
+
+    if let Some(manifest) = &cli.changed_since {
+        return analyze_changed_since(&detector, &cli, cache.as_ref(), manifest);
+    }
 
     // Process each input file/directory
+    let mut tally = Tally::default();
     for file_path in &cli.files {
         if file_path.is_dir() {
             if cli.recursive {
-                analyze_directory_recursive(&detector, &cli, file_path)?;
+                analyze_directory_recursive(&detector, &cli, cache.as_ref(), file_path, &mut tally)?;
             } else {
+                tally.errors += 1;
                 jsonl::output_error(
                     &format!("{} is a directory (use -r for recursive)", file_path.display()),
                     "IS_DIRECTORY",
                     Some(file_path.display().to_string().as_str()),
                 )?;
             }
-        } else if file_path.exists() {
-            analyze_file(&detector, &cli, file_path)?;
+        } else if file_path.exists() || is_member(file_path) {
+            tally.add(analyze_file(&detector, &cli, cache.as_ref(), file_path, None)?);
         } else {
+            tally.errors += 1;
             jsonl::output_error(
                 &format!("File not found: {}", file_path.display()),
                 "FILE_NOT_FOUND",
@@ -95,68 +189,158 @@ fn main() -> Result<()> {
         }
     }
 
-    Ok(())
+    jsonl::output_summary(serde_json::json!({
+        "type": "analyze_summary",
+        "files_analyzed": tally.files,
+        "cache_hits": tally.cache_hits,
+        "errors": tally.errors,
+    }))
 }
 
-fn analyze_file(detector: &PatternDetector, cli: &Cli, file_path: &PathBuf) -> Result<()> {
-    if cli.verbose {
-        jsonl::output_info(serde_json::json!({
-            "file": file_path.display().to_string(),
-            "operation": "analyze",
-            "status": "starting",
-        }))?;
+/// Files a run analyzed, for the `analyze_summary`
+#[derive(Debug, Default)]
+struct Tally {
+    files: u64,
+    cache_hits: u64,
+    errors: u64,
+}
+
+impl Tally {
+    /// Count an analyzed file, given whether it came from the cache
+    fn add(&mut self, cache_hit: bool) {
+        self.files += 1;
+        self.cache_hits += u64::from(cache_hit);
     }
+}
 
-    // Read file content
-    let content = fs::read(file_path)
-        .map_err(ai_coreutils::error::AiCoreutilsError::Io)?;
+/// How a file compares with the `--changed-since` manifest
+struct Since<'a> {
+    change: &'static str,
+    /// Digest of the file's current contents
+    digest: &'a str,
+    algorithm: HashAlgorithm,
+}
 
-    // Classify file
-    if cli.classify {
-        let classification = FileClassifier::classify(file_path, &content)?;
+/// Analyze one file; returns whether its results came from the cache
+fn analyze_file(
+    detector: &PatternDetector,
+    cli: &Cli,
+    cache: Option<&ResultCache>,
+    file_path: &Path,
+    since: Option<&Since>,
+) -> Result<bool> {
+    if cli.verbose {
+        jsonl::output_data(RecordData::Status {
+            file: file_path.display().to_string(),
+            operation: "analyze".to_string(),
+            status: "starting".to_string(),
+        })?;
+    }
 
-        if cli.jsonl {
-            jsonl::output_result(serde_json::json!({
-                "type": "classification",
-                "file": file_path.display().to_string(),
-                "file_type": classification.file_type,
-                "mime_type": classification.mime_type,
-                "encoding": classification.encoding,
-                "is_binary": classification.is_binary,
-                "language": classification.language,
-                "confidence": classification.confidence,
-            }))?;
+    // Files the manifest vouches for are looked up by their recorded digest
+    // without being read
+    let digest_key = cache.zip(since).map(|(_, since)| {
+        let options = serde_json::json!({
+            "algorithm": since.algorithm.as_str(),
+            "options": cache_options(cli, file_path),
+        });
+        CacheKey::new("analyze-digest", since.digest.as_bytes(), &options)
+    });
+    let reused = match (cache, &digest_key, since) {
+        (Some(cache), Some(key), Some(since)) if since.change == "unchanged" => cache.get::<Analyzed>(key),
+        _ => None,
+    };
+
+    let (mut analyzed, cache_hit, extract_error, content) = match reused {
+        Some(analyzed) => (analyzed, true, None, None),
+        None => {
+            // Read file content
+            let (content, compression) = read_file(file_path)?;
+            limits::read_file(file_path, content.len() as u64)?;
+
+            // Classify file; a decompressed file by its name without `.gz` and the like
+            let name = match compression {
+                Some(_) => Cow::Owned(file_path.with_extension("")),
+                None => Cow::Borrowed(file_path),
+            };
+            let key = cache.map(|_| CacheKey::new("analyze", &content, &cache_options(cli, &name)));
+            let cached = cache.zip(key.as_ref()).and_then(|(cache, key)| cache.get::<Analyzed>(key));
+            let cache_hit = cached.is_some();
+            let (analyzed, extract_error) = match cached {
+                Some(analyzed) => (analyzed, None),
+                None => analyze(detector, cli, &name, &content, compression)?,
+            };
+            // Results whose text extraction failed are not kept, so the error
+            // is reported again next time
+            if let (Some(cache), None) = (cache, &extract_error) {
+                let mut keys = key.iter().filter(|_| !cache_hit).chain(&digest_key);
+                if let Err(e) = keys.try_for_each(|key| cache.put(key, &analyzed)) {
+                    let message = format!("Cannot cache the analysis: {}", e);
+                    jsonl::output_warning(LogLevel::Info, "CACHE_ERROR", &message, Some(&cache.dir().to_string_lossy()))?;
+                }
+            }
+            (analyzed, cache_hit, extract_error, Some(content))
         }
+    };
+    let compression = analyzed.compression.clone();
+    let classification = &analyzed.classification;
+
+    if cli.classify && cli.jsonl {
+        jsonl::output_data(RecordData::Classification {
+            file: file_path.display().to_string(),
+            file_type: classification.file_type.clone(),
+            mime_type: classification.mime_type.clone(),
+            encoding: classification.encoding.clone(),
+            is_binary: classification.is_binary,
+            language: classification.language.clone(),
+            confidence: classification.confidence,
+            cache_hit: Some(cache_hit),
+            image: classification.image.clone(),
+            compression: compression.clone(),
+            change: since.map(|since| since.change.to_string()),
+        })?;
+    }
+
+    // SQLite opens databases by path, which archive members and compressed
+    // files lack
+    if cli.jsonl
+        && classification.mime_type == sqlite_info::SQLITE_MIME_TYPE
+        && !is_member(file_path)
+        && compression.is_none()
+    {
+        output_sqlite_schema(file_path)?;
     }
 
     // Analyze content for patterns
-    if cli.patterns {
-        let text = String::from_utf8_lossy(&content);
-        let analysis = detector.analyze_content(&text, file_path)?;
+    if let Some(analysis) = analyzed.analysis.as_mut() {
+        if let Some(message) = &extract_error {
+            jsonl::output_error(message, "EXTRACT_ERROR", Some(file_path.display().to_string().as_str()))?;
+        }
+        // Binary files are expected not to be text
+        if let Some(content) = content.as_ref().filter(|_| analyzed.extracted_from.is_none()) {
+            if !analyzed.classification.is_binary {
+                jsonl::warn_invalid_utf8(file_path, content)?;
+            }
+        }
+        analysis.path = file_path.display().to_string();
 
         if cli.jsonl {
-            jsonl::output_result(serde_json::json!({
-                "type": "analysis",
-                "file": file_path.display().to_string(),
-                "total_patterns": analysis.total_patterns,
-                "patterns_by_type": analysis.patterns_by_type,
-                "statistics": {
-                    "lines": analysis.statistics.lines,
-                    "words": analysis.statistics.words,
-                    "characters": analysis.statistics.characters,
-                    "bytes": analysis.statistics.bytes,
-                    "avg_line_length": analysis.statistics.avg_line_length,
-                    "max_line_length": analysis.statistics.max_line_length,
-                    "whitespace_ratio": analysis.statistics.whitespace_ratio,
-                    "entropy": analysis.statistics.entropy,
-                },
-                "issues": analysis.issues,
-            }))?;
+            jsonl::output_data(RecordData::Analysis {
+                file: file_path.display().to_string(),
+                total_patterns: analysis.total_patterns,
+                patterns_by_type: analysis.patterns_by_type.clone(),
+                statistics: analysis.statistics.clone(),
+                issues: analysis.issues.clone(),
+                cache_hit: Some(cache_hit),
+                extracted_from: analyzed.extracted_from.clone(),
+                compression: compression.clone(),
+                change: since.map(|since| since.change.to_string()),
+            })?;
 
             // Output individual pattern matches if verbose
             if cli.verbose && !analysis.matches.is_empty() {
                 for pattern_match in analysis.matches.iter().take(100) {
-                    jsonl::output_result(serde_json::json!({
+                    let mut record = serde_json::json!({
                         "type": "pattern_match",
                         "file": file_path.display().to_string(),
                         "pattern_type": format!("{:?}", pattern_match.pattern_type),
@@ -166,7 +350,14 @@ fn analyze_file(detector: &PatternDetector, cli: &Cli, file_path: &PathBuf) -> R
                             "end": pattern_match.end,
                         },
                         "confidence": pattern_match.confidence,
-                    }))?;
+                    });
+                    if let Some(value) = &pattern_match.parsed_value {
+                        record["parsed_value"] = value.as_str().into();
+                    }
+                    if let Some(trace) = &pattern_match.stack_trace {
+                        record["stack_trace"] = serde_json::to_value(trace)?;
+                    }
+                    jsonl::output_result(record)?;
                 }
             }
         }
@@ -216,33 +407,278 @@ fn analyze_file(detector: &PatternDetector, cli: &Cli, file_path: &PathBuf) -> R
     }
 
     if cli.verbose {
-        jsonl::output_info(serde_json::json!({
-            "file": file_path.display().to_string(),
-            "operation": "analyze",
-            "status": "complete",
+        jsonl::output_data(RecordData::Status {
+            file: file_path.display().to_string(),
+            operation: "analyze".to_string(),
+            status: "complete".to_string(),
+        })?;
+    }
+
+    Ok(cache_hit)
+}
+
+/// Classification and pattern analysis of one file, as cached
+#[derive(Debug, Serialize, Deserialize)]
+struct Analyzed {
+    classification: FileClassification,
+    /// `None` without pattern analysis
+    analysis: Option<ContentAnalysis>,
+    /// Set when the text analyzed was extracted from a document: the kind
+    /// of document, if known
+    extracted_from: Option<Option<String>>,
+    /// Compression the content was decoded from
+    compression: Option<String>,
+}
+
+/// Everything besides the content that [`analyze`] depends on
+fn cache_options(cli: &Cli, name: &Path) -> serde_json::Value {
+    serde_json::json!({
+        // Classification goes by extension and well-known file names
+        "name": name.file_name().map(|name| name.to_string_lossy()),
+        "patterns": cli.patterns,
+        "statistics": cli.statistics,
+        "min_confidence": cli.min_confidence,
+        "overlap": format!("{:?}", cli.overlap),
+        "entropy_window": cli.entropy_window,
+        "pattern_packs": config::get().pattern_packs,
+    })
+}
+
+/// Classify `content`, and analyze its text with patterns enabled
+///
+/// Also returns the error of a document whose text could not be extracted;
+/// its raw content is analyzed instead.
+fn analyze(
+    detector: &PatternDetector,
+    cli: &Cli,
+    name: &Path,
+    content: &[u8],
+    compression: Option<&str>,
+) -> Result<(Analyzed, Option<String>)> {
+    let classification = FileClassifier::classify(name, content)?;
+    let compression = compression.map(str::to_string);
+    if !cli.patterns {
+        return Ok((Analyzed { classification, analysis: None, extracted_from: None, compression }, None));
+    }
+
+    // Documents are analyzed by their text when it can be extracted
+    let (extracted, extract_error) = match document_text::extract(&classification.mime_type, content) {
+        Ok(extracted) => (extracted, None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    let text = match &extracted {
+        Some(text) => Cow::Borrowed(text.as_str()),
+        None => String::from_utf8_lossy(content),
+    };
+    let analysis = detector.analyze_content(&text, name)?;
+    let extracted_from = extracted.map(|_| {
+        DocumentKind::from_mime_type(&classification.mime_type).map(|kind| kind.as_str().to_string())
+    });
+    Ok((Analyzed { classification, analysis: Some(analysis), extracted_from, compression }, extract_error))
+}
+
+/// Analyze the files under a manifest's tree that changed since it was taken
+///
+/// Unchanged files get their earlier results, found by digest, and removed
+/// files an `analysis_removed` record; an `incremental_summary` ends the
+/// output. With `--update-manifest`, the manifest is rewritten to match the
+/// tree, so the next run compares with this one.
+fn analyze_changed_since(
+    detector: &PatternDetector,
+    cli: &Cli,
+    cache: Option<&ResultCache>,
+    manifest_path: &Path,
+) -> Result<()> {
+    let fail = |message: String, path: &Path| -> Result<()> {
+        jsonl::output_error(&message, "INCREMENTAL_ERROR", Some(&path.to_string_lossy()))?;
+        std::process::exit(1);
+    };
+    if cli.files.len() > 1 {
+        return fail("--changed-since takes at most one directory".to_string(), &cli.files[1]);
+    }
+    let mut manifest = match TreeSnapshot::load(manifest_path) {
+        Ok(manifest) => manifest,
+        Err(e) => return fail(e.to_string(), manifest_path),
+    };
+    let root = cli.files.first().cloned().unwrap_or_else(|| manifest.root.clone());
+
+    let started = chrono::Utc::now();
+    let diff = match manifest.diff_live(&root, &[manifest_path.to_path_buf()], false) {
+        Ok(diff) => diff,
+        Err(e) => return fail(e.to_string(), &root),
+    };
+    for (path, e) in &diff.errors {
+        jsonl::output_error(&e.to_string(), "ANALYSIS_FAILED", Some(&path.to_string_lossy()))?;
+    }
+    let unreadable: std::collections::HashSet<&Path> = diff.errors.iter().map(|(path, _)| path.as_path()).collect();
+
+    // Every file there now, by relative path, with how it compares
+    let mut files: std::collections::BTreeMap<PathBuf, (&'static str, SnapshotEntry)> = manifest
+        .files
+        .iter()
+        .map(|(path, entry)| (path.clone(), ("unchanged", entry.clone())))
+        .collect();
+    let mut removed = Vec::new();
+    for change in &diff.changes {
+        match (change.kind, &change.after) {
+            (DriftKind::Removed, _) | (_, None) => {
+                files.remove(&change.path);
+                removed.push(change.path.clone());
+            }
+            (kind, Some(after)) => {
+                files.insert(change.path.clone(), (kind.as_str(), after.clone()));
+            }
+        }
+    }
+    files.retain(|path, _| !unreadable.contains(root.join(path).as_path()));
+
+    let mut progress = ProgressReporter::new(format!("Analyzing changes in {}", root.display()), files.len() as u64);
+    let (mut reused, mut analyzed, mut failed) = (0u64, 0u64, diff.errors.len() as u64);
+    for (relative, (change, entry)) in &files {
+        let path = root.join(relative);
+        let since = Since { change, digest: &entry.digest, algorithm: manifest.algorithm };
+        match analyze_file(detector, cli, cache, &path, Some(&since)) {
+            Ok(true) if *change == "unchanged" => reused += 1,
+            Ok(_) => analyzed += 1,
+            Err(e) => {
+                failed += 1;
+                jsonl::output_error(
+                    &format!("Failed to analyze {}: {}", path.display(), e),
+                    "ANALYSIS_FAILED",
+                    Some(path.display().to_string().as_str()),
+                )?;
+            }
+        }
+        progress.advance(1)?;
+    }
+    progress.finish()?;
+    for relative in &removed {
+        jsonl::output_result(serde_json::json!({
+            "type": "analysis_removed",
+            "file": root.join(relative).display().to_string(),
+            "change": "removed",
         }))?;
     }
 
+    let mut updated = false;
+    if cli.update_manifest {
+        manifest.created = started;
+        for relative in &removed {
+            manifest.files.remove(relative);
+        }
+        for (relative, (_, entry)) in files {
+            manifest.files.insert(relative, entry);
+        }
+        match manifest.save(manifest_path) {
+            Ok(()) => updated = true,
+            Err(e) => jsonl::output_error(&e.to_string(), "INCREMENTAL_ERROR", Some(&manifest_path.to_string_lossy()))?,
+        }
+    }
+
+    let count = |kind: &str| diff.changes.iter().filter(|c| c.kind.as_str() == kind).count();
+    jsonl::output_summary(serde_json::json!({
+        "type": "incremental_summary",
+        "manifest": manifest_path.display().to_string(),
+        "root": root.display().to_string(),
+        "added": count("added"),
+        "changed": count("changed"),
+        "removed": removed.len(),
+        "unchanged": diff.unchanged,
+        "rehashed": diff.rehashed,
+        "analyzed": analyzed,
+        "reused": reused,
+        "errors": failed,
+        "manifest_updated": updated,
+    }))?;
     Ok(())
 }
 
+/// One `sqlite_database` record, then a `sqlite_table` record per table and view
+fn output_sqlite_schema(file_path: &Path) -> Result<()> {
+    let file = file_path.display().to_string();
+    let info = match sqlite_info::inspect(file_path) {
+        Ok(Some(info)) => info,
+        Ok(None) => return Ok(()),
+        Err(e) => return jsonl::output_error(&e.to_string(), "SQLITE_ERROR", Some(&file)),
+    };
+
+    jsonl::output_result(serde_json::json!({
+        "type": "sqlite_database",
+        "file": file,
+        "page_size": info.page_size,
+        "page_count": info.page_count,
+        "freelist_count": info.freelist_count,
+        "encoding": info.encoding,
+        "user_version": info.user_version,
+        "tables": info.tables.len(),
+    }))?;
+    for table in &info.tables {
+        let mut record = serde_json::to_value(table)?;
+        record["type"] = "sqlite_table".into();
+        record["file"] = file.as_str().into();
+        jsonl::output_result(record)?;
+    }
+    Ok(())
+}
+
+/// Contents of `path`, which may name an archive member, and the
+/// compression they were decoded from
+fn read_file(path: &Path) -> Result<(Vec<u8>, Option<&'static str>)> {
+    #[cfg(feature = "archives")]
+    if is_member(path) {
+        return Ok((ai_coreutils::archive::read(path)?, None));
+    }
+    let content = fs::read(path).map_err(ai_coreutils::error::AiCoreutilsError::Io)?;
+    #[cfg(feature = "compression")]
+    if let Some(compression) = ai_coreutils::compress::sniff(&content) {
+        return Ok((ai_coreutils::compress::decode(path, compression)?, Some(compression.as_str())));
+    }
+    Ok((content, None))
+}
+
+/// Whether `path` names an archive member
+#[cfg(feature = "archives")]
+fn is_member(path: &Path) -> bool {
+    ai_coreutils::archive::is_member(path)
+}
+
+#[cfg(not(feature = "archives"))]
+fn is_member(_path: &Path) -> bool {
+    false
+}
+
 fn analyze_directory_recursive(
     detector: &PatternDetector,
     cli: &Cli,
+    cache: Option<&ResultCache>,
     dir_path: &PathBuf,
+    tally: &mut Tally,
 ) -> Result<()> {
-    use walkdir::WalkDir;
-
-    let walker = WalkDir::new(dir_path)
-        .follow_links(true)
-        .into_iter()
-        .filter_map(|e| e.ok());
+    let mut files = Vec::new();
+    for event in DirWalk::new(dir_path, cli.follow_symlinks) {
+        match event {
+            Ok(WalkEvent::Entry(entry)) if entry.is_file() => {
+                files.push(entry.into_path());
+            }
+            Ok(WalkEvent::Loop(symlink_loop)) => jsonl::output_info(symlink_loop.to_json())?,
+            Err(e) => {
+                policy::report(&e)?;
+                fs_utils::warn_walk_error(&e)?;
+            }
+            _ => {}
+        }
+    }
 
-    for entry in walker {
-        let path = entry.path();
+    let mut progress = ProgressReporter::new(
+        format!("Analyzing {}", dir_path.display()),
+        files.len() as u64,
+    );
 
-        if path.is_file() {
-            if let Err(e) = analyze_file(detector, cli, &path.to_path_buf()) {
+    for path in &files {
+        match analyze_file(detector, cli, cache, path, None) {
+            Ok(cache_hit) => tally.add(cache_hit),
+            Err(e) => {
+                tally.errors += 1;
                 jsonl::output_error(
                     &format!("Failed to analyze {}: {}", path.display(), e),
                     "ANALYSIS_FAILED",
@@ -250,7 +686,10 @@ fn analyze_directory_recursive(
                 )?;
             }
         }
+        progress.advance(1)?;
     }
 
+    progress.finish()?;
+
     Ok(())
 }
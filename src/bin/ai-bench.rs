@@ -0,0 +1,181 @@
+//! AI-optimized benchmark utility
+//!
+//! Runs built-in benchmarks on the current machine: the SIMD kernels, a
+//! grep over a synthetic corpus at increasing concurrency, file copies
+//! through buffers of increasing size, and cold-cache scans of a mapped
+//! file under each set of `madvise` hints. Every timing is a JSONL record, and
+//! a closing profile names the concurrency and buffer size that did best,
+//! so agents can size their parallelism and chunks to the machine instead
+//! of guessing. A saved profile sets the defaults of the other utilities.
+
+use ai_coreutils::bench::{self, BenchOptions, Measurement, Profile};
+use ai_coreutils::policy::{self, Access};
+use ai_coreutils::session::Session;
+use ai_coreutils::tuning;
+use ai_coreutils::{config, jsonl, JsonlRecord, Result};
+use clap::{Parser, ValueEnum};
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// AI-optimized bench: Measure this machine and recommend settings
+///
+/// This utility provides:
+/// - Throughput of the SIMD kernels, and of the scalar path for comparison
+/// - ai-grep's async pipeline timed at increasing concurrency
+/// - Zero-copy and buffered copies timed at increasing buffer sizes
+/// - Cold-cache mmap scans timed with each set of mapping hints
+/// - A profile with the recommended concurrency and buffer size
+#[derive(Parser, Debug)]
+#[command(name = "ai-bench")]
+#[command(about = "Benchmark this machine and emit a JSONL performance profile", long_about = None)]
+struct Cli {
+    /// Suite to run (repeatable; default: all)
+    #[arg(short, long, value_enum)]
+    suite: Vec<Suite>,
+
+    /// Use small data and short timings, for a rough profile in about a second
+    #[arg(short, long)]
+    quick: bool,
+
+    /// Directory to write the grep corpus, copy and scan files in (default: the session's temporary directory or $TMPDIR)
+    #[arg(short, long, value_name = "DIR")]
+    dir: Option<PathBuf>,
+
+    /// Save the profile where the other utilities read it (tuning.profile or AI_COREUTILS_PROFILE)
+    #[arg(long, conflicts_with = "output")]
+    save: bool,
+
+    /// Save the profile to FILE
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+/// A group of benchmarks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Suite {
+    /// SIMD kernels over an in-memory buffer
+    Simd,
+    /// Async read-and-search over a corpus of files
+    Grep,
+    /// Zero-copy and buffered file copies
+    Copy,
+    /// Cold-cache scans of a memory-mapped file under each set of hints
+    Mmap,
+}
+
+impl Suite {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Suite::Simd => "simd",
+            Suite::Grep => "grep",
+            Suite::Copy => "copy",
+            Suite::Mmap => "mmap",
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+
+    let suites = if cli.suite.is_empty() {
+        vec![Suite::Simd, Suite::Grep, Suite::Copy, Suite::Mmap]
+    } else {
+        cli.suite.clone()
+    };
+    let options = if cli.quick { BenchOptions::quick() } else { BenchOptions::default() };
+
+    let output = match (&cli.output, cli.save) {
+        (Some(path), _) => Some(path.clone()),
+        (None, true) => match tuning::profile_path() {
+            Some(path) => Some(path),
+            None => jsonl::fail("No data directory to save the profile in; use --output", "BENCH_ERROR", None),
+        },
+        (None, false) => None,
+    };
+    policy::enforce(cli.dir.iter().chain(&output), Access::Write)?;
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut measurements = Vec::new();
+
+    if suites.contains(&Suite::Simd) {
+        for measurement in bench::simd_suite(&options) {
+            JsonlRecord::result(measurement.to_json()).write_line(&mut out)?;
+            measurements.push(measurement);
+        }
+        out.flush()?;
+    }
+
+    if suites.iter().any(|suite| *suite != Suite::Simd) {
+        let work = work_dir(cli.dir.as_deref());
+        if let Err(e) = fs::create_dir_all(&work) {
+            jsonl::fail(&e.to_string(), "BENCH_ERROR", Some(&work.to_string_lossy()));
+        }
+        let result = run_file_suites(&suites, &options, &work, &mut out, &mut measurements);
+        let _ = fs::remove_dir_all(&work);
+        if let Err(e) = result {
+            jsonl::fail(&e.to_string(), "BENCH_ERROR", Some(&work.to_string_lossy()));
+        }
+    }
+
+    // Suites not run this time keep their results from the saved profile
+    if let Some(previous) = output.as_deref().and_then(|path| Profile::load(path).ok()) {
+        let ran: Vec<&str> = suites.iter().map(Suite::as_str).collect();
+        let kept = previous.measurements.into_iter().filter(|m| !ran.contains(&m.suite.as_str()));
+        measurements.extend(kept);
+    }
+
+    let profile = Profile::from_measurements(measurements);
+    if let Some(path) = &output {
+        if let Err(e) = profile.save(path) {
+            jsonl::fail(&e.to_string(), "BENCH_ERROR", Some(&path.to_string_lossy()));
+        }
+    }
+
+    let mut record = profile.to_json();
+    record["suites"] = suites.iter().map(Suite::as_str).collect::<Vec<_>>().into();
+    record["quick"] = cli.quick.into();
+    record["saved"] = output.map(|path| path.to_string_lossy().into_owned()).into();
+    JsonlRecord::summary(record).write_line(&mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Run the grep, copy and mmap suites that were asked for in `work`
+fn run_file_suites<W: Write>(
+    suites: &[Suite],
+    options: &BenchOptions,
+    work: &Path,
+    out: &mut W,
+    measurements: &mut Vec<Measurement>,
+) -> Result<()> {
+    let rt = tokio::runtime::Runtime::new()?;
+    let cpus = bench::available_cpus();
+    for suite in [Suite::Grep, Suite::Copy, Suite::Mmap].into_iter().filter(|suite| suites.contains(suite)) {
+        let results = match suite {
+            Suite::Grep => rt.block_on(bench::grep_suite(options, work, cpus))?,
+            Suite::Copy => rt.block_on(bench::copy_suite(options, work))?,
+            _ => bench::mmap_suite(options, work)?,
+        };
+        for measurement in results {
+            JsonlRecord::result(measurement.to_json()).write_line(&mut *out)?;
+            measurements.push(measurement);
+        }
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// A fresh directory for the benchmark files, under `dir`, the session's
+/// temporary directory or the system one
+fn work_dir(dir: Option<&Path>) -> PathBuf {
+    let parent = match dir {
+        Some(dir) => dir.to_path_buf(),
+        None => Session::current()
+            .and_then(|session| session.ensure_tmp_dir().ok())
+            .unwrap_or_else(std::env::temp_dir),
+    };
+    parent.join(format!("ai-bench-{}", uuid::Uuid::new_v4().simple()))
+}
@@ -0,0 +1,133 @@
+//! AI-optimized binary inspection utility
+//!
+//! Reports what `file`, `readelf -h`, `ldd`-style dependency listing and
+//! `strings` would for ELF, PE and Mach-O files, as JSONL: format,
+//! architecture, stripped status, sections, linked libraries and embedded
+//! strings with their offsets and sections.
+
+use ai_coreutils::{binary_info, cli::{LimitArgs, MmapArgs}, config, jsonl, JsonlRecord, Result, SafeMemoryAccess};
+use ai_coreutils::policy::{self, Access};
+use clap::Parser;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+/// AI-optimized binary inspector: Executable headers to JSONL records
+///
+/// This utility provides:
+/// - Format, architecture, word size, endianness and entry point
+/// - Stripped and debug info status, build ID
+/// - Sections with addresses and file offsets, linked libraries
+/// - Embedded printable strings with offsets
+#[derive(Parser, Debug)]
+#[command(name = "ai-binfo")]
+#[command(about = "Inspect ELF, PE and Mach-O binaries as JSONL", long_about = None)]
+struct Cli {
+    /// Binaries to inspect
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// Also output embedded printable strings
+    #[arg(short, long)]
+    strings: bool,
+
+    /// Minimum length of reported strings
+    #[arg(short = 'n', long, default_value = "4")]
+    min_length: usize,
+
+    /// Only output strings from this section (e.g. .rodata)
+    #[arg(long, requires = "strings")]
+    section: Option<String>,
+
+    /// Omit per-section records
+    #[arg(long)]
+    no_sections: bool,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    mmap: MmapArgs,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    cli.mmap.apply();
+    policy::enforce(&cli.files, Access::Read)?;
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut errors = 0;
+
+    for path in &cli.files {
+        let name = path.to_string_lossy();
+        let inspected = SafeMemoryAccess::new(path).and_then(|mem| {
+            let data = mem.as_slice();
+            binary_info::inspect(data).map(|info| (info, mem))
+        });
+        let (info, mem) = match inspected {
+            Ok(inspected) => inspected,
+            Err(e) => {
+                out.flush()?;
+                jsonl::output_error(&e.to_string(), "BINFO_ERROR", Some(&name))?;
+                errors += 1;
+                continue;
+            }
+        };
+
+        JsonlRecord::result(serde_json::json!({
+            "type": "binary_info",
+            "file": name,
+            "format": info.format,
+            "architecture": info.architecture,
+            "bits": info.bits,
+            "endian": info.endian,
+            "kind": info.kind,
+            "entry": format!("{:#x}", info.entry),
+            "stripped": info.stripped,
+            "debug_info": info.debug_info,
+            "build_id": info.build_id,
+            "libraries": info.libraries,
+            "imports": info.imports,
+            "sections": info.sections.len(),
+        })).write_line(&mut out)?;
+
+        if !cli.no_sections {
+            for section in &info.sections {
+                let mut record = serde_json::to_value(section)?;
+                record["type"] = "binary_section".into();
+                record["file"] = name.as_ref().into();
+                JsonlRecord::result(record).write_line(&mut out)?;
+            }
+        }
+
+        if cli.strings {
+            let data = mem.as_slice();
+            for (offset, text) in binary_info::ascii_strings(data, cli.min_length) {
+                let section = info.section_at(offset as u64);
+                if cli.section.as_ref().is_some_and(|wanted| section != Some(wanted.as_str())) {
+                    continue;
+                }
+                JsonlRecord::result(serde_json::json!({
+                    "type": "binary_string",
+                    "file": name,
+                    "offset": offset,
+                    "string": text,
+                    "section": section,
+                })).write_line(&mut out)?;
+            }
+        }
+    }
+    JsonlRecord::summary(serde_json::json!({
+        "type": "binfo_summary",
+        "files": cli.files.len() - errors,
+        "errors": errors,
+    })).write_line(&mut out)?;
+    out.flush()?;
+
+    if errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
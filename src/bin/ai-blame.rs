@@ -0,0 +1,113 @@
+//! AI-optimized blame utility
+//!
+//! Attributes each line of a file to the commit that last changed it and
+//! emits one JSONL record per line with the commit hash, author and time,
+//! so review agents can attribute changes without parsing
+//! `git blame --porcelain`. Requires the `git` feature.
+
+use ai_coreutils::git_info::blame;
+use ai_coreutils::{config, jsonl, JsonlRecord, Result};
+use ai_coreutils::policy::{self, Access};
+use clap::Parser;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, BufWriter, Write};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+/// AI-optimized blame: Last change of every line as JSONL records
+///
+/// This utility provides:
+/// - Commit hash, author, email, time and summary per line
+/// - Uncommitted working tree changes, with a null commit
+/// - Line ranges and a per-author summary
+#[derive(Parser, Debug)]
+#[command(name = "ai-blame")]
+#[command(about = "Per-line last-change metadata from git as JSONL", long_about = None)]
+struct Cli {
+    /// File to blame
+    file: PathBuf,
+
+    /// Lines to blame: A..B, A.., ..B or a single line, numbered from 1
+    #[arg(short = 'L', long, value_parser = parse_line_range)]
+    lines: Option<RangeInclusive<usize>>,
+
+    /// Only output the summary
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+fn parse_line_range(s: &str) -> std::result::Result<RangeInclusive<usize>, String> {
+    let number = |n: &str| n.trim().parse::<usize>().map_err(|_| format!("Invalid line number: {}", n));
+    let (start, end) = match s.split_once("..") {
+        Some((start, end)) => (
+            if start.is_empty() { 1 } else { number(start)? },
+            if end.is_empty() { usize::MAX } else { number(end)? },
+        ),
+        None => (number(s)?, number(s)?),
+    };
+    if start == 0 || start > end {
+        return Err(format!("Invalid line range: {}", s));
+    }
+    Ok(start..=end)
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    policy::enforce([&cli.file], Access::Read)?;
+
+    let name = cli.file.to_string_lossy();
+    let lines = match blame(&cli.file, cli.lines.clone()) {
+        Ok(lines) => lines,
+        Err(e) => {
+            jsonl::output_error(&e.to_string(), "BLAME_ERROR", Some(&name))?;
+            std::process::exit(1);
+        }
+    };
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut commits = HashSet::new();
+    let mut authors: BTreeMap<&str, u64> = BTreeMap::new();
+    let mut uncommitted = 0u64;
+
+    for line in &lines {
+        match &line.commit {
+            Some(commit) => {
+                commits.insert(commit.hash.as_str());
+                *authors.entry(commit.author.as_str()).or_insert(0) += 1;
+            }
+            None => uncommitted += 1,
+        }
+        if !cli.quiet {
+            let commit = line.commit.as_ref();
+            JsonlRecord::result(serde_json::json!({
+                "type": "blame_line",
+                "file": name,
+                "line": line.line,
+                "original_line": line.original_line,
+                "commit": commit.map(|c| &c.hash),
+                "author": commit.map(|c| &c.author),
+                "email": commit.map(|c| &c.email),
+                "time": commit.map(|c| &c.time),
+                "summary": commit.map(|c| &c.summary),
+                "content": line.content,
+            })).write_line(&mut out)?;
+        }
+    }
+
+    JsonlRecord::summary(serde_json::json!({
+        "type": "blame_summary",
+        "file": name,
+        "lines": lines.len(),
+        "first_line": lines.first().map(|l| l.line),
+        "last_line": lines.last().map(|l| l.line),
+        "commits": commits.len(),
+        "uncommitted": uncommitted,
+        "by_author": authors,
+    })).write_line(&mut out)?;
+    out.flush()?;
+
+    Ok(())
+}
+
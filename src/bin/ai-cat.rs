@@ -2,16 +2,43 @@
 //!
 //! Concatenates and displays file contents with memory mapping and JSONL output.
 //! Supports async processing for multiple files.
+//!
+//! With the `compression` feature, gzip, zstd and xz files are shown
+//! decompressed, like zcat, unless `--no-decompress` is given.
 
 use ai_coreutils::{
-    async_ops::{async_read_file, AsyncConfig},
-    jsonl::JsonlRecord,
-    memory::SafeMemoryAccess,
-    Result,
+    async_ops::{async_read_file_with_retry, AsyncConfig, RetryPolicy},
+    cli::{DecompressArgs, LimitArgs, LogArgs, MmapArgs, OrderArgs},
+    config,
+    jsonl::{self, JsonlOutput, JsonlRecord, RecordData, ServedRange},
+    limits,
+    memory::{LineIndex, SafeMemoryAccess},
+    text_format::{LineFormat, LineFormatter},
+    tuning, AiCoreutilsError, Result, SimdByteCounter,
 };
+use ai_coreutils::policy::{self, Access};
+use base64::{engine::general_purpose::STANDARD, Engine};
 use clap::Parser;
+use std::fs::File;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 
+/// Bytes per `--base64` record; a multiple of 3, so chunks concatenate into
+/// the encoding of the whole file
+const BASE64_CHUNK: usize = 48 * 1024;
+
+/// Options that reformat lines and make no sense for byte-exact output
+const LINE_OPTIONS: [&str; 8] = [
+    "number",
+    "number_nonblank",
+    "show_all",
+    "show_ends",
+    "show_tabs",
+    "show_nonprinting",
+    "squeeze_blank",
+    "mem_ptr",
+];
+
 /// AI-optimized cat: Concatenate files with JSONL output
 #[derive(Parser, Debug, Clone)]
 #[command(name = "ai-cat")]
@@ -29,20 +56,24 @@ struct Cli {
     #[arg(short = 'b', long)]
     number_nonblank: bool,
 
-    /// Show all characters (including non-printing)
+    /// Show all characters (equivalent to -vET)
     #[arg(short = 'A', long)]
     show_all: bool,
 
     /// Show end of lines as $
-    #[arg(short, long)]
+    #[arg(short = 'E', long)]
     show_ends: bool,
 
     /// Show tabs as ^I
     #[arg(short = 'T', long)]
     show_tabs: bool,
 
+    /// Show control characters as ^X and M-^X (except tabs)
+    #[arg(short = 'v', long)]
+    show_nonprinting: bool,
+
     /// Squeeze multiple blank lines
-    #[arg(long)]
+    #[arg(short, long)]
     squeeze_blank: bool,
 
     /// Show memory pointer (for AI agent memory access)
@@ -53,34 +84,199 @@ struct Cli {
     #[arg(short = 'a', long)]
     async_mode: bool,
 
-    /// Maximum concurrent operations in async mode
-    #[arg(short = 'j', long, default_value_t = 10)]
-    max_concurrent: usize,
+    /// Maximum concurrent operations in async mode (default: config, then the machine profile)
+    #[arg(short = 'j', long)]
+    max_concurrent: Option<usize>,
+
+    /// Retry transient I/O errors up to N times
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    retries: u32,
 
     /// Output JSONL (always enabled for AI-Coreutils agents)
     #[arg(long, default_value_t = true)]
     json: bool,
+
+    /// Write the original bytes to stdout; JSONL records go to --summary-fd
+    #[arg(long, conflicts_with_all = LINE_OPTIONS, conflicts_with = "base64")]
+    raw: bool,
+
+    /// File descriptor for JSONL records in --raw mode (2 = stderr)
+    #[arg(long, value_name = "FD", default_value_t = 2, requires = "raw")]
+    summary_fd: i32,
+
+    /// Emit file contents as base64 chunk records (binary safe)
+    #[arg(long, conflicts_with_all = LINE_OPTIONS)]
+    base64: bool,
+
+    /// Only output bytes START..END (0-based; `..=END` includes END)
+    #[arg(long, value_name = "RANGE", conflicts_with = "lines")]
+    bytes: Option<Range>,
+
+    /// Only output lines START..END (1-based; `..=END` includes END)
+    #[arg(long, value_name = "RANGE")]
+    lines: Option<Range>,
+
+    #[command(flatten)]
+    decompress: DecompressArgs,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    mmap: MmapArgs,
+
+    #[command(flatten)]
+    order: OrderArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
 }
 
-#[derive(Debug, Clone)]
-struct LineInfo {
-    content: String,
-    line_number: Option<usize>,
-    non_blank_number: Option<usize>,
-    is_blank: bool,
+/// A `START..END`, `START..=END` or single-number range; either end may be omitted
+#[derive(Debug, Clone, Copy)]
+struct Range {
+    start: Option<usize>,
+    /// Exclusive end
+    end: Option<usize>,
+}
+
+impl std::str::FromStr for Range {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, String> {
+        let number = |n: &str| {
+            n.parse::<usize>()
+                .map_err(|_| format!("invalid number '{}' in range '{}'", n, s))
+        };
+        let optional = |n: &str| if n.is_empty() { Ok(None) } else { number(n).map(Some) };
+
+        // Inclusive ends saturate: nothing can be as long as usize::MAX
+        let Some((start, end)) = s.split_once("..") else {
+            let n = number(s)?;
+            return Ok(Range { start: Some(n), end: Some(n.saturating_add(1)) });
+        };
+        let start = optional(start)?;
+        let end = match end.strip_prefix('=') {
+            Some(end) => Some(number(end)?.saturating_add(1)),
+            None => optional(end)?,
+        };
+        if let (Some(start), Some(end)) = (start, end) {
+            if end < start {
+                return Err(format!("range '{}' ends before it starts", s));
+            }
+        }
+        Ok(Range { start, end })
+    }
+}
+
+/// The part of a file selected by `--bytes` or `--lines`
+#[derive(Debug, Clone, Copy)]
+struct Selection {
+    start: usize,
+    end: usize,
+    /// 1-based number of the first line served, for `--lines`
+    first_line: Option<usize>,
+    /// Whether the line range came from an ai-index sidecar
+    indexed: bool,
+}
+
+impl Selection {
+    /// Resolve the CLI range against a mapped file; `None` selects everything
+    ///
+    /// Line ranges use the file's ai-index sidecar when it is fresh, and
+    /// scan the mapping otherwise.
+    fn resolve(path: &Path, mem: &SafeMemoryAccess, cli: &Cli) -> Result<Option<Self>> {
+        if let Some(range) = cli.bytes {
+            let start = range.start.unwrap_or(0).min(mem.size());
+            let end = range.end.unwrap_or(usize::MAX).clamp(start, mem.size());
+            return Ok(Some(Selection { start, end, first_line: None, indexed: false }));
+        }
+
+        let Some(range) = cli.lines else {
+            return Ok(None);
+        };
+        if range.start == Some(0) {
+            return Err(AiCoreutilsError::InvalidInput(
+                "line numbers start at 1".to_string(),
+            ));
+        }
+        let first = range.start.unwrap_or(1);
+        let last = range.end.map(|end| end.max(first) - 1);
+        let index = LineIndex::load_fresh(path);
+        let (start, end) = match &index {
+            Some(index) => index.line_span(first - 1, last),
+            None => mem.line_span(first - 1, last),
+        };
+        let end = end.min(mem.size());
+        Ok(Some(Selection {
+            start: start.min(end),
+            end,
+            first_line: Some(first),
+            indexed: index.is_some(),
+        }))
+    }
+
+    /// Describe the bytes (and lines) actually served from `data`
+    fn to_range(self, data: &[u8]) -> ServedRange {
+        let last_line = self.first_line.map(|first| {
+            let lines = SimdByteCounter::new().count(data, b'\n') + usize::from(!data.ends_with(b"\n"));
+            let lines = if data.is_empty() { 0 } else { lines };
+            (lines > 0).then(|| first + lines - 1)
+        });
+        ServedRange {
+            unit: if self.first_line.is_some() { "lines" } else { "bytes" }.to_string(),
+            byte_start: self.start,
+            byte_end: self.end,
+            size: self.end - self.start,
+            first_line: self.first_line,
+            last_line,
+            line_index: self.first_line.map(|_| self.indexed),
+        }
+    }
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    cli.mmap.apply();
+    cli.log.apply();
+    cli.decompress.apply();
+    // Raw mode writes its records to the summary descriptor, in file order
+    if !cli.raw {
+        cli.order.apply();
+    }
+    policy::enforce(&cli.files, Access::Read)?;
+    // A --bytes range fetches only that part of a remote file, and raw mode
+    // keeps fetch records off stdout
+    #[cfg(feature = "remote")]
+    let cli = {
+        let (start, end) = cli
+            .bytes
+            .map_or((0, None), |range| (range.start.unwrap_or(0) as u64, range.end.map(|end| end as u64)));
+        let writer: Box<dyn Write> = if cli.raw {
+            summary_writer(cli.summary_fd).unwrap_or_else(|_| Box::new(io::stderr()))
+        } else {
+            Box::new(io::stdout())
+        };
+        let files = ai_coreutils::remote::localize_range(&cli.files, start, end, &mut JsonlOutput::new(writer))?;
+        Cli { files, ..cli }
+    };
 
-    if cli.async_mode && cli.files.len() > 1 {
+    let result = if cli.raw {
+        // Bytes must come out in file order, so raw mode is always sequential
+        raw_main(&cli)
+    } else if cli.async_mode && cli.files.len() > 1 && cli.bytes.is_none() && cli.lines.is_none() {
+        // Ranges are served from memory maps, which the sync path uses
         // Use async runtime for concurrent file processing
         let rt = tokio::runtime::Runtime::new()?;
         rt.block_on(async_main(cli))
     } else {
         // Use synchronous processing
         sync_main(cli)
-    }
+    };
+    jsonl::flush_ordered()?;
+    result
 }
 
 fn sync_main(cli: Cli) -> Result<()> {
@@ -88,7 +284,7 @@ fn sync_main(cli: Cli) -> Result<()> {
         if let Err(e) = cat_file(file, &cli) {
             let error_record =
                 JsonlRecord::error(format!("Failed to read {}: {}", file.display(), e), "CAT_ERROR");
-            println!("{}", error_record.to_jsonl()?);
+            error_record.print()?;
         }
     }
 
@@ -99,10 +295,10 @@ async fn async_main(cli: Cli) -> Result<()> {
     use futures::stream::{self, StreamExt};
 
     let config = AsyncConfig {
-        max_concurrent: cli.max_concurrent,
-        buffer_size: 8192,
-        progress: false,
+        retry: RetryPolicy::with_retries(cli.retries),
+        ..AsyncConfig::tuned(cli.max_concurrent)
     };
+    jsonl::output_info(tuning::record())?;
 
     let files = cli.files.clone();
 
@@ -110,8 +306,9 @@ async fn async_main(cli: Cli) -> Result<()> {
     let results = stream::iter(files)
         .map(|file| {
             let cli = cli.clone();
+            let retry = config.retry.clone();
             async move {
-                let result = async_cat_file(&file, &cli).await;
+                let result = async_cat_file(&file, &cli, &retry).await;
                 (file, result)
             }
         })
@@ -126,284 +323,343 @@ async fn async_main(cli: Cli) -> Result<()> {
                 format!("Failed to read {}: {}", path.display(), e),
                 "CAT_ERROR",
             );
-            println!("{}", error_record.to_jsonl()?);
+            error_record.print()?;
         }
     }
 
     Ok(())
 }
 
-async fn async_cat_file(path: &Path, cli: &Cli) -> Result<()> {
+async fn async_cat_file(path: &Path, cli: &Cli, retry: &RetryPolicy) -> Result<()> {
     // Read file asynchronously
-    let data = async_read_file(path).await?;
-    let content = String::from_utf8_lossy(&data).to_string();
+    let data = async_read_file_with_retry(path, retry).await?;
+    let compression = compression(path);
+    if cli.base64 {
+        return output_base64(path, &data, 0, compression);
+    }
+    output_lines(path, &data, cli, None)?;
+    output_summary(path, &data, cli, None, compression)
+}
 
-    let lines: Vec<&str> = content.lines().collect();
-    let mut line_infos = Vec::new();
+fn cat_file(path: &PathBuf, cli: &Cli) -> Result<()> {
+    // Use memory mapping for efficient file reading
+    let mem_access = RetryPolicy::with_retries(cli.retries)
+        .run_blocking(&format!("read {}", path.display()), || SafeMemoryAccess::new(path))?;
 
-    let squeeze_blank = cli.squeeze_blank;
-    let mut last_was_blank = false;
-    let mut non_blank_count = 0;
+    let selection = Selection::resolve(path, &mem_access, cli)?;
+    let (start, end) = selection.map_or((0, mem_access.size()), |s| (s.start, s.end));
+    let Some(data) = mem_access.get(start, end - start) else {
+        return Ok(());
+    };
 
-    for (idx, line) in lines.iter().enumerate() {
-        let is_blank = line.is_empty();
+    let compression = mem_access.compression();
+    if cli.base64 {
+        output_base64(path, data, start, compression)?;
+        return output_selection(path, selection, data, compression);
+    }
 
-        // Skip squeezed blanks
-        if squeeze_blank && is_blank && last_was_blank {
-            continue;
-        }
+    output_lines(path, data, cli, selection.and_then(|s| s.first_line))?;
+    if selection.is_some() {
+        return output_selection(path, selection, data, compression);
+    }
 
-        let line_info = if cli.number_nonblank {
-            if is_blank {
-                LineInfo {
-                    content: String::new(),
-                    line_number: None,
-                    non_blank_number: None,
-                    is_blank: true,
-                }
-            } else {
-                non_blank_count += 1;
-                LineInfo {
-                    content: line.to_string(),
-                    line_number: None,
-                    non_blank_number: Some(non_blank_count),
-                    is_blank: false,
-                }
-            }
-        } else if cli.number {
-            LineInfo {
-                content: line.to_string(),
-                line_number: Some(idx + 1),
-                non_blank_number: None,
-                is_blank: false,
-            }
-        } else if cli.show_all {
-            // Convert all characters to visible representation
-            let all_chars: String = line
-                .chars()
-                .map(|c| match c {
-                    '\t' => "^I".to_string(),
-                    '\n' => "$".to_string(),
-                    c if c.is_control() => format!("^{}", c as u32),
-                    c => c.to_string(),
-                })
-                .collect();
-            LineInfo {
-                content: all_chars,
-                line_number: None,
-                non_blank_number: None,
-                is_blank: false,
-            }
-        } else if cli.show_ends {
-            // Show $ at end of each line
-            let with_ends = format!("{}$", line);
-            LineInfo {
-                content: with_ends,
-                line_number: None,
-                non_blank_number: None,
-                is_blank: false,
-            }
-        } else if cli.show_tabs {
-            // Show tabs as ^I
-            let with_tabs = line.replace('\t', "^I");
-            LineInfo {
-                content: with_tabs,
-                line_number: None,
-                non_blank_number: None,
-                is_blank: false,
-            }
-        } else {
-            LineInfo {
-                content: line.to_string(),
-                line_number: None,
-                non_blank_number: None,
-                is_blank: false,
-            }
-        };
+    let ptr = cli.mem_ptr.then(|| mem_access.as_ptr());
+    output_summary(path, data, cli, ptr, compression)
+}
 
-        if !is_blank || !squeeze_blank {
-            line_infos.push(line_info);
+impl Cli {
+    fn line_format(&self) -> LineFormat {
+        let format = LineFormat {
+            number: self.number,
+            number_nonblank: self.number_nonblank,
+            squeeze_blank: self.squeeze_blank,
+            show_ends: self.show_ends,
+            show_tabs: self.show_tabs,
+            show_nonprinting: self.show_nonprinting,
+        };
+        if self.show_all {
+            format.show_all()
+        } else {
+            format
         }
-
-        last_was_blank = is_blank;
     }
+}
 
-    // Output all lines
-    let line_count = line_infos.len();
-
-    for line_info in &line_infos {
-        let record = JsonlRecord::result(serde_json::json!({
-            "type": "file_content",
-            "file": path.display().to_string(),
-            "content": line_info.content,
-            "line_number": line_info.line_number,
-            "line_non_blank_number": line_info.non_blank_number,
-            "is_blank": line_info.is_blank,
-            "line_count": line_count,
-        }));
-
-        println!("{}", record.to_jsonl()?);
+/// Emit one `file_content` record per decorated line
+///
+/// `first_line` is the file line number of the first line of `data` when
+/// only part of the file is shown; such lines always carry their number.
+fn output_lines(path: &Path, data: &[u8], cli: &Cli, first_line: Option<usize>) -> Result<()> {
+    jsonl::warn_invalid_utf8(path, data)?;
+    let content = String::from_utf8_lossy(data);
+    let mut formatter = LineFormatter::starting_at(cli.line_format(), first_line.unwrap_or(1));
+    let lines = formatter.format_text(&content);
+
+    let stdout = io::stdout();
+    let mut output = JsonlOutput::new(stdout.lock());
+    for line in &lines {
+        let line_number = line.line_number.or(first_line.map(|_| line.source_line));
+        output.write_record(&JsonlRecord::from(RecordData::FileContent {
+            file: path.display().to_string(),
+            content: line.content.clone(),
+            line_number,
+            line_non_blank_number: line.non_blank_number,
+            is_blank: line.is_blank,
+            line_count: lines.len(),
+        }))?;
     }
+    Ok(())
+}
 
-    // If only one file and no special formatting, output a summary record
-    if cli.files.len() == 1
-        && !cli.number
-        && !cli.number_nonblank
-        && !cli.show_all
-        && !cli.show_ends
-        && !cli.show_tabs
-    {
-        let record = JsonlRecord::result(serde_json::json!({
-            "type": "file_summary",
-            "file": path.display().to_string(),
-            "content": content,
-            "size": data.len(),
-        }));
-
-        println!("{}", record.to_jsonl()?);
+/// Emit the whole-file `file_summary` record for a single undecorated file
+///
+/// `compression` names the format `data` was decompressed from, if any.
+fn output_summary(
+    path: &Path,
+    data: &[u8],
+    cli: &Cli,
+    ptr: Option<*const u8>,
+    compression: Option<&str>,
+) -> Result<()> {
+    let format = cli.line_format();
+    if cli.files.len() != 1 || format.decorates() || format.number || format.number_nonblank {
+        return Ok(());
     }
 
+    let record = JsonlRecord::from(RecordData::FileSummary {
+        file: path.display().to_string(),
+        size: data.len(),
+        content: Some(String::from_utf8_lossy(data).into_owned()),
+        memory_pointer: ptr.map(|ptr| format!("{:?}", ptr)),
+        encoding: None,
+        chunks: None,
+        compression: compression.map(String::from),
+    });
+    record.print()?;
     Ok(())
 }
 
-fn cat_file(path: &PathBuf, cli: &Cli) -> Result<()> {
-    // Use memory mapping for efficient file reading
-    let mem_access = SafeMemoryAccess::new(path)?;
-
-    let content = if let Some(data) = mem_access.get(0, mem_access.size()) {
-        String::from_utf8_lossy(data).to_string()
-    } else {
+/// Emit a `file_range` record describing what `--bytes`/`--lines` served
+fn output_selection(
+    path: &Path,
+    selection: Option<Selection>,
+    data: &[u8],
+    compression: Option<&str>,
+) -> Result<()> {
+    let Some(selection) = selection else {
         return Ok(());
     };
+    JsonlRecord::from(RecordData::FileRange {
+        file: path.display().to_string(),
+        range: selection.to_range(data),
+        compression: compression.map(String::from),
+    })
+    .print()?;
+    Ok(())
+}
 
-    let lines: Vec<&str> = content.lines().collect();
-    let mut line_infos = Vec::new();
-
-    let squeeze_blank = cli.squeeze_blank;
-    let mut last_was_blank = false;
-    let mut non_blank_count = 0;
+/// Emit `data` as base64 records of at most [`BASE64_CHUNK`] bytes each
+///
+/// `base` is the file offset of `data`, reported in each chunk's `offset`.
+fn output_base64(path: &Path, data: &[u8], base: usize, compression: Option<&str>) -> Result<()> {
+    let stdout = io::stdout();
+    let mut output = JsonlOutput::new(stdout.lock());
+    let chunks = data.len().div_ceil(BASE64_CHUNK);
+
+    for (index, chunk) in data.chunks(BASE64_CHUNK).enumerate() {
+        output.write_record(&JsonlRecord::from(RecordData::FileContentBase64 {
+            file: path.display().to_string(),
+            chunk: index,
+            chunks,
+            offset: base + index * BASE64_CHUNK,
+            size: chunk.len(),
+            content: STANDARD.encode(chunk),
+        }))?;
+    }
 
-    for (idx, line) in lines.iter().enumerate() {
-        let is_blank = line.is_empty();
+    output.write_record(&JsonlRecord::from(RecordData::FileSummary {
+        file: path.display().to_string(),
+        size: data.len(),
+        content: None,
+        memory_pointer: None,
+        encoding: Some("base64".to_string()),
+        chunks: Some(chunks),
+        compression: compression.map(String::from),
+    }))
+}
 
-        // Skip squeezed blanks
-        if squeeze_blank && is_blank && last_was_blank {
-            continue;
+/// Copy each file's bytes to stdout unchanged
+///
+/// stdout carries nothing but file content, so per-file results, errors and
+/// retries are written as JSONL to the summary descriptor instead.
+fn raw_main(cli: &Cli) -> Result<()> {
+    let mut summary = match summary_writer(cli.summary_fd) {
+        Ok(writer) => JsonlOutput::new(writer),
+        Err(e) => {
+            // Nothing has been written yet, so stderr is still safe to use
+            let record = JsonlRecord::error(format!("Invalid --summary-fd: {}", e), "INVALID_INPUT");
+            record.write_line(io::stderr())?;
+            std::process::exit(1);
         }
-
-        let line_info = if cli.number_nonblank {
-            if is_blank {
-                LineInfo {
-                    content: String::new(),
-                    line_number: None,
-                    non_blank_number: None,
-                    is_blank: true,
-                }
-            } else {
-                non_blank_count += 1;
-                LineInfo {
-                    content: line.to_string(),
-                    line_number: None,
-                    non_blank_number: Some(non_blank_count),
-                    is_blank: false,
-                }
-            }
-        } else if cli.number {
-            LineInfo {
-                content: line.to_string(),
-                line_number: Some(idx + 1),
-                non_blank_number: None,
-                is_blank: false,
-            }
-        } else if cli.show_all {
-            // Convert all characters to visible representation
-            let all_chars: String = line
-                .chars()
-                .map(|c| match c {
-                    '\t' => "^I".to_string(),
-                    '\n' => "$".to_string(),
-                    c if c.is_control() => format!("^{}", c as u32),
-                    c => c.to_string(),
-                })
-                .collect();
-            LineInfo {
-                content: all_chars,
-                line_number: None,
-                non_blank_number: None,
-                is_blank: false,
-            }
-        } else if cli.show_ends {
-            // Show $ at end of each line
-            let with_ends = format!("{}$", line);
-            LineInfo {
-                content: with_ends,
-                line_number: None,
-                non_blank_number: None,
-                is_blank: false,
-            }
-        } else if cli.show_tabs {
-            // Show tabs as ^I
-            let with_tabs = line.replace('\t', "^I");
-            LineInfo {
-                content: with_tabs,
-                line_number: None,
-                non_blank_number: None,
-                is_blank: false,
-            }
+    };
+    let retry = RetryPolicy::with_retries(cli.retries);
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    let mut total_bytes = 0u64;
+    let mut errors = 0u64;
+
+    for path in &cli.files {
+        // Archive members have no file to copy from, and compressed files
+        // are copied decompressed, so they are mapped too
+        let copied = if cli.bytes.is_some()
+            || cli.lines.is_some()
+            || is_member(path)
+            || compression(path).is_some()
+        {
+            with_retry(path, &retry, &mut summary, || SafeMemoryAccess::new(path)).and_then(|mem| {
+                let selection = Selection::resolve(path, &mem, cli)?;
+                let (start, end) = selection.map_or((0, mem.size()), |s| (s.start, s.end));
+                let data = mem.get(start, end - start).unwrap_or_default();
+                out.write_all(data)?;
+                Ok((data.len() as u64, selection.map(|s| s.to_range(data)), mem.compression()))
+            })
         } else {
-            LineInfo {
-                content: line.to_string(),
-                line_number: None,
-                non_blank_number: None,
-                is_blank: false,
-            }
+            with_retry(path, &retry, &mut summary, || File::open(path).map_err(AiCoreutilsError::Io))
+                .and_then(|mut file| {
+                    limits::read_file(path, file.metadata()?.len())?;
+                    io::copy(&mut file, &mut out).map_err(AiCoreutilsError::Io)
+                })
+                .map(|bytes| (bytes, None, None))
         };
 
-        if !is_blank || !squeeze_blank {
-            line_infos.push(line_info);
+        match copied {
+            Ok((bytes, range, compression)) => {
+                total_bytes += bytes;
+                summary.write_record(&JsonlRecord::from(RecordData::RawFile {
+                    file: path.display().to_string(),
+                    bytes,
+                    range,
+                    compression: compression.map(String::from),
+                }))?;
+            }
+            Err(e) => {
+                errors += 1;
+                summary.write_record(&JsonlRecord::error(
+                    format!("Failed to read {}: {}", path.display(), e),
+                    "CAT_ERROR",
+                ))?;
+            }
         }
+    }
+
+    out.flush()?;
+    summary.write_record(&JsonlRecord::from(RecordData::RawSummary {
+        files: cli.files.len(),
+        bytes: total_bytes,
+        errors,
+    }))?;
+    summary.flush()?;
 
-        last_was_blank = is_blank;
+    if errors > 0 {
+        std::process::exit(1);
     }
 
-    // Output all lines
-    let line_count = line_infos.len();
-
-    for line_info in &line_infos {
-        let record = JsonlRecord::result(serde_json::json!({
-            "type": "file_content",
-            "file": path.display().to_string(),
-            "content": line_info.content,
-            "line_number": line_info.line_number,
-            "line_non_blank_number": line_info.non_blank_number,
-            "is_blank": line_info.is_blank,
-            "line_count": line_count,
-        }));
-
-        println!("{}", record.to_jsonl()?);
+    Ok(())
+}
+
+/// Whether `path` names an archive member
+#[cfg(feature = "archives")]
+fn is_member(path: &Path) -> bool {
+    ai_coreutils::archive::is_member(path)
+}
+
+#[cfg(not(feature = "archives"))]
+fn is_member(_path: &Path) -> bool {
+    false
+}
+
+/// Compression `path` is read decompressed from: `gzip`, `zstd` or `xz`
+#[cfg(feature = "compression")]
+fn compression(path: &Path) -> Option<&'static str> {
+    ai_coreutils::compress::detect(path).ok().flatten().map(|c| c.as_str())
+}
+
+#[cfg(not(feature = "compression"))]
+fn compression(_path: &Path) -> Option<&'static str> {
+    None
+}
+
+/// Open `path` with `open`, retrying transient errors and reporting retries to `summary`
+fn with_retry<T, W: Write>(
+    path: &Path,
+    retry: &RetryPolicy,
+    summary: &mut JsonlOutput<W>,
+    mut open: impl FnMut() -> Result<T>,
+) -> Result<T> {
+    let mut attempt = 1;
+    loop {
+        match open() {
+            Err(e) if attempt < retry.max_attempts && RetryPolicy::is_retryable(&e) => {
+                let delay = retry.backoff_for(attempt);
+                summary.write_record(&JsonlRecord::from(RecordData::RetryAttempt {
+                    operation: format!("read {}", path.display()),
+                    attempt,
+                    max_attempts: retry.max_attempts,
+                    delay_ms: delay.as_millis() as u64,
+                    error: e.to_string(),
+                }))?;
+                std::thread::sleep(delay);
+                attempt += 1;
+            }
+            result => return result,
+        }
     }
+}
 
-    // If only one file and no special formatting, output a summary record
-    if cli.files.len() == 1
-        && !cli.number
-        && !cli.number_nonblank
-        && !cli.show_all
-        && !cli.show_ends
-        && !cli.show_tabs
-    {
-        let ptr = mem_access.as_ptr();
-        let size = mem_access.size();
-
-        let record = JsonlRecord::result(serde_json::json!({
-            "type": "file_summary",
-            "file": path.display().to_string(),
-            "content": content,
-            "size": size,
-            "memory_pointer": if cli.mem_ptr { Some(format!("{:?}", ptr)) } else { None },
-        }));
-
-        println!("{}", record.to_jsonl()?);
+/// Writer for `--summary-fd`: stderr, or a duplicate of another open descriptor
+fn summary_writer(fd: i32) -> Result<Box<dyn Write>> {
+    match fd {
+        2 => Ok(Box::new(io::stderr())),
+        0 | 1 => Err(AiCoreutilsError::InvalidInput(format!(
+            "--summary-fd {} would mix records with file content",
+            fd
+        ))),
+        #[cfg(unix)]
+        _ => {
+            use std::os::unix::io::FromRawFd;
+
+            // SAFETY: dup only reads the descriptor table
+            let dup = unsafe { libc::dup(fd) };
+            if dup < 0 {
+                return Err(AiCoreutilsError::Io(io::Error::last_os_error()));
+            }
+            // SAFETY: `dup` is a freshly duplicated descriptor owned by nothing else
+            Ok(Box::new(unsafe { File::from_raw_fd(dup) }))
+        }
+        #[cfg(not(unix))]
+        _ => Err(AiCoreutilsError::InvalidInput(
+            "--summary-fd only supports 2 (stderr) on this platform".to_string(),
+        )),
     }
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_inclusive_end_saturates() {
+        let max = usize::MAX.to_string();
+
+        let range: Range = max.parse().unwrap();
+        assert_eq!((range.start, range.end), (Some(usize::MAX), Some(usize::MAX)));
+
+        let range: Range = format!("..={}", max).parse().unwrap();
+        assert_eq!((range.start, range.end), (None, Some(usize::MAX)));
+
+        let range: Range = "2..=4".parse().unwrap();
+        assert_eq!((range.start, range.end), (Some(2), Some(5)));
+        assert!("5..2".parse::<Range>().is_err());
+    }
 }
@@ -0,0 +1,364 @@
+//! AI-optimized checksum utility
+//!
+//! Hashes files into a manifest, verifies files against a saved manifest,
+//! and with `--watch` keeps the manifest current by re-hashing only the
+//! files that change, reporting every content change as a drift record.
+
+use ai_coreutils::{
+    checksum::{Drift, Manifest},
+    cli::LimitArgs,
+    config,
+    fs_utils::{DirWalk, SymlinkPolicy, WalkEvent},
+    hash::HashAlgorithm,
+    jsonl,
+    watch::{Debouncer, EventKind, Snapshot},
+    AiCoreutilsError, Result,
+};
+use ai_coreutils::policy::{self, Access};
+use clap::Parser;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// AI-optimized checksum: Hash files and detect content drift
+///
+/// This utility provides:
+/// - Per-file digests as JSONL
+/// - Manifests of path to digest, saved atomically
+/// - `--check` to report files that drifted from a manifest
+/// - `--watch` to re-hash only changed files and report drift as it happens
+#[derive(Parser, Debug)]
+#[command(name = "ai-checksum")]
+#[command(about = "Hash files, verify manifests and watch for content drift", long_about = None)]
+struct Cli {
+    /// Files/directories to hash
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Recurse into directories
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Digest algorithm: crc32, xxh64, xxh3 or blake3 (if built with it)
+    ///
+    /// With --check, the manifest's own algorithm is used.
+    #[arg(short, long, default_value = "xxh3")]
+    algorithm: HashAlgorithm,
+
+    /// Manifest file to write (or with --check, to verify against)
+    #[arg(short, long, value_name = "FILE")]
+    manifest: Option<PathBuf>,
+
+    /// Verify the files against the manifest; exit 1 on any drift
+    #[arg(short, long, requires = "manifest")]
+    check: bool,
+
+    /// Keep watching and re-hash files as they change
+    #[arg(short, long)]
+    watch: bool,
+
+    /// Quiet period before changes are re-hashed, in milliseconds (with --watch)
+    #[arg(long, value_name = "MS", default_value_t = 200)]
+    debounce: u64,
+
+    /// How often to check for changes, in milliseconds (with --watch)
+    #[arg(long, value_name = "MS", default_value_t = 250)]
+    interval: u64,
+
+    /// Stop watching after this many seconds
+    #[arg(long, value_name = "SECONDS", requires = "watch")]
+    timeout: Option<f64>,
+
+    /// Only output drift records and the summary, not each digest
+    #[arg(short, long)]
+    quiet: bool,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    files: u64,
+    bytes: u64,
+    drifted: u64,
+    errors: u64,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    policy::enforce(&cli.paths, Access::Read)?;
+    policy::enforce(&cli.manifest, if cli.check { Access::Read } else { Access::Write })?;
+    #[cfg(feature = "remote")]
+    let cli = Cli {
+        paths: ai_coreutils::remote::localize(&cli.paths)?,
+        ..cli
+    };
+
+    let mut stats = Stats::default();
+    // Snapshot before hashing, so changes made during the initial pass are
+    // seen by the watch rather than lost
+    let snapshot = cli
+        .watch
+        .then(|| Snapshot::take(&watched_paths(&cli), SymlinkPolicy::CommandLine).0);
+    let files = collect_files(&cli, &mut stats)?;
+
+    let mut manifest = if cli.check {
+        let path = cli.manifest.as_deref().expect("--check requires --manifest");
+        match Manifest::load(path) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                jsonl::output_error(&e.to_string(), "CHECKSUM_ERROR", Some(&path.to_string_lossy()))?;
+                std::process::exit(1);
+            }
+        }
+    } else {
+        Manifest::new(cli.algorithm)
+    };
+
+    if cli.check {
+        check(&cli, &manifest, &files, &mut stats)?;
+    } else {
+        let algorithm = manifest.algorithm;
+        for file in &files {
+            match manifest.insert(file) {
+                Ok(entry) => {
+                    stats.files += 1;
+                    stats.bytes += entry.size;
+                    if !cli.quiet {
+                        output_checksum(file, algorithm, &entry.digest, entry.size)?;
+                    }
+                }
+                Err(e) => report_error(file, &e, &mut stats)?,
+            }
+        }
+        save(&cli, &manifest, &mut stats)?;
+    }
+
+    output_summary(&cli, &manifest, &stats, cli.watch)?;
+
+    if let Some(snapshot) = snapshot {
+        watch(&cli, snapshot, &mut manifest, &mut stats)?;
+        output_summary(&cli, &manifest, &stats, false)?;
+    }
+
+    if stats.errors > 0 || (cli.check && stats.drifted > 0) {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Expand the command-line paths into the files to hash
+fn collect_files(cli: &Cli, stats: &mut Stats) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in &cli.paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        if !cli.recursive {
+            let error = AiCoreutilsError::InvalidInput("Is a directory (use -r to hash recursively)".to_string());
+            report_error(path, &error, stats)?;
+            continue;
+        }
+        let mut found = Vec::new();
+        for event in DirWalk::new(path, SymlinkPolicy::CommandLine) {
+            match event {
+                Ok(WalkEvent::Entry(entry)) if entry.is_file() => {
+                    // Never hash the manifest being written
+                    if !is_manifest(cli, entry.path()) {
+                        found.push(entry.into_path());
+                    }
+                }
+                Ok(_) => {}
+                Err(e) if policy::report(&e)? => stats.errors += 1,
+                Err(e) => report_error(path, &e, stats)?,
+            }
+        }
+        found.sort();
+        files.extend(found);
+    }
+    Ok(files)
+}
+
+/// Verify `files` against the manifest, reporting every drift
+fn check(cli: &Cli, manifest: &Manifest, files: &[PathBuf], stats: &mut Stats) -> Result<()> {
+    let (drifts, errors) = manifest.verify(files);
+    for (path, e) in &errors {
+        report_error(path, e, stats)?;
+    }
+    for drift in &drifts {
+        output_drift(drift, None)?;
+    }
+    stats.files = (files.len() - errors.len()) as u64;
+    stats.drifted = drifts.len() as u64;
+    if !cli.quiet {
+        let drifted: HashSet<&Path> = drifts.iter().map(|d| d.path.as_path()).collect();
+        for file in files.iter().filter(|f| !drifted.contains(f.as_path())) {
+            if let Some(entry) = manifest.files.get(file) {
+                stats.bytes += entry.size;
+                output_checksum(file, manifest.algorithm, &entry.digest, entry.size)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Poll for changes, re-hashing only the files that changed
+fn watch(cli: &Cli, mut snapshot: Snapshot, manifest: &mut Manifest, stats: &mut Stats) -> Result<()> {
+    let timeout = match cli.timeout {
+        Some(secs) if !secs.is_finite() || secs < 0.0 => {
+            jsonl::output_error(&format!("Invalid timeout: {}", secs), "INVALID_INPUT", None)?;
+            std::process::exit(1);
+        }
+        Some(secs) => Some(Duration::from_secs_f64(secs)),
+        None => None,
+    };
+    let watched = watched_paths(cli);
+    // Unreadable directories would otherwise be reported on every poll
+    let mut reported = HashSet::new();
+    let mut debouncer = Debouncer::new(Duration::from_millis(cli.debounce));
+    let started = Instant::now();
+    let mut batch_number = 0;
+
+    while timeout.is_none_or(|t| started.elapsed() < t) {
+        thread::sleep(Duration::from_millis(cli.interval));
+        let (next, errors) = Snapshot::take(&watched, SymlinkPolicy::CommandLine);
+        for e in errors {
+            let message = e.to_string();
+            if reported.insert(message.clone()) {
+                jsonl::output_error(&message, "CHECKSUM_ERROR", None)?;
+            }
+        }
+        let now = Instant::now();
+        for event in snapshot.diff(&next) {
+            debouncer.push(event, now);
+        }
+        snapshot = next;
+
+        let Some(batch) = debouncer.ready(now) else {
+            continue;
+        };
+        let batch: Vec<_> = batch
+            .into_iter()
+            .filter(|e| !is_manifest(cli, &e.path))
+            .collect();
+        if batch.is_empty() {
+            continue;
+        }
+
+        batch_number += 1;
+        let mut drifted = 0;
+        for event in &batch {
+            match manifest.rehash(&event.path) {
+                Ok(Some(drift)) => {
+                    drifted += 1;
+                    output_drift(&drift, Some(event.kind))?;
+                }
+                Ok(None) => {}
+                Err(e) => report_error(&event.path, &e, stats)?,
+            }
+        }
+        stats.drifted += drifted;
+        if drifted > 0 {
+            save(cli, manifest, stats)?;
+        }
+        jsonl::output_result(serde_json::json!({
+            "type": "checksum_batch",
+            "batch": batch_number,
+            "rehashed": batch.len(),
+            "drifted": drifted,
+        }))?;
+    }
+
+    stats.files = manifest.files.len() as u64;
+    stats.bytes = manifest.files.values().map(|e| e.size).sum();
+    Ok(())
+}
+
+/// The command-line paths that are hashed, and so watched
+fn watched_paths(cli: &Cli) -> Vec<PathBuf> {
+    cli.paths
+        .iter()
+        .filter(|p| cli.recursive || !p.is_dir())
+        .cloned()
+        .collect()
+}
+
+/// Whether `path` is the manifest file, however either path is spelled
+///
+/// Rewriting the manifest inside a watched tree must not count as drift.
+fn is_manifest(cli: &Cli, path: &Path) -> bool {
+    let Some(manifest) = &cli.manifest else {
+        return false;
+    };
+    manifest == path
+        || matches!(
+            (fs::canonicalize(manifest), fs::canonicalize(path)),
+            (Ok(a), Ok(b)) if a == b
+        )
+}
+
+/// Write the manifest, if one was requested and is not being checked against
+fn save(cli: &Cli, manifest: &Manifest, stats: &mut Stats) -> Result<()> {
+    match &cli.manifest {
+        Some(path) if !cli.check => match manifest.save(path) {
+            Ok(()) => Ok(()),
+            Err(e) => report_error(path, &e, stats),
+        },
+        _ => Ok(()),
+    }
+}
+
+fn output_checksum(file: &Path, algorithm: HashAlgorithm, digest: &str, size: u64) -> Result<()> {
+    jsonl::output_result(serde_json::json!({
+        "type": "checksum",
+        "file": file.display().to_string(),
+        "algorithm": algorithm.as_str(),
+        "digest": digest,
+        "size": size,
+    }))
+}
+
+/// Emit a drift record; `event` is the watch event that revealed it
+fn output_drift(drift: &Drift, event: Option<EventKind>) -> Result<()> {
+    let mut record = serde_json::json!({
+        "type": "checksum_drift",
+        "file": drift.path.display().to_string(),
+        "change": drift.kind.as_str(),
+        "expected": drift.expected.as_ref().map(|e| &e.digest),
+        "actual": drift.actual.as_ref().map(|e| &e.digest),
+        "expected_size": drift.expected.as_ref().map(|e| e.size),
+        "actual_size": drift.actual.as_ref().map(|e| e.size),
+    });
+    if let Some(kind) = event {
+        record["event"] = serde_json::json!(kind.as_str());
+    }
+    jsonl::output_result(record)
+}
+
+/// Emit the summary; `watching` tells whether a watch follows it
+fn output_summary(cli: &Cli, manifest: &Manifest, stats: &Stats, watching: bool) -> Result<()> {
+    jsonl::output_summary(serde_json::json!({
+        "type": "checksum_summary",
+        "algorithm": manifest.algorithm.as_str(),
+        "files": stats.files,
+        "bytes": stats.bytes,
+        "drifted": stats.drifted,
+        "errors": stats.errors,
+        "manifest": cli.manifest.as_ref().map(|p| p.display().to_string()),
+        "mode": if cli.check { "check" } else { "hash" },
+        "watching": watching,
+    }))
+}
+
+/// Report a per-path failure and count it; processing continues
+fn report_error(path: &Path, error: &AiCoreutilsError, stats: &mut Stats) -> Result<()> {
+    stats.errors += 1;
+    jsonl::output_error(&error.to_string(), "CHECKSUM_ERROR", Some(&path.to_string_lossy()))
+}
@@ -1,9 +1,15 @@
 //! AI-optimized chmod utility
 //!
-//! Changes file permissions with JSONL output.
+//! Changes file permissions with JSONL output. Previous modes are recorded
+//! in the operation journal so ai-undo can restore them.
 
+use ai_coreutils::cli::DryRunArgs;
+use ai_coreutils::config;
+use ai_coreutils::fs_utils::mode::{self, Mode};
 use ai_coreutils::jsonl;
-use ai_coreutils::Result;
+use ai_coreutils::ops_journal::Recorder;
+use ai_coreutils::{AiCoreutilsError, Result};
+use ai_coreutils::policy::{self, Access};
 use clap::Parser;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -12,9 +18,10 @@ use std::path::{Path, PathBuf};
 #[derive(Parser, Debug)]
 #[command(name = "ai-chmod")]
 #[command(about = "AI-optimized chmod with structured output", long_about = None)]
+#[command(disable_help_flag = true)]
 struct Cli {
-    /// Permission changes (octal mode or symbolic mode)
-    #[arg(required = true)]
+    /// Permission changes: octal (755) or symbolic clauses (u+x,go-w)
+    #[arg(required = true, allow_hyphen_values = true)]
     mode: String,
 
     /// Files/directories to modify
@@ -33,176 +40,197 @@ struct Cli {
     #[arg(long, default_value_t = true)]
     json: bool,
 
-    /// Changes ownership if file is a symbolic link
-    #[arg(short, long)]
-    #[cfg(unix)]
-    symbolic_link: bool,
+    /// Skip symlinks given on the command line instead of following them
+    #[arg(short = 'h', long)]
+    no_dereference: bool,
+
+    #[command(flatten)]
+    dry_run: DryRunArgs,
+
+    /// Maximum directory depth for -R
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_DEPTH)]
+    max_depth: usize,
+
+    /// Print help (-h is --no-dereference, as in GNU chmod)
+    #[arg(long, action = clap::ArgAction::Help)]
+    help: Option<bool>,
 }
 
+/// Depth bound for recursive changes, well below stack exhaustion
+const DEFAULT_MAX_DEPTH: usize = 1024;
+
 #[derive(Debug, Clone)]
 struct ChmodStats {
     files_modified: u64,
     dirs_modified: u64,
+    symlinks_skipped: u64,
     errors: u64,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    config::init()?;
+    cli.dry_run.apply();
+    if cli.no_dereference {
+        policy::enforce_entries(&cli.paths)?;
+    } else {
+        policy::enforce(&cli.paths, Access::Write)?;
+    }
 
     let mut stats = ChmodStats {
         files_modified: 0,
         dirs_modified: 0,
+        symlinks_skipped: 0,
         errors: 0,
     };
 
     // Parse the mode specification
-    let mode_spec = parse_mode(&cli.mode)?;
+    let mode_spec = match Mode::parse(&cli.mode) {
+        Ok(mode) => mode,
+        Err(e) => {
+            jsonl::output_error(&e.to_string(), "INVALID_MODE", None)?;
+            std::process::exit(1);
+        }
+    };
+    let umask = mode::current_umask();
+    let mut journal = Recorder::begin("ai-chmod");
 
     // Apply permissions to each path
     for path in &cli.paths {
-        if let Err(e) = change_permissions(path, &cli, &mode_spec, &mut stats) {
+        change_tree(path, &cli, &mode_spec, umask, 0, &mut stats, &mut journal)?;
+    }
+
+    let journal_id = match journal.finish() {
+        Ok(id) => id,
+        Err(e) => {
             stats.errors += 1;
-            jsonl::output_error(
-                &format!("Failed to change permissions for {}: {}", path.display(), e),
-                "CHMOD_ERROR",
-                Some(&path.to_string_lossy()),
-            )?;
+            jsonl::output_error(&e.to_string(), "JOURNAL_ERROR", None)?;
+            None
         }
-    }
+    };
 
     // Output final stats
-    jsonl::output_result(serde_json::json!({
+    jsonl::output_summary(serde_json::json!({
         "type": "chmod_summary",
         "files_modified": stats.files_modified,
         "dirs_modified": stats.dirs_modified,
+        "symlinks_skipped": stats.symlinks_skipped,
         "errors": stats.errors,
         "mode": cli.mode,
+        "journal": journal_id,
     }))?;
 
+    if stats.errors > 0 {
+        std::process::exit(1);
+    }
+
     Ok(())
 }
 
-#[derive(Debug, Clone)]
-enum ModeSpec {
-    Absolute(u32),
-    #[allow(dead_code)]
-    Symbolic {
-        who: Option<char>,
-        op: char,
-        permissions: u32,
-    },
-}
+/// Change permissions on `path` and, with `-R`, everything below it
+///
+/// Failures are reported per path and counted; traversal continues. Only
+/// failures writing output are returned.
+fn change_tree(
+    path: &Path,
+    cli: &Cli,
+    mode_spec: &Mode,
+    umask: u32,
+    depth: usize,
+    stats: &mut ChmodStats,
+    journal: &mut Recorder,
+) -> Result<()> {
+    // Command-line symlinks are followed unless -h; symlinks found while
+    // recursing never are
+    let follow = depth == 0 && !cli.no_dereference;
+    let metadata = if follow {
+        fs::metadata(path)
+    } else {
+        fs::symlink_metadata(path)
+    };
+    let metadata = match metadata {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let error = AiCoreutilsError::PathNotFound(path.to_path_buf());
+            return report_error(path, &error, "CHMOD_ERROR", stats);
+        }
+        Err(e) => return report_error(path, &AiCoreutilsError::Io(e), "CHMOD_ERROR", stats),
+    };
 
-fn parse_mode(mode_str: &str) -> Result<ModeSpec> {
-    // Check if it's an octal mode (e.g., "755", "644")
-    if mode_str.chars().all(|c| c.is_ascii_digit()) {
-        let mode = u32::from_str_radix(mode_str, 8)
-            .map_err(|_| ai_coreutils::error::AiCoreutilsError::InvalidInput(
-                format!("Invalid octal mode: {}", mode_str)
-            ))?;
-        return Ok(ModeSpec::Absolute(mode));
+    // Symlink permissions cannot be changed and are not meaningful
+    if metadata.file_type().is_symlink() {
+        stats.symlinks_skipped += 1;
+        if cli.verbose {
+            jsonl::output_info(serde_json::json!({
+                "type": "symlink_skipped",
+                "path": path.display().to_string(),
+            }))?;
+        }
+        return Ok(());
     }
 
-    // Otherwise it's a symbolic mode (e.g., "u+x", "go=rwx")
-    if let Some((who, op, permissions)) = parse_symbolic_mode(mode_str)? {
-        return Ok(ModeSpec::Symbolic { who, op, permissions });
+    if let Err(e) = change_permissions(path, &metadata, cli, mode_spec, umask, stats, journal) {
+        report_error(path, &e, "CHMOD_ERROR", stats)?;
     }
 
-    Err(ai_coreutils::error::AiCoreutilsError::InvalidInput(
-        format!("Invalid mode specification: {}", mode_str)
-    ))
-}
-
-fn parse_symbolic_mode(mode_str: &str) -> Result<Option<(Option<char>, char, u32)>> {
-    let chars: Vec<char> = mode_str.chars().collect();
-
-    // Parse who part (u, g, o, a)
-    let mut idx = 0;
-    let mut who = None;
-    while idx < chars.len() {
-        match chars[idx] {
-            'u' | 'g' | 'o' | 'a' => {
-                who = Some(chars[idx]);
-                idx += 1;
-            }
-            '+' | '-' | '=' => break,
-            _ => {
-                return Err(ai_coreutils::error::AiCoreutilsError::InvalidInput(
-                    format!("Invalid who in mode: {}", mode_str)
-                ));
-            }
+    if metadata.is_dir() && cli.recursive {
+        if depth >= cli.max_depth {
+            let message = format!("Maximum recursion depth {} reached; not descending", cli.max_depth);
+            return report_error(path, &AiCoreutilsError::InvalidInput(message), "DEPTH_LIMIT", stats);
         }
-    }
-
-    if idx >= chars.len() {
-        return Err(ai_coreutils::error::AiCoreutilsError::InvalidInput(
-            format!("Missing operator in mode: {}", mode_str)
-        ));
-    }
 
-    // Parse operator (+, -, =)
-    let op = chars[idx];
-    if !matches!(op, '+' | '-' | '=') {
-        return Err(ai_coreutils::error::AiCoreutilsError::InvalidInput(
-            format!("Invalid operator in mode: {}", mode_str)
-        ));
-    }
-    idx += 1;
-
-    // Parse permissions (r, w, x, X)
-    let mut permissions = 0u32;
-    while idx < chars.len() {
-        match chars[idx] {
-            'r' => permissions |= 0o444,
-            'w' => permissions |= 0o222,
-            'x' => permissions |= 0o111,
-            'X' => {
-                // X is special: execute only if directory or already executable
-                permissions |= 0o111;
-            }
-            's' | 't' => {
-                // Special bits - for now, just skip
-                // In a full implementation, these would set setuid/setgid/sticky
-            }
-            _ => {
-                return Err(ai_coreutils::error::AiCoreutilsError::InvalidInput(
-                    format!("Invalid permission in mode: {}", mode_str)
-                ));
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(e) => return report_error(path, &AiCoreutilsError::Io(e), "CHMOD_ERROR", stats),
+        };
+
+        for entry in entries {
+            match entry {
+                Ok(entry) => {
+                    let entry_path = entry.path();
+                    match policy::check_entry(&entry_path) {
+                        Ok(()) => change_tree(&entry_path, cli, mode_spec, umask, depth + 1, stats, journal)?,
+                        Err(e) => report_error(&entry_path, &e, "POLICY_DENIED", stats)?,
+                    }
+                }
+                Err(e) => report_error(path, &AiCoreutilsError::Io(e), "CHMOD_ERROR", stats)?,
             }
         }
-        idx += 1;
     }
 
-    Ok(Some((who, op, permissions)))
+    Ok(())
+}
+
+/// Emit an error record for `path` and count it
+fn report_error(path: &Path, error: &AiCoreutilsError, code: &str, stats: &mut ChmodStats) -> Result<()> {
+    stats.errors += 1;
+    jsonl::output_error(&error.to_string(), code, Some(&path.to_string_lossy()))
 }
 
+/// Apply the mode to a single path
 fn change_permissions(
     path: &Path,
+    metadata: &fs::Metadata,
     cli: &Cli,
-    mode_spec: &ModeSpec,
+    mode_spec: &Mode,
+    umask: u32,
     stats: &mut ChmodStats,
+    journal: &mut Recorder,
 ) -> Result<()> {
-    // Check if path exists
-    if !path.exists() {
-        return Err(ai_coreutils::error::AiCoreutilsError::PathNotFound(path.to_path_buf()));
-    }
-
-    let is_dir = path.is_dir();
-
-    // Get current permissions
-    let metadata = fs::metadata(path)?;
+    let is_dir = metadata.is_dir();
 
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
 
         let current_mode = metadata.permissions().mode();
-        let new_mode = calculate_new_mode(current_mode, mode_spec)?;
+        let new_mode = mode_spec.apply(current_mode, is_dir, umask);
 
         // Set new permissions
-        let mut new_perms = metadata.permissions().clone();
-        new_perms.set_mode(new_mode);
-        fs::set_permissions(path, new_perms)?;
+        if !cli.dry_run.is_enabled() {
+            fs::set_permissions(path, fs::Permissions::from_mode(new_mode))?;
+            journal.mode_changed(path, current_mode & mode::MODE_BITS, new_mode & mode::MODE_BITS);
+        }
 
         // Update stats
         if is_dir {
@@ -217,6 +245,7 @@ fn change_permissions(
                 "path": path.display().to_string(),
                 "old_mode": format!("{:04o}", current_mode & 0o7777),
                 "new_mode": format!("{:04o}", new_mode & 0o7777),
+                "permissions": mode::to_symbolic(new_mode),
             }))?;
         }
     }
@@ -224,84 +253,31 @@ fn change_permissions(
     #[cfg(windows)]
     {
         // On Windows, chmod is more limited
-        // We can only set readonly flag
-        if let ModeSpec::Absolute(mode) = mode_spec {
-            let readonly = (mode & 0o222) == 0; // No write permission = readonly
-            let mut perms = metadata.permissions();
-            perms.set_readonly(readonly);
+        // We can only set readonly flag, so treat it as the owner's write bit
+        let current_mode = if metadata.permissions().readonly() { 0o444 } else { 0o666 };
+        let new_mode = mode_spec.apply(current_mode, is_dir, umask);
+        let readonly = new_mode & 0o200 == 0;
+        let mut perms = metadata.permissions();
+        perms.set_readonly(readonly);
+        if !cli.dry_run.is_enabled() {
             fs::set_permissions(path, perms)?;
-
-            if is_dir {
-                stats.dirs_modified += 1;
-            } else {
-                stats.files_modified += 1;
-            }
-
-            if cli.verbose {
-                jsonl::output_info(serde_json::json!({
-                    "type": "permissions_changed",
-                    "path": path.display().to_string(),
-                    "readonly": readonly,
-                }))?;
-            }
+            journal.mode_changed(path, current_mode, new_mode);
         }
-    }
 
-    // Recursive handling
-    if is_dir && cli.recursive {
-        for entry in fs::read_dir(path)? {
-            let entry = entry?;
-            let entry_path = entry.path();
+        if is_dir {
+            stats.dirs_modified += 1;
+        } else {
+            stats.files_modified += 1;
+        }
 
-            change_permissions(&entry_path, cli, mode_spec, stats)?;
+        if cli.verbose {
+            jsonl::output_info(serde_json::json!({
+                "type": "permissions_changed",
+                "path": path.display().to_string(),
+                "readonly": readonly,
+            }))?;
         }
     }
 
     Ok(())
 }
-
-#[allow(dead_code)]
-fn calculate_new_mode(current_mode: u32, mode_spec: &ModeSpec) -> Result<u32> {
-    match mode_spec {
-        ModeSpec::Absolute(mode) => {
-            // Absolute mode: replace the lower 12 bits (preserving file type bits)
-            Ok((current_mode & 0o770000) | (mode & 0o7777))
-        }
-        ModeSpec::Symbolic { who, op, permissions } => {
-            let mut new_mode = current_mode;
-
-            // Determine which bits to modify
-            let mask = match who {
-                Some('u') => 0o4700,  // User bits
-                Some('g') => 0o2070,  // Group bits
-                Some('o') => 0o1007,  // Other bits
-                Some('a') | None => 0o7777,  // All bits
-                _ => return Err(ai_coreutils::error::AiCoreutilsError::InvalidInput(
-                    "Invalid who in symbolic mode".to_string()
-                )),
-            };
-
-            match op {
-                '+' => {
-                    // Add permissions
-                    new_mode |= permissions & mask;
-                }
-                '-' => {
-                    // Remove permissions
-                    new_mode &= !(permissions & mask);
-                }
-                '=' => {
-                    // Set exact permissions
-                    new_mode = (new_mode & !mask) | (permissions & mask);
-                }
-                _ => {
-                    return Err(ai_coreutils::error::AiCoreutilsError::InvalidInput(
-                        "Invalid operator in symbolic mode".to_string()
-                    ));
-                }
-            }
-
-            Ok(new_mode)
-        }
-    }
-}
@@ -2,17 +2,23 @@
 //!
 //! Changes file owner and group with JSONL output.
 
+use ai_coreutils::cli::DryRunArgs;
+use ai_coreutils::config;
+use ai_coreutils::fs_utils::owner::{self, Owner};
 use ai_coreutils::jsonl;
-use ai_coreutils::Result;
+use ai_coreutils::{AiCoreutilsError, Result};
+use ai_coreutils::policy::{self, Access};
 use clap::Parser;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 /// AI-optimized chown: Change ownership with JSONL output
 #[derive(Parser, Debug)]
 #[command(name = "ai-chown")]
 #[command(about = "AI-optimized chown with structured output", long_about = None)]
+#[command(disable_help_flag = true)]
 struct Cli {
-    /// Owner specification (user[:group])
+    /// Owner specification: user, user:group, user:, or :group (names or IDs)
     #[arg(required = true)]
     owner: String,
 
@@ -24,21 +30,24 @@ struct Cli {
     #[arg(short = 'R', long)]
     recursive: bool,
 
+    /// Change symlinks themselves instead of their targets
+    #[arg(short = 'h', long)]
+    no_dereference: bool,
+
     /// Verbose output
     #[arg(short, long)]
     verbose: bool,
 
+    #[command(flatten)]
+    dry_run: DryRunArgs,
+
     /// Produce output in JSONL format (always enabled)
     #[arg(long, default_value_t = true)]
     json: bool,
-}
 
-#[derive(Debug, Clone)]
-struct OwnerSpec {
-    #[allow(dead_code)]
-    uid: Option<u32>,
-    #[allow(dead_code)]
-    gid: Option<u32>,
+    /// Print help (-h is --no-dereference, as in GNU chown)
+    #[arg(long, action = clap::ArgAction::Help)]
+    help: Option<bool>,
 }
 
 #[derive(Debug, Clone)]
@@ -50,6 +59,13 @@ struct ChownStats {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    config::init()?;
+    cli.dry_run.apply();
+    if cli.no_dereference || cli.recursive {
+        policy::enforce_entries(&cli.paths)?;
+    } else {
+        policy::enforce(&cli.paths, Access::Write)?;
+    }
 
     let mut stats = ChownStats {
         files_modified: 0,
@@ -57,14 +73,24 @@ fn main() -> Result<()> {
         errors: 0,
     };
 
-    // Parse the owner specification
-    let _owner_spec = parse_owner(&cli.owner)?;
+    // Parse the owner specification, resolving user and group names
+    let owner_spec = match Owner::parse(&cli.owner) {
+        Ok(owner) => owner,
+        Err(e) => {
+            jsonl::output_error(&e.to_string(), "INVALID_OWNER", None)?;
+            std::process::exit(1);
+        }
+    };
 
     #[cfg(unix)]
     {
+        // Like GNU chown, command-line symlinks are followed unless -h is
+        // given or the change is recursive
+        let follow = !cli.no_dereference && !cli.recursive;
+
         // Apply ownership changes to each path
         for path in &cli.paths {
-            if let Err(e) = change_ownership(path, &cli, &owner_spec, &mut stats) {
+            if let Err(e) = change_ownership(path, &cli, owner_spec, follow, &mut stats) {
                 stats.errors += 1;
                 jsonl::output_error(
                     &format!("Failed to change ownership for {}: {}", path.display(), e),
@@ -97,7 +123,7 @@ fn main() -> Result<()> {
     }
 
     // Output final stats
-    jsonl::output_result(serde_json::json!({
+    jsonl::output_summary(serde_json::json!({
         "type": "chown_summary",
         "files_modified": stats.files_modified,
         "dirs_modified": stats.dirs_modified,
@@ -108,151 +134,38 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn parse_owner(owner_str: &str) -> Result<OwnerSpec> {
-    let parts: Vec<&str> = owner_str.split(':').collect();
-
-    let uid = if !parts[0].is_empty() {
-        Some(parse_user_id(parts[0])?)
-    } else {
-        None
-    };
-
-    let gid = if parts.len() > 1 && !parts[1].is_empty() {
-        Some(parse_group_id(parts[1])?)
-    } else {
-        None
-    };
-
-    if uid.is_none() && gid.is_none() {
-        return Err(ai_coreutils::error::AiCoreutilsError::InvalidInput(
-            "Invalid owner specification".to_string()
-        ));
-    }
-
-    Ok(OwnerSpec { uid, gid })
-}
-
-#[cfg(unix)]
-fn parse_user_id(user: &str) -> Result<u32> {
-    use std::os::unix::fs::MetadataExt;
-
-    // Try parsing as numeric UID first
-    if let Ok(uid) = user.parse::<u32>() {
-        return Ok(uid);
-    }
-
-    // Try to look up username
-    #[cfg(feature = "user_lookup")]
-    {
-        // In a full implementation, you'd use the `users` crate or similar
-        // For now, return an error
-        return Err(ai_coreutils::error::AiCoreutilsError::InvalidInput(
-            format!("Username lookup not implemented: {}", user)
-        ));
-    }
-
-    #[cfg(not(feature = "user_lookup"))]
-    {
-        // Can't look up usernames without additional dependencies
-        // Try parsing as number or fail
-        user.parse::<u32>()
-            .map_err(|_| ai_coreutils::error::AiCoreutilsError::InvalidInput(
-                format!("Invalid UID or username not found: {}", user)
-            ))
-    }
-}
-
-#[cfg(windows)]
-fn parse_user_id(user: &str) -> Result<u32> {
-    // On Windows, we don't have the same concept of UIDs
-    // Just try to parse as a number
-    user.parse::<u32>()
-        .map_err(|_| ai_coreutils::error::AiCoreutilsError::InvalidInput(
-            format!("Invalid UID: {}", user)
-        ))
-}
-
-#[cfg(unix)]
-fn parse_group_id(group: &str) -> Result<u32> {
-    // Try parsing as numeric GID first
-    if let Ok(gid) = group.parse::<u32>() {
-        return Ok(gid);
-    }
-
-    // Try to look up group name
-    #[cfg(feature = "user_lookup")]
-    {
-        // In a full implementation, you'd use the `users` crate or similar
-        return Err(ai_coreutils::error::AiCoreutilsError::InvalidInput(
-            format!("Group lookup not implemented: {}", group)
-        ));
-    }
-
-    #[cfg(not(feature = "user_lookup"))]
-    {
-        group.parse::<u32>()
-            .map_err(|_| ai_coreutils::error::AiCoreutilsError::InvalidInput(
-                format!("Invalid GID or group not found: {}", group)
-            ))
-    }
-}
-
-#[cfg(windows)]
-fn parse_group_id(group: &str) -> Result<u32> {
-    // On Windows, we don't have the same concept of GIDs
-    group.parse::<u32>()
-        .map_err(|_| ai_coreutils::error::AiCoreutilsError::InvalidInput(
-            format!("Invalid GID: {}", group)
-        ))
-}
-
 #[cfg(unix)]
 fn change_ownership(
     path: &Path,
     cli: &Cli,
-    owner_spec: &OwnerSpec,
+    owner: Owner,
+    follow: bool,
     stats: &mut ChownStats,
 ) -> Result<()> {
     use std::os::unix::fs::MetadataExt;
 
-    // Check if path exists
-    if !path.exists() {
-        return Err(ai_coreutils::error::AiCoreutilsError::PathNotFound(path.to_path_buf()));
+    // Stat the link itself unless it is being followed
+    let metadata = if follow {
+        fs::metadata(path)
+    } else {
+        fs::symlink_metadata(path)
     }
+    .map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => AiCoreutilsError::PathNotFound(path.to_path_buf()),
+        _ => AiCoreutilsError::Io(e),
+    })?;
 
-    let is_dir = path.is_dir();
-
-    // Get current ownership
-    let metadata = fs::metadata(path)?;
+    let is_dir = metadata.is_dir();
     let current_uid = metadata.uid();
     let current_gid = metadata.gid();
 
-    let new_uid = owner_spec.uid.unwrap_or(current_uid);
-    let new_gid = owner_spec.gid.unwrap_or(current_gid);
-
-    // Change ownership using chown system call
-    unsafe {
-        use libc::{chown, strlen};
-        use std::ffi::CString;
-
-        let path_cstr = CString::new(path.to_string_lossy().as_ref())
-            .map_err(|_| ai_coreutils::error::AiCoreutilsError::InvalidInput(
-                "Invalid path for chown".to_string()
-            ))?;
-
-        let result = chown(
-            path_cstr.as_ptr(),
-            new_uid,
-            new_gid,
-        );
-
-        if result != 0 {
-            return Err(ai_coreutils::error::AiCoreutilsError::Io(
-                std::io::Error::last_os_error()
-            ));
-        }
+    if !cli.dry_run.is_enabled() {
+        owner::chown(path, owner, follow)?;
     }
 
+    let new_uid = owner.uid.unwrap_or(current_uid);
+    let new_gid = owner.gid.unwrap_or(current_gid);
+
     // Update stats
     if is_dir {
         stats.dirs_modified += 1;
@@ -268,16 +181,25 @@ fn change_ownership(
             "old_gid": current_gid,
             "new_uid": new_uid,
             "new_gid": new_gid,
+            "user": owner::user_name(new_uid),
+            "group": owner::group_name(new_gid),
+            "symlink": metadata.file_type().is_symlink(),
         }))?;
     }
 
-    // Recursive handling
+    // Recursive handling: symlinks below the starting point are changed
+    // themselves and never traversed
     if is_dir && cli.recursive {
         for entry in fs::read_dir(path)? {
             let entry = entry?;
             let entry_path = entry.path();
+            if let Err(e) = policy::check_entry(&entry_path) {
+                policy::report(&e)?;
+                stats.errors += 1;
+                continue;
+            }
 
-            change_ownership(&entry_path, cli, owner_spec, stats)?;
+            change_ownership(&entry_path, cli, owner, false, stats)?;
         }
     }
 
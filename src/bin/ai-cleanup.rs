@@ -0,0 +1,158 @@
+//! AI-optimized cleanup utility
+//!
+//! Removes the temporary files and directories recorded in the scratch
+//! registry by ai-mktemp: everything one agent session allocated, everything
+//! past its time to live, or all of it. Paths that something else has taken
+//! over since are released from the registry but left alone.
+
+use ai_coreutils::scratch::{Allocation, Registry, ReleaseAction};
+use ai_coreutils::{cli::parse_session_id, config, jsonl, JsonlRecord, Result};
+use chrono::Utc;
+use clap::Parser;
+use std::io::{self, BufWriter, Write};
+
+/// AI-optimized cleanup: Remove registered scratch files by session
+///
+/// This utility provides:
+/// - Removal of everything a session created with ai-mktemp
+/// - Removal of allocations past their time to live
+/// - A listing of the registry, and dry runs
+/// - One record per allocation saying what was done with it
+#[derive(Parser, Debug)]
+#[command(name = "ai-cleanup")]
+#[command(about = "Remove temporary files and directories registered by ai-mktemp", long_about = None)]
+struct Cli {
+    /// Only allocations of this session (default: session.id or AI_COREUTILS_SESSION)
+    #[arg(long, value_name = "ID", conflicts_with = "all", value_parser = parse_session_id)]
+    session: Option<String>,
+
+    /// Only allocations past their time to live
+    #[arg(long, conflicts_with = "all")]
+    expired: bool,
+
+    /// Every allocation in the registry
+    #[arg(long)]
+    all: bool,
+
+    /// List the selected allocations (default: all) instead of removing them
+    #[arg(short, long)]
+    list: bool,
+
+    /// Report what would be removed without removing it
+    #[arg(short = 'n', long)]
+    dry_run: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+
+    let Some(registry) = Registry::from_config() else {
+        jsonl::output_error(
+            "No scratch registry directory (set scratch.dir or AI_COREUTILS_SCRATCH_DIR)",
+            "CLEANUP_ERROR",
+            None,
+        )?;
+        std::process::exit(1);
+    };
+
+    let session = cli.session.clone().or_else(|| {
+        // The configured session is the default only when nothing else selects
+        (!cli.expired && !cli.all && !cli.list).then(|| config::get().session.id.clone()).flatten()
+    });
+    if session.is_none() && !cli.expired && !cli.all && !cli.list {
+        jsonl::output_error(
+            "Nothing selected: give --session, --expired or --all, or set AI_COREUTILS_SESSION",
+            "CLEANUP_ERROR",
+            None,
+        )?;
+        std::process::exit(1);
+    }
+    let now = Utc::now();
+    let selected = |allocation: &Allocation| {
+        session.as_ref().is_none_or(|session| allocation.session.as_ref() == Some(session))
+            && (!cli.expired || allocation.is_expired(now))
+    };
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+
+    if cli.list {
+        let allocations = match registry.allocations() {
+            Ok(allocations) => allocations,
+            Err(e) => fail(&e.to_string(), &registry),
+        };
+        let mut matched = 0u64;
+        for allocation in allocations.iter().filter(|a| selected(a)) {
+            matched += 1;
+            let mut record = allocation_record(allocation);
+            record["type"] = "scratch_entry".into();
+            record["expired"] = allocation.is_expired(now).into();
+            JsonlRecord::result(record).write_line(&mut out)?;
+        }
+        JsonlRecord::summary(serde_json::json!({
+            "type": "scratch_summary",
+            "registry": registry.dir().to_string_lossy(),
+            "allocations": allocations.len(),
+            "matched": matched,
+        })).write_line(&mut out)?;
+        out.flush()?;
+        return Ok(());
+    }
+
+    let outcomes = match registry.release(selected, cli.dry_run) {
+        Ok(outcomes) => outcomes,
+        Err(e) => fail(&e.to_string(), &registry),
+    };
+    let (mut removed, mut missing, mut replaced, mut failed) = (0u64, 0u64, 0u64, 0u64);
+    for (allocation, action) in &outcomes {
+        let mut record = allocation_record(allocation);
+        record["type"] = "cleanup_entry".into();
+        record["action"] = action.as_str().into();
+        match action {
+            ReleaseAction::Removed | ReleaseAction::WouldRemove => removed += 1,
+            ReleaseAction::Missing => missing += 1,
+            ReleaseAction::Replaced => replaced += 1,
+            ReleaseAction::Failed(e) => {
+                record["error"] = e.to_string().into();
+                failed += 1;
+            }
+        }
+        JsonlRecord::result(record).write_line(&mut out)?;
+    }
+
+    JsonlRecord::summary(serde_json::json!({
+        "type": "cleanup_summary",
+        "registry": registry.dir().to_string_lossy(),
+        "matched": outcomes.len(),
+        "removed": if cli.dry_run { 0 } else { removed },
+        "would_remove": if cli.dry_run { removed } else { 0 },
+        "missing": missing,
+        "replaced": replaced,
+        "failed": failed,
+        "dry_run": cli.dry_run,
+    })).write_line(&mut out)?;
+    out.flush()?;
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn allocation_record(allocation: &Allocation) -> serde_json::Value {
+    serde_json::json!({
+        "id": allocation.id,
+        "path": allocation.path.to_string_lossy(),
+        "kind": allocation.kind.as_str(),
+        "session": allocation.session,
+        "created": allocation.created.to_rfc3339(),
+        "expires": allocation.expires.map(|t| t.to_rfc3339()),
+    })
+}
+
+/// Report a registry error and exit
+fn fail(message: &str, registry: &Registry) -> ! {
+    let _ = jsonl::output_error(message, "CLEANUP_ERROR", Some(&registry.dir().to_string_lossy()));
+    std::process::exit(1);
+}
@@ -0,0 +1,149 @@
+//! AI-optimized comm utility
+//!
+//! Compares two sorted files line by line, as `comm` does, streaming both
+//! so inputs of any size use constant memory. Each line is a JSONL record
+//! saying whether it is only in the first file, only in the second or in
+//! both, with its line number and offset in each.
+
+use ai_coreutils::{
+    cli::{LimitArgs, LogArgs},
+    config,
+    jsonl::{self, LogLevel},
+    set_ops::{self, Line, LineStream, Merger, OrderCheck, Side},
+    JsonlRecord, Result,
+};
+use ai_coreutils::policy::{self, Access};
+use clap::Parser;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+/// AI-optimized comm: Compare two sorted files as JSONL
+///
+/// This utility provides:
+/// - Streaming merge of two sorted inputs with SIMD line comparison
+/// - The side of every line: file1, file2 or both
+/// - Line number and byte offset of each line in its file
+/// - Detection of unsorted input
+#[derive(Parser, Debug)]
+#[command(name = "ai-comm")]
+#[command(about = "Compare two sorted files line by line as JSONL", long_about = None)]
+struct Cli {
+    /// First sorted file ("-" for stdin)
+    file1: PathBuf,
+
+    /// Second sorted file ("-" for stdin)
+    file2: PathBuf,
+
+    /// Suppress lines only in the first file
+    #[arg(short = '1')]
+    suppress_file1: bool,
+
+    /// Suppress lines only in the second file
+    #[arg(short = '2')]
+    suppress_file2: bool,
+
+    /// Suppress lines in both files
+    #[arg(short = '3')]
+    suppress_both: bool,
+
+    /// Fail if an input is not sorted
+    #[arg(long, conflicts_with = "nocheck_order")]
+    check_order: bool,
+
+    /// Do not check that the inputs are sorted
+    #[arg(long)]
+    nocheck_order: bool,
+
+    /// Lines end with NUL, not newline
+    #[arg(short, long)]
+    zero_terminated: bool,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    cli.log.apply();
+    let paths = [cli.file1.clone(), cli.file2.clone()];
+    policy::enforce(&paths, Access::Read)?;
+
+    let names = paths.clone().map(|path| path.to_string_lossy().into_owned());
+    let separator = if cli.zero_terminated { b'\0' } else { b'\n' };
+    let mut left = match set_ops::open_input(&paths[0]) {
+        Ok(reader) => LineStream::new(reader, separator),
+        Err(e) => jsonl::fail(&e.to_string(), "COMM_ERROR", Some(&names[0])),
+    };
+    let mut right = match set_ops::open_input(&paths[1]) {
+        Ok(reader) => LineStream::new(reader, separator),
+        Err(e) => jsonl::fail(&e.to_string(), "COMM_ERROR", Some(&names[1])),
+    };
+    let merger = Merger::new().order_check(if cli.check_order {
+        OrderCheck::Fail
+    } else if cli.nocheck_order {
+        OrderCheck::Off
+    } else {
+        OrderCheck::Report
+    });
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let result = merger.comm(&mut left, &mut right, |side, l, r| {
+        let (column, suppressed) = match side {
+            Side::Left => (1, cli.suppress_file1),
+            Side::Right => (2, cli.suppress_file2),
+            Side::Both => (3, cli.suppress_both),
+        };
+        if suppressed {
+            return Ok(());
+        }
+        let content = l.or(r).map(|line| String::from_utf8_lossy(&line.content)).unwrap_or_default();
+        JsonlRecord::result(serde_json::json!({
+            "type": "comm_line",
+            "side": side.as_str(),
+            "column": column,
+            "content": content,
+            "file1": l.map(position),
+            "file2": r.map(position),
+        })).write_line(&mut out)
+    });
+    out.flush()?;
+    let stats = match result {
+        Ok(stats) => stats,
+        Err(e) => jsonl::fail(&e.to_string(), "COMM_ERROR", None),
+    };
+
+    for (name, line) in names.iter().zip(stats.unsorted) {
+        if let Some(line) = line {
+            let message = format!("Input is not in sorted order at line {}; results may be incomplete", line);
+            jsonl::output_warning(LogLevel::Warn, "UNSORTED_INPUT", &message, Some(name))?;
+        }
+    }
+
+    JsonlRecord::summary(serde_json::json!({
+        "type": "comm_summary",
+        "file1": names[0],
+        "file2": names[1],
+        "file1_only": stats.left_only,
+        "file2_only": stats.right_only,
+        "both": stats.both,
+        "sorted": stats.unsorted.iter().all(Option::is_none),
+    })).write_line(&mut out)?;
+    out.flush()?;
+
+    // As GNU comm does, unsorted input fails the run once it is complete
+    if stats.unsorted.iter().any(Option::is_some) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Where a line is in its file
+fn position(line: &Line) -> serde_json::Value {
+    serde_json::json!({ "line_number": line.number, "offset": line.offset })
+}
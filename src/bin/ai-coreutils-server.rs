@@ -0,0 +1,103 @@
+//! AI-Coreutils server
+//!
+//! Serves the read-only utilities to agents on other hosts. `--http` streams
+//! their JSONL records over HTTP with bearer-token auth (`http` feature);
+//! `--grpc` serves the gRPC service as `ai-grpc` does (`grpc` feature). The
+//! sandbox policy from the config applies to every request.
+
+use ai_coreutils::{config, jsonl, AiCoreutilsError, Result};
+use clap::{Args, Parser};
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use tokio::net::TcpListener;
+
+/// Environment variable holding the bearer token for `--http`
+const TOKEN_ENV: &str = "AI_COREUTILS_SERVER_TOKEN";
+
+/// AI-Coreutils server: Inspect files on this host from another
+///
+/// This utility provides:
+/// - `--http`: ls, cat, grep, find, checksum and analyze endpoints streaming JSONL
+/// - Bearer-token auth, from `--token-file` or `AI_COREUTILS_SERVER_TOKEN`
+/// - The sandbox policy enforced on every path a request names or reaches
+#[derive(Parser, Debug)]
+#[command(name = "ai-coreutils-server")]
+#[command(about = "Serve the utilities to remote agents over HTTP", long_about = None)]
+struct Cli {
+    #[command(flatten)]
+    mode: Mode,
+
+    /// Address to listen on (port 0 picks a free port)
+    #[arg(short, long, default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+
+    /// File holding the bearer token clients must send (with --http)
+    ///
+    /// Defaults to the AI_COREUTILS_SERVER_TOKEN environment variable.
+    #[arg(long, value_name = "FILE")]
+    token_file: Option<PathBuf>,
+}
+
+/// Protocol to serve
+#[derive(Args, Debug)]
+#[group(required = true, multiple = false)]
+struct Mode {
+    /// Serve JSONL over HTTP
+    #[arg(long)]
+    http: bool,
+
+    /// Serve the gRPC service
+    #[cfg(feature = "grpc")]
+    #[arg(long)]
+    grpc: bool,
+}
+
+/// The bearer token from `--token-file` or the environment
+fn token(cli: &Cli) -> Result<String> {
+    let token = match &cli.token_file {
+        Some(path) => fs::read_to_string(path)?,
+        None => std::env::var(TOKEN_ENV).unwrap_or_default(),
+    };
+    let token = token.trim();
+    if token.is_empty() {
+        return Err(AiCoreutilsError::InvalidInput(format!(
+            "--http needs a bearer token: pass --token-file or set {}",
+            TOKEN_ENV
+        )));
+    }
+    Ok(token.to_string())
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+
+    let token = if cli.mode.http {
+        match token(&cli) {
+            Ok(token) => Some(token),
+            Err(e) => {
+                jsonl::output_error(&e.to_string(), "INVALID_INPUT", None)?;
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    let listener = TcpListener::bind(cli.listen).await?;
+    jsonl::output_info(serde_json::json!({
+        "type": "server_listening",
+        "protocol": if token.is_some() { "http" } else { "grpc" },
+        "address": listener.local_addr()?.to_string(),
+    }))?;
+
+    match token {
+        Some(token) => ai_coreutils::http::serve(listener, &token).await,
+        #[cfg(feature = "grpc")]
+        None => ai_coreutils::grpc::serve(listener).await,
+        #[cfg(not(feature = "grpc"))]
+        None => unreachable!("--http is the only mode without the grpc feature"),
+    }
+}
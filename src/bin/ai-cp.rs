@@ -1,14 +1,35 @@
 //! AI-optimized cp utility
 //!
 //! Copies files and directories with progress tracking and JSONL output.
-
+//! Created paths, and backups of overwritten files, are recorded in the
+//! operation journal so ai-undo can reverse a copy.
+//!
+//! With the `sftp` feature, a source or the destination may be an `sftp://`
+//! URL. Downloads are journaled like local copies; uploads change the server
+//! directly and are not, so ai-undo cannot reverse them.
+
+use ai_coreutils::audit;
+use ai_coreutils::cli::DryRunArgs;
+use ai_coreutils::config;
+use ai_coreutils::async_ops::RetryPolicy;
+use ai_coreutils::fs_utils::{self, AtomicFile};
 use ai_coreutils::jsonl;
-use ai_coreutils::{jsonl::JsonlRecord, Result};
-use clap::Parser;
+use ai_coreutils::ops_journal::Recorder;
+use ai_coreutils::{jsonl::{JsonlRecord, Link, ProgressReporter, RecordData}, Result};
+use ai_coreutils::policy::{self, Access};
+use ai_coreutils::tuning;
+use clap::{Parser, ValueEnum};
 use std::fs;
-use std::io::{Read, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 
+#[cfg(feature = "sftp")]
+use ai_coreutils::error::AiCoreutilsError;
+#[cfg(feature = "sftp")]
+use ai_coreutils::remote::ssh::{self, Pool, SftpUrl};
+#[cfg(feature = "sftp")]
+use chrono::{DateTime, Utc};
+
 #[cfg(unix)]
 use std::os::unix::fs as unix_fs;
 #[cfg(windows)]
@@ -63,30 +84,71 @@ struct Cli {
     #[arg(short, long)]
     no_clobber: bool,
 
+    /// Control creation of sparse files
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t = SparseMode::Auto)]
+    sparse: SparseMode,
+
+    /// Retry transient I/O errors up to N times
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    retries: u32,
+
+    #[command(flatten)]
+    dry_run: DryRunArgs,
+
     /// Output JSONL (always enabled for AI-Coreutils)
     #[arg(long, default_value_t = true)]
     json: bool,
 }
 
+/// How runs of zero bytes in the source are written to the destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SparseMode {
+    /// Preserve holes of sparse sources
+    Auto,
+    /// Also turn runs of zeros in non-sparse sources into holes
+    Always,
+    /// Write every byte
+    Never,
+}
+
 #[derive(Debug, Clone)]
 struct CopyStats {
     files_copied: u64,
     bytes_copied: u64,
+    bytes_skipped_holes: u64,
     dirs_created: u64,
     errors: u64,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    config::init()?;
+    cli.dry_run.apply();
+    policy::enforce(&cli.sources, Access::Read)?;
+    policy::enforce([&cli.destination], Access::Write)?;
 
     let mut stats = CopyStats {
         files_copied: 0,
         bytes_copied: 0,
+        bytes_skipped_holes: 0,
         dirs_created: 0,
         errors: 0,
     };
 
+    let mut journal = Recorder::begin("ai-cp");
+
     // Determine if destination is a directory
+    #[cfg(feature = "sftp")]
+    let dest_is_dir = if ssh::is_sftp(&cli.destination) {
+        match ssh::pool()?.stat(&parse_url(&cli.destination)?) {
+            Ok(stat) => stat.is_dir,
+            Err(AiCoreutilsError::PathNotFound(_)) => false,
+            Err(e) => return Err(e),
+        }
+    } else {
+        cli.destination.is_dir()
+    };
+    #[cfg(not(feature = "sftp"))]
     let dest_is_dir = cli.destination.exists() && cli.destination.is_dir();
 
     // Handle multiple sources
@@ -108,13 +170,14 @@ fn main() -> Result<()> {
                 &cli.destination.join(source.file_name().unwrap_or_default()),
                 &cli,
                 &mut stats,
+                &mut journal,
             ) {
                 stats.errors += 1;
                 let error_record = JsonlRecord::error(
                     format!("Failed to copy {}: {}", source.display(), e),
                     "CP_ERROR"
                 );
-                println!("{}", error_record.to_jsonl()?);
+                error_record.print()?;
             }
         }
     } else {
@@ -126,31 +189,59 @@ fn main() -> Result<()> {
             cli.destination.clone()
         };
 
-        if let Err(e) = copy_path(source, &dest, &cli, &mut stats) {
+        if let Err(e) = copy_path(source, &dest, &cli, &mut stats, &mut journal) {
             // stats.errors += 1; // Error is already returned below
             let error_record = JsonlRecord::error(
                 format!("Failed to copy {}: {}", source.display(), e),
                 "CP_ERROR"
             );
-            println!("{}", error_record.to_jsonl()?);
+            error_record.print()?;
+            // Files copied before the failure can still be undone
+            finish_journal(journal, &mut stats)?;
             return Err(e);
         }
     }
 
+    let journal_id = finish_journal(journal, &mut stats)?;
+
     // Output final stats
-    let record = JsonlRecord::result(serde_json::json!({
-        "type": "copy_summary",
-        "files_copied": stats.files_copied,
-        "bytes_copied": stats.bytes_copied,
-        "dirs_created": stats.dirs_created,
-        "errors": stats.errors,
-    }));
-    println!("{}", record.to_jsonl()?);
+    let record = JsonlRecord::from(RecordData::CopySummary {
+        files_copied: stats.files_copied,
+        bytes_copied: stats.bytes_copied,
+        bytes_skipped_holes: stats.bytes_skipped_holes,
+        dirs_created: stats.dirs_created,
+        errors: stats.errors,
+        journal: journal_id,
+    });
+    record.print()?;
 
     Ok(())
 }
 
-fn copy_path(source: &PathBuf, dest: &PathBuf, cli: &Cli, stats: &mut CopyStats) -> Result<()> {
+/// Append the recorded changes to the journal, reporting a failure as an error
+fn finish_journal(journal: Recorder, stats: &mut CopyStats) -> Result<Option<String>> {
+    match journal.finish() {
+        Ok(id) => Ok(id),
+        Err(e) => {
+            stats.errors += 1;
+            jsonl::output_error(&e.to_string(), "JOURNAL_ERROR", None)?;
+            Ok(None)
+        }
+    }
+}
+
+fn copy_path(
+    source: &PathBuf,
+    dest: &PathBuf,
+    cli: &Cli,
+    stats: &mut CopyStats,
+    journal: &mut Recorder,
+) -> Result<()> {
+    #[cfg(feature = "sftp")]
+    if ssh::is_sftp(source) || ssh::is_sftp(dest) {
+        return sftp_copy(source, dest, cli, stats, journal);
+    }
+
     // Check if source exists
     if !source.exists() {
         return Err(ai_coreutils::error::AiCoreutilsError::PathNotFound(source.clone()));
@@ -174,11 +265,9 @@ fn copy_path(source: &PathBuf, dest: &PathBuf, cli: &Cli, stats: &mut CopyStats)
 
     // Interactive prompt
     if cli.interactive && dest.exists() {
-        jsonl::output_info(
-            serde_json::json!({
-                "prompt": format!("Overwrite {}? (y/n)", dest.display()),
-            }),
-        )?;
+        jsonl::output_data(RecordData::Prompt {
+            prompt: format!("Overwrite {}? (y/n)", dest.display()),
+        })?;
         // For now, we'll just skip interactive in non-interactive mode
         // In a real implementation, you'd read from stdin here
     }
@@ -189,32 +278,38 @@ fn copy_path(source: &PathBuf, dest: &PathBuf, cli: &Cli, stats: &mut CopyStats)
                 "Omitting directory, use -R to copy directories".to_string(),
             ));
         }
-        copy_directory(source, dest, cli, stats)?;
+        copy_directory(source, dest, cli, stats, journal)?;
     } else {
-        copy_file(source, dest, cli, stats)?;
+        copy_file(source, dest, cli, stats, journal)?;
     }
 
     Ok(())
 }
 
-fn copy_directory(source: &Path, dest: &Path, cli: &Cli, stats: &mut CopyStats) -> Result<()> {
+fn copy_directory(
+    source: &Path,
+    dest: &Path,
+    cli: &Cli,
+    stats: &mut CopyStats,
+    journal: &mut Recorder,
+) -> Result<()> {
     // Create destination directory if it doesn't exist
     if !dest.exists() {
-        fs::create_dir_all(dest)?;
+        if !cli.dry_run.is_enabled() {
+            fs::create_dir_all(dest)?;
+            journal.created_dir(dest);
+        }
         stats.dirs_created += 1;
 
         if cli.verbose {
-            jsonl::output_info(
-                serde_json::json!({
-                    "type": "directory_created",
-                    "path": dest.display().to_string(),
-                }),
-            )?;
+            jsonl::output_data(RecordData::DirectoryCreated {
+                path: dest.display().to_string(),
+            })?;
         }
     }
 
     // Preserve permissions if requested
-    if cli.preserve || cli.archive {
+    if (cli.preserve || cli.archive) && !cli.dry_run.is_enabled() {
         if let Ok(source_meta) = fs::metadata(source) {
             fs::set_permissions(dest, source_meta.permissions())?;
         }
@@ -225,54 +320,67 @@ fn copy_directory(source: &Path, dest: &Path, cli: &Cli, stats: &mut CopyStats)
         let entry = entry?;
         let source_path = entry.path();
         let dest_path = dest.join(entry.file_name());
+        let allowed = policy::check(&source_path, Access::Read)
+            .and_then(|()| policy::check(&dest_path, Access::Write));
+        if let Err(e) = allowed {
+            policy::report(&e)?;
+            stats.errors += 1;
+            continue;
+        }
 
-        copy_path(&source_path, &dest_path, cli, stats)?;
+        copy_path(&source_path, &dest_path, cli, stats, journal)?;
     }
 
     Ok(())
 }
 
-fn copy_file(source: &Path, dest: &Path, cli: &Cli, stats: &mut CopyStats) -> Result<()> {
+fn copy_file(
+    source: &Path,
+    dest: &Path,
+    cli: &Cli,
+    stats: &mut CopyStats,
+    journal: &mut Recorder,
+) -> Result<()> {
     // Check if we should create a link instead
     if cli.link {
-        fs::hard_link(source, dest)?;
+        if !cli.dry_run.is_enabled() {
+            fs::hard_link(source, dest)?;
+            journal.created(dest);
+        }
         stats.files_copied += 1;
 
         if cli.verbose {
-            jsonl::output_info(
-                serde_json::json!({
-                    "type": "hard_link_created",
-                    "source": source.display().to_string(),
-                    "dest": dest.display().to_string(),
-                }),
-            )?;
+            jsonl::output_data(RecordData::HardLinkCreated {
+                source: source.display().to_string(),
+                dest: dest.display().to_string(),
+            })?;
         }
         return Ok(());
     }
 
     if cli.symbolic_link {
-        #[cfg(unix)]
-        {
-            unix_fs::symlink(source, dest)?;
-        }
-        #[cfg(windows)]
-        {
-            if source.is_dir() {
-                windows_fs::symlink_dir(source, dest)?;
-            } else {
-                windows_fs::symlink_file(source, dest)?;
+        if !cli.dry_run.is_enabled() {
+            #[cfg(unix)]
+            {
+                unix_fs::symlink(source, dest)?;
+            }
+            #[cfg(windows)]
+            {
+                if source.is_dir() {
+                    windows_fs::symlink_dir(source, dest)?;
+                } else {
+                    windows_fs::symlink_file(source, dest)?;
+                }
             }
+            journal.created(dest);
         }
         stats.files_copied += 1;
 
         if cli.verbose {
-            jsonl::output_info(
-                serde_json::json!({
-                    "type": "symbolic_link_created",
-                    "source": source.display().to_string(),
-                    "dest": dest.display().to_string(),
-                }),
-            )?;
+            jsonl::output_data(RecordData::SymbolicLinkCreated {
+                source: source.display().to_string(),
+                dest: dest.display().to_string(),
+            })?;
         }
         return Ok(());
     }
@@ -281,35 +389,29 @@ fn copy_file(source: &Path, dest: &Path, cli: &Cli, stats: &mut CopyStats) -> Re
     let source_meta = fs::metadata(source)?;
     let file_size = source_meta.len();
 
-    // Output progress
-    jsonl::output_progress(0, file_size as usize, &format!("Copying {}", source.display()))?;
-
-    // Actually copy the file
-    let mut source_file = fs::File::open(source)?;
-    let mut dest_file = fs::File::create(dest)?;
-
-    let mut buffer = vec![0u8; 8192];
-    let mut total_copied = 0u64;
-
-    loop {
-        let bytes_read = source_file.read(&mut buffer)?;
-        if bytes_read == 0 {
-            break;
-        }
+    // Report progress for this file (throttled)
+    let mut progress = copy_progress(format!("Copying {}", source.display()), file_size, cli);
+    progress.advance(0)?;
 
-        dest_file.write_all(&buffer[..bytes_read])?;
-        total_copied += bytes_read as u64;
-
-        // Output progress for large files
-        if file_size > 1024 * 1024 && total_copied.is_multiple_of(1024 * 1024) {
-            jsonl::output_progress(total_copied as usize, file_size as usize, &format!("Copying {}", source.display()))?;
-        }
+    if cli.dry_run.is_enabled() {
+        let outcome = plan_contents(source, cli.sparse)?;
+        progress.advance(outcome.bytes)?;
+        progress.finish()?;
+        return record_copy(source, dest, cli, stats, outcome, &progress);
     }
 
-    dest_file.sync_all()?;
-
-    stats.files_copied += 1;
-    stats.bytes_copied += total_copied;
+    // Keep the file being overwritten, if any
+    let existed = journal.before_write(dest)?;
+
+    // Actually copy the file, restarting from scratch on transient errors
+    let outcome = RetryPolicy::with_retries(cli.retries).run_blocking(
+        &format!("copy {} -> {}", source.display(), dest.display()),
+        || copy_contents(source, dest, cli.sparse, &mut progress),
+    )?;
+    progress.finish()?;
+    if !existed {
+        journal.created(dest);
+    }
 
     // Preserve attributes if requested
     if cli.preserve || cli.archive {
@@ -330,15 +432,371 @@ fn copy_file(source: &Path, dest: &Path, cli: &Cli, stats: &mut CopyStats) -> Re
         }
     }
 
+    record_copy(source, dest, cli, stats, outcome, &progress)
+}
+
+/// Copy to or from an `sftp://` URL
+#[cfg(feature = "sftp")]
+fn sftp_copy(
+    source: &Path,
+    dest: &Path,
+    cli: &Cli,
+    stats: &mut CopyStats,
+    journal: &mut Recorder,
+) -> Result<()> {
+    if cli.link || cli.symbolic_link {
+        return Err(AiCoreutilsError::NotSupported(
+            "Links cannot be made to or from sftp:// URLs".to_string(),
+        ));
+    }
+    let pool = ssh::pool()?;
+    match (ssh::is_sftp(source), ssh::is_sftp(dest)) {
+        (true, true) => Err(AiCoreutilsError::NotSupported(
+            "Copying between two sftp:// URLs".to_string(),
+        )),
+        (true, false) => download(pool, &parse_url(source)?, dest, cli, stats, journal),
+        _ => upload(pool, source, &parse_url(dest)?, cli, stats),
+    }
+}
+
+#[cfg(feature = "sftp")]
+fn parse_url(path: &Path) -> Result<SftpUrl> {
+    SftpUrl::parse(&path.to_string_lossy())
+}
+
+/// Copy the remote file or tree `source` to the local `dest`
+#[cfg(feature = "sftp")]
+fn download(
+    pool: &Pool,
+    source: &SftpUrl,
+    dest: &Path,
+    cli: &Cli,
+    stats: &mut CopyStats,
+    journal: &mut Recorder,
+) -> Result<()> {
+    let stat = pool.stat(source)?;
+
+    if dest.exists() && cli.no_clobber {
+        return Ok(());
+    }
+    if cli.update && dest.exists() {
+        let dest_modified = DateTime::<Utc>::from(fs::metadata(dest)?.modified()?);
+        if stat.modified.is_some_and(|modified| dest_modified >= modified) {
+            return Ok(());
+        }
+    }
+
+    if stat.is_dir {
+        if !cli.recursive && !cli.archive {
+            return Err(AiCoreutilsError::InvalidInput(
+                "Omitting directory, use -R to copy directories".to_string(),
+            ));
+        }
+        if !dest.exists() {
+            if !cli.dry_run.is_enabled() {
+                fs::create_dir_all(dest)?;
+                journal.created_dir(dest);
+            }
+            stats.dirs_created += 1;
+
+            if cli.verbose {
+                jsonl::output_data(RecordData::DirectoryCreated {
+                    path: dest.display().to_string(),
+                })?;
+            }
+        }
+
+        for (name, _) in pool.read_dir(source)? {
+            let dest_path = dest.join(&name);
+            if let Err(e) = policy::check(&dest_path, Access::Write) {
+                policy::report(&e)?;
+                stats.errors += 1;
+                continue;
+            }
+            download(pool, &source.join(&name), &dest_path, cli, stats, journal)?;
+        }
+        return Ok(());
+    }
+
+    let source_path = PathBuf::from(source.to_string());
+    let mut progress = copy_progress(format!("Copying {}", source), stat.size, cli);
+    progress.advance(0)?;
+
+    if cli.dry_run.is_enabled() {
+        progress.advance(stat.size)?;
+        progress.finish()?;
+        let outcome = CopyOutcome { bytes: stat.size, holes: 0 };
+        return record_copy(&source_path, dest, cli, stats, outcome, &progress);
+    }
+
+    let existed = journal.before_write(dest)?;
+    let bytes = RetryPolicy::with_retries(cli.retries).run_blocking(
+        &format!("copy {} -> {}", source, dest.display()),
+        || {
+            let dest_file = AtomicFile::create(dest)?;
+            let mut writer = dest_file.file();
+            let bytes = pool.read_range(source, 0, u64::MAX, |chunk| {
+                writer.write_all(chunk)?;
+                progress.advance(chunk.len() as u64)
+            })?;
+            dest_file.commit()?;
+            Ok(bytes)
+        },
+    )?;
+    progress.finish()?;
+    if !existed {
+        journal.created(dest);
+    }
+
+    #[cfg(unix)]
+    if cli.preserve || cli.archive {
+        if let Some(mode) = stat.permissions {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(dest, fs::Permissions::from_mode(mode))?;
+        }
+    }
+
+    record_copy(&source_path, dest, cli, stats, CopyOutcome { bytes, holes: 0 }, &progress)
+}
+
+/// Copy the local file or tree `source` to the remote `dest`
+#[cfg(feature = "sftp")]
+fn upload(pool: &Pool, source: &Path, dest: &SftpUrl, cli: &Cli, stats: &mut CopyStats) -> Result<()> {
+    if !source.exists() {
+        return Err(AiCoreutilsError::PathNotFound(source.to_path_buf()));
+    }
+    let existing = match pool.stat(dest) {
+        Ok(stat) => Some(stat),
+        Err(AiCoreutilsError::PathNotFound(_)) => None,
+        Err(e) => return Err(e),
+    };
+
+    if existing.is_some() && cli.no_clobber {
+        return Ok(());
+    }
+    if cli.update {
+        let source_modified = DateTime::<Utc>::from(fs::metadata(source)?.modified()?);
+        if existing.as_ref().and_then(|stat| stat.modified).is_some_and(|modified| modified >= source_modified) {
+            return Ok(());
+        }
+    }
+
+    if source.is_dir() {
+        if !cli.recursive && !cli.archive {
+            return Err(AiCoreutilsError::InvalidInput(
+                "Omitting directory, use -R to copy directories".to_string(),
+            ));
+        }
+        if existing.is_none() {
+            if !cli.dry_run.is_enabled() {
+                pool.create_dir(dest)?;
+            }
+            stats.dirs_created += 1;
+
+            if cli.verbose {
+                jsonl::output_data(RecordData::DirectoryCreated {
+                    path: dest.to_string(),
+                })?;
+            }
+        }
+
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            let source_path = entry.path();
+            if let Err(e) = policy::check(&source_path, Access::Read) {
+                policy::report(&e)?;
+                stats.errors += 1;
+                continue;
+            }
+            let dest_url = dest.join(&entry.file_name().to_string_lossy());
+            upload(pool, &source_path, &dest_url, cli, stats)?;
+        }
+        return Ok(());
+    }
+
+    let dest_path = PathBuf::from(dest.to_string());
+    let size = fs::metadata(source)?.len();
+    let mut progress = copy_progress(format!("Copying {}", source.display()), size, cli);
+    progress.advance(0)?;
+
+    if cli.dry_run.is_enabled() {
+        progress.advance(size)?;
+        progress.finish()?;
+        return record_copy(source, &dest_path, cli, stats, CopyOutcome { bytes: size, holes: 0 }, &progress);
+    }
+
+    let bytes = RetryPolicy::with_retries(cli.retries).run_blocking(
+        &format!("copy {} -> {}", source.display(), dest),
+        || pool.write(dest, fs::File::open(source)?, |n| progress.advance(n)),
+    )?;
+    progress.finish()?;
+    audit::read(source, bytes);
+
+    record_copy(source, &dest_path, cli, stats, CopyOutcome { bytes, holes: 0 }, &progress)
+}
+
+/// Report the progress of copying one file
+///
+/// With `--verbose`, the progress records name the file's `file_copied`
+/// record as their parent.
+fn copy_progress(message: String, total: u64, cli: &Cli) -> ProgressReporter {
+    let progress = ProgressReporter::new(message, total);
     if cli.verbose {
-        jsonl::output_info(
-            serde_json::json!({
-                "type": "file_copied",
-                "source": source.display().to_string(),
-                "dest": dest.display().to_string(),
-                "size": total_copied,
-            }),
-        )?;
+        progress.with_parent(jsonl::next_record_id())
+    } else {
+        progress
+    }
+}
+
+/// Count a copied file and report it with `--verbose`
+fn record_copy(
+    source: &Path,
+    dest: &Path,
+    cli: &Cli,
+    stats: &mut CopyStats,
+    outcome: CopyOutcome,
+    progress: &ProgressReporter,
+) -> Result<()> {
+    stats.files_copied += 1;
+    stats.bytes_copied += outcome.bytes;
+    stats.bytes_skipped_holes += outcome.holes;
+
+    if cli.verbose {
+        let record = JsonlRecord::from(RecordData::FileCopied {
+            source: source.display().to_string(),
+            dest: dest.display().to_string(),
+            size: outcome.bytes,
+            holes: outcome.holes,
+        });
+        record.print_linked(progress.parent().map(Link::id).unwrap_or_default())?;
+    }
+
+    Ok(())
+}
+
+/// Bytes copied for one file
+#[derive(Debug, Default)]
+struct CopyOutcome {
+    /// Apparent bytes written, holes included
+    bytes: u64,
+    /// Bytes left as holes instead of being written
+    holes: u64,
+}
+
+/// What [`copy_contents`] would write, for `--dry-run`
+///
+/// Holes of sparse sources are found the same way; with `--sparse=always`,
+/// runs of zeros in the data are not, as that takes reading it.
+fn plan_contents(source: &Path, sparse: SparseMode) -> Result<CopyOutcome> {
+    let source_file = fs::File::open(source)?;
+    let source_meta = source_file.metadata()?;
+    let segments = match sparse {
+        SparseMode::Never => None,
+        SparseMode::Auto if !fs_utils::is_sparse(&source_meta) => None,
+        _ => fs_utils::data_segments(&source_file)?,
+    };
+
+    let data: u64 = match &segments {
+        Some(segments) => segments.iter().map(|r| r.end - r.start).sum(),
+        None => source_meta.len(),
+    };
+    Ok(CopyOutcome {
+        bytes: source_meta.len(),
+        holes: source_meta.len().saturating_sub(data),
+    })
+}
+
+/// Copy file data into a temporary sibling of `dest` and rename it into place,
+/// so an interrupted copy never leaves a truncated destination
+///
+/// Sparse sources are copied region by region (`SEEK_DATA`/`SEEK_HOLE`) so
+/// their holes are preserved; with `--sparse=always` all-zero blocks are
+/// skipped as well.
+fn copy_contents(
+    source: &Path,
+    dest: &Path,
+    sparse: SparseMode,
+    progress: &mut ProgressReporter,
+) -> Result<CopyOutcome> {
+    let mut source_file = fs::File::open(source)?;
+    let source_meta = source_file.metadata()?;
+    let dest_file = AtomicFile::create(dest)?;
+    let mut writer = dest_file.file();
+
+    let segments = match sparse {
+        SparseMode::Never => None,
+        SparseMode::Auto if !fs_utils::is_sparse(&source_meta) => None,
+        _ => fs_utils::data_segments(&source_file)?,
+    };
+
+    let mut outcome = CopyOutcome::default();
+    let mut length = 0u64;
+
+    match &segments {
+        Some(segments) => {
+            let data: u64 = segments.iter().map(|r| r.end - r.start).sum();
+            let hole_bytes = source_meta.len().saturating_sub(data);
+            outcome.holes += hole_bytes;
+            outcome.bytes += hole_bytes;
+            progress.advance(hole_bytes)?;
+
+            for segment in segments {
+                source_file.seek(SeekFrom::Start(segment.start))?;
+                writer.seek(SeekFrom::Start(segment.start))?;
+                let len = segment.end - segment.start;
+                copy_range(&mut source_file, writer, len, sparse, &mut outcome, progress)?;
+            }
+            length = source_meta.len();
+        }
+        None => {
+            copy_range(&mut source_file, writer, u64::MAX, sparse, &mut outcome, progress)?;
+        }
+    }
+
+    // Skipped trailing zeros do not extend the file by themselves
+    length = length.max(outcome.bytes);
+    writer.set_len(length)?;
+    dest_file.commit()?;
+    audit::read(source, outcome.bytes - outcome.holes);
+
+    Ok(outcome)
+}
+
+/// Copy up to `limit` bytes from the current positions, stopping at end of file
+fn copy_range(
+    reader: &mut fs::File,
+    mut writer: &fs::File,
+    limit: u64,
+    sparse: SparseMode,
+    outcome: &mut CopyOutcome,
+    progress: &mut ProgressReporter,
+) -> Result<()> {
+    // Holes are found a buffer at a time, so --sparse=always keeps small ones
+    let size = match sparse {
+        SparseMode::Always => tuning::DEFAULT_BUFFER_SIZE,
+        _ => tuning::buffer_size(None),
+    };
+    let mut buffer = vec![0u8; size];
+    let mut remaining = limit;
+
+    while remaining > 0 {
+        let want = remaining.min(buffer.len() as u64) as usize;
+        let bytes_read = reader.read(&mut buffer[..want])?;
+        if bytes_read == 0 {
+            break;
+        }
+
+        let chunk = &buffer[..bytes_read];
+        if sparse == SparseMode::Always && chunk.iter().all(|&b| b == 0) {
+            writer.seek(SeekFrom::Current(bytes_read as i64))?;
+            outcome.holes += bytes_read as u64;
+        } else {
+            writer.write_all(chunk)?;
+        }
+
+        outcome.bytes += bytes_read as u64;
+        remaining -= bytes_read as u64;
+        progress.advance(bytes_read as u64)?;
     }
 
     Ok(())
@@ -0,0 +1,446 @@
+//! AI-optimized CSV utility
+//!
+//! Reads CSV/TSV files (memory-mapped, with SIMD delimiter scanning) and
+//! outputs rows as JSONL records keyed by column name:
+//! - `head`: the first rows
+//! - `select`: chosen columns of every row, as JSONL or CSV
+//! - `stats`: row and column counts, and rows with the wrong field count
+//! - `profile`: per-column types, nulls, numeric summaries and cardinality
+//! - `to-jsonl`: every row as a plain JSON object, for other tools to consume
+
+use ai_coreutils::{
+    cli::{LimitArgs, MmapArgs},
+    config,
+    csv_ops::{self, Dialect, Reader, Record, TableProfile},
+    limits::{self, Emit},
+    policy::{self, Access},
+    JsonlRecord, Result, SafeMemoryAccess,
+};
+use clap::{Args, Parser, Subcommand};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::io::{self, BufWriter, StdoutLock, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// AI-optimized CSV: Inspect and convert tabular data
+#[derive(Parser, Debug)]
+#[command(name = "ai-csv")]
+#[command(about = "Inspect, select and convert CSV/TSV files as JSONL", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Output the first rows
+    Head {
+        #[command(flatten)]
+        input: Input,
+
+        /// Number of rows
+        #[arg(short = 'n', long, default_value_t = 10)]
+        rows: usize,
+
+        /// Write CSV instead of JSONL
+        #[arg(long)]
+        csv: bool,
+    },
+
+    /// Output chosen columns of every row
+    Select {
+        #[command(flatten)]
+        input: Input,
+
+        /// Columns to keep, by name or 1-based number (comma-separated)
+        #[arg(short, long, value_delimiter = ',', required = true)]
+        columns: Vec<String>,
+
+        /// Stop after N rows
+        #[arg(short = 'n', long, value_name = "N")]
+        rows: Option<usize>,
+
+        /// Write CSV instead of JSONL
+        #[arg(long)]
+        csv: bool,
+    },
+
+    /// Count rows and columns and find rows with the wrong number of fields
+    Stats {
+        #[command(flatten)]
+        input: Input,
+    },
+
+    /// Profile each column: type, nulls, min/max/mean/stddev, distinct values
+    Profile {
+        #[command(flatten)]
+        input: Input,
+
+        /// Columns to profile, by name or 1-based number (default: all)
+        #[arg(short, long, value_delimiter = ',')]
+        columns: Vec<String>,
+
+        /// Only profile the first N rows
+        #[arg(short = 'n', long, value_name = "N")]
+        rows: Option<usize>,
+    },
+
+    /// Convert rows to plain JSON objects, one per line
+    ToJsonl {
+        #[command(flatten)]
+        input: Input,
+
+        /// Stop after N rows
+        #[arg(short = 'n', long, value_name = "N")]
+        rows: Option<usize>,
+
+        /// Emit numbers and booleans as JSON values and empty fields as null
+        #[arg(long)]
+        typed: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+struct Input {
+    /// File to read ("-" for stdin)
+    file: PathBuf,
+
+    /// Field delimiter: one character, or "tab" (default: from the extension, else sniffed)
+    #[arg(short, long, value_parser = parse_delimiter)]
+    delimiter: Option<u8>,
+
+    /// The first row is data, not a header
+    #[arg(long)]
+    no_header: bool,
+
+    /// Drop whitespace around fields (inside quotes it is kept)
+    #[arg(long)]
+    trim: bool,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    mmap: MmapArgs,
+}
+
+fn parse_delimiter(s: &str) -> std::result::Result<u8, String> {
+    match s {
+        "tab" | "\\t" | "\t" => Ok(b'\t'),
+        _ if s.len() == 1 && s.is_ascii() => Ok(s.as_bytes()[0]),
+        _ => Err(format!("Invalid delimiter: {} (expected one ASCII character or \"tab\")", s)),
+    }
+}
+
+
+/// An opened input: its reader, positioned after the header, and column names
+struct Table<'a> {
+    reader: Reader<'a>,
+    names: Vec<String>,
+}
+
+impl Input {
+    fn open<'a>(&self, data: &'a [u8]) -> Result<Table<'a>> {
+        const SNIFF_LEN: usize = 64 * 1024;
+
+        let dialect = match self.delimiter {
+            Some(delimiter) => Dialect {
+                delimiter,
+                ..Dialect::CSV
+            },
+            None => Dialect::for_path(&self.file)
+                .unwrap_or_else(|| Dialect::sniff(&data[..data.len().min(SNIFF_LEN)])),
+        };
+        let mut reader = Reader::new(data, dialect).trim(self.trim);
+        let names = match self.no_header {
+            true => Vec::new(),
+            false => match reader.next() {
+                Some(header) => csv_ops::column_names(&header?.to_strings()),
+                None => Vec::new(),
+            },
+        };
+        Ok(Table { reader, names })
+    }
+
+    fn display(&self) -> String {
+        self.file.display().to_string()
+    }
+}
+
+type Output<'a> = BufWriter<StdoutLock<'a>>;
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+
+    let input = match &cli.command {
+        Command::Head { input, .. }
+        | Command::Select { input, .. }
+        | Command::Stats { input }
+        | Command::Profile { input, .. }
+        | Command::ToJsonl { input, .. } => input,
+    };
+    input.limits.apply()?;
+    input.mmap.apply();
+    policy::enforce([&input.file], Access::Read)?;
+    let source = match SafeMemoryAccess::open_or_stdin(&input.file) {
+        Ok(source) => source,
+        Err(e) => {
+            let mut out = BufWriter::new(io::stdout().lock());
+            write_error(&mut out, input, &e.to_string())?;
+            out.flush()?;
+            std::process::exit(1);
+        }
+    };
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let ok = match &cli.command {
+        Command::Head { input, rows, csv } => rows_main(&mut out, input, &source, &[], Some(*rows), *csv)?,
+        Command::Select { input, columns, rows, csv } => {
+            rows_main(&mut out, input, &source, columns, *rows, *csv)?
+        }
+        Command::Stats { input } => stats_main(&mut out, input, &source)?,
+        Command::Profile { input, columns, rows } => profile_main(&mut out, input, &source, columns, *rows)?,
+        Command::ToJsonl { input, rows, typed } => to_jsonl_main(&mut out, input, &source, *rows, *typed)?,
+    };
+    out.flush()?;
+
+    if !ok {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Output rows (all columns, or the `columns` chosen) as JSONL or CSV
+fn rows_main(
+    out: &mut Output,
+    input: &Input,
+    source: &SafeMemoryAccess,
+    columns: &[String],
+    limit: Option<usize>,
+    csv: bool,
+) -> Result<bool> {
+    let Table { reader, names } = match input.open(source.as_slice()) {
+        Ok(table) => table,
+        Err(e) => return write_error(out, input, &e.to_string()).map(|_| false),
+    };
+    let dialect = reader.dialect();
+    let selected = match columns {
+        [] => None,
+        specs => match csv_ops::resolve_columns(&names, specs) {
+            Ok(indices) => Some(indices),
+            Err(e) => return write_error(out, input, &e.to_string()).map(|_| false),
+        },
+    };
+
+    if csv && !input.no_header {
+        let header: Vec<&str> = match &selected {
+            Some(indices) => indices.iter().map(|&i| names.get(i).map_or("", String::as_str)).collect(),
+            None => names.iter().map(String::as_str).collect(),
+        };
+        csv_ops::write_record(out, &header, dialect)?;
+    }
+
+    let mut rows = 0;
+    for record in reader.take(limit.unwrap_or(usize::MAX)) {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => return write_error(out, input, &e.to_string()).map(|_| false),
+        };
+        rows += 1;
+        let indices: Vec<usize> = match &selected {
+            Some(indices) => indices.clone(),
+            None => (0..record.len().max(names.len())).collect(),
+        };
+
+        if csv {
+            let fields: Vec<&[u8]> = indices
+                .iter()
+                .map(|&i| record.fields.get(i).map_or(&[][..], |f| f.as_ref()))
+                .collect();
+            csv_ops::write_record(out, &fields, dialect)?;
+            continue;
+        }
+
+        let fields: serde_json::Map<String, serde_json::Value> = indices
+            .iter()
+            .map(|&i| {
+                let value = record.get_str(i).map_or(serde_json::Value::Null, |s| s.into());
+                (csv_ops::column_name(&names, i).into_owned(), value)
+            })
+            .collect();
+        write_record(out, JsonlRecord::result(serde_json::json!({
+            "type": "csv_row",
+            "row": rows,
+            "line": record.line,
+            "fields": fields,
+        })))?;
+    }
+
+    // CSV output stays a plain table
+    if !csv {
+        write_record(out, JsonlRecord::summary(serde_json::json!({
+            "type": "csv_summary",
+            "file": input.display(),
+            "rows": rows,
+        })))?;
+    }
+    Ok(true)
+}
+
+/// Report the shape of the table
+fn stats_main(out: &mut Output, input: &Input, source: &SafeMemoryAccess) -> Result<bool> {
+    let started = Instant::now();
+    let data = source.as_slice();
+    let Table { reader, names } = match input.open(data) {
+        Ok(table) => table,
+        Err(e) => return write_error(out, input, &e.to_string()).map(|_| false),
+    };
+    let dialect = reader.dialect();
+
+    let mut columns = (!input.no_header).then_some(names.len());
+    let (mut rows, mut ragged_rows, mut max_fields, mut empty_fields) = (0u64, 0u64, 0, 0u64);
+    let mut first_ragged_line = None;
+    let mut ok = true;
+    for record in reader {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                write_error(out, input, &e.to_string())?;
+                ok = false;
+                break;
+            }
+        };
+        rows += 1;
+        max_fields = max_fields.max(record.len());
+        empty_fields += record.fields.iter().filter(|f| f.is_empty()).count() as u64;
+        // Without a header, the first row sets the expected width
+        let expected = *columns.get_or_insert(record.len());
+        if record.len() != expected {
+            ragged_rows += 1;
+            first_ragged_line.get_or_insert(record.line);
+        }
+    }
+
+    write_record(out, JsonlRecord::summary(serde_json::json!({
+        "type": "csv_stats",
+        "file": input.display(),
+        "delimiter": (dialect.delimiter as char).to_string(),
+        "header": !input.no_header,
+        "column_names": names,
+        "columns": columns.unwrap_or(0),
+        "rows": rows,
+        "max_fields": max_fields,
+        "ragged_rows": ragged_rows,
+        "first_ragged_line": first_ragged_line,
+        "empty_fields": empty_fields,
+        "bytes": data.len(),
+        "complete": ok,
+        "elapsed_ms": started.elapsed().as_millis() as u64,
+    })))?;
+    Ok(ok)
+}
+
+/// Profile each column in one pass
+fn profile_main(
+    out: &mut Output,
+    input: &Input,
+    source: &SafeMemoryAccess,
+    columns: &[String],
+    limit: Option<usize>,
+) -> Result<bool> {
+    let started = Instant::now();
+    let Table { reader, names } = match input.open(source.as_slice()) {
+        Ok(table) => table,
+        Err(e) => return write_error(out, input, &e.to_string()).map(|_| false),
+    };
+    let mut profile = match columns {
+        [] => TableProfile::new(names),
+        specs => match csv_ops::resolve_columns(&names, specs) {
+            Ok(indices) => TableProfile::with_columns(names, indices),
+            Err(e) => return write_error(out, input, &e.to_string()).map(|_| false),
+        },
+    };
+
+    let mut ok = true;
+    for record in reader.take(limit.unwrap_or(usize::MAX)) {
+        match record {
+            Ok(record) => profile.add_record(&record),
+            Err(e) => {
+                write_error(out, input, &e.to_string())?;
+                ok = false;
+                break;
+            }
+        }
+    }
+
+    let columns: Vec<serde_json::Value> = profile.columns().iter().map(|c| c.to_json()).collect();
+    write_record(out, JsonlRecord::summary(serde_json::json!({
+        "type": "csv_profile",
+        "file": input.display(),
+        "rows": profile.rows(),
+        "columns": columns,
+        "complete": ok,
+        "elapsed_ms": started.elapsed().as_millis() as u64,
+    })))?;
+    Ok(ok)
+}
+
+/// A row as a JSON object with keys in column order
+struct RowObject<'a> {
+    names: &'a [String],
+    record: &'a Record<'a>,
+    typed: bool,
+}
+
+impl Serialize for RowObject<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let width = self.record.len().max(self.names.len());
+        let mut map = serializer.serialize_map(Some(width))?;
+        for i in 0..width {
+            let key = csv_ops::column_name(self.names, i);
+            match self.record.get_str(i) {
+                Some(value) if self.typed => map.serialize_entry(&key, &csv_ops::typed_value(&value))?,
+                Some(value) => map.serialize_entry(&key, &value)?,
+                None => map.serialize_entry(&key, &serde_json::Value::Null)?,
+            }
+        }
+        map.end()
+    }
+}
+
+/// Convert every row to a JSON object
+fn to_jsonl_main(out: &mut Output, input: &Input, source: &SafeMemoryAccess, limit: Option<usize>, typed: bool) -> Result<bool> {
+    let Table { reader, names } = match input.open(source.as_slice()) {
+        Ok(table) => table,
+        Err(e) => return write_error(out, input, &e.to_string()).map(|_| false),
+    };
+
+    for record in reader.take(limit.unwrap_or(usize::MAX)) {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => return write_error(out, input, &e.to_string()).map(|_| false),
+        };
+        match limits::record()? {
+            Emit::Write => {}
+            Emit::Replace(line) => {
+                writeln!(out, "{}", line)?;
+                return Ok(false);
+            }
+            Emit::Skip => continue,
+        }
+        serde_json::to_writer(&mut *out, &RowObject { names: &names, record: &record, typed })?;
+        out.write_all(b"\n")?;
+    }
+    Ok(true)
+}
+
+fn write_record(out: &mut Output, record: JsonlRecord) -> Result<()> {
+    record.write_line(&mut *out)
+}
+
+/// Write a CSV_ERROR record into the output stream
+fn write_error(out: &mut Output, input: &Input, message: &str) -> Result<()> {
+    write_record(out, JsonlRecord::error(format!("{}: {}", input.display(), message), "CSV_ERROR"))
+}
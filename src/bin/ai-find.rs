@@ -2,10 +2,14 @@
 //!
 //! Searches for files in a directory hierarchy with JSONL output.
 
-use ai_coreutils::jsonl;
+use ai_coreutils::cli::{parse_size, LimitArgs, LogArgs};
+use ai_coreutils::config;
+use ai_coreutils::fs_utils::{self, DirWalk, EntryInfo, SymlinkPolicy, WalkEvent};
+use ai_coreutils::git_info::GitAnnotator;
+use ai_coreutils::jsonl::{self, RecordData};
 use ai_coreutils::Result;
+use ai_coreutils::policy::{self, Access};
 use clap::Parser;
-use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
@@ -50,9 +54,17 @@ struct Cli {
     maxdepth: Option<usize>,
 
     /// Minimum depth to search
-    #[arg(short, long)]
+    #[arg(long)]
     mindepth: Option<usize>,
 
+    /// When to follow symlinks: never, command-line or always
+    #[arg(long, value_name = "WHEN", default_value = "command-line")]
+    follow_symlinks: SymlinkPolicy,
+
+    /// Annotate matches with git status and last commit (needs the `git` feature)
+    #[arg(long)]
+    git_status: bool,
+
     /// Verbose output
     #[arg(short, long)]
     verbose: bool,
@@ -60,6 +72,12 @@ struct Cli {
     /// Output JSONL (always enabled for AI-Coreutils)
     #[arg(long, default_value_t = true)]
     json: bool,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -82,23 +100,6 @@ fn parse_type_filter(s: &str) -> std::result::Result<Vec<TypeFilter>, String> {
     Ok(filters)
 }
 
-fn parse_size(s: &str) -> std::result::Result<u64, String> {
-    let s = s.trim();
-    let (num, suffix) = if s.ends_with('K') || s.ends_with('k') {
-        (&s[..s.len()-1], 1024u64)
-    } else if s.ends_with('M') || s.ends_with('m') {
-        (&s[..s.len()-1], 1024 * 1024)
-    } else if s.ends_with('G') || s.ends_with('g') {
-        (&s[..s.len()-1], 1024 * 1024 * 1024)
-    } else {
-        (s, 1u64)
-    };
-
-    num.parse::<u64>()
-        .map(|n| n * suffix)
-        .map_err(|_| format!("Invalid size: {}", s))
-}
-
 fn parse_octal(s: &str) -> std::result::Result<u32, String> {
     u32::from_str_radix(s, 8)
         .map_err(|_| format!("Invalid octal number: {}", s))
@@ -114,6 +115,10 @@ struct MatchStats {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    cli.log.apply();
+    policy::enforce(&cli.paths, Access::Read)?;
 
     let mut stats = MatchStats {
         files_matched: 0,
@@ -122,13 +127,25 @@ fn main() -> Result<()> {
         searched: 0,
     };
 
+    let mut git = if cli.git_status {
+        match GitAnnotator::new() {
+            Ok(git) => Some(git),
+            Err(e) => {
+                jsonl::output_error(&e.to_string(), "GIT_ERROR", None)?;
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
     // Search each starting path
     for start_path in &cli.paths {
-        find_in_directory(start_path, &cli, 0, &mut stats)?;
+        find_in_directory(start_path, &cli, &mut stats, git.as_mut())?;
     }
 
     // Output final stats
-    jsonl::output_result(serde_json::json!({
+    jsonl::output_summary(serde_json::json!({
         "type": "find_summary",
         "files_matched": stats.files_matched,
         "dirs_matched": stats.dirs_matched,
@@ -142,68 +159,51 @@ fn main() -> Result<()> {
 fn find_in_directory(
     path: &Path,
     cli: &Cli,
-    depth: usize,
     stats: &mut MatchStats,
+    mut git: Option<&mut GitAnnotator>,
 ) -> Result<()> {
-    // Check depth constraints
-    if let Some(maxdepth) = cli.maxdepth {
-        if depth > maxdepth {
-            return Ok(());
-        }
-    }
-
-    if let Some(mindepth) = cli.mindepth {
-        if depth < mindepth {
-            // Still need to traverse deeper
-            if path.is_dir() {
-                let entries = match fs::read_dir(path) {
-                    Ok(e) => e,
-                    Err(_) => return Ok(()),
-                };
-
-                for entry in entries {
-                    let entry = entry?;
-                    let entry_path = entry.path();
-                    find_in_directory(&entry_path, cli, depth + 1, stats)?;
-                }
+    // Configured ignore rules are applied by the walker (never to the start point)
+    for event in DirWalk::with_max_depth(path, cli.follow_symlinks, cli.maxdepth) {
+        let entry = match event {
+            Ok(WalkEvent::Entry(entry)) => entry,
+            Ok(WalkEvent::Loop(symlink_loop)) => {
+                jsonl::output_info(symlink_loop.to_json())?;
+                continue;
+            }
+            Err(e) => {
+                policy::report(&e)?;
+                fs_utils::warn_walk_error(&e)?;
+                continue;
             }
-            return Ok(());
-        }
-    }
-
-    // Check if current path matches
-    if matches_filters(path, cli)? {
-        output_match(path, cli)?;
-        update_stats(path, stats);
-    }
-
-    stats.searched += 1;
-
-    // Recurse into directories
-    if path.is_dir() {
-        let entries = match fs::read_dir(path) {
-            Ok(e) => e,
-            Err(_) => return Ok(()),
         };
 
-        for entry in entries {
-            let entry = entry?;
-            let entry_path = entry.path();
-            find_in_directory(&entry_path, cli, depth + 1, stats)?;
+        // Entries above the minimum depth are traversed but not matched
+        if cli.mindepth.is_some_and(|mindepth| entry.depth() < mindepth) {
+            continue;
+        }
+
+        // Check if current entry matches
+        if matches_filters(&entry, cli)? {
+            output_match(&entry, cli, git.as_deref_mut())?;
+            update_stats(&entry, stats);
         }
+
+        stats.searched += 1;
     }
 
     Ok(())
 }
 
-fn matches_filters(path: &Path, cli: &Cli) -> Result<bool> {
+fn matches_filters(entry: &EntryInfo, cli: &Cli) -> Result<bool> {
+    let path = entry.path();
+
     // Type filter
     if let Some(ref filters) = cli.type_filter {
         let matches_type = filters.iter().any(|&filter| {
             match filter {
-                TypeFilter::File => path.is_file(),
-                TypeFilter::Directory => path.is_dir(),
-                TypeFilter::Symlink => path.is_symlink(),
+                TypeFilter::File => entry.is_file(),
+                TypeFilter::Directory => entry.is_dir(),
+                TypeFilter::Symlink => entry.is_symlink(),
             }
         });
         if !matches_type {
@@ -234,9 +234,8 @@ fn matches_filters(path: &Path, cli: &Cli) -> Result<bool> {
     }
 
     // Size filters (only for files)
-    if path.is_file() {
-        if let Ok(metadata) = fs::metadata(path) {
-            let size = metadata.len();
+    if entry.is_file() {
+        if let Some(size) = entry.size() {
 
             if let Some(min_size) = cli.size_min {
                 if size < min_size {
@@ -257,7 +256,7 @@ fn matches_filters(path: &Path, cli: &Cli) -> Result<bool> {
     {
         use std::os::unix::fs::PermissionsExt;
         if let Some(perm) = cli.perm {
-            if let Ok(metadata) = fs::metadata(path) {
+            if let Some(metadata) = entry.metadata() {
                 let mode = metadata.permissions().mode() & 0o777;
                 if mode as u32 != perm {
                     return Ok(false);
@@ -296,48 +295,40 @@ fn matches_pattern(text: &str, pattern: &str) -> bool {
     }
 }
 
-fn output_match(path: &Path, cli: &Cli) -> Result<()> {
-    let metadata = fs::metadata(path).ok();
-    let file_type = if path.is_file() {
-        "file"
-    } else if path.is_dir() {
-        "directory"
-    } else if path.is_symlink() {
-        "symlink"
-    } else {
-        "unknown"
-    };
-
-    let mut result = serde_json::json!({
-        "type": "match",
-        "path": path.display().to_string(),
-        "file_type": file_type,
-    });
+fn output_match(entry: &EntryInfo, cli: &Cli, git: Option<&mut GitAnnotator>) -> Result<()> {
+    let path = entry.path();
+    let metadata = entry.metadata();
 
+    let mut size = None;
+    let mut modified = None;
+    let mut permissions = None;
     if let Some(meta) = metadata {
-        result["size"] = serde_json::json!(meta.len());
-        if let Ok(modified) = meta.modified() {
-            if let Ok(datetime) = modified.duration_since(SystemTime::UNIX_EPOCH) {
-                result["modified"] = serde_json::json!(datetime.as_secs());
-            }
-        }
+        size = Some(meta.len());
+        modified = meta
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|datetime| datetime.as_secs());
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
             let mode = meta.permissions().mode() & 0o777;
-            result["permissions"] = serde_json::json!(format!("{:03o}", mode));
+            permissions = Some(format!("{:03o}", mode));
         }
     }
-
-    if let Some(name) = path.file_name() {
-        result["name"] = serde_json::json!(name.to_string_lossy().to_string());
-    }
-
-    if let Some(parent) = path.parent() {
-        result["parent"] = serde_json::json!(parent.display().to_string());
-    }
-
-    jsonl::output_result(result)?;
+    let annotation = git.and_then(|git| git.annotate(path));
+
+    jsonl::output_data(RecordData::FindMatch {
+        path: path.display().to_string(),
+        file_type: entry.kind().to_string(),
+        size,
+        modified,
+        permissions,
+        name: path.file_name().map(|name| name.to_string_lossy().to_string()),
+        parent: path.parent().map(|parent| parent.display().to_string()),
+        git_status: annotation.as_ref().map(|annotation| annotation.status),
+        last_commit: annotation.and_then(|annotation| annotation.last_commit),
+    })?;
 
     if cli.verbose {
         // Verbose mode outputs additional info
@@ -349,12 +340,12 @@ fn output_match(path: &Path, cli: &Cli) -> Result<()> {
     Ok(())
 }
 
-fn update_stats(path: &Path, stats: &mut MatchStats) {
-    if path.is_file() {
+fn update_stats(entry: &EntryInfo, stats: &mut MatchStats) {
+    if entry.is_file() {
         stats.files_matched += 1;
-    } else if path.is_dir() {
+    } else if entry.is_dir() {
         stats.dirs_matched += 1;
-    } else if path.is_symlink() {
+    } else if entry.is_symlink() {
         stats.symlinks_matched += 1;
     }
 }
@@ -0,0 +1,131 @@
+use ai_coreutils::fs_utils::{FileLock, LockMode};
+use ai_coreutils::{config, jsonl, AiCoreutilsError, Result};
+use ai_coreutils::policy::{self, Access};
+use clap::Parser;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// AI-optimized flock utility - Run a command while holding a file lock
+///
+/// This utility extends util-linux flock with:
+/// - JSONL lock acquisition and release events
+/// - Timeouts with fractional seconds
+/// - Cross-platform advisory locking (flock on Unix, LockFileEx on Windows)
+#[derive(Parser, Debug)]
+#[command(name = "ai-flock")]
+#[command(about = "Serialize operations by running a command under an advisory file lock", long_about = None)]
+struct Cli {
+    /// Lock file (created if it does not exist)
+    lock_file: PathBuf,
+
+    /// Command and arguments to run while the lock is held
+    #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    command: Vec<String>,
+
+    /// Acquire a shared lock instead of an exclusive one
+    #[arg(short, long, conflicts_with = "exclusive")]
+    shared: bool,
+
+    /// Acquire an exclusive lock (default)
+    #[arg(short = 'x', long)]
+    exclusive: bool,
+
+    /// Fail immediately if the lock is held elsewhere
+    #[arg(short, long, conflicts_with = "timeout")]
+    nonblock: bool,
+
+    /// Give up after waiting this many seconds
+    #[arg(short = 'w', long, value_name = "SECONDS")]
+    timeout: Option<f64>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    policy::enforce([&cli.lock_file], Access::Write)?;
+
+    // -s and -x conflict, so at most one is set; neither means exclusive
+    let mode = if cli.exclusive || !cli.shared { LockMode::Exclusive } else { LockMode::Shared };
+    let lock_path = cli.lock_file.display().to_string();
+
+    let wait_start = Instant::now();
+    let lock = match acquire_lock(&cli, mode) {
+        Ok(lock) => lock,
+        Err((message, code)) => {
+            jsonl::output_error(&message, code, Some(lock_path.as_str()))?;
+            std::process::exit(1);
+        }
+    };
+
+    jsonl::output_info(serde_json::json!({
+        "type": "lock_acquired",
+        "path": lock_path,
+        "mode": mode.as_str(),
+        "wait_ms": wait_start.elapsed().as_millis() as u64,
+    }))?;
+
+    let held_start = Instant::now();
+    let status = Command::new(&cli.command[0])
+        .args(&cli.command[1..])
+        .status();
+
+    let exit_code = match status {
+        Ok(status) => {
+            let code = status.code().unwrap_or(1);
+            jsonl::output_info(serde_json::json!({
+                "type": "command_completed",
+                "command": cli.command,
+                "exit_code": status.code(),
+                "success": status.success(),
+                "duration_ms": held_start.elapsed().as_millis() as u64,
+            }))?;
+            code
+        }
+        Err(e) => {
+            jsonl::output_error(
+                &format!("Failed to run {}: {}", cli.command[0], e),
+                "COMMAND_ERROR",
+                Some(lock_path.as_str()),
+            )?;
+            1
+        }
+    };
+
+    lock.release()?;
+    jsonl::output_info(serde_json::json!({
+        "type": "lock_released",
+        "path": lock_path,
+        "mode": mode.as_str(),
+        "held_ms": held_start.elapsed().as_millis() as u64,
+    }))?;
+
+    std::process::exit(exit_code);
+}
+
+/// Acquire the lock according to the CLI options, mapping failures to an error code
+fn acquire_lock(cli: &Cli, mode: LockMode) -> std::result::Result<FileLock, (String, &'static str)> {
+    if cli.nonblock {
+        return match FileLock::try_acquire(&cli.lock_file, mode) {
+            Ok(Some(lock)) => Ok(lock),
+            Ok(None) => Err(("Lock is held by another process".to_string(), "LOCK_BUSY")),
+            Err(e) => Err((e.to_string(), "LOCK_ERROR")),
+        };
+    }
+
+    // Negative, NaN and too-large timeouts are rejected rather than panicking
+    let timeout = match cli.timeout {
+        Some(secs) => match Duration::try_from_secs_f64(secs) {
+            Ok(timeout) => Some(timeout),
+            Err(_) => return Err((format!("Invalid timeout: {:?}", secs), "INVALID_INPUT")),
+        },
+        None => None,
+    };
+
+    FileLock::acquire(&cli.lock_file, mode, timeout).map_err(|e| match e {
+        AiCoreutilsError::Io(ref io) if io.kind() == std::io::ErrorKind::TimedOut => {
+            (e.to_string(), "LOCK_TIMEOUT")
+        }
+        _ => (e.to_string(), "LOCK_ERROR"),
+    })
+}
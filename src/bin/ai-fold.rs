@@ -0,0 +1,132 @@
+//! AI-optimized fold utility
+//!
+//! Wraps lines longer than a width, at any character or after the last
+//! blank, with GNU `fold` column rules. Each output line is a JSONL record
+//! giving the file, line number and byte range it came from, so edits made
+//! against the wrapped text can be applied to the original.
+
+use ai_coreutils::{
+    async_ops::raw_lines,
+    cli::{LimitArgs, MmapArgs},
+    config, jsonl,
+    text_format::Folder,
+    JsonlRecord, Result, SafeMemoryAccess,
+};
+use ai_coreutils::policy::{self, Access};
+use clap::Parser;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+/// AI-optimized fold: Wrap long lines with a map back to the input
+///
+/// This utility provides:
+/// - Wrapping at a width in columns or bytes
+/// - Word wrapping after the last blank before the width
+/// - Original file, line number and byte range of every output line
+#[derive(Parser, Debug)]
+#[command(name = "ai-fold")]
+#[command(about = "Wrap long lines as JSONL, mapped back to the input", long_about = None)]
+struct Cli {
+    /// Files to wrap ("-" or none for stdin)
+    files: Vec<PathBuf>,
+
+    /// Maximum columns per output line
+    #[arg(short, long, default_value_t = 80, value_parser = clap::value_parser!(u64).range(1..))]
+    width: u64,
+
+    /// Count bytes rather than columns
+    #[arg(short, long)]
+    bytes: bool,
+
+    /// Break after the last blank before the width
+    #[arg(short, long)]
+    spaces: bool,
+
+    /// Only output the summary
+    #[arg(short, long)]
+    quiet: bool,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    mmap: MmapArgs,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    cli.mmap.apply();
+    policy::enforce(&cli.files, Access::Read)?;
+
+    let files = if cli.files.is_empty() {
+        vec![PathBuf::from("-")]
+    } else {
+        cli.files.clone()
+    };
+    let folder = Folder {
+        width: cli.width as usize,
+        count_bytes: cli.bytes,
+        break_spaces: cli.spaces,
+    };
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut lines = 0u64;
+    let mut folded = 0u64;
+    let mut output_lines = 0u64;
+    let mut errors = 0u64;
+
+    for path in &files {
+        let name = path.to_string_lossy();
+        let input = match SafeMemoryAccess::open_or_stdin(path) {
+            Ok(input) => input,
+            Err(e) => {
+                out.flush()?;
+                jsonl::output_error(&e.to_string(), "FOLD_ERROR", Some(&name))?;
+                errors += 1;
+                continue;
+            }
+        };
+
+        for line in raw_lines(input.as_slice()) {
+            let pieces = folder.fold(line.content);
+            lines += 1;
+            folded += u64::from(pieces.len() > 1);
+            for (index, range) in pieces.iter().enumerate() {
+                output_lines += 1;
+                if cli.quiet {
+                    continue;
+                }
+                JsonlRecord::result(serde_json::json!({
+                    "type": "folded_line",
+                    "file": name,
+                    "output_line": output_lines,
+                    "line_number": line.number,
+                    "segment": index + 1,
+                    "offset": line.offset + range.start as u64,
+                    "length": range.len(),
+                    "continued": index + 1 < pieces.len(),
+                    "content": String::from_utf8_lossy(&line.content[range.clone()]),
+                })).write_line(&mut out)?;
+            }
+        }
+    }
+
+    JsonlRecord::summary(serde_json::json!({
+        "type": "fold_summary",
+        "files": files.len(),
+        "lines": lines,
+        "folded": folded,
+        "output_lines": output_lines,
+        "width": cli.width,
+        "errors": errors,
+    })).write_line(&mut out)?;
+    out.flush()?;
+
+    if errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
@@ -2,17 +2,33 @@
 //!
 //! Searches for patterns in files with structured JSONL output.
 //! Supports async concurrent file processing.
+//!
+//! Patterns are regular expressions (`regex` crate syntax) unless `-F` is
+//! given; capture groups are reported with each match.
+//!
+//! With the `archives` feature, `ARCHIVE!/MEMBER` paths are searched like
+//! files, and `--search-archives` searches every member of the zip, tar and
+//! tar.gz archives it meets.
+//!
+//! With the `compression` feature, gzip, zstd and xz files are searched
+//! decompressed, like zgrep, unless `--no-decompress` is given.
 
 use ai_coreutils::{
-    async_ops::{async_grep_file, async_walk_dir, AsyncConfig},
-    jsonl::JsonlRecord,
-    memory::SafeMemoryAccess,
-    Result,
+    async_ops::{async_read_file_with_policy, async_walk_dir_with_policy, raw_lines, AsyncConfig, RetryPolicy},
+    cli::{DecompressArgs, LimitArgs, LogArgs, MapArgs, MmapArgs, OrderArgs},
+    config,
+    fs_utils::{self, CachePolicy, DirWalk, SymlinkPolicy, WalkEvent},
+    grep::{Match, Matcher},
+    jsonl::{self, JsonlRecord, Link, LogLevel},
+    memory::{AccessPattern, SafeMemoryAccess},
+    tuning, Result,
 };
+use ai_coreutils::policy::{self, Access};
+#[cfg(feature = "archives")]
+use ai_coreutils::{archive, limits};
 use clap::Parser;
 use futures::stream::{self, StreamExt};
-use std::path::PathBuf;
-use walkdir::WalkDir;
+use std::path::{Path, PathBuf};
 
 /// AI-optimized grep: Search files with JSONL output
 #[derive(Parser, Debug, Clone)]
@@ -30,13 +46,17 @@ struct Cli {
     #[arg(short, long)]
     recursive: bool,
 
+    /// When to follow symlinks in recursive search: never, command-line or always
+    #[arg(long, value_name = "WHEN", default_value = "always")]
+    follow_symlinks: SymlinkPolicy,
+
     /// Enable async concurrent file processing
     #[arg(short = 'a', long)]
     async_mode: bool,
 
-    /// Maximum concurrent operations in async mode
-    #[arg(short = 'j', long, default_value_t = 10)]
-    max_concurrent: usize,
+    /// Maximum concurrent operations in async mode (default: config, then the machine profile)
+    #[arg(short = 'j', long)]
+    max_concurrent: Option<usize>,
 
     /// Show line numbers
     #[arg(short = 'n', long)]
@@ -55,22 +75,30 @@ struct Cli {
     invert_match: bool,
 
     /// List matching files only
-    #[arg(short = 'l', long)]
+    #[arg(short = 'l', long, conflicts_with = "files_without_match")]
     files_with_matches: bool,
 
     /// List non-matching files only
     #[arg(short = 'L', long)]
     files_without_match: bool,
 
+    /// Stop reading a file after NUM selected lines
+    #[arg(short = 'm', long, value_name = "NUM")]
+    max_count: Option<usize>,
+
+    /// Print nothing; exit 0 as soon as any line is selected
+    #[arg(short = 'q', long, visible_alias = "silent")]
+    quiet: bool,
+
     /// Show only matching part
     #[arg(short = 'o', long)]
     only_matching: bool,
 
     /// Fixed strings (not regex)
-    #[arg(short = 'F', long)]
+    #[arg(short = 'F', long, conflicts_with = "extended_regex")]
     fixed_strings: bool,
 
-    /// Extended regex
+    /// Extended regex (the default syntax; accepted for compatibility)
     #[arg(short = 'E', long)]
     extended_regex: bool,
 
@@ -86,61 +114,210 @@ struct Cli {
     #[arg(short = 'C', long, value_name = "NUM")]
     context: Option<usize>,
 
+    /// Binary-search sorted files for lines starting with PATTERN (a literal prefix)
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "invert_match", "recursive", "async_mode", "extended_regex",
+            "context", "after_context", "before_context",
+        ]
+    )]
+    sorted: bool,
+
+    /// Search members of zip, tar and tar.gz archives as ARCHIVE!/MEMBER (needs the `archives` feature)
+    #[arg(long)]
+    search_archives: bool,
+
+    /// Retry transient I/O errors up to N times
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    retries: u32,
+
+    /// Bypass the page cache (O_DIRECT, or fadvise streaming where unsupported)
+    #[arg(long)]
+    direct_io: bool,
+
     /// Output JSONL (always enabled for AI agents)
     #[arg(long, default_value_t = true)]
     json: bool,
+
+    #[command(flatten)]
+    decompress: DecompressArgs,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    mmap: MmapArgs,
+
+    #[command(flatten)]
+    order: OrderArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
+    #[command(flatten)]
+    map: MapArgs,
+}
+
+/// Overall result, turned into grep's exit status and the `grep_summary`
+#[derive(Debug, Default, Clone, Copy)]
+struct Status {
+    /// Files searched
+    files: u64,
+    /// Files in which some line was selected
+    files_matched: u64,
+    /// Files and directories that could not be searched
+    errors: u64,
+}
+
+impl Status {
+    /// Record one file's result; with `-q` the first selected line ends the run
+    fn update(&mut self, result: Result<bool>, path: &Path, cli: &Cli) -> Result<()> {
+        match result {
+            Ok(found) => {
+                self.files += 1;
+                self.files_matched += u64::from(found);
+            }
+            Err(e) => {
+                self.errors += 1;
+                let error_record = JsonlRecord::error(
+                    format!("Failed to search {}: {}", path.display(), e),
+                    "GREP_ERROR",
+                );
+                error_record.print()?;
+            }
+        }
+        if cli.quiet && self.files_matched > 0 {
+            std::process::exit(0);
+        }
+        Ok(())
+    }
+
+    /// Report an archive that could not be walked; its members are
+    /// counted as they are searched
+    #[cfg(feature = "archives")]
+    fn update_archive(&mut self, result: Result<()>, path: &Path, cli: &Cli) -> Result<()> {
+        match result {
+            Ok(()) => Ok(()),
+            Err(e) => self.update(Err(e), path, cli),
+        }
+    }
+
+    /// Emit the `grep_summary` record
+    fn summary(self) -> Result<()> {
+        jsonl::output_summary(serde_json::json!({
+            "type": "grep_summary",
+            "files_searched": self.files,
+            "files_matched": self.files_matched,
+            "errors": self.errors,
+        }))
+    }
+
+    /// 0 if a line was selected, 1 if none was, 2 on errors (as GNU grep)
+    fn exit_code(self) -> i32 {
+        if self.errors > 0 {
+            2
+        } else if self.files_matched > 0 {
+            0
+        } else {
+            1
+        }
+    }
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    cli.mmap.apply();
+    cli.order.apply();
+    cli.log.apply();
+    cli.decompress.apply();
+    policy::enforce(&cli.paths, Access::Read)?;
+    #[cfg(feature = "remote")]
+    let cli = Cli {
+        paths: ai_coreutils::remote::localize(&cli.paths)?,
+        ..cli
+    };
+    #[cfg(not(feature = "archives"))]
+    if cli.search_archives {
+        jsonl::output_error("--search-archives needs the `archives` feature", "INVALID_INPUT", None)?;
+        std::process::exit(2);
+    }
+
+    let matcher = if cli.fixed_strings || cli.sorted {
+        Matcher::new(&cli.pattern, cli.ignore_case, cli.invert_match)
+    } else {
+        match Matcher::regex(&cli.pattern, cli.ignore_case, cli.invert_match) {
+            Ok(matcher) => matcher,
+            Err(e) => {
+                jsonl::output_error(&e.to_string(), "INVALID_PATTERN", None)?;
+                std::process::exit(2);
+            }
+        }
+    };
 
     // Determine if we should use async mode
+    let quiet = cli.quiet;
     let use_async = cli.async_mode && (cli.recursive || cli.paths.len() > 1);
 
-    if use_async {
+    let status = if use_async {
         let rt = tokio::runtime::Runtime::new()?;
-        rt.block_on(async_main(cli))
+        rt.block_on(async_main(cli, matcher))?
     } else {
-        sync_main(cli)
+        sync_main(cli, matcher)?
+    };
+
+    if !quiet {
+        status.summary()?;
     }
+    jsonl::flush_ordered()?;
+    std::process::exit(status.exit_code());
 }
 
-fn sync_main(cli: Cli) -> Result<()> {
+fn sync_main(cli: Cli, matcher: Matcher) -> Result<Status> {
+    let mut status = Status::default();
+
     for path in &cli.paths {
+        #[cfg(feature = "archives")]
+        if cli.search_archives && archive::is_archive(path) {
+            let result = grep_archive(path, &cli, &matcher, &mut status);
+            status.update_archive(result, path, &cli)?;
+            continue;
+        }
         if path.is_dir() {
             if cli.recursive {
-                if let Err(e) = grep_directory(path, &cli) {
+                if let Err(e) = grep_directory(path, &cli, &matcher, &mut status) {
+                    status.errors += 1;
                     let error_record = JsonlRecord::error(
                         format!("Failed to search directory {}: {}", path.display(), e),
                         "GREP_ERROR",
                     );
-                    println!("{}", error_record.to_jsonl()?);
+                    error_record.print()?;
                 }
             } else {
+                status.errors += 1;
                 let error_record = JsonlRecord::error(
                     format!("{} is a directory (use -r for recursive search)", path.display()),
                     "GREP_ERROR",
                 );
-                println!("{}", error_record.to_jsonl()?);
+                error_record.print()?;
             }
-        } else if let Err(e) = grep_file(path, &cli) {
-            let error_record = JsonlRecord::error(
-                format!("Failed to search {}: {}", path.display(), e),
-                "GREP_ERROR",
-            );
-            println!("{}", error_record.to_jsonl()?);
+        } else {
+            status.update(grep_file(path, &cli, &matcher), path, &cli)?;
         }
     }
 
-    Ok(())
+    Ok(status)
 }
 
-async fn async_main(cli: Cli) -> Result<()> {
+async fn async_main(cli: Cli, matcher: Matcher) -> Result<Status> {
     let config = AsyncConfig {
-        max_concurrent: cli.max_concurrent,
-        buffer_size: 8192,
-        progress: false,
+        retry: RetryPolicy::with_retries(cli.retries),
+        cache_policy: cache_policy(&cli),
+        ..AsyncConfig::tuned(cli.max_concurrent)
     };
+    jsonl::output_info(tuning::record())?;
 
     // Collect all files to search
     let mut all_files = Vec::new();
@@ -148,240 +325,332 @@ async fn async_main(cli: Cli) -> Result<()> {
     for path in &cli.paths {
         if path.is_dir() && cli.recursive {
             // Use async directory walking
-            let dir_files = async_walk_dir(path).await?;
-            all_files.extend(dir_files);
-        } else if path.is_file() {
+            let (dir_files, loops) = async_walk_dir_with_policy(path, cli.follow_symlinks).await?;
+            for symlink_loop in loops {
+                jsonl::output_info(symlink_loop.to_json())?;
+            }
+            all_files.extend(
+                dir_files
+                    .into_iter()
+                    .filter(|f| !config::get().is_ignored_under(path, f)),
+            );
+        } else if path.is_file() || is_member(path) {
             all_files.push(path.clone());
         }
     }
-
-    // Process files concurrently
-    let pattern = cli.pattern.clone();
-    let case_insensitive = cli.ignore_case;
-    let invert_match = cli.invert_match;
-
-    let results = stream::iter(all_files)
+    // Archives are walked member by member, after the files
+    #[cfg(feature = "archives")]
+    let (archives, all_files): (Vec<_>, Vec<_>) = all_files
+        .into_iter()
+        .partition(|path| cli.search_archives && archive::is_archive(path));
+
+    // Read files concurrently; each is searched as soon as it arrives, so
+    // at most `max_concurrent` files are held in memory
+    let mut results = stream::iter(all_files)
         .map(|file| {
-            let pattern = pattern.clone();
+            let config = &config;
             async move {
-                let matches = async_grep_file(&file, &pattern, case_insensitive, invert_match)
-                    .await
-                    .unwrap_or_default();
-                (file, matches)
+                let result = async_read_file_with_policy(&file, &config.retry, config.cache_policy).await;
+                (file, result)
             }
         })
-        .buffer_unordered(config.max_concurrent)
-        .collect::<Vec<_>>()
-        .await;
-
-    // Output results
-    for (path, matches) in results {
-        for m in matches {
-            let record = JsonlRecord::MatchRecord {
-                timestamp: chrono::Utc::now(),
-                file: path.display().to_string(),
-                line_number: m.line_number,
-                line_content: m.line,
-                match_start: 0,
-                match_end: 0,
-            };
-            println!("{}", record.to_jsonl()?);
-        }
+        .buffer_unordered(config.max_concurrent);
+
+    let mut status = Status::default();
+
+    while let Some((path, result)) = results.next().await {
+        let result = result.and_then(|(data, applied)| {
+            if config.cache_policy != CachePolicy::Normal {
+                jsonl::output_info(CachePolicy::report(config.cache_policy, applied, &path))?;
+            }
+            report_compression(&path, &cli, compression(&path), data.len())?;
+            grep_data(&path, &cli, &matcher, &data)
+        });
+        status.update(result, &path, &cli)?;
     }
 
-    Ok(())
+    #[cfg(feature = "archives")]
+    for path in &archives {
+        let result = grep_archive(path, &cli, &matcher, &mut status);
+        status.update_archive(result, path, &cli)?;
+    }
+
+    Ok(status)
+}
+
+/// Whether `path` names an archive member, which has no file of its own
+#[cfg(feature = "archives")]
+fn is_member(path: &Path) -> bool {
+    archive::is_member(path)
+}
+
+#[cfg(not(feature = "archives"))]
+fn is_member(_path: &Path) -> bool {
+    false
+}
+
+/// Search every member of the archive at `path` (and of archives inside
+/// it) as a file of its own
+#[cfg(feature = "archives")]
+fn grep_archive(path: &Path, cli: &Cli, matcher: &Matcher, status: &mut Status) -> Result<()> {
+    archive::walk(path, |member, data| {
+        let result = data.and_then(|data| {
+            limits::read_file(member, data.len() as u64)?;
+            grep_data(member, cli, matcher, &data)
+        });
+        status.update(result, member, cli)
+    })
+}
+
+/// Compression `path` is searched decompressed from: `gzip`, `zstd` or `xz`
+#[cfg(feature = "compression")]
+fn compression(path: &Path) -> Option<&'static str> {
+    ai_coreutils::compress::detect(path).ok().flatten().map(|c| c.as_str())
 }
 
-fn grep_file(path: &PathBuf, cli: &Cli) -> Result<bool> {
+#[cfg(not(feature = "compression"))]
+fn compression(_path: &Path) -> Option<&'static str> {
+    None
+}
+
+/// Emit a `decompressed` record for a file searched decompressed
+fn report_compression(path: &Path, cli: &Cli, compression: Option<&str>, size: usize) -> Result<()> {
+    match compression {
+        Some(compression) if !cli.quiet => jsonl::output_info(serde_json::json!({
+            "type": "decompressed",
+            "file": path.display().to_string(),
+            "compression": compression,
+            "size": size,
+        })),
+        _ => Ok(()),
+    }
+}
+
+/// Page cache policy requested on the command line
+fn cache_policy(cli: &Cli) -> CachePolicy {
+    if cli.direct_io {
+        CachePolicy::Direct
+    } else {
+        CachePolicy::Normal
+    }
+}
+
+/// Search one file, returning whether any line was selected
+fn grep_file(path: &Path, cli: &Cli, matcher: &Matcher) -> Result<bool> {
     // Use memory mapping for efficient searching
-    let mem_access = SafeMemoryAccess::new(path)?;
+    let policy = cache_policy(cli);
+    let options = cli.map.options(AccessPattern::Sequential).cache_policy(policy);
+    let mem_access = RetryPolicy::with_retries(cli.retries).run_blocking(
+        &format!("read {}", path.display()),
+        || SafeMemoryAccess::with_options(path, &options),
+    )?;
+    if policy != CachePolicy::Normal {
+        jsonl::output_info(CachePolicy::report(policy, mem_access.cache_policy(), path))?;
+    }
+    MapArgs::report(&options, &mem_access.map_options(), path)?;
+    report_compression(path, cli, mem_access.compression(), mem_access.size())?;
+
+    // Search the mapped bytes directly; only lines that are output get decoded
+    let found = grep_data(path, cli, matcher, mem_access.as_slice())?;
+    // A file written to during the search may have been read half old
+    if let Err(e) = mem_access.verify() {
+        jsonl::output_warning(LogLevel::Warn, "FILE_CHANGED", &e.to_string(), Some(&path.display().to_string()))?;
+    }
+    Ok(found)
+}
 
-    let content = if let Some(data) = mem_access.get(0, mem_access.size()) {
-        String::from_utf8_lossy(data).to_string()
+/// Search a file's contents, returning whether any line was selected
+fn grep_data(path: &Path, cli: &Cli, matcher: &Matcher, data: &[u8]) -> Result<bool> {
+    // Context needs random access to the lines around each match
+    let lines: Vec<&[u8]> = if context_sizes(cli) != (0, 0) {
+        raw_lines(data).map(|line| line.content).collect()
     } else {
-        return Ok(false);
+        Vec::new()
     };
 
-    let search_pattern = if cli.ignore_case {
-        cli.pattern.to_lowercase()
+    let selected = if cli.sorted {
+        matcher.sorted_matches(data, cli.line_number)
     } else {
-        cli.pattern.clone()
+        matcher.matches(data)
     };
+    report(path, cli, matcher, selected, &lines)
+}
 
-    let mut match_count = 0;
-    let mut has_match = false;
-    let lines: Vec<&str> = content.lines().collect();
-
-    for (line_num, line) in lines.iter().enumerate() {
-        let search_line = if cli.ignore_case {
-            line.to_lowercase()
-        } else {
-            line.to_string()
-        };
+/// Lines of context (before, after) requested with -B/-A/-C
+fn context_sizes(cli: &Cli) -> (usize, usize) {
+    let before = cli.before_context.or(cli.context).unwrap_or(0);
+    let after = cli.after_context.or(cli.context).unwrap_or(0);
+    (before, after)
+}
 
-        let line_matches = search_line.contains(&search_pattern);
-        let should_show = if cli.invert_match {
-            !line_matches
-        } else {
-            line_matches
-        };
+/// Apply the output mode to a file's selected lines
+///
+/// Every mode consumes the same iterator, capped by `-m`, and stops as
+/// soon as its answer is known. `lines` holds the whole file when context
+/// was requested. Returns whether any line was selected.
+fn report<'a>(
+    path: &Path,
+    cli: &Cli,
+    matcher: &Matcher,
+    selected: impl Iterator<Item = Match<'a>>,
+    lines: &[&[u8]],
+) -> Result<bool> {
+    let mut selected = selected.take(cli.max_count.unwrap_or(usize::MAX));
+    let file = path.display().to_string();
+
+    if cli.quiet {
+        return Ok(selected.next().is_some());
+    }
 
-        if should_show && line_matches {
-            match_count += 1;
-            has_match = true;
+    if cli.files_with_matches {
+        let found = selected.next().is_some();
+        if found {
+            let record = JsonlRecord::result(serde_json::json!({
+                "file": file,
+            }));
+            record.print()?;
         }
+        return Ok(found);
+    }
 
-        if should_show {
-            if cli.files_with_matches {
-                // Just mark that we found a match, will output at end
-                if line_matches {
-                    has_match = true;
-                }
-                continue;
-            }
-
-            if cli.files_without_match {
-                continue;
-            }
-
-            if cli.count {
-                continue;
-            }
-
-            // Output the match
-            if line_matches {
-                let match_start = search_line.find(&search_pattern).unwrap_or(0);
-                let match_end = match_start + search_pattern.len();
-
-                if cli.only_matching {
-                    // Output only the matching part
-                    let record = JsonlRecord::MatchRecord {
-                        timestamp: chrono::Utc::now(),
-                        file: path.display().to_string(),
-                        line_number: line_num + 1,
-                        line_content: line[match_start..match_end].to_string(),
-                        match_start: 0,
-                        match_end: match_end - match_start,
-                    };
-                    println!("{}", record.to_jsonl()?);
-                } else {
-                    let output_line = if cli.line_number {
-                        format!("{}:{}", line_num + 1, line)
-                    } else {
-                        line.to_string()
-                    };
-
-                    let record = JsonlRecord::MatchRecord {
-                        timestamp: chrono::Utc::now(),
-                        file: path.display().to_string(),
-                        line_number: if cli.line_number {
-                            line_num + 1
-                        } else {
-                            0
-                        },
-                        line_content: output_line,
-                        match_start,
-                        match_end,
-                    };
-
-                    println!("{}", record.to_jsonl()?);
-
-                    // Handle context
-                    let after = cli.after_context.or(cli.context).unwrap_or(0);
-                    let before = cli.before_context.or(cli.context).unwrap_or(0);
-
-                    // Output context before
-                    if before > 0 && line_num > 0 {
-                        let start = line_num.saturating_sub(before);
-                        for ctx_line in lines[start..line_num].iter() {
-                            let record = JsonlRecord::MatchRecord {
-                                timestamp: chrono::Utc::now(),
-                                file: path.display().to_string(),
-                                line_number: 0,
-                                line_content: ctx_line.to_string(),
-                                match_start: 0,
-                                match_end: 0,
-                            };
-                            println!("{}", record.to_jsonl()?);
-                        }
-                    }
-
-                    // Output context after
-                    if after > 0 && line_num + after < lines.len() {
-                        let end = if line_num + after + 1 < lines.len() {
-                            line_num + after + 1
-                        } else {
-                            lines.len()
-                        };
-                        for ctx_line in lines[line_num + 1..end].iter() {
-                            let record = JsonlRecord::MatchRecord {
-                                timestamp: chrono::Utc::now(),
-                                file: path.display().to_string(),
-                                line_number: 0,
-                                line_content: ctx_line.to_string(),
-                                match_start: 0,
-                                match_end: 0,
-                            };
-                            println!("{}", record.to_jsonl()?);
-                        }
-                    }
-                }
-            } else if cli.invert_match {
-                // Show non-matching lines
-                let record = JsonlRecord::MatchRecord {
-                    timestamp: chrono::Utc::now(),
-                    file: path.display().to_string(),
-                    line_number: line_num + 1,
-                    line_content: line.to_string(),
-                    match_start: 0,
-                    match_end: 0,
-                };
-                println!("{}", record.to_jsonl()?);
-            }
+    if cli.files_without_match {
+        let found = selected.next().is_some();
+        if !found {
+            let record = JsonlRecord::result(serde_json::json!({
+                "file": file,
+                "matches": false,
+            }));
+            record.print()?;
         }
+        return Ok(found);
     }
 
-    // Handle file-listing modes
-    if cli.files_with_matches && has_match {
+    if cli.count {
+        let match_count = selected.count();
         let record = JsonlRecord::result(serde_json::json!({
-            "file": path.display().to_string(),
+            "file": file,
+            "match_count": match_count,
         }));
-        println!("{}", record.to_jsonl()?);
+        record.print()?;
+        return Ok(match_count > 0);
     }
 
-    if cli.files_without_match && !has_match {
-        let record = JsonlRecord::result(serde_json::json!({
-            "file": path.display().to_string(),
-            "matches": false,
-        }));
-        println!("{}", record.to_jsonl()?);
+    let (before, after) = context_sizes(cli);
+    // Last line printed (match or context) and last line owed as trailing context
+    let mut printed = 0;
+    let mut after_until = 0;
+    let mut found = false;
+    // Context lines name the match they surround as their parent
+    let mut last_id = None;
+
+    for m in selected {
+        found = true;
+        let mut link = Link::default();
+
+        if !lines.is_empty() {
+            let id = jsonl::next_record_id();
+            link = Link::id(id);
+            // Trailing context of earlier matches, then leading context of this one
+            let trailing_end = after_until.min(m.line_number - 1);
+            let leading_start = m.line_number.saturating_sub(before).max(trailing_end + 1);
+            for number in printed + 1..=trailing_end {
+                output_context(&file, lines[number - 1], last_id)?;
+            }
+            for number in leading_start.max(printed + 1)..m.line_number {
+                output_context(&file, lines[number - 1], Some(id))?;
+            }
+            printed = m.line_number;
+            after_until = m.line_number + after;
+            last_id = Some(id);
+        }
+
+        output_match(&file, cli, matcher, &m, link)?;
     }
 
-    if cli.count {
-        let record = JsonlRecord::result(serde_json::json!({
-            "file": path.display().to_string(),
-            "match_count": match_count,
-        }));
-        println!("{}", record.to_jsonl()?);
+    for number in printed + 1..=after_until.min(lines.len()) {
+        output_context(&file, lines[number - 1], last_id)?;
     }
 
-    Ok(has_match)
+    Ok(found)
 }
 
-fn grep_directory(dir: &PathBuf, cli: &Cli) -> Result<()> {
-    let walker = WalkDir::new(dir).follow_links(true).into_iter();
+/// Emit a selected line, decoding it for output
+///
+/// `match_start`/`match_end` are byte offsets into the original line.
+fn output_match(file: &str, cli: &Cli, matcher: &Matcher, m: &Match, link: Link) -> Result<()> {
+    let captures = m.span.and_then(|_| matcher.captures(m.line));
+    let record = match m.span {
+        Some((start, end)) if cli.only_matching => JsonlRecord::MatchRecord {
+            timestamp: chrono::Utc::now(),
+            file: file.to_string(),
+            line_number: m.line_number,
+            line_content: String::from_utf8_lossy(&m.line[start..end]).into_owned(),
+            match_start: 0,
+            match_end: end - start,
+            captures,
+        },
+        // Inverted lines have no matching part to show
+        None if cli.only_matching => return Ok(()),
+        span => {
+            let (match_start, match_end) = span.unwrap_or((0, 0));
+            JsonlRecord::MatchRecord {
+                timestamp: chrono::Utc::now(),
+                file: file.to_string(),
+                line_number: if cli.line_number { m.line_number } else { 0 },
+                line_content: if cli.line_number {
+                    format!("{}:{}", m.line_number, String::from_utf8_lossy(m.line))
+                } else {
+                    String::from_utf8_lossy(m.line).into_owned()
+                },
+                match_start,
+                match_end,
+                captures,
+            }
+        }
+    };
+    record.print_linked(link)?;
+    Ok(())
+}
 
-    for entry in walker.filter_map(|e| e.ok()) {
+/// Emit a context line of the match with id `parent`
+fn output_context(file: &str, line: &[u8], parent: Option<u64>) -> Result<()> {
+    let record = JsonlRecord::MatchRecord {
+        timestamp: chrono::Utc::now(),
+        file: file.to_string(),
+        line_number: 0,
+        line_content: String::from_utf8_lossy(line).into_owned(),
+        match_start: 0,
+        match_end: 0,
+        captures: None,
+    };
+    record.print_linked(parent.map(Link::child_of).unwrap_or_default())?;
+    Ok(())
+}
+
+fn grep_directory(dir: &Path, cli: &Cli, matcher: &Matcher, status: &mut Status) -> Result<()> {
+    for event in DirWalk::new(dir, cli.follow_symlinks) {
+        let entry = match event {
+            Ok(WalkEvent::Entry(entry)) => entry,
+            Ok(WalkEvent::Loop(symlink_loop)) => {
+                jsonl::output_info(symlink_loop.to_json())?;
+                continue;
+            }
+            Err(e) => {
+                status.errors += u64::from(policy::report(&e)?);
+                fs_utils::warn_walk_error(&e)?;
+                continue;
+            }
+        };
         let path = entry.path();
 
-        if path.is_file() {
-            if let Err(e) = grep_file(&path.to_path_buf(), cli) {
-                let error_record = JsonlRecord::error(
-                    format!("Failed to search {}: {}", path.display(), e),
-                    "GREP_ERROR",
-                );
-                println!("{}", error_record.to_jsonl()?);
+        if entry.is_file() {
+            #[cfg(feature = "archives")]
+            if cli.search_archives && archive::is_archive(path) {
+                let result = grep_archive(path, cli, matcher, status);
+                status.update_archive(result, path, cli)?;
+                continue;
             }
+            status.update(grep_file(path, cli, matcher), path, cli)?;
         }
     }
 
@@ -0,0 +1,40 @@
+//! AI-Coreutils gRPC server
+//!
+//! Serves Analyze, Grep, Find, Checksum and Classify over gRPC
+//! (`proto/ai_coreutils.proto`), so the utilities can run as a sidecar
+//! service. Built with the `grpc` feature.
+
+use ai_coreutils::{config, grpc, jsonl, Result};
+use clap::Parser;
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+
+/// AI-Coreutils gRPC server: The utilities as a streaming sidecar service
+///
+/// This utility provides:
+/// - Analyze, Grep, Find, Checksum and Classify RPCs
+/// - One streamed response per result, with per-file errors in the stream
+/// - The same sandbox policy as the utilities
+#[derive(Parser, Debug)]
+#[command(name = "ai-grpc")]
+#[command(about = "Serve the utilities over gRPC with streaming responses", long_about = None)]
+struct Cli {
+    /// Address to listen on (port 0 picks a free port)
+    #[arg(short, long, default_value = "127.0.0.1:50051")]
+    listen: SocketAddr,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+
+    let listener = TcpListener::bind(cli.listen).await?;
+    jsonl::output_info(serde_json::json!({
+        "type": "grpc_listening",
+        "address": listener.local_addr()?.to_string(),
+        "service": "ai_coreutils.v1.AiCoreutils",
+    }))?;
+
+    grpc::serve(listener).await
+}
@@ -1,4 +1,10 @@
-use ai_coreutils::{jsonl, memory::SafeMemoryAccess, Result};
+use ai_coreutils::{
+    cli::{LimitArgs, MmapArgs},
+    config, jsonl, limits,
+    memory::{LineIndex, SafeMemoryAccess},
+    Result,
+};
+use ai_coreutils::policy::{self, Access};
 use clap::Parser;
 use std::fs::File;
 use std::io::{self, BufRead, Read, Write};
@@ -37,15 +43,25 @@ struct Cli {
     /// Zero-terminated output
     #[arg(short = 'z', long)]
     zero_terminated: bool,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    mmap: MmapArgs,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    cli.mmap.apply();
+    policy::enforce(&cli.files, Access::Read)?;
 
     // If no files specified, read from stdin
     if cli.files.is_empty() {
-        handle_stdin(&cli)?;
-        return Ok(());
+        let bytes_read = handle_stdin(&cli)?;
+        return output_summary(1, 0, bytes_read);
     }
 
     let use_bytes = cli.bytes.is_some();
@@ -54,6 +70,8 @@ fn main() -> Result<()> {
     // Output start message
     jsonl::output_progress(0, cli.files.len(), "Starting head operation")?;
 
+    let (mut errors, mut total_bytes) = (0, 0);
+
     for (index, file) in cli.files.iter().enumerate() {
         // Update progress
         jsonl::output_progress(
@@ -69,17 +87,27 @@ fn main() -> Result<()> {
             println!("==> {} <==", file.display());
         }
 
-        match head_file(file, count, use_bytes, cli.zero_terminated) {
+        // A fresh ai-index sidecar gives the end of line N without scanning
+        let line_index = if use_bytes || cli.zero_terminated {
+            None
+        } else {
+            LineIndex::load_fresh(file)
+        };
+
+        match head_file(file, count, use_bytes, cli.zero_terminated, line_index.as_ref()) {
             Ok(bytes_read) => {
+                total_bytes += bytes_read;
                 jsonl::output_info(serde_json::json!({
                     "file": file.display().to_string(),
                     "operation": "head",
                     "unit": if use_bytes { "bytes" } else { "lines" },
                     "count": count,
                     "bytes_read": bytes_read,
+                    "line_index": line_index.is_some(),
                 }))?;
             }
             Err(e) => {
+                errors += 1;
                 jsonl::output_error(
                     &format!("Failed to read {}: {}", file.display(), e),
                     "HEAD_ERROR",
@@ -94,10 +122,21 @@ fn main() -> Result<()> {
         }
     }
 
-    Ok(())
+    output_summary(cli.files.len() - errors, errors, total_bytes)
+}
+
+/// Emit the `head_summary` record
+fn output_summary(files: usize, errors: usize, bytes_read: usize) -> Result<()> {
+    jsonl::output_summary(serde_json::json!({
+        "type": "head_summary",
+        "files": files,
+        "errors": errors,
+        "bytes_read": bytes_read,
+    }))
 }
 
-fn handle_stdin(cli: &Cli) -> Result<()> {
+/// Copy the head of stdin to stdout, returning the bytes read
+fn handle_stdin(cli: &Cli) -> Result<usize> {
     let mut stdin = io::stdin();
     let use_bytes = cli.bytes.is_some();
     let count = cli.bytes.unwrap_or(cli.lines);
@@ -107,23 +146,26 @@ fn handle_stdin(cli: &Cli) -> Result<()> {
         let n = stdin.read(&mut buffer)?;
         buffer.truncate(n);
         io::stdout().write_all(&buffer)?;
-    } else {
-        let separator = if cli.zero_terminated { b'\0' } else { b'\n' };
-        let reader = stdin.lock();
-        let mut line_reader = io::BufReader::new(reader);
-        let mut line = Vec::new();
-
-        for _ in 0..count {
-            line.clear();
-            let n = line_reader.read_until(separator, &mut line)?;
-            if n == 0 {
-                break;
-            }
-            io::stdout().write_all(&line)?;
+        return Ok(n);
+    }
+
+    let separator = if cli.zero_terminated { b'\0' } else { b'\n' };
+    let reader = stdin.lock();
+    let mut line_reader = io::BufReader::new(reader);
+    let mut line = Vec::new();
+    let mut bytes_read = 0;
+
+    for _ in 0..count {
+        line.clear();
+        let n = line_reader.read_until(separator, &mut line)?;
+        if n == 0 {
+            break;
         }
+        bytes_read += n;
+        io::stdout().write_all(&line)?;
     }
 
-    Ok(())
+    Ok(bytes_read)
 }
 
 fn head_file(
@@ -131,14 +173,21 @@ fn head_file(
     count: usize,
     use_bytes: bool,
     zero_terminated: bool,
+    index: Option<&LineIndex>,
 ) -> Result<usize> {
     // Try to use memory mapping for files
     if let Ok(mmap) = SafeMemoryAccess::new(file) {
+        if let Some(index) = index {
+            let end = index.line_start(count).min(mmap.size());
+            io::stdout().write_all(mmap.get(0, end).unwrap_or_default())?;
+            return Ok(end);
+        }
         return head_mmap(&mmap, count, use_bytes, zero_terminated);
     }
 
     // Fall back to standard I/O
     let mut f = File::open(file).map_err(ai_coreutils::AiCoreutilsError::Io)?;
+    limits::read_file(file, f.metadata()?.len())?;
 
     if use_bytes {
         let mut buffer = vec![0u8; count.min(1024 * 1024)]; // Max 1MB buffer
@@ -0,0 +1,391 @@
+//! AI-optimized index utility
+//!
+//! Builds two kinds of index:
+//! - `lines`: `.aidx` sidecar files holding the line offsets of a file, so
+//!   ai-head, ai-tail and ai-cat --lines can seek straight to any line of an
+//!   unchanged file instead of scanning it
+//! - `build`/`query`/`stats`: a trigram index of a directory's contents, so
+//!   repeated searches only read the files that can match
+
+use ai_coreutils::{
+    config,
+    grep::{Match, Matcher},
+    index::{self, ContentIndex},
+    cli::{LogArgs, MmapArgs},
+    jsonl::{self, LogLevel},
+    memory::line_index::{IndexStatus, LineIndex},
+    policy::{self, Access},
+    replace, AiCoreutilsError, JsonlRecord, Result, SafeMemoryAccess,
+};
+use clap::{Args, Parser, Subcommand};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// AI-optimized index: Precompute line offsets and content indexes
+#[derive(Parser, Debug)]
+#[command(name = "ai-index")]
+#[command(about = "Build line-offset sidecars and trigram content indexes", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    #[command(flatten)]
+    mmap: MmapArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Build `<file>.aidx` line-offset sidecars for fast line access
+    ///
+    /// Sidecars are ignored once the file's size, mtime or sampled contents
+    /// change.
+    Lines {
+        /// Files to index
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Report whether each index is fresh without building; exit 1 if any is not
+        #[arg(long, conflicts_with_all = ["remove", "force"])]
+        check: bool,
+
+        /// Delete the index of each file
+        #[arg(long, conflicts_with = "force")]
+        remove: bool,
+
+        /// Rebuild indexes that are still fresh
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Build or incrementally update the content index of a directory
+    Build {
+        /// Directory to index
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+
+        /// Discard the existing index and re-read every file
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Search a directory through its content index
+    ///
+    /// Files changed since the last build are searched too, so results
+    /// always reflect the current contents.
+    Query(QueryArgs),
+
+    /// Report the size of a directory's content index
+    Stats {
+        /// Indexed directory
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+    },
+}
+
+#[derive(Args, Debug)]
+struct QueryArgs {
+    /// Regex pattern to search for
+    pattern: String,
+
+    /// Directory to search
+    #[arg(default_value = ".")]
+    dir: PathBuf,
+
+    /// Case insensitive matching
+    #[arg(short, long)]
+    ignore_case: bool,
+
+    /// Treat the pattern as a literal string
+    #[arg(short = 'F', long)]
+    fixed_strings: bool,
+
+    /// Only output the names of files with matches
+    #[arg(short = 'l', long)]
+    files_with_matches: bool,
+
+    /// Stop after N matching lines per file
+    #[arg(short = 'm', long, value_name = "N")]
+    max_count: Option<usize>,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    cli.mmap.apply();
+    match &cli.command {
+        Command::Lines { files, .. } => policy::enforce(files, Access::Read)?,
+        // The content index is stored in the directory it covers
+        Command::Build { dir, .. } => policy::enforce([dir], Access::Write)?,
+        Command::Query(args) => {
+            args.log.apply();
+            policy::enforce([&args.dir], Access::Read)?
+        }
+        Command::Stats { dir } => policy::enforce([dir], Access::Read)?,
+    }
+
+    let ok = match &cli.command {
+        Command::Lines { files, check, remove, force } => lines_main(files, *check, *remove, *force)?,
+        Command::Build { dir, force } => report_failure(dir, build_main(dir, *force))?,
+        Command::Query(args) => report_failure(&args.dir, query_main(args))?,
+        Command::Stats { dir } => report_failure(dir, stats_main(dir))?,
+    };
+
+    if !ok {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Report a failure of a directory command as an error record
+fn report_failure(dir: &Path, result: Result<bool>) -> Result<bool> {
+    result.or_else(|e| {
+        jsonl::output_error(&e.to_string(), "INDEX_ERROR", Some(&dir.to_string_lossy()))?;
+        Ok(false)
+    })
+}
+
+fn lines_main(files: &[PathBuf], check: bool, remove: bool, force: bool) -> Result<bool> {
+    let mut ok = true;
+    for file in files {
+        let result = if check {
+            check_lines(file)
+        } else if remove {
+            remove_lines(file)
+        } else {
+            build_lines(file, force)
+        };
+
+        match result {
+            Ok(fresh) => ok &= fresh,
+            Err(e) => {
+                ok = false;
+                jsonl::output_error(&e.to_string(), "INDEX_ERROR", Some(&file.to_string_lossy()))?;
+            }
+        }
+    }
+    Ok(ok)
+}
+
+/// Build (or keep) the line index of `file`
+fn build_lines(file: &Path, force: bool) -> Result<bool> {
+    let sidecar = LineIndex::sidecar_path(file);
+    if !force && LineIndex::status(file)? == IndexStatus::Fresh {
+        let index = LineIndex::load(&sidecar)?;
+        output_lines(file, &sidecar, "fresh", Some(&index))?;
+        return Ok(true);
+    }
+
+    let index = LineIndex::build_file(file)?;
+    index.save(&sidecar)?;
+    output_lines(file, &sidecar, "built", Some(&index))?;
+    Ok(true)
+}
+
+/// Report whether the line index of `file` is fresh
+fn check_lines(file: &Path) -> Result<bool> {
+    if !file.exists() {
+        return Err(AiCoreutilsError::PathNotFound(file.to_path_buf()));
+    }
+    let sidecar = LineIndex::sidecar_path(file);
+    let status = LineIndex::status(file)?;
+    let index = match status {
+        IndexStatus::Fresh => Some(LineIndex::load(&sidecar)?),
+        _ => None,
+    };
+    output_lines(file, &sidecar, status.as_str(), index.as_ref())?;
+    Ok(status == IndexStatus::Fresh)
+}
+
+/// Delete the line index of `file`, if it has one
+fn remove_lines(file: &Path) -> Result<bool> {
+    let sidecar = LineIndex::sidecar_path(file);
+    let status = match fs::remove_file(&sidecar) {
+        Ok(()) => "removed",
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => "missing",
+        Err(e) => return Err(AiCoreutilsError::Io(e)),
+    };
+    output_lines(file, &sidecar, status, None)?;
+    Ok(true)
+}
+
+fn output_lines(file: &Path, sidecar: &Path, status: &str, index: Option<&LineIndex>) -> Result<()> {
+    let mut record = serde_json::json!({
+        "type": "line_index",
+        "file": file.display().to_string(),
+        "index": sidecar.display().to_string(),
+        "status": status,
+    });
+    if let Some(index) = index {
+        record["lines"] = serde_json::json!(index.line_count());
+        record["size"] = serde_json::json!(index.fingerprint().size);
+        record["index_bytes"] = serde_json::json!(fs::metadata(sidecar).map(|m| m.len()).ok());
+    }
+    jsonl::output_result(record)
+}
+
+/// Update (or with `force`, rebuild) the content index of `dir`
+fn build_main(dir: &Path, force: bool) -> Result<bool> {
+    ensure_dir(dir)?;
+    let started = Instant::now();
+    let existing = if force { None } else { ContentIndex::open(dir)? };
+    let mut content_index = existing.unwrap_or_else(|| ContentIndex::new(dir));
+
+    let update = content_index.update();
+    for (path, e) in &update.errors {
+        jsonl::output_error(&e.to_string(), "INDEX_ERROR", Some(&path.to_string_lossy()))?;
+    }
+    content_index.save()?;
+
+    let stats = content_index.stats();
+    jsonl::output_result(serde_json::json!({
+        "type": "index_built",
+        "root": dir.display().to_string(),
+        "added": update.added,
+        "updated": update.updated,
+        "removed": update.removed,
+        "unchanged": update.unchanged,
+        "files": stats.files,
+        "trigrams": stats.trigrams,
+        "errors": update.errors.len(),
+        "elapsed_ms": started.elapsed().as_millis() as u64,
+    }))?;
+
+    Ok(update.errors.is_empty())
+}
+
+/// Search `dir`, reading only the files the index cannot rule out
+fn query_main(args: &QueryArgs) -> Result<bool> {
+    let dir = &args.dir;
+    ensure_dir(dir)?;
+    let started = Instant::now();
+
+    let matcher = if args.fixed_strings {
+        Matcher::new(&args.pattern, args.ignore_case, false)
+    } else {
+        match Matcher::regex(&args.pattern, args.ignore_case, false) {
+            Ok(matcher) => matcher,
+            Err(e) => {
+                jsonl::output_error(&e.to_string(), "INVALID_PATTERN", None)?;
+                std::process::exit(2);
+            }
+        }
+    };
+
+    // Without an index every file is a candidate, as if all were stale
+    let content_index = ContentIndex::open(dir)?;
+    let indexed = content_index.is_some();
+    let content_index = content_index.unwrap_or_else(|| ContentIndex::new(dir));
+    let literals = index::required_literals(&args.pattern, args.fixed_strings, args.ignore_case);
+    let candidates = content_index.candidates(&literals);
+    for (path, e) in &candidates.errors {
+        jsonl::output_error(&e.to_string(), "INDEX_ERROR", Some(&path.to_string_lossy()))?;
+    }
+
+    let mut files_matched = 0;
+    let mut match_count = 0;
+    let mut binary_skipped = candidates.binary;
+    let mut errors = candidates.errors.len();
+    for path in &candidates.files {
+        let mem = match SafeMemoryAccess::new(path) {
+            Ok(mem) => mem,
+            Err(e) => {
+                errors += 1;
+                jsonl::output_error(&e.to_string(), "INDEX_ERROR", Some(&path.to_string_lossy()))?;
+                continue;
+            }
+        };
+        let data = mem.as_slice();
+        if replace::looks_binary(data) {
+            binary_skipped += 1;
+            jsonl::output_warning(LogLevel::Info, "BINARY_SKIPPED", "Binary file skipped", Some(&path.to_string_lossy()))?;
+            continue;
+        }
+
+        let file = path.display().to_string();
+        let mut matches = matcher.matches(data).take(args.max_count.unwrap_or(usize::MAX)).peekable();
+        if matches.peek().is_none() {
+            continue;
+        }
+        files_matched += 1;
+        if args.files_with_matches {
+            jsonl::output_result(serde_json::json!({ "file": file }))?;
+            continue;
+        }
+        for m in matches {
+            match_count += 1;
+            output_match(&file, &matcher, &m)?;
+        }
+    }
+
+    jsonl::output_summary(serde_json::json!({
+        "type": "search_summary",
+        "root": dir.display().to_string(),
+        "index": indexed,
+        "files_searched": candidates.files.len(),
+        "files_filtered": candidates.filtered,
+        "files_stale": if indexed { candidates.stale } else { 0 },
+        "binary_skipped": binary_skipped,
+        "files_matched": files_matched,
+        "matches": match_count,
+        "errors": errors,
+        "elapsed_ms": started.elapsed().as_millis() as u64,
+    }))?;
+
+    Ok(errors == 0)
+}
+
+fn output_match(file: &str, matcher: &Matcher, m: &Match) -> Result<()> {
+    let (match_start, match_end) = m.span.unwrap_or((0, 0));
+    let record = JsonlRecord::MatchRecord {
+        timestamp: chrono::Utc::now(),
+        file: file.to_string(),
+        line_number: m.line_number,
+        line_content: String::from_utf8_lossy(m.line).into_owned(),
+        match_start,
+        match_end,
+        captures: matcher.captures(m.line),
+    };
+    record.print()?;
+    Ok(())
+}
+
+/// Report the size of the content index of `dir`
+fn stats_main(dir: &Path) -> Result<bool> {
+    ensure_dir(dir)?;
+    let Some(content_index) = ContentIndex::open(dir)? else {
+        return Err(AiCoreutilsError::InvalidInput(
+            "No content index (run `ai-index build` first)".to_string(),
+        ));
+    };
+
+    let stats = content_index.stats();
+    let index_path = ContentIndex::index_path(dir);
+    jsonl::output_result(serde_json::json!({
+        "type": "index_stats",
+        "root": dir.display().to_string(),
+        "index": index_path.display().to_string(),
+        "files": stats.files,
+        "indexed_files": stats.indexed_files,
+        "binary_files": stats.binary_files,
+        "large_files": stats.large_files,
+        "trigrams": stats.trigrams,
+        "postings": stats.postings,
+        "index_bytes": fs::metadata(&index_path).map(|m| m.len()).ok(),
+    }))?;
+    Ok(true)
+}
+
+fn ensure_dir(dir: &Path) -> Result<()> {
+    if !dir.exists() {
+        return Err(AiCoreutilsError::PathNotFound(dir.to_path_buf()));
+    }
+    if !dir.is_dir() {
+        return Err(AiCoreutilsError::InvalidInput("Not a directory".to_string()));
+    }
+    Ok(())
+}
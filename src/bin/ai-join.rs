@@ -0,0 +1,207 @@
+//! AI-optimized join utility
+//!
+//! Joins the lines of two files sorted on a key field, as `join` does,
+//! streaming both and holding only the lines that share the current key.
+//! Each output line is a JSONL record with the key, the fields of each
+//! side and where the lines are in their files; unpaired lines say which
+//! file they came from.
+
+use ai_coreutils::{
+    cli::{LimitArgs, LogArgs},
+    config,
+    jsonl::{self, LogLevel},
+    set_ops::{self, JoinEvent, JoinSpec, Line, LineStream, Merger, OrderCheck, Side},
+    JsonlRecord, Result,
+};
+use ai_coreutils::policy::{self, Access};
+use clap::Parser;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+/// AI-optimized join: Relational join of two sorted files as JSONL
+///
+/// This utility provides:
+/// - Streaming merge join on a key field with SIMD key comparison
+/// - The fields of each side of every joined line
+/// - Unpaired lines from either file, labelled with their side
+/// - Line number and byte offset of each line in its file
+#[derive(Parser, Debug)]
+#[command(name = "ai-join")]
+#[command(about = "Join lines of two sorted files on a key field as JSONL", long_about = None)]
+struct Cli {
+    /// First file, sorted on its join field ("-" for stdin)
+    file1: PathBuf,
+
+    /// Second file, sorted on its join field ("-" for stdin)
+    file2: PathBuf,
+
+    /// Join on this field of the first file
+    #[arg(short = '1', value_name = "FIELD", default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    field1: u64,
+
+    /// Join on this field of the second file
+    #[arg(short = '2', value_name = "FIELD", default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    field2: u64,
+
+    /// Join on this field of both files
+    #[arg(short = 'j', value_name = "FIELD", conflicts_with_all = ["field1", "field2"], value_parser = clap::value_parser!(u64).range(1..))]
+    field: Option<u64>,
+
+    /// Field separator (default: runs of blanks)
+    #[arg(short = 't', value_name = "CHAR", value_parser = parse_separator)]
+    separator: Option<u8>,
+
+    /// Also output unpaired lines from file 1 or 2
+    #[arg(short = 'a', value_name = "FILENUM", value_parser = clap::value_parser!(u8).range(1..=2))]
+    unpaired: Vec<u8>,
+
+    /// Output only unpaired lines from file 1 or 2
+    #[arg(short = 'v', value_name = "FILENUM", value_parser = clap::value_parser!(u8).range(1..=2))]
+    only_unpaired: Vec<u8>,
+
+    /// Ignore case when comparing keys
+    #[arg(short, long)]
+    ignore_case: bool,
+
+    /// Treat the first line of each file as a header
+    #[arg(long)]
+    header: bool,
+
+    /// Fail if an input is not sorted on its join field
+    #[arg(long, conflicts_with = "nocheck_order")]
+    check_order: bool,
+
+    /// Do not check that the inputs are sorted
+    #[arg(long)]
+    nocheck_order: bool,
+
+    /// Lines end with NUL, not newline
+    #[arg(short, long)]
+    zero_terminated: bool,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+fn parse_separator(s: &str) -> std::result::Result<u8, String> {
+    match s.as_bytes() {
+        [byte] => Ok(*byte),
+        _ if s == "\\t" => Ok(b'\t'),
+        _ => Err(format!("separator must be a single byte: {}", s)),
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    cli.log.apply();
+    let paths = [cli.file1.clone(), cli.file2.clone()];
+    policy::enforce(&paths, Access::Read)?;
+
+    let names = paths.clone().map(|path| path.to_string_lossy().into_owned());
+    let separator = if cli.zero_terminated { b'\0' } else { b'\n' };
+    let mut left = match set_ops::open_input(&paths[0]) {
+        Ok(reader) => LineStream::new(reader, separator),
+        Err(e) => jsonl::fail(&e.to_string(), "JOIN_ERROR", Some(&names[0])),
+    };
+    let mut right = match set_ops::open_input(&paths[1]) {
+        Ok(reader) => LineStream::new(reader, separator),
+        Err(e) => jsonl::fail(&e.to_string(), "JOIN_ERROR", Some(&names[1])),
+    };
+    let [field1, field2] = cli.field.map_or([cli.field1, cli.field2], |field| [field, field]);
+    let spec = JoinSpec {
+        fields: [field1 as usize, field2 as usize],
+        separator: cli.separator,
+        header: cli.header,
+    };
+    let merger = Merger::new().ignore_case(cli.ignore_case).order_check(if cli.check_order {
+        OrderCheck::Fail
+    } else if cli.nocheck_order {
+        OrderCheck::Off
+    } else {
+        OrderCheck::Report
+    });
+    // -v replaces the joined lines with the unpaired ones; -a adds them
+    let show_paired = cli.only_unpaired.is_empty();
+    let show_unpaired = |side: Side| {
+        let number = if side == Side::Left { 1 } else { 2 };
+        cli.unpaired.contains(&number) || cli.only_unpaired.contains(&number)
+    };
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut output = 0u64;
+    let result = merger.join(&spec, &mut left, &mut right, |event| {
+        let (kind, l, r) = match event {
+            JoinEvent::Header(l, r) => ("join_header", l, r),
+            JoinEvent::Paired(l, r) if show_paired => ("joined", Some(l), Some(r)),
+            JoinEvent::Unpaired(Side::Left, line) if show_unpaired(Side::Left) => ("unpaired", Some(line), None),
+            JoinEvent::Unpaired(Side::Right, line) if show_unpaired(Side::Right) => ("unpaired", None, Some(line)),
+            _ => return Ok(()),
+        };
+        let key = match (l, r) {
+            (Some(line), _) => spec.key(0, &line.content),
+            (None, Some(line)) => spec.key(1, &line.content),
+            (None, None) => &[],
+        };
+        let content = spec.output_line(l.map(|line| &line.content[..]), r.map(|line| &line.content[..]));
+        let mut record = serde_json::json!({
+            "type": kind,
+            "key": String::from_utf8_lossy(key),
+            "content": String::from_utf8_lossy(&content),
+            "file1": l.map(|line| side_record(&spec, line)),
+            "file2": r.map(|line| side_record(&spec, line)),
+        });
+        if kind == "unpaired" {
+            let side = if l.is_some() { Side::Left } else { Side::Right };
+            record["side"] = side.as_str().into();
+        }
+        output += 1;
+        JsonlRecord::result(record).write_line(&mut out)
+    });
+    out.flush()?;
+    let stats = match result {
+        Ok(stats) => stats,
+        Err(e) => jsonl::fail(&e.to_string(), "JOIN_ERROR", None),
+    };
+
+    for (name, line) in names.iter().zip(stats.unsorted) {
+        if let Some(line) = line {
+            let message = format!("Input is not sorted on its join field at line {}; results may be incomplete", line);
+            jsonl::output_warning(LogLevel::Warn, "UNSORTED_INPUT", &message, Some(name))?;
+        }
+    }
+
+    JsonlRecord::summary(serde_json::json!({
+        "type": "join_summary",
+        "file1": names[0],
+        "file2": names[1],
+        "fields": [field1, field2],
+        "paired": stats.paired,
+        "unpaired_file1": stats.unpaired[0],
+        "unpaired_file2": stats.unpaired[1],
+        "output": output,
+        "sorted": stats.unsorted.iter().all(Option::is_none),
+    })).write_line(&mut out)?;
+    out.flush()?;
+
+    // As GNU join does, unsorted input fails the run once it is complete
+    if stats.unsorted.iter().any(Option::is_some) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// One side of an output record: where the line is, and its fields
+fn side_record(spec: &JoinSpec, line: &Line) -> serde_json::Value {
+    let fields: Vec<_> = spec.split(&line.content).into_iter().map(String::from_utf8_lossy).collect();
+    serde_json::json!({
+        "line_number": line.number,
+        "offset": line.offset,
+        "fields": fields,
+    })
+}
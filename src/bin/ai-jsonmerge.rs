@@ -0,0 +1,177 @@
+//! AI-optimized JSONL merge utility
+//!
+//! Combines the JSONL output of earlier tool runs: merges several files,
+//! sorts the records by timestamp, filters them by type and time, and
+//! counts them by any field, so reports across many invocations need no
+//! custom scripts.
+
+use ai_coreutils::{
+    cli::LimitArgs,
+    config, jsonl,
+    jsonl_merge::{self, Counter, Filter, SourcedRecord},
+    limits::{self, Emit},
+    JsonlRecord, Result,
+};
+use ai_coreutils::policy::{self, Access};
+use chrono::{DateTime, Utc};
+use clap::Parser;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+/// AI-optimized JSONL merge: Combine, filter and aggregate tool output
+///
+/// This utility provides:
+/// - Merging of many JSONL files, optionally sorted by timestamp
+/// - Filtering by record type (`type` or `data.type`) and time range
+/// - Counts of records by any field, instead of the records themselves
+#[derive(Parser, Debug)]
+#[command(name = "ai-jsonmerge")]
+#[command(about = "Merge, sort, filter and count JSONL records from many files", long_about = None)]
+struct Cli {
+    /// JSONL files to merge ("-" for stdin)
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// Sort the merged records by timestamp (records without one go last)
+    #[arg(short, long)]
+    sort: bool,
+
+    /// Keep only records whose type or data.type is listed (comma-separated)
+    #[arg(short, long = "type", value_name = "TYPES", value_delimiter = ',')]
+    types: Vec<String>,
+
+    /// Keep only records at or after this RFC 3339 time
+    #[arg(long, value_name = "TIME")]
+    since: Option<DateTime<Utc>>,
+
+    /// Keep only records before this RFC 3339 time
+    #[arg(long, value_name = "TIME")]
+    until: Option<DateTime<Utc>>,
+
+    /// Add the source file and line to each record
+    #[arg(long)]
+    source: bool,
+
+    /// Output counts of records by the value at this dot-separated path
+    /// (repeatable) instead of the records
+    #[arg(short, long, value_name = "PATH")]
+    count_by: Vec<String>,
+
+    /// Finish with a summary record
+    #[arg(long)]
+    summary: bool,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    policy::enforce(&cli.files, Access::Read)?;
+
+    let filter = Filter {
+        types: cli.types.clone(),
+        since: cli.since,
+        until: cli.until,
+    };
+
+    let mut records = Vec::new();
+    let (mut read, mut invalid_lines, mut errors) = (0u64, 0u64, 0u64);
+    for (source, path) in cli.files.iter().enumerate() {
+        let outcome = if path.to_str() == Some("-") {
+            jsonl_merge::read_records(io::stdin().lock(), source)
+        } else {
+            File::open(path).and_then(|file| {
+                limits::read_file(path, file.metadata()?.len()).map_err(io::Error::other)?;
+                jsonl_merge::read_records(BufReader::new(file), source)
+            })
+        };
+        let outcome = match outcome {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                jsonl::output_error(&e.to_string(), "JSONMERGE_ERROR", Some(&path.to_string_lossy()))?;
+                errors += 1;
+                continue;
+            }
+        };
+
+        if let Some(line) = outcome.first_invalid_line {
+            let message = format!("{} invalid JSON lines skipped (first on line {})", outcome.invalid_lines, line);
+            jsonl::output_error(&message, "INVALID_JSONL", Some(&path.to_string_lossy()))?;
+        }
+        read += outcome.records.len() as u64;
+        invalid_lines += outcome.invalid_lines;
+        records.extend(outcome.records.into_iter().filter(|r| filter.matches(r)));
+    }
+
+    if cli.sort {
+        jsonl_merge::sort_by_timestamp(&mut records);
+    }
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    if cli.count_by.is_empty() {
+        for record in records.iter_mut() {
+            write_merged(&mut out, &cli, record)?;
+        }
+    } else {
+        let mut counter = Counter::new(cli.count_by.clone());
+        for record in &records {
+            counter.add(&record.value);
+        }
+        for (field, value, count) in counter.results() {
+            write_record(&mut out, JsonlRecord::result(serde_json::json!({
+                "type": "jsonmerge_count",
+                "field": field,
+                "value": value,
+                "count": count,
+            })))?;
+        }
+    }
+
+    if cli.summary || !cli.count_by.is_empty() {
+        write_record(&mut out, JsonlRecord::summary(serde_json::json!({
+            "type": "jsonmerge_summary",
+            "files": cli.files.len(),
+            "records_read": read,
+            "records_matched": records.len(),
+            "invalid_lines": invalid_lines,
+            "errors": errors,
+            "sorted": cli.sort,
+        })))?;
+    }
+    out.flush()?;
+
+    if errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Write a merged record as it was read, plus its source if asked
+fn write_merged(out: &mut impl Write, cli: &Cli, record: &mut SourcedRecord) -> Result<()> {
+    if cli.source {
+        if let Some(fields) = record.value.as_object_mut() {
+            let file = cli.files[record.source].to_string_lossy().into_owned();
+            fields.insert("source".into(), serde_json::json!({ "file": file, "line": record.line }));
+        }
+    }
+    match limits::record()? {
+        Emit::Write => {}
+        Emit::Replace(line) => {
+            writeln!(out, "{}", line)?;
+            return Ok(());
+        }
+        Emit::Skip => return Ok(()),
+    }
+    serde_json::to_writer(&mut *out, &record.value)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+fn write_record(out: &mut impl Write, record: JsonlRecord) -> Result<()> {
+    record.write_line(&mut *out)
+}
@@ -0,0 +1,142 @@
+//! AI-optimized log parsing utility
+//!
+//! Turns raw logs (syslog, nginx/Apache access logs, JSON logs, Python,
+//! Java, Rust and Node stack traces, generic application logs) into typed
+//! JSONL entries with a normalized timestamp, level and message, so agents
+//! can filter and sort them without writing a parser per format.
+
+use ai_coreutils::{
+    cli::{LimitArgs, MmapArgs},
+    config, jsonl,
+    log_parse::{Level, LogFormat, LogParser},
+    JsonlRecord, Result, SafeMemoryAccess,
+};
+use ai_coreutils::policy::{self, Access};
+use clap::Parser;
+use std::collections::BTreeMap;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+/// AI-optimized log parser: Raw logs to typed JSONL records
+///
+/// This utility provides:
+/// - Built-in parsers for syslog, access logs, JSON logs and tracebacks
+/// - Per-line format detection for mixed logs
+/// - Level filtering and entity extraction (URLs, IPs, emails, UUIDs, paths)
+#[derive(Parser, Debug)]
+#[command(name = "ai-logparse")]
+#[command(about = "Parse raw logs into typed JSONL entries", long_about = None)]
+struct Cli {
+    /// Log files to parse ("-" for stdin)
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// Log format: auto, syslog, combined, json, traceback or generic
+    #[arg(short, long, default_value = "auto")]
+    format: LogFormat,
+
+    /// Only output entries at or above this level (trace, debug, info,
+    /// notice, warning, error, critical)
+    #[arg(short, long, value_parser = parse_level)]
+    level: Option<Level>,
+
+    /// Extract URLs, IPs, emails, UUIDs and paths from messages
+    #[arg(short, long)]
+    entities: bool,
+
+    /// Skip lines that no format recognizes
+    #[arg(long)]
+    skip_unparsed: bool,
+
+    /// Only output the summary
+    #[arg(short, long)]
+    quiet: bool,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    mmap: MmapArgs,
+}
+
+fn parse_level(s: &str) -> std::result::Result<Level, String> {
+    Level::parse(s).ok_or_else(|| format!("Unknown level: {}", s))
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    entries: u64,
+    output: u64,
+    unparsed: u64,
+    errors: u64,
+    by_format: BTreeMap<&'static str, u64>,
+    by_level: BTreeMap<&'static str, u64>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    cli.mmap.apply();
+    policy::enforce(&cli.files, Access::Read)?;
+
+    let parser = LogParser::new(cli.format)?.with_entities(cli.entities)?;
+    let mut stats = Stats::default();
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+
+    for path in &cli.files {
+        let name = path.to_string_lossy();
+        let data = match SafeMemoryAccess::open_or_stdin(path) {
+            Ok(data) => data,
+            Err(e) => {
+                out.flush()?;
+                jsonl::output_error(&e.to_string(), "LOGPARSE_ERROR", Some(&name))?;
+                stats.errors += 1;
+                continue;
+            }
+        };
+
+        for entry in parser.entries(data.as_slice()) {
+            stats.entries += 1;
+            let format = entry.format.map_or("unknown", |f| f.as_str());
+            *stats.by_format.entry(format).or_insert(0) += 1;
+            *stats.by_level.entry(entry.level.map_or("none", |l| l.as_str())).or_insert(0) += 1;
+            if entry.format.is_none() {
+                stats.unparsed += 1;
+                if cli.skip_unparsed {
+                    continue;
+                }
+            }
+            if cli.level.is_some_and(|min| entry.level.is_none_or(|level| level < min)) {
+                continue;
+            }
+
+            stats.output += 1;
+            if !cli.quiet {
+                let mut data = entry.to_json();
+                data["file"] = name.as_ref().into();
+                JsonlRecord::result(data).write_line(&mut out)?;
+            }
+        }
+    }
+
+    JsonlRecord::summary(serde_json::json!({
+        "type": "logparse_summary",
+        "files": cli.files.len(),
+        "format": cli.format.as_str(),
+        "entries": stats.entries,
+        "output": stats.output,
+        "unparsed": stats.unparsed,
+        "errors": stats.errors,
+        "by_format": stats.by_format,
+        "by_level": stats.by_level,
+    })).write_line(&mut out)?;
+    out.flush()?;
+
+    if stats.errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
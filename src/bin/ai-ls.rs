@@ -2,10 +2,23 @@
 //!
 //! Lists directory contents with structured JSONL output.
 
-use ai_coreutils::{jsonl::JsonlRecord, Result};
+use ai_coreutils::git_info::{GitAnnotation, GitAnnotator};
+use ai_coreutils::{
+    cli::{LimitArgs, LogArgs},
+    config, fs_utils,
+    jsonl::{self, JsonlRecord},
+    Result,
+};
+use ai_coreutils::policy::{self, Access};
 use chrono::{DateTime, Utc};
 use clap::Parser;
 use std::path::PathBuf;
+#[cfg(feature = "sftp")]
+use ai_coreutils::jsonl::LogLevel;
+#[cfg(feature = "sftp")]
+use ai_coreutils::remote::ssh::{self, SftpUrl, Stat};
+#[cfg(feature = "sftp")]
+use std::path::Path;
 
 /// AI-optimized ls: List directory contents with JSONL output
 #[derive(Parser, Debug)]
@@ -44,9 +57,19 @@ struct Cli {
     #[arg(short, long)]
     reverse: bool,
 
+    /// Annotate entries with git status and last commit (needs the `git` feature)
+    #[arg(long)]
+    git_status: bool,
+
     /// Output JSONL (always enabled for AI agents)
     #[arg(long, default_value_t = true)]
     json: bool,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
 }
 
 #[derive(Debug, Clone)]
@@ -59,6 +82,7 @@ struct FileInfo {
     is_symlink: bool,
     is_hidden: bool,
     permissions: String,
+    git: Option<GitAnnotation>,
 }
 
 impl FileInfo {
@@ -74,10 +98,7 @@ impl FileInfo {
         #[cfg(unix)]
         let permissions = {
             use std::os::unix::fs::PermissionsExt;
-            metadata.permissions()
-                .mode()
-                .map(|m| format!("{:o}", m & 0o777))
-                .unwrap_or_else(|_| "??????????".to_string())
+            format!("{:o}", metadata.permissions().mode() & 0o777)
         };
         #[cfg(not(unix))]
         let permissions = "??????????".to_string();
@@ -100,9 +121,29 @@ impl FileInfo {
             is_symlink: metadata.is_symlink(),
             is_hidden,
             permissions,
+            git: None,
         })
     }
 
+    #[cfg(feature = "sftp")]
+    fn from_stat(url: &SftpUrl, stat: &Stat) -> Self {
+        let name = url.path.rsplit('/').find(|s| !s.is_empty()).unwrap_or(&url.host).to_string();
+        Self {
+            path: PathBuf::from(url.to_string()),
+            is_hidden: name.starts_with('.'),
+            name,
+            size: stat.size,
+            modified: stat.modified.unwrap_or_else(Utc::now),
+            is_dir: stat.is_dir,
+            is_symlink: stat.is_symlink,
+            permissions: stat
+                .permissions
+                .map(|mode| format!("{:o}", mode & 0o777))
+                .unwrap_or_else(|| "??????????".to_string()),
+            git: None,
+        }
+    }
+
     fn to_jsonl_record(&self, show_long: bool, human_readable: bool) -> JsonlRecord {
         let size_str = if human_readable {
             format_size(self.size)
@@ -113,7 +154,7 @@ impl FileInfo {
         let path_str = self.path.display().to_string();
 
         if show_long {
-            JsonlRecord::result(serde_json::json!({
+            let mut data = serde_json::json!({
                 "type": "file",
                 "timestamp": Utc::now(),
                 "path": path_str,
@@ -125,7 +166,14 @@ impl FileInfo {
                 "is_symlink": self.is_symlink,
                 "is_hidden": self.is_hidden,
                 "permissions": self.permissions,
-            }))
+            });
+            if let Some(git) = &self.git {
+                data["git_status"] = serde_json::json!(git.status);
+                if let Some(commit) = &git.last_commit {
+                    data["last_commit"] = serde_json::json!(commit);
+                }
+            }
+            JsonlRecord::result(data)
         } else {
             JsonlRecord::FileEntry {
                 timestamp: Utc::now(),
@@ -135,6 +183,8 @@ impl FileInfo {
                 is_dir: self.is_dir,
                 is_symlink: self.is_symlink,
                 permissions: self.permissions.clone(),
+                git_status: self.git.as_ref().map(|git| git.status),
+                last_commit: self.git.as_ref().and_then(|git| git.last_commit.clone()),
             }
         }
     }
@@ -161,21 +211,53 @@ fn format_size(size: u64) -> String {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    cli.log.apply();
+    policy::enforce(&cli.paths, Access::Read)?;
+
+    let mut git = if cli.git_status {
+        match GitAnnotator::new() {
+            Ok(git) => Some(git),
+            Err(e) => {
+                JsonlRecord::error(e.to_string(), "GIT_ERROR").print()?;
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
 
+    let (mut entries, mut errors) = (0, 0);
     for path in &cli.paths {
-        if let Err(e) = list_path(path, &cli) {
-            let error_record = JsonlRecord::error(
-                format!("Failed to list {}: {}", path.display(), e),
-                "LS_ERROR"
-            );
-            println!("{}", error_record.to_jsonl()?);
+        match list_path(path, &cli, git.as_mut()) {
+            Ok(listed) => entries += listed,
+            Err(e) => {
+                errors += 1;
+                let error_record = JsonlRecord::error(
+                    format!("Failed to list {}: {}", path.display(), e),
+                    "LS_ERROR"
+                );
+                error_record.print()?;
+            }
         }
     }
 
-    Ok(())
+    jsonl::output_summary(serde_json::json!({
+        "type": "ls_summary",
+        "paths": cli.paths.len(),
+        "entries": entries,
+        "errors": errors,
+    }))
 }
 
-fn list_path(path: &PathBuf, cli: &Cli) -> Result<()> {
+/// List one command-line path, returning the number of entries output
+fn list_path(path: &PathBuf, cli: &Cli, mut git: Option<&mut GitAnnotator>) -> Result<usize> {
+    #[cfg(feature = "sftp")]
+    if ssh::is_sftp(path) {
+        return list_remote(path, cli);
+    }
+
     let mut entries = Vec::new();
 
     // Build walkdir iterator
@@ -193,11 +275,11 @@ fn list_path(path: &PathBuf, cli: &Cli) -> Result<()> {
             is_symlink: metadata.is_symlink(),
             is_hidden: false,
             permissions: "??????????".to_string(),
+            git: git.and_then(|git| git.annotate(path)),
         };
         entries.push(file_info);
 
-        output_entries(&entries, cli)?;
-        return Ok(());
+        return output_entries(&entries, cli);
     };
 
     // Configure walker
@@ -207,11 +289,35 @@ fn list_path(path: &PathBuf, cli: &Cli) -> Result<()> {
         walker = walker.max_depth(1);
     }
 
-    // Collect entries
-    let result = walker.into_iter().collect::<Vec<_>>();
+    // Collect entries, skipping paths matched by configured ignore rules and
+    // those the sandbox policy denies
+    let mut denied = Vec::new();
+    let result = walker
+        .into_iter()
+        .filter_entry(|e| {
+            if e.depth() == 0 {
+                return true;
+            }
+            if config::get().is_ignored(e.path()) {
+                return false;
+            }
+            policy::check(e.path(), Access::Read)
+                .map_err(|err| denied.push(err))
+                .is_ok()
+        })
+        .collect::<Vec<_>>();
+    for err in &denied {
+        policy::report(err)?;
+    }
 
     for entry in result {
-        let entry = entry?;
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                fs_utils::warn_walk_error(&e.into())?;
+                continue;
+            }
+        };
 
         // Skip hidden files unless --all is specified
         let file_name = entry.file_name().to_string_lossy();
@@ -221,7 +327,13 @@ fn list_path(path: &PathBuf, cli: &Cli) -> Result<()> {
 
         match FileInfo::from_entry(&entry) {
             Ok(info) => entries.push(info),
-            Err(_) => continue, // Skip entries we can't read
+            Err(e) => fs_utils::warn_walk_error(&e)?,
+        }
+    }
+
+    if let Some(git) = git.as_mut() {
+        for entry in &mut entries {
+            entry.git = git.annotate(&entry.path);
         }
     }
 
@@ -229,9 +341,45 @@ fn list_path(path: &PathBuf, cli: &Cli) -> Result<()> {
     sort_entries(&mut entries, cli);
 
     // Output entries
-    output_entries(&entries, cli)?;
+    output_entries(&entries, cli)
+}
 
-    Ok(())
+/// List an `sftp://` URL; git annotations do not apply to remote files
+#[cfg(feature = "sftp")]
+fn list_remote(path: &Path, cli: &Cli) -> Result<usize> {
+    let pool = ssh::pool()?;
+    let url = SftpUrl::parse(&path.to_string_lossy())?;
+    let stat = pool.stat(&url)?;
+    let mut entries = vec![FileInfo::from_stat(&url, &stat)];
+
+    let mut pending = if stat.is_dir { vec![url.clone()] } else { Vec::new() };
+    while let Some(dir) = pending.pop() {
+        let children = match pool.read_dir(&dir) {
+            Ok(children) => children,
+            // The top-level directory fails the listing; deeper ones warn
+            Err(e) if dir != url => {
+                jsonl::output_warning(LogLevel::Warn, "UNREADABLE_ENTRY", &e.to_string(), Some(&dir.to_string()))?;
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        for (name, stat) in children {
+            if !cli.all && name.starts_with('.') {
+                continue;
+            }
+            let child = dir.join(&name);
+            if config::get().is_ignored(Path::new(&child.path)) {
+                continue;
+            }
+            if cli.recursive && stat.is_dir && !stat.is_symlink {
+                pending.push(child.clone());
+            }
+            entries.push(FileInfo::from_stat(&child, &stat));
+        }
+    }
+
+    sort_entries(&mut entries, cli);
+    output_entries(&entries, cli)
 }
 
 fn sort_entries(entries: &mut Vec<FileInfo>, cli: &Cli) {
@@ -265,10 +413,10 @@ fn sort_entries(entries: &mut Vec<FileInfo>, cli: &Cli) {
     });
 }
 
-fn output_entries(entries: &[FileInfo], cli: &Cli) -> Result<()> {
+fn output_entries(entries: &[FileInfo], cli: &Cli) -> Result<usize> {
     for entry in entries {
         let record = entry.to_jsonl_record(cli.long, cli.human_readable);
-        println!("{}", record.to_jsonl()?);
+        record.print()?;
     }
-    Ok(())
+    Ok(entries.len())
 }
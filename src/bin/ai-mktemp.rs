@@ -0,0 +1,150 @@
+//! AI-optimized mktemp utility
+//!
+//! Creates a uniquely named temporary file or directory from a `mktemp`
+//! template, readable only by its owner, and records it in the scratch
+//! registry with the agent session that asked for it and an optional time
+//! to live, so ai-cleanup can remove it later without the path having to
+//! be remembered. Inside a session, the temporary directory is the
+//! session's own, which ai-session --end removes as a whole.
+
+use ai_coreutils::{
+    cli::{parse_duration, parse_session_id, LogArgs},
+    config,
+    jsonl::{self, LogLevel},
+    scratch::{Allocation, Registry, ScratchKind, Template, DEFAULT_TEMPLATE},
+    session::{self, Session},
+    JsonlRecord, Result,
+};
+use ai_coreutils::policy::{self, Access};
+use clap::Parser;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// AI-optimized mktemp: Registered temporary files and directories
+///
+/// This utility provides:
+/// - `mktemp` templates, suffixes and temporary directory rules
+/// - Files and directories created with owner-only permissions
+/// - A registry entry per allocation, tagged with a session id and expiry
+/// - Cleanup of a whole session's scratch space with ai-cleanup
+#[derive(Parser, Debug)]
+#[command(name = "ai-mktemp")]
+#[command(about = "Create a registered temporary file or directory with JSONL output", long_about = None)]
+struct Cli {
+    /// Template with at least 3 trailing X's (default: tmp.XXXXXXXXXX in the temporary directory)
+    template: Option<PathBuf>,
+
+    /// Create a directory instead of a file
+    #[arg(short, long)]
+    directory: bool,
+
+    /// Only print a name; create and register nothing
+    #[arg(short = 'u', long)]
+    dry_run: bool,
+
+    /// Interpret the template relative to DIR
+    #[arg(short = 'p', long, value_name = "DIR")]
+    tmpdir: Option<PathBuf>,
+
+    /// Interpret the template relative to the session's temporary directory, $TMPDIR, or the platform default
+    #[arg(short = 't')]
+    in_tmpdir: bool,
+
+    /// Append SUFFIX to the template
+    #[arg(long, value_name = "SUFFIX", default_value = "")]
+    suffix: String,
+
+    /// Let ai-cleanup --expired remove it after DURATION (suffix s/m/h/d)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    ttl: Option<Duration>,
+
+    /// Session to record it under (default: session.id or AI_COREUTILS_SESSION)
+    #[arg(long, value_name = "ID", value_parser = parse_session_id)]
+    session: Option<String>,
+
+    /// Do not record it in the scratch registry
+    #[arg(long)]
+    no_register: bool,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    cli.log.apply();
+
+    let session = cli.session.clone().or_else(|| config::get().session.id.clone());
+    let session_dir = session.as_deref().and_then(|id| Session::new(id, &session::root()?).ok());
+    let temp_dir = || session_dir.as_ref().map_or_else(std::env::temp_dir, Session::tmp_dir);
+    let base = cli.tmpdir.clone().or_else(|| (cli.in_tmpdir || cli.template.is_none()).then(temp_dir));
+    let template_path = match (&cli.template, base) {
+        (Some(template), Some(_)) if template.is_absolute() => {
+            jsonl::fail("The template must be relative with -p or -t", "MKTEMP_ERROR", Some(&template.to_string_lossy()))
+        }
+        (Some(template), Some(base)) => base.join(template),
+        (Some(template), None) => template.clone(),
+        (None, base) => base.unwrap_or_default().join(DEFAULT_TEMPLATE),
+    };
+    policy::enforce([&template_path], Access::Write)?;
+    if let Some(session_dir) = session_dir.as_ref().filter(|s| !cli.dry_run && template_path.starts_with(s.tmp_dir())) {
+        if let Err(e) = session_dir.ensure_tmp_dir() {
+            jsonl::fail(&e.to_string(), "MKTEMP_ERROR", Some(&session_dir.tmp_dir().to_string_lossy()));
+        }
+    }
+
+    let template = match Template::parse(&template_path, &cli.suffix) {
+        Ok(template) => template,
+        Err(e) => jsonl::fail(&e.to_string(), "MKTEMP_ERROR", None),
+    };
+    let kind = if cli.directory { ScratchKind::Dir } else { ScratchKind::File };
+
+    let path = if cli.dry_run {
+        template.generate()
+    } else {
+        match template.create(kind) {
+            Ok(path) => path,
+            Err(e) => jsonl::fail(&e.to_string(), "MKTEMP_ERROR", Some(&template_path.to_string_lossy())),
+        }
+    };
+    let allocation = if cli.dry_run {
+        None
+    } else {
+        match Allocation::new(&path, kind, session.clone(), cli.ttl) {
+            Ok(allocation) => Some(allocation),
+            Err(e) => jsonl::fail(&e.to_string(), "MKTEMP_ERROR", Some(&path.to_string_lossy())),
+        }
+    };
+
+    let mut registered = false;
+    if let Some(allocation) = allocation.as_ref().filter(|_| !cli.no_register) {
+        let recorded = match Registry::from_config() {
+            Some(registry) => registry.record(allocation).map_err(|e| e.to_string()),
+            None => Err("No scratch registry directory (set scratch.dir or AI_COREUTILS_SCRATCH_DIR)".to_string()),
+        };
+        match recorded {
+            Ok(()) => registered = true,
+            Err(message) => {
+                let message = format!("Not registered, so ai-cleanup will not remove it: {}", message);
+                jsonl::output_warning(LogLevel::Warn, "SCRATCH_UNREGISTERED", &message, Some(&path.to_string_lossy()))?;
+            }
+        }
+    }
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    JsonlRecord::result(serde_json::json!({
+        "type": "temp_created",
+        "path": allocation.as_ref().map_or(path.clone(), |a| a.path.clone()).to_string_lossy(),
+        "kind": kind.as_str(),
+        "created": !cli.dry_run,
+        "registered": registered,
+        "id": allocation.as_ref().filter(|_| registered).map(|a| &a.id),
+        "session": session,
+        "expires": allocation.as_ref().and_then(|a| a.expires).map(|t| t.to_rfc3339()),
+    })).write_line(&mut out)?;
+    out.flush()?;
+    Ok(())
+}
@@ -1,9 +1,16 @@
 //! AI-optimized mv utility
 //!
 //! Moves and renames files and directories with progress tracking and JSONL output.
+//! Moves, and files they overwrite, are recorded in the operation journal
+//! so ai-undo can reverse them.
 
+use ai_coreutils::audit;
+use ai_coreutils::cli::DryRunArgs;
+use ai_coreutils::config;
 use ai_coreutils::jsonl;
+use ai_coreutils::ops_journal::Recorder;
 use ai_coreutils::{jsonl::JsonlRecord, Result};
+use ai_coreutils::policy::{self, Access};
 use clap::Parser;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -37,6 +44,9 @@ struct Cli {
     #[arg(short, long)]
     force: bool,
 
+    #[command(flatten)]
+    dry_run: DryRunArgs,
+
     /// Output JSONL (always enabled for AI-Coreutils)
     #[arg(long, default_value_t = true)]
     json: bool,
@@ -52,6 +62,12 @@ struct MoveStats {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    config::init()?;
+    cli.dry_run.apply();
+    // Sources are renamed as entries; the destination may be a link to the
+    // directory they land in
+    policy::enforce_entries(&cli.sources)?;
+    policy::enforce([&cli.destination], Access::Write)?;
 
     let mut stats = MoveStats {
         files_moved: 0,
@@ -60,6 +76,8 @@ fn main() -> Result<()> {
         errors: 0,
     };
 
+    let mut journal = Recorder::begin("ai-mv");
+
     // Determine if destination is a directory
     let dest_is_dir = cli.destination.exists() && cli.destination.is_dir();
 
@@ -82,13 +100,14 @@ fn main() -> Result<()> {
                 &cli.destination.join(source.file_name().unwrap_or_default()),
                 &cli,
                 &mut stats,
+                &mut journal,
             ) {
                 stats.errors += 1;
                 let error_record = JsonlRecord::error(
                     format!("Failed to move {}: {}", source.display(), e),
                     "MV_ERROR"
                 );
-                println!("{}", error_record.to_jsonl()?);
+                error_record.print()?;
             }
         }
     } else {
@@ -100,31 +119,54 @@ fn main() -> Result<()> {
             cli.destination.clone()
         };
 
-        if let Err(e) = move_path(source, &dest, &cli, &mut stats) {
+        if let Err(e) = move_path(source, &dest, &cli, &mut stats, &mut journal) {
             // stats.errors += 1; // Error is already returned below
             let error_record = JsonlRecord::error(
                 format!("Failed to move {}: {}", source.display(), e),
                 "MV_ERROR"
             );
-            println!("{}", error_record.to_jsonl()?);
+            error_record.print()?;
+            // Keep the backup of a destination the failed move may have replaced
+            finish_journal(journal, &mut stats)?;
             return Err(e);
         }
     }
 
+    let journal_id = finish_journal(journal, &mut stats)?;
+
     // Output final stats
-    let record = JsonlRecord::result(serde_json::json!({
+    let record = JsonlRecord::summary(serde_json::json!({
         "type": "move_summary",
         "files_moved": stats.files_moved,
         "bytes_moved": stats.bytes_moved,
         "dirs_moved": stats.dirs_moved,
         "errors": stats.errors,
+        "journal": journal_id,
     }));
-    println!("{}", record.to_jsonl()?);
+    record.print()?;
 
     Ok(())
 }
 
-fn move_path(source: &PathBuf, dest: &PathBuf, cli: &Cli, stats: &mut MoveStats) -> Result<()> {
+/// Append the recorded moves to the journal, reporting a failure as an error
+fn finish_journal(journal: Recorder, stats: &mut MoveStats) -> Result<Option<String>> {
+    match journal.finish() {
+        Ok(id) => Ok(id),
+        Err(e) => {
+            stats.errors += 1;
+            jsonl::output_error(&e.to_string(), "JOURNAL_ERROR", None)?;
+            Ok(None)
+        }
+    }
+}
+
+fn move_path(
+    source: &PathBuf,
+    dest: &PathBuf,
+    cli: &Cli,
+    stats: &mut MoveStats,
+    journal: &mut Recorder,
+) -> Result<()> {
     // Check if source exists
     if !source.exists() {
         return Err(ai_coreutils::error::AiCoreutilsError::PathNotFound(source.clone()));
@@ -153,8 +195,9 @@ fn move_path(source: &PathBuf, dest: &PathBuf, cli: &Cli, stats: &mut MoveStats)
         // In a real implementation, you'd read from stdin here
     }
 
-    // Get file size for stats
-    let file_size = if source.is_file() {
+    // Get file size for stats; the source is gone once moved
+    let is_file = source.is_file();
+    let file_size = if is_file {
         fs::metadata(source)
             .map(|m| m.len())
             .unwrap_or(0)
@@ -162,23 +205,31 @@ fn move_path(source: &PathBuf, dest: &PathBuf, cli: &Cli, stats: &mut MoveStats)
         0
     };
 
-    // Try to perform the move
-    let move_result = fs::rename(source, dest);
+    // Try to perform the move, keeping whatever it replaces
+    let move_result = if cli.dry_run.is_enabled() {
+        Ok(())
+    } else {
+        journal.before_write(dest)?;
+        fs::rename(source, dest)
+    };
 
     if let Err(_e) = move_result {
         // If rename fails (cross-device), try copy + delete
         // This returns Ok(()) with stats already updated
         if source.is_dir() {
             move_directory_fallback(source, dest, cli, stats)?;
-            return Ok(());
         } else {
             move_file_fallback(source, dest, cli, stats, file_size)?;
-            return Ok(());
         }
+        journal.moved(source, dest);
+        return Ok(());
+    }
+    if !cli.dry_run.is_enabled() {
+        journal.moved(source, dest);
     }
 
     // Normal move succeeded - update stats
-    if source.is_file() {
+    if is_file {
         stats.files_moved += 1;
         stats.bytes_moved += file_size;
     } else {
@@ -200,7 +251,9 @@ fn move_path(source: &PathBuf, dest: &PathBuf, cli: &Cli, stats: &mut MoveStats)
 
 fn move_file_fallback(source: &Path, dest: &Path, cli: &Cli, stats: &mut MoveStats, file_size: u64) -> Result<()> {
     // Copy the file
-    fs::copy(source, dest)?;
+    let copied = fs::copy(source, dest)?;
+    audit::read(source, copied);
+    audit::written(dest, copied);
 
     // Remove the source
     fs::remove_file(source)?;
@@ -0,0 +1,180 @@
+//! AI-optimized nl utility
+//!
+//! Numbers lines with GNU `nl`'s styles, number formats and logical page
+//! sections, writing each output line as a JSONL record that maps it back
+//! to its file, line number and byte offset, so edits made against the
+//! numbered text can be applied to the original.
+
+use ai_coreutils::{
+    async_ops::raw_lines,
+    cli::{LimitArgs, MmapArgs},
+    config, jsonl,
+    text_format::{LineNumberer, NumberFormat, Numbering, NumberingStyle},
+    JsonlRecord, Result, SafeMemoryAccess,
+};
+use ai_coreutils::policy::{self, Access};
+use clap::Parser;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+/// AI-optimized nl: Number lines with a map back to the input
+///
+/// This utility provides:
+/// - GNU nl numbering styles, formats and header/body/footer sections
+/// - Original file, line number and byte offset of every output line
+#[derive(Parser, Debug)]
+#[command(name = "ai-nl")]
+#[command(about = "Number lines as JSONL, mapped back to the input", long_about = None)]
+#[command(disable_help_flag = true)]
+struct Cli {
+    /// Files to number ("-" or none for stdin)
+    files: Vec<PathBuf>,
+
+    /// Body numbering style: a (all), t (non-empty), n (none) or pREGEX
+    #[arg(short = 'b', long, value_name = "STYLE", default_value = "t")]
+    body_numbering: NumberingStyle,
+
+    /// Header numbering style
+    #[arg(short = 'h', long, value_name = "STYLE", default_value = "n")]
+    header_numbering: NumberingStyle,
+
+    /// Footer numbering style
+    #[arg(short = 'f', long, value_name = "STYLE", default_value = "n")]
+    footer_numbering: NumberingStyle,
+
+    /// Section delimiter; empty turns sections off
+    #[arg(short = 'd', long, value_name = "CC", default_value = "\\:")]
+    section_delimiter: String,
+
+    /// Line number increment
+    #[arg(short = 'i', long, default_value_t = 1, allow_negative_numbers = true)]
+    line_increment: i64,
+
+    /// Count this many consecutive empty lines as one (with -b a)
+    #[arg(short = 'l', long, value_name = "N", default_value_t = 1)]
+    join_blank_lines: usize,
+
+    /// Number format: ln, rn or rz
+    #[arg(short = 'n', long, value_name = "FORMAT", default_value = "rn")]
+    number_format: NumberFormat,
+
+    /// Do not restart numbering at section delimiters
+    #[arg(short = 'p', long)]
+    no_renumber: bool,
+
+    /// Text after the line number
+    #[arg(short = 's', long, value_name = "STRING", default_value = "\t")]
+    number_separator: String,
+
+    /// First line number of each section
+    #[arg(short = 'v', long, default_value_t = 1, allow_negative_numbers = true)]
+    starting_line_number: i64,
+
+    /// Line number width
+    #[arg(short = 'w', long, default_value_t = 6)]
+    number_width: usize,
+
+    /// Only output the summary
+    #[arg(short, long)]
+    quiet: bool,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    mmap: MmapArgs,
+
+    /// Print help (-h is --header-numbering, as in GNU nl)
+    #[arg(long, action = clap::ArgAction::Help)]
+    help: Option<bool>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    cli.mmap.apply();
+    policy::enforce(&cli.files, Access::Read)?;
+
+    let files = if cli.files.is_empty() {
+        vec![PathBuf::from("-")]
+    } else {
+        cli.files.clone()
+    };
+    // Numbering and sections run on across files, as in GNU nl
+    let mut numberer = LineNumberer::new(Numbering {
+        header: cli.header_numbering.clone(),
+        body: cli.body_numbering.clone(),
+        footer: cli.footer_numbering.clone(),
+        delimiter: cli.section_delimiter.clone().into_bytes(),
+        start: cli.starting_line_number,
+        increment: cli.line_increment,
+        join_blank: cli.join_blank_lines,
+        format: cli.number_format,
+        width: cli.number_width,
+        separator: cli.number_separator.clone(),
+        restart: !cli.no_renumber,
+    });
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut output_lines = 0u64;
+    let mut numbered = 0u64;
+    let mut errors = 0u64;
+
+    'files: for path in &files {
+        let name = path.to_string_lossy();
+        let input = match SafeMemoryAccess::open_or_stdin(path) {
+            Ok(input) => input,
+            Err(e) => {
+                out.flush()?;
+                jsonl::output_error(&e.to_string(), "NL_ERROR", Some(&name))?;
+                errors += 1;
+                continue;
+            }
+        };
+
+        for line in raw_lines(input.as_slice()) {
+            let result = match numberer.number_line(line.content) {
+                Ok(result) => result,
+                Err(e) => {
+                    out.flush()?;
+                    jsonl::output_error(&e.to_string(), "NL_ERROR", Some(&name))?;
+                    errors += 1;
+                    break 'files;
+                }
+            };
+            output_lines += 1;
+            numbered += u64::from(result.number.is_some());
+            if cli.quiet {
+                continue;
+            }
+            JsonlRecord::result(serde_json::json!({
+                "type": "numbered_line",
+                "file": name,
+                "output_line": output_lines,
+                "line_number": line.number,
+                "offset": line.offset,
+                "length": line.content.len(),
+                "section": result.section.as_str(),
+                "delimiter": result.delimiter,
+                "number": result.number,
+                "content": result.content,
+            })).write_line(&mut out)?;
+        }
+    }
+
+    JsonlRecord::summary(serde_json::json!({
+        "type": "nl_summary",
+        "files": files.len(),
+        "lines": output_lines,
+        "numbered": numbered,
+        "errors": errors,
+    })).write_line(&mut out)?;
+    out.flush()?;
+
+    if errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
@@ -0,0 +1,178 @@
+//! AI-optimized realpath utility
+//!
+//! Canonicalizes paths as `realpath` and `readlink -f` do, expanding one
+//! component at a time so each symbolic link on the way is reported as a
+//! hop. Loops, missing components and whether the final target exists are
+//! part of each path's record, which makes symlink farms and container
+//! mounts debuggable without a shell loop over `readlink`.
+
+use ai_coreutils::{
+    config,
+    fs_utils::paths::relative_to,
+    fs_utils::resolve::{Hop, MustExist, ResolveFailure, Resolver},
+    jsonl, JsonlRecord, Result,
+};
+use ai_coreutils::policy::{self, Access};
+use clap::Parser;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// AI-optimized realpath: Canonical paths with every symlink hop
+///
+/// This utility provides:
+/// - Canonical absolute paths with `realpath` existence rules
+/// - Each symbolic link expanded on the way, in order
+/// - Loop detection that names the link closing the cycle
+/// - Whether, and as what, the final target exists
+#[derive(Parser, Debug)]
+#[command(name = "ai-realpath")]
+#[command(about = "Resolve paths and their symlink chains as JSONL", long_about = None)]
+struct Cli {
+    /// Paths to resolve
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Every component must exist
+    #[arg(short = 'e', long, conflicts_with = "canonicalize_missing")]
+    canonicalize_existing: bool,
+
+    /// No component needs to exist
+    #[arg(short = 'm', long)]
+    canonicalize_missing: bool,
+
+    /// Resolve `..` lexically and do not expand symlinks
+    #[arg(short = 's', long, visible_alias = "strip")]
+    no_symlinks: bool,
+
+    /// Also print each resolved path relative to DIR
+    #[arg(long, value_name = "DIR")]
+    relative_to: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    policy::enforce(cli.paths.iter().chain(&cli.relative_to), Access::Read)?;
+
+    let resolver = Resolver {
+        must_exist: if cli.canonicalize_existing {
+            MustExist::All
+        } else if cli.canonicalize_missing {
+            MustExist::None
+        } else {
+            MustExist::AllButLast
+        },
+        no_symlinks: cli.no_symlinks,
+    };
+    let base = match &cli.relative_to {
+        Some(dir) => match resolver.resolve(dir) {
+            Ok(resolution) if resolution.failure.is_none() => Some(resolution.path),
+            Ok(resolution) => {
+                let reason = resolution.failure.map_or("", |failure| failure.as_str());
+                let message = format!("Cannot resolve --relative-to directory: {}", reason);
+                jsonl::output_error(&message, "REALPATH_ERROR", Some(&dir.to_string_lossy()))?;
+                std::process::exit(1);
+            }
+            Err(e) => {
+                jsonl::output_error(&e.to_string(), "REALPATH_ERROR", Some(&dir.to_string_lossy()))?;
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut resolved = 0u64;
+    let mut missing = 0u64;
+    let mut loops = 0u64;
+    let mut failed = 0u64;
+
+    for path in &cli.paths {
+        let name = path.to_string_lossy();
+        let resolution = match resolver.resolve(path) {
+            Ok(resolution) => resolution,
+            Err(e) => {
+                out.flush()?;
+                jsonl::output_error(&e.to_string(), "REALPATH_ERROR", Some(&name))?;
+                failed += 1;
+                continue;
+            }
+        };
+
+        let hops: Vec<_> = resolution.hops.iter().map(hop_record).collect();
+        let mut record = serde_json::json!({
+            "type": "realpath",
+            "path": name,
+            "resolved": resolution.path.to_string_lossy(),
+            "exists": resolution.exists,
+            "file_type": file_type(&resolution.path, resolution.exists),
+            "hops": hops,
+            "loop": false,
+        });
+        match &resolution.failure {
+            None => {
+                resolved += 1;
+                missing += u64::from(!resolution.exists);
+                if let Some(base) = &base {
+                    let relative = relative_to(&resolution.path, base);
+                    record["relative"] = relative.map(|path| path.to_string_lossy().into_owned()).into();
+                }
+            }
+            Some(failure) => {
+                failed += 1;
+                let looped = *failure == ResolveFailure::Loop;
+                loops += u64::from(looped);
+                record["resolved"] = serde_json::Value::Null;
+                record["loop"] = looped.into();
+                let mut error = serde_json::json!({
+                    "reason": failure.as_str(),
+                    "at": resolution.path.to_string_lossy(),
+                });
+                if let ResolveFailure::Io(message) = failure {
+                    error["message"] = message.as_str().into();
+                }
+                record["error"] = error;
+            }
+        }
+        JsonlRecord::result(record).write_line(&mut out)?;
+    }
+
+    JsonlRecord::summary(serde_json::json!({
+        "type": "realpath_summary",
+        "paths": cli.paths.len(),
+        "resolved": resolved,
+        "missing": missing,
+        "loops": loops,
+        "failed": failed,
+    })).write_line(&mut out)?;
+    out.flush()?;
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn hop_record(hop: &Hop) -> serde_json::Value {
+    serde_json::json!({
+        "link": hop.link.to_string_lossy(),
+        "target": hop.target.to_string_lossy(),
+    })
+}
+
+/// What the resolved path is, following a final symlink left by `-s`
+fn file_type(path: &Path, exists: bool) -> Option<&'static str> {
+    if !exists {
+        return None;
+    }
+    let metadata = fs::metadata(path).ok()?;
+    Some(if metadata.is_dir() {
+        "directory"
+    } else if metadata.is_file() {
+        "file"
+    } else {
+        "other"
+    })
+}
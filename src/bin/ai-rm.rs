@@ -1,9 +1,15 @@
 //! AI-optimized rm utility
 //!
 //! Removes files and directories with safety features and JSONL output.
+//! Removed files are moved into the operation journal so ai-undo can
+//! restore them.
 
+use ai_coreutils::cli::DryRunArgs;
+use ai_coreutils::config;
 use ai_coreutils::jsonl;
+use ai_coreutils::ops_journal::Recorder;
 use ai_coreutils::{jsonl::JsonlRecord, Result};
+use ai_coreutils::policy;
 use clap::Parser;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -41,6 +47,9 @@ struct Cli {
     #[arg(long, default_value_t = true)]
     preserve_root: bool,
 
+    #[command(flatten)]
+    dry_run: DryRunArgs,
+
     /// Output JSONL (always enabled for AI-Coreutils)
     #[arg(long, default_value_t = true)]
     json: bool,
@@ -51,16 +60,21 @@ struct RemoveStats {
     files_removed: u64,
     dirs_removed: u64,
     bytes_freed: u64,
+    bytes_journaled: u64,
     errors: u64,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    config::init()?;
+    cli.dry_run.apply();
+    policy::enforce_entries(&cli.paths)?;
 
     let mut stats = RemoveStats {
         files_removed: 0,
         dirs_removed: 0,
         bytes_freed: 0,
+        bytes_journaled: 0,
         errors: 0,
     };
 
@@ -80,9 +94,11 @@ fn main() -> Result<()> {
         }
     }
 
+    let mut journal = Recorder::begin("ai-rm");
+
     // Remove each path
     for path in &cli.paths {
-        if let Err(e) = remove_path(path, &cli, &mut stats) {
+        if let Err(e) = remove_path(path, &cli, &mut stats, &mut journal) {
             stats.errors += 1;
 
             // Only output error if not in force mode
@@ -91,25 +107,36 @@ fn main() -> Result<()> {
                     format!("Failed to remove {}: {}", path.display(), e),
                     "RM_ERROR"
                 );
-                println!("{}", error_record.to_jsonl()?);
+                error_record.print()?;
             }
         }
     }
 
+    let journal_id = match journal.finish() {
+        Ok(id) => id,
+        Err(e) => {
+            stats.errors += 1;
+            jsonl::output_error(&e.to_string(), "JOURNAL_ERROR", None)?;
+            None
+        }
+    };
+
     // Output final stats
-    let record = JsonlRecord::result(serde_json::json!({
+    let record = JsonlRecord::summary(serde_json::json!({
         "type": "remove_summary",
         "files_removed": stats.files_removed,
         "dirs_removed": stats.dirs_removed,
         "bytes_freed": stats.bytes_freed,
+        "bytes_journaled": stats.bytes_journaled,
         "errors": stats.errors,
+        "journal": journal_id,
     }));
-    println!("{}", record.to_jsonl()?);
+    record.print()?;
 
     Ok(())
 }
 
-fn remove_path(path: &PathBuf, cli: &Cli, stats: &mut RemoveStats) -> Result<()> {
+fn remove_path(path: &PathBuf, cli: &Cli, stats: &mut RemoveStats, journal: &mut Recorder) -> Result<()> {
     // Check if path exists
     if !path.exists() {
         if cli.force {
@@ -142,24 +169,28 @@ fn remove_path(path: &PathBuf, cli: &Cli, stats: &mut RemoveStats) -> Result<()>
                 "Cannot remove directory without -r/--recursive".to_string(),
             ));
         }
-        remove_directory(path, cli, stats)?;
+        remove_directory(path, cli, stats, journal)?;
     } else {
-        remove_file(path, cli, stats, size)?;
+        remove_file(path, cli, stats, size, journal)?;
     }
 
     Ok(())
 }
 
-fn remove_file(path: &Path, cli: &Cli, stats: &mut RemoveStats, size: u64) -> Result<()> {
+fn remove_file(path: &Path, cli: &Cli, stats: &mut RemoveStats, size: u64, journal: &mut Recorder) -> Result<()> {
     // Output progress
     jsonl::output_progress(0, size as usize, &format!("Removing {}", path.display()))?;
 
-    // Remove the file
-    fs::remove_file(path)?;
+    // Remove the file, keeping it in the journal if there is one
+    let kept = !cli.dry_run.is_enabled() && journal.remove_file(path)?;
 
-    // Update stats
+    // Update stats; bytes moved into the journal still take up space
     stats.files_removed += 1;
-    stats.bytes_freed += size;
+    if kept {
+        stats.bytes_journaled += size;
+    } else {
+        stats.bytes_freed += size;
+    }
 
     if cli.verbose {
         jsonl::output_info(
@@ -174,24 +205,34 @@ fn remove_file(path: &Path, cli: &Cli, stats: &mut RemoveStats, size: u64) -> Re
     Ok(())
 }
 
-fn remove_directory(path: &Path, cli: &Cli, stats: &mut RemoveStats) -> Result<()> {
+fn remove_directory(path: &Path, cli: &Cli, stats: &mut RemoveStats, journal: &mut Recorder) -> Result<()> {
+    let metadata = fs::metadata(path)?;
+
     // Remove all contents first
     for entry in fs::read_dir(path)? {
         let entry = entry?;
         let entry_path = entry.path();
+        if let Err(e) = policy::check_entry(&entry_path) {
+            policy::report(&e)?;
+            stats.errors += 1;
+            continue;
+        }
 
         if entry_path.is_dir() {
-            remove_directory(&entry_path, cli, stats)?;
+            remove_directory(&entry_path, cli, stats, journal)?;
         } else {
             let size = fs::metadata(&entry_path)
                 .map(|m| m.len())
                 .unwrap_or(0);
-            remove_file(&entry_path, cli, stats, size)?;
+            remove_file(&entry_path, cli, stats, size, journal)?;
         }
     }
 
     // Remove the directory itself
-    fs::remove_dir(path)?;
+    if !cli.dry_run.is_enabled() {
+        fs::remove_dir(path)?;
+        journal.removed_dir(path, &metadata);
+    }
 
     // Update stats
     stats.dirs_removed += 1;
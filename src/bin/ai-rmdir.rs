@@ -1,4 +1,5 @@
-use ai_coreutils::{AiCoreutilsError, jsonl, Result};
+use ai_coreutils::{config, telemetry, AiCoreutilsError, jsonl, Result};
+use ai_coreutils::policy::{self, Access};
 use clap::Parser;
 use std::fs;
 use std::path::PathBuf;
@@ -32,6 +33,8 @@ struct Cli {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    config::init()?;
+    policy::enforce(&cli.directories, Access::Write)?;
 
     // Output start message
     jsonl::output_progress(0, cli.directories.len(), "Starting rmdir operation")?;
@@ -83,12 +86,12 @@ fn main() -> Result<()> {
     }
 
     // Output summary
-    jsonl::output_info(serde_json::json!({
+    jsonl::output_info(telemetry::attach(serde_json::json!({
         "operation": "rmdir_summary",
         "total_directories": cli.directories.len(),
         "successful": success_count,
         "errors": error_count,
-    }))?;
+    })))?;
 
     Ok(())
 }
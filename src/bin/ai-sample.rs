@@ -0,0 +1,214 @@
+//! AI-optimized sample utility
+//!
+//! Draws a random sample of lines from inputs of any size in one pass:
+//! `--count N` keeps a uniform sample of exactly N lines (reservoir
+//! sampling, memory proportional to N), `--percent P` keeps each line with
+//! probability P% and streams it out at once. Every record keeps the line's
+//! file, line number and byte offset, and the seed used is reported in the
+//! summary so the sample can be drawn again.
+
+use ai_coreutils::{
+    async_ops::raw_lines,
+    cli::{LimitArgs, MmapArgs},
+    config, jsonl,
+    sample::{Reservoir, Rng},
+    JsonlRecord, Result, SafeMemoryAccess,
+};
+use ai_coreutils::policy::{self, Access};
+use clap::{ArgGroup, Parser};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::PathBuf;
+
+/// AI-optimized sample: Random sample of lines with provenance
+///
+/// This utility provides:
+/// - Reservoir sampling of N lines from files or streams of any length
+/// - Bernoulli sampling of a percentage of lines
+/// - Original file, line number and offset of every sampled line
+/// - Reproducible samples from a seed
+#[derive(Parser, Debug)]
+#[command(name = "ai-sample")]
+#[command(about = "Randomly sample lines from large files and streams as JSONL", long_about = None)]
+#[command(group(ArgGroup::new("size").required(true).args(["count", "percent"])))]
+struct Cli {
+    /// Files to sample ("-" or none for stdin)
+    files: Vec<PathBuf>,
+
+    /// Sample exactly this many lines (fewer if the input is shorter)
+    #[arg(short = 'n', long)]
+    count: Option<usize>,
+
+    /// Keep each line with this probability, in percent (0-100)
+    #[arg(short, long, value_parser = parse_percent)]
+    percent: Option<f64>,
+
+    /// Seed for the sample; a random seed is used and reported if omitted
+    #[arg(long)]
+    seed: Option<u64>,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    mmap: MmapArgs,
+}
+
+fn parse_percent(s: &str) -> std::result::Result<f64, String> {
+    let percent: f64 = s.parse().map_err(|_| format!("invalid percentage: {}", s))?;
+    if (0.0..=100.0).contains(&percent) {
+        Ok(percent)
+    } else {
+        Err(format!("percentage must be between 0 and 100: {}", s))
+    }
+}
+
+/// A line kept by the reservoir
+struct Sampled {
+    file: usize,
+    line_number: usize,
+    offset: u64,
+    content: String,
+}
+
+/// Where sampled lines go
+enum Sampler {
+    Reservoir(Reservoir<Sampled>),
+    Bernoulli { probability: f64, sampled: u64 },
+}
+
+struct Run<'a, W: Write> {
+    names: &'a [String],
+    rng: Rng,
+    sampler: Sampler,
+    lines: u64,
+    out: W,
+}
+
+impl<W: Write> Run<'_, W> {
+    fn line(&mut self, file: usize, line_number: usize, offset: u64, content: &[u8]) -> Result<()> {
+        self.lines += 1;
+        match &mut self.sampler {
+            Sampler::Reservoir(reservoir) => {
+                reservoir.offer(&mut self.rng, || Sampled {
+                    file,
+                    line_number,
+                    offset,
+                    content: String::from_utf8_lossy(content).into_owned(),
+                });
+            }
+            Sampler::Bernoulli { probability, sampled } => {
+                if self.rng.next_f64() < *probability {
+                    *sampled += 1;
+                    record(&self.names[file], line_number, offset, &String::from_utf8_lossy(content))
+                        .write_line(&mut self.out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sample the lines of stdin as they arrive
+    fn stdin(&mut self, file: usize) -> Result<()> {
+        let mut reader = io::stdin().lock();
+        let mut buffer = Vec::new();
+        let mut offset = 0u64;
+        let mut line_number = 0;
+        loop {
+            buffer.clear();
+            if reader.read_until(b'\n', &mut buffer)? == 0 {
+                return Ok(());
+            }
+            line_number += 1;
+            let content = raw_lines(&buffer).next().map_or(&[][..], |line| line.content);
+            self.line(file, line_number, offset, content)?;
+            offset += buffer.len() as u64;
+        }
+    }
+}
+
+fn record(file: &str, line_number: usize, offset: u64, content: &str) -> JsonlRecord {
+    JsonlRecord::result(serde_json::json!({
+        "type": "sampled_line",
+        "file": file,
+        "line_number": line_number,
+        "offset": offset,
+        "content": content,
+    }))
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    cli.mmap.apply();
+    policy::enforce(&cli.files, Access::Read)?;
+
+    let files = if cli.files.is_empty() {
+        vec![PathBuf::from("-")]
+    } else {
+        cli.files.clone()
+    };
+    let names: Vec<String> = files.iter().map(|path| path.to_string_lossy().into_owned()).collect();
+    let seed = cli.seed.unwrap_or_else(Rng::random_seed);
+    let sampler = match (cli.count, cli.percent) {
+        (Some(count), _) => Sampler::Reservoir(Reservoir::new(count)),
+        (None, percent) => Sampler::Bernoulli {
+            probability: percent.unwrap_or(0.0) / 100.0,
+            sampled: 0,
+        },
+    };
+    let stdout = io::stdout();
+    let mut run = Run {
+        names: &names,
+        rng: Rng::new(seed),
+        sampler,
+        lines: 0,
+        out: BufWriter::new(stdout.lock()),
+    };
+    let mut errors = 0u64;
+
+    for (file, path) in files.iter().enumerate() {
+        let result = if path.to_str() == Some("-") {
+            run.stdin(file)
+        } else {
+            SafeMemoryAccess::new(path).and_then(|mem| {
+                raw_lines(mem.as_slice())
+                    .try_for_each(|line| run.line(file, line.number, line.offset, line.content))
+            })
+        };
+        if let Err(e) = result {
+            run.out.flush()?;
+            jsonl::output_error(&e.to_string(), "SAMPLE_ERROR", Some(&names[file]))?;
+            errors += 1;
+        }
+    }
+
+    let Run { sampler, lines, mut out, .. } = run;
+    let (method, sampled) = match sampler {
+        Sampler::Reservoir(reservoir) => {
+            let mut items = reservoir.into_items();
+            items.sort_by_key(|item| (item.file, item.line_number));
+            for item in &items {
+                record(&names[item.file], item.line_number, item.offset, &item.content).write_line(&mut out)?;
+            }
+            ("reservoir", items.len() as u64)
+        }
+        Sampler::Bernoulli { sampled, .. } => ("bernoulli", sampled),
+    };
+
+    JsonlRecord::summary(serde_json::json!({
+        "type": "sample_summary",
+        "method": method,
+        "files": files.len(),
+        "lines": lines,
+        "sampled": sampled,
+        "seed": seed,
+        "errors": errors,
+    })).write_line(&mut out)?;
+    out.flush()?;
+
+    if errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
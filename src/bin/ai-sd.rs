@@ -0,0 +1,302 @@
+//! AI-optimized find-and-replace utility
+//!
+//! Replaces regex or literal matches across files with capture-group
+//! templates. Runs as a dry run by default, reporting every changed line as
+//! JSONL; `--write` applies the changes with atomic in-place writes,
+//! keeping the previous contents in the operation journal for ai-undo.
+
+use ai_coreutils::{
+    async_ops::RetryPolicy,
+    cli::{DryRunArgs, LimitArgs, LogArgs},
+    config,
+    fs_utils::{self, DirWalk, SymlinkPolicy, WalkEvent},
+    jsonl::{self, LogLevel},
+    limits,
+    ops_journal::Recorder,
+    policy::{self, Access},
+    replace::{self, Replacement, Replacer},
+    AiCoreutilsError, Result,
+};
+use clap::Parser;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// AI-optimized sd: Find and replace across files with JSONL output
+///
+/// This utility extends sd/sed substitution with:
+/// - Dry-run by default, with per-line before/after records
+/// - Capture-group templates checked against the pattern up front
+/// - Atomic in-place writes (temp file + fsync + rename)
+/// - Per-file and total replacement counts
+#[derive(Parser, Debug)]
+#[command(name = "ai-sd")]
+#[command(about = "Find and replace across files with dry-run diffs and atomic writes", long_about = None)]
+struct Cli {
+    /// Regex pattern to search for
+    pattern: String,
+
+    /// Replacement template ($1, ${name}, $$ for a literal $)
+    replacement: String,
+
+    /// Files/directories to edit
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Apply the changes (default is a dry run)
+    #[arg(short, long, conflicts_with = "dry_run")]
+    write: bool,
+
+    #[command(flatten)]
+    dry_run: DryRunArgs,
+
+    /// Treat the pattern and replacement as literal strings
+    #[arg(short = 'F', long)]
+    fixed_strings: bool,
+
+    /// Case insensitive matching
+    #[arg(short, long)]
+    ignore_case: bool,
+
+    /// Recurse into directories
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// When to follow symlinks in recursive search: never, command-line or always
+    #[arg(long, value_name = "WHEN", default_value = "command-line")]
+    follow_symlinks: SymlinkPolicy,
+
+    /// Only report per-file counts, not each changed line
+    #[arg(long)]
+    no_diff: bool,
+
+    /// Retry transient I/O errors up to N times
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    retries: u32,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+#[derive(Debug, Default)]
+struct ReplaceStats {
+    files_scanned: u64,
+    files_changed: u64,
+    lines_changed: u64,
+    replacements: u64,
+    binary_skipped: u64,
+    errors: u64,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    cli.log.apply();
+    // Without --write this is a dry run whether or not --dry-run was given
+    jsonl::set_dry_run(!cli.write);
+    policy::enforce(&cli.paths, if cli.write { Access::Write } else { Access::Read })?;
+
+    let replacer = match Replacer::new(&cli.pattern, &cli.replacement, cli.fixed_strings, cli.ignore_case) {
+        Ok(replacer) => replacer,
+        Err(e) => {
+            jsonl::output_error(&e.to_string(), "INVALID_PATTERN", None)?;
+            std::process::exit(1);
+        }
+    };
+
+    let mut stats = ReplaceStats::default();
+    let mut journal = Recorder::begin("ai-sd");
+
+    for path in &cli.paths {
+        if path.is_dir() {
+            if cli.recursive {
+                replace_directory(path, &cli, &replacer, &mut stats, &mut journal)?;
+            } else {
+                let error = AiCoreutilsError::InvalidInput(
+                    "Is a directory (use -r for recursive replace)".to_string(),
+                );
+                report_error(path, &error, "REPLACE_ERROR", &mut stats)?;
+            }
+        } else {
+            replace_file(path, &cli, &replacer, &mut stats, &mut journal)?;
+        }
+    }
+
+    let journal_id = match journal.finish() {
+        Ok(id) => id,
+        Err(e) => {
+            stats.errors += 1;
+            jsonl::output_error(&e.to_string(), "JOURNAL_ERROR", None)?;
+            None
+        }
+    };
+
+    jsonl::output_summary(serde_json::json!({
+        "type": "replace_summary",
+        "files_scanned": stats.files_scanned,
+        "files_changed": stats.files_changed,
+        "lines_changed": stats.lines_changed,
+        "replacements": stats.replacements,
+        "binary_skipped": stats.binary_skipped,
+        "errors": stats.errors,
+        "dry_run": !cli.write,
+        "journal": journal_id,
+    }))?;
+
+    if stats.errors > 0 {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn replace_directory(
+    dir: &Path,
+    cli: &Cli,
+    replacer: &Replacer,
+    stats: &mut ReplaceStats,
+    journal: &mut Recorder,
+) -> Result<()> {
+    for event in DirWalk::new(dir, cli.follow_symlinks) {
+        let entry = match event {
+            Ok(WalkEvent::Entry(entry)) => entry,
+            Ok(WalkEvent::Loop(symlink_loop)) => {
+                jsonl::output_info(symlink_loop.to_json())?;
+                continue;
+            }
+            Err(e) => {
+                if policy::report(&e)? {
+                    stats.errors += 1;
+                } else {
+                    report_error(dir, &e, "REPLACE_ERROR", stats)?;
+                }
+                continue;
+            }
+        };
+
+        if entry.is_file() && !config::get().is_ignored_under(dir, entry.path()) {
+            replace_file(entry.path(), cli, replacer, stats, journal)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace within one file, reporting the changes and writing them with `--write`
+///
+/// Failures are reported and counted; only failures writing output are
+/// returned.
+fn replace_file(
+    path: &Path,
+    cli: &Cli,
+    replacer: &Replacer,
+    stats: &mut ReplaceStats,
+    journal: &mut Recorder,
+) -> Result<()> {
+    let retry = RetryPolicy::with_retries(cli.retries);
+    let read = retry.run_blocking(&format!("read {}", path.display()), || {
+        let before = fs::metadata(path).map_err(|e| not_found_or_io(path, e))?;
+        let data = fs::read(path).map_err(|e| not_found_or_io(path, e))?;
+        limits::read_file(path, data.len() as u64)?;
+        Ok((before, data))
+    });
+    let (before, data) = match read {
+        Ok(read) => read,
+        Err(e) => return report_error(path, &e, "REPLACE_ERROR", stats),
+    };
+    stats.files_scanned += 1;
+
+    let file = path.display().to_string();
+    if replace::looks_binary(&data) {
+        stats.binary_skipped += 1;
+        return jsonl::output_warning(LogLevel::Info, "BINARY_SKIPPED", "Binary file skipped", Some(file.as_str()));
+    }
+
+    let replacement = replacer.replace(&data);
+    if !replacement.is_changed() {
+        return Ok(());
+    }
+
+    if !cli.no_diff {
+        output_diff(&file, &replacement)?;
+    }
+
+    if cli.write {
+        match write_back(path, &before, &replacement.content, journal) {
+            Ok(true) => {}
+            Ok(false) => {
+                let error = AiCoreutilsError::InvalidInput(
+                    "File changed while replacing; not written".to_string(),
+                );
+                return report_error(path, &error, "CONCURRENT_MODIFICATION", stats);
+            }
+            Err(e) => return report_error(path, &e, "REPLACE_ERROR", stats),
+        }
+    }
+
+    stats.files_changed += 1;
+    stats.lines_changed += replacement.changes.len() as u64;
+    stats.replacements += replacement.replacements as u64;
+
+    jsonl::output_result(serde_json::json!({
+        "type": "replace_file",
+        "file": file,
+        "replacements": replacement.replacements,
+        "lines_changed": replacement.changes.len(),
+        "written": cli.write,
+    }))
+}
+
+/// Emit one `replace_diff` record per changed line
+fn output_diff(file: &str, replacement: &Replacement) -> Result<()> {
+    for change in &replacement.changes {
+        jsonl::output_info(serde_json::json!({
+            "type": "replace_diff",
+            "file": file,
+            "line_number": change.line_number,
+            "before": String::from_utf8_lossy(&change.before),
+            "after": String::from_utf8_lossy(&change.after),
+            "replacements": change.replacements,
+        }))?;
+    }
+    Ok(())
+}
+
+/// Atomically replace the file's contents
+///
+/// Returns false without writing if the file changed since it was read.
+/// Symlinks are resolved so the target is rewritten rather than the link
+/// being replaced by a regular file. The previous contents are backed up to
+/// the journal first.
+fn write_back(path: &Path, before: &fs::Metadata, content: &[u8], journal: &mut Recorder) -> Result<bool> {
+    let target = fs::canonicalize(path).map_err(AiCoreutilsError::Io)?;
+    let now = fs::metadata(&target).map_err(AiCoreutilsError::Io)?;
+    if now.len() != before.len() || modified(&now) != modified(before) {
+        return Ok(false);
+    }
+    journal.before_write(&target)?;
+    fs_utils::atomic_write(&target, content)?;
+    Ok(true)
+}
+
+fn modified(metadata: &fs::Metadata) -> Option<SystemTime> {
+    metadata.modified().ok()
+}
+
+fn not_found_or_io(path: &Path, e: std::io::Error) -> AiCoreutilsError {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        AiCoreutilsError::PathNotFound(path.to_path_buf())
+    } else {
+        AiCoreutilsError::Io(e)
+    }
+}
+
+/// Report a per-path failure and count it; traversal continues
+fn report_error(path: &Path, error: &AiCoreutilsError, code: &str, stats: &mut ReplaceStats) -> Result<()> {
+    stats.errors += 1;
+    jsonl::output_error(&error.to_string(), code, Some(&path.to_string_lossy()))
+}
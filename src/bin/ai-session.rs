@@ -0,0 +1,212 @@
+//! AI-optimized session utility
+//!
+//! Starts, inspects, lists and ends agent sessions. A session is an id set
+//! in `AI_COREUTILS_SESSION` that marks every record the utilities write,
+//! plus a state directory holding the run's operation journal, temporary
+//! files and remote cache. Ending a session removes its directory in one
+//! step and releases the scratch allocations registered under it.
+
+use ai_coreutils::cli::parse_session_id;
+use ai_coreutils::policy::{self, Access};
+use ai_coreutils::scratch::{Registry, ReleaseAction};
+use ai_coreutils::session::{self, Session};
+use ai_coreutils::{config, jsonl, JsonlRecord, Result};
+use chrono::{DateTime, Utc};
+use clap::Parser;
+use std::io::{self, BufWriter, Write};
+
+/// AI-optimized session: Correlate, resume and clean up multi-step runs
+///
+/// This utility provides:
+/// - New session ids with their state directory, ready to export
+/// - The state a session holds: journal, temporary files, cache, allocations
+/// - A listing of every session with its disk use
+/// - Atomic removal of a session and everything it allocated
+#[derive(Parser, Debug)]
+#[command(name = "ai-session")]
+#[command(about = "Start, inspect, list and end agent sessions with JSONL output", long_about = None)]
+struct Cli {
+    /// Session id (default: session.id or AI_COREUTILS_SESSION; with --new, a generated one)
+    #[arg(value_parser = parse_session_id)]
+    id: Option<String>,
+
+    /// Create the session's state directory and print its id
+    #[arg(long, conflicts_with_all = ["list", "end"])]
+    new: bool,
+
+    /// List every session with a state directory
+    #[arg(short, long, conflicts_with = "end")]
+    list: bool,
+
+    /// Remove the session's state directory and its registered scratch files
+    #[arg(long)]
+    end: bool,
+
+    /// With --end, report what would be removed without removing it
+    #[arg(short = 'n', long, requires = "end")]
+    dry_run: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+
+    let Some(root) = session::root() else {
+        jsonl::fail("No sessions directory (set session.dir or AI_COREUTILS_SESSION_DIR)", "SESSION_ERROR", None);
+    };
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+
+    if cli.list {
+        let sessions = match Session::list(&root) {
+            Ok(sessions) => sessions,
+            Err(e) => jsonl::fail(&e.to_string(), "SESSION_ERROR", Some(&root.to_string_lossy())),
+        };
+        let allocations = allocation_counts();
+        let current = config::get().session.id.as_deref();
+        let mut bytes = 0u64;
+        for session in &sessions {
+            let mut record = session_record(session, &allocations);
+            record["type"] = "session_entry".into();
+            record["current"] = (Some(session.id()) == current).into();
+            bytes += record["bytes"].as_u64().unwrap_or(0);
+            JsonlRecord::result(record).write_line(&mut out)?;
+        }
+        JsonlRecord::summary(serde_json::json!({
+            "type": "session_summary",
+            "root": root.to_string_lossy(),
+            "sessions": sessions.len(),
+            "bytes": bytes,
+        })).write_line(&mut out)?;
+        out.flush()?;
+        return Ok(());
+    }
+
+    let id = match (cli.id.clone(), cli.new) {
+        (Some(id), _) => id,
+        (None, true) => session::generate_id(),
+        (None, false) => match config::get().session.id.clone() {
+            Some(id) => id,
+            None => jsonl::fail("No session: give an id or set AI_COREUTILS_SESSION", "SESSION_ERROR", None),
+        },
+    };
+    let session = match Session::new(&id, &root) {
+        Ok(session) => session,
+        Err(e) => jsonl::fail(&e.to_string(), "SESSION_ERROR", None),
+    };
+    let dir = session.dir().to_string_lossy().into_owned();
+
+    if cli.new {
+        policy::enforce([session.dir()], Access::Write)?;
+        let existed = session.exists();
+        if let Err(e) = session.create() {
+            jsonl::fail(&e.to_string(), "SESSION_ERROR", Some(&dir));
+        }
+        JsonlRecord::result(serde_json::json!({
+            "type": "session_created",
+            "id": session.id(),
+            "dir": dir,
+            "existed": existed,
+            "export": format!("AI_COREUTILS_SESSION={}", session.id()),
+        })).write_line(&mut out)?;
+        out.flush()?;
+        return Ok(());
+    }
+
+    if cli.end {
+        let (released, failed) = release_allocations(&session, cli.dry_run, &mut out)?;
+        let existed = session.exists();
+        let removed = if cli.dry_run || failed > 0 {
+            false
+        } else {
+            match session.end() {
+                Ok(removed) => removed,
+                Err(e) => jsonl::fail(&e.to_string(), "SESSION_ERROR", Some(&dir)),
+            }
+        };
+        JsonlRecord::summary(serde_json::json!({
+            "type": "session_ended",
+            "id": session.id(),
+            "dir": dir,
+            "existed": existed,
+            "removed": removed,
+            "would_remove": cli.dry_run && existed,
+            "allocations_released": released,
+            "allocations_failed": failed,
+            "dry_run": cli.dry_run,
+        })).write_line(&mut out)?;
+        out.flush()?;
+
+        if failed > 0 {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let mut record = session_record(&session, &allocation_counts());
+    record["type"] = "session_info".into();
+    record["current"] = (config::get().session.id.as_deref() == Some(session.id())).into();
+    record["journal_dir"] = session.journal_dir().to_string_lossy().into();
+    record["tmp_dir"] = session.tmp_dir().to_string_lossy().into();
+    record["cache_dir"] = session.cache_dir().to_string_lossy().into();
+    JsonlRecord::result(record).write_line(&mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Scratch allocations registered per session
+fn allocation_counts() -> std::collections::HashMap<String, u64> {
+    let mut counts = std::collections::HashMap::new();
+    let allocations = Registry::from_config().and_then(|registry| registry.allocations().ok());
+    for session in allocations.into_iter().flatten().filter_map(|a| a.session) {
+        *counts.entry(session).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn session_record(session: &Session, allocations: &std::collections::HashMap<String, u64>) -> serde_json::Value {
+    let usage = session.usage();
+    serde_json::json!({
+        "id": session.id(),
+        "dir": session.dir().to_string_lossy(),
+        "exists": session.exists(),
+        "files": usage.files,
+        "bytes": usage.bytes,
+        "modified": usage.modified.map(|t| DateTime::<Utc>::from(t).to_rfc3339()),
+        "allocations": allocations.get(session.id()).copied().unwrap_or(0),
+    })
+}
+
+/// Release the scratch allocations of `session`, one `cleanup_entry` each
+///
+/// Returns how many were released and how many could not be removed.
+fn release_allocations<W: Write>(session: &Session, dry_run: bool, out: &mut W) -> Result<(u64, u64)> {
+    let Some(registry) = Registry::from_config() else {
+        return Ok((0, 0));
+    };
+    let outcomes = match registry.release(|a| a.session.as_deref() == Some(session.id()), dry_run) {
+        Ok(outcomes) => outcomes,
+        Err(e) => jsonl::fail(&e.to_string(), "SESSION_ERROR", Some(&registry.dir().to_string_lossy())),
+    };
+
+    let (mut released, mut failed) = (0u64, 0u64);
+    for (allocation, action) in &outcomes {
+        let mut record = serde_json::json!({
+            "type": "cleanup_entry",
+            "id": allocation.id,
+            "path": allocation.path.to_string_lossy(),
+            "kind": allocation.kind.as_str(),
+            "action": action.as_str(),
+        });
+        match action {
+            ReleaseAction::Failed(e) => {
+                record["error"] = e.to_string().into();
+                failed += 1;
+            }
+            _ => released += 1,
+        }
+        JsonlRecord::result(record).write_line(&mut *out)?;
+    }
+    Ok((released, failed))
+}
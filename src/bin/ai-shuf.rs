@@ -0,0 +1,110 @@
+//! AI-optimized shuf utility
+//!
+//! Writes the lines of its input in a random order as JSONL records that
+//! keep each line's file, line number and byte offset, so a shuffled
+//! dataset can be traced back to its source. A `--seed` makes the order
+//! reproducible, and the seed used is always reported in the summary.
+
+use ai_coreutils::{
+    async_ops::{raw_lines, RawLine},
+    cli::{LimitArgs, MmapArgs},
+    config, jsonl,
+    sample::Rng,
+    JsonlRecord, Result, SafeMemoryAccess,
+};
+use ai_coreutils::policy::{self, Access};
+use clap::Parser;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+/// AI-optimized shuf: Random permutation of lines with provenance
+///
+/// This utility provides:
+/// - Lines from all inputs shuffled together
+/// - Original file, line number and offset of every line
+/// - Reproducible order from a seed
+#[derive(Parser, Debug)]
+#[command(name = "ai-shuf")]
+#[command(about = "Shuffle lines into a random order as JSONL", long_about = None)]
+struct Cli {
+    /// Files to shuffle ("-" or none for stdin)
+    files: Vec<PathBuf>,
+
+    /// Seed for the random order; a random seed is used and reported if omitted
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Output at most this many lines
+    #[arg(short = 'n', long)]
+    head_count: Option<usize>,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    mmap: MmapArgs,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    cli.mmap.apply();
+    policy::enforce(&cli.files, Access::Read)?;
+
+    let files = if cli.files.is_empty() {
+        vec![PathBuf::from("-")]
+    } else {
+        cli.files.clone()
+    };
+    let seed = cli.seed.unwrap_or_else(Rng::random_seed);
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut errors = 0u64;
+
+    let mut inputs = Vec::new();
+    for path in &files {
+        match SafeMemoryAccess::open_or_stdin(path) {
+            Ok(input) => inputs.push((path.to_string_lossy(), input)),
+            Err(e) => {
+                jsonl::output_error(&e.to_string(), "SHUF_ERROR", Some(&path.to_string_lossy()))?;
+                errors += 1;
+            }
+        }
+    }
+
+    let mut lines: Vec<(usize, RawLine)> = inputs
+        .iter()
+        .enumerate()
+        .flat_map(|(file, (_, input))| raw_lines(input.as_slice()).map(move |line| (file, line)))
+        .collect();
+    let count = cli.head_count.unwrap_or(lines.len()).min(lines.len());
+    Rng::new(seed).partial_shuffle(&mut lines, count);
+
+    for (position, (file, line)) in lines[..count].iter().enumerate() {
+        JsonlRecord::result(serde_json::json!({
+            "type": "shuffled_line",
+            "file": inputs[*file].0,
+            "line_number": line.number,
+            "offset": line.offset,
+            "position": position + 1,
+            "content": line.to_string_lossy(),
+        })).write_line(&mut out)?;
+    }
+
+    JsonlRecord::summary(serde_json::json!({
+        "type": "shuf_summary",
+        "files": files.len(),
+        "lines": lines.len(),
+        "output": count,
+        "seed": seed,
+        "errors": errors,
+    })).write_line(&mut out)?;
+    out.flush()?;
+
+    if errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
@@ -0,0 +1,207 @@
+//! AI-optimized snapshot utility
+//!
+//! Records the size, mtime and digest of every file in a tree, then
+//! reports which files were added, removed or changed since, against a
+//! later snapshot or the live tree. Live comparisons only re-hash files
+//! whose size or mtime moved, so "what did my last action change?" is
+//! cheap to ask even of large trees.
+
+use ai_coreutils::{
+    checksum::DriftKind,
+    config,
+    hash::HashAlgorithm,
+    jsonl,
+    policy::{self, Access},
+    snapshot::{Change, SnapshotEntry, TreeSnapshot},
+    AiCoreutilsError, Result,
+};
+use clap::{Parser, Subcommand};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// AI-optimized snapshot: Record a tree and report what changed since
+#[derive(Parser, Debug)]
+#[command(name = "ai-snapshot")]
+#[command(about = "Snapshot a directory tree and diff it against later states", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Record the size, mtime and digest of every file under a directory
+    Create {
+        /// Directory to snapshot
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+
+        /// Snapshot file to write
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+
+        /// Digest algorithm: crc32, xxh64, xxh3 or blake3 (if built with it)
+        #[arg(short, long, default_value = "xxh3")]
+        algorithm: HashAlgorithm,
+    },
+
+    /// Report files added, removed or changed since a snapshot
+    ///
+    /// Compares with a second snapshot if one is given, otherwise with the
+    /// files under the snapshot's directory now.
+    Diff {
+        /// Snapshot to compare from
+        base: PathBuf,
+
+        /// Later snapshot to compare with, instead of the live tree
+        other: Option<PathBuf>,
+
+        /// Directory to compare with, instead of the one the snapshot was taken of
+        #[arg(long, value_name = "DIR", conflicts_with = "other")]
+        root: Option<PathBuf>,
+
+        /// Hash every file, even those whose size and mtime are unchanged
+        #[arg(long, conflicts_with = "other")]
+        full: bool,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    match &cli.command {
+        Command::Create { dir, output, .. } => {
+            policy::enforce([dir], Access::Read)?;
+            policy::enforce([output], Access::Write)?;
+        }
+        Command::Diff { base, other, root, .. } => {
+            let paths = [Some(base), other.as_ref(), root.as_ref()];
+            policy::enforce(paths.into_iter().flatten(), Access::Read)?;
+        }
+    }
+
+    let ok = match &cli.command {
+        Command::Create { dir, output, algorithm } => create_main(dir, output, *algorithm)?,
+        Command::Diff { base, other, root, full } => diff_main(base, other.as_deref(), root.as_deref(), *full)?,
+    };
+
+    if !ok {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn create_main(dir: &Path, output: &Path, algorithm: HashAlgorithm) -> Result<bool> {
+    let (snapshot, errors) = match TreeSnapshot::create(dir, algorithm, &[output.to_path_buf()]) {
+        Ok(created) => created,
+        Err(e) => return report_failure(dir, e),
+    };
+    for (path, e) in &errors {
+        report_error(path, e)?;
+    }
+    if let Err(e) = snapshot.save(output) {
+        return report_failure(output, e);
+    }
+
+    jsonl::output_result(serde_json::json!({
+        "type": "snapshot",
+        "root": dir.display().to_string(),
+        "snapshot": output.display().to_string(),
+        "algorithm": algorithm.as_str(),
+        "created": snapshot.created.to_rfc3339(),
+        "files": snapshot.files.len(),
+        "bytes": snapshot.bytes(),
+        "errors": errors.len(),
+    }))?;
+    Ok(errors.is_empty())
+}
+
+fn diff_main(base_path: &Path, other: Option<&Path>, root: Option<&Path>, full: bool) -> Result<bool> {
+    let base = match TreeSnapshot::load(base_path) {
+        Ok(base) => base,
+        Err(e) => return report_failure(base_path, e),
+    };
+
+    let mut counts = BTreeMap::from([("added", 0u64), ("removed", 0), ("changed", 0)]);
+    let mut summary = serde_json::json!({
+        "type": "snapshot_diff",
+        "base": base_path.display().to_string(),
+    });
+
+    let (changes, errors) = match other {
+        Some(other_path) => {
+            let changes = TreeSnapshot::load(other_path).and_then(|other| base.diff(&other));
+            match changes {
+                Ok(changes) => {
+                    summary["against"] = serde_json::json!(other_path.display().to_string());
+                    (changes, Vec::new())
+                }
+                Err(e) => return report_failure(other_path, e),
+            }
+        }
+        None => {
+            let root = root.unwrap_or(&base.root);
+            match base.diff_live(root, &[base_path.to_path_buf()], full) {
+                Ok(diff) => {
+                    summary["against"] = serde_json::json!("live");
+                    summary["root"] = serde_json::json!(root.display().to_string());
+                    summary["unchanged"] = serde_json::json!(diff.unchanged);
+                    summary["rehashed"] = serde_json::json!(diff.rehashed);
+                    (diff.changes, diff.errors)
+                }
+                Err(e) => return report_failure(root, e),
+            }
+        }
+    };
+
+    for (path, e) in &errors {
+        report_error(path, e)?;
+    }
+    for change in &changes {
+        *counts.entry(change.kind.as_str()).or_insert(0) += 1;
+        output_change(change)?;
+    }
+
+    for (kind, count) in counts {
+        summary[kind] = serde_json::json!(count);
+    }
+    summary["errors"] = serde_json::json!(errors.len());
+    jsonl::output_result(summary)?;
+    Ok(changes.is_empty() && errors.is_empty())
+}
+
+fn output_change(change: &Change) -> Result<()> {
+    let entry = |entry: &Option<SnapshotEntry>| {
+        entry.as_ref().map(|e| {
+            serde_json::json!({
+                "size": e.size,
+                "mtime": e.mtime.to_rfc3339(),
+                "digest": e.digest,
+            })
+        })
+    };
+    let mut record = serde_json::json!({
+        "type": "snapshot_change",
+        "path": change.path.display().to_string(),
+        "change": change.kind.as_str(),
+        "before": entry(&change.before),
+        "after": entry(&change.after),
+    });
+    if change.kind == DriftKind::Changed {
+        if let (Some(before), Some(after)) = (&change.before, &change.after) {
+            record["size_delta"] = serde_json::json!(after.size as i64 - before.size as i64);
+        }
+    }
+    jsonl::output_result(record)
+}
+
+/// Report a failure that stops the command
+fn report_failure(path: &Path, error: AiCoreutilsError) -> Result<bool> {
+    report_error(path, &error)?;
+    Ok(false)
+}
+
+/// Report a per-path failure; processing continues
+fn report_error(path: &Path, error: &AiCoreutilsError) -> Result<()> {
+    jsonl::output_error(&error.to_string(), "SNAPSHOT_ERROR", Some(&path.to_string_lossy()))
+}
@@ -0,0 +1,172 @@
+//! AI-optimized strings utility
+//!
+//! Extracts printable ASCII and UTF-16LE runs from binary files as JSONL
+//! records with their offset and encoding, using a SIMD printable-byte
+//! classifier so multi-gigabyte images and core dumps scan at memory speed.
+
+use ai_coreutils::{cli::{LimitArgs, MmapArgs}, config, jsonl, JsonlRecord, PrintableEncoding, Result, SafeMemoryAccess, SimdPrintableScanner};
+use ai_coreutils::policy::{self, Access};
+use clap::{Parser, ValueEnum};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// AI-optimized strings: Printable runs in binary files as JSONL records
+///
+/// This utility provides:
+/// - SIMD detection of printable ASCII and UTF-16LE (wide) strings
+/// - File offset and encoding of every string
+/// - Minimum length and regex filtering
+#[derive(Parser, Debug)]
+#[command(name = "ai-strings")]
+#[command(about = "Extract printable strings from binary files as JSONL", long_about = None)]
+struct Cli {
+    /// Files to scan ("-" for stdin)
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// Minimum string length in characters
+    #[arg(short = 'n', long, default_value = "4")]
+    min_length: usize,
+
+    /// Encodings to extract
+    #[arg(short, long, value_enum, default_value = "all")]
+    encoding: EncodingArg,
+
+    /// Only output strings matching this regex
+    #[arg(short, long)]
+    regex: Option<String>,
+
+    /// Only output the summary
+    #[arg(short, long)]
+    quiet: bool,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    mmap: MmapArgs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum EncodingArg {
+    /// Printable ASCII
+    Ascii,
+    /// Little-endian UTF-16 (Windows wide strings)
+    Utf16le,
+    /// Both
+    All,
+}
+
+impl EncodingArg {
+    fn encodings(self) -> &'static [PrintableEncoding] {
+        match self {
+            EncodingArg::Ascii => &[PrintableEncoding::Ascii],
+            EncodingArg::Utf16le => &[PrintableEncoding::Utf16Le],
+            EncodingArg::All => &[PrintableEncoding::Ascii, PrintableEncoding::Utf16Le],
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    strings: u64,
+    output: u64,
+    errors: u64,
+    by_encoding: BTreeMap<&'static str, u64>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    cli.mmap.apply();
+    policy::enforce(&cli.files, Access::Read)?;
+
+    let regex = match cli.regex.as_deref().map(Regex::new).transpose() {
+        Ok(regex) => regex,
+        Err(e) => {
+            jsonl::output_error(&format!("Invalid regex: {}", e), "STRINGS_ERROR", None)?;
+            std::process::exit(2);
+        }
+    };
+    let scanner = SimdPrintableScanner::new();
+    let mut stats = Stats::default();
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+
+    for path in &cli.files {
+        let name = path.to_string_lossy();
+        let input = match read_input(path) {
+            Ok(input) => input,
+            Err(e) => {
+                out.flush()?;
+                jsonl::output_error(&e.to_string(), "STRINGS_ERROR", Some(&name))?;
+                stats.errors += 1;
+                continue;
+            }
+        };
+        let data = input.bytes();
+
+        for run in scanner.find_runs(data, cli.min_length, cli.encoding.encodings()) {
+            stats.strings += 1;
+            *stats.by_encoding.entry(run.encoding.as_str()).or_insert(0) += 1;
+            let text = run.text(data);
+            if regex.as_ref().is_some_and(|regex| !regex.is_match(&text)) {
+                continue;
+            }
+
+            stats.output += 1;
+            if !cli.quiet {
+                JsonlRecord::result(serde_json::json!({
+                    "type": "string",
+                    "file": name,
+                    "offset": run.range.start,
+                    "encoding": run.encoding.as_str(),
+                    "length": run.chars(),
+                    "string": text,
+                })).write_line(&mut out)?;
+            }
+        }
+    }
+
+    JsonlRecord::summary(serde_json::json!({
+        "type": "strings_summary",
+        "files": cli.files.len(),
+        "strings": stats.strings,
+        "output": stats.output,
+        "errors": stats.errors,
+        "by_encoding": stats.by_encoding,
+    })).write_line(&mut out)?;
+    out.flush()?;
+
+    if stats.errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Input bytes, mapped from a file or read from stdin
+enum Input {
+    Mapped(SafeMemoryAccess),
+    Buffer(Vec<u8>),
+}
+
+impl Input {
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Input::Mapped(mem) => mem.as_slice(),
+            Input::Buffer(buffer) => buffer,
+        }
+    }
+}
+
+fn read_input(path: &Path) -> Result<Input> {
+    if path.to_str() == Some("-") {
+        let mut buffer = Vec::new();
+        io::stdin().read_to_end(&mut buffer)?;
+        return Ok(Input::Buffer(buffer));
+    }
+    Ok(Input::Mapped(SafeMemoryAccess::new(path)?))
+}
@@ -1,15 +1,21 @@
-use ai_coreutils::{jsonl, memory::SafeMemoryAccess, Result};
+use ai_coreutils::{
+    cli::{self, LimitArgs, LogArgs, MapArgs, MmapArgs},
+    config, jsonl::{self, LogLevel}, limits,
+    memory::{AccessPattern, LineIndex, ReverseReader, SafeMemoryAccess},
+    Result,
+};
+use ai_coreutils::policy::{self, Access};
 use clap::Parser;
 use std::fs::File;
-use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
-use std::path::PathBuf;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 
 /// AI-optimized tail utility - Output last part of files
 ///
 /// This utility extends GNU tail with:
 /// - JSONL structured output
-/// - Memory-mapped file access for large files
-/// - Detailed metadata
+/// - Memory-mapped file access for large files, scanned back from the end
+/// - Exact byte offsets of the output
 #[derive(Parser, Debug)]
 #[command(name = "ai-tail")]
 #[command(about = "Output last part of files", long_about = None)]
@@ -18,13 +24,13 @@ struct Cli {
     #[arg(required = false)]
     files: Vec<PathBuf>,
 
-    /// Number of lines to show
-    #[arg(short = 'n', long, default_value = "10")]
-    lines: usize,
+    /// Number of lines to show; +K starts at line K
+    #[arg(short = 'n', long, default_value = "10", allow_hyphen_values = true)]
+    lines: Count,
 
-    /// Number of bytes to show
-    #[arg(short = 'c', long)]
-    bytes: Option<usize>,
+    /// Number of bytes to show; +K starts at byte K
+    #[arg(short = 'c', long, allow_hyphen_values = true)]
+    bytes: Option<Count>,
 
     /// Follow file (output appended data as file grows)
     #[arg(short = 'f', long)]
@@ -41,23 +47,83 @@ struct Cli {
     /// Zero-terminated output
     #[arg(short = 'z', long)]
     zero_terminated: bool,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    mmap: MmapArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
+    #[command(flatten)]
+    map: MapArgs,
+}
+
+/// A `-n`/`-c` count: the last N units, or everything from the Nth with `+N`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Count {
+    n: usize,
+    from_start: bool,
+}
+
+impl std::str::FromStr for Count {
+    type Err = String;
+
+    /// `N` or `-N` for the last N, `+N` for the Nth onwards (1-based, as
+    /// GNU tail); N takes a K, M or G suffix
+    fn from_str(s: &str) -> std::result::Result<Self, String> {
+        let (from_start, n) = match s.strip_prefix('+') {
+            Some(n) => (true, n),
+            None => (false, s.strip_prefix('-').unwrap_or(s)),
+        };
+        let n = cli::parse_size(n)?;
+        Ok(Count { n: usize::try_from(n).unwrap_or(usize::MAX), from_start })
+    }
+}
+
+impl Cli {
+    fn count(&self) -> Count {
+        self.bytes.unwrap_or(self.lines)
+    }
+
+    fn separator(&self) -> u8 {
+        if self.zero_terminated {
+            b'\0'
+        } else {
+            b'\n'
+        }
+    }
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    cli.mmap.apply();
+    cli.log.apply();
+    policy::enforce(&cli.files, Access::Read)?;
 
     // If no files specified, read from stdin
     if cli.files.is_empty() {
-        handle_stdin(&cli)?;
-        return Ok(());
+        return match handle_stdin(&cli) {
+            Ok(bytes_read) => output_summary(1, 0, bytes_read),
+            Err(e) => {
+                jsonl::output_error(&format!("Failed to read stdin: {}", e), "TAIL_ERROR", Some("stdin"))?;
+                output_summary(0, 1, 0)
+            }
+        };
     }
 
     let use_bytes = cli.bytes.is_some();
-    let count = cli.bytes.unwrap_or(cli.lines);
+    let count = cli.count();
 
     // Output start message
     jsonl::output_progress(0, cli.files.len(), "Starting tail operation")?;
 
+    let (mut errors, mut total_bytes) = (0, 0);
+
     for (index, file) in cli.files.iter().enumerate() {
         // Update progress
         jsonl::output_progress(
@@ -73,18 +139,33 @@ fn main() -> Result<()> {
             println!("==> {} <==", file.display());
         }
 
-        match tail_file(file, count, use_bytes, cli.zero_terminated, cli.follow) {
-            Ok(bytes_read) => {
+        // A fresh ai-index sidecar gives the start of the last N lines
+        // without scanning backwards
+        let line_index = if use_bytes || cli.zero_terminated {
+            None
+        } else {
+            LineIndex::load_fresh(file)
+        };
+
+        match tail_file(file, &cli, line_index.as_ref()) {
+            Ok((start, size)) => {
+                total_bytes += size - start;
                 jsonl::output_info(serde_json::json!({
                     "file": file.display().to_string(),
                     "operation": "tail",
                     "unit": if use_bytes { "bytes" } else { "lines" },
-                    "count": count,
-                    "bytes_read": bytes_read,
+                    "count": count.n,
+                    "from_start": count.from_start,
+                    "byte_start": start,
+                    "byte_end": size,
+                    "file_size": size,
+                    "bytes_read": size - start,
                     "following": cli.follow,
+                    "line_index": line_index.is_some(),
                 }))?;
             }
             Err(e) => {
+                errors += 1;
                 jsonl::output_error(
                     &format!("Failed to read {}: {}", file.display(), e),
                     "TAIL_ERROR",
@@ -99,156 +180,101 @@ fn main() -> Result<()> {
         }
     }
 
-    Ok(())
+    output_summary(cli.files.len() - errors, errors, total_bytes)
 }
 
-fn handle_stdin(cli: &Cli) -> Result<()> {
-    let use_bytes = cli.bytes.is_some();
-    let count = cli.bytes.unwrap_or(cli.lines);
-
-    if use_bytes {
-        // Read all and keep last N bytes
-        let mut stdin = io::stdin();
-        let mut all_data = Vec::new();
-        stdin.read_to_end(&mut all_data)?;
-
-        let start = if all_data.len() > count {
-            all_data.len() - count
-        } else {
-            0
-        };
-
-        io::stdout().write_all(&all_data[start..])?;
-    } else {
-        // Read all lines and keep last N
-        let separator = if cli.zero_terminated { b'\0' } else { b'\n' };
-        let stdin = io::stdin();
-        let reader = stdin.lock();
-        let line_reader = io::BufReader::new(reader);
-
-        let lines: io::Result<Vec<Vec<u8>>> = line_reader.split(separator).collect();
-        let lines = lines?;
+/// Emit the `tail_summary` record
+fn output_summary(files: usize, errors: usize, bytes_read: usize) -> Result<()> {
+    jsonl::output_summary(serde_json::json!({
+        "type": "tail_summary",
+        "files": files,
+        "errors": errors,
+        "bytes_read": bytes_read,
+    }))
+}
 
-        let start = if lines.len() > count {
-            lines.len() - count
-        } else {
-            0
-        };
+/// Copy the end of stdin to stdout, returning the bytes output
+fn handle_stdin(cli: &Cli) -> Result<usize> {
+    // A pipe cannot be read from the end, so it is read whole
+    let input = SafeMemoryAccess::open_or_stdin("-")?;
+    let data = input.as_slice();
 
-        for line in &lines[start..] {
-            io::stdout().write_all(line)?;
-            io::stdout().write_all(&[separator])?;
-        }
-    }
+    let start = output_start(data, cli, None);
+    io::stdout().write_all(&data[start..])?;
 
-    Ok(())
+    Ok(data.len() - start)
 }
 
-fn tail_file(
-    file: &PathBuf,
-    count: usize,
-    use_bytes: bool,
-    zero_terminated: bool,
-    _follow: bool,
-) -> Result<usize> {
-    // Try to use memory mapping for files
-    if let Ok(mmap) = SafeMemoryAccess::new(file) {
-        return tail_mmap(&mmap, count, use_bytes, zero_terminated);
+/// Write the selected end of `file` to stdout
+///
+/// Returns the offset the output started at and the file size, where it
+/// ended.
+fn tail_file(file: &Path, cli: &Cli, index: Option<&LineIndex>) -> Result<(usize, usize)> {
+    // Only the pages of the output are read, wherever the lines start, so
+    // read-ahead from the front of the file is wasted
+    let options = cli.map.options(AccessPattern::Random);
+    if let Ok(mmap) = SafeMemoryAccess::with_options(file, &options) {
+        MapArgs::report(&options, &mmap.map_options(), file)?;
+        let data = mmap.as_slice();
+        let start = output_start(data, cli, index);
+        io::stdout().write_all(&data[start..])?;
+        // A log rotated or appended to meanwhile was output as it was
+        if let Err(e) = mmap.verify() {
+            jsonl::output_warning(LogLevel::Warn, "FILE_CHANGED", &e.to_string(), Some(&file.display().to_string()))?;
+        }
+        return Ok((start, data.len()));
     }
 
     // Fall back to standard I/O
     let mut f = File::open(file).map_err(ai_coreutils::AiCoreutilsError::Io)?;
     let metadata = f.metadata()?;
-    let file_size = metadata.len() as usize;
+    limits::read_file(file, metadata.len())?;
 
-    if use_bytes {
-        // Seek to position and read
-        let start = file_size.saturating_sub(count);
+    // Only the bytes output are read, unless the size is unknown, as for
+    // procfs files that report none
+    if cli.bytes.is_some() && metadata.len() > 0 {
+        let start = byte_start(metadata.len() as usize, cli.count());
         f.seek(SeekFrom::Start(start as u64))?;
-
-        let mut buffer = Vec::new();
-        f.read_to_end(&mut buffer)?;
-        io::stdout().write_all(&buffer)?;
-
-        return Ok(buffer.len());
+        let mut data = Vec::new();
+        f.read_to_end(&mut data)?;
+        io::stdout().write_all(&data)?;
+        return Ok((start, start + data.len()));
     }
 
-    // For lines, we need to read backwards
-    // Read the whole file for simplicity (could be optimized)
-    let mut content = String::new();
-    f.read_to_string(&mut content)?;
-
-    let separator = if zero_terminated { '\0' } else { '\n' };
-    let lines: Vec<&str> = content.split(separator).collect();
+    let mut data = Vec::new();
+    f.read_to_end(&mut data)?;
+    let start = output_start(&data, cli, index);
+    io::stdout().write_all(&data[start..])?;
+    Ok((start, data.len()))
+}
 
-    let start = if lines.len() > count {
-        lines.len() - count
+/// Offset at which `-c` output starts in a file of `size` bytes
+fn byte_start(size: usize, Count { n, from_start }: Count) -> usize {
+    if from_start {
+        n.saturating_sub(1).min(size)
     } else {
-        0
-    };
-
-    let mut bytes_written = 0;
-    for line in &lines[start..] {
-        bytes_written += line.len() + 1;
-        print!("{}{}", line, separator);
+        size.saturating_sub(n)
     }
-
-    Ok(bytes_written)
 }
 
-fn tail_mmap(
-    mmap: &SafeMemoryAccess,
-    count: usize,
-    use_bytes: bool,
-    zero_terminated: bool,
-) -> Result<usize> {
-    let size = mmap.size();
-
-    if use_bytes {
-        // Read last N bytes
-        let start = size.saturating_sub(count);
-        let bytes_to_read = size - start;
-
-        if let Some(data) = mmap.get(start, bytes_to_read) {
-            io::stdout().write_all(data)?;
-            return Ok(bytes_to_read);
-        }
-        return Ok(0);
-    }
-
-    // Read last N lines
-    // Scan backwards from end
-    let separator = if zero_terminated { 0 } else { b'\n' };
-    let mut lines_found = 0;
-    let mut start = size;
-
-    // Scan backwards looking for line separators
-    for i in (0..size).rev() {
-        let byte = mmap.get(i, 1).map(|bytes| bytes[0]);
-
-        if byte == Some(separator) || byte == Some(b'\n') {
-            lines_found += 1;
-            start = i + 1;
-
-            if lines_found > count {
-                break;
-            }
-        }
-    }
-
-    // If we didn't find enough lines, start from beginning
-    if lines_found < count {
-        start = 0;
+/// Offset in `data` at which tail's output starts
+///
+/// Line counts are looked up in `index` when there is one, and otherwise
+/// found by scanning back from the end (forwards for `+K`).
+fn output_start(data: &[u8], cli: &Cli, index: Option<&LineIndex>) -> usize {
+    let size = data.len();
+    let Count { n, from_start } = cli.count();
+
+    if cli.bytes.is_some() {
+        return byte_start(size, cli.count());
     }
 
-    // Output the data
-    if start < size {
-        let bytes_to_read = size - start;
-        if let Some(data) = mmap.get(start, bytes_to_read) {
-            io::stdout().write_all(data)?;
-            return Ok(bytes_to_read);
-        }
+    // `+0` means the same as `+1`: everything
+    let skip = n.saturating_sub(1);
+    match index {
+        Some(index) if from_start => index.line_start(skip).min(size),
+        Some(index) => index.line_start(index.line_count().saturating_sub(n)).min(size),
+        None if from_start => ReverseReader::new(data, cli.separator()).skip_lines(skip),
+        None => ReverseReader::new(data, cli.separator()).last_lines(n),
     }
-
-    Ok(0)
 }
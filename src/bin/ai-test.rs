@@ -0,0 +1,255 @@
+//! AI-optimized test utility
+//!
+//! Evaluates file predicates, as `test` / `[` does, and reports the answer
+//! as one JSONL record with the outcome of each check and the facts behind
+//! it, plus an exit code agents can branch on: 0 true, 1 false, 2 error.
+
+use ai_coreutils::{
+    config,
+    fs_utils::{accessible, AccessCheck},
+    jsonl, JsonlRecord, Result,
+};
+use ai_coreutils::cli::parse_size;
+use ai_coreutils::policy::{self, Access};
+use chrono::{DateTime, Utc};
+use clap::Parser;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// AI-optimized test: File predicates as a JSONL boolean
+///
+/// This utility provides:
+/// - `test`-style checks: exists, type, permissions, size, age
+/// - One result record with the outcome and details of each check
+/// - Exit code 0 when true, 1 when false, 2 on error
+#[derive(Parser, Debug)]
+#[command(name = "ai-test")]
+#[command(about = "Evaluate file predicates as a JSONL boolean with a matching exit code", long_about = None)]
+struct Cli {
+    /// Path to test
+    path: PathBuf,
+
+    /// Path exists (the default check when none is given)
+    #[arg(short, long)]
+    exists: bool,
+
+    /// Path is a regular file
+    #[arg(short, long)]
+    file: bool,
+
+    /// Path is a directory
+    #[arg(short, long)]
+    dir: bool,
+
+    /// Path is a symbolic link
+    #[arg(short = 'L', long)]
+    symlink: bool,
+
+    /// Path is readable by this process
+    #[arg(short, long)]
+    readable: bool,
+
+    /// Path is writable by this process
+    #[arg(short, long)]
+    writable: bool,
+
+    /// Path is executable (searchable, for a directory) by this process
+    #[arg(short = 'x', long)]
+    executable: bool,
+
+    /// Path is larger than zero bytes
+    #[arg(short = 's', long)]
+    non_empty: bool,
+
+    /// Path is larger than SIZE bytes (suffix K/M/G)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    size_gt: Option<u64>,
+
+    /// Path is smaller than SIZE bytes (suffix K/M/G)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    size_lt: Option<u64>,
+
+    /// Path was modified after FILE, or exists while FILE does not
+    #[arg(long, value_name = "FILE")]
+    newer_than: Option<PathBuf>,
+
+    /// Path was modified before FILE, or does not exist while FILE does
+    #[arg(long, value_name = "FILE")]
+    older_than: Option<PathBuf>,
+
+    /// True if any check holds, rather than all of them
+    #[arg(long)]
+    any: bool,
+
+    /// Negate the result
+    #[arg(short, long)]
+    not: bool,
+}
+
+/// The outcome of one predicate
+struct Check {
+    predicate: &'static str,
+    result: bool,
+    detail: serde_json::Value,
+}
+
+impl Check {
+    fn new(predicate: &'static str, result: bool) -> Self {
+        Self { predicate, result, detail: serde_json::Value::Null }
+    }
+
+    fn with_detail(mut self, detail: serde_json::Value) -> Self {
+        self.detail = detail;
+        self
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let mut check = serde_json::json!({ "predicate": self.predicate, "result": self.result });
+        if !self.detail.is_null() {
+            check["detail"] = self.detail.clone();
+        }
+        check
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    policy::enforce(std::iter::once(&cli.path).chain(&cli.newer_than).chain(&cli.older_than), Access::Read)?;
+
+    let checks = match evaluate(&cli) {
+        Ok(checks) => checks,
+        Err(e) => {
+            jsonl::output_error(&e.to_string(), "TEST_ERROR", Some(&cli.path.to_string_lossy()))?;
+            std::process::exit(2);
+        }
+    };
+    let holds = if cli.any {
+        checks.iter().any(|check| check.result)
+    } else {
+        checks.iter().all(|check| check.result)
+    };
+    let result = holds != cli.not;
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    JsonlRecord::result(serde_json::json!({
+        "type": "test_result",
+        "path": cli.path.to_string_lossy(),
+        "result": result,
+        "mode": if cli.any { "any" } else { "all" },
+        "negated": cli.not,
+        "checks": checks.iter().map(Check::to_json).collect::<Vec<_>>(),
+        "file": describe(&cli.path),
+    })).write_line(&mut out)?;
+    out.flush()?;
+
+    if !result {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn evaluate(cli: &Cli) -> Result<Vec<Check>> {
+    let path = &cli.path;
+    let metadata = fs::metadata(path).ok();
+    let size = metadata.as_ref().filter(|m| m.is_file()).map(|m| m.len());
+    let mut checks = Vec::new();
+
+    if cli.file {
+        checks.push(Check::new("file", metadata.as_ref().is_some_and(|m| m.is_file())));
+    }
+    if cli.dir {
+        checks.push(Check::new("dir", metadata.as_ref().is_some_and(|m| m.is_dir())));
+    }
+    if cli.symlink {
+        let is_symlink = fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink());
+        checks.push(Check::new("symlink", is_symlink));
+    }
+    for (wanted, check, name) in [
+        (cli.readable, AccessCheck::Read, "readable"),
+        (cli.writable, AccessCheck::Write, "writable"),
+        (cli.executable, AccessCheck::Execute, "executable"),
+    ] {
+        if wanted {
+            checks.push(Check::new(name, accessible(path, check)));
+        }
+    }
+    if cli.non_empty {
+        let len = metadata.as_ref().map(|m| m.len());
+        checks.push(Check::new("non_empty", len.is_some_and(|len| len > 0)).with_detail(serde_json::json!({ "size": len })));
+    }
+    if let Some(limit) = cli.size_gt {
+        checks.push(
+            Check::new("size_gt", size.is_some_and(|size| size > limit))
+                .with_detail(serde_json::json!({ "size": size, "limit": limit })),
+        );
+    }
+    if let Some(limit) = cli.size_lt {
+        checks.push(
+            Check::new("size_lt", size.is_some_and(|size| size < limit))
+                .with_detail(serde_json::json!({ "size": size, "limit": limit })),
+        );
+    }
+    let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+    if let Some(reference) = &cli.newer_than {
+        checks.push(compare_times("newer_than", modified, reference, |a, b| a > b)?);
+    }
+    if let Some(reference) = &cli.older_than {
+        checks.push(compare_times("older_than", modified, reference, |a, b| a < b)?);
+    }
+    if cli.exists || checks.is_empty() {
+        checks.insert(0, Check::new("exists", metadata.is_some()));
+    }
+    Ok(checks)
+}
+
+/// Compare modification times as `test -nt` and `-ot` do: a missing path
+/// is older than any existing one
+fn compare_times(
+    predicate: &'static str,
+    modified: Option<SystemTime>,
+    reference: &Path,
+    newer: fn(Option<SystemTime>, Option<SystemTime>) -> bool,
+) -> Result<Check> {
+    let reference_modified = match fs::metadata(reference) {
+        Ok(metadata) => Some(metadata.modified()?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Check::new(predicate, newer(modified, reference_modified)).with_detail(serde_json::json!({
+        "reference": reference.to_string_lossy(),
+        "modified": modified.map(timestamp),
+        "reference_modified": reference_modified.map(timestamp),
+    })))
+}
+
+/// What is at `path`, for the result record; `null` if nothing
+fn describe(path: &Path) -> serde_json::Value {
+    let Ok(link) = fs::symlink_metadata(path) else {
+        return serde_json::Value::Null;
+    };
+    let target = fs::metadata(path).ok();
+    let file_type = match &target {
+        Some(m) if m.is_dir() => "directory",
+        Some(m) if m.is_file() => "file",
+        Some(_) => "other",
+        None => "broken_symlink",
+    };
+    let mut file = serde_json::json!({
+        "type": file_type,
+        "symlink": link.file_type().is_symlink(),
+        "size": target.as_ref().map(|m| m.len()),
+        "modified": target.as_ref().and_then(|m| m.modified().ok()).map(timestamp),
+    });
+    if link.file_type().is_symlink() {
+        file["symlink_target"] = fs::read_link(path).ok().map(|t| t.to_string_lossy().into_owned()).into();
+    }
+    file
+}
+
+fn timestamp(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339()
+}
@@ -1,4 +1,5 @@
-use ai_coreutils::{AiCoreutilsError, jsonl, Result};
+use ai_coreutils::{config, telemetry, AiCoreutilsError, jsonl, Result};
+use ai_coreutils::policy::{self, Access};
 use clap::Parser;
 use std::fs;
 use std::path::PathBuf;
@@ -44,6 +45,9 @@ struct Cli {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    config::init()?;
+    policy::enforce(&cli.files, Access::Write)?;
+    policy::enforce(&cli.reference, Access::Read)?;
 
     // Output start message
     jsonl::output_progress(0, cli.files.len(), "Starting touch operation")?;
@@ -82,12 +86,12 @@ fn main() -> Result<()> {
     }
 
     // Output summary
-    jsonl::output_info(serde_json::json!({
+    jsonl::output_info(telemetry::attach(serde_json::json!({
         "operation": "touch_summary",
         "total_files": cli.files.len(),
         "successful": success_count,
         "errors": error_count,
-    }))?;
+    })))?;
 
     Ok(())
 }
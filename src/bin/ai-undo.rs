@@ -0,0 +1,145 @@
+//! AI-optimized undo utility
+//!
+//! Reverses the most recent operations recorded in the operation journal
+//! by ai-cp, ai-mv, ai-rm, ai-chmod and ai-sd --write: removes what they
+//! created, moves back what they moved, restores what they removed or
+//! overwrote and resets the modes they changed.
+
+use ai_coreutils::ops_journal::{Action, Journal};
+use ai_coreutils::{config, jsonl, policy, Result};
+use clap::Parser;
+
+/// AI-optimized undo: Reverse recent file operations with JSONL output
+///
+/// This utility provides:
+/// - Undo of the last N operations, newest first
+/// - A listing of the journal with each operation's undo state
+/// - Per-action results, keeping backups of anything it could not restore
+#[derive(Parser, Debug)]
+#[command(name = "ai-undo")]
+#[command(about = "Reverse recent ai-cp, ai-mv, ai-rm, ai-chmod and ai-sd operations", long_about = None)]
+struct Cli {
+    /// Operations to undo (default 1), or to list (default all)
+    #[arg(short = 'n', long, value_name = "N")]
+    count: Option<usize>,
+
+    /// List journaled operations, newest first, instead of undoing
+    #[arg(short, long)]
+    list: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+
+    let Some(journal) = Journal::from_config() else {
+        jsonl::output_error(
+            "Operation journal is disabled (journal.enabled or AI_COREUTILS_JOURNAL)",
+            "JOURNAL_ERROR",
+            None,
+        )?;
+        std::process::exit(1);
+    };
+
+    let ok = if cli.list {
+        list_main(&journal, cli.count.unwrap_or(usize::MAX))?
+    } else {
+        undo_main(&journal, cli.count.unwrap_or(1))?
+    };
+
+    if !ok {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn list_main(journal: &Journal, count: usize) -> Result<bool> {
+    let history = match journal.history() {
+        Ok(history) => history,
+        Err(e) => return report_failure(journal, &e.to_string(), "JOURNAL_ERROR"),
+    };
+
+    for entry in history.iter().rev().take(count) {
+        let operation = &entry.operation;
+        jsonl::output_result(serde_json::json!({
+            "type": "journal_entry",
+            "id": operation.id,
+            "tool": operation.tool,
+            "args": operation.args,
+            "cwd": operation.cwd.display().to_string(),
+            "time": operation.time.to_rfc3339(),
+            "actions": operation.actions.len(),
+            "undone": entry.undone.map(|t| t.to_rfc3339()),
+        }))?;
+    }
+
+    jsonl::output_summary(serde_json::json!({
+        "type": "journal_summary",
+        "journal": journal.dir().display().to_string(),
+        "operations": history.len(),
+        "undoable": history.iter().filter(|e| e.undone.is_none()).count(),
+    }))?;
+    Ok(true)
+}
+
+fn undo_main(journal: &Journal, count: usize) -> Result<bool> {
+    let outcomes = match journal.undo(count) {
+        Ok(outcomes) => outcomes,
+        Err(e) if policy::report(&e)? => return Ok(false),
+        Err(e) => return report_failure(journal, &e.to_string(), "JOURNAL_ERROR"),
+    };
+    if outcomes.is_empty() {
+        return report_failure(journal, "Nothing to undo", "NOTHING_TO_UNDO");
+    }
+
+    let (mut reverted, mut failed) = (0, 0);
+    for outcome in &outcomes {
+        let id = &outcome.operation.id;
+        for action in &outcome.reverted {
+            jsonl::output_result(action_record(id, action))?;
+        }
+        for (action, e) in &outcome.failed {
+            jsonl::output_error(&e.to_string(), "UNDO_ERROR", Some(&action.path().to_string_lossy()))?;
+        }
+        reverted += outcome.reverted.len();
+        failed += outcome.failed.len();
+
+        jsonl::output_result(serde_json::json!({
+            "type": "undo_operation",
+            "id": id,
+            "tool": outcome.operation.tool,
+            "args": outcome.operation.args,
+            "time": outcome.operation.time.to_rfc3339(),
+            "reverted": outcome.reverted.len(),
+            "failed": outcome.failed.len(),
+        }))?;
+    }
+
+    jsonl::output_summary(serde_json::json!({
+        "type": "undo_summary",
+        "operations": outcomes.len(),
+        "reverted": reverted,
+        "failed": failed,
+    }))?;
+    Ok(failed == 0)
+}
+
+/// `undo_action` record: the reversed action's fields plus its operation id
+fn action_record(id: &str, action: &Action) -> serde_json::Value {
+    let mut record = serde_json::json!({
+        "type": "undo_action",
+        "operation": id,
+    });
+    if let (Some(record), Ok(serde_json::Value::Object(fields))) =
+        (record.as_object_mut(), serde_json::to_value(action))
+    {
+        record.extend(fields);
+    }
+    record
+}
+
+/// Report a failure that stops the command
+fn report_failure(journal: &Journal, message: &str, code: &str) -> Result<bool> {
+    jsonl::output_error(message, code, Some(&journal.dir().to_string_lossy()))?;
+    Ok(false)
+}
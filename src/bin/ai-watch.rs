@@ -0,0 +1,271 @@
+//! AI-optimized file watching utility
+//!
+//! Polls a set of paths and emits debounced create/modify/delete events as
+//! JSONL, optionally re-running a command after each batch of changes with
+//! its output captured as records.
+
+use ai_coreutils::{
+    config,
+    fs_utils::SymlinkPolicy,
+    jsonl,
+    watch::{self, Debouncer, EventKind, Snapshot, WatchEvent},
+    AiCoreutilsError, Result,
+};
+use ai_coreutils::policy::{self, Access};
+use chrono::{DateTime, Utc};
+use clap::Parser;
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// AI-optimized watch: Report file changes as debounced JSONL events
+///
+/// This utility provides:
+/// - Create, modify and delete events with paths and timestamps
+/// - Debouncing, so a burst of writes becomes one batch
+/// - `--exec` to re-run a command on change, capturing its output
+/// - `--once` and `--timeout` for agents that wait for a single change
+#[derive(Parser, Debug)]
+#[command(name = "ai-watch")]
+#[command(about = "Watch files and directories for changes with debounced JSONL events", long_about = None)]
+struct Cli {
+    /// Files/directories to watch (directories recursively)
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Event kinds to report: create, modify, delete
+    #[arg(long, value_delimiter = ',', default_value = "create,modify,delete")]
+    events: Vec<EventKind>,
+
+    /// Quiet period before a batch of changes is reported, in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 200)]
+    debounce: u64,
+
+    /// How often to check for changes, in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 250)]
+    interval: u64,
+
+    /// Shell command to run after each batch of changes
+    #[arg(long, value_name = "COMMAND")]
+    exec: Option<String>,
+
+    /// Exit after the first batch of changes (and its command)
+    #[arg(long)]
+    once: bool,
+
+    /// Stop watching after this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    timeout: Option<f64>,
+
+    /// When to follow symlinks: never, command-line or always
+    #[arg(long, value_name = "WHEN", default_value = "command-line")]
+    follow_symlinks: SymlinkPolicy,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    config::init()?;
+    policy::enforce(&cli.paths, Access::Read)?;
+
+    if let Err(e) = watch::validate_paths(&cli.paths) {
+        jsonl::output_error(&e.to_string(), "WATCH_ERROR", None)?;
+        std::process::exit(1);
+    }
+    let timeout = match cli.timeout {
+        Some(secs) if !secs.is_finite() || secs < 0.0 => {
+            jsonl::output_error(&format!("Invalid timeout: {}", secs), "INVALID_INPUT", None)?;
+            std::process::exit(1);
+        }
+        Some(secs) => Some(Duration::from_secs_f64(secs)),
+        None => None,
+    };
+
+    let (mut snapshot, errors) = Snapshot::take(&cli.paths, cli.follow_symlinks);
+    // Unreadable directories would otherwise be reported on every poll
+    let mut reported = HashSet::new();
+    report_errors(errors, &mut reported)?;
+
+    let kinds: Vec<&str> = cli.events.iter().map(EventKind::as_str).collect();
+    jsonl::output_info(serde_json::json!({
+        "type": "watch_started",
+        "paths": cli.paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>(),
+        "events": kinds,
+        "files": snapshot.len(),
+        "debounce_ms": cli.debounce,
+        "interval_ms": cli.interval,
+    }))?;
+
+    let started = Instant::now();
+    let mut debouncer = Debouncer::new(Duration::from_millis(cli.debounce));
+    let mut batches = 0;
+    let mut exit_code = 0;
+    let reason = loop {
+        if timeout.is_some_and(|t| started.elapsed() >= t) {
+            break "timeout";
+        }
+        thread::sleep(Duration::from_millis(cli.interval));
+
+        let (next, errors) = Snapshot::take(&cli.paths, cli.follow_symlinks);
+        report_errors(errors, &mut reported)?;
+        let now = Instant::now();
+        for event in snapshot.diff(&next) {
+            debouncer.push(event, now);
+        }
+        snapshot = next;
+
+        let Some(batch) = debouncer.ready(now) else {
+            continue;
+        };
+        let batch: Vec<WatchEvent> = batch.into_iter().filter(|e| cli.events.contains(&e.kind)).collect();
+        if batch.is_empty() {
+            continue;
+        }
+
+        batches += 1;
+        output_batch(batches, &batch)?;
+        if let Some(command) = &cli.exec {
+            exit_code = run_command(command, batches, &batch)?;
+        }
+        if cli.once {
+            break "once";
+        }
+    };
+
+    jsonl::output_info(serde_json::json!({
+        "type": "watch_stopped",
+        "reason": reason,
+        "batches": batches,
+        "elapsed_ms": started.elapsed().as_millis() as u64,
+    }))?;
+
+    // With --once, the command's status is the outcome the caller waited for
+    std::process::exit(if cli.once { exit_code } else { 0 });
+}
+
+fn report_errors(errors: Vec<AiCoreutilsError>, reported: &mut HashSet<String>) -> Result<()> {
+    for e in errors {
+        let message = e.to_string();
+        if reported.insert(message.clone()) {
+            jsonl::output_error(&message, "WATCH_ERROR", None)?;
+        }
+    }
+    Ok(())
+}
+
+/// Emit one record per event, then a summary of the batch
+fn output_batch(batch_number: u64, batch: &[WatchEvent]) -> Result<()> {
+    for event in batch {
+        jsonl::output_result(serde_json::json!({
+            "type": "watch_event",
+            "batch": batch_number,
+            "path": event.path.display().to_string(),
+            "kind": event.kind.as_str(),
+            "detected_at": DateTime::<Utc>::from(event.detected_at).to_rfc3339(),
+        }))?;
+    }
+
+    let count = |kind| batch.iter().filter(|e| e.kind == kind).count();
+    jsonl::output_result(serde_json::json!({
+        "type": "watch_batch",
+        "batch": batch_number,
+        "events": batch.len(),
+        "created": count(EventKind::Create),
+        "modified": count(EventKind::Modify),
+        "deleted": count(EventKind::Delete),
+    }))
+}
+
+/// Run `command` through the shell, streaming its output as records
+///
+/// The changed paths are passed in `AI_WATCH_PATHS`, one per line. Returns
+/// the command's exit code (1 if it could not be run or was killed).
+fn run_command(command: &str, batch_number: u64, batch: &[WatchEvent]) -> Result<i32> {
+    let paths: Vec<String> = batch.iter().map(|e| e.path.display().to_string()).collect();
+    let started = Instant::now();
+
+    let spawned = shell(command)
+        .env("AI_WATCH_PATHS", paths.join("\n"))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => {
+            jsonl::output_error(&format!("Failed to run {}: {}", command, e), "COMMAND_ERROR", None)?;
+            return Ok(1);
+        }
+    };
+
+    // Both streams are read on their own threads so neither pipe can fill up
+    let (sender, receiver) = mpsc::channel();
+    let mut readers = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        readers.push(forward_lines("stdout", stdout, sender.clone()));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        readers.push(forward_lines("stderr", stderr, sender.clone()));
+    }
+    drop(sender);
+
+    for (stream, line) in receiver {
+        jsonl::output_info(serde_json::json!({
+            "type": "exec_output",
+            "batch": batch_number,
+            "stream": stream,
+            "line": line,
+        }))?;
+    }
+    for reader in readers {
+        let _ = reader.join();
+    }
+
+    let status = child.wait().map_err(AiCoreutilsError::Io)?;
+    jsonl::output_info(serde_json::json!({
+        "type": "exec_completed",
+        "batch": batch_number,
+        "command": command,
+        "exit_code": status.code(),
+        "success": status.success(),
+        "duration_ms": started.elapsed().as_millis() as u64,
+    }))?;
+    Ok(status.code().unwrap_or(1))
+}
+
+/// Send each line of `pipe`, without its terminator, tagged with `stream`
+fn forward_lines(
+    stream: &'static str,
+    pipe: impl Read + Send + 'static,
+    sender: mpsc::Sender<(&'static str, String)>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut line = Vec::new();
+        while matches!(reader.read_until(b'\n', &mut line), Ok(n) if n > 0) {
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end_matches('\n').trim_end_matches('\r').to_string();
+            if sender.send((stream, text)).is_err() {
+                break;
+            }
+            line.clear();
+        }
+    })
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
@@ -1,7 +1,8 @@
-use ai_coreutils::{jsonl, memory::SafeMemoryAccess, Result};
+use ai_coreutils::{cli::{DecompressArgs, LimitArgs, MmapArgs}, config, jsonl, limits, memory::SafeMemoryAccess, Result};
+use ai_coreutils::policy::{self, Access};
 use clap::Parser;
 use std::fs::File;
-use std::io::{self, Read};
+use std::io::Read;
 use std::path::PathBuf;
 
 /// AI-optimized wc utility - Word, line, character count
@@ -10,6 +11,7 @@ use std::path::PathBuf;
 /// - JSONL structured output
 /// - Memory-mapped file access for large files
 /// - Detailed statistics
+/// - gzip, zstd and xz files counted decompressed (`compression` feature)
 #[derive(Parser, Debug)]
 #[command(name = "ai-wc")]
 #[command(about = "Print newline, word, and byte counts for each file", long_about = None)]
@@ -37,6 +39,15 @@ struct Cli {
     /// Print maximum line length
     #[arg(short = 'L', long)]
     max_line_length: bool,
+
+    #[command(flatten)]
+    decompress: DecompressArgs,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    mmap: MmapArgs,
 }
 
 #[derive(Debug, Default)]
@@ -46,14 +57,27 @@ struct Counts {
     bytes: usize,
     chars: usize,
     max_line_length: usize,
+    /// Compression the file was counted decompressed from
+    compression: Option<&'static str>,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    config::init()?;
+    cli.limits.apply()?;
+    cli.mmap.apply();
+    cli.decompress.apply();
+    policy::enforce(&cli.files, Access::Read)?;
 
     // If no files specified, read from stdin
     if cli.files.is_empty() {
-        let counts = count_stdin(&cli)?;
+        let counts = match count_stdin(&cli) {
+            Ok(counts) => counts,
+            Err(e) => {
+                jsonl::output_error(&format!("Failed to count stdin: {}", e), "WC_ERROR", Some("stdin"))?;
+                return output_summary(&Counts::default(), 0, 1);
+            }
+        };
         print_counts(&counts, "stdin", &cli);
         jsonl::output_info(serde_json::json!({
             "file": "stdin",
@@ -64,13 +88,14 @@ fn main() -> Result<()> {
             "chars": counts.chars,
             "max_line_length": counts.max_line_length,
         }))?;
-        return Ok(());
+        return output_summary(&counts, 1, 0);
     }
 
     // Output start message
     jsonl::output_progress(0, cli.files.len(), "Starting wc operation")?;
 
     let mut total_counts = Counts::default();
+    let (mut files_counted, mut errors) = (0, 0);
 
     for (index, file) in cli.files.iter().enumerate() {
         // Update progress
@@ -83,6 +108,7 @@ fn main() -> Result<()> {
         match count_file(file, &cli) {
             Ok(counts) => {
                 print_counts(&counts, &file.display().to_string(), &cli);
+                files_counted += 1;
 
                 total_counts.lines += counts.lines;
                 total_counts.words += counts.words;
@@ -98,9 +124,11 @@ fn main() -> Result<()> {
                     "bytes": counts.bytes,
                     "chars": counts.chars,
                     "max_line_length": counts.max_line_length,
+                    "compression": counts.compression,
                 }))?;
             }
             Err(e) => {
+                errors += 1;
                 jsonl::output_error(
                     &format!("Failed to count {}: {}", file.display(), e),
                     "WC_ERROR",
@@ -115,95 +143,58 @@ fn main() -> Result<()> {
         print_counts(&total_counts, "total", &cli);
     }
 
-    Ok(())
+    output_summary(&total_counts, files_counted, errors)
 }
 
-fn count_stdin(cli: &Cli) -> Result<Counts> {
-    let mut stdin = io::stdin();
-    let mut buffer = Vec::new();
-    stdin.read_to_end(&mut buffer)?;
+/// Emit the `wc_summary` record with the totals over all files
+fn output_summary(total: &Counts, files: usize, errors: usize) -> Result<()> {
+    jsonl::output_summary(serde_json::json!({
+        "type": "wc_summary",
+        "files": files,
+        "errors": errors,
+        "lines": total.lines,
+        "words": total.words,
+        "bytes": total.bytes,
+        "chars": total.chars,
+        "max_line_length": total.max_line_length,
+    }))
+}
 
-    count_bytes(&buffer, cli)
+fn count_stdin(cli: &Cli) -> Result<Counts> {
+    count_contents(&SafeMemoryAccess::open_or_stdin("-")?, cli)
 }
 
 fn count_file(file: &PathBuf, cli: &Cli) -> Result<Counts> {
     // Try to use memory mapping for files
     if let Ok(mmap) = SafeMemoryAccess::new(file) {
-        return count_mmap(&mmap, cli);
+        return count_contents(&mmap, cli);
     }
 
     // Fall back to standard I/O
     let mut f = File::open(file).map_err(ai_coreutils::AiCoreutilsError::Io)?;
     let mut buffer = Vec::new();
     f.read_to_end(&mut buffer).map_err(ai_coreutils::AiCoreutilsError::Io)?;
+    limits::read_file(file, buffer.len() as u64)?;
 
-    count_bytes(&buffer, cli)
+    count_contents(&SafeMemoryAccess::from_vec(buffer), cli)
 }
 
-fn count_mmap(mmap: &SafeMemoryAccess, _cli: &Cli) -> Result<Counts> {
-    let size = mmap.size();
-    let data = if let Some(d) = mmap.get(0, size) {
-        d
-    } else {
-        return Ok(Counts::default());
+/// Count a file's contents, mapped or read from a pipe alike
+fn count_contents(mmap: &SafeMemoryAccess, _cli: &Cli) -> Result<Counts> {
+    // One SIMD pass gives every count, characters and the longest line included
+    let metrics = match mmap.count_text_metrics_in(0, mmap.size()) {
+        Some(metrics) => metrics,
+        None => return Ok(Counts::default()),
     };
 
-    // Use SIMD-accelerated text metrics for basic counts
-    let (lines, words, bytes) = mmap.count_text_metrics();
-
-    let mut counts = Counts::default();
-    counts.lines = lines;
-    counts.words = words;
-    counts.bytes = bytes;
-    counts.chars = bytes; // For ASCII, chars == bytes
-
-    // Still need to calculate max line length
-    let mut current_line_length = 0;
-    for &byte in data.iter() {
-        if byte == b'\n' {
-            counts.max_line_length = counts.max_line_length.max(current_line_length);
-            current_line_length = 0;
-        } else if byte != b'\r' {
-            current_line_length += 1;
-        }
-    }
-    counts.max_line_length = counts.max_line_length.max(current_line_length);
-
-    Ok(counts)
-}
-
-fn count_bytes(data: &[u8], _cli: &Cli) -> Result<Counts> {
-    let mut counts = Counts::default();
-    counts.bytes = data.len();
-    counts.chars = data.len(); // For ASCII, chars == bytes; UTF-8 would need proper handling
-
-    let mut in_word = false;
-    let mut current_line_length = 0;
-
-    for &byte in data.iter() {
-        // Track line length
-        if byte == b'\n' {
-            counts.lines += 1;
-            counts.max_line_length = counts.max_line_length.max(current_line_length);
-            current_line_length = 0;
-        } else if byte != b'\r' {
-            current_line_length += 1;
-        }
-
-        // Track words (whitespace-separated)
-        let is_whitespace = byte == b' ' || byte == b'\t' || byte == b'\n' || byte == b'\r';
-        if is_whitespace {
-            in_word = false;
-        } else if !in_word {
-            in_word = true;
-            counts.words += 1;
-        }
-    }
-
-    // Don't forget the last line if it doesn't end with newline
-    counts.max_line_length = counts.max_line_length.max(current_line_length);
-
-    Ok(counts)
+    Ok(Counts {
+        lines: metrics.lines,
+        words: metrics.words,
+        bytes: metrics.bytes,
+        chars: metrics.chars,
+        max_line_length: metrics.max_line_length,
+        compression: mmap.compression(),
+    })
 }
 
 fn print_counts(counts: &Counts, name: &str, cli: &Cli) {
@@ -0,0 +1,243 @@
+//! Executable and object file inspection for ai-binfo
+//!
+//! [`inspect`] reads the headers of an ELF, PE or Mach-O file (and COFF
+//! objects) into a [`BinaryInfo`]: format, architecture, word size, entry
+//! point, the sections with their file ranges, the shared libraries it
+//! links against and whether it was stripped. [`ascii_strings`] finds the
+//! printable runs that `strings` would, with their offsets, and
+//! [`BinaryInfo::section_at`] maps an offset back to its section.
+//!
+//! Parsing only borrows the input, so it works directly on a memory map.
+
+use crate::error::{AiCoreutilsError, Result};
+use crate::simd_ops::SimdPrintableScanner;
+use object::elf::{DF_1_PIE, DT_FLAGS_1, DT_NEEDED};
+use object::read::elf::{Dyn, ElfFile, FileHeader};
+use object::{BinaryFormat, Endianness, Object, ObjectKind, ObjectSection, ObjectSymbolTable};
+use serde::Serialize;
+
+/// One section of a binary
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SectionInfo {
+    /// Section name (`.text`, `__TEXT,__text`, ...)
+    pub name: String,
+    /// What the section holds: `text`, `data`, `read_only_data`, `debug`, ...
+    pub kind: String,
+    /// Virtual address when loaded
+    pub address: u64,
+    /// Size in memory
+    pub size: u64,
+    /// Offset of the section's bytes in the file, if it has any
+    pub file_offset: Option<u64>,
+    /// Number of bytes in the file
+    pub file_size: u64,
+}
+
+/// Header information of a binary
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BinaryInfo {
+    /// `elf`, `pe`, `macho` or `coff`
+    pub format: &'static str,
+    /// Architecture (`x86_64`, `aarch64`, `arm`, `i386`, ...)
+    pub architecture: String,
+    /// 32 or 64
+    pub bits: u8,
+    /// `little` or `big`
+    pub endian: &'static str,
+    /// `executable`, `shared_library`, `relocatable`, `core` or `unknown`
+    pub kind: &'static str,
+    /// Entry point address (0 if none)
+    pub entry: u64,
+    /// Whether the symbol table has been removed
+    pub stripped: bool,
+    /// Whether debug information is present
+    pub debug_info: bool,
+    /// GNU build ID or Mach-O UUID, as hex
+    pub build_id: Option<String>,
+    /// Shared libraries the binary needs, in load order
+    pub libraries: Vec<String>,
+    /// Number of imported symbols
+    pub imports: usize,
+    /// Sections in header order
+    pub sections: Vec<SectionInfo>,
+}
+
+impl BinaryInfo {
+    /// Name of the section whose file bytes contain `offset`
+    pub fn section_at(&self, offset: u64) -> Option<&str> {
+        self.sections
+            .iter()
+            .find(|s| s.file_offset.is_some_and(|start| offset >= start && offset - start < s.file_size))
+            .map(|s| s.name.as_str())
+    }
+}
+
+/// Parse the headers of an executable or object file
+pub fn inspect(data: &[u8]) -> Result<BinaryInfo> {
+    let file = object::File::parse(data)
+        .map_err(|e| AiCoreutilsError::InvalidInput(format!("Not a recognized executable or object file: {}", e)))?;
+
+    let (mut libraries, pie) = match &file {
+        object::File::Elf32(elf) => elf_dynamic(elf),
+        object::File::Elf64(elf) => elf_dynamic(elf),
+        _ => (Vec::new(), false),
+    };
+    let format = match file.format() {
+        BinaryFormat::Elf => "elf",
+        BinaryFormat::Pe => "pe",
+        BinaryFormat::MachO => "macho",
+        BinaryFormat::Coff => "coff",
+        other => {
+            return Err(AiCoreutilsError::NotSupported(format!("Unsupported binary format: {:?}", other)));
+        }
+    };
+    let kind = match file.kind() {
+        ObjectKind::Executable => "executable",
+        ObjectKind::Dynamic if pie => "executable",
+        ObjectKind::Dynamic => "shared_library",
+        ObjectKind::Relocatable => "relocatable",
+        ObjectKind::Core => "core",
+        _ => "unknown",
+    };
+
+    let sections = file
+        .sections()
+        .map(|section| {
+            let range = section.file_range();
+            SectionInfo {
+                name: section.name().unwrap_or_default().to_string(),
+                kind: snake_case(&format!("{:?}", section.kind())),
+                address: section.address(),
+                size: section.size(),
+                file_offset: range.map(|(offset, _)| offset),
+                file_size: range.map_or(0, |(_, size)| size),
+            }
+        })
+        .collect();
+
+    let imports = file.imports().unwrap_or_default();
+    for import in &imports {
+        let library = String::from_utf8_lossy(import.library());
+        if !library.is_empty() && !libraries.iter().any(|l| *l == library) {
+            libraries.push(library.into_owned());
+        }
+    }
+
+    let build_id = match file.build_id() {
+        Ok(Some(id)) => Some(id.to_vec()),
+        _ => file.mach_uuid().ok().flatten().map(|uuid| uuid.to_vec()),
+    };
+
+    Ok(BinaryInfo {
+        format,
+        architecture: architecture_name(file.architecture()),
+        bits: if file.is_64() { 64 } else { 32 },
+        endian: if file.is_little_endian() { "little" } else { "big" },
+        kind,
+        entry: file.entry(),
+        stripped: file.symbol_table().is_none_or(|symbols| symbols.symbols().next().is_none()),
+        debug_info: file.has_debug_symbols(),
+        build_id: build_id.map(|id| id.iter().map(|b| format!("{:02x}", b)).collect()),
+        libraries,
+        imports: imports.len(),
+        sections,
+    })
+}
+
+/// `DT_NEEDED` entries of an ELF file's dynamic section, and whether
+/// `DF_1_PIE` marks it as a position-independent executable rather than
+/// a shared library (both are `ET_DYN`)
+fn elf_dynamic<Elf: FileHeader<Endian = Endianness>>(file: &ElfFile<'_, Elf>) -> (Vec<String>, bool) {
+    let endian = file.endian();
+    let sections = file.elf_section_table();
+    let Ok(Some((dynamic, link))) = sections.dynamic(endian, file.data()) else {
+        return (Vec::new(), false);
+    };
+    let Ok(strings) = sections.strings(endian, file.data(), link) else {
+        return (Vec::new(), false);
+    };
+    let needed = dynamic
+        .iter()
+        .filter(|entry| entry.tag32(endian) == Some(DT_NEEDED))
+        .filter_map(|entry| entry.string(endian, strings).ok())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .collect();
+    let pie = dynamic.iter().any(|entry| {
+        entry.tag32(endian) == Some(DT_FLAGS_1) && entry.val32(endian).is_some_and(|flags| flags & DF_1_PIE != 0)
+    });
+    (needed, pie)
+}
+
+/// Conventional lowercase name of an architecture
+fn architecture_name(architecture: object::Architecture) -> String {
+    use object::Architecture;
+    match architecture {
+        Architecture::X86_64 | Architecture::X86_64_X32 => "x86_64".to_string(),
+        Architecture::I386 => "i386".to_string(),
+        Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => "aarch64".to_string(),
+        Architecture::Arm => "arm".to_string(),
+        other => snake_case(&format!("{:?}", other)),
+    }
+}
+
+/// `ReadOnlyData` as `read_only_data`
+fn snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 && !out.ends_with('_') {
+            out.push('_');
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
+
+/// Runs of at least `min_length` printable ASCII bytes, with their offsets
+///
+/// Printable means space through `~` plus tab, as in `strings`.
+pub fn ascii_strings(data: &[u8], min_length: usize) -> impl Iterator<Item = (usize, &str)> + '_ {
+    SimdPrintableScanner::new()
+        .ascii_runs(data, min_length)
+        .into_iter()
+        // Printable ASCII is always valid UTF-8
+        .map(move |range| (range.start, std::str::from_utf8(&data[range]).unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_strings() {
+        let data = b"\x00\x01hello\x00ab\x7fworld!\tx\xffend";
+        let found: Vec<_> = ascii_strings(data, 3).collect();
+        assert_eq!(found, [(2, "hello"), (11, "world!\tx"), (20, "end")]);
+        assert_eq!(ascii_strings(data, 6).count(), 1);
+    }
+
+    #[test]
+    fn test_snake_case() {
+        assert_eq!(snake_case("ReadOnlyData"), "read_only_data");
+        assert_eq!(snake_case("Text"), "text");
+    }
+
+    #[test]
+    fn test_rejects_non_binaries() {
+        assert!(inspect(b"#!/bin/sh\necho hi\n").is_err());
+        assert!(inspect(b"").is_err());
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn test_inspect_own_executable() {
+        let data = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+        let info = inspect(&data).unwrap();
+        assert_eq!((info.format, info.architecture.as_str(), info.bits, info.endian), ("elf", "x86_64", 64, "little"));
+        assert_eq!(info.kind, "executable");
+        assert!(info.libraries.iter().any(|l| l.starts_with("libc.so")));
+        let text = info.sections.iter().find(|s| s.name == ".text").unwrap();
+        assert_eq!(text.kind, "text");
+        assert_eq!(info.section_at(text.file_offset.unwrap()), Some(".text"));
+        assert!(info.imports > 0);
+    }
+}
@@ -0,0 +1,234 @@
+//! File digests and manifests for ai-checksum
+//!
+//! A [`Manifest`] maps paths to the digest and size of their contents. It
+//! can be saved, compared with the files on disk ([`Manifest::verify`]), or
+//! kept current while watching a tree by re-hashing only the files that
+//! changed ([`Manifest::rehash`]). Any difference between the recorded and
+//! the current contents is reported as a [`Drift`].
+//!
+//! Manifests are JSON: `{"algorithm": "xxh3", "files": {path: {digest, size}}}`,
+//! with paths as given on the command line or reached by walking them. Any
+//! [`HashAlgorithm`] can be used; the manifest records which.
+
+use crate::error::{AiCoreutilsError, Result};
+use crate::fs_utils;
+use crate::memory::SafeMemoryAccess;
+use crate::hash::{Digest, HashAlgorithm};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Recorded contents of one file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Hex digest of the contents
+    pub digest: String,
+    /// Size in bytes
+    pub size: u64,
+}
+
+/// Digest the file at `path`
+pub fn hash_file(path: &Path, algorithm: HashAlgorithm) -> Result<Entry> {
+    let mem = SafeMemoryAccess::new(path)?;
+    let data = mem.as_slice();
+    Ok(Entry {
+        digest: algorithm.hex_digest(data),
+        size: data.len() as u64,
+    })
+}
+
+/// How a file differs from its manifest entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriftKind {
+    /// The file is not in the manifest
+    Added,
+    /// The file's contents changed
+    Changed,
+    /// The file in the manifest no longer exists
+    Removed,
+}
+
+impl DriftKind {
+    /// Name used in JSONL output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DriftKind::Added => "added",
+            DriftKind::Changed => "changed",
+            DriftKind::Removed => "removed",
+        }
+    }
+}
+
+/// A difference between a manifest and the files on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift {
+    /// Path of the file
+    pub path: PathBuf,
+    /// What changed
+    pub kind: DriftKind,
+    /// Recorded entry, if the file was in the manifest
+    pub expected: Option<Entry>,
+    /// Current entry, if the file exists
+    pub actual: Option<Entry>,
+}
+
+/// Digests of a set of files
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Algorithm of every digest
+    pub algorithm: HashAlgorithm,
+    /// Entries by path
+    pub files: BTreeMap<PathBuf, Entry>,
+}
+
+impl Manifest {
+    /// An empty manifest
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        Self {
+            algorithm,
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Read a manifest file
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => AiCoreutilsError::PathNotFound(path.to_path_buf()),
+            _ => AiCoreutilsError::Io(e),
+        })?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Atomically write the manifest to `path`
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut json = serde_json::to_vec_pretty(self)?;
+        json.push(b'\n');
+        fs_utils::atomic_write(path, &json)
+    }
+
+    /// Hash `path` and record it, returning the entry
+    pub fn insert(&mut self, path: &Path) -> Result<&Entry> {
+        let entry = hash_file(path, self.algorithm)?;
+        self.files.insert(path.to_path_buf(), entry);
+        Ok(&self.files[path])
+    }
+
+    /// Re-hash `path` after a change, updating the manifest
+    ///
+    /// Returns the drift, or `None` if the contents are what was recorded
+    /// (the file was only touched). A file that no longer exists is removed
+    /// from the manifest.
+    pub fn rehash(&mut self, path: &Path) -> Result<Option<Drift>> {
+        let actual = match hash_file(path, self.algorithm) {
+            Ok(entry) => Some(entry),
+            Err(AiCoreutilsError::PathNotFound(_)) => None,
+            Err(AiCoreutilsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        let expected = match &actual {
+            Some(entry) => self.files.insert(path.to_path_buf(), entry.clone()),
+            None => self.files.remove(path),
+        };
+        Ok(drift(path, expected, actual))
+    }
+
+    /// Compare the manifest with the files on disk
+    ///
+    /// `current` lists the files that exist now; recorded files missing from
+    /// it are reported as removed. Files that cannot be read are returned as
+    /// errors.
+    pub fn verify(&self, current: &[PathBuf]) -> (Vec<Drift>, Vec<(PathBuf, AiCoreutilsError)>) {
+        let mut drifts = Vec::new();
+        let mut errors = Vec::new();
+
+        for path in current {
+            match hash_file(path, self.algorithm) {
+                Ok(actual) => drifts.extend(drift(path, self.files.get(path).cloned(), Some(actual))),
+                Err(e) => errors.push((path.clone(), e)),
+            }
+        }
+        let present: std::collections::HashSet<&PathBuf> = current.iter().collect();
+        for (path, expected) in &self.files {
+            if !present.contains(path) {
+                drifts.extend(drift(path, Some(expected.clone()), None));
+            }
+        }
+
+        drifts.sort_by(|a, b| a.path.cmp(&b.path));
+        (drifts, errors)
+    }
+}
+
+fn drift(path: &Path, expected: Option<Entry>, actual: Option<Entry>) -> Option<Drift> {
+    let kind = match (&expected, &actual) {
+        (None, Some(_)) => DriftKind::Added,
+        (Some(_), None) => DriftKind::Removed,
+        (Some(e), Some(a)) if e != a => DriftKind::Changed,
+        _ => return None,
+    };
+    Some(Drift {
+        path: path.to_path_buf(),
+        kind,
+        expected,
+        actual,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_and_rehash() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b, c) = (dir.path().join("a"), dir.path().join("b"), dir.path().join("c"));
+        fs::write(&a, "alpha").unwrap();
+        fs::write(&b, "beta").unwrap();
+
+        let mut manifest = Manifest::new(HashAlgorithm::Xxh3);
+        manifest.insert(&a).unwrap();
+        manifest.insert(&b).unwrap();
+        let (drifts, errors) = manifest.verify(&[a.clone(), b.clone()]);
+        assert!(drifts.is_empty() && errors.is_empty());
+
+        fs::write(&a, "ALPHA").unwrap();
+        fs::remove_file(&b).unwrap();
+        fs::write(&c, "gamma").unwrap();
+        let (drifts, _) = manifest.verify(&[a.clone(), c.clone()]);
+        let kinds: Vec<_> = drifts.iter().map(|d| (d.path.clone(), d.kind)).collect();
+        assert_eq!(
+            kinds,
+            [(a.clone(), DriftKind::Changed), (b.clone(), DriftKind::Removed), (c.clone(), DriftKind::Added)]
+        );
+
+        // Re-hashing updates the manifest, and reports nothing when only touched
+        let changed = manifest.rehash(&a).unwrap().unwrap();
+        assert_ne!(changed.expected, changed.actual);
+        assert_eq!(manifest.rehash(&a).unwrap(), None);
+        assert_eq!(manifest.rehash(&b).unwrap().unwrap().kind, DriftKind::Removed);
+        assert!(!manifest.files.contains_key(&b));
+        assert_eq!(manifest.rehash(&b).unwrap(), None);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("data");
+        fs::write(&file, "123456789").unwrap();
+        let mut manifest = Manifest::new(HashAlgorithm::Crc32);
+        assert_eq!(manifest.insert(&file).unwrap().digest, "cbf43926");
+
+        let path = dir.path().join("manifest.json");
+        manifest.save(&path).unwrap();
+        assert_eq!(Manifest::load(&path).unwrap(), manifest);
+        assert!(Manifest::load(&dir.path().join("missing.json")).is_err());
+
+        manifest.algorithm = HashAlgorithm::Xxh3;
+        let entry = manifest.insert(&file).unwrap();
+        assert_eq!(entry.digest.len(), 16);
+        manifest.save(&path).unwrap();
+        assert_eq!(Manifest::load(&path).unwrap().algorithm, HashAlgorithm::Xxh3);
+    }
+}
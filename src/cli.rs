@@ -0,0 +1,291 @@
+//! Command-line options shared by several utilities
+//!
+//! Binaries embed these with `#[command(flatten)]` so the flags are spelled
+//! and behave the same everywhere.
+
+use crate::error::Result;
+use crate::jsonl::{self, LogLevel};
+use crate::limits::{self, Limit};
+use crate::memory::{self, AccessPattern, MapOptions};
+use clap::Args;
+
+/// `--dry-run` for utilities that change the filesystem
+///
+/// A dry run goes through the same checks and emits the same records as a
+/// real run, each marked `"dry_run": true`, but changes nothing and records
+/// nothing in the operation journal.
+#[derive(Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DryRunArgs {
+    /// Report what would be done without changing anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+impl DryRunArgs {
+    /// Whether `--dry-run` was given
+    pub fn is_enabled(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Mark the process's JSONL output as a dry run if `--dry-run` was given
+    ///
+    /// Returns whether it was.
+    pub fn apply(&self) -> bool {
+        if self.dry_run {
+            jsonl::set_dry_run(true);
+        }
+        self.dry_run
+    }
+}
+
+/// `--ordered` for utilities whose parallel modes finish files in any order
+///
+/// Records are held back and written sorted by path, then line, so the
+/// output is the same on every run (see [`jsonl::set_ordered`]).
+#[derive(Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OrderArgs {
+    /// Write records sorted by path, then line, once the utility is done
+    #[arg(long)]
+    pub ordered: bool,
+}
+
+impl OrderArgs {
+    /// Hold the process's JSONL output back for sorting if `--ordered` was given
+    ///
+    /// Returns whether it was.
+    pub fn apply(&self) -> bool {
+        if self.ordered {
+            jsonl::set_ordered();
+        }
+        self.ordered
+    }
+}
+
+/// `--log-level` for utilities that emit `warning` records
+///
+/// Overrides `jsonl.log_level` from config; see [`jsonl::LogLevel`].
+#[derive(Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LogArgs {
+    /// Warnings to write: error (none), warn or info
+    #[arg(long, value_name = "LEVEL")]
+    pub log_level: Option<LogLevel>,
+}
+
+impl LogArgs {
+    /// Set the process's log level if `--log-level` was given
+    ///
+    /// Call after [`crate::config::init`], which sets it from config.
+    pub fn apply(&self) {
+        if let Some(level) = self.log_level {
+            jsonl::set_log_level(level);
+        }
+    }
+}
+
+/// `--no-decompress` for utilities that read file contents
+///
+/// With the `compression` feature, gzip, zstd and xz files are read
+/// decompressed (see the `compress` module); the flag reads them as stored.
+/// Without the feature files are always read as stored.
+#[derive(Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecompressArgs {
+    /// Read gzip, zstd and xz files as stored instead of decompressing them
+    #[arg(long)]
+    pub no_decompress: bool,
+}
+
+impl DecompressArgs {
+    /// Turn decompression off for the process if `--no-decompress` was given
+    pub fn apply(&self) {
+        #[cfg(feature = "compression")]
+        if self.no_decompress {
+            crate::compress::set_enabled(false);
+        }
+    }
+}
+
+/// `--madvise`, `--will-need`, `--populate` and `--huge-pages` for utilities
+/// that memory map files
+///
+/// Each utility advises the access pattern that fits how it reads unless
+/// `--madvise` overrides it; see [`MapOptions`]. Files are mapped guarded,
+/// so small ones are read in whole, and `--lock` holds a shared lock on
+/// larger ones while they are read. With `--log-level info` the hints
+/// applied to each file are reported in a `map_hints` record.
+#[derive(Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MapArgs {
+    /// Access pattern to advise for mapped files: normal, sequential or random
+    #[arg(long, value_name = "PATTERN")]
+    pub madvise: Option<AccessPattern>,
+
+    /// Ask the kernel to read mapped files ahead in full
+    #[arg(long)]
+    pub will_need: bool,
+
+    /// Read mapped files in before searching them (Linux)
+    #[arg(long)]
+    pub populate: bool,
+
+    /// Back mapped files with transparent huge pages (Linux)
+    #[arg(long)]
+    pub huge_pages: bool,
+
+    /// Hold a shared lock on mapped files while reading them, keeping out writers that lock
+    #[arg(long)]
+    pub lock: bool,
+}
+
+impl MapArgs {
+    /// Hints for a utility whose own access pattern is `access`
+    pub fn options(&self, access: AccessPattern) -> MapOptions {
+        MapOptions::new()
+            .access(self.madvise.unwrap_or(access))
+            .will_need(self.will_need)
+            .populate(self.populate)
+            .huge_pages(self.huge_pages)
+            .guarded(true)
+            .lock(self.lock)
+    }
+
+    /// Write a `map_hints` record for `path` if the log level is `info`
+    pub fn report(requested: &MapOptions, applied: &MapOptions, path: &std::path::Path) -> Result<()> {
+        if jsonl::log_level() >= LogLevel::Info {
+            jsonl::output_info(MapOptions::report(requested, applied, path))?;
+        }
+        Ok(())
+    }
+}
+
+/// `--no-mmap` for utilities that memory map files
+///
+/// A mapped file truncated by another process while it is read kills the
+/// reader with `SIGBUS`. The flag reads files into memory instead, for
+/// files that may change under the utility, such as live logs, at the cost
+/// of holding each one in memory whole.
+#[derive(Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MmapArgs {
+    /// Read files into memory instead of memory mapping them
+    #[arg(long, global = true)]
+    pub no_mmap: bool,
+}
+
+impl MmapArgs {
+    /// Turn memory mapping off for the process if `--no-mmap` was given
+    pub fn apply(&self) {
+        if self.no_mmap {
+            memory::set_mmap_enabled(false);
+        }
+    }
+}
+
+/// `--max-bytes`, `--max-files` and `--max-records` for utilities that read
+/// many files or emit many records, and `--resume` to page through output
+/// they cut short
+///
+/// Flags given override the `[limits]` config section; see [`crate::limits`]
+/// for how a utility stops once one is reached.
+#[derive(Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct LimitArgs {
+    /// Stop after reading this many bytes of file contents (suffix K/M/G)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_bytes: Option<u64>,
+
+    /// Stop after reading this many files
+    #[arg(long, value_name = "N")]
+    pub max_files: Option<u64>,
+
+    /// Stop after writing this many JSONL records
+    #[arg(long, value_name = "N")]
+    pub max_records: Option<u64>,
+
+    /// Continue from the token in a `limit_exceeded` record
+    #[arg(long, value_name = "TOKEN")]
+    pub resume: Option<String>,
+}
+
+impl LimitArgs {
+    /// Set the process's limits from the flags given
+    ///
+    /// Call after [`crate::config::init`], which sets them from config. An
+    /// invalid `--resume` token is reported as an `INVALID_RESUME_TOKEN`
+    /// error record and returned.
+    pub fn apply(&self) -> Result<()> {
+        for (limit, max) in [
+            (Limit::Bytes, self.max_bytes),
+            (Limit::Files, self.max_files),
+            (Limit::Records, self.max_records),
+        ] {
+            if max.is_some() {
+                limits::set(limit, max);
+            }
+        }
+
+        if let Some(token) = &self.resume {
+            if let Err(e) = limits::resume(token) {
+                jsonl::output_error(&e.to_string(), "INVALID_RESUME_TOKEN", None)?;
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse a size in bytes, with an optional K, M or G suffix (powers of 1024)
+pub fn parse_size(s: &str) -> std::result::Result<u64, String> {
+    let s = s.trim();
+    let (num, suffix) = if s.ends_with('K') || s.ends_with('k') {
+        (&s[..s.len()-1], 1024u64)
+    } else if s.ends_with('M') || s.ends_with('m') {
+        (&s[..s.len()-1], 1024 * 1024)
+    } else if s.ends_with('G') || s.ends_with('g') {
+        (&s[..s.len()-1], 1024 * 1024 * 1024)
+    } else {
+        (s, 1u64)
+    };
+
+    num.parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(suffix))
+        .ok_or_else(|| format!("Invalid size: {}", s))
+}
+
+/// Parse a duration in seconds, with an optional s, m, h or d suffix
+pub fn parse_duration(s: &str) -> std::result::Result<std::time::Duration, String> {
+    let s = s.trim();
+    let (num, unit) = match s.char_indices().last() {
+        Some((i, 's')) => (&s[..i], 1u64),
+        Some((i, 'm')) => (&s[..i], 60),
+        Some((i, 'h')) => (&s[..i], 60 * 60),
+        Some((i, 'd')) => (&s[..i], 24 * 60 * 60),
+        _ => (s, 1),
+    };
+
+    num.parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .map(std::time::Duration::from_secs)
+        .ok_or_else(|| format!("Invalid duration: {}", s))
+}
+
+/// Parse a session id (see [`crate::session::validate_id`])
+pub fn parse_session_id(s: &str) -> std::result::Result<String, String> {
+    crate::session::validate_id(s).map(|()| s.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("4k"), Ok(4096));
+        assert_eq!(parse_size(" 2M "), Ok(2 << 20));
+        assert_eq!(parse_size("1G"), Ok(1 << 30));
+        assert!(parse_size("1.5M").is_err());
+        assert!(parse_size("99999999999999999999K").is_err());
+        // Fits in u64, but not once multiplied
+        assert!(parse_size("20000000000000000K").is_err());
+        assert_eq!(parse_size("16777216G"), Ok(1 << 54));
+    }
+}
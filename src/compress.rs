@@ -0,0 +1,250 @@
+//! Transparent decompression (`compression` feature)
+//!
+//! Readers recognise gzip, zstd and xz files by their magic bytes and see
+//! the decompressed contents, the way zcat and zgrep do.
+//! [`crate::memory::SafeMemoryAccess`] and the async file readers decode
+//! such files unless [`set_enabled`] has turned this off (`--no-decompress`).
+//!
+//! All three formats are decoded in process, as streams (`flate2`, `zstd`
+//! and `xz2`): [`Decoder`] feeds line scanners chunk by chunk, and
+//! [`decode`] stops buffering at the caller's `--max-bytes`.
+
+use crate::error::{AiCoreutilsError, Result};
+use crate::limits;
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use xz2::read::XzDecoder;
+
+/// Whether readers decode compressed files
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Longest magic number recognised
+const MAGIC_LEN: u64 = 6;
+
+/// Compression formats decoded by this module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// gzip (`.gz`), including concatenated members
+    Gzip,
+    /// Zstandard (`.zst`)
+    Zstd,
+    /// xz (`.xz`)
+    Xz,
+}
+
+impl Compression {
+    /// The format whose magic number starts `data`
+    pub fn from_magic(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if data.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else if data.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Compression::Xz)
+        } else {
+            None
+        }
+    }
+
+    /// Name used in output records
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Xz => "xz",
+        }
+    }
+}
+
+/// Turn decoding of compressed files on or off for the process
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether readers decode compressed files
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// The compression of a file starting with `data`, if readers decode it
+///
+/// `None` for data that is not compressed, and for everything once
+/// decoding is turned off.
+pub fn sniff(data: &[u8]) -> Option<Compression> {
+    if enabled() {
+        Compression::from_magic(data)
+    } else {
+        None
+    }
+}
+
+/// The compression of the file at `path`, if readers decode it
+///
+/// Only regular files are looked at, so nothing is consumed from pipes.
+pub fn detect(path: &Path) -> Result<Option<Compression>> {
+    if !enabled() {
+        return Ok(None);
+    }
+    let file = File::open(path)?;
+    if !file.metadata()?.is_file() {
+        return Ok(None);
+    }
+    let mut magic = Vec::with_capacity(MAGIC_LEN as usize);
+    file.take(MAGIC_LEN).read_to_end(&mut magic)?;
+    Ok(sniff(&magic))
+}
+
+/// Decode the file at `path` into memory
+///
+/// Decoding stops one byte past what `--max-bytes` leaves, so a file that
+/// expands enormously fails the caller's limit check rather than
+/// exhausting memory.
+pub fn decode(path: &Path, compression: Compression) -> Result<Vec<u8>> {
+    let cap = limits::bytes_left().map_or(u64::MAX, |left| left.saturating_add(1));
+    let mut data = Vec::new();
+    Decoder::open(path, compression)?
+        .take(cap)
+        .read_to_end(&mut data)
+        .map_err(|e| decode_error(path, compression, e))?;
+    Ok(data)
+}
+
+/// Decompressed contents of a file, read as a stream
+///
+/// Reads fail with the underlying decoder's error; [`Decoder::error`]
+/// turns one into the error utilities report.
+pub struct Decoder {
+    path: PathBuf,
+    compression: Compression,
+    inner: Box<dyn Read + Send>,
+}
+
+impl Decoder {
+    /// Start decoding the file at `path`
+    pub fn open(path: &Path, compression: Compression) -> Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        let inner: Box<dyn Read + Send> = match compression {
+            Compression::Gzip => Box::new(MultiGzDecoder::new(file)),
+            Compression::Zstd => {
+                Box::new(zstd::Decoder::with_buffer(file).map_err(|e| decode_error(path, compression, e))?)
+            }
+            // Concatenated streams decode as one, as with xzcat
+            Compression::Xz => Box::new(XzDecoder::new_multi_decoder(file)),
+        };
+        Ok(Self { path: path.to_path_buf(), compression, inner })
+    }
+
+    /// The error to report for a failed read
+    pub fn error(&self, e: io::Error) -> AiCoreutilsError {
+        decode_error(&self.path, self.compression, e)
+    }
+}
+
+impl Read for Decoder {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+fn decode_error(path: &Path, compression: Compression, e: io::Error) -> AiCoreutilsError {
+    AiCoreutilsError::Decompression(format!(
+        "{}: invalid {} data: {} (pass --no-decompress to read it as is)",
+        path.display(),
+        compression.as_str(),
+        e
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    /// Compresses a buffer in one of the supported formats
+    type Encoder = fn(&[u8]) -> Vec<u8>;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn zstd(data: &[u8]) -> Vec<u8> {
+        zstd::encode_all(data, 0).unwrap()
+    }
+
+    fn xz(data: &[u8]) -> Vec<u8> {
+        let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_from_magic() {
+        assert_eq!(Compression::from_magic(&gzip(b"x")), Some(Compression::Gzip));
+        assert_eq!(Compression::from_magic(&[0x28, 0xb5, 0x2f, 0xfd, 0]), Some(Compression::Zstd));
+        assert_eq!(Compression::from_magic(b"\xfd7zXZ\x00\x00"), Some(Compression::Xz));
+        assert_eq!(Compression::from_magic(b"\xfd7zXZ"), None);
+        assert_eq!(Compression::from_magic(b"plain text"), None);
+        assert_eq!(Compression::from_magic(b""), None);
+    }
+
+    #[test]
+    fn test_decode_gzip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("app.log.gz");
+        // Concatenated members decode as one stream, as with zcat
+        let mut data = gzip(b"first\n");
+        data.extend(gzip(b"second\n"));
+        std::fs::write(&path, data).unwrap();
+
+        assert_eq!(detect(&path).unwrap(), Some(Compression::Gzip));
+        assert_eq!(decode(&path, Compression::Gzip).unwrap(), b"first\nsecond\n");
+
+        let plain = dir.path().join("plain.txt");
+        std::fs::write(&plain, b"plain").unwrap();
+        assert_eq!(detect(&plain).unwrap(), None);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("broken.gz");
+        let mut data = gzip(b"some text that will be cut short");
+        data.truncate(data.len() - 10);
+        std::fs::write(&path, data).unwrap();
+
+        let err = decode(&path, Compression::Gzip).unwrap_err();
+        assert!(matches!(err, AiCoreutilsError::Decompression(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_decode_zstd_and_xz() {
+        let dir = TempDir::new().unwrap();
+        let encoders: [(Compression, Encoder); 2] = [(Compression::Zstd, zstd), (Compression::Xz, xz)];
+        for (compression, encode) in encoders {
+            // Concatenated frames and streams decode as one
+            let mut data = encode(b"hello\n");
+            data.extend(encode(b"world\n"));
+            let path = dir.path().join(format!("data.{}", compression.as_str()));
+            std::fs::write(&path, &data).unwrap();
+            assert_eq!(detect(&path).unwrap(), Some(compression));
+            assert_eq!(decode(&path, compression).unwrap(), b"hello\nworld\n");
+
+            // The stream is decoded as it is read
+            let mut decoder = Decoder::open(&path, compression).unwrap();
+            let mut first = [0u8; 6];
+            decoder.read_exact(&mut first).unwrap();
+            assert_eq!(&first, b"hello\n");
+
+            std::fs::write(&path, &data[..data.len() / 4]).unwrap();
+            let err = decode(&path, compression).unwrap_err();
+            assert!(matches!(err, AiCoreutilsError::Decompression(_)), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,799 @@
+//! Configuration file support
+//!
+//! Loads shared defaults for all AI-Coreutils binaries from
+//! `~/.config/ai-coreutils/config.toml` and `AI_COREUTILS_*` environment
+//! variables. Precedence (lowest to highest): built-in defaults, config file,
+//! environment variables, CLI flags.
+
+use crate::error::{AiCoreutilsError, Result};
+use crate::jsonl::LogLevel;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Environment variable overriding the config file location
+pub const CONFIG_PATH_ENV: &str = "AI_COREUTILS_CONFIG";
+
+/// Prefix shared by all environment variable overrides
+pub const ENV_PREFIX: &str = "AI_COREUTILS_";
+
+/// Concurrency when neither configuration nor a machine profile sets one
+pub const DEFAULT_CONCURRENCY: usize = 10;
+
+static GLOBAL_CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// JSONL output options
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct JsonlConfig {
+    /// Include timestamps in emitted records
+    pub timestamps: bool,
+    /// Include a `telemetry` field in summary records
+    pub telemetry: bool,
+    /// Which `warning` records are written
+    pub log_level: LogLevel,
+}
+
+impl Default for JsonlConfig {
+    fn default() -> Self {
+        Self {
+            timestamps: true,
+            telemetry: true,
+            log_level: LogLevel::default(),
+        }
+    }
+}
+
+/// Operation journal options (see [`crate::ops_journal`])
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct JournalConfig {
+    /// Record changes made by mutating utilities so `ai-undo` can reverse them
+    ///
+    /// Off by default: with it on, removed files stay on disk in the journal.
+    pub enabled: bool,
+    /// Journal directory; `None` uses `ai-coreutils/journal` in the platform data directory
+    pub dir: Option<PathBuf>,
+    /// Operations kept; older ones are dropped along with their backups
+    pub max_operations: usize,
+    /// Largest file, in bytes, kept in the journal when removed; larger ones are deleted outright
+    pub max_backup_bytes: u64,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: None,
+            max_operations: 100,
+            max_backup_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+/// Scratch space options (see [`crate::scratch`])
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ScratchConfig {
+    /// Registry directory; `None` uses `ai-coreutils/scratch` in the platform data directory
+    pub dir: Option<PathBuf>,
+}
+
+/// Agent session options (see [`crate::session`])
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SessionConfig {
+    /// Session id marked on every record; `None` runs outside any session
+    pub id: Option<String>,
+    /// Directory holding the session directories; `None` uses `ai-coreutils/sessions` in the platform data directory
+    pub dir: Option<PathBuf>,
+}
+
+/// Result cache options (see [`crate::result_cache`])
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Reuse and store results of expensive analyses
+    pub enabled: bool,
+    /// Cache directory; `None` uses `ai-coreutils/analysis` in the platform cache directory
+    pub dir: Option<PathBuf>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self { enabled: true, dir: None }
+    }
+}
+
+/// Concurrency and buffer size tuning (see `tuning`)
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct TuningConfig {
+    /// Where tuned values come from when none is set explicitly
+    pub mode: TuningMode,
+    /// Machine profile written by `ai-bench --save`; `None` uses `ai-coreutils/profile.json` in the platform data directory
+    pub profile: Option<PathBuf>,
+    /// I/O buffer size in bytes; `None` takes it from the profile
+    pub buffer_size: Option<usize>,
+}
+
+/// Source of tuned values that are not set explicitly
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TuningMode {
+    /// Use the saved machine profile if there is one, else built-in defaults
+    #[default]
+    Profile,
+    /// As `profile`, but measure and save a profile first if there is none
+    Measure,
+    /// Always use built-in defaults
+    Off,
+}
+
+impl std::str::FromStr for TuningMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "profile" => Ok(TuningMode::Profile),
+            "measure" => Ok(TuningMode::Measure),
+            "off" => Ok(TuningMode::Off),
+            _ => Err(format!("Invalid tuning mode: {} (expected profile, measure or off)", s)),
+        }
+    }
+}
+
+/// Audit log options (see [`crate::audit`])
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// JSONL file each invocation is appended to; `None` disables the audit log
+    pub path: Option<PathBuf>,
+}
+
+/// Remote file options (see `remote`, with the `remote` feature)
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RemoteConfig {
+    /// Cache directory; `None` uses `ai-coreutils/remote` in the platform cache directory
+    pub cache_dir: Option<PathBuf>,
+    /// S3 endpoint for `s3://` URLs, addressed path-style; `None` uses AWS
+    pub endpoint: Option<String>,
+    /// S3 region; `None` uses `AWS_REGION`, then `us-east-1`
+    pub region: Option<String>,
+    /// Options for `sftp://` URLs
+    pub ssh: SshConfig,
+}
+
+/// Options for `sftp://` URLs (with the `sftp` feature)
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SshConfig {
+    /// OpenSSH client that carries the SFTP subsystem
+    pub program: String,
+    /// What to do with host keys not in `known_hosts`
+    pub host_key_policy: HostKeyPolicy,
+    /// Known hosts file; `None` uses OpenSSH's own
+    pub known_hosts: Option<PathBuf>,
+    /// Private key; `None` uses OpenSSH's own choice and the agent
+    pub identity_file: Option<PathBuf>,
+    /// Seconds to wait for a connection
+    pub connect_timeout: u64,
+}
+
+impl Default for SshConfig {
+    fn default() -> Self {
+        Self {
+            program: "ssh".to_string(),
+            host_key_policy: HostKeyPolicy::default(),
+            known_hosts: None,
+            identity_file: None,
+            connect_timeout: 30,
+        }
+    }
+}
+
+/// Handling of SSH host keys (OpenSSH's `StrictHostKeyChecking`)
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HostKeyPolicy {
+    /// Refuse hosts whose key is not already known
+    #[default]
+    Strict,
+    /// Record the keys of new hosts, but refuse changed keys
+    AcceptNew,
+    /// Accept any key (for throwaway test hosts only)
+    Off,
+}
+
+impl HostKeyPolicy {
+    /// Value of OpenSSH's `StrictHostKeyChecking` option
+    pub fn as_ssh_option(self) -> &'static str {
+        match self {
+            HostKeyPolicy::Strict => "yes",
+            HostKeyPolicy::AcceptNew => "accept-new",
+            HostKeyPolicy::Off => "no",
+        }
+    }
+}
+
+impl std::str::FromStr for HostKeyPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "strict" => Ok(HostKeyPolicy::Strict),
+            "accept-new" => Ok(HostKeyPolicy::AcceptNew),
+            "off" => Ok(HostKeyPolicy::Off),
+            _ => Err(format!("Invalid host key policy: {} (expected strict, accept-new or off)", s)),
+        }
+    }
+}
+
+/// Resource limits (see [`crate::limits`])
+///
+/// Unset limits are unlimited; `--max-bytes`, `--max-files` and
+/// `--max-records` override them.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// Bytes of file contents one invocation may read
+    pub max_bytes: Option<u64>,
+    /// Files one invocation may read
+    pub max_files: Option<u64>,
+    /// JSONL records one invocation may write
+    pub max_records: Option<u64>,
+}
+
+/// Sandbox path policy (see [`crate::policy`])
+///
+/// Empty lists leave every path accessible.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SandboxConfig {
+    /// Path prefixes that may be read or written; empty allows all
+    pub allow: Vec<PathBuf>,
+    /// Path prefixes that may never be accessed, even inside an allowed prefix
+    pub deny: Vec<PathBuf>,
+    /// Path prefixes that may be written, in place of `allow`; empty uses `allow`
+    pub allow_write: Vec<PathBuf>,
+}
+
+impl SandboxConfig {
+    /// Whether no rule is set
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty() && self.allow_write.is_empty()
+    }
+}
+
+/// Shared configuration for all utilities
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Config {
+    /// Default maximum concurrent operations for async modes
+    ///
+    /// `None` takes it from the machine profile, or uses 10.
+    pub concurrency: Option<usize>,
+    /// Process-wide file descriptor budget for async operations
+    ///
+    /// `None` derives it from `RLIMIT_NOFILE`.
+    pub fd_budget: Option<usize>,
+    /// Enable SIMD acceleration
+    pub simd: bool,
+    /// JSONL output options
+    pub jsonl: JsonlConfig,
+    /// Glob patterns of paths to skip during directory traversal
+    pub ignore: Vec<String>,
+    /// Named packs of custom regex patterns for content analysis
+    pub pattern_packs: BTreeMap<String, Vec<String>>,
+    /// Operation journal options
+    pub journal: JournalConfig,
+    /// Scratch space options
+    pub scratch: ScratchConfig,
+    /// Agent session options
+    pub session: SessionConfig,
+    /// Result cache options
+    pub cache: CacheConfig,
+    /// Concurrency and buffer size tuning
+    pub tuning: TuningConfig,
+    /// Sandbox path policy
+    pub sandbox: SandboxConfig,
+    /// Audit log options
+    pub audit: AuditConfig,
+    /// Resource limits
+    pub limits: LimitsConfig,
+    /// Remote file options
+    pub remote: RemoteConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            concurrency: None,
+            fd_budget: None,
+            simd: true,
+            jsonl: JsonlConfig::default(),
+            ignore: Vec::new(),
+            pattern_packs: BTreeMap::new(),
+            journal: JournalConfig::default(),
+            scratch: ScratchConfig::default(),
+            session: SessionConfig::default(),
+            cache: CacheConfig::default(),
+            tuning: TuningConfig::default(),
+            sandbox: SandboxConfig::default(),
+            audit: AuditConfig::default(),
+            limits: LimitsConfig::default(),
+            remote: RemoteConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Load configuration from the default file location and environment
+    ///
+    /// A missing config file is not an error; a malformed one is.
+    pub fn load() -> Result<Self> {
+        let mut config = match Self::default_path() {
+            Some(path) if path.exists() => Self::from_file(&path)?,
+            _ => Self::default(),
+        };
+        config.apply_env(std::env::vars())?;
+        Ok(config)
+    }
+
+    /// Parse configuration from a TOML file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(AiCoreutilsError::Io)?;
+        Self::from_toml(&contents)
+            .map_err(|e| AiCoreutilsError::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Parse configuration from a TOML string
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let config: Self = toml::from_str(contents).map_err(|e| AiCoreutilsError::Config(e.to_string()))?;
+        if let Some(id) = &config.session.id {
+            crate::session::validate_id(id)
+                .map_err(|e| AiCoreutilsError::Config(format!("Invalid session.id: {}", e)))?;
+        }
+        Ok(config)
+    }
+
+    /// Location of the config file, honouring `AI_COREUTILS_CONFIG`
+    pub fn default_path() -> Option<PathBuf> {
+        if let Ok(path) = std::env::var(CONFIG_PATH_ENV) {
+            return Some(PathBuf::from(path));
+        }
+        dirs::config_dir().map(|dir| dir.join("ai-coreutils").join("config.toml"))
+    }
+
+    /// Apply `AI_COREUTILS_*` overrides from the given variables
+    pub fn apply_env<I>(&mut self, vars: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        for (key, value) in vars {
+            let Some(name) = key.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+
+            match name {
+                "CONCURRENCY" => {
+                    let concurrency: usize = value.parse().map_err(|_| {
+                        AiCoreutilsError::Config(format!("Invalid {}: {}", key, value))
+                    })?;
+                    self.concurrency = Some(concurrency);
+                }
+                "FD_BUDGET" => {
+                    let budget: usize = value.parse().map_err(|_| {
+                        AiCoreutilsError::Config(format!("Invalid {}: {}", key, value))
+                    })?;
+                    self.fd_budget = Some(budget);
+                }
+                "SIMD" => self.simd = parse_bool(&key, &value)?,
+                "JSONL_TIMESTAMPS" => self.jsonl.timestamps = parse_bool(&key, &value)?,
+                "JSONL_TELEMETRY" => self.jsonl.telemetry = parse_bool(&key, &value)?,
+                "LOG_LEVEL" => {
+                    self.jsonl.log_level = value
+                        .parse()
+                        .map_err(|_| AiCoreutilsError::Config(format!("Invalid {}: {}", key, value)))?;
+                }
+                "JOURNAL" => self.journal.enabled = parse_bool(&key, &value)?,
+                "JOURNAL_DIR" => self.journal.dir = Some(PathBuf::from(value)),
+                "JOURNAL_MAX_BACKUP" => {
+                    self.journal.max_backup_bytes = crate::cli::parse_size(&value).map_err(|_| {
+                        AiCoreutilsError::Config(format!("Invalid {}: {}", key, value))
+                    })?;
+                }
+                "SCRATCH_DIR" => self.scratch.dir = Some(PathBuf::from(value)),
+                "SESSION" => {
+                    crate::session::validate_id(&value)
+                        .map_err(|e| AiCoreutilsError::Config(format!("Invalid {}: {}", key, e)))?;
+                    self.session.id = Some(value);
+                }
+                "SESSION_DIR" => self.session.dir = Some(PathBuf::from(value)),
+                "CACHE" => self.cache.enabled = parse_bool(&key, &value)?,
+                "CACHE_DIR" => self.cache.dir = Some(PathBuf::from(value)),
+                "TUNING" => {
+                    self.tuning.mode = value
+                        .parse()
+                        .map_err(|_| AiCoreutilsError::Config(format!("Invalid {}: {}", key, value)))?;
+                }
+                "PROFILE" => self.tuning.profile = Some(PathBuf::from(value)),
+                "BUFFER_SIZE" => {
+                    let size = crate::cli::parse_size(&value).map_err(|_| {
+                        AiCoreutilsError::Config(format!("Invalid {}: {}", key, value))
+                    })?;
+                    self.tuning.buffer_size = Some(size as usize);
+                }
+                "MAX_BYTES" => {
+                    let max = crate::cli::parse_size(&value).map_err(|_| {
+                        AiCoreutilsError::Config(format!("Invalid {}: {}", key, value))
+                    })?;
+                    self.limits.max_bytes = Some(max);
+                }
+                "MAX_FILES" => self.limits.max_files = Some(parse_count(&key, &value)?),
+                "MAX_RECORDS" => self.limits.max_records = Some(parse_count(&key, &value)?),
+                "AUDIT_LOG" => self.audit.path = Some(PathBuf::from(value)),
+                "REMOTE_CACHE_DIR" => self.remote.cache_dir = Some(PathBuf::from(value)),
+                "SSH_HOST_KEY_POLICY" => {
+                    self.remote.ssh.host_key_policy = value
+                        .parse()
+                        .map_err(|_| AiCoreutilsError::Config(format!("Invalid {}: {}", key, value)))?;
+                }
+                "SANDBOX" => {
+                    let (deny, allow) = split_list(&value)
+                        .into_iter()
+                        .partition::<Vec<_>, _>(|s| s.starts_with('!'));
+                    self.sandbox.allow = allow.into_iter().map(PathBuf::from).collect();
+                    self.sandbox.deny = deny.iter().map(|s| PathBuf::from(&s[1..])).collect();
+                }
+                "SANDBOX_WRITE" => {
+                    self.sandbox.allow_write =
+                        split_list(&value).into_iter().map(PathBuf::from).collect();
+                }
+                "IGNORE" => self.ignore = split_list(&value),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolve the concurrency to use, preferring an explicit CLI value
+    ///
+    /// Ignores the machine profile; see `tuning::concurrency` for that.
+    pub fn concurrency_or(&self, cli_value: Option<usize>) -> usize {
+        cli_value.or(self.concurrency).unwrap_or(DEFAULT_CONCURRENCY).max(1)
+    }
+
+    /// Check whether a path matches any configured ignore pattern
+    ///
+    /// Patterns containing `/` are matched against the whole path, others
+    /// against the file name only. Walkers that prune ignored directories
+    /// only need this check.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        if self.ignore.is_empty() {
+            return false;
+        }
+
+        let full = path.to_string_lossy();
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+
+        self.ignore.iter().any(|pattern| {
+            if pattern.contains('/') {
+                glob_match(pattern, &full)
+            } else {
+                glob_match(pattern, &name)
+            }
+        })
+    }
+
+    /// Check whether any component of `path` below `root` is ignored
+    ///
+    /// Used for flat file lists produced without pruning.
+    pub fn is_ignored_under(&self, root: &Path, path: &Path) -> bool {
+        if self.ignore.is_empty() {
+            return false;
+        }
+
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let mut current = root.to_path_buf();
+        relative.components().any(|c| {
+            current.push(c);
+            self.is_ignored(&current)
+        })
+    }
+
+    /// Apply process-wide settings (SIMD, JSONL options, fd budget, limits)
+    pub fn apply_globals(&self) {
+        use crate::limits::{self, Limit};
+
+        crate::simd_ops::SimdConfig::set_global_enabled(self.simd);
+        crate::jsonl::set_timestamps_enabled(self.jsonl.timestamps);
+        crate::telemetry::set_enabled(self.jsonl.telemetry);
+        crate::jsonl::set_log_level(self.jsonl.log_level);
+        if let Some(id) = &self.session.id {
+            crate::jsonl::set_session(id);
+        }
+        #[cfg(not(target_family = "wasm"))]
+        if let Some(budget) = self.fd_budget {
+            crate::async_ops::set_fd_budget(budget);
+        }
+        limits::set(Limit::Bytes, self.limits.max_bytes);
+        limits::set(Limit::Files, self.limits.max_files);
+        limits::set(Limit::Records, self.limits.max_records);
+    }
+}
+
+/// Load the shared configuration once per process and apply its globals
+///
+/// Binaries call this at startup; later calls return the cached value. With
+/// an audit log configured, this also starts recording the invocation, and
+/// fails if the log cannot be opened.
+pub fn init() -> Result<&'static Config> {
+    crate::telemetry::start();
+    if let Some(config) = GLOBAL_CONFIG.get() {
+        return Ok(config);
+    }
+
+    let config = Config::load()?;
+    config.apply_globals();
+    if let Some(path) = &config.audit.path {
+        crate::audit::start(path)?;
+    }
+    Ok(GLOBAL_CONFIG.get_or_init(|| config))
+}
+
+/// Get the shared configuration, loading defaults if `init` was never called
+pub fn get() -> &'static Config {
+    GLOBAL_CONFIG.get_or_init(|| Config::load().unwrap_or_default())
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(AiCoreutilsError::Config(format!("Invalid {}: {}", key, value))),
+    }
+}
+
+fn parse_count(key: &str, value: &str) -> Result<u64> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| AiCoreutilsError::Config(format!("Invalid {}: {}", key, value)))
+}
+
+/// Split a comma-separated environment value, dropping empty entries
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Match text against a wildcard pattern (`*` any sequence, `?` any char)
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<usize> = None;
+    let mut star_ti = 0;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some(pi);
+            star_ti = ti;
+            pi += 1;
+        } else if let Some(s) = star {
+            pi = s + 1;
+            star_ti += 1;
+            ti = star_ti;
+        } else {
+            return false;
+        }
+    }
+
+    while pi < p.len() && p[pi] == '*' {
+        pi += 1;
+    }
+
+    pi == p.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        let config = Config::default();
+        assert_eq!(config.concurrency, None);
+        assert_eq!(config.tuning.mode, TuningMode::Profile);
+        assert!(config.simd);
+        assert!(config.jsonl.timestamps);
+        assert!(config.jsonl.telemetry);
+        assert_eq!(config.jsonl.log_level, LogLevel::Warn);
+        assert!(config.ignore.is_empty());
+        assert!(!config.journal.enabled);
+        assert_eq!(config.journal.max_operations, 100);
+        assert_eq!(config.journal.max_backup_bytes, 64 * 1024 * 1024);
+        assert!(config.sandbox.is_empty());
+        assert!(config.audit.path.is_none());
+        assert_eq!(config.remote.ssh.host_key_policy, HostKeyPolicy::Strict);
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let config = Config::from_toml(
+            r#"
+            concurrency = 4
+            simd = false
+            ignore = ["target", "*.log"]
+
+            [jsonl]
+            timestamps = false
+            log_level = "error"
+
+            [pattern_packs]
+            secrets = ["AKIA[0-9A-Z]{16}"]
+
+            [journal]
+            enabled = true
+            max_operations = 10
+            max_backup_bytes = 1048576
+
+            [sandbox]
+            allow = ["/work"]
+            deny = ["/work/.env"]
+
+            [session]
+            id = "build-42"
+
+            [tuning]
+            mode = "off"
+            buffer_size = 65536
+
+            [audit]
+            path = "/var/log/ai-coreutils.jsonl"
+
+            [remote.ssh]
+            host_key_policy = "accept-new"
+            connect_timeout = 5
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.concurrency, Some(4));
+        assert!(!config.simd);
+        assert!(!config.jsonl.timestamps);
+        assert_eq!(config.jsonl.log_level, LogLevel::Error);
+        assert_eq!(config.ignore, vec!["target", "*.log"]);
+        assert_eq!(config.pattern_packs["secrets"].len(), 1);
+        assert!(config.journal.enabled);
+        assert_eq!(config.journal.max_operations, 10);
+        assert_eq!(config.journal.max_backup_bytes, 1024 * 1024);
+        assert_eq!(config.session.id.as_deref(), Some("build-42"));
+        assert_eq!(config.tuning.mode, TuningMode::Off);
+        assert_eq!(config.tuning.buffer_size, Some(65536));
+        assert_eq!(config.sandbox.allow, vec![PathBuf::from("/work")]);
+        assert_eq!(config.sandbox.deny, vec![PathBuf::from("/work/.env")]);
+        assert!(config.sandbox.allow_write.is_empty());
+        assert_eq!(config.audit.path, Some(PathBuf::from("/var/log/ai-coreutils.jsonl")));
+        assert_eq!(config.remote.ssh.host_key_policy, HostKeyPolicy::AcceptNew);
+        assert_eq!(config.remote.ssh.connect_timeout, 5);
+        assert_eq!(config.remote.ssh.program, "ssh");
+    }
+
+    #[test]
+    fn test_from_toml_invalid() {
+        let err = Config::from_toml("concurrency = \"many\"").unwrap_err();
+        assert!(matches!(err, AiCoreutilsError::Config(_)));
+
+        let err = Config::from_toml("[session]\nid = \"../escape\"").unwrap_err();
+        assert!(matches!(err, AiCoreutilsError::Config(_)));
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        let mut config = Config::from_toml("concurrency = 4").unwrap();
+        config
+            .apply_env(vec![
+                ("AI_COREUTILS_CONCURRENCY".to_string(), "32".to_string()),
+                ("AI_COREUTILS_SIMD".to_string(), "off".to_string()),
+                ("AI_COREUTILS_FD_BUDGET".to_string(), "256".to_string()),
+                ("AI_COREUTILS_IGNORE".to_string(), ".git, node_modules".to_string()),
+                ("AI_COREUTILS_JOURNAL_DIR".to_string(), "/tmp/journal".to_string()),
+                ("AI_COREUTILS_JOURNAL_MAX_BACKUP".to_string(), "8M".to_string()),
+                ("AI_COREUTILS_SESSION".to_string(), "agent-7".to_string()),
+                ("AI_COREUTILS_CACHE".to_string(), "off".to_string()),
+                ("AI_COREUTILS_TUNING".to_string(), "measure".to_string()),
+                ("AI_COREUTILS_PROFILE".to_string(), "/tmp/profile.json".to_string()),
+                ("AI_COREUTILS_BUFFER_SIZE".to_string(), "256K".to_string()),
+                ("AI_COREUTILS_SANDBOX".to_string(), "/work, !/work/.git".to_string()),
+                ("AI_COREUTILS_SANDBOX_WRITE".to_string(), "/work/out".to_string()),
+                ("AI_COREUTILS_AUDIT_LOG".to_string(), "/tmp/audit.jsonl".to_string()),
+                ("AI_COREUTILS_REMOTE_CACHE_DIR".to_string(), "/tmp/remote".to_string()),
+                ("AI_COREUTILS_SSH_HOST_KEY_POLICY".to_string(), "accept-new".to_string()),
+                ("AI_COREUTILS_MAX_BYTES".to_string(), "2M".to_string()),
+                ("AI_COREUTILS_MAX_RECORDS".to_string(), "500".to_string()),
+                ("AI_COREUTILS_JSONL_TELEMETRY".to_string(), "0".to_string()),
+                ("AI_COREUTILS_LOG_LEVEL".to_string(), "info".to_string()),
+                ("UNRELATED".to_string(), "x".to_string()),
+            ])
+            .unwrap();
+
+        assert_eq!(config.concurrency, Some(32));
+        assert_eq!(config.fd_budget, Some(256));
+        assert!(!config.simd);
+        assert_eq!(config.ignore, vec![".git", "node_modules"]);
+        assert_eq!(config.journal.dir, Some(PathBuf::from("/tmp/journal")));
+        assert_eq!(config.journal.max_backup_bytes, 8 * 1024 * 1024);
+        assert_eq!(config.session.id.as_deref(), Some("agent-7"));
+        assert!(!config.cache.enabled);
+        assert_eq!(config.tuning.mode, TuningMode::Measure);
+        assert_eq!(config.tuning.profile, Some(PathBuf::from("/tmp/profile.json")));
+        assert_eq!(config.tuning.buffer_size, Some(256 * 1024));
+        assert_eq!(config.sandbox.allow, vec![PathBuf::from("/work")]);
+        assert_eq!(config.sandbox.deny, vec![PathBuf::from("/work/.git")]);
+        assert_eq!(config.sandbox.allow_write, vec![PathBuf::from("/work/out")]);
+        assert_eq!(config.audit.path, Some(PathBuf::from("/tmp/audit.jsonl")));
+        assert_eq!(config.remote.cache_dir, Some(PathBuf::from("/tmp/remote")));
+        assert_eq!(config.remote.ssh.host_key_policy, HostKeyPolicy::AcceptNew);
+        assert_eq!(config.limits.max_bytes, Some(2 * 1024 * 1024));
+        assert_eq!(config.limits.max_files, None);
+        assert_eq!(config.limits.max_records, Some(500));
+        assert!(!config.jsonl.telemetry);
+        assert_eq!(config.jsonl.log_level, LogLevel::Info);
+    }
+
+    #[test]
+    fn test_env_invalid_value() {
+        let mut config = Config::default();
+        let result = config.apply_env(vec![("AI_COREUTILS_SIMD".to_string(), "maybe".to_string())]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_cli_overrides_config() {
+        let config = Config::default();
+        assert_eq!(config.concurrency_or(None), DEFAULT_CONCURRENCY);
+        assert_eq!(config.concurrency_or(Some(2)), 2);
+    }
+
+    #[test]
+    fn test_is_ignored() {
+        let config = Config {
+            ignore: vec!["target".to_string(), "*.log".to_string(), "src/gen/*".to_string()],
+            ..Config::default()
+        };
+
+        assert!(config.is_ignored(Path::new("project/target")));
+        assert!(config.is_ignored(Path::new("logs/app.log")));
+        assert!(config.is_ignored(Path::new("src/gen/out.rs")));
+        assert!(!config.is_ignored(Path::new("src/main.rs")));
+
+        let root = Path::new("project");
+        assert!(config.is_ignored_under(root, Path::new("project/target/debug/app")));
+        assert!(!config.is_ignored_under(Path::new("target"), Path::new("target/debug/app")));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.rs", "main.rs"));
+        assert!(glob_match("te?t", "test"));
+        assert!(glob_match("a*b*c", "aXXbYYc"));
+        assert!(!glob_match("*.rs", "main.py"));
+        assert!(glob_match("*", ""));
+    }
+}
@@ -0,0 +1,560 @@
+//! CSV/TSV parsing for ai-csv
+//!
+//! [`Reader`] parses RFC 4180 records out of a byte buffer (usually a
+//! memory-mapped file): fields may be quoted, quoted fields may contain
+//! delimiters, newlines and doubled quotes, and records end at `\n` or
+//! `\r\n`. Field boundaries are found with [`SimdByteClassSearcher`], so
+//! unquoted data is scanned 32 bytes at a time, and fields borrow from the
+//! buffer unless they contain escaped quotes.
+//!
+//! Parsing is lenient where real-world files are sloppy: a quote inside an
+//! unquoted field is kept literally, text after a closing quote is appended
+//! to the field, and blank lines are skipped. An unterminated quoted field
+//! is the only error.
+//!
+//! With [`Reader::trim`], whitespace around fields is dropped, so a quote
+//! after leading spaces still opens a quoted field; the whitespace inside
+//! quotes is kept.
+
+pub mod profile;
+
+use crate::error::{AiCoreutilsError, Result};
+use crate::simd_ops::{SimdByteClassSearcher, SimdWhitespaceDetector};
+use std::borrow::Cow;
+use std::io::Write;
+use std::path::Path;
+
+pub use profile::{ColumnProfile, ColumnType, TableProfile};
+
+/// Delimiter and quote character of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dialect {
+    /// Field separator
+    pub delimiter: u8,
+    /// Quote character
+    pub quote: u8,
+}
+
+impl Dialect {
+    /// Comma-separated values
+    pub const CSV: Dialect = Dialect {
+        delimiter: b',',
+        quote: b'"',
+    };
+
+    /// Tab-separated values
+    pub const TSV: Dialect = Dialect {
+        delimiter: b'\t',
+        quote: b'"',
+    };
+
+    /// Delimiters considered by [`Dialect::sniff`]
+    const CANDIDATES: [u8; 4] = [b',', b'\t', b';', b'|'];
+
+    /// Dialect implied by a `.tsv` or `.tab` extension, if any
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        matches!(extension.as_str(), "tsv" | "tab").then_some(Dialect::TSV)
+    }
+
+    /// Guess the delimiter from the start of a file
+    ///
+    /// Picks the candidate that splits the sampled records into the same
+    /// number of fields (more than one), preferring the most fields;
+    /// defaults to CSV.
+    pub fn sniff(sample: &[u8]) -> Self {
+        const SAMPLE_RECORDS: usize = 20;
+
+        // The last line of a truncated sample may be cut short
+        let sample = match sample.iter().rposition(|&b| b == b'\n') {
+            Some(end) => &sample[..=end],
+            None => sample,
+        };
+
+        let mut best = (Dialect::CSV, 1);
+        for delimiter in Self::CANDIDATES {
+            let dialect = Dialect {
+                delimiter,
+                ..Dialect::CSV
+            };
+            let counts: Vec<usize> = Reader::new(sample, dialect)
+                .take(SAMPLE_RECORDS)
+                .map_while(|record| record.ok().map(|r| r.len()))
+                .collect();
+            let consistent = counts.first().filter(|&&n| counts.iter().all(|&c| c == n));
+            if let Some(&fields) = consistent {
+                if fields > best.1 {
+                    best = (dialect, fields);
+                }
+            }
+        }
+        best.0
+    }
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        Dialect::CSV
+    }
+}
+
+/// One parsed record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record<'a> {
+    /// Field values with quoting removed
+    pub fields: Vec<Cow<'a, [u8]>>,
+    /// Line the record starts on (1-based)
+    pub line: u64,
+    /// Byte offset of the record in the buffer
+    pub offset: usize,
+}
+
+impl<'a> Record<'a> {
+    /// Number of fields
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Whether the record has no fields
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Field `index` as text (invalid UTF-8 is replaced)
+    pub fn get_str(&self, index: usize) -> Option<Cow<'_, str>> {
+        self.fields.get(index).map(|f| String::from_utf8_lossy(f))
+    }
+
+    /// All fields as owned strings
+    pub fn to_strings(&self) -> Vec<String> {
+        self.fields
+            .iter()
+            .map(|f| String::from_utf8_lossy(f).into_owned())
+            .collect()
+    }
+}
+
+/// Streaming parser over a buffer of CSV data
+///
+/// Yields one [`Record`] per row. After an error the reader is exhausted.
+pub struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    line: u64,
+    dialect: Dialect,
+    field_end: SimdByteClassSearcher,
+    quote: SimdByteClassSearcher,
+    /// Set when fields are trimmed
+    whitespace: Option<SimdWhitespaceDetector>,
+    failed: bool,
+}
+
+impl<'a> Reader<'a> {
+    /// Parse `data`, skipping a leading UTF-8 byte order mark
+    pub fn new(data: &'a [u8], dialect: Dialect) -> Self {
+        let pos = if data.starts_with(b"\xEF\xBB\xBF") { 3 } else { 0 };
+        Self {
+            data,
+            pos,
+            line: 1,
+            dialect,
+            field_end: SimdByteClassSearcher::new(&[dialect.delimiter, b'\n', b'\r']),
+            quote: SimdByteClassSearcher::new(&[dialect.quote]),
+            whitespace: None,
+            failed: false,
+        }
+    }
+
+    /// Drop leading and trailing whitespace of every field
+    pub fn trim(mut self, trim: bool) -> Self {
+        self.whitespace = trim.then(SimdWhitespaceDetector::new);
+        self
+    }
+
+    /// Dialect being parsed
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    /// Byte offset of the next record
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Skip blank lines before a record; false at the end of the data
+    fn skip_blank_lines(&mut self) -> bool {
+        loop {
+            match self.data.get(self.pos) {
+                None => return false,
+                Some(b'\n') => self.pos += 1,
+                Some(b'\r') if self.data.get(self.pos + 1) == Some(&b'\n') => self.pos += 2,
+                Some(_) => return true,
+            }
+            self.line += 1;
+        }
+    }
+
+    fn read_record(&mut self) -> Result<Record<'a>> {
+        let (line, offset) = (self.line, self.pos);
+        let mut fields = Vec::new();
+
+        loop {
+            fields.push(self.read_field(line)?);
+            match self.data.get(self.pos) {
+                None => break,
+                Some(&b) if b == self.dialect.delimiter => {
+                    self.pos += 1;
+                    // A trailing delimiter ends with an empty field
+                    if self.pos == self.data.len() {
+                        fields.push(Cow::Borrowed(&[][..]));
+                        break;
+                    }
+                }
+                Some(b'\r') => {
+                    self.pos += 1;
+                    if self.data.get(self.pos) == Some(&b'\n') {
+                        self.pos += 1;
+                    }
+                    self.line += 1;
+                    break;
+                }
+                Some(_) => {
+                    // `\n`: the only other byte a field stops at
+                    self.pos += 1;
+                    self.line += 1;
+                    break;
+                }
+            }
+        }
+
+        Ok(Record { fields, line, offset })
+    }
+
+    /// Read one field, leaving `pos` at its terminator
+    fn read_field(&mut self, record_line: u64) -> Result<Cow<'a, [u8]>> {
+        let data = self.data;
+        if let Some(whitespace) = &self.whitespace {
+            // Stop at the terminator, which may be whitespace itself
+            let end = self.field_end.find_from(data, self.pos).unwrap_or(data.len());
+            self.pos = whitespace.skip_whitespace(&data[..end], self.pos);
+        }
+        if data.get(self.pos) != Some(&self.dialect.quote) {
+            let start = self.pos;
+            let end = self.field_end.find_from(data, start).unwrap_or(data.len());
+            self.pos = end;
+            let end = match &self.whitespace {
+                Some(whitespace) => whitespace.rskip_whitespace(data, end).max(start),
+                None => end,
+            };
+            return Ok(Cow::Borrowed(&data[start..end]));
+        }
+
+        // Quoted: runs of text separated by doubled quotes
+        let start = self.pos + 1;
+        let mut pos = start;
+        let mut owned: Option<Vec<u8>> = None;
+        let mut field = loop {
+            let Some(close) = self.quote.find_from(data, pos) else {
+                return Err(AiCoreutilsError::InvalidInput(format!(
+                    "Unterminated quoted field in record starting on line {}",
+                    record_line
+                )));
+            };
+            self.line += data[pos..close].iter().filter(|&&b| b == b'\n').count() as u64;
+
+            if data.get(close + 1) == Some(&self.dialect.quote) {
+                // Doubled quote: keep one and continue
+                owned
+                    .get_or_insert_with(Vec::new)
+                    .extend_from_slice(&data[pos..=close]);
+                pos = close + 2;
+                continue;
+            }
+
+            self.pos = close + 1;
+            break match owned.take() {
+                Some(mut value) => {
+                    value.extend_from_slice(&data[pos..close]);
+                    Cow::Owned(value)
+                }
+                None => Cow::Borrowed(&data[start..close]),
+            };
+        };
+
+        // Text between the closing quote and the terminator is kept
+        let end = self.field_end.find_from(data, self.pos).unwrap_or(data.len());
+        let text_end = match &self.whitespace {
+            Some(whitespace) => whitespace.rskip_whitespace(data, end).max(self.pos),
+            None => end,
+        };
+        if text_end > self.pos {
+            field.to_mut().extend_from_slice(&data[self.pos..text_end]);
+        }
+        self.pos = end;
+        Ok(field)
+    }
+}
+
+impl<'a> Iterator for Reader<'a> {
+    type Item = Result<Record<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || !self.skip_blank_lines() {
+            return None;
+        }
+        let record = self.read_record();
+        self.failed = record.is_err();
+        Some(record)
+    }
+}
+
+/// Write one record, quoting the fields that need it
+pub fn write_record<W, F>(out: &mut W, fields: &[F], dialect: Dialect) -> std::io::Result<()>
+where
+    W: Write,
+    F: AsRef<[u8]>,
+{
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            out.write_all(&[dialect.delimiter])?;
+        }
+        let field = field.as_ref();
+        let needs_quotes = field
+            .iter()
+            .any(|&b| b == dialect.delimiter || b == dialect.quote || b == b'\n' || b == b'\r');
+        if !needs_quotes {
+            out.write_all(field)?;
+            continue;
+        }
+        out.write_all(&[dialect.quote])?;
+        for part in field.split_inclusive(|&b| b == dialect.quote) {
+            out.write_all(part)?;
+            if part.last() == Some(&dialect.quote) {
+                out.write_all(&[dialect.quote])?;
+            }
+        }
+        out.write_all(&[dialect.quote])?;
+    }
+    out.write_all(b"\n")
+}
+
+/// Column names for a header row
+///
+/// Blank names become `column_N` (1-based) and repeated names get a `_2`,
+/// `_3`, ... suffix, so every name can be used as a JSON key.
+pub fn column_names(header: &[String]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::with_capacity(header.len());
+    for (i, name) in header.iter().enumerate() {
+        let base = match name.trim() {
+            "" => format!("column_{}", i + 1),
+            trimmed => trimmed.to_string(),
+        };
+        let mut unique = base.clone();
+        let mut n = 2;
+        while names.contains(&unique) {
+            unique = format!("{}_{}", base, n);
+            n += 1;
+        }
+        names.push(unique);
+    }
+    names
+}
+
+/// Name of column `index` in `names`, or `column_N` past its end
+pub fn column_name(names: &[String], index: usize) -> Cow<'_, str> {
+    match names.get(index) {
+        Some(name) => Cow::Borrowed(name),
+        None => Cow::Owned(format!("column_{}", index + 1)),
+    }
+}
+
+/// Resolve column specs (names, or 1-based numbers) to 0-based indices
+///
+/// A spec that matches a column name exactly is a name; otherwise a number
+/// selects by position.
+pub fn resolve_columns(names: &[String], specs: &[String]) -> Result<Vec<usize>> {
+    specs
+        .iter()
+        .map(|spec| {
+            if let Some(index) = names.iter().position(|n| n == spec) {
+                return Ok(index);
+            }
+            match spec.parse::<usize>() {
+                Ok(n) if n >= 1 => Ok(n - 1),
+                _ => Err(AiCoreutilsError::InvalidInput(format!("Unknown column: {}", spec))),
+            }
+        })
+        .collect()
+}
+
+/// What a field holds, as inferred from its text
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldValue<'a> {
+    /// An empty field
+    Null,
+    /// `true` or `false`, in any case
+    Bool(bool),
+    /// A number that fits in an `i64`
+    Integer(i64),
+    /// Any other finite number
+    Float(f64),
+    /// Anything else
+    Text(&'a str),
+}
+
+/// Infer the type of a field
+///
+/// Numbers with leading zeros, such as ZIP codes, are text, as are `inf`,
+/// `NaN` and numbers too large for an `f64`.
+pub fn classify(field: &str) -> FieldValue<'_> {
+    if field.is_empty() {
+        return FieldValue::Null;
+    }
+    if field.eq_ignore_ascii_case("true") || field.eq_ignore_ascii_case("false") {
+        return FieldValue::Bool(field.eq_ignore_ascii_case("true"));
+    }
+    if looks_numeric(field) {
+        if let Ok(n) = field.parse::<i64>() {
+            return FieldValue::Integer(n);
+        }
+        if let Some(n) = field.parse::<f64>().ok().filter(|n| n.is_finite()) {
+            return FieldValue::Float(n);
+        }
+    }
+    FieldValue::Text(field)
+}
+
+/// A field as the JSON value it most likely holds
+///
+/// Integers and decimals become numbers, `true`/`false` (any case)
+/// booleans, and empty fields null; see [`classify`].
+pub fn typed_value(field: &str) -> serde_json::Value {
+    match classify(field) {
+        FieldValue::Null => serde_json::Value::Null,
+        FieldValue::Bool(b) => b.into(),
+        FieldValue::Integer(n) => n.into(),
+        FieldValue::Float(n) => serde_json::Number::from_f64(n).map_or_else(|| field.into(), serde_json::Value::Number),
+        FieldValue::Text(text) => text.into(),
+    }
+}
+
+/// Decimal number syntax, without leading zeros, `inf` or `NaN`
+fn looks_numeric(field: &str) -> bool {
+    let digits = field.strip_prefix('-').unwrap_or(field);
+    let integer_part = digits.split(['.', 'e', 'E']).next().unwrap_or("");
+    digits.starts_with(|c: char| c.is_ascii_digit())
+        && !(integer_part.len() > 1 && integer_part.starts_with('0'))
+        && digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(data: &[u8], dialect: Dialect) -> Vec<Vec<String>> {
+        Reader::new(data, dialect)
+            .map(|record| record.unwrap().to_strings())
+            .collect()
+    }
+
+    #[test]
+    fn test_trim() {
+        let data = b" id , name ,note\n 1,  \"Smith, J \" ,\t\n2 ,\"\" x , \n";
+        let trimmed: Vec<_> = Reader::new(data, Dialect::CSV)
+            .trim(true)
+            .map(|record| record.unwrap().to_strings())
+            .collect();
+        assert_eq!(
+            trimmed,
+            [
+                vec!["id", "name", "note"],
+                vec!["1", "Smith, J ", ""],
+                vec!["2", " x", ""],
+            ]
+        );
+        assert_eq!(parse(data, Dialect::CSV)[1], [" 1", "  \"Smith", " J \" ", "\t"]);
+
+        // A tab delimiter is not trimmed away
+        let trimmed: Vec<_> = Reader::new(b" a \t\t b\n", Dialect::TSV)
+            .trim(true)
+            .map(|record| record.unwrap().to_strings())
+            .collect();
+        assert_eq!(trimmed, [vec!["a", "", "b"]]);
+    }
+
+    #[test]
+    fn test_quoted_fields() {
+        let data = b"name,quote,n\r\n\"Smith, J\",\"He said \"\"hi\"\"\",1\n\nplain,\"multi\nline\",\n";
+        assert_eq!(
+            parse(data, Dialect::CSV),
+            [
+                vec!["name", "quote", "n"],
+                vec!["Smith, J", "He said \"hi\"", "1"],
+                vec!["plain", "multi\nline", ""],
+            ]
+        );
+
+        let records: Vec<_> = Reader::new(data, Dialect::CSV).map(|r| r.unwrap()).collect();
+        assert_eq!(records.iter().map(|r| r.line).collect::<Vec<_>>(), [1, 2, 4]);
+        assert!(matches!(records[1].fields[0], Cow::Borrowed(_)));
+        assert!(matches!(records[1].fields[1], Cow::Owned(_)));
+        assert_eq!(records[2].offset, data.len() - 20);
+    }
+
+    #[test]
+    fn test_lenient_and_errors() {
+        // BOM, stray quotes, text after a closing quote, no final newline
+        let data = "\u{feff}a,b\"c,\"d\"e,f,".as_bytes();
+        assert_eq!(parse(data, Dialect::CSV), [vec!["a", "b\"c", "de", "f", ""]]);
+
+        let mut reader = Reader::new(b"ok\n\"open,\nstill", Dialect::CSV);
+        assert!(reader.next().unwrap().is_ok());
+        let error = reader.next().unwrap().unwrap_err().to_string();
+        assert!(error.contains("line 2"), "{}", error);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_sniff_and_write() {
+        assert_eq!(Dialect::sniff(b"a\tb\tc\n1\t2\t3\n4\t5"), Dialect::TSV);
+        assert_eq!(Dialect::sniff(b"a;b\n\"x;y\";2\n").delimiter, b';');
+        assert_eq!(Dialect::sniff(b"just one column\n"), Dialect::CSV);
+        assert_eq!(Dialect::for_path(Path::new("data.TSV")), Some(Dialect::TSV));
+
+        let fields = ["plain", "a,b", "say \"hi\"", "two\nlines"];
+        let mut out = Vec::new();
+        write_record(&mut out, &fields, Dialect::CSV).unwrap();
+        assert_eq!(out, b"plain,\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\"\n");
+        assert_eq!(parse(&out, Dialect::CSV), [fields.to_vec()]);
+    }
+
+    #[test]
+    fn test_columns() {
+        let names = column_names(&["id".into(), "".into(), "id".into(), " x ".into()]);
+        assert_eq!(names, ["id", "column_2", "id_2", "x"]);
+        assert_eq!(column_name(&names, 5), "column_6");
+        assert_eq!(
+            resolve_columns(&names, &["x".into(), "1".into(), "id_2".into()]).unwrap(),
+            [3, 0, 2]
+        );
+        assert!(resolve_columns(&names, &["missing".into()]).is_err());
+        assert!(resolve_columns(&names, &["0".into()]).is_err());
+    }
+
+    #[test]
+    fn test_typed_value() {
+        use serde_json::json;
+
+        assert_eq!(typed_value("42"), json!(42));
+        assert_eq!(typed_value("-3.5"), json!(-3.5));
+        assert_eq!(typed_value("1e3"), json!(1000.0));
+        assert_eq!(typed_value("0.25"), json!(0.25));
+        assert_eq!(typed_value("TRUE"), json!(true));
+        assert_eq!(typed_value(""), json!(null));
+        for text in ["00501", "inf", "NaN", ".5", "1-2", "12 apples", "99999999999999999999e999"] {
+            assert_eq!(typed_value(text), json!(text), "{}", text);
+        }
+    }
+}
@@ -36,6 +36,40 @@ pub enum AiCoreutilsError {
     #[error("Operation not supported: {0}")]
     NotSupported(String),
 
+    /// Configuration error
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    /// Access refused by the sandbox path policy
+    #[error("Sandbox policy denies {access} access: {}", path.display())]
+    PolicyDenied {
+        /// Path as given
+        path: PathBuf,
+        /// Access that was refused
+        access: crate::policy::Access,
+    },
+
+    /// A resource limit stopped the utility
+    #[error("Limit exceeded: {limit} {max}")]
+    LimitExceeded {
+        /// Limit that was reached
+        limit: crate::limits::Limit,
+        /// Its value
+        max: u64,
+    },
+
+    /// A remote file could not be fetched
+    #[error("Remote fetch error: {0}")]
+    Remote(String),
+
+    /// An archive could not be read
+    #[error("Archive error: {0}")]
+    Archive(String),
+
+    /// A compressed file could not be decoded
+    #[error("Decompression error: {0}")]
+    Decompression(String),
+
     /// WalkDir error
     #[error("Directory traversal error: {0}")]
     WalkDir(#[from] walkdir::Error),
@@ -2,9 +2,18 @@
 //!
 //! Common file system operations used across AI-Coreutils.
 
+pub mod mode;
+pub mod owner;
+pub mod paths;
+pub mod resolve;
+
 use crate::error::{AiCoreutilsError, Result};
+use crate::jsonl::LogLevel;
 use std::fs;
+use std::io::Write;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 /// Get file metadata as a structured value
 pub fn get_file_metadata(path: &Path) -> Result<serde_json::Value> {
@@ -31,6 +40,51 @@ pub fn path_exists(path: &Path) -> bool {
     path.exists()
 }
 
+/// Kind of access checked by [`accessible`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessCheck {
+    /// May be read (`test -r`)
+    Read,
+    /// May be written (`test -w`)
+    Write,
+    /// May be executed, or searched for a directory (`test -x`)
+    Execute,
+}
+
+/// Whether this process may access `path` in the given way
+///
+/// On Unix the kernel decides, with the effective user and group, so
+/// ACLs, read-only mounts and root's overrides count, as with `test`.
+/// Elsewhere this is approximated from opening the file and its read-only
+/// flag. A missing path is never accessible.
+pub fn accessible(path: &Path, check: AccessCheck) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let Ok(cpath) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+            return false;
+        };
+        let mode = match check {
+            AccessCheck::Read => libc::R_OK,
+            AccessCheck::Write => libc::W_OK,
+            AccessCheck::Execute => libc::X_OK,
+        };
+        // SAFETY: cpath is a valid NUL-terminated string
+        unsafe { libc::faccessat(libc::AT_FDCWD, cpath.as_ptr(), mode, libc::AT_EACCESS) == 0 }
+    }
+    #[cfg(not(unix))]
+    {
+        let Ok(metadata) = fs::metadata(path) else {
+            return false;
+        };
+        match check {
+            AccessCheck::Read => metadata.is_dir() || fs::File::open(path).is_ok(),
+            AccessCheck::Write => !metadata.permissions().readonly(),
+            AccessCheck::Execute => metadata.is_dir() || path.extension().is_some_and(|ext| ext == "exe"),
+        }
+    }
+}
+
 /// Validate that a path is accessible
 pub fn validate_path(path: &Path) -> Result<()> {
     if !path.exists() {
@@ -70,6 +124,958 @@ pub fn is_path_within_base(path: &Path, base: &Path) -> bool {
     resolved_path.starts_with(&resolved_base)
 }
 
+/// Suffix appended to the backup created by [`safe_overwrite`]
+pub const BACKUP_SUFFIX: &str = ".bak";
+
+/// A file written to a temporary sibling and renamed into place on commit
+///
+/// Readers of the destination see either the old contents or the complete
+/// new contents, never a truncated file. If the `AtomicFile` is dropped
+/// without calling [`AtomicFile::commit`], the temporary file is removed.
+///
+/// A destination that is a symlink is written through: the file it points
+/// to is replaced and the link is kept. An existing destination's
+/// permissions, and on Unix its owner and group where the process may set
+/// them, are carried over to the new file.
+pub struct AtomicFile {
+    file: Option<fs::File>,
+    temp_path: PathBuf,
+    dest: PathBuf,
+}
+
+impl AtomicFile {
+    /// Create a temporary file next to `dest`, or next to the file it links to
+    ///
+    /// Fails if the sandbox policy forbids writing `dest` or its target, or
+    /// if `dest` is a dangling symlink.
+    pub fn create(dest: &Path) -> Result<Self> {
+        crate::policy::check(dest, crate::policy::Access::Write)?;
+        let dest = match fs::symlink_metadata(dest) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let target = fs::canonicalize(dest).map_err(|_| {
+                    AiCoreutilsError::InvalidInput(format!("Not writing through dangling symlink: {}", dest.display()))
+                })?;
+                crate::policy::check(&target, crate::policy::Access::Write)?;
+                target
+            }
+            _ => dest.to_path_buf(),
+        };
+        let dest = dest.as_path();
+        let dir = match dest.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let name = dest
+            .file_name()
+            .ok_or_else(|| AiCoreutilsError::InvalidInput(format!("Not a file path: {}", dest.display())))?;
+
+        let temp_path = dir.join(format!(
+            ".{}.tmp-{}",
+            name.to_string_lossy(),
+            uuid::Uuid::new_v4().simple()
+        ));
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+            .map_err(AiCoreutilsError::Io)?;
+
+        let atomic = Self {
+            file: Some(file),
+            temp_path,
+            dest: dest.to_path_buf(),
+        };
+        if let Ok(metadata) = fs::metadata(dest) {
+            atomic.keep_metadata(&metadata)?;
+        }
+        Ok(atomic)
+    }
+
+    /// Give the temporary file the existing destination's permissions and,
+    /// where allowed, its owner and group
+    fn keep_metadata(&self, metadata: &fs::Metadata) -> Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            // Only root may give a file away; others can still set a group
+            // they belong to
+            let file = self.file();
+            if std::os::unix::fs::fchown(file, Some(metadata.uid()), Some(metadata.gid())).is_err() {
+                let _ = std::os::unix::fs::fchown(file, None, Some(metadata.gid()));
+            }
+        }
+        // After chown, which may clear the set-id bits
+        self.file().set_permissions(metadata.permissions()).map_err(AiCoreutilsError::Io)
+    }
+
+    /// Path of the temporary file
+    pub fn temp_path(&self) -> &Path {
+        &self.temp_path
+    }
+
+    /// Access the underlying temporary file
+    pub fn file(&self) -> &fs::File {
+        self.file.as_ref().expect("AtomicFile used after commit")
+    }
+
+    /// Flush, fsync and rename the temporary file over the destination
+    pub fn commit(mut self) -> Result<()> {
+        let file = self.file.take().expect("AtomicFile committed twice");
+        file.sync_all().map_err(AiCoreutilsError::Io)?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        drop(file);
+
+        fs::rename(&self.temp_path, &self.dest).map_err(AiCoreutilsError::Io)?;
+        sync_parent_dir(&self.dest);
+        crate::audit::written(&self.dest, size);
+        Ok(())
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file.as_mut().expect("AtomicFile used after commit").write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.as_mut().expect("AtomicFile used after commit").flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if self.file.take().is_some() {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+/// Make a completed rename durable by syncing the parent directory (Unix only)
+fn sync_parent_dir(path: &Path) {
+    #[cfg(unix)]
+    {
+        let parent = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        };
+        if let Ok(dir) = fs::File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+/// Atomically replace `path` with `data` (temp file + fsync + rename)
+///
+/// Existing permissions of `path` are carried over to the new file.
+pub fn atomic_write(path: &Path, data: &[u8]) -> Result<()> {
+    let mut file = AtomicFile::create(path)?;
+    file.write_all(data).map_err(AiCoreutilsError::Io)?;
+    file.commit()
+}
+
+/// Atomically overwrite `path`, keeping the previous contents as a backup
+///
+/// Returns the backup path (`<path>.bak`) if the file existed before.
+pub fn safe_overwrite(path: &Path, data: &[u8]) -> Result<Option<PathBuf>> {
+    let backup = if path.exists() {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(BACKUP_SUFFIX);
+        let backup = PathBuf::from(backup);
+        fs::copy(path, &backup).map_err(AiCoreutilsError::Io)?;
+        Some(backup)
+    } else {
+        None
+    };
+
+    atomic_write(path, data)?;
+    Ok(backup)
+}
+
+/// Advisory lock mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    /// Multiple holders may read concurrently
+    Shared,
+    /// A single holder excludes all others
+    Exclusive,
+}
+
+impl LockMode {
+    /// Name used in JSONL output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LockMode::Shared => "shared",
+            LockMode::Exclusive => "exclusive",
+        }
+    }
+}
+
+/// An advisory lock on a file, released when dropped
+///
+/// Uses `flock` on Unix and `LockFileEx` on Windows (via `std::fs::File`
+/// locking). The lock is advisory: it only serializes processes that also
+/// take the lock.
+#[derive(Debug)]
+pub struct FileLock {
+    file: fs::File,
+    path: PathBuf,
+    mode: LockMode,
+}
+
+impl FileLock {
+    /// Acquire a lock on `path`, creating the file if needed
+    ///
+    /// With `timeout` set to `None` this blocks until the lock is available;
+    /// otherwise it polls and fails with `TimedOut` once the timeout elapses.
+    pub fn acquire(path: &Path, mode: LockMode, timeout: Option<Duration>) -> Result<Self> {
+        let file = open_lock_file(path)?;
+
+        match timeout {
+            None => {
+                match mode {
+                    LockMode::Shared => file.lock_shared(),
+                    LockMode::Exclusive => file.lock(),
+                }
+                .map_err(AiCoreutilsError::Io)?;
+            }
+            Some(timeout) => {
+                let start = Instant::now();
+                let mut delay = Duration::from_millis(5);
+                while !try_lock_file(&file, mode)? {
+                    let elapsed = start.elapsed();
+                    if elapsed >= timeout {
+                        return Err(AiCoreutilsError::Io(std::io::Error::new(
+                            std::io::ErrorKind::TimedOut,
+                            format!("Timed out waiting for {} lock on {}", mode.as_str(), path.display()),
+                        )));
+                    }
+                    std::thread::sleep(delay.min(timeout - elapsed));
+                    delay = (delay * 2).min(Duration::from_millis(100));
+                }
+            }
+        }
+
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+            mode,
+        })
+    }
+
+    /// Try to acquire a lock without waiting
+    ///
+    /// Returns `Ok(None)` if another process holds a conflicting lock.
+    pub fn try_acquire(path: &Path, mode: LockMode) -> Result<Option<Self>> {
+        let file = open_lock_file(path)?;
+        if !try_lock_file(&file, mode)? {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            file,
+            path: path.to_path_buf(),
+            mode,
+        }))
+    }
+
+    /// Path of the locked file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Mode the lock was acquired in
+    pub fn mode(&self) -> LockMode {
+        self.mode
+    }
+
+    /// Release the lock explicitly
+    pub fn release(self) -> Result<()> {
+        self.file.unlock().map_err(AiCoreutilsError::Io)
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+fn open_lock_file(path: &Path) -> Result<fs::File> {
+    fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .or_else(|e| match e.kind() {
+            // Read-only files can still be locked through a read handle
+            std::io::ErrorKind::PermissionDenied => fs::File::open(path),
+            _ => Err(e),
+        })
+        .map_err(AiCoreutilsError::Io)
+}
+
+fn try_lock_file(file: &fs::File, mode: LockMode) -> Result<bool> {
+    let result = match mode {
+        LockMode::Shared => file.try_lock_shared(),
+        LockMode::Exclusive => file.try_lock(),
+    };
+
+    match result {
+        Ok(()) => Ok(true),
+        Err(fs::TryLockError::WouldBlock) => Ok(false),
+        Err(fs::TryLockError::Error(e)) => Err(AiCoreutilsError::Io(e)),
+    }
+}
+
+/// Bytes allocated on disk for a file
+///
+/// On Unix this is the block count reported by `stat`; elsewhere the
+/// apparent size is returned.
+pub fn allocated_size(metadata: &fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        metadata.blocks() * 512
+    }
+    #[cfg(not(unix))]
+    {
+        metadata.len()
+    }
+}
+
+/// Check whether a file occupies less disk space than its apparent size
+pub fn is_sparse(metadata: &fs::Metadata) -> bool {
+    metadata.is_file() && allocated_size(metadata) < metadata.len()
+}
+
+/// Find the data regions of a file using `SEEK_DATA`/`SEEK_HOLE`
+///
+/// Returns `Ok(None)` when the platform or filesystem cannot report holes.
+/// Everything outside the returned ranges reads as zeros. The file position
+/// is left unspecified, so callers must seek before reading.
+pub fn data_segments(file: &fs::File) -> Result<Option<Vec<Range<u64>>>> {
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        let fd = file.as_raw_fd();
+        let len = file.metadata().map_err(AiCoreutilsError::Io)?.len();
+        let mut segments = Vec::new();
+        let mut offset: u64 = 0;
+
+        while offset < len {
+            // SAFETY: lseek only reads the descriptor, which `file` keeps open
+            let data = unsafe { libc::lseek(fd, offset as libc::off_t, libc::SEEK_DATA) };
+            if data < 0 {
+                let err = std::io::Error::last_os_error();
+                return match err.raw_os_error() {
+                    // No data after `offset`: the rest of the file is a hole
+                    Some(libc::ENXIO) => Ok(Some(segments)),
+                    Some(libc::EINVAL) if offset == 0 => Ok(None),
+                    _ => Err(AiCoreutilsError::Io(err)),
+                };
+            }
+
+            // SAFETY: as above
+            let hole = unsafe { libc::lseek(fd, data, libc::SEEK_HOLE) };
+            if hole < 0 {
+                return Err(AiCoreutilsError::Io(std::io::Error::last_os_error()));
+            }
+
+            segments.push(data as u64..hole as u64);
+            offset = hole as u64;
+        }
+
+        Ok(Some(segments))
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+    {
+        let _ = file;
+        Ok(None)
+    }
+}
+
+/// Page cache policy for large sequential reads
+///
+/// Scanning multi-GB files through the page cache evicts other processes'
+/// data on shared hosts. `Stream` hints sequential access and drops the
+/// cached pages once the file is closed; `Direct` bypasses the cache with
+/// `O_DIRECT` where the filesystem supports it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CachePolicy {
+    /// Regular buffered I/O
+    #[default]
+    Normal,
+    /// `posix_fadvise(SEQUENTIAL)` on open, `DONTNEED` on close
+    Stream,
+    /// `O_DIRECT` reads, bypassing the page cache
+    Direct,
+}
+
+impl CachePolicy {
+    /// Name used in JSONL output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CachePolicy::Normal => "normal",
+            CachePolicy::Stream => "stream",
+            CachePolicy::Direct => "direct",
+        }
+    }
+
+    /// JSONL record describing a requested and an applied policy
+    pub fn report(requested: CachePolicy, applied: CachePolicy, path: &Path) -> serde_json::Value {
+        serde_json::json!({
+            "type": "cache_policy",
+            "path": path.display().to_string(),
+            "requested": requested.as_str(),
+            "applied": applied.as_str(),
+            "honored": requested == applied,
+        })
+    }
+}
+
+/// Advise the kernel that `file` will be read sequentially
+///
+/// Returns whether the hint was accepted.
+pub fn advise_sequential(file: &fs::File) -> bool {
+    fadvise(file, FadviseHint::Sequential)
+}
+
+/// Ask the kernel to drop cached pages of `file`
+///
+/// Returns whether the hint was accepted.
+pub fn drop_cache(file: &fs::File) -> bool {
+    fadvise(file, FadviseHint::DontNeed)
+}
+
+enum FadviseHint {
+    Sequential,
+    DontNeed,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+fn fadvise(file: &fs::File, hint: FadviseHint) -> bool {
+    use std::os::unix::io::AsRawFd;
+
+    let advice = match hint {
+        FadviseHint::Sequential => libc::POSIX_FADV_SEQUENTIAL,
+        FadviseHint::DontNeed => libc::POSIX_FADV_DONTNEED,
+    };
+    // SAFETY: the descriptor is kept open by `file`; a zero length covers the whole file
+    unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, advice) == 0 }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+fn fadvise(_file: &fs::File, _hint: FadviseHint) -> bool {
+    false
+}
+
+/// Open a file for reading with `O_DIRECT`
+///
+/// Reads through the returned file must use buffers, offsets and lengths
+/// aligned to [`DIRECT_IO_ALIGNMENT`]. Fails with `Unsupported` off Linux.
+pub fn open_direct(path: &Path) -> std::io::Result<fs::File> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_DIRECT)
+            .open(path)
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    {
+        let _ = path;
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "O_DIRECT is not available"))
+    }
+}
+
+/// Alignment used for `O_DIRECT` buffers and transfer sizes
+pub const DIRECT_IO_ALIGNMENT: usize = 4096;
+
+/// Read a whole file with `O_DIRECT`, bypassing the page cache
+pub fn read_direct(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut file = open_direct(path)?;
+    let size = file.metadata()?.len() as usize;
+
+    const CHUNK: usize = 256 * DIRECT_IO_ALIGNMENT;
+    let layout = std::alloc::Layout::from_size_align(CHUNK, DIRECT_IO_ALIGNMENT)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let buffer = AlignedBuffer::new(layout);
+
+    let mut data = Vec::with_capacity(size);
+    loop {
+        // SAFETY: the buffer owns `layout.size()` bytes for its whole lifetime
+        let chunk = unsafe { std::slice::from_raw_parts_mut(buffer.ptr, layout.size()) };
+        let n = std::io::Read::read(&mut file, chunk)?;
+        if n == 0 {
+            break;
+        }
+        data.extend_from_slice(&chunk[..n]);
+        if n < CHUNK {
+            // Short read: end of file
+            break;
+        }
+    }
+
+    Ok(data)
+}
+
+/// Heap allocation with a fixed alignment, freed on drop
+struct AlignedBuffer {
+    ptr: *mut u8,
+    layout: std::alloc::Layout,
+}
+
+impl AlignedBuffer {
+    fn new(layout: std::alloc::Layout) -> Self {
+        // SAFETY: layout has a non-zero size
+        let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
+        if ptr.is_null() {
+            std::alloc::handle_alloc_error(layout);
+        }
+        Self { ptr, layout }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        // SAFETY: allocated in `new` with the same layout
+        unsafe { std::alloc::dealloc(self.ptr, self.layout) }
+    }
+}
+
+/// Drops a file's cached pages when it goes out of scope
+///
+/// Hold one next to a memory map (declared after it) so the pages are
+/// released once the map is gone.
+#[derive(Debug)]
+pub struct DropCacheOnClose {
+    file: fs::File,
+}
+
+impl DropCacheOnClose {
+    /// Wrap an open file
+    pub fn new(file: fs::File) -> Self {
+        Self { file }
+    }
+}
+
+impl Drop for DropCacheOnClose {
+    fn drop(&mut self) {
+        drop_cache(&self.file);
+    }
+}
+
+/// How file data was moved by [`kernel_copy`] or a buffered fallback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyMechanism {
+    /// `copy_file_range(2)` (Linux)
+    CopyFileRange,
+    /// `fcopyfile(3)` (macOS)
+    Fcopyfile,
+    /// `CopyFileExW` (Windows)
+    CopyFileEx,
+    /// Read/write loop through a userspace buffer
+    Buffered,
+}
+
+impl CopyMechanism {
+    /// Name used in JSONL output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CopyMechanism::CopyFileRange => "copy_file_range",
+            CopyMechanism::Fcopyfile => "fcopyfile",
+            CopyMechanism::CopyFileEx => "copy_file_ex",
+            CopyMechanism::Buffered => "buffered",
+        }
+    }
+}
+
+/// Copy `src` to `dest` without passing the data through userspace
+///
+/// Uses `copy_file_range` on Linux (reporting progress per chunk), and the
+/// platform copy (`fcopyfile`, `CopyFileExW`) via `std::fs::copy` on macOS
+/// and Windows. Returns `Ok(None)` when no zero-copy mechanism applies
+/// (unsupported platform, filesystem or kernel, or a source such as a
+/// procfs file that `copy_file_range` reads as empty) and nothing was
+/// written, so the caller can fall back to a buffered copy.
+pub fn kernel_copy<F>(src: &Path, dest: &Path, mut on_progress: F) -> Result<Option<(u64, CopyMechanism)>>
+where
+    F: FnMut(u64) -> Result<()>,
+{
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        const CHUNK: usize = 16 * 1024 * 1024;
+
+        let source = fs::File::open(src).map_err(AiCoreutilsError::Io)?;
+        let target = fs::File::create(dest).map_err(AiCoreutilsError::Io)?;
+        let mut copied = 0u64;
+
+        loop {
+            // SAFETY: both descriptors stay open for the call; null offsets
+            // use and advance the file positions
+            let n = unsafe {
+                libc::copy_file_range(
+                    source.as_raw_fd(),
+                    std::ptr::null_mut(),
+                    target.as_raw_fd(),
+                    std::ptr::null_mut(),
+                    CHUNK,
+                    0,
+                )
+            };
+
+            if n < 0 {
+                let err = std::io::Error::last_os_error();
+                let unsupported = matches!(
+                    err.raw_os_error(),
+                    Some(libc::ENOSYS | libc::EXDEV | libc::EINVAL | libc::EOPNOTSUPP | libc::EPERM)
+                );
+                if unsupported && copied == 0 {
+                    return Ok(None);
+                }
+                return Err(AiCoreutilsError::Io(err));
+            }
+            if n == 0 {
+                // procfs and sysfs files report no data to copy_file_range;
+                // let the buffered copy read them
+                if copied == 0 {
+                    return Ok(None);
+                }
+                break;
+            }
+
+            copied += n as u64;
+            on_progress(n as u64)?;
+        }
+
+        Ok(Some((copied, CopyMechanism::CopyFileRange)))
+    }
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    {
+        let copied = fs::copy(src, dest).map_err(AiCoreutilsError::Io)?;
+        on_progress(copied)?;
+        Ok(Some((copied, CopyMechanism::Fcopyfile)))
+    }
+    #[cfg(windows)]
+    {
+        let copied = fs::copy(src, dest).map_err(AiCoreutilsError::Io)?;
+        on_progress(copied)?;
+        Ok(Some((copied, CopyMechanism::CopyFileEx)))
+    }
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        windows
+    )))]
+    {
+        let _ = (src, dest, &mut on_progress);
+        Ok(None)
+    }
+}
+
+/// When directory walks follow symbolic links
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SymlinkPolicy {
+    /// Never follow symlinks, not even a symlinked starting point
+    Never,
+    /// Follow symlinks given as starting points only
+    #[default]
+    CommandLine,
+    /// Follow every symlink, skipping directories already visited
+    Always,
+}
+
+impl SymlinkPolicy {
+    /// Name used on the command line and in JSONL output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SymlinkPolicy::Never => "never",
+            SymlinkPolicy::CommandLine => "command-line",
+            SymlinkPolicy::Always => "always",
+        }
+    }
+
+    /// Whether a symlink found at `depth` (0 = starting point) is followed
+    pub fn follows_at(&self, depth: usize) -> bool {
+        match self {
+            SymlinkPolicy::Never => false,
+            SymlinkPolicy::CommandLine => depth == 0,
+            SymlinkPolicy::Always => true,
+        }
+    }
+}
+
+impl std::str::FromStr for SymlinkPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "never" => Ok(SymlinkPolicy::Never),
+            "command-line" => Ok(SymlinkPolicy::CommandLine),
+            "always" => Ok(SymlinkPolicy::Always),
+            _ => Err(format!(
+                "Invalid symlink policy: {} (expected never, command-line or always)",
+                s
+            )),
+        }
+    }
+}
+
+/// A directory reached again through a symlink during a walk
+///
+/// The directory is not descended into a second time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymlinkLoop {
+    /// Path at which the directory was reached again
+    pub path: PathBuf,
+    /// Path at which the directory was first visited
+    pub target: PathBuf,
+}
+
+impl SymlinkLoop {
+    /// JSONL record describing the skipped directory
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "symlink_loop",
+            "path": self.path.display().to_string(),
+            "target": self.target.display().to_string(),
+        })
+    }
+}
+
+/// A walked entry with its stat results, each fetched at most once
+///
+/// The file type comes from the directory listing, so classifying an entry
+/// costs no syscall on most platforms; metadata is read on first use and
+/// cached. Unfollowed symlinks describe the link itself.
+#[derive(Debug)]
+pub struct EntryInfo {
+    entry: walkdir::DirEntry,
+    metadata: std::cell::OnceCell<Option<fs::Metadata>>,
+}
+
+impl EntryInfo {
+    /// Wrap a `walkdir` entry
+    pub fn new(entry: walkdir::DirEntry) -> Self {
+        Self {
+            entry,
+            metadata: std::cell::OnceCell::new(),
+        }
+    }
+
+    /// Path of the entry
+    pub fn path(&self) -> &Path {
+        self.entry.path()
+    }
+
+    /// Consume the entry, returning its path
+    pub fn into_path(self) -> PathBuf {
+        self.entry.into_path()
+    }
+
+    /// Depth below the starting point (0 = starting point)
+    pub fn depth(&self) -> usize {
+        self.entry.depth()
+    }
+
+    /// File type (of the target, if the symlink was followed)
+    pub fn file_type(&self) -> fs::FileType {
+        self.entry.file_type()
+    }
+
+    /// Whether this is a regular file
+    pub fn is_file(&self) -> bool {
+        self.file_type().is_file()
+    }
+
+    /// Whether this is a directory
+    pub fn is_dir(&self) -> bool {
+        self.file_type().is_dir()
+    }
+
+    /// Whether this is a symlink that was not followed
+    pub fn is_symlink(&self) -> bool {
+        self.file_type().is_symlink()
+    }
+
+    /// Name used in JSONL output
+    pub fn kind(&self) -> &'static str {
+        if self.is_file() {
+            "file"
+        } else if self.is_dir() {
+            "directory"
+        } else if self.is_symlink() {
+            "symlink"
+        } else {
+            "unknown"
+        }
+    }
+
+    /// Metadata, read on first call; `None` if the entry vanished
+    pub fn metadata(&self) -> Option<&fs::Metadata> {
+        self.metadata.get_or_init(|| self.entry.metadata().ok()).as_ref()
+    }
+
+    /// Size in bytes
+    pub fn size(&self) -> Option<u64> {
+        self.metadata().map(|m| m.len())
+    }
+}
+
+/// Item produced by [`DirWalk`]
+#[derive(Debug)]
+pub enum WalkEvent {
+    /// A file, directory or (unfollowed) symlink
+    Entry(EntryInfo),
+    /// A directory skipped because it was already visited
+    Loop(SymlinkLoop),
+}
+
+/// Recursive directory walk with symlink-cycle detection
+///
+/// Follows symlinks according to a [`SymlinkPolicy`] and skips paths matched
+/// by the configured ignore rules. Entries the sandbox policy denies are
+/// yielded as errors and not descended into. When following every symlink, each
+/// directory is identified by device and inode, so neither a cycle nor two
+/// links to the same directory visit it twice.
+pub struct DirWalk {
+    inner: walkdir::IntoIter,
+    policy: SymlinkPolicy,
+    visited: std::collections::HashMap<(u64, u64), PathBuf>,
+}
+
+impl DirWalk {
+    /// Walk `root` and everything below it
+    pub fn new(root: &Path, policy: SymlinkPolicy) -> Self {
+        Self::with_max_depth(root, policy, None)
+    }
+
+    /// Walk `root`, descending at most `max_depth` levels below it
+    pub fn with_max_depth(root: &Path, policy: SymlinkPolicy, max_depth: Option<usize>) -> Self {
+        let mut walker = walkdir::WalkDir::new(root)
+            .follow_links(policy == SymlinkPolicy::Always)
+            .follow_root_links(policy != SymlinkPolicy::Never);
+        if let Some(depth) = max_depth {
+            walker = walker.max_depth(depth);
+        }
+
+        Self {
+            inner: walker.into_iter(),
+            policy,
+            visited: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Record a directory, returning where it was first seen if already visited
+    fn visit(&mut self, entry: &EntryInfo) -> Option<PathBuf> {
+        let id = dir_id(entry)?;
+        match self.visited.get(&id) {
+            Some(first) => Some(first.clone()),
+            None => {
+                self.visited.insert(id, entry.path().to_path_buf());
+                None
+            }
+        }
+    }
+}
+
+impl Iterator for DirWalk {
+    type Item = Result<WalkEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = match self.inner.next()? {
+                Ok(entry) => EntryInfo::new(entry),
+                Err(e) => {
+                    if let (Some(ancestor), Some(path)) = (e.loop_ancestor(), e.path()) {
+                        return Some(Ok(WalkEvent::Loop(SymlinkLoop {
+                            path: path.to_path_buf(),
+                            target: ancestor.to_path_buf(),
+                        })));
+                    }
+                    return Some(Err(e.into()));
+                }
+            };
+
+            if entry.depth() > 0 && crate::config::get().is_ignored(entry.path()) {
+                if entry.is_dir() {
+                    self.inner.skip_current_dir();
+                }
+                continue;
+            }
+
+            if let Err(e) = crate::policy::check(entry.path(), crate::policy::Access::Read) {
+                if entry.is_dir() {
+                    self.inner.skip_current_dir();
+                }
+                return Some(Err(e));
+            }
+
+            if self.policy == SymlinkPolicy::Always && entry.is_dir() {
+                if let Some(target) = self.visit(&entry) {
+                    self.inner.skip_current_dir();
+                    return Some(Ok(WalkEvent::Loop(SymlinkLoop {
+                        path: entry.into_path(),
+                        target,
+                    })));
+                }
+            }
+
+            return Some(Ok(WalkEvent::Entry(entry)));
+        }
+    }
+}
+
+/// Emit a warning record for a path a walk could not read and skipped
+///
+/// `PERMISSION_DENIED` when access was refused, `UNREADABLE_ENTRY` for any
+/// other failure, such as an entry removed during the walk.
+pub fn warn_unreadable(path: &Path, error: &std::io::Error) -> Result<()> {
+    let code = match error.kind() {
+        std::io::ErrorKind::PermissionDenied => "PERMISSION_DENIED",
+        _ => "UNREADABLE_ENTRY",
+    };
+    crate::jsonl::output_warning(LogLevel::Warn, code, &error.to_string(), Some(&path.display().to_string()))
+}
+
+/// Emit a warning record for an error yielded by [`DirWalk`]
+///
+/// Only walk failures are reported (see [`warn_unreadable`]); sandbox
+/// denials are left to [`crate::policy::report`].
+pub fn warn_walk_error(error: &AiCoreutilsError) -> Result<()> {
+    let AiCoreutilsError::WalkDir(e) = error else {
+        return Ok(());
+    };
+    let path = e.path().unwrap_or(Path::new(""));
+    match e.io_error() {
+        Some(io) => warn_unreadable(path, io),
+        None => crate::jsonl::output_warning(LogLevel::Warn, "UNREADABLE_ENTRY", &e.to_string(), Some(&path.display().to_string())),
+    }
+}
+
+/// Device and inode of a directory entry (following symlinks)
+#[cfg(unix)]
+fn dir_id(entry: &EntryInfo) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    entry.metadata().map(|m| (m.dev(), m.ino()))
+}
+
+/// Windows lacks stable inode numbers in std; walkdir's ancestor check
+/// still catches cycles there
+#[cfg(not(unix))]
+fn dir_id(_entry: &EntryInfo) -> Option<(u64, u64)> {
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -101,6 +1107,205 @@ mod tests {
         assert!(validate_path(&non_existent).is_err());
     }
 
+    #[test]
+    fn test_accessible() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        fs::write(&file_path, b"test").unwrap();
+
+        assert!(accessible(&file_path, AccessCheck::Read));
+        assert!(accessible(&file_path, AccessCheck::Write));
+        assert!(accessible(temp_dir.path(), AccessCheck::Execute));
+        assert!(!accessible(&temp_dir.path().join("missing"), AccessCheck::Read));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&file_path, fs::Permissions::from_mode(0o444)).unwrap();
+            assert!(!accessible(&file_path, AccessCheck::Execute));
+            // root may write anything
+            let root = unsafe { libc::geteuid() } == 0;
+            assert_eq!(accessible(&file_path, AccessCheck::Write), root);
+        }
+    }
+
+    #[test]
+    fn test_atomic_write() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("out.txt");
+
+        atomic_write(&file_path, b"first").unwrap();
+        atomic_write(&file_path, b"second").unwrap();
+
+        assert_eq!(fs::read(&file_path).unwrap(), b"second");
+        // No temporary files left behind
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_atomic_file_dropped_without_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("out.txt");
+        fs::write(&file_path, b"original").unwrap();
+
+        {
+            let mut file = AtomicFile::create(&file_path).unwrap();
+            file.write_all(b"partial").unwrap();
+        }
+
+        assert_eq!(fs::read(&file_path).unwrap(), b"original");
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_atomic_file_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("script.sh");
+        fs::write(&file_path, b"old").unwrap();
+        fs::set_permissions(&file_path, fs::Permissions::from_mode(0o750)).unwrap();
+
+        let mut file = AtomicFile::create(&file_path).unwrap();
+        file.write_all(b"new").unwrap();
+        file.commit().unwrap();
+
+        assert_eq!(fs::read(&file_path).unwrap(), b"new");
+        assert_eq!(fs::metadata(&file_path).unwrap().permissions().mode() & 0o777, 0o750);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_atomic_file_writes_through_symlink() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("target.txt");
+        let link = temp_dir.path().join("link.txt");
+        fs::write(&target, b"old").unwrap();
+        std::os::unix::fs::symlink("target.txt", &link).unwrap();
+
+        atomic_write(&link, b"new").unwrap();
+
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read(&target).unwrap(), b"new");
+
+        let dangling = temp_dir.path().join("dangling.txt");
+        std::os::unix::fs::symlink("missing.txt", &dangling).unwrap();
+        assert!(AtomicFile::create(&dangling).is_err());
+        assert!(!temp_dir.path().join("missing.txt").exists());
+    }
+
+    #[test]
+    fn test_safe_overwrite() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("config.txt");
+
+        assert!(safe_overwrite(&file_path, b"v1").unwrap().is_none());
+
+        let backup = safe_overwrite(&file_path, b"v2").unwrap().unwrap();
+        assert_eq!(fs::read(&file_path).unwrap(), b"v2");
+        assert_eq!(fs::read(&backup).unwrap(), b"v1");
+    }
+
+    #[test]
+    fn test_file_lock_exclusive() {
+        let temp_dir = TempDir::new().unwrap();
+        let lock_path = temp_dir.path().join("lock");
+
+        let lock = FileLock::acquire(&lock_path, LockMode::Exclusive, None).unwrap();
+        assert!(FileLock::try_acquire(&lock_path, LockMode::Shared).unwrap().is_none());
+
+        let err = FileLock::acquire(&lock_path, LockMode::Exclusive, Some(Duration::from_millis(20)))
+            .unwrap_err();
+        assert!(matches!(err, AiCoreutilsError::Io(ref e) if e.kind() == std::io::ErrorKind::TimedOut));
+
+        lock.release().unwrap();
+        assert!(FileLock::try_acquire(&lock_path, LockMode::Exclusive).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_file_lock_shared() {
+        let temp_dir = TempDir::new().unwrap();
+        let lock_path = temp_dir.path().join("lock");
+
+        let _first = FileLock::acquire(&lock_path, LockMode::Shared, None).unwrap();
+        let second = FileLock::try_acquire(&lock_path, LockMode::Shared).unwrap();
+        assert!(second.is_some());
+        assert!(FileLock::try_acquire(&lock_path, LockMode::Exclusive).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_data_segments() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("sparse.bin");
+
+        let file = fs::File::create(&file_path).unwrap();
+        file.set_len(4 * 1024 * 1024).unwrap();
+        let mut writer = &file;
+        std::io::Seek::seek(&mut writer, std::io::SeekFrom::Start(1024 * 1024)).unwrap();
+        writer.write_all(&[1u8; 4096]).unwrap();
+        drop(file);
+
+        let file = fs::File::open(&file_path).unwrap();
+        if let Some(segments) = data_segments(&file).unwrap() {
+            let data: u64 = segments.iter().map(|r| r.end - r.start).sum();
+            assert!(segments.iter().any(|r| r.contains(&(1024 * 1024))));
+            assert!(data <= 4 * 1024 * 1024);
+        }
+    }
+
+    #[test]
+    fn test_read_direct_or_unsupported() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("data.bin");
+        let data: Vec<u8> = (0..DIRECT_IO_ALIGNMENT * 300 + 17).map(|i| i as u8).collect();
+        fs::write(&file_path, &data).unwrap();
+
+        // tmpfs and some other filesystems reject O_DIRECT
+        if let Ok(read) = read_direct(&file_path) {
+            assert_eq!(read, data);
+        }
+    }
+
+    #[test]
+    fn test_cache_policy_report() {
+        let report = CachePolicy::report(CachePolicy::Direct, CachePolicy::Stream, Path::new("f"));
+        assert_eq!(report["applied"], "stream");
+        assert_eq!(report["honored"], false);
+    }
+
+    #[test]
+    fn test_kernel_copy() {
+        let temp_dir = TempDir::new().unwrap();
+        let src = temp_dir.path().join("src.bin");
+        let dest = temp_dir.path().join("dest.bin");
+        let data: Vec<u8> = (0..100_000).map(|i| i as u8).collect();
+        fs::write(&src, &data).unwrap();
+
+        let mut reported = 0;
+        let result = kernel_copy(&src, &dest, |n| {
+            reported += n;
+            Ok(())
+        })
+        .unwrap();
+
+        if let Some((copied, mechanism)) = result {
+            assert_ne!(mechanism, CopyMechanism::Buffered);
+            assert_eq!(copied, data.len() as u64);
+            assert_eq!(reported, copied);
+            assert_eq!(fs::read(&dest).unwrap(), data);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_kernel_copy_proc_file_falls_back() {
+        let temp_dir = TempDir::new().unwrap();
+        let dest = temp_dir.path().join("status");
+
+        let result = kernel_copy(Path::new("/proc/self/status"), &dest, |_| Ok(())).unwrap();
+        assert!(result.is_none());
+    }
+
     #[test]
     fn test_is_path_within_base() {
         let temp_dir = TempDir::new().unwrap();
@@ -115,4 +1320,60 @@ mod tests {
         assert!(is_path_within_base(&safe_path, base));
         assert!(!is_path_within_base(&unsafe_path, base));
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dir_walk_symlink_loops() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::write(root.join("a/b/file.txt"), "x").unwrap();
+        // Cycle back to an ancestor and a second link to the same directory
+        std::os::unix::fs::symlink(root.join("a"), root.join("a/b/up")).unwrap();
+        std::os::unix::fs::symlink(root.join("a/b"), root.join("alias")).unwrap();
+
+        let walk = |policy| {
+            let (mut files, mut loops) = (0, 0);
+            for event in DirWalk::new(root, policy) {
+                match event.unwrap() {
+                    WalkEvent::Entry(e) if e.is_file() => files += 1,
+                    WalkEvent::Entry(_) => {}
+                    WalkEvent::Loop(_) => loops += 1,
+                }
+            }
+            (files, loops)
+        };
+
+        assert_eq!(walk(SymlinkPolicy::Never), (1, 0));
+        assert_eq!(walk(SymlinkPolicy::CommandLine), (1, 0));
+        let (files, loops) = walk(SymlinkPolicy::Always);
+        assert_eq!(files, 1);
+        assert_eq!(loops, 2);
+
+        assert_eq!("command-line".parse::<SymlinkPolicy>().unwrap(), SymlinkPolicy::CommandLine);
+        assert!("sometimes".parse::<SymlinkPolicy>().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_entry_info() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("file.txt"), "12345").unwrap();
+        std::os::unix::fs::symlink("file.txt", temp_dir.path().join("link")).unwrap();
+
+        let entries: Vec<EntryInfo> = DirWalk::new(temp_dir.path(), SymlinkPolicy::Never)
+            .filter_map(|e| match e.unwrap() {
+                WalkEvent::Entry(e) if e.depth() == 1 => Some(e),
+                _ => None,
+            })
+            .collect();
+        let file = entries.iter().find(|e| e.path().ends_with("file.txt")).unwrap();
+        let link = entries.iter().find(|e| e.path().ends_with("link")).unwrap();
+
+        assert_eq!(file.kind(), "file");
+        assert_eq!(file.size(), Some(5));
+        // Unfollowed symlinks describe the link itself
+        assert_eq!(link.kind(), "symlink");
+        assert!(link.metadata().unwrap().file_type().is_symlink());
+    }
 }
@@ -0,0 +1,414 @@
+//! File mode parsing with GNU `chmod` semantics
+//!
+//! Supports numeric modes (`755`, `2755`, `00755`) and symbolic modes made of
+//! comma-separated clauses, each with optional `ugoa` classes followed by one
+//! or more operations: `u+x,go-w`, `ug=rw`, `a+rX`, `u+s`, `+t`, `g=u`.
+//!
+//! As in GNU `chmod`:
+//! - without classes, bits set in the umask are left alone (`+w` under
+//!   umask 022 only adds `u+w`)
+//! - `X` adds execute only to directories and files already executable by
+//!   someone
+//! - `s` affects setuid with `u` and setgid with `g`; `t` needs `o` (or no
+//!   class)
+//! - directories keep their setuid/setgid bits unless the mode mentions them;
+//!   numeric modes clear them only with five or more digits (`00755`)
+
+use crate::error::{AiCoreutilsError, Result};
+
+const S_ISUID: u32 = 0o4000;
+const S_ISGID: u32 = 0o2000;
+const S_ISVTX: u32 = 0o1000;
+const S_IRWXU: u32 = 0o700;
+const S_IRWXG: u32 = 0o070;
+const S_IRWXO: u32 = 0o007;
+const READ_BITS: u32 = 0o444;
+const WRITE_BITS: u32 = 0o222;
+const EXEC_BITS: u32 = 0o111;
+
+/// Permission and special bits `chmod` can change
+pub const MODE_BITS: u32 = 0o7777;
+
+/// How an operation derives the bits it applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    /// Fixed bits from `rwxst` or a numeric mode
+    Bits,
+    /// `X`: execute bits, if a directory or already executable
+    ExecIfAny,
+    /// `u`, `g` or `o`: copy that class's current bits
+    Copy,
+}
+
+/// A single `+`, `-` or `=` operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Change {
+    op: char,
+    source: Source,
+    /// Bits selected by the class letters; 0 means none given
+    affected: u32,
+    value: u32,
+    /// Bits the operation names explicitly (protects directory setuid/setgid)
+    mentioned: u32,
+}
+
+/// A parsed mode specification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mode {
+    changes: Vec<Change>,
+    numeric: bool,
+}
+
+impl Mode {
+    /// Parse a numeric or symbolic mode
+    pub fn parse(spec: &str) -> Result<Self> {
+        let invalid = || AiCoreutilsError::InvalidInput(format!("Invalid mode: '{}'", spec));
+
+        if !spec.is_empty() && spec.bytes().all(|b| b.is_ascii_digit()) {
+            let mode = u32::from_str_radix(spec, 8).map_err(|_| invalid())?;
+            if mode > MODE_BITS {
+                return Err(invalid());
+            }
+            let mentioned = if spec.len() < 5 {
+                (mode & (S_ISUID | S_ISGID)) | S_ISVTX | 0o777
+            } else {
+                MODE_BITS
+            };
+            return Ok(Self {
+                changes: vec![Change {
+                    op: '=',
+                    source: Source::Bits,
+                    affected: MODE_BITS,
+                    value: mode,
+                    mentioned,
+                }],
+                numeric: true,
+            });
+        }
+
+        let mut changes = Vec::new();
+        for clause in spec.split(',') {
+            let bytes = clause.as_bytes();
+            let mut i = 0;
+
+            let mut affected = 0;
+            while let Some(&b) = bytes.get(i) {
+                affected |= match b {
+                    b'u' => S_ISUID | S_IRWXU,
+                    b'g' => S_ISGID | S_IRWXG,
+                    b'o' => S_ISVTX | S_IRWXO,
+                    b'a' => MODE_BITS,
+                    _ => break,
+                };
+                i += 1;
+            }
+
+            // Each clause needs at least one operation
+            if !matches!(bytes.get(i), Some(b'+' | b'-' | b'=')) {
+                return Err(invalid());
+            }
+
+            while let Some(&op) = bytes.get(i).filter(|b| matches!(b, b'+' | b'-' | b'=')) {
+                i += 1;
+                let (source, value) = match bytes.get(i) {
+                    Some(&class @ (b'u' | b'g' | b'o')) => {
+                        i += 1;
+                        (Source::Copy, class_bits(class))
+                    }
+                    _ => parse_perms(bytes, &mut i),
+                };
+
+                changes.push(Change {
+                    op: op as char,
+                    source,
+                    affected,
+                    value,
+                    mentioned: if affected != 0 { affected & value } else { value },
+                });
+            }
+
+            if i != bytes.len() {
+                return Err(invalid());
+            }
+        }
+
+        Ok(Self {
+            changes,
+            numeric: false,
+        })
+    }
+
+    /// Whether this is a numeric (octal) mode
+    pub fn is_numeric(&self) -> bool {
+        self.numeric
+    }
+
+    /// Compute the new permission bits for a file
+    ///
+    /// `current` may include file type bits; only [`MODE_BITS`] are returned.
+    /// `umask` applies to operations without class letters.
+    pub fn apply(&self, current: u32, is_dir: bool, umask: u32) -> u32 {
+        let mut mode = current & MODE_BITS;
+
+        for change in &self.changes {
+            let omit = if is_dir { (S_ISUID | S_ISGID) & !change.mentioned } else { 0 };
+            let mut value = change.value;
+
+            match change.source {
+                Source::Bits => {}
+                Source::Copy => {
+                    value &= mode;
+                    value |= spread(value & READ_BITS, READ_BITS)
+                        | spread(value & WRITE_BITS, WRITE_BITS)
+                        | spread(value & EXEC_BITS, EXEC_BITS);
+                }
+                Source::ExecIfAny => {
+                    if mode & EXEC_BITS != 0 || is_dir {
+                        value |= EXEC_BITS;
+                    }
+                }
+            }
+
+            let limit = if change.affected != 0 { change.affected } else { !umask };
+            value &= limit & !omit;
+
+            match change.op {
+                '=' => {
+                    let preserved = if change.affected != 0 { !change.affected } else { 0 } | omit;
+                    mode = (mode & preserved) | value;
+                }
+                '+' => mode |= value,
+                _ => mode &= !value,
+            }
+        }
+
+        mode & MODE_BITS
+    }
+}
+
+impl std::str::FromStr for Mode {
+    type Err = AiCoreutilsError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Mode::parse(s)
+    }
+}
+
+/// Permission bits of the `u`, `g` or `o` class
+fn class_bits(class: u8) -> u32 {
+    match class {
+        b'u' => S_IRWXU,
+        b'g' => S_IRWXG,
+        _ => S_IRWXO,
+    }
+}
+
+/// Parse a run of `rwxXst` letters starting at `*i`
+fn parse_perms(bytes: &[u8], i: &mut usize) -> (Source, u32) {
+    let mut source = Source::Bits;
+    let mut value = 0;
+    while let Some(&b) = bytes.get(*i) {
+        match b {
+            b'r' => value |= READ_BITS,
+            b'w' => value |= WRITE_BITS,
+            b'x' => value |= EXEC_BITS,
+            b'X' => source = Source::ExecIfAny,
+            b's' => value |= S_ISUID | S_ISGID,
+            b't' => value |= S_ISVTX,
+            _ => break,
+        }
+        *i += 1;
+    }
+    (source, value)
+}
+
+/// All bits of `class_bits` if any of `bits` is set
+fn spread(bits: u32, class_bits: u32) -> u32 {
+    if bits != 0 {
+        class_bits
+    } else {
+        0
+    }
+}
+
+/// The process umask
+#[cfg(unix)]
+pub fn current_umask() -> u32 {
+    // SAFETY: umask cannot fail; the original value is restored immediately
+    unsafe {
+        let mask = libc::umask(0o022);
+        libc::umask(mask);
+        mask as u32
+    }
+}
+
+/// The process umask (always 0 where there is none)
+#[cfg(not(unix))]
+pub fn current_umask() -> u32 {
+    0
+}
+
+/// Render permission bits as `ls -l` does, e.g. `rwxr-sr-t`
+pub fn to_symbolic(mode: u32) -> String {
+    let class = |read: u32, write: u32, exec: u32, special: u32, set: char, unset: char| {
+        let mut s = String::with_capacity(3);
+        s.push(if mode & read != 0 { 'r' } else { '-' });
+        s.push(if mode & write != 0 { 'w' } else { '-' });
+        s.push(match (mode & exec != 0, mode & special != 0) {
+            (true, true) => set,
+            (false, true) => unset,
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+        s
+    };
+
+    class(0o400, 0o200, 0o100, S_ISUID, 's', 'S')
+        + &class(0o040, 0o020, 0o010, S_ISGID, 's', 'S')
+        + &class(0o004, 0o002, 0o001, S_ISVTX, 't', 'T')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(spec: &str, current: u32) -> u32 {
+        Mode::parse(spec).unwrap().apply(current, false, 0o022)
+    }
+
+    #[test]
+    fn test_numeric() {
+        assert_eq!(apply("755", 0o100644), 0o755);
+        assert_eq!(apply("4755", 0o644), 0o4755);
+        assert!(Mode::parse("8").is_err());
+        assert!(Mode::parse("17777").is_err());
+
+        // Directories keep setgid unless five digits are given
+        let mode = Mode::parse("755").unwrap();
+        assert_eq!(mode.apply(0o2775, true, 0), 0o2755);
+        assert_eq!(Mode::parse("00755").unwrap().apply(0o2775, true, 0), 0o755);
+    }
+
+    #[test]
+    fn test_symbolic_clauses() {
+        assert_eq!(apply("u+x,go-w", 0o666), 0o744);
+        assert_eq!(apply("ug+rw", 0o400), 0o660);
+        assert_eq!(apply("u=rwx,g=rx,o=", 0o777), 0o750);
+        assert_eq!(apply("u+r-w", 0o200), 0o400);
+        assert_eq!(apply("g=u", 0o640), 0o660);
+        assert_eq!(apply("o=g-w", 0o670), 0o675);
+    }
+
+    #[test]
+    fn test_single_class_bits() {
+        // Only the named class changes, not every class
+        assert_eq!(apply("u+w", 0o444), 0o644);
+        assert_eq!(apply("o-r", 0o444), 0o440);
+        assert_eq!(apply("g=x", 0o777), 0o717);
+    }
+
+    #[test]
+    fn test_special_bits() {
+        assert_eq!(apply("u+s", 0o755), 0o4755);
+        assert_eq!(apply("g+s", 0o755), 0o2755);
+        assert_eq!(apply("+t", 0o755), 0o1755);
+        assert_eq!(apply("o+s", 0o755), 0o755);
+        assert_eq!(apply("a-s", 0o6755), 0o755);
+        assert_eq!(to_symbolic(0o7755), "rwsr-sr-t");
+        assert_eq!(to_symbolic(0o1644), "rw-r--r-T");
+    }
+
+    #[test]
+    fn test_umask_and_capital_x() {
+        // No class: bits in the umask are left alone
+        assert_eq!(apply("+w", 0o444), 0o644);
+        assert_eq!(apply("=rw", 0o777), 0o644);
+        assert_eq!(apply("a+w", 0o444), 0o666);
+
+        let mode = Mode::parse("a+X").unwrap();
+        assert_eq!(mode.apply(0o644, false, 0), 0o644);
+        assert_eq!(mode.apply(0o744, false, 0), 0o755);
+        assert_eq!(mode.apply(0o644, true, 0), 0o755);
+    }
+
+    #[test]
+    fn test_invalid() {
+        for spec in ["", "u", "u+q", "z+x", "u+x,", "+x,,g-w", "u+x g-w"] {
+            assert!(Mode::parse(spec).is_err(), "{:?} should be rejected", spec);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    mod against_chmod {
+        use super::*;
+        use proptest::prelude::*;
+        use std::os::unix::fs::PermissionsExt;
+        use std::process::Command;
+
+        fn clause() -> impl Strategy<Value = String> {
+            let who = proptest::sample::subsequence(vec!['u', 'g', 'o', 'a'], 0..=2);
+            let perms = prop_oneof![
+                proptest::sample::subsequence(vec!['r', 'w', 'x', 'X', 's', 't'], 0..=3)
+                    .prop_map(|p| p.into_iter().collect::<String>()),
+                Just("u".to_string()),
+                Just("g".to_string()),
+                Just("o".to_string()),
+            ];
+            let ops = proptest::collection::vec((prop_oneof![Just('+'), Just('-'), Just('=')], perms), 1..=2);
+            (who, ops).prop_map(|(who, ops)| {
+                let mut s: String = who.into_iter().collect();
+                for (op, perms) in ops {
+                    s.push(op);
+                    s.push_str(&perms);
+                }
+                s
+            })
+        }
+
+        fn spec() -> impl Strategy<Value = String> {
+            prop_oneof![
+                proptest::collection::vec(clause(), 1..=3).prop_map(|c| c.join(",")),
+                (0u32..=0o7777, 3usize..=5).prop_map(|(m, width)| format!("{:0width$o}", m, width = width)),
+            ]
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(128))]
+
+            /// Results match the system chmod for files and directories
+            #[test]
+            fn matches_system_chmod(spec in spec(), initial in 0u32..=0o7777, is_dir: bool) {
+                let Ok(status) = Command::new("chmod").arg("--version").output() else {
+                    return Ok(());
+                };
+                if !String::from_utf8_lossy(&status.stdout).contains("GNU") {
+                    return Ok(());
+                }
+
+                let temp_dir = tempfile::TempDir::new().unwrap();
+                let path = temp_dir.path().join("target");
+                if is_dir {
+                    std::fs::create_dir(&path).unwrap();
+                } else {
+                    std::fs::write(&path, "").unwrap();
+                }
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(initial)).unwrap();
+                // Reading back avoids bits the filesystem refuses (e.g. setgid without group membership)
+                let before = std::fs::metadata(&path).unwrap().permissions().mode() & MODE_BITS;
+
+                let output = Command::new("chmod").arg("--").arg(&spec).arg(&path).output().unwrap();
+                let after = std::fs::metadata(&path).unwrap().permissions().mode() & MODE_BITS;
+                // Restore access so TempDir can clean up
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o700)).unwrap();
+
+                let ours = Mode::parse(&spec).map(|m| m.apply(before, is_dir, current_umask()));
+                match ours {
+                    Ok(mode) => {
+                        prop_assert_eq!(mode, after, "spec {} on {:o}", spec, before);
+                    }
+                    Err(_) => prop_assert!(!output.status.success(), "chmod accepted {}", spec),
+                }
+            }
+        }
+    }
+}
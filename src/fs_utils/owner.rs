@@ -0,0 +1,259 @@
+//! File ownership: owner specs, user/group name resolution and `chown`
+//!
+//! Owner specs follow GNU `chown`: `user`, `user:group`, `user:` (the user's
+//! login group), `:group`, and the deprecated `user.group`. Names are
+//! resolved through the system user database (`getpwnam_r`/`getgrnam_r`, so
+//! NSS sources such as LDAP work); a name that does not exist but is numeric
+//! is taken as an ID, and a leading `+` forces a numeric ID.
+
+use crate::error::{AiCoreutilsError, Result};
+use std::path::Path;
+
+/// A parsed owner specification; `None` leaves that ID unchanged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Owner {
+    /// New user ID
+    pub uid: Option<u32>,
+    /// New group ID
+    pub gid: Option<u32>,
+}
+
+impl Owner {
+    /// Parse and resolve an owner specification
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (user, group) = match spec.split_once(':') {
+            Some((user, group)) => (user, Some(group)),
+            // `user.group` is only tried when no user has that dotted name
+            None => match spec.split_once('.') {
+                Some((user, group)) if !user.is_empty() && lookup_user(spec)?.is_none() => {
+                    (user, Some(group))
+                }
+                _ => (spec, None),
+            },
+        };
+
+        let mut owner = Owner { uid: None, gid: None };
+
+        if !user.is_empty() {
+            let (uid, login_gid) = resolve_user(user)?;
+            owner.uid = Some(uid);
+            // `user:` means the user's login group
+            if group == Some("") {
+                owner.gid = Some(login_gid.ok_or_else(|| {
+                    AiCoreutilsError::InvalidInput(format!(
+                        "Cannot get the login group of a numeric UID: '{}'",
+                        user
+                    ))
+                })?);
+            }
+        }
+
+        if let Some(group) = group.filter(|g| !g.is_empty()) {
+            owner.gid = Some(resolve_group(group)?);
+        }
+
+        if owner.uid.is_none() && owner.gid.is_none() && spec != ":" {
+            return Err(AiCoreutilsError::InvalidInput(format!(
+                "Invalid owner specification: '{}'",
+                spec
+            )));
+        }
+
+        Ok(owner)
+    }
+}
+
+/// Resolve a user name or ID to a UID and, for names, the login group
+fn resolve_user(user: &str) -> Result<(u32, Option<u32>)> {
+    let invalid = || AiCoreutilsError::InvalidInput(format!("Invalid user: '{}'", user));
+
+    if let Some(id) = user.strip_prefix('+') {
+        return id.parse().map(|uid| (uid, None)).map_err(|_| invalid());
+    }
+    if let Some((uid, gid)) = lookup_user(user)? {
+        return Ok((uid, Some(gid)));
+    }
+    user.parse().map(|uid| (uid, None)).map_err(|_| invalid())
+}
+
+/// Resolve a group name or ID to a GID
+fn resolve_group(group: &str) -> Result<u32> {
+    let invalid = || AiCoreutilsError::InvalidInput(format!("Invalid group: '{}'", group));
+
+    if let Some(id) = group.strip_prefix('+') {
+        return id.parse().map_err(|_| invalid());
+    }
+    if let Some(gid) = lookup_group(group)? {
+        return Ok(gid);
+    }
+    group.parse().map_err(|_| invalid())
+}
+
+/// Look up a user by name, returning its UID and login GID
+#[cfg(unix)]
+pub fn lookup_user(name: &str) -> Result<Option<(u32, u32)>> {
+    let Ok(cname) = std::ffi::CString::new(name) else {
+        return Ok(None);
+    };
+    with_buffer(|buf| {
+        // SAFETY: passwd is plain old data filled in by getpwnam_r
+        let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut result = std::ptr::null_mut();
+        // SAFETY: all pointers are valid for the duration of the call
+        let rc = unsafe {
+            libc::getpwnam_r(cname.as_ptr(), &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result)
+        };
+        (rc, (!result.is_null()).then_some((pwd.pw_uid, pwd.pw_gid)))
+    })
+}
+
+/// Look up a group by name, returning its GID
+#[cfg(unix)]
+pub fn lookup_group(name: &str) -> Result<Option<u32>> {
+    let Ok(cname) = std::ffi::CString::new(name) else {
+        return Ok(None);
+    };
+    with_buffer(|buf| {
+        // SAFETY: group is plain old data filled in by getgrnam_r
+        let mut grp: libc::group = unsafe { std::mem::zeroed() };
+        let mut result = std::ptr::null_mut();
+        // SAFETY: all pointers are valid for the duration of the call
+        let rc = unsafe {
+            libc::getgrnam_r(cname.as_ptr(), &mut grp, buf.as_mut_ptr(), buf.len(), &mut result)
+        };
+        (rc, (!result.is_null()).then_some(grp.gr_gid))
+    })
+}
+
+/// Name of the user with `uid`, if it has one
+#[cfg(unix)]
+pub fn user_name(uid: u32) -> Option<String> {
+    with_buffer(|buf| {
+        // SAFETY: passwd is plain old data filled in by getpwuid_r
+        let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut result = std::ptr::null_mut();
+        // SAFETY: all pointers are valid for the duration of the call
+        let rc = unsafe { libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+        // SAFETY: on success pw_name points into `buf`
+        (rc, (!result.is_null()).then(|| unsafe { c_string(pwd.pw_name) }))
+    })
+    .ok()
+    .flatten()
+}
+
+/// Name of the group with `gid`, if it has one
+#[cfg(unix)]
+pub fn group_name(gid: u32) -> Option<String> {
+    with_buffer(|buf| {
+        // SAFETY: group is plain old data filled in by getgrgid_r
+        let mut grp: libc::group = unsafe { std::mem::zeroed() };
+        let mut result = std::ptr::null_mut();
+        // SAFETY: all pointers are valid for the duration of the call
+        let rc = unsafe { libc::getgrgid_r(gid, &mut grp, buf.as_mut_ptr(), buf.len(), &mut result) };
+        // SAFETY: on success gr_name points into `buf`
+        (rc, (!result.is_null()).then(|| unsafe { c_string(grp.gr_name) }))
+    })
+    .ok()
+    .flatten()
+}
+
+/// Call a `get*_r` function, growing the scratch buffer on `ERANGE`
+#[cfg(unix)]
+fn with_buffer<T>(mut call: impl FnMut(&mut [libc::c_char]) -> (libc::c_int, Option<T>)) -> Result<Option<T>> {
+    let mut buf = vec![0 as libc::c_char; 1024];
+    loop {
+        match call(&mut buf) {
+            (0, value) => return Ok(value),
+            (libc::ERANGE, _) if buf.len() < 1 << 20 => buf.resize(buf.len() * 2, 0),
+            // "Not found" is reported as an error by some libcs
+            (libc::ENOENT | libc::ESRCH | libc::EBADF | libc::EPERM, _) => return Ok(None),
+            (rc, _) => return Err(AiCoreutilsError::Io(std::io::Error::from_raw_os_error(rc))),
+        }
+    }
+}
+
+#[cfg(unix)]
+unsafe fn c_string(ptr: *const libc::c_char) -> String {
+    std::ffi::CStr::from_ptr(ptr).to_string_lossy().into_owned()
+}
+
+/// Change the owner and/or group of `path`
+///
+/// With `follow` false, a symlink itself is changed (`lchown`) rather than
+/// its target.
+#[cfg(unix)]
+pub fn chown(path: &Path, owner: Owner, follow: bool) -> Result<()> {
+    let result = if follow {
+        std::os::unix::fs::chown(path, owner.uid, owner.gid)
+    } else {
+        std::os::unix::fs::lchown(path, owner.uid, owner.gid)
+    };
+    result.map_err(AiCoreutilsError::Io)
+}
+
+/// Look up a user by name (no user database on this platform)
+#[cfg(not(unix))]
+pub fn lookup_user(_name: &str) -> Result<Option<(u32, u32)>> {
+    Ok(None)
+}
+
+/// Look up a group by name (no group database on this platform)
+#[cfg(not(unix))]
+pub fn lookup_group(_name: &str) -> Result<Option<u32>> {
+    Ok(None)
+}
+
+/// Change ownership (unsupported on this platform)
+#[cfg(not(unix))]
+pub fn chown(_path: &Path, _owner: Owner, _follow: bool) -> Result<()> {
+    Err(AiCoreutilsError::Io(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "chown is not supported on this platform",
+    )))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_numeric() {
+        assert_eq!(Owner::parse("1000").unwrap(), Owner { uid: Some(1000), gid: None });
+        assert_eq!(Owner::parse("1000:50").unwrap(), Owner { uid: Some(1000), gid: Some(50) });
+        assert_eq!(Owner::parse(":50").unwrap(), Owner { uid: None, gid: Some(50) });
+        assert_eq!(Owner::parse("+0").unwrap(), Owner { uid: Some(0), gid: None });
+        assert!(Owner::parse("1000:").is_err());
+        assert!(Owner::parse("").is_err());
+        assert!(Owner::parse("no-such-user-xyz").is_err());
+    }
+
+    #[test]
+    fn test_parse_names() {
+        // root exists on every Unix system with UID 0
+        let (uid, gid) = lookup_user("root").unwrap().unwrap();
+        assert_eq!(uid, 0);
+        assert_eq!(Owner::parse("root").unwrap().uid, Some(0));
+        assert_eq!(Owner::parse("root:").unwrap(), Owner { uid: Some(0), gid: Some(gid) });
+        assert_eq!(Owner::parse("root.0").unwrap(), Owner { uid: Some(0), gid: Some(0) });
+        assert_eq!(user_name(0).as_deref(), Some("root"));
+        assert!(lookup_user("no-such-user-xyz").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_chown_to_current_owner() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file = temp_dir.path().join("file");
+        std::fs::write(&file, "x").unwrap();
+        let link = temp_dir.path().join("link");
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+
+        // Changing to the current IDs is always permitted
+        let metadata = std::fs::metadata(&file).unwrap();
+        let owner = Owner { uid: Some(metadata.uid()), gid: Some(metadata.gid()) };
+        chown(&file, owner, true).unwrap();
+        chown(&link, owner, false).unwrap();
+        chown(&file, Owner { uid: None, gid: None }, true).unwrap();
+    }
+}
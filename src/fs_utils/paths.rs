@@ -0,0 +1,193 @@
+//! Lexical path manipulation
+//!
+//! Helpers that compute paths without touching the filesystem, so they give
+//! the same answer whether or not the path exists. They treat `..` as
+//! removing the previous component, which is only equivalent to what the
+//! kernel does when that component is not a symbolic link; use
+//! [`super::resolve::Resolver`] when it may be.
+
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+
+/// Collapse `.` and `..` components and redundant separators
+///
+/// `..` at the root is dropped, as the root is its own parent, while
+/// leading `..` of a relative path are kept. An empty result is `.`.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    // Normal components pushed so far, which `..` may remove
+    let mut depth = 0usize;
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => normalized.push(component.as_os_str()),
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => {
+                normalized.pop();
+                depth -= 1;
+            }
+            Component::ParentDir if normalized.has_root() => {}
+            Component::ParentDir => normalized.push(".."),
+            Component::Normal(name) => {
+                normalized.push(name);
+                depth += 1;
+            }
+        }
+    }
+    if normalized.as_os_str().is_empty() {
+        normalized.push(".");
+    }
+    normalized
+}
+
+/// Remove a Windows verbatim prefix when the path means the same without it
+///
+/// `\\?\C:\dir` becomes `C:\dir` and `\\?\UNC\server\share` becomes
+/// `\\server\share`, the forms most programs accept. Paths that rely on
+/// verbatim semantics, such as ones containing `/`, `.` or `..` components
+/// or names ending in a dot or space, or that are over 260 characters, are
+/// returned unchanged, as is anything else.
+pub fn strip_verbatim(path: &Path) -> PathBuf {
+    let Some(text) = path.to_str() else {
+        return path.to_path_buf();
+    };
+    let stripped = if let Some(rest) = text.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", rest)
+    } else if let Some(rest) = text.strip_prefix(r"\\?\") {
+        let bytes = rest.as_bytes();
+        if bytes.len() < 3 || !bytes[0].is_ascii_alphabetic() || &bytes[1..3] != b":\\" {
+            return path.to_path_buf();
+        }
+        rest.to_string()
+    } else {
+        return path.to_path_buf();
+    };
+    // Win32 path parsing drops trailing dots and spaces, `.` and `..` included
+    let plain = stripped.len() < 260
+        && !stripped.contains('/')
+        && stripped.split('\\').all(|part| !part.ends_with(['.', ' ']));
+    if plain {
+        PathBuf::from(stripped)
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// The current user's home directory
+pub fn home_dir() -> Option<PathBuf> {
+    dirs::home_dir()
+}
+
+/// Expand a leading `~` to the home directory
+///
+/// Only `~` alone or followed by a separator is expanded; `~user` and paths
+/// with no home directory to expand to are returned unchanged.
+pub fn expand_home(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => home_dir()
+            .map(|home| if rest.as_os_str().is_empty() { home } else { home.join(rest) })
+            .unwrap_or_else(|| path.to_path_buf()),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// `path` relative to `base`, after normalizing both
+///
+/// `None` if there is no relative path between them: one is absolute and
+/// the other is not, they are on different Windows drives, or `base` climbs
+/// above `path`'s starting point with `..` so the names to come back
+/// through are unknown.
+pub fn relative_to(path: &Path, base: &Path) -> Option<PathBuf> {
+    let path = normalize(path);
+    let base = normalize(base);
+    if path.has_root() != base.has_root() || prefix(&path) != prefix(&base) {
+        return None;
+    }
+    let path: Vec<_> = path.components().filter(|c| *c != Component::CurDir).collect();
+    let base: Vec<_> = base.components().filter(|c| *c != Component::CurDir).collect();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    if base[common..].contains(&Component::ParentDir) {
+        return None;
+    }
+
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for component in &path[common..] {
+        relative.push(component.as_os_str());
+    }
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    Some(relative)
+}
+
+fn prefix(path: &Path) -> Option<OsString> {
+    match path.components().next() {
+        Some(Component::Prefix(prefix)) => Some(prefix.as_os_str().to_ascii_lowercase()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalized(path: &str) -> PathBuf {
+        normalize(Path::new(path))
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalized("a/./b//c/../d"), PathBuf::from("a/b/d"));
+        assert_eq!(normalized("a/.."), PathBuf::from("."));
+        assert_eq!(normalized("../../a/../b"), PathBuf::from("../../b"));
+        assert_eq!(normalized("a/../../b"), PathBuf::from("../b"));
+        assert_eq!(normalized(""), PathBuf::from("."));
+        #[cfg(unix)]
+        {
+            assert_eq!(normalized("/../a/./b/.."), PathBuf::from("/a"));
+            assert_eq!(normalized("/.."), PathBuf::from("/"));
+        }
+    }
+
+    #[test]
+    fn test_strip_verbatim() {
+        let strip = |path: &str| strip_verbatim(Path::new(path));
+        assert_eq!(strip(r"\\?\C:\Users\me"), PathBuf::from(r"C:\Users\me"));
+        assert_eq!(strip(r"\\?\UNC\server\share\dir"), PathBuf::from(r"\\server\share\dir"));
+        // Verbatim paths whose meaning would change are kept
+        assert_eq!(strip(r"\\?\C:\a\..\b"), PathBuf::from(r"\\?\C:\a\..\b"));
+        assert_eq!(strip(r"\\?\C:\a/b"), PathBuf::from(r"\\?\C:\a/b"));
+        assert_eq!(strip(r"\\?\C:\name."), PathBuf::from(r"\\?\C:\name."));
+        assert_eq!(strip(r"\\?\Volume{123}\dir"), PathBuf::from(r"\\?\Volume{123}\dir"));
+        assert_eq!(strip("/usr/lib"), PathBuf::from("/usr/lib"));
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let Some(home) = home_dir() else {
+            return;
+        };
+        assert_eq!(expand_home(Path::new("~")), home);
+        assert_eq!(expand_home(Path::new("~/notes.txt")), home.join("notes.txt"));
+        assert_eq!(expand_home(Path::new("~other/x")), PathBuf::from("~other/x"));
+        assert_eq!(expand_home(Path::new("a/~")), PathBuf::from("a/~"));
+    }
+
+    #[test]
+    fn test_relative_to() {
+        let rel = |path: &str, base: &str| relative_to(Path::new(path), Path::new(base));
+        assert_eq!(rel("a/b/c", "a"), Some(PathBuf::from("b/c")));
+        assert_eq!(rel("a/b", "a/x/y"), Some(PathBuf::from("../../b")));
+        assert_eq!(rel("a", "./a/."), Some(PathBuf::from(".")));
+        assert_eq!(rel("src/../lib", "src"), Some(PathBuf::from("../lib")));
+        assert_eq!(rel("../x", "."), Some(PathBuf::from("../x")));
+        assert_eq!(rel("x", ".."), None);
+        #[cfg(unix)]
+        {
+            assert_eq!(rel("/", "/a"), Some(PathBuf::from("..")));
+            assert_eq!(rel("/a", "a"), None);
+        }
+    }
+}
@@ -0,0 +1,250 @@
+//! Step-by-step path canonicalization
+//!
+//! Resolves a path one component at a time, as `realpath` does, but records
+//! every symbolic link expanded on the way so a chain of links can be
+//! followed hop by hop. A link reached again with the same components left
+//! to resolve is reported as a loop rather than as the kernel's generic
+//! "too many levels of symbolic links".
+
+use std::collections::{HashSet, VecDeque};
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Most symlinks expanded in one resolution, as Linux's `MAXSYMLINKS`
+pub const MAX_HOPS: usize = 40;
+
+/// Which components of a path must exist
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MustExist {
+    /// Every component, as `realpath -e`
+    All,
+    /// Every component but the last, as `realpath` without options
+    #[default]
+    AllButLast,
+    /// None of them, as `realpath -m`
+    None,
+}
+
+/// One symbolic link expanded during a resolution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hop {
+    /// Absolute path of the link
+    pub link: PathBuf,
+    /// The link's contents, as stored
+    pub target: PathBuf,
+}
+
+/// Why a resolution stopped early
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveFailure {
+    /// A link was reached again with the same components left to resolve
+    Loop,
+    /// More than [`MAX_HOPS`] links were expanded
+    TooManyLinks,
+    /// A component that must exist does not
+    Missing,
+    /// A component followed by others is not a directory
+    NotADirectory,
+    /// A component could not be inspected
+    Io(String),
+}
+
+impl ResolveFailure {
+    /// Name used in JSONL output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResolveFailure::Loop => "loop",
+            ResolveFailure::TooManyLinks => "too_many_links",
+            ResolveFailure::Missing => "missing",
+            ResolveFailure::NotADirectory => "not_a_directory",
+            ResolveFailure::Io(_) => "io",
+        }
+    }
+}
+
+/// The outcome of resolving one path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolution {
+    /// The canonical path; on failure, the component resolution stopped at
+    pub path: PathBuf,
+    /// Links expanded, in order
+    pub hops: Vec<Hop>,
+    /// Whether the canonical path exists
+    pub exists: bool,
+    /// Why resolution stopped early, if it did
+    pub failure: Option<ResolveFailure>,
+}
+
+/// Canonicalizes paths, recording each symlink expanded
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Resolver {
+    /// Which components must exist
+    pub must_exist: MustExist,
+    /// Resolve `..` lexically and leave symlinks in place, as `realpath -s`
+    pub no_symlinks: bool,
+}
+
+impl Resolver {
+    /// Resolve `path`, relative paths against the current directory
+    pub fn resolve(&self, path: &Path) -> io::Result<Resolution> {
+        let cwd = if path.is_absolute() { PathBuf::new() } else { std::env::current_dir()? };
+        let mut resolved = PathBuf::new();
+        let mut remaining = VecDeque::new();
+        restart(&mut resolved, &mut remaining, &cwd, path);
+
+        let mut hops = Vec::new();
+        let mut seen = HashSet::new();
+        let mut missing = false;
+        let fail = |path: PathBuf, hops: Vec<Hop>, failure| {
+            Ok(Resolution { path, hops, exists: false, failure: Some(failure) })
+        };
+
+        while let Some(name) = remaining.pop_front() {
+            if name == ".." {
+                resolved.pop();
+                continue;
+            }
+            let candidate = resolved.join(&name);
+            // Past a missing component there is nothing left to inspect
+            if missing {
+                resolved = candidate;
+                continue;
+            }
+            match fs::symlink_metadata(&candidate) {
+                Ok(metadata) if metadata.file_type().is_symlink() && !self.no_symlinks => {
+                    let target = fs::read_link(&candidate)?;
+                    if !seen.insert((candidate.clone(), remaining.clone())) {
+                        hops.push(Hop { link: candidate.clone(), target });
+                        return fail(candidate, hops, ResolveFailure::Loop);
+                    }
+                    if hops.len() == MAX_HOPS {
+                        return fail(candidate, hops, ResolveFailure::TooManyLinks);
+                    }
+                    let parent = resolved.clone();
+                    restart(&mut resolved, &mut remaining, &parent, &target);
+                    hops.push(Hop { link: candidate, target });
+                }
+                Ok(metadata) => {
+                    let is_dir = if metadata.file_type().is_symlink() {
+                        fs::metadata(&candidate).is_ok_and(|m| m.is_dir())
+                    } else {
+                        metadata.is_dir()
+                    };
+                    if !is_dir && !remaining.is_empty() && self.must_exist != MustExist::None {
+                        return fail(candidate, hops, ResolveFailure::NotADirectory);
+                    }
+                    resolved = candidate;
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    let required = match self.must_exist {
+                        MustExist::All => true,
+                        MustExist::AllButLast => !remaining.is_empty(),
+                        MustExist::None => false,
+                    };
+                    if required {
+                        return fail(candidate, hops, ResolveFailure::Missing);
+                    }
+                    missing = true;
+                    resolved = candidate;
+                }
+                Err(e) => return fail(candidate, hops, ResolveFailure::Io(e.to_string())),
+            }
+        }
+
+        let exists = !missing && fs::metadata(&resolved).is_ok();
+        Ok(Resolution { path: resolved, hops, exists, failure: None })
+    }
+}
+
+/// Queue the components of `path` in front of those still `remaining`,
+/// restarting from the root if it is absolute and from `base` otherwise
+fn restart(resolved: &mut PathBuf, remaining: &mut VecDeque<OsString>, base: &Path, path: &Path) {
+    let mut names = Vec::new();
+    let mut prefixed = false;
+    *resolved = base.to_path_buf();
+    for component in path.components() {
+        match component {
+            Component::Prefix(prefix) => {
+                *resolved = PathBuf::from(prefix.as_os_str());
+                prefixed = true;
+            }
+            Component::RootDir => {
+                if !prefixed {
+                    *resolved = PathBuf::new();
+                }
+                resolved.push(component.as_os_str());
+            }
+            Component::CurDir => {}
+            Component::ParentDir => names.push(OsString::from("..")),
+            Component::Normal(name) => names.push(name.to_os_string()),
+        }
+    }
+    for name in names.into_iter().rev() {
+        remaining.push_front(name);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use tempfile::TempDir;
+
+    fn canonical_tempdir() -> (TempDir, PathBuf) {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        (temp_dir, root)
+    }
+
+    #[test]
+    fn test_resolve_records_each_hop() {
+        let (_temp_dir, root) = canonical_tempdir();
+        fs::create_dir(root.join("real")).unwrap();
+        fs::write(root.join("real/file"), "x").unwrap();
+        symlink("real", root.join("dir")).unwrap();
+        symlink("dir/file", root.join("first")).unwrap();
+        symlink(root.join("first"), root.join("second")).unwrap();
+
+        let resolution = Resolver::default().resolve(&root.join("second")).unwrap();
+        assert_eq!(resolution.path, root.join("real/file"));
+        assert!(resolution.exists);
+        assert_eq!(resolution.failure, None);
+        let links: Vec<_> = resolution.hops.iter().map(|hop| hop.link.clone()).collect();
+        assert_eq!(links, [root.join("second"), root.join("first"), root.join("dir")]);
+        assert_eq!(resolution.hops[1].target, PathBuf::from("dir/file"));
+
+        // `..` applies to where a link leads, not to the link's own name
+        let up = Resolver::default().resolve(&root.join("dir/../real/./file")).unwrap();
+        assert_eq!(up.path, root.join("real/file"));
+        let lexical = Resolver { no_symlinks: true, ..Resolver::default() };
+        assert_eq!(lexical.resolve(&root.join("dir/../second")).unwrap().path, root.join("second"));
+    }
+
+    #[test]
+    fn test_resolve_loops_and_missing_components() {
+        let (_temp_dir, root) = canonical_tempdir();
+        symlink("b", root.join("a")).unwrap();
+        symlink("a", root.join("b")).unwrap();
+        fs::write(root.join("file"), "x").unwrap();
+
+        let looped = Resolver::default().resolve(&root.join("a")).unwrap();
+        assert_eq!(looped.failure, Some(ResolveFailure::Loop));
+        assert_eq!(looped.hops.len(), 3);
+        assert_eq!(looped.hops[2].link, root.join("a"));
+
+        let last_missing = Resolver::default().resolve(&root.join("new")).unwrap();
+        assert_eq!((last_missing.failure, last_missing.exists), (None, false));
+        let inner_missing = Resolver::default().resolve(&root.join("gone/new")).unwrap();
+        assert_eq!(inner_missing.failure, Some(ResolveFailure::Missing));
+        assert_eq!(inner_missing.path, root.join("gone"));
+        let all = Resolver { must_exist: MustExist::All, ..Resolver::default() };
+        assert_eq!(all.resolve(&root.join("new")).unwrap().failure, Some(ResolveFailure::Missing));
+        let none = Resolver { must_exist: MustExist::None, ..Resolver::default() };
+        assert_eq!(none.resolve(&root.join("gone/x/../y")).unwrap().path, root.join("gone/y"));
+
+        let not_dir = Resolver::default().resolve(&root.join("file/x")).unwrap();
+        assert_eq!(not_dir.failure, Some(ResolveFailure::NotADirectory));
+    }
+}
@@ -0,0 +1,499 @@
+//! Git status, last-commit annotations and line blame
+//!
+//! With the `git` feature, [`GitAnnotator`] tells ai-ls and ai-find whether
+//! an entry is clean, modified, untracked or ignored in the repository that
+//! contains it, and which commit last changed it. Each repository is opened
+//! and its status read once; history is walked newest first and only as far
+//! as needed to find the paths asked about, so listing a few files of a
+//! large repository stays cheap. [`blame`] attributes each line of a file,
+//! as it is in the working tree, to the commit that last changed it.
+//! Without the feature the types still exist but [`GitAnnotator::new`] and
+//! [`blame`] fail.
+
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+/// State of a path in the working tree, relative to `HEAD` and the index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GitStatus {
+    /// Tracked and unchanged (for directories: nothing beneath has changed)
+    Clean,
+    /// Changed in the index or the working tree
+    Modified,
+    /// New in the index
+    Added,
+    /// Removed from the index
+    Deleted,
+    /// Not tracked and not ignored
+    Untracked,
+    /// Matched by an ignore rule
+    Ignored,
+    /// Has unresolved merge conflicts
+    Conflicted,
+}
+
+/// The commit that last changed a path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LastCommit {
+    /// Full commit hash
+    pub hash: String,
+    /// Author name
+    pub author: String,
+    /// Author email
+    pub email: String,
+    /// Author time as RFC 3339, in the author's time zone
+    pub time: String,
+    /// First line of the commit message
+    pub summary: String,
+}
+
+#[cfg(feature = "git")]
+impl LastCommit {
+    fn from_commit(commit: &git2::Commit) -> Self {
+        let author = commit.author();
+        let when = author.when();
+        let time = chrono::FixedOffset::east_opt(when.offset_minutes() * 60)
+            .and_then(|zone| chrono::DateTime::from_timestamp(when.seconds(), 0).map(|t| t.with_timezone(&zone)))
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default();
+        LastCommit {
+            hash: commit.id().to_string(),
+            author: String::from_utf8_lossy(author.name_bytes()).into_owned(),
+            email: String::from_utf8_lossy(author.email_bytes()).into_owned(),
+            time,
+            summary: String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default()).into_owned(),
+        }
+    }
+}
+
+/// Git information about one path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitAnnotation {
+    /// Working tree status
+    pub status: GitStatus,
+    /// Last commit that touched the path (anything beneath it, for directories)
+    pub last_commit: Option<LastCommit>,
+}
+
+/// One line of a blamed file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlameLine {
+    /// Line number in the working tree file, from 1
+    pub line: usize,
+    /// Line number in the file as of `commit`, `None` if not committed
+    pub original_line: Option<usize>,
+    /// Text of the line without its line ending
+    pub content: String,
+    /// Commit that last changed the line, `None` if the change is not committed
+    pub commit: Option<LastCommit>,
+}
+
+/// Annotates paths with git information, caching each repository it meets
+pub struct GitAnnotator {
+    #[cfg(feature = "git")]
+    repos: Vec<repo::RepoState>,
+    /// Repository (index into `repos`) of each directory looked up so far
+    #[cfg(feature = "git")]
+    dirs: std::collections::HashMap<std::path::PathBuf, Option<usize>>,
+}
+
+impl GitAnnotator {
+    /// Create an annotator
+    #[cfg(feature = "git")]
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            repos: Vec::new(),
+            dirs: std::collections::HashMap::new(),
+        })
+    }
+
+    /// Create an annotator; always fails without the `git` feature
+    #[cfg(not(feature = "git"))]
+    pub fn new() -> Result<Self> {
+        Err(unsupported())
+    }
+
+    /// Git status and last commit of `path`, or `None` if it is not inside
+    /// the working tree of a repository
+    ///
+    /// Symbolic links are described themselves, not their targets.
+    #[cfg(feature = "git")]
+    pub fn annotate(&mut self, path: &Path) -> Option<GitAnnotation> {
+        let is_dir = std::fs::symlink_metadata(path).ok()?.is_dir();
+        let absolute = match (is_dir, path.file_name()) {
+            (false, Some(name)) => {
+                let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+                parent.canonicalize().ok()?.join(name)
+            }
+            _ => path.canonicalize().ok()?,
+        };
+        let dir = if is_dir { absolute.as_path() } else { absolute.parent()? };
+
+        let index = match self.dirs.get(dir) {
+            Some(index) => *index,
+            None => {
+                let index = self.open(dir);
+                self.dirs.insert(dir.to_path_buf(), index);
+                index
+            }
+        }?;
+        self.repos[index].annotate(&absolute, is_dir)
+    }
+
+    /// Git status and last commit of `path`; never called without the `git` feature
+    #[cfg(not(feature = "git"))]
+    pub fn annotate(&mut self, _path: &Path) -> Option<GitAnnotation> {
+        None
+    }
+
+    /// Find the repository containing `dir`, opening it if it is new
+    #[cfg(feature = "git")]
+    fn open(&mut self, dir: &Path) -> Option<usize> {
+        let repository = git2::Repository::discover(dir).ok()?;
+        let workdir = repository.workdir()?.canonicalize().ok()?;
+        if let Some(index) = self.repos.iter().position(|r| r.workdir == workdir) {
+            return Some(index);
+        }
+        self.repos.push(repo::RepoState::load(repository, workdir).ok()?);
+        Some(self.repos.len() - 1)
+    }
+}
+
+/// Blame the working tree version of `path`, or only `lines` of it
+///
+/// Lines are numbered from 1; a range running past the end of the file
+/// stops at the last line.
+#[cfg(feature = "git")]
+pub fn blame(path: &Path, lines: Option<RangeInclusive<usize>>) -> Result<Vec<BlameLine>> {
+    use crate::error::AiCoreutilsError;
+    use std::collections::HashMap;
+
+    let failed = |e: git2::Error| AiCoreutilsError::InvalidInput(format!("Cannot blame {}: {}", path.display(), e.message()));
+    let absolute = path.canonicalize()?;
+    let repository = git2::Repository::discover(absolute.parent().unwrap_or(&absolute)).map_err(failed)?;
+    let workdir = repository
+        .workdir()
+        .ok_or_else(|| AiCoreutilsError::InvalidInput("Cannot blame in a bare repository".to_string()))?
+        .canonicalize()?;
+    let relative = absolute.strip_prefix(&workdir).map_err(|_| {
+        AiCoreutilsError::InvalidInput(format!("{} is outside the working tree", path.display()))
+    })?;
+
+    // Blame the committed history, then the working tree on top of it
+    let content = std::fs::read(&absolute)?;
+    let committed = repository.blame_file(relative, None).map_err(failed)?;
+    let blame = committed.blame_buffer(&content).map_err(failed)?;
+
+    let mut text: Vec<&[u8]> = content.split(|&b| b == b'\n').collect();
+    if content.ends_with(b"\n") || content.is_empty() {
+        text.pop();
+    }
+    let (start, end) = lines.as_ref().map_or((1, text.len()), |range| (*range.start(), *range.end()));
+    if lines.is_some() && (start == 0 || start > end) {
+        return Err(AiCoreutilsError::InvalidInput(format!("Invalid line range {}..{}", start, end)));
+    }
+    if lines.is_some() && start > text.len() {
+        return Err(AiCoreutilsError::InvalidInput(format!(
+            "Line {} is past the end of {} ({} lines)",
+            start,
+            path.display(),
+            text.len()
+        )));
+    }
+
+    let mut commits: HashMap<git2::Oid, LastCommit> = HashMap::new();
+    let mut blamed = Vec::new();
+    for line in start..=end.min(text.len()) {
+        let hunk = blame.get_line(line).ok_or_else(|| {
+            AiCoreutilsError::InvalidInput(format!("Cannot blame line {} of {}", line, path.display()))
+        })?;
+        let id = hunk.final_commit_id();
+        let commit = match commits.get(&id) {
+            _ if id.is_zero() => None,
+            Some(commit) => Some(commit.clone()),
+            None => {
+                let commit = LastCommit::from_commit(&repository.find_commit(id).map_err(failed)?);
+                commits.insert(id, commit.clone());
+                Some(commit)
+            }
+        };
+        let content = text[line - 1].strip_suffix(b"\r").unwrap_or(text[line - 1]);
+        blamed.push(BlameLine {
+            line,
+            original_line: commit.is_some().then(|| hunk.orig_start_line() + (line - hunk.final_start_line())),
+            content: String::from_utf8_lossy(content).into_owned(),
+            commit,
+        });
+    }
+    Ok(blamed)
+}
+
+/// Blame the working tree version of `path`; always fails without the `git` feature
+#[cfg(not(feature = "git"))]
+pub fn blame(_path: &Path, _lines: Option<RangeInclusive<usize>>) -> Result<Vec<BlameLine>> {
+    Err(unsupported())
+}
+
+#[cfg(not(feature = "git"))]
+fn unsupported() -> crate::error::AiCoreutilsError {
+    crate::error::AiCoreutilsError::NotSupported("git support requires ai-coreutils to be built with the `git` feature".to_string())
+}
+
+#[cfg(feature = "git")]
+mod repo {
+    use super::{GitAnnotation, GitStatus, LastCommit};
+    use git2::{Oid, Repository, Sort, Status, StatusOptions};
+    use std::collections::{HashMap, HashSet};
+    use std::path::{Path, PathBuf};
+
+    /// An opened repository with its status and the history read so far
+    pub(super) struct RepoState {
+        repository: Repository,
+        pub(super) workdir: PathBuf,
+        /// Status of every path that is not clean; ignored and untracked
+        /// directories are reported whole
+        statuses: HashMap<PathBuf, GitStatus>,
+        /// Commits reachable from `HEAD`, newest first
+        commits: Vec<Oid>,
+        /// Number of `commits` whose changes have been read
+        scanned: usize,
+        /// Newest commit (index into `commits`) changing each path or directory
+        changed_in: HashMap<PathBuf, usize>,
+    }
+
+    impl RepoState {
+        pub(super) fn load(repository: Repository, workdir: PathBuf) -> Result<Self, git2::Error> {
+            let mut options = StatusOptions::new();
+            options
+                .include_untracked(true)
+                .recurse_untracked_dirs(false)
+                .include_ignored(true)
+                .recurse_ignored_dirs(false)
+                .exclude_submodules(true);
+            let statuses = repository
+                .statuses(Some(&mut options))?
+                .iter()
+                .filter_map(|entry| {
+                    let path = entry.path()?.trim_end_matches('/');
+                    Some((PathBuf::from(path), status_of(entry.status())))
+                })
+                .collect();
+
+            // An unborn HEAD has no history
+            let commits = {
+                let mut walk = repository.revwalk()?;
+                walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+                match walk.push_head() {
+                    Ok(()) => walk.collect::<Result<_, _>>()?,
+                    Err(_) => Vec::new(),
+                }
+            };
+
+            Ok(Self {
+                repository,
+                workdir,
+                statuses,
+                commits,
+                scanned: 0,
+                changed_in: HashMap::new(),
+            })
+        }
+
+        pub(super) fn annotate(&mut self, absolute: &Path, is_dir: bool) -> Option<GitAnnotation> {
+            let relative = absolute.strip_prefix(&self.workdir).ok()?;
+            if relative.starts_with(".git") {
+                return None;
+            }
+            let status = self.status(relative, is_dir);
+            let last_commit = match status {
+                GitStatus::Untracked | GitStatus::Ignored | GitStatus::Added => None,
+                _ => self.last_commit(relative),
+            };
+            Some(GitAnnotation { status, last_commit })
+        }
+
+        fn status(&self, relative: &Path, is_dir: bool) -> GitStatus {
+            // The path itself, or an ignored or untracked directory above it
+            if let Some(status) = relative.ancestors().find_map(|p| self.statuses.get(p)) {
+                return *status;
+            }
+            let changed_beneath = || {
+                self.statuses
+                    .iter()
+                    .any(|(path, status)| *status != GitStatus::Ignored && path.starts_with(relative))
+            };
+            if is_dir && changed_beneath() {
+                GitStatus::Modified
+            } else {
+                GitStatus::Clean
+            }
+        }
+
+        fn last_commit(&mut self, relative: &Path) -> Option<LastCommit> {
+            while !self.changed_in.contains_key(relative) && self.scanned < self.commits.len() {
+                self.scan_next().ok()?;
+            }
+            let commit = self.repository.find_commit(self.commits[*self.changed_in.get(relative)?]).ok()?;
+            Some(LastCommit::from_commit(&commit))
+        }
+
+        /// Record the paths changed by the next commit in history
+        ///
+        /// A merge only counts as changing a path that differs from every
+        /// parent, as in `git log`, so changes are credited to the commit on
+        /// the branch that made them.
+        fn scan_next(&mut self) -> Result<(), git2::Error> {
+            let index = self.scanned;
+            self.scanned += 1;
+            let commit = self.repository.find_commit(self.commits[index])?;
+            let tree = commit.tree()?;
+
+            let mut changed: Option<HashSet<PathBuf>> = None;
+            let parents: Vec<_> = commit.parents().collect();
+            for parent in parents.iter().map(Some).chain(parents.is_empty().then_some(None)) {
+                let parent_tree = parent.map(|p| p.tree()).transpose()?;
+                let diff = self.repository.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+                let paths: HashSet<PathBuf> = diff
+                    .deltas()
+                    .flat_map(|delta| [delta.old_file().path(), delta.new_file().path()])
+                    .flatten()
+                    .map(Path::to_path_buf)
+                    .collect();
+                changed = Some(match changed {
+                    Some(previous) => previous.intersection(&paths).cloned().collect(),
+                    None => paths,
+                });
+            }
+
+            for path in changed.unwrap_or_default() {
+                // Directories up to the repository root ("") changed too
+                for ancestor in path.ancestors() {
+                    self.changed_in.entry(ancestor.to_path_buf()).or_insert(index);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn status_of(status: Status) -> GitStatus {
+        let staged = Status::INDEX_NEW
+            | Status::INDEX_MODIFIED
+            | Status::INDEX_DELETED
+            | Status::INDEX_RENAMED
+            | Status::INDEX_TYPECHANGE;
+        if status.is_conflicted() {
+            GitStatus::Conflicted
+        } else if status.is_ignored() {
+            GitStatus::Ignored
+        } else if status.is_wt_new() && !status.intersects(staged) {
+            GitStatus::Untracked
+        } else if status.is_index_new() {
+            GitStatus::Added
+        } else if status.intersects(Status::INDEX_DELETED | Status::WT_DELETED) {
+            GitStatus::Deleted
+        } else {
+            GitStatus::Modified
+        }
+    }
+}
+
+#[cfg(all(test, feature = "git"))]
+mod tests {
+    use super::*;
+    use git2::{Repository, Signature, Time};
+    use std::fs;
+
+    /// Stage everything and commit it as Jane at `seconds`
+    fn commit(repo: &Repository, seconds: i64, message: &str) {
+        let mut index = repo.index().unwrap();
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+        index.update_all(["*"], None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let author = Signature::new("Jane Doe", "jane@example.com", &Time::new(seconds, 120)).unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        let parents: Vec<_> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &author, &author, message, &tree, &parents).unwrap();
+    }
+
+    #[test]
+    fn test_status_and_last_commit() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        let repo = Repository::init(root).unwrap();
+
+        fs::create_dir_all(root.join("src/util")).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n*.log\n").unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(root.join("src/util/mod.rs"), "// util\n").unwrap();
+        fs::write(root.join("README.md"), "# demo\n").unwrap();
+        commit(&repo, 1_700_000_000, "Initial commit\n\nWith a body");
+        fs::write(root.join("src/util/mod.rs"), "// util v2\n").unwrap();
+        commit(&repo, 1_700_003_600, "Update util");
+
+        fs::write(root.join("README.md"), "# demo, edited\n").unwrap();
+        fs::write(root.join("notes.txt"), "todo\n").unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::write(root.join("target/debug/app"), "binary").unwrap();
+        fs::write(root.join("build.log"), "log").unwrap();
+
+        let mut git = GitAnnotator::new().unwrap();
+        let status = |git: &mut GitAnnotator, path: &str| git.annotate(&root.join(path)).unwrap().status;
+        assert_eq!(status(&mut git, "README.md"), GitStatus::Modified);
+        assert_eq!(status(&mut git, "notes.txt"), GitStatus::Untracked);
+        assert_eq!(status(&mut git, "build.log"), GitStatus::Ignored);
+        assert_eq!(status(&mut git, "target"), GitStatus::Ignored);
+        assert_eq!(status(&mut git, "target/debug/app"), GitStatus::Ignored);
+        assert_eq!(status(&mut git, "src"), GitStatus::Clean);
+        assert_eq!(status(&mut git, "."), GitStatus::Modified);
+        assert_eq!(git.annotate(&root.join(".git/HEAD")), None);
+
+        let main = git.annotate(&root.join("src/main.rs")).unwrap();
+        assert_eq!(main.status, GitStatus::Clean);
+        let first = main.last_commit.unwrap();
+        assert_eq!((first.author.as_str(), first.email.as_str()), ("Jane Doe", "jane@example.com"));
+        assert_eq!((first.time.as_str(), first.summary.as_str()), ("2023-11-15T00:13:20+02:00", "Initial commit"));
+
+        let second = git.annotate(&root.join("src/util/mod.rs")).unwrap().last_commit.unwrap();
+        assert_eq!(second.summary, "Update util");
+        assert_eq!(git.annotate(&root.join("src")).unwrap().last_commit.unwrap(), second);
+        assert_ne!(first.hash, second.hash);
+        assert_eq!(git.annotate(&root.join("notes.txt")).unwrap().last_commit, None);
+
+        let outside = tempfile::TempDir::new().unwrap();
+        fs::write(outside.path().join("loose.txt"), "x").unwrap();
+        assert_eq!(git.annotate(&outside.path().join("loose.txt")), None);
+    }
+
+    #[test]
+    fn test_blame_lines() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        let repo = Repository::init(root).unwrap();
+
+        fs::write(root.join("lib.rs"), "fn a() {}\nfn b() {}\nfn c() {}\n").unwrap();
+        commit(&repo, 1_700_000_000, "Add lib");
+        fs::write(root.join("lib.rs"), "fn a() {}\nfn b2() {}\nfn c() {}\n").unwrap();
+        commit(&repo, 1_700_003_600, "Rename b");
+        fs::write(root.join("lib.rs"), "// header\r\nfn a() {}\nfn b2() {}\nfn c() {}\n").unwrap();
+
+        let lines = blame(&root.join("lib.rs"), None).unwrap();
+        let summaries: Vec<_> = lines.iter().map(|l| l.commit.as_ref().map(|c| c.summary.as_str())).collect();
+        assert_eq!(summaries, [None, Some("Add lib"), Some("Rename b"), Some("Add lib")]);
+        assert_eq!((lines[0].line, lines[0].original_line, lines[0].content.as_str()), (1, None, "// header"));
+        assert_eq!((lines[3].line, lines[3].original_line, lines[3].content.as_str()), (4, Some(3), "fn c() {}"));
+        assert_eq!(lines[2].commit.as_ref().unwrap().time, "2023-11-15T01:13:20+02:00");
+
+        let range = blame(&root.join("lib.rs"), Some(2..=3)).unwrap();
+        assert_eq!(range, lines[1..3]);
+        assert_eq!(blame(&root.join("lib.rs"), Some(4..=99)).unwrap().len(), 1);
+        assert!(blame(&root.join("lib.rs"), Some(5..=6)).is_err());
+        assert!(blame(&root.join("lib.rs"), Some(0..=1)).is_err());
+
+        fs::write(root.join("new.rs"), "x\n").unwrap();
+        assert!(blame(&root.join("new.rs"), None).is_err());
+    }
+}
@@ -0,0 +1,468 @@
+//! Line matching core for ai-grep
+//!
+//! [`Matcher`] decides which lines are selected and where the match is;
+//! [`Matcher::matches`] turns a buffer into an iterator of [`Match`] events.
+//! Output modes (`-c`, `-l`, `-L`, `-q`, `-m`) are layered on top of that
+//! iterator by the caller, so each mode sees exactly the same selection and
+//! can stop as early as it likes.
+//!
+//! Matching works on raw bytes, so offsets are exact even in files with
+//! invalid UTF-8, and only selected lines need decoding for output. When a
+//! literal pattern can be searched for across the whole buffer
+//! (case-sensitive, or ASCII with `-i`), lines are only located around each
+//! hit; otherwise the buffer is split into lines and each is tested.
+//!
+//! Regular expressions use the `regex` crate syntax and are matched one line
+//! at a time, so `^` and `$` anchor at line boundaries. Their capture groups
+//! are available through [`Matcher::captures`].
+//!
+//! For sorted files, [`Matcher::sorted_matches`] finds the lines starting
+//! with a literal prefix by binary search, touching only O(log n) lines.
+
+use crate::error::{AiCoreutilsError, Result};
+use crate::simd_ops::{SimdByteCounter, SimdCaseFolder, SimdNewlineCounter, SimdPatternSearcher};
+use regex::bytes::{Regex, RegexBuilder};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// Capture group values of a match, keyed by group name (or number for
+/// unnamed groups); `None` for groups that did not participate
+pub type Captures = BTreeMap<String, Option<String>>;
+
+/// A literal or regex pattern with grep's `-i` and `-v` options
+pub struct Matcher {
+    pattern: Vec<u8>,
+    /// Compiled pattern, for patterns that are not plain literals
+    regex: Option<Regex>,
+    /// Lowercase pattern for `-i` with non-ASCII patterns
+    folded: Option<String>,
+    ignore_case: bool,
+    invert: bool,
+    searcher: SimdPatternSearcher,
+    case_folder: SimdCaseFolder,
+    newlines: SimdNewlineCounter,
+    byte_counter: SimdByteCounter,
+}
+
+/// A selected line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match<'a> {
+    /// Line number (1-indexed)
+    pub line_number: usize,
+    /// Byte offset of the line in the searched buffer
+    pub byte_offset: usize,
+    /// Line content without its terminator (`\n` or `\r\n`)
+    pub line: &'a [u8],
+    /// Byte range of the first match in `line`; `None` for inverted matches
+    pub span: Option<(usize, usize)>,
+}
+
+impl Matcher {
+    /// Create a matcher for a literal pattern
+    pub fn new(pattern: &str, ignore_case: bool, invert: bool) -> Self {
+        let folded = (ignore_case && !pattern.is_ascii()).then(|| pattern.to_lowercase());
+        Self {
+            pattern: pattern.as_bytes().to_vec(),
+            regex: None,
+            folded,
+            ignore_case,
+            invert,
+            searcher: SimdPatternSearcher::new(),
+            case_folder: SimdCaseFolder::new(),
+            newlines: SimdNewlineCounter::new(),
+            byte_counter: SimdByteCounter::new(),
+        }
+    }
+
+    /// Create a matcher for a regular expression
+    ///
+    /// Patterns without metacharacters are searched for as literals.
+    pub fn regex(pattern: &str, ignore_case: bool, invert: bool) -> Result<Self> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(ignore_case)
+            .build()
+            .map_err(|e| AiCoreutilsError::InvalidInput(format!("Invalid pattern: {}", e)))?;
+
+        let mut matcher = Self::new(pattern, ignore_case, invert);
+        if regex::escape(pattern) != pattern {
+            matcher.regex = Some(regex);
+        }
+        Ok(matcher)
+    }
+
+    /// Byte range of the first occurrence of the pattern in `line`
+    pub fn find(&self, line: &[u8]) -> Option<(usize, usize)> {
+        if let Some(regex) = &self.regex {
+            return regex.find(line).map(|m| (m.start(), m.end()));
+        }
+        if let Some(folded) = &self.folded {
+            return find_unicode_caseless(line, folded);
+        }
+        self.find_bytes(line)
+            .map(|start| (start, start + self.pattern.len()))
+    }
+
+    /// Capture groups of the first match in `line`
+    ///
+    /// `None` for literal patterns, patterns without groups, and lines that
+    /// do not match. Group 0 (the whole match) is not included.
+    pub fn captures(&self, line: &[u8]) -> Option<Captures> {
+        let regex = self.regex.as_ref().filter(|r| r.captures_len() > 1)?;
+        let caps = regex.captures(line)?;
+        Some(
+            regex
+                .capture_names()
+                .enumerate()
+                .skip(1)
+                .map(|(i, name)| {
+                    let key = name.map_or_else(|| i.to_string(), String::from);
+                    let value = caps.get(i).map(|m| String::from_utf8_lossy(m.as_bytes()).into_owned());
+                    (key, value)
+                })
+                .collect(),
+        )
+    }
+
+    /// Whether `line` is selected (matches, or does not with `-v`)
+    pub fn is_selected(&self, line: &[u8]) -> bool {
+        self.find(line).is_some() != self.invert
+    }
+
+    /// Selected lines of `data`, in order
+    pub fn matches<'a>(&'a self, data: &'a [u8]) -> Matches<'a> {
+        Matches {
+            matcher: self,
+            data,
+            pos: 0,
+            line_number: 0,
+        }
+    }
+
+    /// Lines of sorted `data` starting with the pattern, found by binary search
+    ///
+    /// `data` must be sorted by byte value (`LC_ALL=C sort`), or with `-i`
+    /// by ASCII uppercase as `sort -f` does. The pattern is used as a literal
+    /// prefix. Line numbers need a count of every line before the first
+    /// match, so they are only exact with `number_lines`; otherwise they
+    /// count from 1 at the first match.
+    pub fn sorted_matches<'a>(&'a self, data: &'a [u8], number_lines: bool) -> Matches<'a> {
+        let start = self.partition_point(data, |order| order == Ordering::Less);
+        let end = start + self.partition_point(&data[start..], |order| order != Ordering::Greater);
+        let line_number = if number_lines {
+            self.byte_counter.count(&data[..start], b'\n')
+        } else {
+            0
+        };
+        Matches {
+            matcher: self,
+            data: &data[..end],
+            pos: start,
+            line_number,
+        }
+    }
+
+    /// Start of the first line of sorted `data` for which `before` is false
+    ///
+    /// `before` gets the ordering of a line's prefix against the pattern.
+    fn partition_point(&self, data: &[u8], before: impl Fn(Ordering) -> bool) -> usize {
+        // `lo` and `hi` are always line starts (or the end of `data`)
+        let (mut lo, mut hi) = (0, data.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let start = self
+                .searcher
+                .rfind_byte(&data[lo..mid], b'\n')
+                .map_or(lo, |i| lo + i + 1);
+            let end = self.line_end(data, start);
+            if before(self.compare_prefix(strip_terminator(&data[start..end]))) {
+                lo = end;
+            } else {
+                hi = start;
+            }
+        }
+        lo
+    }
+
+    /// Order of the start of `line` against the pattern, in sort order
+    fn compare_prefix(&self, line: &[u8]) -> Ordering {
+        let key = &line[..line.len().min(self.pattern.len())];
+        if self.ignore_case {
+            let upper = |b: &u8| b.to_ascii_uppercase();
+            key.iter().map(upper).cmp(self.pattern.iter().map(upper))
+        } else {
+            key.cmp(&self.pattern)
+        }
+    }
+
+    /// Offset of the pattern in `haystack`, for patterns matched byte-wise
+    fn find_bytes(&self, haystack: &[u8]) -> Option<usize> {
+        if self.ignore_case {
+            self.case_folder.find_caseless(haystack, &self.pattern)
+        } else {
+            self.searcher.find_first(haystack, &self.pattern)
+        }
+    }
+
+    /// Whether hits can be searched for across line boundaries
+    fn scans_buffer(&self) -> bool {
+        !self.invert
+            && self.regex.is_none()
+            && self.folded.is_none()
+            && !self.pattern.contains(&b'\n')
+            && !self.pattern.ends_with(b"\r")
+    }
+
+    /// Offset just past the line containing `from` (after its `\n`, or the end)
+    fn line_end(&self, data: &[u8], from: usize) -> usize {
+        self.newlines
+            .find_nth_newline(&data[from..], 1)
+            .map_or(data.len(), |i| from + i + 1)
+    }
+}
+
+/// Iterator over the lines a [`Matcher`] selects
+pub struct Matches<'a> {
+    matcher: &'a Matcher,
+    data: &'a [u8],
+    /// Start of the first line not yet examined
+    pos: usize,
+    /// Lines before `pos`
+    line_number: usize,
+}
+
+impl<'a> Matches<'a> {
+    /// Find the next hit anywhere in the buffer, then the line around it
+    fn next_hit(&mut self) -> Option<Match<'a>> {
+        let data = self.data;
+        let hit = self.pos + self.matcher.find_bytes(&data[self.pos..])?;
+
+        let line_start = self
+            .matcher
+            .searcher
+            .rfind_byte(&data[self.pos..hit], b'\n')
+            .map_or(self.pos, |i| self.pos + i + 1);
+        let line_end = self.matcher.line_end(data, hit);
+
+        self.line_number += self.matcher.byte_counter.count(&data[self.pos..line_start], b'\n') + 1;
+        self.pos = line_end;
+
+        let start = hit - line_start;
+        Some(Match {
+            line_number: self.line_number,
+            byte_offset: line_start,
+            line: strip_terminator(&data[line_start..line_end]),
+            span: Some((start, start + self.matcher.pattern.len())),
+        })
+    }
+
+    /// Test lines one at a time
+    fn next_line(&mut self) -> Option<Match<'a>> {
+        while self.pos < self.data.len() {
+            let line_start = self.pos;
+            self.pos = self.matcher.line_end(self.data, line_start);
+            self.line_number += 1;
+
+            let line = strip_terminator(&self.data[line_start..self.pos]);
+            let span = self.matcher.find(line);
+            if span.is_some() != self.matcher.invert {
+                return Some(Match {
+                    line_number: self.line_number,
+                    byte_offset: line_start,
+                    line,
+                    span,
+                });
+            }
+        }
+        None
+    }
+}
+
+impl<'a> Iterator for Matches<'a> {
+    type Item = Match<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.data.len() {
+            return None;
+        }
+        if self.matcher.scans_buffer() {
+            self.next_hit()
+        } else {
+            self.next_line()
+        }
+    }
+}
+
+/// Line content without a trailing `\n` or `\r\n`
+fn strip_terminator(line: &[u8]) -> &[u8] {
+    match line.strip_suffix(b"\n") {
+        Some(line) => line.strip_suffix(b"\r").unwrap_or(line),
+        None => line,
+    }
+}
+
+/// Unicode case-insensitive search within the valid UTF-8 runs of `line`
+fn find_unicode_caseless(line: &[u8], folded: &str) -> Option<(usize, usize)> {
+    let mut base = 0;
+    for chunk in line.utf8_chunks() {
+        let text = chunk.valid();
+        let found = text
+            .char_indices()
+            .find_map(|(start, _)| caseless_prefix(&text[start..], folded).map(|len| (start, start + len)));
+        if let Some((start, end)) = found {
+            return Some((base + start, base + end));
+        }
+        base += text.len() + chunk.invalid().len();
+    }
+    None
+}
+
+/// Length of the prefix of `text` that lowercases to `folded`
+///
+/// The prefix always ends on a character boundary of `text`.
+fn caseless_prefix(text: &str, folded: &str) -> Option<usize> {
+    let mut pattern = folded.chars();
+    let mut remaining = folded.len();
+
+    for (offset, c) in text.char_indices() {
+        for lower in c.to_lowercase() {
+            if pattern.next() != Some(lower) {
+                return None;
+            }
+            remaining -= lower.len_utf8();
+        }
+        if remaining == 0 {
+            return Some(offset + c.len_utf8());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find() {
+        let matcher = Matcher::new("lo", false, false);
+        assert_eq!(matcher.find(b"hello"), Some((3, 5)));
+        assert_eq!(matcher.find(b"HELLO"), None);
+
+        let matcher = Matcher::new("LO w", true, false);
+        assert_eq!(matcher.find(b"Hello World"), Some((3, 7)));
+        // Offsets stay valid on the original line when case changes byte lengths
+        let matcher = Matcher::new("é", true, false);
+        assert_eq!(matcher.find("xÉy".as_bytes()), Some((1, 3)));
+        assert_eq!(matcher.find(b"\xff\xfex\xc3\x89"), Some((3, 5)));
+    }
+
+    #[test]
+    fn test_matches_and_invert() {
+        let text = b"apple\nbanana\ncherry\napple pie";
+        let matcher = Matcher::new("apple", false, false);
+        let found: Vec<_> = matcher.matches(text).map(|m| (m.line_number, m.byte_offset, m.span)).collect();
+        assert_eq!(found, [(1, 0, Some((0, 5))), (4, 20, Some((0, 5)))]);
+
+        let matcher = Matcher::new("apple", false, true);
+        let found: Vec<_> = matcher.matches(text).map(|m| (m.line_number, m.line, m.span)).collect();
+        assert_eq!(found, [(2, &b"banana"[..], None), (3, &b"cherry"[..], None)]);
+        assert!(matcher.is_selected(b"kiwi"));
+    }
+
+    #[test]
+    fn test_buffer_scan_matches_line_scan() {
+        // Long enough for the SIMD paths, with CRLF, invalid UTF-8 and blank lines
+        let mut data = Vec::new();
+        for i in 0..500 {
+            match i % 7 {
+                0 => data.extend_from_slice(b"an Error here\r\n"),
+                1 => data.extend_from_slice(b"\xff\xfe error \xc3\n"),
+                2 => data.extend_from_slice(b"\n"),
+                3 => data.extend_from_slice(b"errerror error\n"),
+                _ => data.extend_from_slice(format!("line {} without it\n", i).as_bytes()),
+            }
+        }
+        data.extend_from_slice(b"last ERROR");
+
+        for (pattern, ignore_case) in [("error", false), ("error", true), ("", false), ("e", true)] {
+            let matcher = Matcher::new(pattern, ignore_case, false);
+            let scanned: Vec<_> = matcher.matches(&data).collect();
+            let by_line: Vec<_> = crate::async_ops::raw_lines(&data)
+                .filter_map(|line| {
+                    matcher.find(line.content).map(|span| Match {
+                        line_number: line.number,
+                        byte_offset: line.offset as usize,
+                        line: line.content,
+                        span: Some(span),
+                    })
+                })
+                .collect();
+            assert_eq!(scanned, by_line, "{:?} -i={}", pattern, ignore_case);
+        }
+    }
+
+    #[test]
+    fn test_regex_and_captures() {
+        let matcher = Matcher::regex(r"(?P<level>ERROR|WARN) (\d+)(x)?", false, false).unwrap();
+        let line = b"12:00 WARN 42 disk";
+        assert_eq!(matcher.find(line), Some((6, 13)));
+        let captures = matcher.captures(line).unwrap();
+        assert_eq!(captures["level"].as_deref(), Some("WARN"));
+        assert_eq!(captures["2"].as_deref(), Some("42"));
+        assert_eq!(captures["3"], None);
+        assert!(matcher.captures(b"INFO").is_none());
+
+        // Anchors apply per line, including CRLF lines
+        let matcher = Matcher::regex("^b.*d$", false, false).unwrap();
+        let lines: Vec<_> = matcher.matches(b"bad
+abd
+bd").map(|m| m.line_number).collect();
+        assert_eq!(lines, [1, 3]);
+
+        // Plain words stay literal searches and have no captures
+        let matcher = Matcher::regex("error", true, false).unwrap();
+        assert_eq!(matcher.find(b"An ERROR"), Some((3, 8)));
+        assert!(matcher.captures(b"error").is_none());
+        assert!(Matcher::regex("(", false, false).is_err());
+    }
+
+    #[test]
+    fn test_sorted_matches() {
+        let mut words: Vec<String> = (0..2000).map(|i| format!("w{:x}", i * 7919 % 100_003)).collect();
+        words.extend(["app", "apple", "applet", "apply", "b"].map(String::from));
+        words.sort();
+        let data = words.join("\n");
+
+        for prefix in ["app", "apple", "w1", "w", "zzz", "", "0"] {
+            let matcher = Matcher::new(prefix, false, false);
+            let found: Vec<_> = matcher.sorted_matches(data.as_bytes(), true).collect();
+            let expected: Vec<_> = matcher
+                .matches(data.as_bytes())
+                .filter(|m| m.line.starts_with(prefix.as_bytes()))
+                .collect();
+            assert_eq!(found, expected, "{:?}", prefix);
+        }
+
+        // Without line numbers, numbering restarts at the first match
+        let matcher = Matcher::new("apple", false, false);
+        let lines: Vec<_> = matcher
+            .sorted_matches(b"a\napple\napplet\nb\n", false)
+            .map(|m| (m.line_number, m.byte_offset))
+            .collect();
+        assert_eq!(lines, [(1, 2), (2, 8)]);
+
+        // `sort -f` order: case folded to uppercase, so '_' sorts after letters
+        let data = b"Apple\nAPPLET\napple_pie\nbanana\n_x";
+        let matcher = Matcher::new("APPLE", true, false);
+        let lines: Vec<_> = matcher.sorted_matches(data, true).map(|m| m.line_number).collect();
+        assert_eq!(lines, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_modes_share_one_selection() {
+        let text = b"a1\nb\na2\na3\n";
+        let matcher = Matcher::new("a", false, false);
+        assert_eq!(matcher.matches(text).count(), 3);
+        assert_eq!(matcher.matches(text).take(2).count(), 2);
+        assert_eq!(matcher.matches(text).next().unwrap().line_number, 1);
+    }
+}
@@ -0,0 +1,552 @@
+//! gRPC service
+//!
+//! With the `grpc` feature, [`serve`] runs a tonic server with the
+//! `AiCoreutils` service from `proto/ai_coreutils.proto`: Analyze, Grep,
+//! Find, Checksum and Classify, each streaming one response per result. It
+//! is for running the utilities as a sidecar service; `ai-grpc` is the
+//! binary.
+//!
+//! Responses carry either a result or a [`pb::FileError`] for a path that
+//! could not be processed, with the JSONL error code the utility would use
+//! (`POLICY_DENIED` for paths the sandbox policy refuses), so one unreadable
+//! file does not end the stream. Requests that cannot run at all, such as an
+//! invalid pattern or algorithm, fail with `INVALID_ARGUMENT`.
+//!
+//! The work for each call runs on a blocking thread and stops once the
+//! client goes away.
+
+use crate::checksum;
+use crate::config::glob_match;
+use crate::error::{AiCoreutilsError, Result};
+use crate::fs_utils::{DirWalk, SymlinkPolicy, WalkEvent};
+use crate::grep::Matcher;
+use crate::hash::HashAlgorithm;
+use crate::limits;
+use crate::memory::SafeMemoryAccess;
+use crate::ml_ops::{ContentAnalysis, FileClassification, FileClassifier, PatternDetector};
+use crate::policy::{self, Access};
+use pb::ai_coreutils_server::{AiCoreutils, AiCoreutilsServer};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{Request, Response, Status};
+
+/// Messages and service traits generated from `proto/ai_coreutils.proto`
+#[allow(missing_docs, clippy::all)]
+pub mod pb {
+    tonic::include_proto!("ai_coreutils.v1");
+}
+
+/// Responses buffered per call before the work waits for the client
+const CHANNEL_CAPACITY: usize = 64;
+
+/// Stream of responses for one call
+pub type ResponseStream<T> = ReceiverStream<std::result::Result<T, Status>>;
+
+/// A streamed response: one result or one file error
+trait Item: Send + 'static {
+    fn error(error: pb::FileError) -> Self;
+}
+
+macro_rules! item {
+    ($response:ident, $module:ident, $variant:ident, $message:ident) => {
+        impl Item for pb::$response {
+            fn error(error: pb::FileError) -> Self {
+                Self { result: Some(pb::$module::Result::Error(error)) }
+            }
+        }
+
+        impl From<pb::$message> for pb::$response {
+            fn from(message: pb::$message) -> Self {
+                Self { result: Some(pb::$module::Result::$variant(message)) }
+            }
+        }
+    };
+}
+
+item!(AnalyzeResponse, analyze_response, Analysis, Analysis);
+item!(GrepResponse, grep_response, Match, GrepMatch);
+item!(FindResponse, find_response, Entry, Entry);
+item!(ChecksumResponse, checksum_response, Digest, Digest);
+item!(ClassifyResponse, classify_response, Classification, Classification);
+
+/// Sending half of a response stream
+struct Sink<T> {
+    tx: mpsc::Sender<std::result::Result<T, Status>>,
+    /// Error code for files that fail
+    code: &'static str,
+}
+
+impl<T: Item> Sink<T> {
+    /// Send a response; false once the client has gone away
+    fn send(&self, item: impl Into<T>) -> bool {
+        self.tx.blocking_send(Ok(item.into())).is_ok()
+    }
+
+    /// Send an error for `path`; false once the client has gone away
+    fn error(&self, path: &Path, error: &AiCoreutilsError) -> bool {
+        let (path, code) = match error {
+            AiCoreutilsError::PolicyDenied { path, .. } => (path.as_path(), "POLICY_DENIED"),
+            _ => (path, self.code),
+        };
+        self.send(T::error(pb::FileError {
+            path: path.to_string_lossy().into_owned(),
+            message: error.to_string(),
+            code: code.to_string(),
+        }))
+    }
+
+    /// Send the result of processing `path`, or its error
+    fn send_result(&self, path: &Path, result: Result<impl Into<T>>) -> bool {
+        match result {
+            Ok(item) => self.send(item),
+            Err(e) => self.error(path, &e),
+        }
+    }
+}
+
+/// Run `work` on a blocking thread, streaming the responses it sends
+fn stream<T: Item>(
+    code: &'static str,
+    work: impl FnOnce(&Sink<T>) + Send + 'static,
+) -> Response<ResponseStream<T>> {
+    let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+    tokio::task::spawn_blocking(move || work(&Sink { tx, code }));
+    Response::new(ReceiverStream::new(rx))
+}
+
+/// Error for requests without paths
+fn no_paths() -> Status {
+    Status::invalid_argument("no paths given")
+}
+
+/// Call `visit` for each file in `paths`, sending errors for the rest
+///
+/// Directories are walked when `recursive` is set and are errors otherwise.
+/// Returns false, stopping early, once a send or `visit` returns false.
+fn for_each_file<T: Item>(
+    sink: &Sink<T>,
+    paths: &[String],
+    recursive: bool,
+    mut visit: impl FnMut(&Path) -> bool,
+) -> bool {
+    for path in paths.iter().map(Path::new) {
+        let sent = if let Err(e) = policy::check(path, Access::Read) {
+            sink.error(path, &e)
+        } else if !path.is_dir() {
+            visit(path)
+        } else if !recursive {
+            let e = AiCoreutilsError::InvalidInput("Is a directory (set recursive to walk it)".to_string());
+            sink.error(path, &e)
+        } else {
+            DirWalk::new(path, SymlinkPolicy::CommandLine).all(|event| match event {
+                Ok(WalkEvent::Entry(entry)) if entry.is_file() => visit(entry.path()),
+                Ok(_) => true,
+                Err(e) => sink.error(path, &e),
+            })
+        };
+        if !sent {
+            return false;
+        }
+    }
+    true
+}
+
+/// Whole contents of the file at `path`, counted towards resource limits
+fn read(path: &Path) -> Result<Vec<u8>> {
+    let content = fs::read(path)?;
+    limits::read_file(path, content.len() as u64)?;
+    Ok(content)
+}
+
+impl From<ContentAnalysis> for pb::Analysis {
+    fn from(analysis: ContentAnalysis) -> Self {
+        let statistics = analysis.statistics;
+        Self {
+            path: analysis.path,
+            total_patterns: analysis.total_patterns as u64,
+            patterns_by_type: analysis
+                .patterns_by_type
+                .into_iter()
+                .map(|(name, count)| (name, count as u64))
+                .collect(),
+            matches: analysis
+                .matches
+                .into_iter()
+                .map(|m| pb::PatternMatch {
+                    pattern_type: format!("{:?}", m.pattern_type),
+                    matched_text: m.matched_text,
+                    start: m.start as u64,
+                    end: m.end as u64,
+                    confidence: m.confidence,
+                })
+                .collect(),
+            statistics: Some(pb::TextStatistics {
+                characters: statistics.characters as u64,
+                bytes: statistics.bytes as u64,
+                lines: statistics.lines as u64,
+                words: statistics.words as u64,
+                avg_line_length: statistics.avg_line_length,
+                max_line_length: statistics.max_line_length as u64,
+                whitespace_ratio: statistics.whitespace_ratio,
+                entropy: statistics.entropy,
+            }),
+            issues: analysis.issues,
+        }
+    }
+}
+
+impl From<FileClassification> for pb::Classification {
+    fn from(classification: FileClassification) -> Self {
+        Self {
+            path: classification.path,
+            file_type: classification.file_type,
+            confidence: classification.confidence,
+            encoding: classification.encoding,
+            mime_type: classification.mime_type,
+            is_binary: classification.is_binary,
+            language: classification.language,
+        }
+    }
+}
+
+/// Send every selected line of the file at `path`
+fn grep_file(sink: &Sink<pb::GrepResponse>, matcher: &Matcher, path: &Path) -> bool {
+    let mem = match SafeMemoryAccess::new(path) {
+        Ok(mem) => mem,
+        Err(e) => return sink.error(path, &e),
+    };
+    let data = mem.as_slice();
+    matcher.matches(data).all(|m| {
+        sink.send(pb::GrepMatch {
+            path: path.to_string_lossy().into_owned(),
+            line_number: m.line_number as u64,
+            byte_offset: m.byte_offset as u64,
+            line: String::from_utf8_lossy(m.line).into_owned(),
+            match_start: m.span.map(|(start, _)| start as u64),
+            match_end: m.span.map(|(_, end)| end as u64),
+        })
+    })
+}
+
+/// The `AiCoreutils` gRPC service
+pub struct Service {
+    detector: Arc<PatternDetector>,
+}
+
+impl Service {
+    /// Create the service, compiling the pattern detector once for all calls
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            detector: Arc::new(PatternDetector::new()?),
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl AiCoreutils for Service {
+    type AnalyzeStream = ResponseStream<pb::AnalyzeResponse>;
+    type GrepStream = ResponseStream<pb::GrepResponse>;
+    type FindStream = ResponseStream<pb::FindResponse>;
+    type ChecksumStream = ResponseStream<pb::ChecksumResponse>;
+    type ClassifyStream = ResponseStream<pb::ClassifyResponse>;
+
+    async fn analyze(
+        &self,
+        request: Request<pb::AnalyzeRequest>,
+    ) -> std::result::Result<Response<Self::AnalyzeStream>, Status> {
+        let request = request.into_inner();
+        if request.paths.is_empty() {
+            return Err(no_paths());
+        }
+        let detector = Arc::clone(&self.detector);
+        Ok(stream("ANALYZE_ERROR", move |sink| {
+            for_each_file(sink, &request.paths, request.recursive, |path| {
+                let analysis = read(path).and_then(|content| {
+                    detector.analyze_content(&String::from_utf8_lossy(&content), path)
+                });
+                sink.send_result(path, analysis.map(pb::Analysis::from))
+            });
+        }))
+    }
+
+    async fn grep(
+        &self,
+        request: Request<pb::GrepRequest>,
+    ) -> std::result::Result<Response<Self::GrepStream>, Status> {
+        let request = request.into_inner();
+        if request.paths.is_empty() {
+            return Err(no_paths());
+        }
+        let matcher = if request.fixed_strings {
+            Matcher::new(&request.pattern, request.ignore_case, request.invert)
+        } else {
+            Matcher::regex(&request.pattern, request.ignore_case, request.invert)
+                .map_err(|e| Status::invalid_argument(e.to_string()))?
+        };
+        Ok(stream("GREP_ERROR", move |sink| {
+            for_each_file(sink, &request.paths, request.recursive, |path| grep_file(sink, &matcher, path));
+        }))
+    }
+
+    async fn find(
+        &self,
+        request: Request<pb::FindRequest>,
+    ) -> std::result::Result<Response<Self::FindStream>, Status> {
+        let request = request.into_inner();
+        if request.paths.is_empty() {
+            return Err(no_paths());
+        }
+        if !matches!(request.r#type.as_str(), "" | "file" | "directory" | "symlink") {
+            return Err(Status::invalid_argument(format!(
+                "Unknown type: {} (expected file, directory or symlink)",
+                request.r#type
+            )));
+        }
+        let symlinks = if request.follow_symlinks {
+            SymlinkPolicy::Always
+        } else {
+            SymlinkPolicy::CommandLine
+        };
+        Ok(stream("FIND_ERROR", move |sink| {
+            for root in request.paths.iter().map(Path::new) {
+                if let Err(e) = policy::check(root, Access::Read) {
+                    if !sink.error(root, &e) {
+                        return;
+                    }
+                    continue;
+                }
+                let max_depth = request.max_depth.map(|depth| depth as usize);
+                let sent = DirWalk::with_max_depth(root, symlinks, max_depth).all(|event| match event {
+                    Ok(WalkEvent::Entry(entry)) => {
+                        let name = entry.path().file_name().unwrap_or_default().to_string_lossy();
+                        let selected = (request.name.is_empty() || glob_match(&request.name, &name))
+                            && (request.r#type.is_empty() || request.r#type == entry.kind());
+                        !selected
+                            || sink.send(pb::Entry {
+                                path: entry.path().to_string_lossy().into_owned(),
+                                kind: entry.kind().to_string(),
+                                depth: entry.depth() as u64,
+                                size: entry.size(),
+                            })
+                    }
+                    Ok(WalkEvent::Loop(_)) => true,
+                    Err(e) => sink.error(root, &e),
+                });
+                if !sent {
+                    return;
+                }
+            }
+        }))
+    }
+
+    async fn checksum(
+        &self,
+        request: Request<pb::ChecksumRequest>,
+    ) -> std::result::Result<Response<Self::ChecksumStream>, Status> {
+        let request = request.into_inner();
+        if request.paths.is_empty() {
+            return Err(no_paths());
+        }
+        let algorithm: HashAlgorithm = match request.algorithm.as_str() {
+            "" => HashAlgorithm::default(),
+            name => name.parse().map_err(Status::invalid_argument)?,
+        };
+        Ok(stream("CHECKSUM_ERROR", move |sink| {
+            for_each_file(sink, &request.paths, request.recursive, |path| {
+                let digest = checksum::hash_file(path, algorithm).map(|entry| pb::Digest {
+                    path: path.to_string_lossy().into_owned(),
+                    algorithm: algorithm.as_str().to_string(),
+                    hex: entry.digest,
+                    size: entry.size,
+                });
+                sink.send_result(path, digest)
+            });
+        }))
+    }
+
+    async fn classify(
+        &self,
+        request: Request<pb::ClassifyRequest>,
+    ) -> std::result::Result<Response<Self::ClassifyStream>, Status> {
+        let request = request.into_inner();
+        if request.paths.is_empty() {
+            return Err(no_paths());
+        }
+        Ok(stream("CLASSIFY_ERROR", move |sink| {
+            for_each_file(sink, &request.paths, request.recursive, |path| {
+                let classification =
+                    read(path).and_then(|content| FileClassifier::classify(path, &content));
+                sink.send_result(path, classification.map(pb::Classification::from))
+            });
+        }))
+    }
+}
+
+/// Serve the `AiCoreutils` service on `listener` until the process ends
+pub async fn serve(listener: TcpListener) -> Result<()> {
+    tonic::transport::Server::builder()
+        .add_service(AiCoreutilsServer::new(Service::new()?))
+        .serve_with_incoming(TcpListenerStream::new(listener))
+        .await
+        .map_err(|e| AiCoreutilsError::Io(std::io::Error::other(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::Digest;
+    use pb::ai_coreutils_client::AiCoreutilsClient;
+    use tempfile::TempDir;
+    use tokio_stream::StreamExt;
+
+    /// Every response of a call, in order
+    async fn collect<T>(response: std::result::Result<Response<ResponseStream<T>>, Status>) -> Vec<T> {
+        let stream = response.unwrap().into_inner();
+        stream.map(|item| item.unwrap()).collect().await
+    }
+
+    fn path(dir: &TempDir, name: &str) -> String {
+        dir.path().join(name).to_string_lossy().into_owned()
+    }
+
+    fn tree() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "alpha\nbeta\nAlphabet\n").unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub/b.rs"), "fn main() {}\n").unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_grep() {
+        let dir = tree();
+        let service = Service::new().unwrap();
+        let request = pb::GrepRequest {
+            pattern: "^alpha".to_string(),
+            paths: vec![path(&dir, "a.txt")],
+            ignore_case: true,
+            ..Default::default()
+        };
+        let responses = collect(service.grep(Request::new(request)).await).await;
+        let lines: Vec<(u64, String, Option<u64>)> = responses
+            .into_iter()
+            .map(|r| match r.result {
+                Some(pb::grep_response::Result::Match(m)) => (m.line_number, m.line, m.match_end),
+                other => panic!("unexpected response: {:?}", other),
+            })
+            .collect();
+        assert_eq!(lines, vec![(1, "alpha".to_string(), Some(5)), (3, "Alphabet".to_string(), Some(5))]);
+
+        let request = pb::GrepRequest {
+            pattern: "(".to_string(),
+            paths: vec![path(&dir, "a.txt")],
+            ..Default::default()
+        };
+        let status = service.grep(Request::new(request)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_find() {
+        let dir = tree();
+        let service = Service::new().unwrap();
+        let request = pb::FindRequest {
+            paths: vec![dir.path().to_string_lossy().into_owned()],
+            name: "*.rs".to_string(),
+            r#type: "file".to_string(),
+            ..Default::default()
+        };
+        let responses = collect(service.find(Request::new(request.clone())).await).await;
+        let entries: Vec<pb::Entry> = responses
+            .into_iter()
+            .filter_map(|r| match r.result {
+                Some(pb::find_response::Result::Entry(entry)) => Some(entry),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, path(&dir, "sub/b.rs"));
+        assert_eq!((entries[0].depth, entries[0].size), (2, Some(13)));
+
+        let shallow = pb::FindRequest { max_depth: Some(1), ..request };
+        assert!(collect(service.find(Request::new(shallow)).await).await.is_empty());
+
+        let bad_type = pb::FindRequest { paths: vec![path(&dir, "a.txt")], r#type: "fifo".to_string(), ..Default::default() };
+        assert_eq!(service.find(Request::new(bad_type)).await.unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_checksum_errors_in_stream() {
+        let dir = tree();
+        let service = Service::new().unwrap();
+        let request = pb::ChecksumRequest {
+            paths: vec![path(&dir, "a.txt"), path(&dir, "sub"), path(&dir, "missing")],
+            algorithm: "crc32".to_string(),
+            recursive: false,
+        };
+        let responses = collect(service.checksum(Request::new(request)).await).await;
+        assert_eq!(responses.len(), 3);
+        match &responses[0].result {
+            Some(pb::checksum_response::Result::Digest(digest)) => {
+                assert_eq!(digest.algorithm, "crc32");
+                assert_eq!(digest.hex, HashAlgorithm::Crc32.hex_digest(b"alpha\nbeta\nAlphabet\n"));
+                assert_eq!(digest.size, 20);
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+        for (response, name) in responses[1..].iter().zip(["sub", "missing"]) {
+            match &response.result {
+                Some(pb::checksum_response::Result::Error(error)) => {
+                    assert_eq!(error.path, path(&dir, name));
+                    assert_eq!(error.code, "CHECKSUM_ERROR");
+                }
+                other => panic!("unexpected response: {:?}", other),
+            }
+        }
+
+        let request = pb::ChecksumRequest { paths: vec![path(&dir, "a.txt")], algorithm: "md5".to_string(), recursive: false };
+        assert_eq!(service.checksum(Request::new(request)).await.unwrap_err().code(), tonic::Code::InvalidArgument);
+        let request = pb::ChecksumRequest::default();
+        assert_eq!(service.checksum(Request::new(request)).await.unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_and_classify_over_tcp() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("notes.txt"), "see https://example.com\n").unwrap();
+        fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener));
+        let mut client = AiCoreutilsClient::connect(format!("http://{}", addr)).await.unwrap();
+
+        let request = pb::AnalyzeRequest { paths: vec![path(&dir, "notes.txt")], recursive: false };
+        let mut stream = client.analyze(request).await.unwrap().into_inner();
+        match stream.message().await.unwrap().and_then(|r| r.result) {
+            Some(pb::analyze_response::Result::Analysis(analysis)) => {
+                assert_eq!(analysis.patterns_by_type.get("Url"), Some(&1));
+                assert_eq!(analysis.statistics.unwrap().lines, 1);
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+        assert!(stream.message().await.unwrap().is_none());
+
+        let request = pb::ClassifyRequest { paths: vec![dir.path().to_string_lossy().into_owned()], recursive: true };
+        let mut stream = client.classify(request).await.unwrap().into_inner();
+        let mut classifications = Vec::new();
+        while let Some(response) = stream.message().await.unwrap() {
+            match response.result {
+                Some(pb::classify_response::Result::Classification(c)) => classifications.push(c),
+                other => panic!("unexpected response: {:?}", other),
+            }
+        }
+        classifications.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(classifications.len(), 2);
+        assert!(classifications.iter().all(|c| !c.is_binary));
+    }
+}
@@ -0,0 +1,366 @@
+//! Content digests behind one interface
+//!
+//! [`Digest`] is implemented by each supported algorithm and by
+//! [`HashAlgorithm`], which selects one at runtime (from a command-line
+//! flag or a saved manifest). Digests are rendered as lowercase hex of the
+//! big-endian value, matching the usual `xxhsum`/`b3sum` output.
+//!
+//! | Algorithm | Bits | Use |
+//! |-----------|------|-----|
+//! | `crc32` | 32 | Detecting accidental corruption |
+//! | `xxh64` | 64 | Fast fingerprinting |
+//! | `xxh3` | 64 | Fastest fingerprinting and deduplication (default) |
+//! | `blake3` | 256 | Cryptographic integrity (feature `blake3`) |
+//!
+//! None of the non-cryptographic hashes resist deliberate collisions.
+//!
+//! [`StreamingHasher`] computes the same digests from data fed in chunks,
+//! for hashing huge files with bounded memory ([`hash_reader`]) or hashing
+//! data as it is copied.
+
+use crate::error::{AiCoreutilsError, Result};
+use crate::simd_ops::SimdHasher;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+/// A hash function over byte buffers
+pub trait Digest {
+    /// Name used on the command line and in JSONL output
+    fn name(&self) -> &'static str;
+
+    /// Digest length in bytes
+    fn output_len(&self) -> usize;
+
+    /// Digest of `data`, big-endian
+    fn digest(&self, data: &[u8]) -> Vec<u8>;
+
+    /// Lowercase hex digest of `data`
+    fn hex_digest(&self, data: &[u8]) -> String {
+        to_hex(&self.digest(data))
+    }
+}
+
+/// CRC-32/IEEE
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Crc32;
+
+impl Digest for Crc32 {
+    fn name(&self) -> &'static str {
+        "crc32"
+    }
+
+    fn output_len(&self) -> usize {
+        4
+    }
+
+    fn digest(&self, data: &[u8]) -> Vec<u8> {
+        SimdHasher::new().crc32(data).to_be_bytes().to_vec()
+    }
+}
+
+/// XXH64 with a seed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Xxh64 {
+    /// Seed mixed into the hash
+    pub seed: u64,
+}
+
+impl Digest for Xxh64 {
+    fn name(&self) -> &'static str {
+        "xxh64"
+    }
+
+    fn output_len(&self) -> usize {
+        8
+    }
+
+    fn digest(&self, data: &[u8]) -> Vec<u8> {
+        SimdHasher::new().xxh64(data, self.seed).to_be_bytes().to_vec()
+    }
+}
+
+/// XXH3, 64-bit output
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Xxh3;
+
+impl Digest for Xxh3 {
+    fn name(&self) -> &'static str {
+        "xxh3"
+    }
+
+    fn output_len(&self) -> usize {
+        8
+    }
+
+    fn digest(&self, data: &[u8]) -> Vec<u8> {
+        SimdHasher::new().xxh3(data).to_be_bytes().to_vec()
+    }
+}
+
+/// BLAKE3, 256-bit output
+#[cfg(feature = "blake3")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Blake3;
+
+#[cfg(feature = "blake3")]
+impl Digest for Blake3 {
+    fn name(&self) -> &'static str {
+        "blake3"
+    }
+
+    fn output_len(&self) -> usize {
+        blake3::OUT_LEN
+    }
+
+    fn digest(&self, data: &[u8]) -> Vec<u8> {
+        blake3::hash(data).as_bytes().to_vec()
+    }
+}
+
+/// A digest algorithm chosen at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    /// CRC-32/IEEE
+    Crc32,
+    /// XXH64, seed 0
+    Xxh64,
+    /// XXH3 64-bit
+    #[default]
+    Xxh3,
+    /// BLAKE3
+    #[cfg(feature = "blake3")]
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// Every algorithm available in this build
+    pub fn all() -> &'static [HashAlgorithm] {
+        &[
+            HashAlgorithm::Crc32,
+            HashAlgorithm::Xxh64,
+            HashAlgorithm::Xxh3,
+            #[cfg(feature = "blake3")]
+            HashAlgorithm::Blake3,
+        ]
+    }
+
+    /// Name used on the command line and in JSONL output
+    pub fn as_str(&self) -> &'static str {
+        self.name()
+    }
+}
+
+impl Digest for HashAlgorithm {
+    fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Crc32 => Crc32.name(),
+            HashAlgorithm::Xxh64 => Xxh64::default().name(),
+            HashAlgorithm::Xxh3 => Xxh3.name(),
+            #[cfg(feature = "blake3")]
+            HashAlgorithm::Blake3 => Blake3.name(),
+        }
+    }
+
+    fn output_len(&self) -> usize {
+        match self {
+            HashAlgorithm::Crc32 => Crc32.output_len(),
+            HashAlgorithm::Xxh64 => Xxh64::default().output_len(),
+            HashAlgorithm::Xxh3 => Xxh3.output_len(),
+            #[cfg(feature = "blake3")]
+            HashAlgorithm::Blake3 => Blake3.output_len(),
+        }
+    }
+
+    fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Crc32 => Crc32.digest(data),
+            HashAlgorithm::Xxh64 => Xxh64::default().digest(data),
+            HashAlgorithm::Xxh3 => Xxh3.digest(data),
+            #[cfg(feature = "blake3")]
+            HashAlgorithm::Blake3 => Blake3.digest(data),
+        }
+    }
+}
+
+impl std::str::FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if let Some(algorithm) = Self::all().iter().find(|a| a.name() == s) {
+            return Ok(*algorithm);
+        }
+        let names: Vec<&str> = Self::all().iter().map(|a| a.name()).collect();
+        let hint = if s == "blake3" {
+            " (this build lacks the blake3 feature)"
+        } else {
+            ""
+        };
+        Err(format!("Invalid algorithm: {} (expected {}){}", s, names.join(", "), hint))
+    }
+}
+
+/// Digest state that is fed data in chunks
+///
+/// Feeding a buffer in any number of [`update`](Self::update) calls gives
+/// the same digest as [`Digest::digest`] over the whole buffer.
+#[derive(Clone)]
+pub struct StreamingHasher {
+    algorithm: HashAlgorithm,
+    state: State,
+    bytes: u64,
+}
+
+#[derive(Clone)]
+enum State {
+    Crc32(u32),
+    Xxh64(Box<xxhash_rust::xxh64::Xxh64>),
+    Xxh3(Box<xxhash_rust::xxh3::Xxh3>),
+    #[cfg(feature = "blake3")]
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl StreamingHasher {
+    /// Start a digest
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        let state = match algorithm {
+            HashAlgorithm::Crc32 => State::Crc32(0),
+            HashAlgorithm::Xxh64 => State::Xxh64(Box::new(xxhash_rust::xxh64::Xxh64::new(0))),
+            HashAlgorithm::Xxh3 => State::Xxh3(Box::default()),
+            #[cfg(feature = "blake3")]
+            HashAlgorithm::Blake3 => State::Blake3(Box::default()),
+        };
+        Self {
+            algorithm,
+            state,
+            bytes: 0,
+        }
+    }
+
+    /// Algorithm being computed
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// Number of bytes fed so far
+    pub fn bytes_hashed(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Feed the next chunk of data
+    pub fn update(&mut self, data: &[u8]) {
+        match &mut self.state {
+            State::Crc32(crc) => *crc = SimdHasher::new().crc32_update(*crc, data),
+            State::Xxh64(state) => state.update(data),
+            State::Xxh3(state) => state.update(data),
+            #[cfg(feature = "blake3")]
+            State::Blake3(state) => {
+                state.update(data);
+            }
+        }
+        self.bytes += data.len() as u64;
+    }
+
+    /// Digest of everything fed so far
+    ///
+    /// Does not consume the state, so more data can still be fed.
+    pub fn finalize(&self) -> Vec<u8> {
+        match &self.state {
+            State::Crc32(crc) => crc.to_be_bytes().to_vec(),
+            State::Xxh64(state) => state.digest().to_be_bytes().to_vec(),
+            State::Xxh3(state) => state.digest().to_be_bytes().to_vec(),
+            #[cfg(feature = "blake3")]
+            State::Blake3(state) => state.finalize().as_bytes().to_vec(),
+        }
+    }
+
+    /// Lowercase hex digest of everything fed so far
+    pub fn finalize_hex(&self) -> String {
+        to_hex(&self.finalize())
+    }
+}
+
+impl std::fmt::Debug for StreamingHasher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamingHasher")
+            .field("algorithm", &self.algorithm)
+            .field("bytes", &self.bytes)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Buffer size used by [`hash_reader`]
+pub const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Digest everything `reader` yields, in chunks of [`READ_CHUNK_SIZE`]
+///
+/// Memory use does not depend on the length of the input. Returns the
+/// finished state, which also tells how many bytes were read.
+pub fn hash_reader<R: Read>(algorithm: HashAlgorithm, mut reader: R) -> Result<StreamingHasher> {
+    let mut hasher = StreamingHasher::new(algorithm);
+    let mut buffer = vec![0u8; READ_CHUNK_SIZE];
+    loop {
+        let n = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(AiCoreutilsError::Io(e)),
+        };
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hasher)
+}
+
+/// Lowercase hex of `bytes`
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_digests() {
+        assert_eq!(Crc32.hex_digest(b"123456789"), "cbf43926");
+        assert_eq!(Xxh64::default().hex_digest(b""), "ef46db3751d8e999");
+        assert_eq!(Xxh3.hex_digest(b""), "2d06800538d394c2");
+        #[cfg(feature = "blake3")]
+        assert_eq!(
+            Blake3.hex_digest(b""),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+    }
+
+    #[test]
+    fn test_algorithm_dispatch() {
+        for algorithm in HashAlgorithm::all() {
+            let digest = algorithm.digest(b"data");
+            assert_eq!(digest.len(), algorithm.output_len());
+            assert_eq!(algorithm.hex_digest(b"data").len(), 2 * algorithm.output_len());
+            assert_ne!(digest, algorithm.digest(b"Data"));
+            assert_eq!(algorithm.name().parse::<HashAlgorithm>(), Ok(*algorithm));
+        }
+        assert_eq!(HashAlgorithm::default(), HashAlgorithm::Xxh3);
+        assert!("md5".parse::<HashAlgorithm>().is_err());
+        assert_eq!(serde_json::to_string(&HashAlgorithm::Xxh64).unwrap(), "\"xxh64\"");
+    }
+
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        // Long enough to cross XXH3's internal block and stripe boundaries
+        let data: Vec<u8> = (0..5000u32).map(|i| (i * 31 % 251) as u8).collect();
+        for algorithm in HashAlgorithm::all() {
+            let mut hasher = StreamingHasher::new(*algorithm);
+            assert_eq!(hasher.finalize(), algorithm.digest(b""));
+            for chunk in data.chunks(333) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize(), algorithm.digest(&data), "{}", algorithm.name());
+            assert_eq!(hasher.bytes_hashed(), data.len() as u64);
+
+            let read = hash_reader(*algorithm, &data[..]).unwrap();
+            assert_eq!(read.finalize_hex(), algorithm.hex_digest(&data));
+        }
+    }
+}
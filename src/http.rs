@@ -0,0 +1,746 @@
+//! HTTP server
+//!
+//! With the `http` feature, [`router`] serves the read-only utilities over
+//! HTTP, so an agent on one host can inspect files on another;
+//! `ai-coreutils-server --http` is the binary. Each endpoint takes a JSON
+//! body with the utility's options and streams back JSONL records, as
+//! `application/x-ndjson`, in the shapes the utility writes them:
+//!
+//! | Endpoint | Utility | Records |
+//! |----------|---------|---------|
+//! | `POST /v1/ls` | `ai-ls` | `file` |
+//! | `POST /v1/cat` | `ai-cat` | `file_content`, or `file_content_base64` for binary files |
+//! | `POST /v1/grep` | `ai-grep -n` | `match` |
+//! | `POST /v1/find` | `ai-find` | `match` results |
+//! | `POST /v1/checksum` | `ai-checksum` | `checksum` results |
+//! | `POST /v1/analyze` | `ai-analyze --classify --patterns` | `classification` and `analysis` results |
+//!
+//! Every request needs an `Authorization: Bearer` header with the server's
+//! token, or it fails with 401. The paths a request names are checked
+//! against the sandbox policy before anything is read; if any is refused,
+//! the request fails with 403 and a `POLICY_DENIED` record for each. Other
+//! invalid requests fail with 400 and one `INVALID_INPUT` record. Once the
+//! stream has started, files that cannot be read are error records in it.
+
+use crate::checksum;
+use crate::config::glob_match;
+use crate::error::{AiCoreutilsError, Result};
+use crate::fs_utils::{DirWalk, SymlinkPolicy, WalkEvent};
+use crate::grep::Matcher;
+use crate::hash::HashAlgorithm;
+use crate::jsonl::{JsonlRecord, RecordData};
+use crate::limits;
+use crate::memory::SafeMemoryAccess;
+use crate::ml_ops::{FileClassifier, PatternDetector};
+use crate::policy::{self, Access};
+use axum::body::{Body, Bytes};
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Media type of every response
+const JSONL_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Lines buffered per request before the work waits for the client
+const CHANNEL_CAPACITY: usize = 64;
+
+/// Bytes per `file_content_base64` record, as in `ai-cat`
+const BASE64_CHUNK: usize = 48 * 1024;
+
+/// State shared by every request
+struct Server {
+    token: String,
+    /// Compiled once; compiling its regexes is the slow part of a detector
+    detector: PatternDetector,
+}
+
+/// Body of `POST /v1/ls`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LsRequest {
+    /// Directories to list, or files to describe
+    pub paths: Vec<String>,
+    /// Include hidden entries
+    pub all: bool,
+}
+
+/// Body of `POST /v1/cat`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CatRequest {
+    /// Files to read
+    pub paths: Vec<String>,
+}
+
+/// Body of `POST /v1/grep`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GrepRequest {
+    /// Regular expression, or literal with `fixed_strings`
+    pub pattern: String,
+    /// Files, or directories with `recursive`
+    pub paths: Vec<String>,
+    /// Case-insensitive matching
+    pub ignore_case: bool,
+    /// Select lines that do not match
+    pub invert: bool,
+    /// Search directories recursively
+    pub recursive: bool,
+    /// Treat the pattern as a literal
+    pub fixed_strings: bool,
+}
+
+/// Body of `POST /v1/find`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FindRequest {
+    /// Directories (or files) to start from
+    pub paths: Vec<String>,
+    /// Wildcard pattern for the file name (`*` and `?`)
+    pub name: Option<String>,
+    /// `file`, `directory` or `symlink`
+    #[serde(rename = "type")]
+    pub file_type: Option<String>,
+    /// Levels to descend below each path
+    pub max_depth: Option<usize>,
+    /// Follow every symlink rather than only the starting paths
+    pub follow_symlinks: bool,
+}
+
+/// Body of `POST /v1/checksum`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChecksumRequest {
+    /// Files, or directories with `recursive`
+    pub paths: Vec<String>,
+    /// Digest algorithm (default: xxh3)
+    pub algorithm: Option<String>,
+    /// Hash directories recursively
+    pub recursive: bool,
+}
+
+/// Body of `POST /v1/analyze`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalyzeRequest {
+    /// Files, or directories with `recursive`
+    pub paths: Vec<String>,
+    /// Analyze directories recursively
+    pub recursive: bool,
+    /// Also send a `pattern_match` record for each of the first 100 matches
+    pub verbose: bool,
+}
+
+/// Response for a request that fails before its stream starts
+fn reject(status: StatusCode, records: &[JsonlRecord]) -> Response {
+    let body: String = records
+        .iter()
+        .filter_map(|record| record.to_jsonl().ok())
+        .map(|line| line + "\n")
+        .collect();
+    (status, [(header::CONTENT_TYPE, JSONL_CONTENT_TYPE)], body).into_response()
+}
+
+/// Why a request was refused before its stream started
+enum Refusal {
+    /// 400, with one `INVALID_INPUT` record
+    Invalid(String),
+    /// 403, with a `POLICY_DENIED` record for each path
+    Denied(Vec<JsonlRecord>),
+}
+
+impl IntoResponse for Refusal {
+    fn into_response(self) -> Response {
+        match self {
+            Refusal::Invalid(message) => {
+                reject(StatusCode::BAD_REQUEST, &[JsonlRecord::error(message, "INVALID_INPUT")])
+            }
+            Refusal::Denied(records) => reject(StatusCode::FORBIDDEN, &records),
+        }
+    }
+}
+
+/// What a handler returns: its stream, or why the request was refused
+type Handled = std::result::Result<Response, Refusal>;
+
+/// The request body, or why it was refused
+fn parse<T>(payload: std::result::Result<Json<T>, JsonRejection>) -> std::result::Result<T, Refusal> {
+    payload.map(|Json(request)| request).map_err(|e| Refusal::Invalid(e.body_text()))
+}
+
+/// Sending half of a response body
+struct Sink {
+    tx: mpsc::Sender<std::io::Result<Bytes>>,
+    /// Error code for files that fail
+    code: &'static str,
+}
+
+impl Sink {
+    /// Send a record; false once the client has gone away
+    fn send(&self, record: JsonlRecord) -> bool {
+        match record.to_jsonl() {
+            Ok(line) => self.tx.blocking_send(Ok(Bytes::from(line + "\n"))).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// Send a result record
+    fn result(&self, data: serde_json::Value) -> bool {
+        self.send(JsonlRecord::result(data))
+    }
+
+    /// Send a typed record (see [`RecordData`])
+    fn data(&self, data: RecordData) -> bool {
+        self.send(data.into())
+    }
+
+    /// Send an error record for `path`, as the utilities write them
+    fn error(&self, path: &Path, error: &AiCoreutilsError) -> bool {
+        let code = match error {
+            AiCoreutilsError::PolicyDenied { .. } => "POLICY_DENIED",
+            _ => self.code,
+        };
+        self.send(JsonlRecord::error(format!("{}: {}", path.display(), error), code))
+    }
+}
+
+/// Refuse requests without paths, or naming any the sandbox policy refuses
+fn check_paths(paths: &[String]) -> std::result::Result<(), Refusal> {
+    if paths.is_empty() {
+        return Err(Refusal::Invalid("no paths given".to_string()));
+    }
+    let denied: Vec<JsonlRecord> = paths
+        .iter()
+        .filter_map(|path| policy::check(Path::new(path), Access::Read).err())
+        .map(|e| JsonlRecord::error(e.to_string(), "POLICY_DENIED"))
+        .collect();
+    if !denied.is_empty() {
+        return Err(Refusal::Denied(denied));
+    }
+    Ok(())
+}
+
+/// Stream the records `work` sends from a blocking thread
+fn stream(code: &'static str, work: impl FnOnce(&Sink) + Send + 'static) -> Response {
+    let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+    tokio::task::spawn_blocking(move || work(&Sink { tx, code }));
+    (
+        [(header::CONTENT_TYPE, JSONL_CONTENT_TYPE)],
+        Body::from_stream(ReceiverStream::new(rx)),
+    )
+        .into_response()
+}
+
+/// Call `visit` for each file in `paths`, sending errors for the rest
+///
+/// Directories are walked when `recursive` is set and are errors otherwise.
+/// Returns false, stopping early, once a send or `visit` returns false.
+fn for_each_file(sink: &Sink, paths: &[String], recursive: bool, mut visit: impl FnMut(&Path) -> bool) -> bool {
+    for path in paths.iter().map(Path::new) {
+        let sent = if !path.is_dir() {
+            visit(path)
+        } else if !recursive {
+            let e = AiCoreutilsError::InvalidInput("Is a directory (set recursive to walk it)".to_string());
+            sink.error(path, &e)
+        } else {
+            DirWalk::new(path, SymlinkPolicy::CommandLine).all(|event| match event {
+                Ok(WalkEvent::Entry(entry)) if entry.is_file() => visit(entry.path()),
+                Ok(_) => true,
+                Err(e) => sink.error(path, &e),
+            })
+        };
+        if !sent {
+            return false;
+        }
+    }
+    true
+}
+
+/// Modification time of `metadata` in whole seconds
+fn modified_secs(metadata: &fs::Metadata) -> Option<u64> {
+    Some(metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs())
+}
+
+/// `ai-ls` record for the entry at `path`
+fn file_entry(path: &Path, metadata: &fs::Metadata) -> JsonlRecord {
+    #[cfg(unix)]
+    let permissions = {
+        use std::os::unix::fs::PermissionsExt;
+        format!("{:o}", metadata.permissions().mode() & 0o777)
+    };
+    #[cfg(not(unix))]
+    let permissions = "??????????".to_string();
+
+    JsonlRecord::FileEntry {
+        timestamp: Utc::now(),
+        path: path.display().to_string(),
+        size: metadata.len(),
+        modified: modified_secs(metadata)
+            .and_then(|secs| DateTime::from_timestamp(secs as i64, 0))
+            .unwrap_or_else(Utc::now),
+        is_dir: metadata.is_dir(),
+        is_symlink: metadata.is_symlink(),
+        permissions,
+        git_status: None,
+        last_commit: None,
+    }
+}
+
+/// Send the entries of the directory at `path`, sorted by name, or the
+/// entry for `path` itself if it is not a directory
+fn ls_path(sink: &Sink, path: &Path, all: bool) -> bool {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => return sink.error(path, &e.into()),
+    };
+    if !metadata.is_dir() {
+        return sink.send(file_entry(path, &metadata));
+    }
+    let mut entries = Vec::new();
+    for entry in DirWalk::with_max_depth(path, SymlinkPolicy::CommandLine, Some(1)) {
+        match entry {
+            Ok(WalkEvent::Entry(entry)) if entry.depth() == 1 => {
+                let hidden = entry.path().file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+                if all || !hidden {
+                    entries.push(entry);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                if !sink.error(path, &e) {
+                    return false;
+                }
+            }
+        }
+    }
+    entries.sort_by(|a, b| a.path().cmp(b.path()));
+    entries.iter().all(|entry| match entry.metadata() {
+        Some(metadata) => sink.send(file_entry(entry.path(), metadata)),
+        None => true,
+    })
+}
+
+/// Send the `ai-cat` records for the file at `path`
+fn cat_file(sink: &Sink, path: &Path) -> bool {
+    let mem = match SafeMemoryAccess::new(path) {
+        Ok(mem) => mem,
+        Err(e) => return sink.error(path, &e),
+    };
+    let data = mem.as_slice();
+    let file = path.display().to_string();
+
+    let Ok(text) = std::str::from_utf8(data) else {
+        let chunks = data.len().div_ceil(BASE64_CHUNK);
+        return data.chunks(BASE64_CHUNK).enumerate().all(|(index, chunk)| {
+            sink.result(serde_json::json!({
+                "type": "file_content_base64",
+                "file": file,
+                "chunk": index,
+                "chunks": chunks,
+                "offset": index * BASE64_CHUNK,
+                "size": chunk.len(),
+                "content": STANDARD.encode(chunk),
+            }))
+        });
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    lines.iter().enumerate().all(|(index, line)| {
+        sink.data(RecordData::FileContent {
+            file: file.clone(),
+            content: line.to_string(),
+            line_number: Some(index + 1),
+            line_non_blank_number: None,
+            is_blank: line.trim().is_empty(),
+            line_count: lines.len(),
+        })
+    })
+}
+
+/// Send an `ai-grep -n` record for every selected line of the file at `path`
+fn grep_file(sink: &Sink, matcher: &Matcher, path: &Path) -> bool {
+    let mem = match SafeMemoryAccess::new(path) {
+        Ok(mem) => mem,
+        Err(e) => return sink.error(path, &e),
+    };
+    let data = mem.as_slice();
+    let file = path.display().to_string();
+    matcher.matches(data).all(|m| {
+        let (match_start, match_end) = m.span.unwrap_or((0, 0));
+        sink.send(JsonlRecord::MatchRecord {
+            timestamp: Utc::now(),
+            file: file.clone(),
+            line_number: m.line_number,
+            line_content: String::from_utf8_lossy(m.line).into_owned(),
+            match_start,
+            match_end,
+            captures: m.span.and_then(|_| matcher.captures(m.line)),
+        })
+    })
+}
+
+/// Send the `ai-analyze` records for the file at `path`
+fn analyze_file(sink: &Sink, detector: &PatternDetector, path: &Path, verbose: bool) -> bool {
+    let content = match fs::read(path).map_err(AiCoreutilsError::from).and_then(|content| {
+        limits::read_file(path, content.len() as u64)?;
+        Ok(content)
+    }) {
+        Ok(content) => content,
+        Err(e) => return sink.error(path, &e),
+    };
+    let file = path.display().to_string();
+
+    let classification = match FileClassifier::classify(path, &content) {
+        Ok(classification) => classification,
+        Err(e) => return sink.error(path, &e),
+    };
+    let sent = sink.data(RecordData::Classification {
+        file: file.clone(),
+        file_type: classification.file_type,
+        mime_type: classification.mime_type,
+        encoding: classification.encoding,
+        is_binary: classification.is_binary,
+        language: classification.language,
+        confidence: classification.confidence,
+        cache_hit: None,
+        image: None,
+        compression: None,
+        change: None,
+    });
+    if !sent {
+        return false;
+    }
+
+    let analysis = match detector.analyze_content(&String::from_utf8_lossy(&content), path) {
+        Ok(analysis) => analysis,
+        Err(e) => return sink.error(path, &e),
+    };
+    let sent = sink.data(RecordData::Analysis {
+        file: file.clone(),
+        total_patterns: analysis.total_patterns,
+        patterns_by_type: analysis.patterns_by_type,
+        statistics: analysis.statistics,
+        issues: analysis.issues,
+        cache_hit: None,
+        extracted_from: None,
+        compression: None,
+        change: None,
+    });
+    if !sent || !verbose {
+        return sent;
+    }
+    analysis.matches.iter().take(100).all(|m| {
+        sink.result(serde_json::json!({
+            "type": "pattern_match",
+            "file": file,
+            "pattern_type": format!("{:?}", m.pattern_type),
+            "matched_text": m.matched_text,
+            "position": { "start": m.start, "end": m.end },
+            "confidence": m.confidence,
+        }))
+    })
+}
+
+async fn ls(payload: std::result::Result<Json<LsRequest>, JsonRejection>) -> Handled {
+    let request = parse(payload)?;
+    check_paths(&request.paths)?;
+    Ok(stream("LS_ERROR", move |sink| {
+        for path in &request.paths {
+            if !ls_path(sink, Path::new(path), request.all) {
+                return;
+            }
+        }
+    }))
+}
+
+async fn cat(payload: std::result::Result<Json<CatRequest>, JsonRejection>) -> Handled {
+    let request = parse(payload)?;
+    check_paths(&request.paths)?;
+    Ok(stream("CAT_ERROR", move |sink| {
+        for_each_file(sink, &request.paths, false, |path| cat_file(sink, path));
+    }))
+}
+
+async fn grep(payload: std::result::Result<Json<GrepRequest>, JsonRejection>) -> Handled {
+    let request = parse(payload)?;
+    check_paths(&request.paths)?;
+    let matcher = if request.fixed_strings {
+        Matcher::new(&request.pattern, request.ignore_case, request.invert)
+    } else {
+        match Matcher::regex(&request.pattern, request.ignore_case, request.invert) {
+            Ok(matcher) => matcher,
+            Err(e) => return Err(Refusal::Invalid(e.to_string())),
+        }
+    };
+    Ok(stream("GREP_ERROR", move |sink| {
+        for_each_file(sink, &request.paths, request.recursive, |path| grep_file(sink, &matcher, path));
+    }))
+}
+
+async fn find(payload: std::result::Result<Json<FindRequest>, JsonRejection>) -> Handled {
+    let request = parse(payload)?;
+    check_paths(&request.paths)?;
+    if let Some(file_type) = request.file_type.as_deref() {
+        if !matches!(file_type, "file" | "directory" | "symlink") {
+            return Err(Refusal::Invalid(format!("Unknown type: {} (expected file, directory or symlink)", file_type)));
+        }
+    }
+    let symlinks = if request.follow_symlinks {
+        SymlinkPolicy::Always
+    } else {
+        SymlinkPolicy::CommandLine
+    };
+    Ok(stream("FIND_ERROR", move |sink| {
+        for root in request.paths.iter().map(Path::new) {
+            let sent = DirWalk::with_max_depth(root, symlinks, request.max_depth).all(|event| match event {
+                Ok(WalkEvent::Entry(entry)) => {
+                    let name = entry.path().file_name().unwrap_or_default().to_string_lossy();
+                    let selected = request.name.as_deref().is_none_or(|pattern| glob_match(pattern, &name))
+                        && request.file_type.as_deref().is_none_or(|kind| kind == entry.kind());
+                    if !selected {
+                        return true;
+                    }
+                    let mut result = serde_json::json!({
+                        "type": "match",
+                        "path": entry.path().display().to_string(),
+                        "file_type": entry.kind(),
+                    });
+                    if let Some(metadata) = entry.metadata() {
+                        result["size"] = metadata.len().into();
+                        if let Some(secs) = modified_secs(metadata) {
+                            result["modified"] = secs.into();
+                        }
+                    }
+                    sink.result(result)
+                }
+                Ok(WalkEvent::Loop(symlink_loop)) => sink.send(JsonlRecord::metadata(symlink_loop.to_json())),
+                Err(e) => sink.error(root, &e),
+            });
+            if !sent {
+                return;
+            }
+        }
+    }))
+}
+
+async fn checksum(payload: std::result::Result<Json<ChecksumRequest>, JsonRejection>) -> Handled {
+    let request = parse(payload)?;
+    check_paths(&request.paths)?;
+    let algorithm: HashAlgorithm = match request.algorithm.as_deref().map(str::parse).transpose() {
+        Ok(algorithm) => algorithm.unwrap_or_default(),
+        Err(e) => return Err(Refusal::Invalid(e)),
+    };
+    Ok(stream("CHECKSUM_ERROR", move |sink| {
+        for_each_file(sink, &request.paths, request.recursive, |path| {
+            match checksum::hash_file(path, algorithm) {
+                Ok(entry) => sink.result(serde_json::json!({
+                    "type": "checksum",
+                    "file": path.display().to_string(),
+                    "algorithm": algorithm.as_str(),
+                    "digest": entry.digest,
+                    "size": entry.size,
+                })),
+                Err(e) => sink.error(path, &e),
+            }
+        });
+    }))
+}
+
+async fn analyze(
+    State(server): State<Arc<Server>>,
+    payload: std::result::Result<Json<AnalyzeRequest>, JsonRejection>,
+) -> Handled {
+    let request = parse(payload)?;
+    check_paths(&request.paths)?;
+    Ok(stream("ANALYZE_ERROR", move |sink| {
+        for_each_file(sink, &request.paths, request.recursive, |path| {
+            analyze_file(sink, &server.detector, path, request.verbose)
+        });
+    }))
+}
+
+/// Refuse requests without the server's bearer token
+async fn authorize(State(server): State<Arc<Server>>, request: Request, next: Next) -> Response {
+    let given = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match given {
+        Some(token) if constant_time_eq(token.as_bytes(), server.token.as_bytes()) => next.run(request).await,
+        _ => {
+            let record = JsonlRecord::error("Missing or invalid bearer token", "UNAUTHORIZED");
+            let mut response = reject(StatusCode::UNAUTHORIZED, &[record]);
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, header::HeaderValue::from_static("Bearer"));
+            response
+        }
+    }
+}
+
+/// Compare secrets in time that depends only on their lengths
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// The HTTP endpoints, requiring `token` as a bearer token
+pub fn router(token: &str) -> Result<Router> {
+    if token.is_empty() {
+        return Err(AiCoreutilsError::InvalidInput("the bearer token must not be empty".to_string()));
+    }
+    let server = Arc::new(Server {
+        token: token.to_string(),
+        detector: PatternDetector::new()?,
+    });
+    Ok(Router::new()
+        .route("/v1/ls", post(ls))
+        .route("/v1/cat", post(cat))
+        .route("/v1/grep", post(grep))
+        .route("/v1/find", post(find))
+        .route("/v1/checksum", post(checksum))
+        .route("/v1/analyze", post(analyze))
+        .route_layer(middleware::from_fn_with_state(Arc::clone(&server), authorize))
+        .with_state(server))
+}
+
+/// Serve the HTTP endpoints on `listener` until the process ends
+pub async fn serve(listener: TcpListener, token: &str) -> Result<()> {
+    axum::serve(listener, router(token)?).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::Digest;
+    use serde_json::{json, Value};
+    use tempfile::TempDir;
+
+    const TOKEN: &str = "secret";
+
+    /// Serve on a free port, returning its base URL
+    async fn start() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, TOKEN));
+        format!("http://{}", addr)
+    }
+
+    /// POST `body` to `endpoint`, returning the status and the records
+    async fn post(base: &str, token: Option<&str>, endpoint: &str, body: Value) -> (u16, Vec<Value>) {
+        let mut request = reqwest::Client::new().post(format!("{}{}", base, endpoint)).json(&body);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await.unwrap();
+        let status = response.status().as_u16();
+        let text = response.text().await.unwrap();
+        (status, text.lines().map(|line| serde_json::from_str(line).unwrap()).collect())
+    }
+
+    fn tree() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "alpha\nbeta\nAlphabet\n").unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub/b.rs"), "fn main() {}\n").unwrap();
+        dir
+    }
+
+    fn path(dir: &TempDir, name: &str) -> String {
+        dir.path().join(name).to_string_lossy().into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_requires_token() {
+        let dir = tree();
+        let base = start().await;
+        let body = json!({ "paths": [path(&dir, "a.txt")] });
+        for token in [None, Some("wrong")] {
+            let (status, records) = post(&base, token, "/v1/cat", body.clone()).await;
+            assert_eq!(status, 401);
+            assert_eq!(records[0]["code"], "UNAUTHORIZED");
+        }
+        let (status, _) = post(&base, Some(TOKEN), "/v1/cat", body).await;
+        assert_eq!(status, 200);
+
+        assert!(router("").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_grep_and_cat() {
+        let dir = tree();
+        let base = start().await;
+        let body = json!({ "pattern": "^alpha", "paths": [path(&dir, "a.txt")], "ignore_case": true });
+        let (status, records) = post(&base, Some(TOKEN), "/v1/grep", body).await;
+        assert_eq!(status, 200);
+        let lines: Vec<(u64, &str)> = records
+            .iter()
+            .map(|r| (r["line_number"].as_u64().unwrap(), r["line_content"].as_str().unwrap()))
+            .collect();
+        assert_eq!(lines, vec![(1, "alpha"), (3, "Alphabet")]);
+        assert!(records.iter().all(|r| r["type"] == "match" && r["match_end"] == 5));
+
+        let body = json!({ "pattern": "(", "paths": [path(&dir, "a.txt")] });
+        let (status, records) = post(&base, Some(TOKEN), "/v1/grep", body).await;
+        assert_eq!((status, records[0]["code"].as_str()), (400, Some("INVALID_INPUT")));
+        let (status, _) = post(&base, Some(TOKEN), "/v1/grep", json!({ "pattern": "a", "bogus": 1 })).await;
+        assert_eq!(status, 400);
+
+        let (_, records) = post(&base, Some(TOKEN), "/v1/cat", json!({ "paths": [path(&dir, "a.txt")] })).await;
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1]["data"]["type"], "file_content");
+        assert_eq!(records[1]["data"]["content"], "beta");
+        assert_eq!(records[1]["data"]["line_count"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_ls_find_checksum_analyze() {
+        let dir = tree();
+        let base = start().await;
+
+        let (_, records) = post(&base, Some(TOKEN), "/v1/ls", json!({ "paths": [dir.path()] })).await;
+        let listed: Vec<(&str, bool)> = records
+            .iter()
+            .map(|r| (r["path"].as_str().unwrap(), r["is_dir"].as_bool().unwrap()))
+            .collect();
+        assert_eq!(listed, vec![(path(&dir, "a.txt").as_str(), false), (path(&dir, "sub").as_str(), true)]);
+
+        let body = json!({ "paths": [dir.path()], "name": "*.rs", "type": "file" });
+        let (_, records) = post(&base, Some(TOKEN), "/v1/find", body).await;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["data"]["path"], path(&dir, "sub/b.rs"));
+        assert_eq!(records[0]["data"]["size"], 13);
+
+        let body = json!({ "paths": [path(&dir, "a.txt"), path(&dir, "sub")], "algorithm": "crc32" });
+        let (_, records) = post(&base, Some(TOKEN), "/v1/checksum", body).await;
+        assert_eq!(records[0]["data"]["digest"], HashAlgorithm::Crc32.hex_digest(b"alpha\nbeta\nAlphabet\n"));
+        assert_eq!(records[1]["type"], "error");
+        assert_eq!(records[1]["code"], "CHECKSUM_ERROR");
+        let body = json!({ "paths": [path(&dir, "a.txt")], "algorithm": "md5" });
+        assert_eq!(post(&base, Some(TOKEN), "/v1/checksum", body).await.0, 400);
+
+        fs::write(dir.path().join("notes.txt"), "see https://example.com\n").unwrap();
+        let body = json!({ "paths": [path(&dir, "notes.txt")] });
+        let (_, records) = post(&base, Some(TOKEN), "/v1/analyze", body).await;
+        assert_eq!(records[0]["data"]["type"], "classification");
+        assert_eq!(records[0]["data"]["is_binary"], false);
+        assert_eq!(records[1]["data"]["type"], "analysis");
+        assert_eq!(records[1]["data"]["patterns_by_type"]["Url"], 1);
+
+        assert_eq!(post(&base, Some(TOKEN), "/v1/analyze", json!({ "paths": [] })).await.0, 400);
+    }
+}
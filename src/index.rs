@@ -0,0 +1,777 @@
+//! Persistent trigram index for repeated content searches
+//!
+//! A [`ContentIndex`] maps every three-byte sequence (trigram) in the files
+//! under a directory to the files that contain it. A search first extracts
+//! the literal fragments every match must contain ([`required_literals`]),
+//! intersects the postings of their trigrams, and only reads the files that
+//! survive; the regex is still run on each of them, so the index narrows a
+//! search but never changes its result.
+//!
+//! The index lives in `<root>/.ai-index`. Files are tracked by size and
+//! mtime: [`ContentIndex::update`] re-reads only files whose stat changed,
+//! and [`ContentIndex::candidates`] treats such files as unindexed, so a
+//! stale index costs speed, not correctness. Trigrams are ASCII-lowercased,
+//! which lets one index serve both case-sensitive and `-i` searches.
+//!
+//! Binary files (a NUL in the first 8 KiB) are recorded but not indexed, and
+//! files over 32 MiB are recorded but always searched.
+//!
+//! # Format
+//!
+//! | Field | Type |
+//! |-------|------|
+//! | Magic `AITG` | 4 bytes |
+//! | Version | u8 |
+//! | File count, then per file: path, size, mtime, coverage | varints, i64 LE, u8 |
+//! | Trigram count, then per trigram: key, file count, file id deltas | varints |
+//!
+//! Paths are relative to the root and stored as UTF-8 (lossily converted).
+
+use crate::error::{AiCoreutilsError, Result};
+use crate::fs_utils::{self, DirWalk, SymlinkPolicy, WalkEvent};
+use crate::memory::line_index::{read_varint, write_varint};
+use crate::memory::SafeMemoryAccess;
+use crate::replace::looks_binary;
+use regex_syntax::hir::{Class, Hir, HirKind};
+use regex_syntax::ParserBuilder;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Name of the index file in the indexed directory
+pub const INDEX_FILE_NAME: &str = ".ai-index";
+
+const MAGIC: &[u8; 4] = b"AITG";
+const VERSION: u8 = 1;
+
+/// Files larger than this are not indexed
+const MAX_INDEXED_SIZE: u64 = 32 * 1024 * 1024;
+
+/// Number of distinct trigram keys (three bytes)
+const TRIGRAM_SPACE: usize = 1 << 24;
+
+/// How much of a file the index covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coverage {
+    /// Every trigram of the file is indexed
+    Indexed,
+    /// Binary file, skipped by searches
+    Binary,
+    /// Too large to index, always searched
+    TooLarge,
+}
+
+impl Coverage {
+    fn to_byte(self) -> u8 {
+        match self {
+            Coverage::Indexed => 0,
+            Coverage::Binary => 1,
+            Coverage::TooLarge => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Coverage::Indexed),
+            1 => Some(Coverage::Binary),
+            2 => Some(Coverage::TooLarge),
+            _ => None,
+        }
+    }
+}
+
+/// A file recorded in the index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedFile {
+    /// Path relative to the index root
+    pub path: String,
+    /// Size in bytes when indexed
+    pub size: u64,
+    /// Modification time when indexed, seconds since the Unix epoch
+    pub mtime_secs: i64,
+    /// Sub-second part of the modification time
+    pub mtime_nanos: u32,
+    /// How much of the file is indexed
+    pub coverage: Coverage,
+}
+
+/// Counts from one [`ContentIndex::update`]
+#[derive(Debug, Default)]
+pub struct UpdateStats {
+    /// Files indexed for the first time
+    pub added: usize,
+    /// Files re-indexed because their size or mtime changed
+    pub updated: usize,
+    /// Files dropped because they no longer exist
+    pub removed: usize,
+    /// Files left as they were
+    pub unchanged: usize,
+    /// Paths that could not be walked or read
+    pub errors: Vec<(PathBuf, AiCoreutilsError)>,
+}
+
+/// Size of an index
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexStats {
+    /// Files recorded
+    pub files: usize,
+    /// Files whose trigrams are indexed
+    pub indexed_files: usize,
+    /// Binary files recorded but not indexed
+    pub binary_files: usize,
+    /// Files too large to index
+    pub large_files: usize,
+    /// Distinct trigrams
+    pub trigrams: usize,
+    /// Total (trigram, file) entries
+    pub postings: usize,
+}
+
+/// Files a search has to read
+#[derive(Debug, Default)]
+pub struct Candidates {
+    /// Files that may contain a match, sorted by path
+    pub files: Vec<PathBuf>,
+    /// Files ruled out by the index without being read
+    pub filtered: usize,
+    /// Files searched because they changed since they were indexed (or are new)
+    pub stale: usize,
+    /// Unchanged binary files skipped
+    pub binary: usize,
+    /// Paths that could not be walked
+    pub errors: Vec<(PathBuf, AiCoreutilsError)>,
+}
+
+/// Inverted trigram index over the files below a directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentIndex {
+    root: PathBuf,
+    /// Recorded files; a file's id is its position
+    files: Vec<IndexedFile>,
+    /// Sorted ids of the files containing each trigram
+    postings: HashMap<u32, Vec<u32>>,
+}
+
+/// A regular file found by walking the root
+struct WalkedFile {
+    path: PathBuf,
+    relative: String,
+    size: u64,
+    mtime: (i64, u32),
+}
+
+impl ContentIndex {
+    /// An empty index of `root`
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            files: Vec::new(),
+            postings: HashMap::new(),
+        }
+    }
+
+    /// The index file of `root`
+    pub fn index_path(root: &Path) -> PathBuf {
+        root.join(INDEX_FILE_NAME)
+    }
+
+    /// Load the index of `root`, or `None` if it has not been built
+    pub fn open(root: &Path) -> Result<Option<Self>> {
+        let path = Self::index_path(root);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(AiCoreutilsError::Io(e)),
+        };
+        Self::decode(root, &bytes).map(Some).ok_or_else(|| {
+            AiCoreutilsError::InvalidInput(format!("Corrupt content index: {}", path.display()))
+        })
+    }
+
+    /// Atomically write the index to its index file
+    pub fn save(&self) -> Result<()> {
+        fs_utils::atomic_write(&Self::index_path(&self.root), &self.encode())
+    }
+
+    /// The indexed directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Recorded files, in id order
+    pub fn files(&self) -> &[IndexedFile] {
+        &self.files
+    }
+
+    /// Counts of files, trigrams and postings
+    pub fn stats(&self) -> IndexStats {
+        let count = |coverage| self.files.iter().filter(|f| f.coverage == coverage).count();
+        IndexStats {
+            files: self.files.len(),
+            indexed_files: count(Coverage::Indexed),
+            binary_files: count(Coverage::Binary),
+            large_files: count(Coverage::TooLarge),
+            trigrams: self.postings.len(),
+            postings: self.postings.values().map(Vec::len).sum(),
+        }
+    }
+
+    /// Bring the index up to date with the files under the root
+    ///
+    /// Only new files and files whose size or mtime changed are read.
+    /// Failures are collected in the returned stats rather than aborting.
+    pub fn update(&mut self) -> UpdateStats {
+        let mut stats = UpdateStats::default();
+        let walked = walk_files(&self.root, &mut stats.errors);
+
+        let mut keep = vec![false; self.files.len()];
+        let mut changed = Vec::new();
+        {
+            let ids: HashMap<&str, usize> =
+                self.files.iter().enumerate().map(|(id, f)| (f.path.as_str(), id)).collect();
+            for file in walked {
+                match ids.get(file.relative.as_str()) {
+                    Some(&id) if self.files[id].size == file.size && self.files[id].mtime() == file.mtime => {
+                        keep[id] = true;
+                        stats.unchanged += 1;
+                    }
+                    Some(_) => changed.push((file, true)),
+                    None => changed.push((file, false)),
+                }
+            }
+        }
+        let dropped = keep.iter().filter(|&&k| !k).count();
+        stats.removed = dropped - changed.iter().filter(|(_, existed)| *existed).count();
+
+        if dropped > 0 {
+            self.retain(&keep);
+        }
+
+        let mut trigrams = TrigramSet::new();
+        for (file, existed) in changed {
+            match self.add(&file, &mut trigrams) {
+                Ok(()) if existed => stats.updated += 1,
+                Ok(()) => stats.added += 1,
+                Err(e) => stats.errors.push((file.path, e)),
+            }
+        }
+
+        stats
+    }
+
+    /// Drop the files not marked in `keep`, renumbering the rest
+    fn retain(&mut self, keep: &[bool]) {
+        let mut remap = vec![u32::MAX; keep.len()];
+        let kept = keep.iter().enumerate().filter(|(_, &k)| k);
+        for (next, (id, _)) in kept.enumerate() {
+            remap[id] = next as u32;
+        }
+
+        let mut id = 0;
+        self.files.retain(|_| {
+            id += 1;
+            keep[id - 1]
+        });
+        // Kept ids keep their order, so renumbered postings stay sorted
+        self.postings.retain(|_, ids| {
+            ids.retain_mut(|id| {
+                *id = remap[*id as usize];
+                *id != u32::MAX
+            });
+            !ids.is_empty()
+        });
+    }
+
+    /// Read and index one file, appending it with the next id
+    fn add(&mut self, file: &WalkedFile, trigrams: &mut TrigramSet) -> Result<()> {
+        let id = self.files.len() as u32;
+        let coverage = if file.size > MAX_INDEXED_SIZE {
+            Coverage::TooLarge
+        } else {
+            let mem = SafeMemoryAccess::new(&file.path)?;
+            let data = mem.as_slice();
+            if looks_binary(data) {
+                Coverage::Binary
+            } else {
+                for key in trigrams.collect(data) {
+                    self.postings.entry(key).or_default().push(id);
+                }
+                Coverage::Indexed
+            }
+        };
+
+        self.files.push(IndexedFile {
+            path: file.relative.clone(),
+            size: file.size,
+            mtime_secs: file.mtime.0,
+            mtime_nanos: file.mtime.1,
+            coverage,
+        });
+        Ok(())
+    }
+
+    /// Files under the root that may contain every one of `literals`
+    ///
+    /// The tree is walked so files changed since the last update are always
+    /// included; literals shorter than three bytes do not narrow the search.
+    pub fn candidates(&self, literals: &[Vec<u8>]) -> Candidates {
+        let mut candidates = Candidates::default();
+        let walked = walk_files(&self.root, &mut candidates.errors);
+
+        let ids: HashMap<&str, usize> =
+            self.files.iter().enumerate().map(|(id, f)| (f.path.as_str(), id)).collect();
+        let mut selected = vec![false; self.files.len()];
+        match self.matching_ids(literals) {
+            Some(matching) => matching.into_iter().for_each(|id| selected[id as usize] = true),
+            None => selected.iter_mut().for_each(|s| *s = true),
+        }
+
+        for file in walked {
+            let id = ids
+                .get(file.relative.as_str())
+                .copied()
+                .filter(|&id| self.files[id].size == file.size && self.files[id].mtime() == file.mtime);
+            let Some(id) = id else {
+                candidates.stale += 1;
+                candidates.files.push(file.path);
+                continue;
+            };
+            match self.files[id].coverage {
+                Coverage::Binary => candidates.binary += 1,
+                Coverage::Indexed if !selected[id] => candidates.filtered += 1,
+                _ => candidates.files.push(file.path),
+            }
+        }
+
+        candidates.files.sort();
+        candidates
+    }
+
+    /// Ids of the indexed files containing every trigram of `literals`
+    ///
+    /// `None` when the literals have no trigrams, so nothing can be ruled out.
+    fn matching_ids(&self, literals: &[Vec<u8>]) -> Option<Vec<u32>> {
+        let mut keys: Vec<u32> = literals.iter().flat_map(|l| l.windows(3).map(trigram)).collect();
+        keys.sort_unstable();
+        keys.dedup();
+
+        let mut lists: Vec<&[u32]> = keys
+            .iter()
+            .map(|key| self.postings.get(key).map_or(&[][..], Vec::as_slice))
+            .collect();
+        // Intersect the rarest trigrams first so the running set stays small
+        lists.sort_by_key(|ids| ids.len());
+
+        let (first, rest) = lists.split_first()?;
+        let mut ids = first.to_vec();
+        for list in rest {
+            if ids.is_empty() {
+                break;
+            }
+            ids = intersect(&ids, list);
+        }
+        Some(ids)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+
+        write_varint(&mut out, self.files.len() as u64);
+        for file in &self.files {
+            write_varint(&mut out, file.path.len() as u64);
+            out.extend_from_slice(file.path.as_bytes());
+            write_varint(&mut out, file.size);
+            out.extend_from_slice(&file.mtime_secs.to_le_bytes());
+            write_varint(&mut out, u64::from(file.mtime_nanos));
+            out.push(file.coverage.to_byte());
+        }
+
+        let mut keys: Vec<u32> = self.postings.keys().copied().collect();
+        keys.sort_unstable();
+        write_varint(&mut out, keys.len() as u64);
+        for key in keys {
+            let ids = &self.postings[&key];
+            write_varint(&mut out, u64::from(key));
+            write_varint(&mut out, ids.len() as u64);
+            let mut previous = 0;
+            for &id in ids {
+                write_varint(&mut out, u64::from(id - previous));
+                previous = id;
+            }
+        }
+        out
+    }
+
+    fn decode(root: &Path, bytes: &[u8]) -> Option<Self> {
+        let mut rest = bytes.strip_prefix(MAGIC)?;
+        let (&version, body) = rest.split_first()?;
+        if version != VERSION {
+            return None;
+        }
+        rest = body;
+
+        let file_count = read_varint(&mut rest)? as usize;
+        let mut files = Vec::with_capacity(file_count.min(rest.len()));
+        for _ in 0..file_count {
+            let len = read_varint(&mut rest)? as usize;
+            let path = std::str::from_utf8(rest.get(..len)?).ok()?.to_string();
+            rest = &rest[len..];
+            let size = read_varint(&mut rest)?;
+            let mtime_secs = i64::from_le_bytes(rest.get(..8)?.try_into().ok()?);
+            rest = &rest[8..];
+            let mtime_nanos = u32::try_from(read_varint(&mut rest)?).ok()?;
+            let (&coverage, body) = rest.split_first()?;
+            rest = body;
+            files.push(IndexedFile {
+                path,
+                size,
+                mtime_secs,
+                mtime_nanos,
+                coverage: Coverage::from_byte(coverage)?,
+            });
+        }
+
+        let key_count = read_varint(&mut rest)? as usize;
+        let mut postings = HashMap::with_capacity(key_count.min(rest.len()));
+        for _ in 0..key_count {
+            let key = u32::try_from(read_varint(&mut rest)?).ok()?;
+            let len = read_varint(&mut rest)? as usize;
+            let mut ids = Vec::with_capacity(len.min(rest.len()));
+            let mut id = 0u64;
+            for _ in 0..len {
+                id += read_varint(&mut rest)?;
+                if id >= files.len() as u64 {
+                    return None;
+                }
+                ids.push(id as u32);
+            }
+            postings.insert(key, ids);
+        }
+
+        rest.is_empty().then(|| Self {
+            root: root.to_path_buf(),
+            files,
+            postings,
+        })
+    }
+}
+
+impl IndexedFile {
+    fn mtime(&self) -> (i64, u32) {
+        (self.mtime_secs, self.mtime_nanos)
+    }
+}
+
+/// Regular files under `root`, excluding the index file itself
+///
+/// Symlinks are not followed, so each file is recorded under one path.
+fn walk_files(root: &Path, errors: &mut Vec<(PathBuf, AiCoreutilsError)>) -> Vec<WalkedFile> {
+    let index_path = ContentIndex::index_path(root);
+    let mut files = Vec::new();
+
+    for event in DirWalk::new(root, SymlinkPolicy::Never) {
+        let entry = match event {
+            Ok(WalkEvent::Entry(entry)) => entry,
+            Ok(WalkEvent::Loop(_)) => continue,
+            Err(e) => {
+                errors.push((root.to_path_buf(), e));
+                continue;
+            }
+        };
+        if !entry.is_file() || entry.path() == index_path {
+            continue;
+        }
+        // Files that vanish mid-walk are simply not recorded
+        let Some(metadata) = entry.metadata() else {
+            continue;
+        };
+        let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+        files.push(WalkedFile {
+            relative: relative.to_string_lossy().into_owned(),
+            size: metadata.len(),
+            mtime: mtime(metadata),
+            path: entry.into_path(),
+        });
+    }
+
+    files
+}
+
+fn mtime(metadata: &fs::Metadata) -> (i64, u32) {
+    match metadata.modified().map(|t| t.duration_since(UNIX_EPOCH)) {
+        Ok(Ok(since)) => (since.as_secs() as i64, since.subsec_nanos()),
+        _ => (0, 0),
+    }
+}
+
+/// Key of a trigram, ASCII-lowercased
+fn trigram(bytes: &[u8]) -> u32 {
+    bytes[..3]
+        .iter()
+        .fold(0, |key, b| (key << 8) | u32::from(b.to_ascii_lowercase()))
+}
+
+/// Intersection of two sorted id lists
+fn intersect(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(a.len().min(b.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                out.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Distinct trigrams of a buffer, using a bitmap reused across files
+struct TrigramSet {
+    seen: Vec<u64>,
+    keys: Vec<u32>,
+}
+
+impl TrigramSet {
+    fn new() -> Self {
+        Self {
+            seen: vec![0; TRIGRAM_SPACE / 64],
+            keys: Vec::new(),
+        }
+    }
+
+    /// The distinct trigram keys of `data`, in first-seen order
+    fn collect(&mut self, data: &[u8]) -> Vec<u32> {
+        for window in data.windows(3) {
+            let key = trigram(window);
+            let (word, bit) = ((key / 64) as usize, 1u64 << (key % 64));
+            if self.seen[word] & bit == 0 {
+                self.seen[word] |= bit;
+                self.keys.push(key);
+            }
+        }
+        for &key in &self.keys {
+            self.seen[(key / 64) as usize] = 0;
+        }
+        std::mem::take(&mut self.keys)
+    }
+}
+
+/// Byte strings every match of `pattern` must contain
+///
+/// The extraction is conservative: an empty list means the pattern cannot
+/// be narrowed (an alternation at the top, a pattern of classes, or one
+/// that fails to parse). With `fixed_strings` the pattern is taken
+/// literally. Case-insensitive letters count as literals when all their
+/// case variants are ASCII, since the index ignores ASCII case.
+pub fn required_literals(pattern: &str, fixed_strings: bool, ignore_case: bool) -> Vec<Vec<u8>> {
+    let source = if fixed_strings {
+        regex::escape(pattern)
+    } else {
+        pattern.to_string()
+    };
+    let hir = match ParserBuilder::new()
+        .case_insensitive(ignore_case)
+        .utf8(false)
+        .build()
+        .parse(&source)
+    {
+        Ok(hir) => hir,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut literals = Vec::new();
+    let mut current = Vec::new();
+    collect_literals(&hir, &mut current, &mut literals);
+    flush(&mut current, &mut literals);
+    literals
+}
+
+/// Extend `current` with the bytes `hir` always matches, flushing completed
+/// runs into `literals`
+fn collect_literals(hir: &Hir, current: &mut Vec<u8>, literals: &mut Vec<Vec<u8>>) {
+    match hir.kind() {
+        HirKind::Literal(literal) => current.extend_from_slice(&literal.0),
+        HirKind::Class(class) => match single_byte(class) {
+            Some(byte) => current.push(byte),
+            None => flush(current, literals),
+        },
+        // Zero-width, so the bytes on either side stay adjacent
+        HirKind::Empty | HirKind::Look(_) => {}
+        HirKind::Capture(capture) => collect_literals(&capture.sub, current, literals),
+        HirKind::Concat(parts) => {
+            for part in parts {
+                collect_literals(part, current, literals);
+            }
+        }
+        HirKind::Repetition(repetition) => {
+            flush(current, literals);
+            if repetition.min > 0 {
+                collect_literals(&repetition.sub, current, literals);
+                flush(current, literals);
+            }
+        }
+        HirKind::Alternation(_) => flush(current, literals),
+    }
+}
+
+fn flush(current: &mut Vec<u8>, literals: &mut Vec<Vec<u8>>) {
+    if !current.is_empty() {
+        literals.push(std::mem::take(current));
+    }
+}
+
+/// The one byte a class matches, ignoring ASCII case
+///
+/// `[aA]` gives `a`; a class with a non-ASCII member (such as the Kelvin
+/// sign that `(?i)k` also matches) gives `None`.
+fn single_byte(class: &Class) -> Option<u8> {
+    let members: Vec<u32> = match class {
+        Class::Unicode(class) => class
+            .iter()
+            .flat_map(|r| u32::from(r.start())..=u32::from(r.end()))
+            .take(4)
+            .collect(),
+        Class::Bytes(class) => class
+            .iter()
+            .flat_map(|r| u32::from(r.start())..=u32::from(r.end()))
+            .take(4)
+            .collect(),
+    };
+    let first = u8::try_from(*members.first()?).ok()?.to_ascii_lowercase();
+    members
+        .iter()
+        .all(|&m| u8::try_from(m).is_ok_and(|b| b.is_ascii() && b.to_ascii_lowercase() == first))
+        .then_some(first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grep::Matcher;
+
+    fn literals(pattern: &str, ignore_case: bool) -> Vec<String> {
+        required_literals(pattern, false, ignore_case)
+            .into_iter()
+            .map(|l| String::from_utf8(l).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_required_literals() {
+        assert_eq!(literals("hello", false), ["hello"]);
+        assert_eq!(literals(r"fn (\w+)_test\(", false), ["fn ", "_test("]);
+        assert_eq!(literals("^abc$", false), ["abc"]);
+        assert_eq!(literals("colou?r", false), ["colo", "r"]);
+        assert_eq!(literals("ab+c", false), ["a", "b", "c"]);
+        assert_eq!(literals(r"\x41BC", false), ["ABC"]);
+        assert!(literals("foo|bar", false).is_empty());
+        assert!(literals(r"\d+", false).is_empty());
+        assert!(literals("(", false).is_empty());
+
+        // Case-insensitive letters are literals unless a non-ASCII character folds to them
+        assert_eq!(literals("Error", true), ["error"]);
+        assert_eq!(literals("wake", true), ["wa", "e"]);
+        assert_eq!(literals("(?i)caf\u{e9}s", false), ["caf"]);
+        assert_eq!(required_literals("a.b", true, false), [b"a.b".to_vec()]);
+    }
+
+    #[test]
+    fn test_update_is_incremental() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "alpha beta\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "gamma delta\n").unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub/c.bin"), b"beta\0gamma").unwrap();
+
+        let mut index = ContentIndex::new(dir.path());
+        let stats = index.update();
+        assert_eq!((stats.added, stats.updated, stats.removed, stats.unchanged), (3, 0, 0, 0));
+        assert_eq!(index.stats().binary_files, 1);
+
+        // Changing one file, deleting another and adding a third touches only those
+        fs::write(dir.path().join("a.txt"), "alpha epsilon zeta\n").unwrap();
+        fs::remove_file(dir.path().join("b.txt")).unwrap();
+        fs::write(dir.path().join("d.txt"), "delta\n").unwrap();
+        let stats = index.update();
+        assert_eq!((stats.added, stats.updated, stats.removed, stats.unchanged), (1, 1, 1, 1));
+
+        let mut fresh = ContentIndex::new(dir.path());
+        fresh.update();
+        assert_eq!(index.stats(), fresh.stats());
+        let found = |index: &ContentIndex, word: &str| {
+            let candidates = index.candidates(&required_literals(word, true, false));
+            candidates
+                .files
+                .iter()
+                .map(|p| p.strip_prefix(dir.path()).unwrap().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(found(&index, "delta"), ["d.txt"]);
+        assert_eq!(found(&index, "epsilon"), ["a.txt"]);
+        assert!(found(&index, "gamma").is_empty());
+        assert_eq!(found(&fresh, "delta"), ["d.txt"]);
+    }
+
+    #[test]
+    fn test_candidates_include_stale_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "needle\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "hay\n").unwrap();
+        let mut index = ContentIndex::new(dir.path());
+        index.update();
+
+        let literals = required_literals("NEEDLE", false, true);
+        let candidates = index.candidates(&literals);
+        assert_eq!(candidates.files, [dir.path().join("a.txt")]);
+        assert_eq!((candidates.filtered, candidates.stale), (1, 0));
+
+        // A file changed or added since the update is searched regardless
+        fs::write(dir.path().join("b.txt"), "more hay, longer\n").unwrap();
+        fs::write(dir.path().join("c.txt"), "new\n").unwrap();
+        let candidates = index.candidates(&literals);
+        assert_eq!(candidates.files.len(), 3);
+        assert_eq!(candidates.stale, 2);
+
+        // Short literals cannot be narrowed
+        assert_eq!(index.candidates(&required_literals("ne", false, false)).files.len(), 3);
+    }
+
+    #[test]
+    fn test_save_and_open() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(ContentIndex::open(dir.path()).unwrap().is_none());
+        for i in 0..50 {
+            fs::write(dir.path().join(format!("f{}.rs", i)), format!("fn item_{}() {{}}\n", i)).unwrap();
+        }
+        let mut index = ContentIndex::new(dir.path());
+        index.update();
+        index.save().unwrap();
+
+        let loaded = ContentIndex::open(dir.path()).unwrap().unwrap();
+        assert_eq!(loaded, index);
+        // The index file itself is never indexed
+        assert_eq!(loaded.clone().update().unchanged, 50);
+
+        // Every candidate the index rules out really has no match
+        let matcher = Matcher::regex(r"item_4\d", false, false).unwrap();
+        let candidates = loaded.candidates(&required_literals(r"item_4\d", false, false));
+        assert_eq!(candidates.files.len(), 11);
+        let literal = loaded.candidates(&required_literals("item_42(", true, false));
+        assert_eq!(literal.files, [dir.path().join("f42.rs")]);
+        for file in loaded.files() {
+            let data = fs::read(dir.path().join(&file.path)).unwrap();
+            let matched = matcher.matches(&data).next().is_some();
+            assert!(!matched || candidates.files.contains(&dir.path().join(&file.path)));
+        }
+
+        fs::write(ContentIndex::index_path(dir.path()), b"AITG\x01\x05").unwrap();
+        assert!(ContentIndex::open(dir.path()).is_err());
+    }
+}
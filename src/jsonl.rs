@@ -3,10 +3,272 @@
 //! Provides structured JSONL output for all AI-Coreutils operations.
 
 use crate::error::Result;
+use crate::git_info::{GitStatus, LastCommit};
+use crate::limits::{self, Emit, Limit};
+use crate::ml_ops::{ImageMetadata, TextStatistics};
 use crate::AiCoreutilsError;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+static TIMESTAMPS_ENABLED: AtomicBool = AtomicBool::new(true);
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+static LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Warn as u8);
+static SESSION: OnceLock<String> = OnceLock::new();
+static INVOCATION_ID: OnceLock<String> = OnceLock::new();
+static NEXT_RECORD_ID: AtomicU64 = AtomicU64::new(1);
+/// Records held back by `--ordered`; `None` when output is not ordered
+static HELD: Mutex<Option<Held>> = Mutex::new(None);
+
+/// Enable or disable timestamps in serialized records (process-wide)
+pub fn set_timestamps_enabled(enabled: bool) {
+    TIMESTAMPS_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Mark every serialized record with `"dry_run": true` (process-wide)
+///
+/// Set by utilities running with `--dry-run`, so their output has the same
+/// records as a real run while being distinguishable from one.
+pub fn set_dry_run(dry_run: bool) {
+    DRY_RUN.store(dry_run, Ordering::Relaxed);
+}
+
+/// Whether output is marked as a dry run
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// Mark every serialized record with `"session": id` (process-wide)
+///
+/// Set from the configured [session](crate::session) so the records of a
+/// multi-step run can be correlated. The first id set is kept.
+pub fn set_session(id: &str) {
+    let _ = SESSION.set(id.to_string());
+}
+
+/// The session records are marked with, if any
+pub fn session() -> Option<&'static str> {
+    SESSION.get().map(String::as_str)
+}
+
+/// Identifier every serialized record is marked with as `"invocation_id"`
+///
+/// A fresh UUID for each run of a utility, so its records can be regrouped
+/// after the output of several runs is merged.
+pub fn invocation_id() -> &'static str {
+    INVOCATION_ID.get_or_init(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Allocate a record id, unique within this invocation (see [`Link`])
+pub fn next_record_id() -> u64 {
+    NEXT_RECORD_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// How a record relates to the other records of its invocation
+///
+/// Ids come from [`next_record_id`] before the records they name are
+/// written, so a child may come before its parent, as leading context
+/// lines come before their match. Consumers group by `invocation_id` and
+/// `parent_record_id`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Link {
+    /// The record's own id, written as `record_id`
+    pub record_id: Option<u64>,
+    /// Id of the record this one belongs to, written as `parent_record_id`
+    pub parent_record_id: Option<u64>,
+}
+
+impl Link {
+    /// A record that others may name as their parent
+    pub fn id(id: u64) -> Self {
+        Self { record_id: Some(id), parent_record_id: None }
+    }
+
+    /// A record belonging to the record with id `parent`
+    pub fn child_of(parent: u64) -> Self {
+        Self { record_id: None, parent_record_id: Some(parent) }
+    }
+}
+
+/// Which `warning` records are written
+///
+/// Levels are ordered from quietest to most verbose; a warning is written
+/// when its level is at or below the level set. Errors are always written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    /// Errors only
+    Error,
+    /// Conditions that make output incomplete or lossy
+    #[default]
+    Warn,
+    /// Routine skips as well, such as binary files passed over by a search
+    Info,
+}
+
+impl LogLevel {
+    /// Name used on the command line, in config and in JSONL output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "error" => Ok(LogLevel::Error),
+            "warn" | "warning" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            _ => Err(format!("Invalid log level: {} (expected error, warn or info)", s)),
+        }
+    }
+}
+
+/// Set which `warning` records are written (process-wide)
+pub fn set_log_level(level: LogLevel) {
+    LOG_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// The level set by [`set_log_level`]
+pub fn log_level() -> LogLevel {
+    match LOG_LEVEL.load(Ordering::Relaxed) {
+        0 => LogLevel::Error,
+        1 => LogLevel::Warn,
+        _ => LogLevel::Info,
+    }
+}
+
+/// Hold records back and write them sorted when the process exits
+///
+/// For utilities whose parallel modes finish files in any order. Records
+/// are sorted by their `file` or `path` field, then by `line_number` or
+/// `line`; records without a line number stay after the lines of their
+/// file that came before them, and records without a path (summaries,
+/// errors) come last in the order they were written. Progress records are
+/// written as they come. Held records are written to stdout by
+/// [`flush_ordered`], which runs at exit on Unix.
+pub fn set_ordered() {
+    let mut held = held();
+    if held.is_some() {
+        return;
+    }
+    *held = Some(Held::default());
+
+    #[cfg(unix)]
+    // SAFETY: registers a handler that only touches process-wide statics
+    unsafe {
+        libc::atexit(flush_at_exit);
+    }
+}
+
+/// Whether records are being held back for ordered output
+pub fn is_ordered() -> bool {
+    held().is_some()
+}
+
+/// Write the records held back by [`set_ordered`] to stdout, sorted
+///
+/// Limits are applied here, in the sorted order, so `--max-records` pages
+/// are the same on every run. Later records are written as they come.
+pub fn flush_ordered() -> Result<()> {
+    let Some(held) = held().take() else {
+        return Ok(());
+    };
+    let mut records = held.records;
+    // Stable, so records with equal keys keep the order they came in
+    records.sort_by(|a, b| (a.path.is_none(), &a.path, a.line).cmp(&(b.path.is_none(), &b.path, b.line)));
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    for record in records {
+        match limits::record_held()? {
+            Emit::Write => writeln!(out, "{}", record.text)?,
+            Emit::Replace(line) => {
+                writeln!(out, "{}", line)?;
+                return Ok(out.flush()?);
+            }
+            Emit::Skip => {}
+        }
+    }
+    if let Some(line) = limits::finish_held()? {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(out.flush()?)
+}
+
+#[cfg(unix)]
+extern "C" fn flush_at_exit() {
+    if let Err(e) = flush_ordered() {
+        eprintln!("ai-coreutils: failed to write ordered output: {}", e);
+    }
+}
+
+/// Records held back for ordered output
+#[derive(Debug, Default)]
+struct Held {
+    records: Vec<HeldRecord>,
+    /// Last line number held for each path
+    last_line: HashMap<String, u64>,
+}
+
+#[derive(Debug)]
+struct HeldRecord {
+    path: Option<String>,
+    line: u64,
+    text: String,
+}
+
+fn held() -> MutexGuard<'static, Option<Held>> {
+    HELD.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Hold `record` back if output is ordered, returning whether it was
+fn hold(record: &JsonlRecord, link: Link) -> Result<bool> {
+    let mut held = held();
+    let Some(held) = held.as_mut() else {
+        return Ok(false);
+    };
+    // A read limit stops the utility, as unordered output would
+    limits::check()?;
+
+    let value = serde_json::to_value(record)?;
+    let fields = match value.get("data") {
+        Some(data) if data.is_object() => data,
+        _ => &value,
+    };
+    let field = |names: [&str; 2]| names.iter().find_map(|name| fields.get(*name).filter(|v| !v.is_null()));
+    let path = field(["file", "path"]).and_then(|v| v.as_str()).map(String::from);
+    let line = field(["line_number", "line"]).and_then(|v| v.as_u64());
+
+    let line = match (&path, line) {
+        (Some(path), Some(line)) => {
+            held.last_line.insert(path.clone(), line);
+            line
+        }
+        (Some(path), None) => held.last_line.get(path).copied().unwrap_or(0),
+        (None, _) => 0,
+    };
+    held.records.push(HeldRecord { path, line, text: record.to_jsonl_linked(link)? });
+    Ok(true)
+}
 
 /// JSONL record types
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,6 +314,21 @@ pub enum JsonlRecord {
         total: usize,
         /// Progress message
         message: String,
+        /// Identifier shared by all progress records of one operation
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        operation_id: Option<String>,
+        /// Completion percentage (0.0 to 100.0)
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        percent: Option<f64>,
+        /// Processing rate in units per second
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        rate: Option<f64>,
+        /// Estimated seconds remaining
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        eta_secs: Option<f64>,
+        /// Seconds elapsed since the operation started
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        elapsed_secs: Option<f64>,
     },
 
     /// File entry record (for directory listings)
@@ -71,6 +348,44 @@ pub enum JsonlRecord {
         is_symlink: bool,
         /// File permissions string
         permissions: String,
+        /// Git status, when requested and the file is in a repository
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        git_status: Option<GitStatus>,
+        /// Last commit that changed the file, when requested
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        last_commit: Option<LastCommit>,
+    },
+
+    /// A non-fatal condition: the utility carried on, but its output may be
+    /// incomplete or altered
+    #[serde(rename = "warning")]
+    Warning {
+        /// Timestamp when the condition was met
+        timestamp: DateTime<Utc>,
+        /// Level the warning is written at
+        level: LogLevel,
+        /// Warning code
+        code: String,
+        /// Human-readable explanation
+        message: String,
+        /// Path the warning is about, if any
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+    },
+
+    /// A resource limit was reached and the utility stopped
+    #[serde(rename = "limit_exceeded")]
+    LimitExceeded {
+        /// Timestamp when the limit was reached
+        timestamp: DateTime<Utc>,
+        /// Limit that was reached (`max_bytes`, `max_files` or `max_records`)
+        limit: String,
+        /// Its value
+        max: u64,
+        /// Human-readable explanation
+        message: String,
+        /// Continuation token for `--resume`
+        resume: String,
     },
 
     /// Match record (for grep operations)
@@ -88,7 +403,356 @@ pub enum JsonlRecord {
         match_start: usize,
         /// End position of match within line
         match_end: usize,
+        /// Regex capture groups of the match, keyed by name or number
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        captures: Option<BTreeMap<String, Option<String>>>,
+    },
+}
+
+/// Typed payload of a result, summary or status record
+///
+/// Serialized with its kind in a `type` field, as the `data` of a `result`
+/// record (or the `info` of a `metadata` record, for [`RecordData::Status`]),
+/// so `{"type":"result","data":{"type":"file_content",...}}` is read back
+/// with [`JsonlRecord::data`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecordData {
+    /// One line of a file, as `ai-cat` shows it
+    FileContent {
+        /// File the line was read from
+        file: String,
+        /// Line text, without its line ending
+        content: String,
+        /// Line number, when numbered or served from `--lines`
+        line_number: Option<usize>,
+        /// Number among non-blank lines, with `-b`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        line_non_blank_number: Option<usize>,
+        /// Whether the line is empty or whitespace
+        is_blank: bool,
+        /// Lines in the record's file
+        line_count: usize,
+    },
+
+    /// A chunk of a binary file, base64 encoded, as `ai-cat` shows it
+    FileContentBase64 {
+        /// File the chunk was read from
+        file: String,
+        /// Index of the chunk, from 0
+        chunk: usize,
+        /// Chunks in the file
+        chunks: usize,
+        /// File offset of the chunk
+        offset: usize,
+        /// Size of the chunk in bytes, before encoding
+        size: usize,
+        /// Base64 of the chunk
+        content: String,
+    },
+
+    /// The part of a file `ai-cat --bytes` or `--lines` served
+    FileRange {
+        /// File the part was read from
+        file: String,
+        /// Bytes (and lines) served
+        #[serde(flatten)]
+        range: ServedRange,
+        /// Compression the file was read decompressed from
+        compression: Option<String>,
+    },
+
+    /// A file `ai-cat --raw` copied to stdout
+    RawFile {
+        /// File copied
+        file: String,
+        /// Bytes copied
+        bytes: u64,
+        /// Part of the file copied, with `--bytes` or `--lines`
+        range: Option<ServedRange>,
+        /// Compression the file was read decompressed from
+        compression: Option<String>,
+    },
+
+    /// Totals of an `ai-cat --raw` run
+    RawSummary {
+        /// Files named on the command line
+        files: usize,
+        /// Bytes copied
+        bytes: u64,
+        /// Files that failed
+        errors: u64,
+    },
+
+    /// A whole file, as text or as the totals of its base64 chunks
+    FileSummary {
+        /// File summarized
+        file: String,
+        /// Size in bytes
+        size: usize,
+        /// Contents, for text files
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        content: Option<String>,
+        /// Address the file was mapped at, for text files
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        memory_pointer: Option<String>,
+        /// `base64` for binary files
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        encoding: Option<String>,
+        /// Number of `file_content_base64` records, for binary files
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        chunks: Option<usize>,
+        /// Compression the file was read decompressed from
+        compression: Option<String>,
+    },
+
+    /// Totals of an `ai-cp` run
+    CopySummary {
+        /// Files copied
+        files_copied: u64,
+        /// Bytes copied
+        bytes_copied: u64,
+        /// Bytes of holes skipped in sparse files
+        bytes_skipped_holes: u64,
+        /// Directories created
+        dirs_created: u64,
+        /// Files that failed
+        errors: u64,
+        /// Journal entry recording the copy, for `ai-undo`
+        journal: Option<String>,
+    },
+
+    /// A path `ai-find` matched
+    #[serde(rename = "match")]
+    FindMatch {
+        /// Path matched
+        path: String,
+        /// `file`, `directory`, `symlink` or `unknown`
+        file_type: String,
+        /// Size in bytes
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        size: Option<u64>,
+        /// Modification time in seconds since the epoch
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        modified: Option<u64>,
+        /// Permission bits in octal
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        permissions: Option<String>,
+        /// Final path component
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        /// Parent directory
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        parent: Option<String>,
+        /// Git status, with `--git`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        git_status: Option<GitStatus>,
+        /// Last commit that changed the path, with `--git`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        last_commit: Option<LastCommit>,
     },
+
+    /// What kind of file a file is (see [`crate::ml_ops::FileClassifier`])
+    Classification {
+        /// File classified
+        file: String,
+        /// Detected file type
+        file_type: String,
+        /// MIME type
+        mime_type: String,
+        /// Detected encoding
+        encoding: String,
+        /// Whether the file appears to be binary
+        is_binary: bool,
+        /// Detected language, for text
+        language: Option<String>,
+        /// Confidence in the classification
+        confidence: f64,
+        /// Whether the result came from the result cache
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_hit: Option<bool>,
+        /// Image basics, for images
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        image: Option<ImageMetadata>,
+        /// Compression the file was read decompressed from
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        compression: Option<String>,
+        /// How the file changed, with `--changed-since`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        change: Option<String>,
+    },
+
+    /// Patterns and statistics of a file's text (see [`crate::ml_ops::ContentAnalysis`])
+    Analysis {
+        /// File analyzed
+        file: String,
+        /// Patterns found
+        total_patterns: usize,
+        /// Patterns found, by type
+        patterns_by_type: HashMap<String, usize>,
+        /// Text statistics
+        statistics: TextStatistics,
+        /// Issues detected
+        issues: Vec<String>,
+        /// Whether the result came from the result cache
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_hit: Option<bool>,
+        /// Set when the text was extracted from a document: the kind of
+        /// document, if known
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "present"
+        )]
+        extracted_from: Option<Option<String>>,
+        /// Compression the file was read decompressed from
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        compression: Option<String>,
+        /// How the file changed, with `--changed-since`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        change: Option<String>,
+    },
+
+    /// Where an operation on a file has got to
+    Status {
+        /// File operated on
+        file: String,
+        /// Operation, such as `analyze`
+        operation: String,
+        /// `starting` or `complete`
+        status: String,
+    },
+
+    /// A transient failure about to be retried
+    RetryAttempt {
+        /// Operation retried, such as `read notes.txt`
+        operation: String,
+        /// Attempt that failed, from 1
+        attempt: u32,
+        /// Attempts allowed in all
+        max_attempts: u32,
+        /// Wait before the next attempt
+        delay_ms: u64,
+        /// Error of the failed attempt
+        error: String,
+    },
+
+    /// A question `ai-cp -i` would ask before overwriting
+    Prompt {
+        /// Question asked
+        prompt: String,
+    },
+
+    /// A directory `ai-cp --verbose` created
+    DirectoryCreated {
+        /// Directory created
+        path: String,
+    },
+
+    /// A hard link `ai-cp --verbose -l` created
+    HardLinkCreated {
+        /// File linked to
+        source: String,
+        /// Link created
+        dest: String,
+    },
+
+    /// A symbolic link `ai-cp --verbose -s` created
+    SymbolicLinkCreated {
+        /// Target of the link
+        source: String,
+        /// Link created
+        dest: String,
+    },
+
+    /// A file `ai-cp --verbose` copied
+    FileCopied {
+        /// File copied
+        source: String,
+        /// Copy written
+        dest: String,
+        /// Apparent bytes written, holes included
+        size: u64,
+        /// Bytes left as holes instead of being written
+        holes: u64,
+    },
+}
+
+/// Bytes, and with `--lines` the lines, `ai-cat` served from a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServedRange {
+    /// `bytes` or `lines`, as selected on the command line
+    pub unit: String,
+    /// File offset of the first byte served
+    pub byte_start: usize,
+    /// File offset just past the last byte served
+    pub byte_end: usize,
+    /// Bytes served
+    pub size: usize,
+    /// First line served, with `--lines`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_line: Option<usize>,
+    /// Last line served, with `--lines`; `null` when no line was
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "present"
+    )]
+    pub last_line: Option<Option<usize>>,
+    /// Whether an `ai-index` sidecar located the lines, with `--lines`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_index: Option<bool>,
+}
+
+/// Deserialize a field that is present, even as `null`, as `Some`
+fn present<'de, D, T>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+impl RecordData {
+    /// Whether the payload ends a utility's run, and so is written with
+    /// [`JsonlRecord::summary`]
+    pub fn is_summary(&self) -> bool {
+        matches!(
+            self,
+            RecordData::FileSummary { .. } | RecordData::CopySummary { .. } | RecordData::RawSummary { .. }
+        )
+    }
+
+    /// Whether the payload describes the run rather than its results, and
+    /// so is written as a `metadata` record
+    pub fn is_metadata(&self) -> bool {
+        matches!(
+            self,
+            RecordData::Status { .. }
+                | RecordData::RetryAttempt { .. }
+                | RecordData::Prompt { .. }
+                | RecordData::DirectoryCreated { .. }
+                | RecordData::HardLinkCreated { .. }
+                | RecordData::SymbolicLinkCreated { .. }
+                | RecordData::FileCopied { .. }
+        )
+    }
+}
+
+impl From<RecordData> for JsonlRecord {
+    /// A `metadata` record for statuses and other run metadata, a summary
+    /// for summaries and a `result` record otherwise
+    fn from(data: RecordData) -> Self {
+        // Every variant is plain data, which always serializes
+        let value = serde_json::to_value(&data).unwrap_or_default();
+        if data.is_metadata() {
+            JsonlRecord::metadata(value)
+        } else if data.is_summary() {
+            JsonlRecord::summary(value)
+        } else {
+            JsonlRecord::result(value)
+        }
+    }
 }
 
 impl JsonlRecord {
@@ -109,6 +773,23 @@ impl JsonlRecord {
         }
     }
 
+    /// Create a utility's summary record: a result with a `telemetry` field
+    /// (see [`crate::telemetry`])
+    pub fn summary(data: serde_json::Value) -> Self {
+        Self::result(crate::telemetry::attach(data))
+    }
+
+    /// The typed payload of a result or metadata record, if it is one of
+    /// the kinds [`RecordData`] knows
+    pub fn data(&self) -> Option<RecordData> {
+        match self {
+            JsonlRecord::Result { data: value, .. } | JsonlRecord::Metadata { info: value, .. } => {
+                serde_json::from_value(value.clone()).ok()
+            }
+            _ => None,
+        }
+    }
+
     /// Create a new metadata record
     pub fn metadata(info: serde_json::Value) -> Self {
         JsonlRecord::Metadata {
@@ -117,9 +798,100 @@ impl JsonlRecord {
         }
     }
 
+    /// Create a new warning record
+    pub fn warning(level: LogLevel, code: impl Into<String>, message: impl Into<String>, path: Option<&str>) -> Self {
+        JsonlRecord::Warning {
+            timestamp: Utc::now(),
+            level,
+            code: code.into(),
+            message: message.into(),
+            path: path.map(String::from),
+        }
+    }
+
+    /// Create a record reporting that `limit` stopped the utility
+    pub fn limit_exceeded(limit: Limit, max: u64, resume: String) -> Self {
+        JsonlRecord::LimitExceeded {
+            timestamp: Utc::now(),
+            limit: limit.as_str().to_string(),
+            max,
+            message: format!("Stopped at {} {}; continue with --resume {}", limit, max, resume),
+            resume,
+        }
+    }
+
+    /// Write the record to `out` as one line
+    ///
+    /// Counts towards `--max-records` and skips records an earlier page
+    /// wrote; once a limit is reached, a `limit_exceeded` record is written
+    /// instead and later records fail (see [`crate::limits`]). Progress
+    /// records are throttled by time, so they are never counted or skipped.
+    ///
+    /// With [`set_ordered`], records other than progress are held back
+    /// instead, and `out` is not used.
+    pub fn write_line<W: Write>(&self, out: W) -> Result<()> {
+        self.write_linked(out, Link::default())
+    }
+
+    /// Write the record to `out` as one line, with the ids of `link`
+    /// (see [`JsonlRecord::write_line`])
+    pub fn write_linked<W: Write>(&self, mut out: W, link: Link) -> Result<()> {
+        if !matches!(self, JsonlRecord::Progress { .. }) && hold(self, link)? {
+            return Ok(());
+        }
+        let emit = match self {
+            JsonlRecord::Progress { .. } => limits::check().map(|()| Emit::Write)?,
+            _ => limits::record()?,
+        };
+        match emit {
+            Emit::Write => writeln!(out, "{}", self.to_jsonl_linked(link)?)?,
+            Emit::Replace(line) => writeln!(out, "{}", line)?,
+            Emit::Skip => {}
+        }
+        Ok(())
+    }
+
+    /// Write the record to stdout as one line (see [`JsonlRecord::write_line`])
+    pub fn print(&self) -> Result<()> {
+        self.write_line(std::io::stdout().lock())
+    }
+
+    /// Write the record to stdout as one line, with the ids of `link`
+    pub fn print_linked(&self, link: Link) -> Result<()> {
+        self.write_linked(std::io::stdout().lock(), link)
+    }
+
     /// Serialize to JSONL string
     pub fn to_jsonl(&self) -> Result<String> {
-        serde_json::to_string(self).map_err(AiCoreutilsError::from)
+        self.to_jsonl_linked(Link::default())
+    }
+
+    /// Serialize to JSONL string, with the ids of `link`
+    pub fn to_jsonl_linked(&self, link: Link) -> Result<String> {
+        let timestamps = TIMESTAMPS_ENABLED.load(Ordering::Relaxed);
+        let dry_run = DRY_RUN.load(Ordering::Relaxed);
+        let session = session();
+
+        let mut value = serde_json::to_value(self)?;
+        if let Some(obj) = value.as_object_mut() {
+            if !timestamps {
+                obj.remove("timestamp");
+            }
+            obj.insert("invocation_id".to_string(), serde_json::Value::from(invocation_id()));
+            if let Some(id) = link.record_id {
+                obj.insert("record_id".to_string(), serde_json::Value::from(id));
+            }
+            if let Some(parent) = link.parent_record_id {
+                obj.insert("parent_record_id".to_string(), serde_json::Value::from(parent));
+            }
+            if dry_run {
+                obj.insert("dry_run".to_string(), serde_json::Value::Bool(true));
+            }
+            if let Some(session) = session {
+                obj.insert("session".to_string(), serde_json::Value::from(session));
+            }
+        }
+        serde_json::to_string(&value).map_err(AiCoreutilsError::from)
     }
 }
 
@@ -136,10 +908,12 @@ impl<W: Write> JsonlOutput<W> {
 
     /// Write a record to the output
     pub fn write_record(&mut self, record: &JsonlRecord) -> Result<()> {
-        let jsonl = record.to_jsonl()?;
-        writeln!(self.writer, "{}", jsonl)
-            .map_err(AiCoreutilsError::Io)?;
-        Ok(())
+        record.write_line(&mut self.writer)
+    }
+
+    /// Write a record to the output with the ids of `link`
+    pub fn write_linked(&mut self, record: &JsonlRecord, link: Link) -> Result<()> {
+        record.write_linked(&mut self.writer, link)
     }
 
     /// Flush the output
@@ -176,6 +950,37 @@ pub fn output_error(message: &str, code: &str, path: Option<&str>) -> Result<()>
     output.flush()
 }
 
+/// Output an error record and exit with status 1
+///
+/// For failures a utility cannot continue from, after which no summary or
+/// further records are written.
+pub fn fail(message: &str, code: &str, path: Option<&str>) -> ! {
+    let _ = output_error(message, code, path);
+    std::process::exit(1);
+}
+
+/// Output a warning record to stdout, unless `level` is above the log level
+pub fn output_warning(level: LogLevel, code: &str, message: &str, path: Option<&str>) -> Result<()> {
+    if level > log_level() {
+        return Ok(());
+    }
+    let mut output = JsonlOutput::new(std::io::stdout());
+    output.write_record(&JsonlRecord::warning(level, code, message, path))?;
+    output.flush()
+}
+
+/// Output an `INVALID_UTF8` warning for `path` if `data` is not valid UTF-8
+///
+/// For utilities that show file contents as JSON strings, where invalid
+/// sequences are replaced with U+FFFD.
+pub fn warn_invalid_utf8(path: &Path, data: &[u8]) -> Result<()> {
+    let Err(e) = std::str::from_utf8(data) else {
+        return Ok(());
+    };
+    let message = format!("Invalid UTF-8 at byte {} replaced with U+FFFD", e.valid_up_to());
+    output_warning(LogLevel::Warn, "INVALID_UTF8", &message, Some(&path.display().to_string()))
+}
+
 /// Output a result record to stdout
 pub fn output_result(data: serde_json::Value) -> Result<()> {
     let mut output = JsonlOutput::new(std::io::stdout());
@@ -183,6 +988,20 @@ pub fn output_result(data: serde_json::Value) -> Result<()> {
     output.flush()
 }
 
+/// Output a summary record to stdout (see [`JsonlRecord::summary`])
+pub fn output_summary(data: serde_json::Value) -> Result<()> {
+    let mut output = JsonlOutput::new(std::io::stdout());
+    output.write_record(&JsonlRecord::summary(data))?;
+    output.flush()
+}
+
+/// Output a typed record to stdout (see [`RecordData`])
+pub fn output_data(data: RecordData) -> Result<()> {
+    let mut output = JsonlOutput::new(std::io::stdout());
+    output.write_record(&data.into())?;
+    output.flush()
+}
+
 /// Output a metadata record to stdout
 pub fn output_info(info: serde_json::Value) -> Result<()> {
     let mut output = JsonlOutput::new(std::io::stdout());
@@ -198,10 +1017,155 @@ pub fn output_progress(current: usize, total: usize, message: &str) -> Result<()
         current,
         total,
         message: message.to_string(),
+        operation_id: None,
+        percent: None,
+        rate: None,
+        eta_secs: None,
+        elapsed_secs: None,
     })?;
     output.flush()
 }
 
+/// Default minimum interval between emitted progress records
+pub const DEFAULT_PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Throttled progress reporter with rate and ETA
+///
+/// Tracks progress of one operation (in bytes, files, or any other unit) and
+/// emits `progress` records at most once per interval, so agents get a
+/// consistent stream they can render or use to detect stalls.
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    operation_id: String,
+    message: String,
+    current: u64,
+    total: u64,
+    started: Instant,
+    last_emit: Option<Instant>,
+    interval: Duration,
+    parent: Option<u64>,
+}
+
+impl ProgressReporter {
+    /// Create a reporter for an operation with the given total (0 if unknown)
+    pub fn new(message: impl Into<String>, total: u64) -> Self {
+        Self {
+            operation_id: uuid::Uuid::new_v4().to_string(),
+            message: message.into(),
+            current: 0,
+            total,
+            started: Instant::now(),
+            last_emit: None,
+            interval: DEFAULT_PROGRESS_INTERVAL,
+            parent: None,
+        }
+    }
+
+    /// Set the minimum interval between emitted records
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Mark the records written by [`advance`](Self::advance) and
+    /// [`finish`](Self::finish) as children of the record with id `parent`
+    pub fn with_parent(mut self, parent: u64) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    /// Id of the record progress is reported for, if set
+    pub fn parent(&self) -> Option<u64> {
+        self.parent
+    }
+
+    /// Get the operation identifier
+    pub fn operation_id(&self) -> &str {
+        &self.operation_id
+    }
+
+    /// Get the current progress count
+    pub fn current(&self) -> u64 {
+        self.current
+    }
+
+    /// Update the progress message
+    pub fn set_message(&mut self, message: impl Into<String>) {
+        self.message = message.into();
+    }
+
+    /// Advance progress, returning a record if one is due
+    pub fn tick(&mut self, delta: u64) -> Option<JsonlRecord> {
+        self.current = self.current.saturating_add(delta);
+        let now = Instant::now();
+        let due = match self.last_emit {
+            None => true,
+            Some(last) => now.duration_since(last) >= self.interval,
+        };
+
+        if due {
+            self.last_emit = Some(now);
+            Some(self.record())
+        } else {
+            None
+        }
+    }
+
+    /// Advance progress and write a record to stdout if one is due
+    pub fn advance(&mut self, delta: u64) -> Result<()> {
+        if let Some(record) = self.tick(delta) {
+            let mut output = JsonlOutput::new(std::io::stdout());
+            output.write_linked(&record, self.link())?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Write a final record to stdout regardless of throttling
+    pub fn finish(&mut self) -> Result<()> {
+        self.last_emit = Some(Instant::now());
+        let mut output = JsonlOutput::new(std::io::stdout());
+        output.write_linked(&self.record(), self.link())?;
+        output.flush()
+    }
+
+    /// How the records written link to their parent
+    fn link(&self) -> Link {
+        self.parent.map(Link::child_of).unwrap_or_default()
+    }
+
+    /// Build a progress record for the current state
+    pub fn record(&self) -> JsonlRecord {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let rate = if elapsed > 0.0 {
+            Some(self.current as f64 / elapsed)
+        } else {
+            None
+        };
+
+        let (percent, eta_secs) = if self.total > 0 {
+            let percent = (self.current as f64 / self.total as f64 * 100.0).min(100.0);
+            let remaining = self.total.saturating_sub(self.current) as f64;
+            let eta = rate.filter(|r| *r > 0.0).map(|r| remaining / r);
+            (Some(percent), eta)
+        } else {
+            (None, None)
+        };
+
+        JsonlRecord::Progress {
+            timestamp: Utc::now(),
+            current: self.current as usize,
+            total: self.total as usize,
+            message: self.message.clone(),
+            operation_id: Some(self.operation_id.clone()),
+            percent,
+            rate,
+            eta_secs,
+            elapsed_secs: Some(elapsed),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -222,6 +1186,27 @@ mod tests {
         assert!(jsonl.contains("test"));
     }
 
+    #[test]
+    fn test_warning_record() {
+        let record = JsonlRecord::warning(LogLevel::Warn, "PERMISSION_DENIED", "Permission denied", Some("/srv/private"));
+        let value: serde_json::Value = serde_json::from_str(&record.to_jsonl().unwrap()).unwrap();
+        assert_eq!(value["type"], "warning");
+        assert_eq!(value["level"], "warn");
+        assert_eq!(value["path"], "/srv/private");
+
+        let record = JsonlRecord::warning(LogLevel::Info, "BINARY_SKIPPED", "Binary file skipped", None);
+        assert!(!record.to_jsonl().unwrap().contains("\"path\""));
+    }
+
+    #[test]
+    fn test_log_levels() {
+        assert!(LogLevel::Error < LogLevel::Warn && LogLevel::Warn < LogLevel::Info);
+        assert_eq!("warning".parse::<LogLevel>(), Ok(LogLevel::Warn));
+        assert_eq!("INFO".parse::<LogLevel>(), Ok(LogLevel::Info));
+        assert!("debug".parse::<LogLevel>().is_err());
+        assert_eq!(log_level(), LogLevel::default());
+    }
+
     #[test]
     fn test_file_entry_record() {
         let record = JsonlRecord::FileEntry {
@@ -232,12 +1217,308 @@ mod tests {
             is_dir: false,
             is_symlink: false,
             permissions: "rw-r--r--".to_string(),
+            git_status: None,
+            last_commit: None,
         };
         let jsonl = record.to_jsonl().unwrap();
         assert!(jsonl.contains("\"type\":\"file\""));
         assert!(jsonl.contains("/test/path"));
     }
 
+    #[test]
+    fn test_progress_reporter_throttles() {
+        let mut reporter = ProgressReporter::new("Copying", 100).with_interval(Duration::from_secs(60));
+
+        // First tick always emits, subsequent ones are throttled
+        assert!(reporter.tick(10).is_some());
+        assert!(reporter.tick(10).is_none());
+        assert_eq!(reporter.current(), 20);
+
+        match reporter.record() {
+            JsonlRecord::Progress { current, total, operation_id, percent, .. } => {
+                assert_eq!(current, 20);
+                assert_eq!(total, 100);
+                assert_eq!(operation_id.as_deref(), Some(reporter.operation_id()));
+                assert_eq!(percent, Some(20.0));
+            }
+            other => panic!("unexpected record: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_progress_reporter_unknown_total() {
+        let mut reporter = ProgressReporter::new("Scanning", 0);
+        reporter.tick(5);
+        let jsonl = reporter.record().to_jsonl().unwrap();
+        assert!(jsonl.contains("\"operation_id\""));
+        assert!(!jsonl.contains("\"percent\""));
+        assert!(!jsonl.contains("\"eta_secs\""));
+    }
+
+    /// Serialize `data` in its record and read it back
+    fn round_trip(data: RecordData) -> (serde_json::Value, RecordData) {
+        let line = serde_json::to_string(&JsonlRecord::from(data)).unwrap();
+        let record: JsonlRecord = serde_json::from_str(&line).unwrap();
+        (serde_json::from_str(&line).unwrap(), record.data().unwrap())
+    }
+
+    #[test]
+    fn test_record_data_round_trip() {
+        let statistics = TextStatistics {
+            characters: 12,
+            bytes: 12,
+            lines: 2,
+            words: 2,
+            avg_line_length: 5.5,
+            max_line_length: 6,
+            whitespace_ratio: 0.1,
+            entropy: 3.2,
+        };
+        let payloads = [
+            RecordData::FileContent {
+                file: "a.txt".into(),
+                content: "hello".into(),
+                line_number: Some(1),
+                line_non_blank_number: None,
+                is_blank: false,
+                line_count: 2,
+            },
+            RecordData::FileSummary {
+                file: "a.bin".into(),
+                size: 4096,
+                content: None,
+                memory_pointer: None,
+                encoding: Some("base64".into()),
+                chunks: Some(1),
+                compression: Some("gzip".into()),
+            },
+            RecordData::CopySummary {
+                files_copied: 3,
+                bytes_copied: 300,
+                bytes_skipped_holes: 0,
+                dirs_created: 1,
+                errors: 0,
+                journal: None,
+            },
+            RecordData::FindMatch {
+                path: "src/lib.rs".into(),
+                file_type: "file".into(),
+                size: Some(10),
+                modified: Some(1_700_000_000),
+                permissions: Some("644".into()),
+                name: Some("lib.rs".into()),
+                parent: Some("src".into()),
+                git_status: Some(GitStatus::Modified),
+                last_commit: None,
+            },
+            RecordData::Classification {
+                file: "a.txt".into(),
+                file_type: "text".into(),
+                mime_type: "text/plain".into(),
+                encoding: "utf-8".into(),
+                is_binary: false,
+                language: None,
+                confidence: 0.9,
+                cache_hit: Some(false),
+                image: None,
+                compression: None,
+                change: Some("added".into()),
+            },
+            RecordData::Analysis {
+                file: "a.txt".into(),
+                total_patterns: 1,
+                patterns_by_type: HashMap::from([("Email".into(), 1)]),
+                statistics,
+                issues: vec!["High entropy".into()],
+                cache_hit: Some(true),
+                extracted_from: Some(None),
+                compression: None,
+                change: None,
+            },
+            RecordData::Status {
+                file: "a.txt".into(),
+                operation: "analyze".into(),
+                status: "complete".into(),
+            },
+            RecordData::FileContentBase64 {
+                file: "a.bin".into(),
+                chunk: 0,
+                chunks: 1,
+                offset: 0,
+                size: 3,
+                content: "AAEC".into(),
+            },
+            RecordData::RawFile {
+                file: "a.txt".into(),
+                bytes: 5,
+                range: None,
+                compression: None,
+            },
+            RecordData::RawSummary {
+                files: 1,
+                bytes: 5,
+                errors: 0,
+            },
+            RecordData::RetryAttempt {
+                operation: "read a.txt".into(),
+                attempt: 1,
+                max_attempts: 3,
+                delay_ms: 100,
+                error: "IO error: interrupted".into(),
+            },
+            RecordData::Prompt {
+                prompt: "Overwrite b.txt? (y/n)".into(),
+            },
+            RecordData::DirectoryCreated { path: "out".into() },
+            RecordData::HardLinkCreated {
+                source: "a.txt".into(),
+                dest: "b.txt".into(),
+            },
+            RecordData::SymbolicLinkCreated {
+                source: "a.txt".into(),
+                dest: "b.txt".into(),
+            },
+            RecordData::FileCopied {
+                source: "a.txt".into(),
+                dest: "b.txt".into(),
+                size: 8192,
+                holes: 4096,
+            },
+        ];
+
+        for data in payloads {
+            let expected = serde_json::to_value(&data).unwrap();
+            let (line, parsed) = round_trip(data);
+            assert_eq!(serde_json::to_value(&parsed).unwrap(), expected);
+            let payload = if line["type"] == "metadata" { &line["info"] } else { &line["data"] };
+            assert_eq!(payload["type"], expected["type"]);
+        }
+    }
+
+    #[test]
+    fn test_record_data_shapes() {
+        let (line, _) = round_trip(RecordData::CopySummary {
+            files_copied: 1,
+            bytes_copied: 5,
+            bytes_skipped_holes: 0,
+            dirs_created: 0,
+            errors: 0,
+            journal: None,
+        });
+        assert_eq!(line["type"], "result");
+        assert_eq!(line["data"]["type"], "copy_summary");
+        assert!(line["data"]["telemetry"].is_object());
+        assert!(line["data"]["journal"].is_null());
+
+        let (line, parsed) = round_trip(RecordData::Status {
+            file: "a.txt".into(),
+            operation: "analyze".into(),
+            status: "starting".into(),
+        });
+        assert_eq!(line["type"], "metadata");
+        assert_eq!(line["info"]["status"], "starting");
+        assert!(matches!(parsed, RecordData::Status { .. }));
+
+        // A document whose kind is unknown keeps its explicit null
+        let analysis = |extracted_from| RecordData::Analysis {
+            file: "a.pdf".into(),
+            total_patterns: 0,
+            patterns_by_type: HashMap::new(),
+            statistics: TextStatistics {
+                characters: 0,
+                bytes: 0,
+                lines: 0,
+                words: 0,
+                avg_line_length: 0.0,
+                max_line_length: 0,
+                whitespace_ratio: 0.0,
+                entropy: 0.0,
+            },
+            issues: Vec::new(),
+            cache_hit: None,
+            extracted_from,
+            compression: None,
+            change: None,
+        };
+        let (line, parsed) = round_trip(analysis(Some(None)));
+        assert!(line["data"].as_object().unwrap().contains_key("extracted_from"));
+        assert!(matches!(parsed, RecordData::Analysis { extracted_from: Some(None), .. }));
+        let (line, _) = round_trip(analysis(None));
+        assert!(!line["data"].as_object().unwrap().contains_key("extracted_from"));
+
+        // A range sits beside the file's fields, and a `--lines` range
+        // that served no line keeps its null `last_line`
+        let range = ServedRange {
+            unit: "lines".into(),
+            byte_start: 10,
+            byte_end: 10,
+            size: 0,
+            first_line: Some(4),
+            last_line: Some(None),
+            line_index: Some(false),
+        };
+        let (line, parsed) = round_trip(RecordData::FileRange {
+            file: "a.txt".into(),
+            range: range.clone(),
+            compression: None,
+        });
+        assert_eq!(line["data"]["byte_start"], 10);
+        assert!(line["data"]["last_line"].is_null());
+        assert!(matches!(parsed, RecordData::FileRange { range: parsed, .. } if parsed == range));
+
+        let (line, _) = round_trip(RecordData::FileCopied {
+            source: "a".into(),
+            dest: "b".into(),
+            size: 1,
+            holes: 0,
+        });
+        assert_eq!(line["type"], "metadata");
+
+        // Ad-hoc payloads have no typed form
+        assert!(JsonlRecord::result(serde_json::json!({"type": "csv_row"})).data().is_none());
+        assert!(JsonlRecord::error("Test error", "TEST_ERR").data().is_none());
+    }
+
+    #[test]
+    fn test_progress_round_trip() {
+        let mut reporter = ProgressReporter::new("Copying", 100);
+        reporter.tick(25);
+        let line = serde_json::to_string(&reporter.record()).unwrap();
+        match serde_json::from_str(&line).unwrap() {
+            JsonlRecord::Progress { current, total, message, percent, .. } => {
+                assert_eq!((current, total), (25, 100));
+                assert_eq!(message, "Copying");
+                assert_eq!(percent, Some(25.0));
+            }
+            other => panic!("unexpected record: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_record_links() {
+        let parse = |line: String| serde_json::from_str::<serde_json::Value>(&line).unwrap();
+        let record = JsonlRecord::error("Test error", "TEST_ERR");
+
+        let plain = parse(record.to_jsonl().unwrap());
+        assert_eq!(plain["invocation_id"], invocation_id());
+        assert!(plain.get("record_id").is_none() && plain.get("parent_record_id").is_none());
+
+        let id = next_record_id();
+        assert!(next_record_id() > id);
+        let parent = parse(record.to_jsonl_linked(Link::id(id)).unwrap());
+        assert_eq!(parent["record_id"], id);
+        let child = parse(record.to_jsonl_linked(Link::child_of(id)).unwrap());
+        assert_eq!(child["parent_record_id"], id);
+        assert_eq!(child["invocation_id"], parent["invocation_id"]);
+
+        // The ids are written around the record, which still reads back
+        assert!(matches!(serde_json::from_value(child).unwrap(), JsonlRecord::Error { .. }));
+
+        let reporter = ProgressReporter::new("Copying", 10).with_parent(id);
+        assert_eq!(reporter.link(), Link::child_of(id));
+        assert_eq!(ProgressReporter::new("Copying", 10).link(), Link::default());
+    }
+
     #[test]
     fn test_jsonl_output_to_vec() {
         let mut output = JsonlOutput::new(Vec::new());
@@ -0,0 +1,265 @@
+//! Merging and aggregating JSONL output for ai-jsonmerge
+//!
+//! Tools write one JSON record per line; agents that run many of them end
+//! up with a pile of JSONL files. [`read_records`] loads them (remembering
+//! where each record came from), [`Filter`] selects records by type and
+//! time, [`sort_by_timestamp`] interleaves them chronologically and
+//! [`Counter`] tallies records by the value of any field.
+//!
+//! Fields are addressed by dot-separated paths such as `data.type` or
+//! `code`; a numeric segment indexes into an array.
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::io::BufRead;
+
+/// One record and where it was read from
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourcedRecord {
+    /// The parsed record
+    pub value: Value,
+    /// Index of the input it came from
+    pub source: usize,
+    /// Line number within that input (1-based)
+    pub line: u64,
+    /// The record's `timestamp`, if it has a valid RFC 3339 one
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+/// Records read from one input
+#[derive(Debug, Default)]
+pub struct ReadOutcome {
+    /// Records that parsed as JSON
+    pub records: Vec<SourcedRecord>,
+    /// Lines that were not valid JSON (blank lines are skipped silently)
+    pub invalid_lines: u64,
+    /// First invalid line, if any
+    pub first_invalid_line: Option<u64>,
+}
+
+/// Read every JSON line of `reader`, tagging records with `source`
+pub fn read_records<R: BufRead>(reader: R, source: usize) -> std::io::Result<ReadOutcome> {
+    let mut outcome = ReadOutcome::default();
+    for (i, line) in reader.split(b'\n').enumerate() {
+        let line = line?;
+        let line_number = i as u64 + 1;
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        match serde_json::from_slice::<Value>(&line) {
+            Ok(value) => outcome.records.push(SourcedRecord {
+                timestamp: record_timestamp(&value),
+                value,
+                source,
+                line: line_number,
+            }),
+            Err(_) => {
+                outcome.invalid_lines += 1;
+                outcome.first_invalid_line.get_or_insert(line_number);
+            }
+        }
+    }
+    Ok(outcome)
+}
+
+/// The value at a dot-separated `path` in `value`
+pub fn field<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, segment| match value {
+        Value::Object(map) => map.get(segment),
+        Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    })
+}
+
+/// A record's `timestamp` field as a time
+pub fn record_timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    let text = value.get("timestamp")?.as_str()?;
+    DateTime::parse_from_rfc3339(text)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Stable sort by timestamp; records without one keep their order at the end
+pub fn sort_by_timestamp(records: &mut [SourcedRecord]) {
+    records.sort_by_key(|r| (r.timestamp.is_none(), r.timestamp));
+}
+
+/// Which records to keep
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    /// Keep records whose `type` or `data.type` is one of these (all if empty)
+    pub types: Vec<String>,
+    /// Keep records at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Keep records before this time
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl Filter {
+    /// Whether the record passes the filter
+    ///
+    /// With a time bound, records without a timestamp are dropped.
+    pub fn matches(&self, record: &SourcedRecord) -> bool {
+        if !self.types.is_empty() {
+            let type_matches = ["type", "data.type"].iter().any(|path| {
+                field(&record.value, path)
+                    .and_then(Value::as_str)
+                    .is_some_and(|t| self.types.iter().any(|wanted| wanted == t))
+            });
+            if !type_matches {
+                return false;
+            }
+        }
+        if self.since.is_none() && self.until.is_none() {
+            return true;
+        }
+        let Some(timestamp) = record.timestamp else {
+            return false;
+        };
+        self.since.is_none_or(|since| timestamp >= since) && self.until.is_none_or(|until| timestamp < until)
+    }
+}
+
+/// Counts of records by the value of one or more fields
+#[derive(Debug, Clone)]
+pub struct Counter {
+    paths: Vec<String>,
+    counts: Vec<HashMap<String, u64>>,
+}
+
+impl Counter {
+    /// Count by each of `paths` independently
+    pub fn new(paths: Vec<String>) -> Self {
+        let counts = vec![HashMap::new(); paths.len()];
+        Self { paths, counts }
+    }
+
+    /// Count one record; a missing field counts under `null`
+    pub fn add(&mut self, value: &Value) {
+        for (path, counts) in self.paths.iter().zip(&mut self.counts) {
+            let key = match field(value, path) {
+                Some(Value::String(s)) => s.clone(),
+                Some(other) => other.to_string(),
+                None => "null".to_string(),
+            };
+            *counts.entry(key).or_insert(0) += 1;
+        }
+    }
+
+    /// `(path, value, count)` for every path, most frequent values first
+    ///
+    /// Ties are broken by value so the output is deterministic.
+    pub fn results(&self) -> Vec<(&str, &str, u64)> {
+        let mut results = Vec::new();
+        for (path, counts) in self.paths.iter().zip(&self.counts) {
+            let mut sorted: Vec<_> = counts.iter().collect();
+            sorted.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+            results.extend(sorted.into_iter().map(|(value, &count)| (path.as_str(), value.as_str(), count)));
+        }
+        results
+    }
+
+    /// Counts for one path, keyed by value
+    pub fn counts(&self, path: &str) -> BTreeMap<&str, u64> {
+        self.paths
+            .iter()
+            .position(|p| p == path)
+            .map(|i| self.counts[i].iter().map(|(k, &v)| (k.as_str(), v)).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const LOG_A: &str = concat!(
+        r#"{"type":"result","timestamp":"2026-01-01T00:00:02Z","data":{"type":"checksum","file":"a"}}"#,
+        "\n\n",
+        r#"{"type":"error","timestamp":"2026-01-01T00:00:00Z","message":"x","code":"IO_ERROR"}"#,
+        "\nnot json\n",
+    );
+    const LOG_B: &str = concat!(
+        r#"{"type":"result","timestamp":"2026-01-01T00:00:01+00:00","data":{"type":"checksum","file":"b"}}"#,
+        "\n",
+        r#"{"type":"result","data":{"type":"csv_row"}}"#,
+    );
+
+    fn load() -> Vec<SourcedRecord> {
+        let a = read_records(LOG_A.as_bytes(), 0).unwrap();
+        assert_eq!((a.invalid_lines, a.first_invalid_line), (1, Some(4)));
+        let b = read_records(LOG_B.as_bytes(), 1).unwrap();
+        a.records.into_iter().chain(b.records).collect()
+    }
+
+    #[test]
+    fn test_read_and_sort() {
+        let mut records = load();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[1].line, 3);
+
+        sort_by_timestamp(&mut records);
+        let order: Vec<_> = records.iter().map(|r| (r.source, r.line)).collect();
+        assert_eq!(order, [(0, 3), (1, 1), (0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn test_field_paths() {
+        let value = json!({"data": {"items": [{"name": "x"}], "n": 1}});
+        assert_eq!(field(&value, "data.items.0.name"), Some(&json!("x")));
+        assert_eq!(field(&value, "data.n"), Some(&json!(1)));
+        assert_eq!(field(&value, "data.items.1"), None);
+        assert_eq!(field(&value, "data.n.x"), None);
+    }
+
+    #[test]
+    fn test_filter() {
+        let records = load();
+        let keep = |filter: &Filter| records.iter().filter(|r| filter.matches(r)).count();
+
+        assert_eq!(keep(&Filter::default()), 4);
+        let checksums = Filter {
+            types: vec!["checksum".into()],
+            ..Filter::default()
+        };
+        assert_eq!(keep(&checksums), 2);
+        let errors = Filter {
+            types: vec!["error".into()],
+            ..Filter::default()
+        };
+        assert_eq!(keep(&errors), 1);
+
+        let since = Filter {
+            since: Some("2026-01-01T00:00:01Z".parse().unwrap()),
+            ..Filter::default()
+        };
+        assert_eq!(keep(&since), 2);
+        let window = Filter {
+            until: Some("2026-01-01T00:00:02Z".parse().unwrap()),
+            ..since
+        };
+        assert_eq!(keep(&window), 1);
+    }
+
+    #[test]
+    fn test_counter() {
+        let mut counter = Counter::new(vec!["type".into(), "data.type".into()]);
+        for record in load() {
+            counter.add(&record.value);
+        }
+        assert_eq!(
+            counter.results(),
+            [
+                ("type", "result", 3),
+                ("type", "error", 1),
+                ("data.type", "checksum", 2),
+                ("data.type", "csv_row", 1),
+                ("data.type", "null", 1),
+            ]
+        );
+        assert_eq!(counter.counts("type").get("error"), Some(&1));
+        assert!(counter.counts("missing").is_empty());
+    }
+}
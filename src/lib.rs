@@ -6,21 +6,85 @@
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 
-pub mod async_ops;
+pub mod audit;
+pub mod binary_info;
+pub mod checksum;
+pub mod cli;
+pub mod config;
+pub mod csv_ops;
 pub mod error;
 pub mod jsonl;
+pub mod jsonl_merge;
+pub mod limits;
+pub mod log_parse;
 pub mod memory;
 pub mod fs_utils;
+pub mod git_info;
+pub mod hash;
 pub mod simd_ops;
+pub mod snapshot;
+pub mod telemetry;
 pub mod ml_ops;
+pub mod ops_journal;
+pub mod policy;
+pub mod result_cache;
+pub mod sample;
+pub mod scratch;
+pub mod session;
+pub mod set_ops;
+pub mod text_format;
+pub mod watch;
+
+// Modules built on tokio's file APIs, which wasm targets lack
+#[cfg(not(target_family = "wasm"))]
+pub mod async_ops;
+#[cfg(not(target_family = "wasm"))]
+pub mod bench;
+#[cfg(not(target_family = "wasm"))]
+pub mod grep;
+#[cfg(not(target_family = "wasm"))]
+pub mod index;
+#[cfg(not(target_family = "wasm"))]
+pub mod replace;
+#[cfg(not(target_family = "wasm"))]
+pub mod tuning;
+
+// io_uring file API, separate from async_ops (optional, Linux only)
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+pub mod uring_ops;
 
 // Python bindings (optional)
 #[cfg(feature = "python")]
 pub mod python;
 
+// gRPC server (optional)
+#[cfg(feature = "grpc")]
+pub mod grpc;
+
+// HTTP server (optional)
+#[cfg(feature = "http")]
+pub mod http;
+
+// Remote files (optional)
+#[cfg(feature = "remote")]
+pub mod remote;
+
+// Archive members as paths (optional)
+#[cfg(feature = "archives")]
+pub mod archive;
+
+// Transparent decompression of gzip, zstd and xz files (optional)
+#[cfg(feature = "compression")]
+pub mod compress;
+
+// WebAssembly exports (optional)
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
 // Re-export commonly used types
+pub use config::Config;
 pub use error::{AiCoreutilsError, Result};
 pub use jsonl::{JsonlOutput, JsonlRecord};
 pub use memory::SafeMemoryAccess;
-pub use simd_ops::{SimdConfig, SimdPatternSearcher, SimdByteCounter, SimdTextProcessor, TextMetrics, SimdNewlineCounter, SimdMemoryOps, SimdHasher, SimdEntropyCalculator, SimdWhitespaceDetector, SimdCaseFolder, SimdUtf8Validator, SimdStringComparer, SimdMultiPatternSearcher};
+pub use simd_ops::{SimdConfig, SimdPatternSearcher, SimdByteCounter, SimdTextProcessor, TextMetrics, SimdNewlineCounter, NewlinePositions, SimdMemoryOps, SimdHasher, SimdEntropyCalculator, SimdWhitespaceDetector, SimdCaseFolder, SimdUtf8Validator, SimdStringComparer, SimdMultiPatternSearcher, SimdPrintableScanner, PrintableEncoding, PrintableRun};
 pub use ml_ops::{PatternDetector, FileClassifier, MlConfig, PatternType, ContentAnalysis, FileClassification};
@@ -0,0 +1,328 @@
+//! Resource limits
+//!
+//! Caps on how much one invocation reads and emits, set by `--max-bytes`,
+//! `--max-files` and `--max-records` or the `[limits]` config section. The
+//! library feeds the counters: whole-file reads go through [`read_file`],
+//! and every JSONL record through [`record`] (called by
+//! [`JsonlRecord::write_line`](crate::jsonl::JsonlRecord::write_line)).
+//!
+//! Once a limit is hit, the next record written is replaced by a
+//! `limit_exceeded` record, and every later read or record fails with
+//! [`AiCoreutilsError::LimitExceeded`]. Utilities propagate output errors,
+//! so they stop at their next record, after flushing what they have written.
+//!
+//! The `limit_exceeded` record carries a continuation token. Running the
+//! same command with `--resume TOKEN` ([`resume`]) passes over the records
+//! earlier pages wrote without writing them or counting what they read, so
+//! the output continues where the last page stopped. Tokens are tied to the
+//! command's arguments, other than the limits themselves.
+
+use crate::audit;
+use crate::error::{AiCoreutilsError, Result};
+use crate::jsonl::JsonlRecord;
+use crate::simd_ops::SimdHasher;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+const UNLIMITED: u64 = u64::MAX;
+
+static MAX_BYTES: AtomicU64 = AtomicU64::new(UNLIMITED);
+static MAX_FILES: AtomicU64 = AtomicU64::new(UNLIMITED);
+static MAX_RECORDS: AtomicU64 = AtomicU64::new(UNLIMITED);
+
+/// Files and bytes read so far
+static READ: Mutex<(u64, u64)> = Mutex::new((0, 0));
+/// Records seen so far, including those passed over by `--resume`
+static RECORDS: AtomicU64 = AtomicU64::new(0);
+/// Records written by earlier pages
+static SKIP: AtomicU64 = AtomicU64::new(0);
+
+/// The first limit hit, and whether its record has been written
+static EXCEEDED: OnceLock<(Limit, u64)> = OnceLock::new();
+static REPORTED: AtomicBool = AtomicBool::new(false);
+
+/// A resource limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    /// Bytes of file contents read
+    Bytes,
+    /// Files read
+    Files,
+    /// JSONL records written
+    Records,
+}
+
+impl Limit {
+    /// Name used in `limit_exceeded` records
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Limit::Bytes => "max_bytes",
+            Limit::Files => "max_files",
+            Limit::Records => "max_records",
+        }
+    }
+
+    fn max(self) -> &'static AtomicU64 {
+        match self {
+            Limit::Bytes => &MAX_BYTES,
+            Limit::Files => &MAX_FILES,
+            Limit::Records => &MAX_RECORDS,
+        }
+    }
+}
+
+impl fmt::Display for Limit {
+    /// The command-line flag, e.g. `--max-bytes`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "--{}", self.as_str().replace('_', "-"))
+    }
+}
+
+/// What to do with a JSONL record about to be written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Emit {
+    /// Write it
+    Write,
+    /// Write this `limit_exceeded` line instead
+    Replace(String),
+    /// Leave it out; an earlier page wrote it
+    Skip,
+}
+
+/// Set a limit for the process; `None` removes it
+pub fn set(limit: Limit, max: Option<u64>) {
+    limit.max().store(max.unwrap_or(UNLIMITED), Ordering::Relaxed);
+}
+
+/// The limit currently set, if any
+pub fn get(limit: Limit) -> Option<u64> {
+    match limit.max().load(Ordering::Relaxed) {
+        UNLIMITED => None,
+        max => Some(max),
+    }
+}
+
+/// Account for reading a whole file of `bytes` bytes
+///
+/// Call before the contents are used. Fails, leaving the counters as they
+/// were, if the file would take the process past `--max-files` or
+/// `--max-bytes`. Accepted reads are noted in the audit log.
+pub fn read_file(path: &Path, bytes: u64) -> Result<()> {
+    stopped()?;
+    if resuming() {
+        audit::read(path, bytes);
+        return Ok(());
+    }
+    {
+        let mut read = READ.lock().unwrap_or_else(|e| e.into_inner());
+        let (files, total) = (read.0 + 1, read.1.saturating_add(bytes));
+        if files > MAX_FILES.load(Ordering::Relaxed) {
+            return Err(exceed(Limit::Files));
+        }
+        if total > MAX_BYTES.load(Ordering::Relaxed) {
+            return Err(exceed(Limit::Bytes));
+        }
+        *read = (files, total);
+    }
+    audit::read(path, bytes);
+    Ok(())
+}
+
+/// Fail, as [`read_file`] would, if a file of `bytes` bytes cannot be read
+///
+/// Accounts for nothing. For readers that buffer a file before accounting
+/// for it, so one past `--max-files` or `--max-bytes` fails before it is
+/// read in.
+pub fn check_read(bytes: u64) -> Result<()> {
+    stopped()?;
+    if resuming() {
+        return Ok(());
+    }
+    let read = READ.lock().unwrap_or_else(|e| e.into_inner());
+    if read.0 + 1 > MAX_FILES.load(Ordering::Relaxed) {
+        return Err(exceed(Limit::Files));
+    }
+    if read.1.saturating_add(bytes) > MAX_BYTES.load(Ordering::Relaxed) {
+        return Err(exceed(Limit::Bytes));
+    }
+    Ok(())
+}
+
+/// Bytes that can still be read before `--max-bytes` is reached, if set
+pub fn bytes_left() -> Option<u64> {
+    if resuming() {
+        return None;
+    }
+    let max = get(Limit::Bytes)?;
+    let read = READ.lock().unwrap_or_else(|e| e.into_inner());
+    Some(max.saturating_sub(read.1))
+}
+
+/// Account for one JSONL record about to be written
+///
+/// Records an earlier page wrote are skipped. The first record after a
+/// limit was hit (including `--max-records` by this record) is replaced by
+/// a `limit_exceeded` line, and every record after that fails.
+pub fn record() -> Result<Emit> {
+    if EXCEEDED.get().is_some() {
+        // Reads hit the limit before this record was counted
+        return report(RECORDS.load(Ordering::Relaxed));
+    }
+    count()
+}
+
+/// Account for a record that was held back, as `--ordered` output is
+///
+/// Held records are counted when they are finally written, in their final
+/// order, so a read limit hit while they were held does not cut them short;
+/// [`finish_held`] reports it after the last one.
+pub fn record_held() -> Result<Emit> {
+    if REPORTED.load(Ordering::Relaxed) {
+        return stopped().map(|()| Emit::Skip);
+    }
+    count()
+}
+
+/// The `limit_exceeded` line owed after the last held record, if any
+pub fn finish_held() -> Result<Option<String>> {
+    if EXCEEDED.get().is_none() || REPORTED.load(Ordering::Relaxed) {
+        return Ok(None);
+    }
+    match report(RECORDS.load(Ordering::Relaxed))? {
+        Emit::Replace(line) => Ok(Some(line)),
+        _ => Ok(None),
+    }
+}
+
+/// Count one record against `--resume` and `--max-records`
+fn count() -> Result<Emit> {
+    let seen = RECORDS.fetch_add(1, Ordering::Relaxed);
+    let skip = SKIP.load(Ordering::Relaxed);
+    if seen < skip {
+        return Ok(Emit::Skip);
+    }
+    if seen - skip < MAX_RECORDS.load(Ordering::Relaxed) {
+        return Ok(Emit::Write);
+    }
+    exceed(Limit::Records);
+    report(seen)
+}
+
+/// The `limit_exceeded` line, continuing after `offset` records, the first
+/// time; an error after that
+fn report(offset: u64) -> Result<Emit> {
+    if REPORTED.swap(true, Ordering::Relaxed) {
+        return stopped().map(|()| Emit::Skip);
+    }
+    let &(limit, max) = EXCEEDED.get().expect("limit recorded before reporting");
+    let token = format!("{}.{:016x}", offset, fingerprint(std::env::args_os()));
+    JsonlRecord::limit_exceeded(limit, max, token).to_jsonl().map(Emit::Replace)
+}
+
+/// Fail if a limit has been hit
+///
+/// For records that are written without being counted, such as progress.
+pub fn check() -> Result<()> {
+    stopped()
+}
+
+/// Continue from a `limit_exceeded` record's continuation token
+///
+/// Fails if the token is malformed or was issued for different arguments.
+pub fn resume(token: &str) -> Result<()> {
+    let invalid = |reason: &str| AiCoreutilsError::InvalidInput(format!("resume token {}: {}", token, reason));
+    let (offset, hash) = token.split_once('.').ok_or_else(|| invalid("malformed"))?;
+    let offset: u64 = offset.parse().map_err(|_| invalid("malformed"))?;
+    let hash = u64::from_str_radix(hash, 16).map_err(|_| invalid("malformed"))?;
+    if hash != fingerprint(std::env::args_os()) {
+        return Err(invalid("issued for different arguments"));
+    }
+
+    SKIP.store(offset, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Whether `--resume` is still passing over records an earlier page wrote
+fn resuming() -> bool {
+    RECORDS.load(Ordering::Relaxed) < SKIP.load(Ordering::Relaxed)
+}
+
+/// Hash of a command line, leaving out the limits and `--resume`
+///
+/// Pages of one command can use different limits, so these flags and their
+/// values do not change the fingerprint.
+fn fingerprint(args: impl IntoIterator<Item = OsString>) -> u64 {
+    const FLAGS: [&str; 4] = ["--max-bytes", "--max-files", "--max-records", "--resume"];
+
+    let mut args = args.into_iter();
+    let mut kept = Vec::new();
+    if let Some(arg0) = args.next() {
+        // The tool, not where it was run from
+        kept.extend(Path::new(&arg0).file_name().unwrap_or(&arg0).as_encoded_bytes());
+        kept.push(0);
+    }
+    while let Some(arg) = args.next() {
+        let text = arg.to_string_lossy();
+        if FLAGS.contains(&text.as_ref()) {
+            args.next();
+            continue;
+        }
+        if FLAGS.iter().any(|flag| text.starts_with(&format!("{}=", flag))) {
+            continue;
+        }
+        kept.extend(arg.as_encoded_bytes());
+        kept.push(0);
+    }
+    SimdHasher::new().xxh3(&kept)
+}
+
+/// Fail if a limit has been hit
+fn stopped() -> Result<()> {
+    match EXCEEDED.get() {
+        Some(&(limit, max)) => Err(AiCoreutilsError::LimitExceeded { limit, max }),
+        None => Ok(()),
+    }
+}
+
+/// Record `limit` as hit, unless another one was first
+fn exceed(limit: Limit) -> AiCoreutilsError {
+    let max = limit.max().load(Ordering::Relaxed);
+    let &(limit, max) = EXCEEDED.get_or_init(|| (limit, max));
+    AiCoreutilsError::LimitExceeded { limit, max }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_names() {
+        assert_eq!(Limit::Bytes.as_str(), "max_bytes");
+        assert_eq!(Limit::Records.to_string(), "--max-records");
+    }
+
+    #[test]
+    fn test_unlimited_by_default() {
+        // Counters are process-wide, so only the defaults are checked here;
+        // hitting a limit and resuming are covered by the integration tests
+        assert_eq!(get(Limit::Files), None);
+        assert_eq!(bytes_left(), None);
+        assert!(check_read(1 << 40).is_ok());
+        assert!(read_file(Path::new("a.txt"), 1 << 40).is_ok());
+        assert_eq!(record().unwrap(), Emit::Write);
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_limits() {
+        let fingerprint = |args: &[&str]| fingerprint(args.iter().map(OsString::from));
+        let base = fingerprint(&["/usr/bin/ai-grep", "-r", "TODO", "src"]);
+
+        assert_eq!(base, fingerprint(&["ai-grep", "-r", "--max-records", "50", "TODO", "src"]));
+        assert_eq!(base, fingerprint(&["ai-grep", "-r", "TODO", "--resume=3.00ff", "src"]));
+        assert_ne!(base, fingerprint(&["ai-grep", "-r", "TODO", "tests"]));
+        assert_ne!(base, fingerprint(&["ai-cat", "-r", "TODO", "src"]));
+    }
+}
@@ -0,0 +1,742 @@
+//! Log parsing for ai-logparse
+//!
+//! [`LogParser`] turns raw log lines into [`LogEntry`] records with a
+//! normalized timestamp, level and message plus format-specific fields.
+//! Built-in formats:
+//!
+//! - syslog, both RFC 3164 (`Jan  2 03:04:05 host app[pid]: msg`) and
+//!   RFC 5424 (`<34>1 2026-01-02T03:04:05Z host app pid msgid - msg`)
+//! - nginx/Apache access logs in common and combined format
+//! - JSON lines, with the usual names for time, level and message fields
+//! - Python, Java, Rust panic and Node stack traces, grouped into one entry
+//! - generic application logs that start with a timestamp and/or a level
+//!
+//! In [`LogFormat::Auto`] mode every line is tried against each format, so
+//! logs that interleave formats (an app log with embedded tracebacks) parse
+//! without configuration. Lines no format recognizes are returned with
+//! format `unknown` and the line as the message.
+//!
+//! Timestamps are normalized to ISO 8601. Times without a zone are kept
+//! without one rather than guessed; RFC 3164 syslog times have no year and
+//! are placed in the most recent year that does not put them in the future.
+
+use crate::error::{AiCoreutilsError, Result};
+use crate::ml_ops::timestamp::{common_log_timestamp, epoch_timestamp, syslog_3164_timestamp};
+use crate::ml_ops::{PatternDetector, PatternType, StackTraceDetector};
+use chrono::Utc;
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+pub use crate::ml_ops::timestamp::normalize_timestamp;
+
+/// Log formats that can be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Detect the format of each line
+    Auto,
+    /// RFC 3164 or RFC 5424 syslog
+    Syslog,
+    /// nginx/Apache common or combined access log
+    Combined,
+    /// One JSON object per line
+    Json,
+    /// Python tracebacks and Java stack traces
+    Traceback,
+    /// Lines starting with a timestamp and/or a level
+    Generic,
+}
+
+impl LogFormat {
+    /// Name used in output records
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogFormat::Auto => "auto",
+            LogFormat::Syslog => "syslog",
+            LogFormat::Combined => "combined",
+            LogFormat::Json => "json",
+            LogFormat::Traceback => "traceback",
+            LogFormat::Generic => "generic",
+        }
+    }
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = AiCoreutilsError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "auto" => Ok(LogFormat::Auto),
+            "syslog" => Ok(LogFormat::Syslog),
+            "combined" | "common" | "nginx" | "apache" => Ok(LogFormat::Combined),
+            "json" => Ok(LogFormat::Json),
+            "traceback" | "stacktrace" => Ok(LogFormat::Traceback),
+            "generic" => Ok(LogFormat::Generic),
+            _ => Err(AiCoreutilsError::InvalidInput(format!(
+                "Unknown log format: {} (expected auto, syslog, combined, json, traceback or generic)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Severity of an entry, in increasing order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Finest-grained tracing
+    Trace,
+    /// Debugging detail
+    Debug,
+    /// Normal operation
+    Info,
+    /// Normal but significant
+    Notice,
+    /// Something unexpected that was handled
+    Warning,
+    /// A failed operation
+    Error,
+    /// Critical, alert, emergency, fatal and panic
+    Critical,
+}
+
+impl Level {
+    /// Parse a level name as used by common loggers (any case)
+    pub fn parse(name: &str) -> Option<Level> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "trace" | "finest" | "finer" => Level::Trace,
+            "debug" | "fine" | "dbg" => Level::Debug,
+            "info" | "information" | "informational" | "config" => Level::Info,
+            "notice" => Level::Notice,
+            "warn" | "warning" => Level::Warning,
+            "error" | "err" | "severe" => Level::Error,
+            "critical" | "crit" | "alert" | "emerg" | "emergency" | "fatal" | "panic" => Level::Critical,
+            _ => return None,
+        })
+    }
+
+    /// Level for a syslog severity (0 = emergency ... 7 = debug)
+    pub fn from_syslog_severity(severity: u8) -> Level {
+        match severity {
+            0..=2 => Level::Critical,
+            3 => Level::Error,
+            4 => Level::Warning,
+            5 => Level::Notice,
+            6 => Level::Info,
+            _ => Level::Debug,
+        }
+    }
+
+    /// Level for a numeric bunyan/pino level (10 = trace ... 60 = fatal)
+    fn from_number(level: u64) -> Option<Level> {
+        Some(match level {
+            10 => Level::Trace,
+            20 => Level::Debug,
+            30 => Level::Info,
+            40 => Level::Warning,
+            50 => Level::Error,
+            60 => Level::Critical,
+            _ => return None,
+        })
+    }
+
+    /// Name used in output records
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Trace => "trace",
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Notice => "notice",
+            Level::Warning => "warning",
+            Level::Error => "error",
+            Level::Critical => "critical",
+        }
+    }
+}
+
+/// One parsed log entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    /// First line of the entry (1-based)
+    pub line: u64,
+    /// Last line of the entry; differs from `line` for multi-line entries
+    pub end_line: u64,
+    /// Format that recognized the entry, or `None` if none did
+    pub format: Option<LogFormat>,
+    /// Timestamp in ISO 8601
+    pub timestamp: Option<String>,
+    /// Normalized level
+    pub level: Option<Level>,
+    /// Message text
+    pub message: String,
+    /// Format-specific fields
+    pub fields: Map<String, Value>,
+}
+
+impl LogEntry {
+    fn new(line: u64, format: LogFormat, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            end_line: line,
+            format: Some(format),
+            timestamp: None,
+            level: None,
+            message: message.into(),
+            fields: Map::new(),
+        }
+    }
+
+    /// Entry as the `data` of a `log_entry` result record
+    pub fn to_json(&self) -> Value {
+        let mut data = serde_json::json!({
+            "type": "log_entry",
+            "line": self.line,
+            "format": self.format.map_or("unknown", |f| f.as_str()),
+            "timestamp": self.timestamp,
+            "level": self.level.map(|l| l.as_str()),
+            "message": self.message,
+        });
+        if self.end_line != self.line {
+            data["end_line"] = self.end_line.into();
+        }
+        if !self.fields.is_empty() {
+            data["fields"] = Value::Object(self.fields.clone());
+        }
+        data
+    }
+}
+
+/// Field names tried, in order, for the parts of a JSON log line
+const JSON_TIME_KEYS: &[&str] = &["timestamp", "@timestamp", "time", "ts", "datetime", "date", "asctime"];
+const JSON_LEVEL_KEYS: &[&str] = &["level", "severity", "levelname", "lvl", "log.level", "loglevel"];
+const JSON_MESSAGE_KEYS: &[&str] = &["message", "msg", "event", "log", "text"];
+
+/// Most lines a single stack trace may span
+const MAX_TRACE_LINES: usize = 1000;
+
+/// Level names recognized in generic lines
+const LEVEL_NAMES: &str = "TRACE|DEBUG|INFO|NOTICE|WARN|WARNING|ERROR|ERR|SEVERE|CRIT|CRITICAL|ALERT|EMERG|FATAL|PANIC";
+
+/// Compiled parsers for every format
+pub struct LogParser {
+    format: LogFormat,
+    syslog_3164: Regex,
+    syslog_5424: Regex,
+    combined: Regex,
+    generic: Regex,
+    level_prefix: Regex,
+    traces: StackTraceDetector,
+    detector: Option<PatternDetector>,
+}
+
+impl LogParser {
+    /// Parser for `format`
+    pub fn new(format: LogFormat) -> Result<Self> {
+        let compile = |pattern: &str| {
+            Regex::new(pattern).map_err(|e| AiCoreutilsError::InvalidInput(format!("Invalid log regex: {}", e)))
+        };
+        Ok(Self {
+            format,
+            syslog_3164: compile(
+                r"^(?:<(?P<pri>\d{1,3})>)?(?P<ts>[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}) (?P<host>\S+) (?P<app>[^\s\[:]+)(?:\[(?P<pid>\d+)\])?: ?(?P<msg>.*)$",
+            )?,
+            syslog_5424: compile(
+                r"^<(?P<pri>\d{1,3})>1 (?P<ts>\S+) (?P<host>\S+) (?P<app>\S+) (?P<pid>\S+) (?P<msgid>\S+) (?P<sd>-|(?:\[(?:[^\]\\]|\\.)*\])+) ?(?P<msg>.*)$",
+            )?,
+            combined: compile(
+                r#"^(?P<client>\S+) (?P<ident>\S+) (?P<user>\S+) \[(?P<ts>[^\]]+)\] "(?P<request>(?:[^"\\]|\\.)*)" (?P<status>\d{3}) (?P<bytes>\d+|-)(?: "(?P<referer>(?:[^"\\]|\\.)*)" "(?P<agent>(?:[^"\\]|\\.)*)")?"#,
+            )?,
+            generic: compile(&format!(
+                r"^\[?(?P<ts>\d{{4}}-\d{{2}}-\d{{2}}[T ]\d{{2}}:\d{{2}}:\d{{2}}(?:[.,]\d+)?(?:Z|[+-]\d{{2}}:?\d{{2}})?)\]?(?:\s+\[?(?P<level>(?i:{}))\]?:?)?\s*(?P<msg>.*)$",
+                LEVEL_NAMES
+            ))?,
+            level_prefix: compile(&format!(r"^\[?(?P<level>{})\]?(?::|\s)\s*(?P<msg>.*)$", LEVEL_NAMES))?,
+            traces: StackTraceDetector::new()?,
+            detector: None,
+        })
+    }
+
+    /// Also extract entities (URLs, IPs, emails, UUIDs, paths) from messages
+    pub fn with_entities(mut self, enabled: bool) -> Result<Self> {
+        self.detector = match enabled {
+            true => Some(PatternDetector::new()?),
+            false => None,
+        };
+        Ok(self)
+    }
+
+    /// Format being parsed
+    pub fn format(&self) -> LogFormat {
+        self.format
+    }
+
+    /// Parse every entry of `data`, which is split into lines
+    ///
+    /// Lines are decoded lossily, so invalid UTF-8 never stops parsing.
+    pub fn entries<'p, 'd>(&'p self, data: &'d [u8]) -> Entries<'p, 'd> {
+        Entries {
+            parser: self,
+            lines: Lines::new(data),
+        }
+    }
+
+    fn allows(&self, format: LogFormat) -> bool {
+        self.format == LogFormat::Auto || self.format == format
+    }
+
+    /// Parse one single-line entry
+    fn parse_line(&self, line: &str, number: u64) -> Option<LogEntry> {
+        let trimmed = line.trim_start();
+        if self.allows(LogFormat::Json) && trimmed.starts_with('{') {
+            if let Some(entry) = self.parse_json(trimmed, number) {
+                return Some(entry);
+            }
+        }
+        if self.allows(LogFormat::Syslog) {
+            if let Some(entry) = self.parse_syslog(line, number) {
+                return Some(entry);
+            }
+        }
+        if self.allows(LogFormat::Combined) {
+            if let Some(entry) = self.parse_combined(line, number) {
+                return Some(entry);
+            }
+        }
+        if self.allows(LogFormat::Generic) {
+            return self.parse_generic(line, number);
+        }
+        None
+    }
+
+    fn parse_syslog(&self, line: &str, number: u64) -> Option<LogEntry> {
+        let (caps, timestamp) = if let Some(caps) = self.syslog_5424.captures(line) {
+            let timestamp = normalize_timestamp(&caps["ts"]);
+            (caps, timestamp)
+        } else {
+            let caps = self.syslog_3164.captures(line)?;
+            let timestamp = syslog_3164_timestamp(&caps["ts"], Utc::now());
+            (caps, timestamp)
+        };
+
+        let mut entry = LogEntry::new(number, LogFormat::Syslog, &caps["msg"]);
+        entry.timestamp = timestamp;
+        let nil = |name: &str| caps.name(name).map(|m| m.as_str()).filter(|v| *v != "-");
+        if let Some(pri) = caps.name("pri").and_then(|p| p.as_str().parse::<u8>().ok()) {
+            entry.level = Some(Level::from_syslog_severity(pri % 8));
+            entry.fields.insert("facility".into(), (pri / 8).into());
+        }
+        for (name, key) in [("host", "host"), ("app", "app"), ("msgid", "msgid"), ("sd", "structured_data")] {
+            if let Some(value) = nil(name) {
+                entry.fields.insert(key.into(), value.into());
+            }
+        }
+        if let Some(pid) = nil("pid") {
+            let value = pid.parse::<u64>().map_or_else(|_| Value::from(pid), Value::from);
+            entry.fields.insert("pid".into(), value);
+        }
+        Some(entry)
+    }
+
+    fn parse_combined(&self, line: &str, number: u64) -> Option<LogEntry> {
+        let caps = self.combined.captures(line)?;
+        let status: u16 = caps["status"].parse().ok()?;
+        let mut entry = LogEntry::new(number, LogFormat::Combined, &caps["request"]);
+        entry.timestamp = common_log_timestamp(&caps["ts"]);
+        entry.level = Some(match status {
+            500.. => Level::Error,
+            400..=499 => Level::Warning,
+            _ => Level::Info,
+        });
+
+        let fields = &mut entry.fields;
+        fields.insert("client".into(), caps["client"].into());
+        if &caps["user"] != "-" {
+            fields.insert("user".into(), caps["user"].into());
+        }
+        let mut request = caps["request"].splitn(3, ' ');
+        if let (Some(method), Some(path)) = (request.next(), request.next()) {
+            fields.insert("method".into(), method.into());
+            fields.insert("path".into(), path.into());
+            if let Some(protocol) = request.next() {
+                fields.insert("protocol".into(), protocol.into());
+            }
+        }
+        fields.insert("status".into(), status.into());
+        fields.insert("bytes".into(), caps["bytes"].parse::<u64>().ok().into());
+        for (name, key) in [("referer", "referer"), ("agent", "user_agent")] {
+            if let Some(value) = caps.name(name).map(|m| m.as_str()).filter(|v| *v != "-") {
+                fields.insert(key.into(), value.into());
+            }
+        }
+        Some(entry)
+    }
+
+    fn parse_json(&self, line: &str, number: u64) -> Option<LogEntry> {
+        let Value::Object(mut object) = serde_json::from_str::<Value>(line).ok()? else {
+            return None;
+        };
+        let mut take = |keys: &[&str]| keys.iter().find_map(|key| object.remove(*key));
+
+        let timestamp = take(JSON_TIME_KEYS);
+        let level = take(JSON_LEVEL_KEYS);
+        let message = match take(JSON_MESSAGE_KEYS) {
+            Some(Value::String(message)) => message,
+            Some(other) => other.to_string(),
+            None => String::new(),
+        };
+
+        let mut entry = LogEntry::new(number, LogFormat::Json, message);
+        entry.timestamp = match timestamp {
+            Some(Value::String(text)) => Some(normalize_timestamp(&text).unwrap_or(text)),
+            Some(Value::Number(n)) => n.as_f64().and_then(epoch_timestamp),
+            _ => None,
+        };
+        entry.level = match level {
+            Some(Value::String(name)) => Level::parse(&name),
+            Some(Value::Number(n)) => n.as_u64().and_then(Level::from_number),
+            _ => None,
+        };
+        entry.fields = object;
+        Some(entry)
+    }
+
+    fn parse_generic(&self, line: &str, number: u64) -> Option<LogEntry> {
+        if let Some(caps) = self.generic.captures(line) {
+            let mut entry = LogEntry::new(number, LogFormat::Generic, &caps["msg"]);
+            entry.timestamp = normalize_timestamp(&caps["ts"]);
+            entry.level = caps.name("level").and_then(|l| Level::parse(l.as_str()));
+            return Some(entry);
+        }
+        let caps = self.level_prefix.captures(line)?;
+        let mut entry = LogEntry::new(number, LogFormat::Generic, &caps["msg"]);
+        entry.level = Level::parse(&caps["level"]);
+        Some(entry)
+    }
+
+    /// Extract the stack trace that starts at `first`, consuming its lines
+    fn parse_trace(&self, first: &str, number: u64, lines: &mut Lines<'_>) -> Option<LogEntry> {
+        let mut window = vec![first];
+        window.extend(lines.window(MAX_TRACE_LINES - 1));
+        let trace = self.traces.parse(&window)?;
+        for _ in 1..trace.line_count {
+            lines.next();
+        }
+
+        let message = match trace.message.is_empty() {
+            true => trace.exception.clone(),
+            false => format!("{}: {}", trace.exception, trace.message),
+        };
+        let mut entry = LogEntry::new(number, LogFormat::Traceback, message);
+        entry.end_line = number + trace.line_count as u64 - 1;
+        entry.level = Some(Level::Error);
+        let fields = &mut entry.fields;
+        fields.insert("language".into(), trace.language.as_str().into());
+        fields.insert("exception".into(), trace.exception.into());
+        fields.insert("exception_message".into(), trace.message.into());
+        fields.insert("frames".into(), serde_json::to_value(trace.frames).unwrap_or_default());
+        if !trace.causes.is_empty() {
+            fields.insert("causes".into(), trace.causes.into());
+        }
+        if let Some(thread) = trace.thread {
+            fields.insert("thread".into(), thread.into());
+        }
+        Some(entry)
+    }
+
+    fn add_entities(&self, entry: &mut LogEntry) {
+        let Some(detector) = &self.detector else {
+            return;
+        };
+        let mut entities: Map<String, Value> = Map::new();
+        for found in detector.detect_patterns(&entry.message) {
+            let kind = match found.pattern_type {
+                PatternType::Url => "urls",
+                PatternType::IpAddress => "ips",
+                PatternType::Email => "emails",
+                PatternType::Uuid => "uuids",
+                PatternType::FilePath => "paths",
+                _ => continue,
+            };
+            let values = entities.entry(kind).or_insert_with(|| Value::Array(Vec::new()));
+            let values = values.as_array_mut().expect("entity lists are arrays");
+            let text = Value::from(found.matched_text);
+            if !values.contains(&text) {
+                values.push(text);
+            }
+        }
+        if !entities.is_empty() {
+            entry.fields.insert("entities".into(), Value::Object(entities));
+        }
+    }
+}
+
+/// Lines of a buffer with lookahead, decoded lossily and numbered from 1
+struct Lines<'d> {
+    data: &'d [u8],
+    pos: usize,
+    number: u64,
+    ahead: VecDeque<(u64, Cow<'d, str>)>,
+}
+
+impl<'d> Lines<'d> {
+    fn new(data: &'d [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            number: 0,
+            ahead: VecDeque::new(),
+        }
+    }
+
+    fn read(&mut self) -> Option<(u64, Cow<'d, str>)> {
+        if self.pos >= self.data.len() {
+            return None;
+        }
+        let rest = &self.data[self.pos..];
+        let end = rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+        self.pos += end + 1;
+        self.number += 1;
+        let line = &rest[..end];
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        Some((self.number, String::from_utf8_lossy(line)))
+    }
+
+    /// The line `n` lines ahead of the next one
+    fn peek(&mut self, n: usize) -> Option<&(u64, Cow<'d, str>)> {
+        while self.ahead.len() <= n {
+            let line = self.read()?;
+            self.ahead.push_back(line);
+        }
+        self.ahead.get(n)
+    }
+
+    /// Up to `n` of the next lines, without consuming them
+    fn window(&mut self, n: usize) -> Vec<&str> {
+        if n > 0 {
+            self.peek(n - 1);
+        }
+        self.ahead.iter().take(n).map(|(_, line)| line.as_ref()).collect()
+    }
+}
+
+impl<'d> Iterator for Lines<'d> {
+    type Item = (u64, Cow<'d, str>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.ahead.pop_front().or_else(|| self.read())
+    }
+}
+
+/// Iterator over the entries of a buffer, from [`LogParser::entries`]
+pub struct Entries<'p, 'd> {
+    parser: &'p LogParser,
+    lines: Lines<'d>,
+}
+
+impl Iterator for Entries<'_, '_> {
+    type Item = LogEntry;
+
+    fn next(&mut self) -> Option<LogEntry> {
+        let parser = self.parser;
+        loop {
+            let (number, line) = self.lines.next()?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let next = self.lines.peek(0).map(|(_, next)| next.as_ref());
+            let trace = match parser.allows(LogFormat::Traceback) && parser.traces.starts_trace(&line, next) {
+                true => parser.parse_trace(&line, number, &mut self.lines),
+                false => None,
+            };
+            let mut entry = if let Some(entry) = trace {
+                entry
+            } else if let Some(mut entry) = parser.parse_line(&line, number) {
+                if entry.format == Some(LogFormat::Generic) {
+                    // Indented lines continue a generic entry's message
+                    while let Some((n, next)) = self.lines.peek(0) {
+                        if !(next.starts_with(' ') || next.starts_with('\t')) || next.trim().is_empty() {
+                            break;
+                        }
+                        entry.message.push('\n');
+                        entry.message.push_str(next);
+                        entry.end_line = *n;
+                        self.lines.next();
+                    }
+                }
+                entry
+            } else {
+                LogEntry {
+                    format: None,
+                    ..LogEntry::new(number, LogFormat::Auto, line.as_ref())
+                }
+            };
+            parser.add_entities(&mut entry);
+            return Some(entry);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(format: LogFormat, text: &str) -> Vec<LogEntry> {
+        LogParser::new(format).unwrap().entries(text.as_bytes()).collect()
+    }
+
+    #[test]
+    fn test_syslog() {
+        let entries = parse(
+            LogFormat::Syslog,
+            "<34>1 2026-01-02T03:04:05.123Z web01 sshd 4242 ID47 - Failed password\nJan  2 03:04:05 web01 cron[99]: job done\n",
+        );
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].timestamp.as_deref(), Some("2026-01-02T03:04:05.123+00:00"));
+        assert_eq!(entries[0].level, Some(Level::Critical));
+        assert_eq!(entries[0].fields["facility"], 4);
+        assert_eq!(entries[0].fields["pid"], 4242);
+        assert_eq!(entries[0].message, "Failed password");
+        assert_eq!(entries[1].fields["app"], "cron");
+        assert_eq!(entries[1].message, "job done");
+        assert!(entries[1].timestamp.as_deref().unwrap().ends_with("-01-02T03:04:05"));
+    }
+
+    #[test]
+    fn test_combined() {
+        let line = r#"10.0.0.1 - alice [10/Oct/2025:13:55:36 -0700] "GET /api/items?id=1 HTTP/1.1" 503 512 "-" "curl/8.0""#;
+        let entry = &parse(LogFormat::Combined, line)[0];
+        assert_eq!(entry.timestamp.as_deref(), Some("2025-10-10T13:55:36-07:00"));
+        assert_eq!(entry.level, Some(Level::Error));
+        assert_eq!(entry.fields["method"], "GET");
+        assert_eq!(entry.fields["path"], "/api/items?id=1");
+        assert_eq!(entry.fields["status"], 503);
+        assert_eq!(entry.fields["user"], "alice");
+        assert_eq!(entry.fields["user_agent"], "curl/8.0");
+        assert!(entry.fields.get("referer").is_none());
+
+        let common = parse(LogFormat::Combined, r#"::1 - - [10/Oct/2025:13:55:36 +0000] "POST /x HTTP/1.0" 404 -"#);
+        assert_eq!(common[0].level, Some(Level::Warning));
+        assert_eq!(common[0].fields["bytes"], Value::Null);
+    }
+
+    #[test]
+    fn test_json() {
+        let entries = parse(
+            LogFormat::Json,
+            "{\"ts\":1767225600.5,\"level\":50,\"msg\":\"boom\",\"req\":7}\n{\"@timestamp\":\"2026-01-01 00:00:00\",\"severity\":\"WARN\",\"message\":\"slow\"}\nnot json",
+        );
+        assert_eq!(entries[0].timestamp.as_deref(), Some("2026-01-01T00:00:00.500+00:00"));
+        assert_eq!(entries[0].level, Some(Level::Error));
+        assert_eq!(entries[0].message, "boom");
+        assert_eq!(entries[0].fields["req"], 7);
+        assert!(!entries[0].fields.contains_key("msg"));
+        assert_eq!(entries[1].timestamp.as_deref(), Some("2026-01-01T00:00:00"));
+        assert_eq!(entries[1].level, Some(Level::Warning));
+        assert_eq!(entries[2].format, None);
+    }
+
+    #[test]
+    fn test_generic_and_python_traceback() {
+        let text = "\
+2026-01-01 12:00:00,250 ERROR worker: job failed
+  retrying later
+Traceback (most recent call last):
+  File \"app.py\", line 10, in <module>
+    main()
+  File \"app.py\", line 6, in main
+    raise ValueError(\"bad input\")
+ValueError: bad input
+WARNING: disk almost full
+plain text
+";
+        let entries = parse(LogFormat::Auto, text);
+        assert_eq!(entries.len(), 4);
+
+        assert_eq!(entries[0].format, Some(LogFormat::Generic));
+        assert_eq!(entries[0].timestamp.as_deref(), Some("2026-01-01T12:00:00.250"));
+        assert_eq!(entries[0].level, Some(Level::Error));
+        assert_eq!(entries[0].message, "worker: job failed\n  retrying later");
+        assert_eq!(entries[0].end_line, 2);
+
+        let trace = &entries[1];
+        assert_eq!((trace.line, trace.end_line), (3, 8));
+        assert_eq!(trace.fields["exception"], "ValueError");
+        assert_eq!(trace.fields["exception_message"], "bad input");
+        assert_eq!(trace.fields["frames"][1]["function"], "main");
+        assert_eq!(trace.fields["frames"][1]["line"], 6);
+
+        assert_eq!(entries[2].level, Some(Level::Warning));
+        assert_eq!(entries[2].message, "disk almost full");
+        assert_eq!(entries[3].format, None);
+        assert_eq!(entries[3].message, "plain text");
+    }
+
+    #[test]
+    fn test_chained_python_traceback() {
+        let text = "\
+Traceback (most recent call last):
+  File \"a.py\", line 2, in <module>
+KeyError: 'x'
+
+During handling of the above exception, another exception occurred:
+
+Traceback (most recent call last):
+  File \"a.py\", line 4, in <module>
+RuntimeError: lookup failed
+
+next line
+";
+        let entries = parse(LogFormat::Auto, text);
+        assert_eq!(entries.len(), 2);
+        let trace = &entries[0];
+        assert_eq!(trace.end_line, 9);
+        assert_eq!(trace.fields["exception"], "RuntimeError");
+        assert_eq!(trace.fields["causes"], serde_json::json!(["KeyError: 'x'"]));
+        assert_eq!(trace.fields["frames"][1]["line"], 4);
+    }
+
+    #[test]
+    fn test_java_trace() {
+        let text = "\
+Exception in thread \"main\" java.lang.IllegalStateException: not ready
+\tat com.example.App.start(App.java:42)
+\tat com.example.App.main(App.java:10)
+Caused by: java.io.IOException: closed
+\tat com.example.Io.read(Io.java:7)
+\t... 2 more
+after
+";
+        let entries = parse(LogFormat::Auto, text);
+        assert_eq!(entries.len(), 2);
+        let trace = &entries[0];
+        assert_eq!(trace.end_line, 6);
+        assert_eq!(trace.fields["exception"], "java.lang.IllegalStateException");
+        assert_eq!(trace.fields["thread"], "main");
+        assert_eq!(trace.fields["frames"].as_array().unwrap().len(), 3);
+        assert_eq!(trace.fields["frames"][0]["file"], "App.java");
+        assert_eq!(trace.fields["frames"][0]["line"], 42);
+        assert_eq!(trace.fields["causes"][0], "java.io.IOException: closed");
+    }
+
+    #[test]
+    fn test_entities() {
+        let parser = LogParser::new(LogFormat::Auto).unwrap().with_entities(true).unwrap();
+        let entry = parser
+            .entries(b"ERROR fetch https://example.com/a failed from 10.1.2.3 twice 10.1.2.3")
+            .next()
+            .unwrap();
+        let entities = &entry.fields["entities"];
+        assert_eq!(entities["ips"], serde_json::json!(["10.1.2.3"]));
+        assert!(entities["urls"][0].as_str().unwrap().starts_with("https://example.com"));
+    }
+
+    #[test]
+    fn test_levels() {
+        assert!(Level::Warning > Level::Info);
+        assert_eq!(Level::parse("Fatal"), Some(Level::Critical));
+        assert_eq!(Level::parse("verbose"), None);
+        assert_eq!("nginx".parse::<LogFormat>().unwrap(), LogFormat::Combined);
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+}
@@ -3,18 +3,311 @@
 //! Provides safe memory access with pointer operations for large files.
 
 use crate::error::{AiCoreutilsError, Result};
-use crate::simd_ops::{SimdByteCounter, SimdPatternSearcher, SimdTextProcessor};
-use memmap2::Mmap;
+#[cfg(feature = "compression")]
+use crate::compress::Compression;
+use crate::fs_utils::{self, CachePolicy, DropCacheOnClose};
+use crate::simd_ops::{SimdByteCounter, SimdNewlineCounter, SimdPatternSearcher, SimdTextProcessor, TextMetrics};
+use memmap2::{Mmap, MmapOptions};
 use std::fs::File;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
 
-/// Safe memory access handler for files
+pub mod line_index;
+pub mod reverse;
+
+pub use line_index::LineIndex;
+pub use reverse::ReverseReader;
+
+/// How a mapped file will be read, advised to the kernel with `madvise`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AccessPattern {
+    /// No advice: the kernel's default read-ahead
+    #[default]
+    Normal,
+    /// Front to back, as a search does: aggressive read-ahead
+    Sequential,
+    /// Jumps to offsets found elsewhere, as index lookups do: no read-ahead
+    Random,
+}
+
+impl AccessPattern {
+    /// Name used on the command line and in JSONL output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccessPattern::Normal => "normal",
+            AccessPattern::Sequential => "sequential",
+            AccessPattern::Random => "random",
+        }
+    }
+}
+
+impl std::str::FromStr for AccessPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "normal" => Ok(AccessPattern::Normal),
+            "sequential" => Ok(AccessPattern::Sequential),
+            "random" => Ok(AccessPattern::Random),
+            _ => Err(format!("Invalid access pattern: {} (expected normal, sequential or random)", s)),
+        }
+    }
+}
+
+/// Hints for mapping a file with [`SafeMemoryAccess::with_options`]
+///
+/// Each utility picks the access pattern that fits how it reads: ai-grep
+/// maps files `Sequential`, ai-tail `Random`. Hints are advice; those the
+/// platform or kernel turns down are left out of
+/// [`SafeMemoryAccess::map_options`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MapOptions {
+    /// Page cache policy, as for [`SafeMemoryAccess::with_cache_policy`]
+    pub cache_policy: CachePolicy,
+    /// `MADV_SEQUENTIAL` or `MADV_RANDOM`
+    pub access: AccessPattern,
+    /// `MADV_WILLNEED`: start reading the whole file in the background
+    pub will_need: bool,
+    /// `MAP_POPULATE` (Linux): read the whole file in before mapping returns
+    pub populate: bool,
+    /// `MADV_HUGEPAGE` (Linux): back the mapping with transparent huge pages
+    pub huge_pages: bool,
+    /// Read files up to [`GUARD_COPY_LIMIT`] into memory instead of mapping
+    /// them, so they cannot change under the reader
+    pub guarded: bool,
+    /// Hold a shared advisory lock on the file while it is mapped
+    pub lock: bool,
+}
+
+impl MapOptions {
+    /// No hints
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the page cache policy
+    pub fn cache_policy(mut self, policy: CachePolicy) -> Self {
+        self.cache_policy = policy;
+        self
+    }
+
+    /// Set the access pattern
+    pub fn access(mut self, access: AccessPattern) -> Self {
+        self.access = access;
+        self
+    }
+
+    /// Ask for the file to be read ahead in full
+    pub fn will_need(mut self, will_need: bool) -> Self {
+        self.will_need = will_need;
+        self
+    }
+
+    /// Fault the whole file in when it is mapped
+    pub fn populate(mut self, populate: bool) -> Self {
+        self.populate = populate;
+        self
+    }
+
+    /// Ask for transparent huge pages
+    pub fn huge_pages(mut self, huge_pages: bool) -> Self {
+        self.huge_pages = huge_pages;
+        self
+    }
+
+    /// Copy small files into memory rather than mapping them
+    pub fn guarded(mut self, guarded: bool) -> Self {
+        self.guarded = guarded;
+        self
+    }
+
+    /// Hold a shared lock on the file while it is mapped
+    ///
+    /// The lock is advisory: it keeps out writers that take an exclusive
+    /// lock, such as `ai-flock -x`, and no others. A lock held elsewhere is
+    /// not waited for; the file is mapped without one.
+    pub fn lock(mut self, lock: bool) -> Self {
+        self.lock = lock;
+        self
+    }
+
+    /// The hints as a JSON object
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "cache_policy": self.cache_policy.as_str(),
+            "access": self.access.as_str(),
+            "will_need": self.will_need,
+            "populate": self.populate,
+            "huge_pages": self.huge_pages,
+            "guarded": self.guarded,
+            "lock": self.lock,
+        })
+    }
+
+    /// JSONL record describing requested and applied hints
+    pub fn report(requested: &MapOptions, applied: &MapOptions, path: &Path) -> serde_json::Value {
+        serde_json::json!({
+            "type": "map_hints",
+            "path": path.display().to_string(),
+            "requested": requested.to_json(),
+            "applied": applied.to_json(),
+            "honored": requested == applied,
+        })
+    }
+}
+
+/// Whether files are memory mapped, rather than read into memory
+static MMAP_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Largest file [`MapOptions::guarded`] reads into memory instead of mapping
+pub const GUARD_COPY_LIMIT: usize = 1024 * 1024;
+
+/// Turn memory mapping of files on or off for the process
+///
+/// With mapping off, files are read into memory when opened. A mapped file
+/// that is truncated by another process makes reads past its new end fault
+/// with `SIGBUS`, which kills the process; a signal cannot be caught as a
+/// panic, so reading the file in is the only sure way to avoid it.
+pub fn set_mmap_enabled(enabled: bool) {
+    MMAP_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether files are memory mapped
+pub fn mmap_enabled() -> bool {
+    MMAP_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Most read from a FIFO, device or socket, whose size is not known until
+/// it ends
+pub const STREAM_READ_LIMIT: u64 = 1 << 30;
+
+/// What a path is, which decides how [`SafeMemoryAccess`] reads it
+///
+/// Only regular files with contents are mapped. Mapping an empty file
+/// fails on many platforms, and files under `/proc` and `/sys` report a
+/// size of 0 yet have contents, so `Empty` files are read instead; FIFOs,
+/// devices and sockets are read too, up to [`STREAM_READ_LIMIT`] bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    /// A regular file with contents
+    Regular,
+    /// A regular file reporting a size of 0
+    Empty,
+    /// A directory, which cannot be read as a file
+    Directory,
+    /// A named pipe
+    Fifo,
+    /// A character device, such as `/dev/null`
+    CharDevice,
+    /// A block device
+    BlockDevice,
+    /// A Unix domain socket
+    Socket,
+    /// Anything else the platform has
+    Other,
+}
+
+impl FileKind {
+    /// Classify a file from its metadata
+    pub fn of(metadata: &std::fs::Metadata) -> Self {
+        let file_type = metadata.file_type();
+        if file_type.is_file() {
+            return if metadata.len() == 0 { FileKind::Empty } else { FileKind::Regular };
+        }
+        if file_type.is_dir() {
+            return FileKind::Directory;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            if file_type.is_fifo() {
+                return FileKind::Fifo;
+            }
+            if file_type.is_char_device() {
+                return FileKind::CharDevice;
+            }
+            if file_type.is_block_device() {
+                return FileKind::BlockDevice;
+            }
+            if file_type.is_socket() {
+                return FileKind::Socket;
+            }
+        }
+        FileKind::Other
+    }
+
+    /// Classify the file at `path`, following symlinks
+    pub fn classify(path: &Path) -> Result<Self> {
+        let metadata = std::fs::metadata(path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => AiCoreutilsError::PathNotFound(path.to_path_buf()),
+            _ => AiCoreutilsError::Io(e),
+        })?;
+        Ok(Self::of(&metadata))
+    }
+
+    /// Whether [`SafeMemoryAccess`] maps files of this kind
+    pub fn is_mappable(self) -> bool {
+        self == FileKind::Regular
+    }
+
+    /// Name used in JSONL output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FileKind::Regular => "regular",
+            FileKind::Empty => "empty",
+            FileKind::Directory => "directory",
+            FileKind::Fifo => "fifo",
+            FileKind::CharDevice => "char_device",
+            FileKind::BlockDevice => "block_device",
+            FileKind::Socket => "socket",
+            FileKind::Other => "other",
+        }
+    }
+}
+
+/// The file contents were read from, as it was when opened
+struct Source {
+    file: File,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+/// Memory the contents are served from
+enum Backing {
+    /// A mapping of the file
+    Mapped(Mmap),
+    /// Contents read, decoded or handed over in a buffer
+    Owned(Vec<u8>),
+}
+
+impl std::ops::Deref for Backing {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Backing::Mapped(mmap) => mmap,
+            Backing::Owned(data) => data,
+        }
+    }
+}
+
+/// Safe memory access handler for files and in-memory buffers
 pub struct SafeMemoryAccess {
-    mmap: Mmap,
+    memory: Backing,
     size: usize,
+    // Declared after `memory`, so a lock held on it outlasts the mapping
+    source: Option<Source>,
+    kind: FileKind,
     pattern_searcher: SimdPatternSearcher,
     byte_counter: SimdByteCounter,
     text_processor: SimdTextProcessor,
+    newline_counter: SimdNewlineCounter,
+    map_options: MapOptions,
+    #[cfg(feature = "compression")]
+    compression: Option<Compression>,
+    // Declared after `memory` so the mapping is gone before pages are dropped
+    _cache_guard: Option<DropCacheOnClose>,
 }
 
 impl SafeMemoryAccess {
@@ -34,29 +327,284 @@ impl SafeMemoryAccess {
     /// let data = access.get(0, 100).unwrap();
     /// ```
     pub fn new(path: impl AsRef<Path>) -> Result<Self> {
-        let path = path.as_ref();
+        Self::with_cache_policy(path, CachePolicy::Normal)
+    }
+
+    /// Create a memory-mapped file access with a page cache policy
+    ///
+    /// Memory maps always go through the page cache, so `Direct` is served
+    /// as `Stream`: sequential read-ahead while mapped and cached pages
+    /// dropped afterwards. Check [`SafeMemoryAccess::cache_policy`] for the
+    /// policy that was actually applied.
+    ///
+    /// The mapped size counts towards `--max-bytes` and `--max-files`.
+    pub fn with_cache_policy(path: impl AsRef<Path>, policy: CachePolicy) -> Result<Self> {
+        Self::with_options(path, &MapOptions::new().cache_policy(policy))
+    }
+
+    /// Create a memory-mapped file access with `madvise` and mapping hints
+    ///
+    /// Hints that cannot be applied do not make mapping fail; check
+    /// [`SafeMemoryAccess::map_options`] for those that were. Contents
+    /// decoded into memory (archive members, compressed files) take none.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use ai_coreutils::memory::{AccessPattern, MapOptions, SafeMemoryAccess};
+    ///
+    /// let options = MapOptions::new().access(AccessPattern::Random);
+    /// let access = SafeMemoryAccess::with_options("/path/to/file", &options).unwrap();
+    /// ```
+    pub fn with_options(path: impl AsRef<Path>, options: &MapOptions) -> Result<Self> {
+        Self::map(path.as_ref(), options, true)
+    }
+
+    /// Map a file without counting it towards resource limits
+    ///
+    /// For fingerprinting a file whose contents another read accounts for.
+    pub(crate) fn uncounted(path: &Path) -> Result<Self> {
+        Self::map(path, &MapOptions::new(), false)
+    }
+
+    fn map(path: &Path, options: &MapOptions, counted: bool) -> Result<Self> {
+        crate::policy::check(path, crate::policy::Access::Read)?;
+        #[cfg(feature = "archives")]
+        if crate::archive::is_member(path) {
+            return Self::from_member(path, counted);
+        }
         let file = File::open(path)
             .map_err(AiCoreutilsError::Io)?;
 
         let metadata = file.metadata()
             .map_err(AiCoreutilsError::Io)?;
 
-        let size = metadata.len() as usize;
+        let mut size = metadata.len() as usize;
+        let kind = FileKind::of(&metadata);
+        if kind == FileKind::Directory {
+            return Err(AiCoreutilsError::InvalidInput(format!("{} is a directory", path.display())));
+        }
 
-        let mmap = unsafe {
-            Mmap::map(&file)
-                .map_err(|e| AiCoreutilsError::MemoryAccess(format!("Failed to map file: {}", e)))?
+        let read_in = !kind.is_mappable() || !mmap_enabled() || (options.guarded && size <= GUARD_COPY_LIMIT);
+        let memory = if !kind.is_mappable() {
+            Backing::Owned(read_stream(&file, kind.as_str(), counted)?)
+        } else if read_in {
+            // The size is known, so a file past the limits is not read in
+            if counted {
+                crate::limits::check_read(metadata.len())?;
+            }
+            Backing::Owned(read_into_memory(&file, size)?)
+        } else {
+            let mut mmap_options = MmapOptions::new();
+            if options.populate {
+                mmap_options.populate();
+            }
+            Backing::Mapped(unsafe {
+                mmap_options
+                    .map(&file)
+                    .map_err(|e| AiCoreutilsError::MemoryAccess(format!("Failed to map file: {}", e)))?
+            })
+        };
+        if read_in {
+            size = memory.len();
+        }
+        // Decoding opens the file again, which a pipe cannot be
+        #[cfg(feature = "compression")]
+        if kind.is_mappable() {
+            if let Some(compression) = crate::compress::sniff(&memory) {
+                return Self::decompressed(path, compression, counted);
+            }
+        }
+        if counted {
+            crate::limits::read_file(path, size as u64)?;
+        }
+
+        let (cache_policy, cache_guard) = match options.cache_policy {
+            CachePolicy::Normal => (CachePolicy::Normal, None),
+            CachePolicy::Stream | CachePolicy::Direct => {
+                #[cfg(unix)]
+                if let Backing::Mapped(mmap) = &memory {
+                    let _ = mmap.advise(memmap2::Advice::Sequential);
+                }
+
+                if fs_utils::advise_sequential(&file) {
+                    (CachePolicy::Stream, Some(DropCacheOnClose::new(file.try_clone()?)))
+                } else {
+                    (CachePolicy::Normal, None)
+                }
+            }
+        };
+        let applied = match &memory {
+            Backing::Mapped(mmap) => advise(mmap, options),
+            Backing::Owned(_) => MapOptions::new().guarded(options.guarded),
+        };
+        let map_options = MapOptions {
+            cache_policy,
+            lock: options.lock && !read_in && matches!(file.try_lock_shared(), Ok(())),
+            ..applied
         };
 
         Ok(Self {
-            mmap,
+            memory,
             size,
+            // Only files have a size and modification time to check against
+            source: matches!(kind, FileKind::Regular | FileKind::Empty).then(|| Source {
+                file,
+                len: metadata.len(),
+                modified: metadata.modified().ok(),
+            }),
+            kind,
             pattern_searcher: SimdPatternSearcher::new(),
             byte_counter: SimdByteCounter::new(),
             text_processor: SimdTextProcessor::new(),
+            newline_counter: SimdNewlineCounter::new(),
+            map_options,
+            #[cfg(feature = "compression")]
+            compression: None,
+            _cache_guard: cache_guard,
+        })
+    }
+
+    /// Serve an archive member, read into memory
+    #[cfg(feature = "archives")]
+    fn from_member(path: &Path, counted: bool) -> Result<Self> {
+        let data = crate::archive::read(path)?;
+        if counted {
+            crate::limits::read_file(path, data.len() as u64)?;
+        }
+        Ok(Self::from_vec(data))
+    }
+
+    /// Serve a compressed file, decoded into memory
+    #[cfg(feature = "compression")]
+    fn decompressed(path: &Path, compression: Compression, counted: bool) -> Result<Self> {
+        let data = crate::compress::decode(path, compression)?;
+        if counted {
+            crate::limits::read_file(path, data.len() as u64)?;
+        }
+        Ok(Self {
+            compression: Some(compression),
+            ..Self::from_vec(data)
         })
     }
 
+    /// Serve a buffer already in memory, such as decompressed or fetched data
+    ///
+    /// The buffer is taken over, not copied, and every accessor works on it
+    /// as on a file, so code reading through `SafeMemoryAccess` need not
+    /// know where the bytes came from. There is no file: `verify` always
+    /// passes, no hints or cache policy apply, and no resource limits are
+    /// counted.
+    ///
+    /// # Example
+    /// ```
+    /// use ai_coreutils::memory::SafeMemoryAccess;
+    ///
+    /// let access = SafeMemoryAccess::from_vec(b"one\ntwo\n".to_vec());
+    /// assert_eq!(access.count_byte(b'\n'), 2);
+    /// assert_eq!(access.get(4, 3), Some(&b"two"[..]));
+    /// ```
+    pub fn from_vec(data: Vec<u8>) -> Self {
+        Self {
+            size: data.len(),
+            memory: Backing::Owned(data),
+            source: None,
+            kind: FileKind::Regular,
+            pattern_searcher: SimdPatternSearcher::new(),
+            byte_counter: SimdByteCounter::new(),
+            text_processor: SimdTextProcessor::new(),
+            newline_counter: SimdNewlineCounter::new(),
+            map_options: MapOptions::new(),
+            #[cfg(feature = "compression")]
+            compression: None,
+            _cache_guard: None,
+        }
+    }
+
+    /// Serve a copy of `data`
+    ///
+    /// See [`SafeMemoryAccess::from_vec`], which takes a buffer over
+    /// without copying it.
+    pub fn from_bytes(data: &[u8]) -> Self {
+        Self::from_vec(data.to_vec())
+    }
+
+    /// Open `path` as [`SafeMemoryAccess::new`] does, or read stdin when
+    /// `path` is `-`
+    ///
+    /// stdin is read as a pipe is: to its end, up to [`STREAM_READ_LIMIT`]
+    /// bytes or what `--max-bytes` leaves, and counted against the resource
+    /// limits as a file named `-`.
+    pub fn open_or_stdin(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if path != Path::new("-") {
+            return Self::new(path);
+        }
+        let data = read_stream(std::io::stdin().lock(), "stdin", true)?;
+        crate::limits::read_file(path, data.len() as u64)?;
+        Ok(Self::from_vec(data))
+    }
+
+    /// Page cache policy applied when the file was mapped
+    pub fn cache_policy(&self) -> CachePolicy {
+        self.map_options.cache_policy
+    }
+
+    /// Hints applied when the file was mapped
+    pub fn map_options(&self) -> MapOptions {
+        self.map_options
+    }
+
+    /// What the path was when it was opened
+    ///
+    /// Decoded contents (archive members, compressed files) are `Regular`.
+    pub fn file_kind(&self) -> FileKind {
+        self.kind
+    }
+
+    /// Whether the contents are served from a mapping of the file, rather
+    /// than read or decoded into memory
+    ///
+    /// Only mapped contents can fault if the file is truncated.
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.memory, Backing::Mapped(_))
+    }
+
+    /// Check that the file has not been resized or modified since it was
+    /// opened
+    ///
+    /// Call before relying on results from a file another process may be
+    /// writing: if it has shrunk, reads from a mapping past its new end
+    /// would fault. Contents not read from a file always pass.
+    pub fn verify(&self) -> Result<()> {
+        let Some(source) = &self.source else {
+            return Ok(());
+        };
+        let metadata = source.file.metadata().map_err(AiCoreutilsError::Io)?;
+        if metadata.len() != source.len {
+            return Err(AiCoreutilsError::MemoryAccess(format!(
+                "File changed while open: size was {} bytes, now {}",
+                source.len,
+                metadata.len()
+            )));
+        }
+        if metadata.modified().ok() != source.modified {
+            return Err(AiCoreutilsError::MemoryAccess(
+                "File changed while open: modified since it was opened".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Compression the contents were decoded from: `gzip`, `zstd` or `xz`
+    ///
+    /// `None` for files served as they are on disk.
+    pub fn compression(&self) -> Option<&'static str> {
+        #[cfg(feature = "compression")]
+        return self.compression.map(Compression::as_str);
+        #[cfg(not(feature = "compression"))]
+        None
+    }
+
     /// Get the size of the memory-mapped region
     pub fn size(&self) -> usize {
         self.size
@@ -64,13 +612,18 @@ impl SafeMemoryAccess {
 
     /// Get a raw pointer to the memory
     pub fn as_ptr(&self) -> *const u8 {
-        self.mmap.as_ptr()
+        self.memory.as_ptr()
     }
 
     /// Get a mutable pointer to the memory (if writable)
     #[allow(clippy::mut_from_ref)]
     pub fn as_mut_ptr(&self) -> *mut u8 {
-        self.mmap.as_ptr() as *mut u8
+        self.memory.as_ptr() as *mut u8
+    }
+
+    /// The whole memory-mapped region
+    pub fn as_slice(&self) -> &[u8] {
+        &self.memory[..self.size]
     }
 
     /// Bounds-checked access to a slice of memory
@@ -83,7 +636,7 @@ impl SafeMemoryAccess {
     /// `Some(&[u8])` if the range is valid, `None` otherwise
     pub fn get(&self, offset: usize, len: usize) -> Option<&[u8]> {
         if offset.saturating_add(len) <= self.size {
-            Some(&self.mmap[offset..offset + len])
+            Some(&self.memory[offset..offset + len])
         } else {
             None
         }
@@ -95,7 +648,7 @@ impl SafeMemoryAccess {
     /// `Some(u8)` if the offset is valid, `None` otherwise
     pub fn get_byte(&self, offset: usize) -> Option<u8> {
         if offset < self.size {
-            Some(self.mmap[offset])
+            Some(self.memory[offset])
         } else {
             None
         }
@@ -103,6 +656,8 @@ impl SafeMemoryAccess {
 
     /// Search for a pattern in the memory-mapped region (SIMD-accelerated)
     ///
+    /// Matches do not overlap, as in [`SimdPatternSearcher::find_all`].
+    ///
     /// # Arguments
     /// * `pattern` - Byte pattern to search for
     ///
@@ -114,12 +669,12 @@ impl SafeMemoryAccess {
         }
 
         // Use SIMD-accelerated pattern search
-        self.pattern_searcher.find_all(&self.mmap, pattern)
+        self.pattern_searcher.find_all(self.as_slice(), pattern)
     }
 
     /// Count occurrences of a byte in the memory-mapped region (SIMD-accelerated)
     pub fn count_byte(&self, byte: u8) -> usize {
-        self.byte_counter.count(&self.mmap, byte)
+        self.byte_counter.count(self.as_slice(), byte)
     }
 
     /// Count lines, words, and bytes in the memory-mapped region (SIMD-accelerated)
@@ -127,32 +682,146 @@ impl SafeMemoryAccess {
     /// # Returns
     /// Tuple of (lines, words, bytes)
     pub fn count_text_metrics(&self) -> (usize, usize, usize) {
-        let metrics = self.text_processor.analyze(&self.mmap);
+        let metrics = self.text_processor.analyze(self.as_slice());
         (metrics.lines, metrics.words, metrics.bytes)
     }
 
-    /// Create a SafeMemoryAccess from a vector (for testing)
-    #[cfg(test)]
-    pub fn from_vec(data: Vec<u8>) -> Result<Self> {
-        use std::io::Write;
-        // Create a temporary file
-        let mut temp_file = tempfile::NamedTempFile::new()?;
-        temp_file.write_all(&data)?;
-        temp_file.flush()?;
+    /// Search for a pattern in `len` bytes from `offset` (SIMD-accelerated)
+    ///
+    /// Offsets are from the start of the file, and only matches lying
+    /// wholly inside the range are found.
+    ///
+    /// # Returns
+    /// `None` if the range is out of bounds, as with [`get`](Self::get)
+    pub fn find_pattern_in(&self, offset: usize, len: usize, pattern: &[u8]) -> Option<Vec<usize>> {
+        let data = self.get(offset, len)?;
+        if pattern.is_empty() || pattern.len() > len {
+            return Some(Vec::new());
+        }
+        let matches = self.pattern_searcher.find_all(data, pattern);
+        Some(matches.into_iter().map(|pos| offset + pos).collect())
+    }
+
+    /// Count occurrences of a byte in `len` bytes from `offset` (SIMD-accelerated)
+    ///
+    /// # Returns
+    /// `None` if the range is out of bounds
+    pub fn count_byte_in(&self, offset: usize, len: usize, byte: u8) -> Option<usize> {
+        Some(self.byte_counter.count(self.get(offset, len)?, byte))
+    }
 
-        // Create mmap from the file
-        let mmap = unsafe {
-            Mmap::map(&*temp_file.as_file())
-                .map_err(|e| AiCoreutilsError::MemoryAccess(format!("Failed to create mmap from vec: {}", e)))?
+    /// Text metrics of `len` bytes from `offset`, in one SIMD pass
+    ///
+    /// The range is analyzed as text of its own: a word cut by its start
+    /// counts as a word, and a line cut by its end as the longest line if it
+    /// is.
+    ///
+    /// # Returns
+    /// `None` if the range is out of bounds
+    pub fn count_text_metrics_in(&self, offset: usize, len: usize) -> Option<TextMetrics> {
+        Some(self.text_processor.analyze(self.get(offset, len)?))
+    }
+
+    /// Byte range `(start, end)` holding lines `first..last` (0-based, `last` exclusive)
+    ///
+    /// Only the data up to the last requested line is scanned (SIMD-accelerated).
+    /// `None` for `last` extends to the end of the file. Lines past the end of
+    /// the file give an empty range at the file size.
+    pub fn line_span(&self, first: usize, last: Option<usize>) -> (usize, usize) {
+        let start = self.line_start(self.as_slice(), first);
+        let end = match last {
+            Some(last) if last > first => start + self.line_start(&self.as_slice()[start..], last - first),
+            Some(_) => start,
+            None => self.size,
         };
+        (start, end)
+    }
 
-        Ok(Self {
-            size: data.len(),
-            mmap,
-            pattern_searcher: SimdPatternSearcher::new(),
-            byte_counter: SimdByteCounter::new(),
-            text_processor: SimdTextProcessor::new(),
-        })
+    /// Offset of the start of line `line` (0-based) in `data`, or its length
+    fn line_start(&self, data: &[u8], line: usize) -> usize {
+        if line == 0 {
+            return 0;
+        }
+        self.newline_counter
+            .find_nth_newline(data, line)
+            .map_or(data.len(), |pos| pos + 1)
+    }
+}
+
+/// Read up to `size` bytes of `file`, fewer if it shrank meanwhile
+fn read_into_memory(file: &File, size: usize) -> Result<Vec<u8>> {
+    use std::io::Read;
+    let mut data = Vec::with_capacity(size);
+    file.take(size as u64)
+        .read_to_end(&mut data)
+        .map_err(AiCoreutilsError::Io)?;
+    Ok(data)
+}
+
+/// Read a FIFO, device, socket or stdin (`what`, for errors) to its end,
+/// up to [`STREAM_READ_LIMIT`] bytes
+///
+/// Also used for `Empty` files, which may have contents all the same. When
+/// `counted`, reading stops one byte past `--max-bytes`, and fails there.
+fn read_stream(file: impl std::io::Read, what: &str, counted: bool) -> Result<Vec<u8>> {
+    use std::io::Read;
+    let cap = if counted {
+        crate::limits::check_read(0)?;
+        crate::limits::bytes_left().map_or(STREAM_READ_LIMIT, |left| left.min(STREAM_READ_LIMIT))
+    } else {
+        STREAM_READ_LIMIT
+    };
+    let mut data = Vec::new();
+    file.take(cap + 1)
+        .read_to_end(&mut data)
+        .map_err(AiCoreutilsError::Io)?;
+    if counted {
+        crate::limits::check_read(data.len() as u64)?;
+    }
+    if data.len() as u64 > STREAM_READ_LIMIT {
+        return Err(AiCoreutilsError::InvalidInput(format!(
+            "{} holds more than {} bytes",
+            what,
+            STREAM_READ_LIMIT
+        )));
+    }
+    Ok(data)
+}
+
+/// Apply the `madvise` and mapping hints of `options` to `mmap`
+///
+/// Returns the hints that were applied; the cache policy is left as
+/// requested, for the caller to fill in.
+fn advise(mmap: &Mmap, options: &MapOptions) -> MapOptions {
+    #[cfg(unix)]
+    {
+        use memmap2::Advice;
+        // An empty file has no pages to advise on
+        let advise = |advice| mmap.is_empty() || mmap.advise(advice).is_ok();
+        let access = match options.access {
+            AccessPattern::Normal => AccessPattern::Normal,
+            AccessPattern::Sequential if advise(Advice::Sequential) => AccessPattern::Sequential,
+            AccessPattern::Random if advise(Advice::Random) => AccessPattern::Random,
+            _ => AccessPattern::Normal,
+        };
+        MapOptions {
+            access,
+            will_need: options.will_need && advise(Advice::WillNeed),
+            populate: options.populate && cfg!(target_os = "linux"),
+            #[cfg(target_os = "linux")]
+            huge_pages: options.huge_pages && advise(Advice::HugePage),
+            #[cfg(not(target_os = "linux"))]
+            huge_pages: false,
+            ..*options
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = mmap;
+        MapOptions {
+            cache_policy: options.cache_policy,
+            ..MapOptions::new()
+        }
     }
 }
 
@@ -241,4 +910,254 @@ mod tests {
         assert_eq!(words, 6);
         assert_eq!(bytes, 27);
     }
+
+    #[test]
+    fn test_range_queries() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"abc abc\nabc abc abc\n").unwrap();
+        let access = SafeMemoryAccess::new(temp_file.path()).unwrap();
+
+        assert_eq!(access.as_slice(), b"abc abc\nabc abc abc\n");
+        assert_eq!(access.find_pattern_in(8, 12, b"abc"), Some(vec![8, 12, 16]));
+        // A match crossing the end of the range is not found
+        assert_eq!(access.find_pattern_in(0, 6, b"abc"), Some(vec![0]));
+        assert_eq!(access.find_pattern_in(20, 5, b"abc"), None);
+        assert_eq!(access.count_byte_in(0, 8, b'a'), Some(2));
+        assert_eq!(access.count_byte_in(20, 0, b'a'), Some(0));
+
+        let metrics = access.count_text_metrics_in(8, 12).unwrap();
+        assert_eq!((metrics.lines, metrics.words, metrics.bytes), (1, 3, 12));
+        assert_eq!(metrics.max_line_length, 11);
+        assert_eq!(access.count_text_metrics_in(0, access.size() + 1), None);
+    }
+
+    #[test]
+    fn test_map_options() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(&b"line\n".repeat(10_000)).unwrap();
+
+        let access = SafeMemoryAccess::new(temp_file.path()).unwrap();
+        assert_eq!(access.map_options(), MapOptions::new());
+
+        let options = MapOptions::new()
+            .access(AccessPattern::Random)
+            .will_need(true)
+            .populate(true)
+            .huge_pages(true);
+        let access = SafeMemoryAccess::with_options(temp_file.path(), &options).unwrap();
+        assert_eq!(access.count_byte(b'\n'), 10_000);
+        let applied = access.map_options();
+        #[cfg(target_os = "linux")]
+        {
+            assert_eq!(applied.access, AccessPattern::Random);
+            assert!(applied.will_need && applied.populate);
+        }
+
+        let report = MapOptions::report(&options, &applied, temp_file.path());
+        assert_eq!(report["type"], "map_hints");
+        assert_eq!(report["requested"]["access"], "random");
+        assert_eq!(report["honored"], options == applied);
+
+        // An empty file is read, not mapped, so no hints apply
+        let empty = NamedTempFile::new().unwrap();
+        let options = MapOptions::new().access(AccessPattern::Sequential).will_need(true);
+        let access = SafeMemoryAccess::with_options(empty.path(), &options).unwrap();
+        assert_eq!(access.size(), 0);
+        assert_eq!(access.map_options(), MapOptions::new());
+
+        assert_eq!("Random".parse::<AccessPattern>(), Ok(AccessPattern::Random));
+        assert!("backwards".parse::<AccessPattern>().is_err());
+    }
+
+    #[test]
+    fn test_guarded_and_verify() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"first line\nsecond line\n").unwrap();
+        temp_file.flush().unwrap();
+
+        // Small files are read in whole, and so cannot fault
+        let options = MapOptions::new().guarded(true).lock(true);
+        let guarded = SafeMemoryAccess::with_options(temp_file.path(), &options).unwrap();
+        assert!(!guarded.is_mapped());
+        assert_eq!(guarded.as_slice(), b"first line\nsecond line\n");
+        assert!(guarded.map_options().guarded);
+        assert!(!guarded.map_options().lock);
+
+        let mapped = SafeMemoryAccess::with_options(temp_file.path(), &MapOptions::new().lock(true)).unwrap();
+        assert!(mapped.is_mapped());
+        assert!(mapped.map_options().lock);
+        assert!(mapped.verify().is_ok());
+        assert!(guarded.verify().is_ok());
+
+        // Truncation is caught before the mapping is read past the new end
+        temp_file.as_file().set_len(5).unwrap();
+        let error = mapped.verify().unwrap_err().to_string();
+        assert!(error.contains("size was 23 bytes, now 5"), "{}", error);
+        assert!(guarded.verify().is_err());
+        assert_eq!(guarded.as_slice().len(), 23);
+
+        assert!(SafeMemoryAccess::from_vec(b"abc".to_vec()).verify().is_ok());
+    }
+
+    #[test]
+    fn test_empty_file() {
+        let empty = NamedTempFile::new().unwrap();
+        assert_eq!(FileKind::classify(empty.path()).unwrap(), FileKind::Empty);
+
+        let access = SafeMemoryAccess::new(empty.path()).unwrap();
+        assert_eq!(access.file_kind(), FileKind::Empty);
+        assert!(!access.is_mapped());
+        assert_eq!(access.size(), 0);
+        assert_eq!(access.as_slice(), b"");
+        assert_eq!(access.get(0, 0), Some(&b""[..]));
+        assert_eq!(access.get(0, 1), None);
+        assert!(access.find_pattern(b"a").is_empty());
+        assert_eq!(access.count_text_metrics(), (0, 0, 0));
+        assert!(access.verify().is_ok());
+    }
+
+    #[test]
+    fn test_directory_is_an_error() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(FileKind::classify(dir.path()).unwrap(), FileKind::Directory);
+        assert!(matches!(
+            SafeMemoryAccess::new(dir.path()),
+            Err(AiCoreutilsError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            FileKind::classify(&dir.path().join("missing")),
+            Err(AiCoreutilsError::PathNotFound(_))
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_proc_file_reporting_no_size() {
+        let path = Path::new("/proc/self/status");
+        assert_eq!(FileKind::classify(path).unwrap(), FileKind::Empty);
+        let access = SafeMemoryAccess::new(path).unwrap();
+        assert!(!access.is_mapped());
+        assert!(access.size() > 0);
+        assert!(!access.find_pattern(b"Name:").is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_char_device() {
+        let path = Path::new("/dev/null");
+        assert_eq!(FileKind::classify(path).unwrap(), FileKind::CharDevice);
+        let access = SafeMemoryAccess::new(path).unwrap();
+        assert_eq!(access.file_kind(), FileKind::CharDevice);
+        assert!(!access.is_mapped());
+        assert_eq!(access.size(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fifo() {
+        use std::os::unix::ffi::OsStrExt;
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("pipe");
+        let cpath = std::ffi::CString::new(path.as_os_str().as_bytes()).unwrap();
+        // SAFETY: cpath is a valid NUL-terminated string
+        assert_eq!(unsafe { libc::mkfifo(cpath.as_ptr(), 0o600) }, 0);
+        assert_eq!(FileKind::classify(&path).unwrap(), FileKind::Fifo);
+
+        let writer_path = path.clone();
+        let writer = std::thread::spawn(move || {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .open(&writer_path)
+                .unwrap()
+                .write_all(b"one\ntwo\n")
+                .unwrap();
+        });
+        let access = SafeMemoryAccess::new(&path).unwrap();
+        writer.join().unwrap();
+
+        assert_eq!(access.file_kind(), FileKind::Fifo);
+        assert!(!access.is_mapped());
+        assert_eq!(access.as_slice(), b"one\ntwo\n");
+        assert_eq!(access.count_byte(b'\n'), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_socket() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("socket");
+        let _listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        assert_eq!(FileKind::classify(&path).unwrap(), FileKind::Socket);
+        assert!(!FileKind::Socket.is_mappable());
+        // Sockets cannot be opened as files; the error is returned, not a fault
+        assert!(SafeMemoryAccess::new(&path).is_err());
+    }
+
+    #[test]
+    fn test_from_vec_and_bytes() {
+        let data = b"abc abc\nabc abc abc\n";
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(data).unwrap();
+        let file = SafeMemoryAccess::new(temp_file.path()).unwrap();
+
+        for access in [SafeMemoryAccess::from_vec(data.to_vec()), SafeMemoryAccess::from_bytes(data)] {
+            assert!(!access.is_mapped());
+            assert_eq!(access.file_kind(), FileKind::Regular);
+            assert_eq!(access.as_slice(), file.as_slice());
+            assert_eq!(access.get(4, 3), file.get(4, 3));
+            assert_eq!(access.get(20, 2), None);
+            assert_eq!(access.find_pattern(b"abc"), file.find_pattern(b"abc"));
+            assert_eq!(access.count_text_metrics(), file.count_text_metrics());
+            assert_eq!(access.count_text_metrics_in(8, 12), file.count_text_metrics_in(8, 12));
+            assert_eq!(access.line_span(1, None), file.line_span(1, None));
+            assert!(access.verify().is_ok());
+        }
+
+        let empty = SafeMemoryAccess::from_vec(Vec::new());
+        assert_eq!(empty.size(), 0);
+        assert_eq!(empty.count_byte(b'a'), 0);
+    }
+
+    #[test]
+    fn test_line_span() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let text: String = (1..=100).map(|i| format!("line {}\n", i)).collect();
+        temp_file.write_all(text.as_bytes()).unwrap();
+        temp_file.write_all(b"no newline").unwrap();
+
+        let access = SafeMemoryAccess::new(temp_file.path()).unwrap();
+        let slice = |(start, end): (usize, usize)| access.get(start, end - start).unwrap();
+
+        assert_eq!(slice(access.line_span(0, Some(1))), b"line 1\n");
+        assert_eq!(slice(access.line_span(49, Some(51))), b"line 50\nline 51\n");
+        assert_eq!(slice(access.line_span(100, None)), b"no newline");
+        assert_eq!(slice(access.line_span(99, Some(500))), b"line 100\nno newline");
+        assert_eq!(access.line_span(200, Some(300)), (access.size(), access.size()));
+        assert_eq!(access.line_span(5, Some(5)).0, access.line_span(5, Some(5)).1);
+    }
+
+    #[test]
+    fn test_cache_policy_direct_falls_back() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"Hello, World!").unwrap();
+
+        let access = SafeMemoryAccess::with_cache_policy(temp_file.path(), CachePolicy::Direct).unwrap();
+        assert_ne!(access.cache_policy(), CachePolicy::Direct);
+        assert_eq!(access.get(0, 5).unwrap(), b"Hello");
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_gzip_file_is_decompressed() {
+        use flate2::write::GzEncoder;
+
+        let mut encoder = GzEncoder::new(NamedTempFile::new().unwrap(), flate2::Compression::default());
+        encoder.write_all(b"line 1\nline 2\n").unwrap();
+        let temp_file = encoder.finish().unwrap();
+
+        let access = SafeMemoryAccess::new(temp_file.path()).unwrap();
+        assert_eq!(access.compression(), Some("gzip"));
+        assert_eq!(access.get(0, access.size()).unwrap(), b"line 1\nline 2\n");
+        assert_eq!(access.count_text_metrics().0, 2);
+    }
 }
@@ -0,0 +1,384 @@
+//! Persistent line-offset index
+//!
+//! A [`LineIndex`] records where every line of a file starts, so a line
+//! range can be turned into a byte range without scanning the file. Indexes
+//! are stored in a sidecar next to the file (`<file>.aidx`, written by
+//! `ai-index`) and picked up by `ai-head`, `ai-tail` and `ai-cat --lines`.
+//!
+//! A sidecar is only used while it matches the file: its size, mtime and an
+//! XXH3 hash of the first and last 64 KiB are recorded when it is built and
+//! compared before use. Hashing the samples rather than the whole file keeps
+//! the check O(1); it catches in-place rewrites that keep size and mtime,
+//! but not ones that change only the middle of the file.
+//!
+//! # Format
+//!
+//! All integers are little-endian:
+//!
+//! | Field | Type |
+//! |-------|------|
+//! | Magic `AIDX` | 4 bytes |
+//! | Version | u8 |
+//! | File size | u64 |
+//! | Mtime seconds, nanoseconds | i64, u32 |
+//! | Sample XXH3 | u64 |
+//! | Newline count | u64 |
+//! | Line start deltas | LEB128 varints |
+//!
+//! Each delta is the distance from the previous line start (the first line
+//! starts at 0), so typical lines take one or two bytes.
+
+use crate::error::{AiCoreutilsError, Result};
+use crate::fs_utils;
+use crate::memory::SafeMemoryAccess;
+use crate::simd_ops::{SimdHasher, SimdNewlineCounter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+const MAGIC: &[u8; 4] = b"AIDX";
+const VERSION: u8 = 2;
+const HEADER_LEN: usize = 4 + 1 + 8 + 8 + 4 + 8 + 8;
+
+/// Bytes hashed at each end of the file for the fingerprint
+const SAMPLE_LEN: usize = 64 * 1024;
+
+/// Extension appended to the file name for the sidecar
+pub const SIDECAR_EXTENSION: &str = "aidx";
+
+/// What a sidecar was built from, to detect changes to the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// File size in bytes
+    pub size: u64,
+    /// Modification time, seconds since the Unix epoch
+    pub mtime_secs: i64,
+    /// Sub-second part of the modification time
+    pub mtime_nanos: u32,
+    /// XXH3 of the first and last 64 KiB
+    pub sample_hash: u64,
+}
+
+impl Fingerprint {
+    /// Fingerprint the file at `path`, whose contents are `data`
+    fn new(metadata: &fs::Metadata, data: &[u8]) -> Self {
+        let (mtime_secs, mtime_nanos) = match metadata.modified().map(|t| t.duration_since(UNIX_EPOCH)) {
+            Ok(Ok(since)) => (since.as_secs() as i64, since.subsec_nanos()),
+            _ => (0, 0),
+        };
+        Self {
+            size: data.len() as u64,
+            mtime_secs,
+            mtime_nanos,
+            sample_hash: sample_hash(data),
+        }
+    }
+
+    /// Fingerprint the file at `path` as it is now
+    pub fn of(path: &Path) -> Result<Self> {
+        let metadata = fs::metadata(path).map_err(AiCoreutilsError::Io)?;
+        let mem = SafeMemoryAccess::uncounted(path)?;
+        let data = mem.as_slice();
+        Ok(Self::new(&metadata, data))
+    }
+}
+
+/// XXH3 over the first and last [`SAMPLE_LEN`] bytes (once if they overlap)
+fn sample_hash(data: &[u8]) -> u64 {
+    let hasher = SimdHasher::new();
+    if data.len() <= 2 * SAMPLE_LEN {
+        return hasher.xxh3(data);
+    }
+    let mut sample = Vec::with_capacity(2 * SAMPLE_LEN);
+    sample.extend_from_slice(&data[..SAMPLE_LEN]);
+    sample.extend_from_slice(&data[data.len() - SAMPLE_LEN..]);
+    hasher.xxh3(&sample)
+}
+
+/// Freshness of a file's sidecar index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexStatus {
+    /// The sidecar matches the file
+    Fresh,
+    /// The file changed since the sidecar was built
+    Stale,
+    /// There is no sidecar
+    Missing,
+}
+
+impl IndexStatus {
+    /// Name used in JSONL output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IndexStatus::Fresh => "fresh",
+            IndexStatus::Stale => "stale",
+            IndexStatus::Missing => "missing",
+        }
+    }
+}
+
+/// Start offsets of every line of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    fingerprint: Fingerprint,
+    /// Offset just past each `\n`, in order
+    line_starts: Vec<u64>,
+}
+
+impl LineIndex {
+    /// Index `data`, the contents of a file with `metadata`
+    pub fn build(data: &[u8], metadata: &fs::Metadata) -> Self {
+        let counter = SimdNewlineCounter::new();
+        let mut line_starts = Vec::new();
+        let mut pos = 0;
+        while let Some(i) = counter.find_nth_newline(&data[pos..], 1) {
+            pos += i + 1;
+            line_starts.push(pos as u64);
+        }
+        Self {
+            fingerprint: Fingerprint::new(metadata, data),
+            line_starts,
+        }
+    }
+
+    /// Index the file at `path`
+    pub fn build_file(path: &Path) -> Result<Self> {
+        let metadata = fs::metadata(path).map_err(AiCoreutilsError::Io)?;
+        let mem = SafeMemoryAccess::new(path)?;
+        Ok(Self::build(mem.as_slice(), &metadata))
+    }
+
+    /// The sidecar path for `path`: `<path>.aidx`
+    pub fn sidecar_path(path: &Path) -> PathBuf {
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(".");
+        sidecar.push(SIDECAR_EXTENSION);
+        PathBuf::from(sidecar)
+    }
+
+    /// Load the sidecar of `path` if it exists and matches the file
+    ///
+    /// A missing, stale or unreadable sidecar gives `None`, so callers can
+    /// fall back to scanning.
+    pub fn load_fresh(path: &Path) -> Option<Self> {
+        let index = Self::load(&Self::sidecar_path(path)).ok()?;
+        let current = Fingerprint::of(path).ok()?;
+        (index.fingerprint == current).then_some(index)
+    }
+
+    /// Whether `path` has a sidecar, and whether it matches the file
+    pub fn status(path: &Path) -> Result<IndexStatus> {
+        let sidecar = Self::sidecar_path(path);
+        if !sidecar.exists() {
+            return Ok(IndexStatus::Missing);
+        }
+        let fresh = match Self::load(&sidecar) {
+            Ok(index) => index.fingerprint == Fingerprint::of(path)?,
+            Err(_) => false,
+        };
+        Ok(if fresh { IndexStatus::Fresh } else { IndexStatus::Stale })
+    }
+
+    /// Read an index file
+    pub fn load(sidecar: &Path) -> Result<Self> {
+        let bytes = fs::read(sidecar).map_err(AiCoreutilsError::Io)?;
+        Self::decode(&bytes).ok_or_else(|| {
+            AiCoreutilsError::InvalidInput(format!("Corrupt line index: {}", sidecar.display()))
+        })
+    }
+
+    /// Atomically write the index to `sidecar`
+    pub fn save(&self, sidecar: &Path) -> Result<()> {
+        fs_utils::atomic_write(sidecar, &self.encode())
+    }
+
+    /// What the index was built from
+    pub fn fingerprint(&self) -> Fingerprint {
+        self.fingerprint
+    }
+
+    /// Number of lines, counting an unterminated last line
+    pub fn line_count(&self) -> usize {
+        let tail = self.line_starts.last().copied().unwrap_or(0);
+        self.line_starts.len() + usize::from(tail < self.fingerprint.size)
+    }
+
+    /// Offset of the start of line `line` (0-based), or the file size past the end
+    pub fn line_start(&self, line: usize) -> usize {
+        let offset = match line {
+            0 => 0,
+            n => self.line_starts.get(n - 1).copied().unwrap_or(self.fingerprint.size),
+        };
+        offset as usize
+    }
+
+    /// Byte range `(start, end)` holding lines `first..last` (0-based, `last` exclusive)
+    ///
+    /// Same contract as [`SafeMemoryAccess::line_span`], answered without
+    /// touching the file.
+    pub fn line_span(&self, first: usize, last: Option<usize>) -> (usize, usize) {
+        let start = self.line_start(first);
+        let end = match last {
+            Some(last) if last > first => self.line_start(last),
+            Some(_) => start,
+            None => self.fingerprint.size as usize,
+        };
+        (start, end)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let fp = &self.fingerprint;
+        let mut out = Vec::with_capacity(HEADER_LEN + self.line_starts.len() * 2);
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&fp.size.to_le_bytes());
+        out.extend_from_slice(&fp.mtime_secs.to_le_bytes());
+        out.extend_from_slice(&fp.mtime_nanos.to_le_bytes());
+        out.extend_from_slice(&fp.sample_hash.to_le_bytes());
+        out.extend_from_slice(&(self.line_starts.len() as u64).to_le_bytes());
+
+        let mut previous = 0;
+        for &start in &self.line_starts {
+            write_varint(&mut out, start - previous);
+            previous = start;
+        }
+        out
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC || bytes[4] != VERSION {
+            return None;
+        }
+        let mut header = &bytes[5..HEADER_LEN];
+        let mut take = |n: usize| {
+            let (field, rest) = header.split_at(n);
+            header = rest;
+            field
+        };
+        let fingerprint = Fingerprint {
+            size: u64::from_le_bytes(take(8).try_into().ok()?),
+            mtime_secs: i64::from_le_bytes(take(8).try_into().ok()?),
+            mtime_nanos: u32::from_le_bytes(take(4).try_into().ok()?),
+            sample_hash: u64::from_le_bytes(take(8).try_into().ok()?),
+        };
+        let count = u64::from_le_bytes(take(8).try_into().ok()?);
+
+        // Every line takes at least one byte, which bounds a corrupt count
+        if count > fingerprint.size {
+            return None;
+        }
+        let mut line_starts = Vec::with_capacity(count as usize);
+        let mut body = &bytes[HEADER_LEN..];
+        let mut previous = 0u64;
+        for _ in 0..count {
+            let delta = read_varint(&mut body)?;
+            previous = previous.checked_add(delta).filter(|&s| delta > 0 && s <= fingerprint.size)?;
+            line_starts.push(previous);
+        }
+        body.is_empty().then_some(Self { fingerprint, line_starts })
+    }
+}
+
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+pub(crate) fn read_varint(input: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = input.split_first()?;
+        *input = rest;
+        value |= u64::from(byte & 0x7f).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &TempDir, name: &str, data: &[u8]) -> PathBuf {
+        let path = dir.path().join(name);
+        fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_spans_match_scanning() {
+        let dir = TempDir::new().unwrap();
+        let long_line = "x".repeat(300);
+        let data = format!("a\n\n{}\r\nlast", long_line);
+        let path = write(&dir, "f.txt", data.as_bytes());
+
+        let index = LineIndex::build_file(&path).unwrap();
+        let mem = SafeMemoryAccess::new(&path).unwrap();
+        assert_eq!(index.line_count(), 4);
+        for first in 0..6 {
+            for last in [None, Some(0), Some(first + 1), Some(first + 2), Some(10)] {
+                assert_eq!(index.line_span(first, last), mem.line_span(first, last), "{} {:?}", first, last);
+            }
+        }
+
+        let empty = write(&dir, "empty.txt", b"");
+        assert_eq!(LineIndex::build_file(&empty).unwrap().line_count(), 0);
+        let terminated = write(&dir, "t.txt", b"a\nb\n");
+        assert_eq!(LineIndex::build_file(&terminated).unwrap().line_count(), 2);
+    }
+
+    #[test]
+    fn test_round_trip_and_staleness() {
+        let dir = TempDir::new().unwrap();
+        let path = write(&dir, "log.txt", b"one\ntwo\nthree\n");
+        assert_eq!(LineIndex::status(&path).unwrap(), IndexStatus::Missing);
+
+        let index = LineIndex::build_file(&path).unwrap();
+        let sidecar = LineIndex::sidecar_path(&path);
+        assert_eq!(sidecar, dir.path().join("log.txt.aidx"));
+        index.save(&sidecar).unwrap();
+        assert_eq!(LineIndex::load(&sidecar).unwrap(), index);
+        assert_eq!(LineIndex::status(&path).unwrap(), IndexStatus::Fresh);
+        assert_eq!(LineIndex::load_fresh(&path), Some(index.clone()));
+
+        // Same size, same mtime, different contents
+        let mtime = fs::metadata(&path).unwrap().modified().unwrap();
+        fs::write(&path, b"ONE\ntwo\nthree\n").unwrap();
+        fs::File::options().write(true).open(&path).unwrap().set_modified(mtime).unwrap();
+        assert_eq!(LineIndex::status(&path).unwrap(), IndexStatus::Stale);
+        assert_eq!(LineIndex::load_fresh(&path), None);
+    }
+
+    #[test]
+    fn test_corrupt_sidecars_rejected() {
+        let dir = TempDir::new().unwrap();
+        let path = write(&dir, "f.txt", b"a\nb\nc\n");
+        let encoded = LineIndex::build_file(&path).unwrap().encode();
+        assert!(LineIndex::decode(&encoded).is_some());
+        assert!(LineIndex::decode(&encoded[..encoded.len() - 1]).is_none());
+        assert!(LineIndex::decode(&[encoded.as_slice(), &[1]].concat()).is_none());
+        assert!(LineIndex::decode(b"AIDX").is_none());
+
+        let mut bad_delta = encoded.clone();
+        *bad_delta.last_mut().unwrap() = 0x7f;
+        assert!(LineIndex::decode(&bad_delta).is_none());
+    }
+
+    #[test]
+    fn test_varint() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut out = Vec::new();
+            write_varint(&mut out, value);
+            let mut input = out.as_slice();
+            assert_eq!(read_varint(&mut input), Some(value));
+            assert!(input.is_empty());
+        }
+        assert_eq!(read_varint(&mut &[0x80u8][..]), None);
+    }
+}
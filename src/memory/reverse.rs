@@ -0,0 +1,187 @@
+//! Reverse block reader
+//!
+//! A [`ReverseReader`] finds where the last lines of a file start by
+//! scanning fixed-size blocks back from the end, with
+//! [`SimdNewlineCounter`] for newline-separated data and
+//! [`SimdPatternSearcher::rfind_byte`] for other separators. Over a memory
+//! map only the pages holding those lines are touched, however large the
+//! file is; `ai-tail` uses it when no fresh ai-index sidecar is available.
+
+use crate::simd_ops::{SimdNewlineCounter, SimdPatternSearcher};
+
+/// Bytes scanned per step
+const BLOCK_SIZE: usize = 64 * 1024;
+
+/// Locates line boundaries in data from its end
+pub struct ReverseReader<'a> {
+    data: &'a [u8],
+    separator: u8,
+    block_size: usize,
+    newlines: SimdNewlineCounter,
+    searcher: SimdPatternSearcher,
+}
+
+impl<'a> ReverseReader<'a> {
+    /// Reader over `data`, whose lines end with `separator`
+    pub fn new(data: &'a [u8], separator: u8) -> Self {
+        Self {
+            data,
+            separator,
+            block_size: BLOCK_SIZE,
+            newlines: SimdNewlineCounter::new(),
+            searcher: SimdPatternSearcher::new(),
+        }
+    }
+
+    /// Scan `block_size` bytes per step instead of 64 KiB
+    pub fn with_block_size(mut self, block_size: usize) -> Self {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    /// Offset at which the last `lines` lines start
+    ///
+    /// A separator ending the data ends the last line rather than starting
+    /// an empty one, as in `tail`. With fewer lines than asked for, this is
+    /// 0; asking for none gives the data length.
+    pub fn last_lines(&self, lines: usize) -> usize {
+        if lines == 0 {
+            return self.data.len();
+        }
+        let end = match self.data.last() {
+            Some(&last) if last == self.separator => self.data.len() - 1,
+            _ => self.data.len(),
+        };
+        self.nth_separator_before(end, lines).map_or(0, |pos| pos + 1)
+    }
+
+    /// Offset just past the first `lines` lines, scanning forwards
+    ///
+    /// The start of `tail -n +K` output is `skip_lines(K - 1)`; past the
+    /// last line this is the data length.
+    pub fn skip_lines(&self, lines: usize) -> usize {
+        if lines == 0 {
+            return 0;
+        }
+        let found = if self.separator == b'\n' {
+            self.newlines.find_nth_newline(self.data, lines)
+        } else {
+            self.data
+                .iter()
+                .enumerate()
+                .filter(|&(_, &byte)| byte == self.separator)
+                .nth(lines - 1)
+                .map(|(pos, _)| pos)
+        };
+        found.map_or(self.data.len(), |pos| pos + 1)
+    }
+
+    /// Position of the `n`th separator (from 1) counting back from `end`
+    fn nth_separator_before(&self, end: usize, n: usize) -> Option<usize> {
+        let mut remaining = n;
+        let mut hi = end;
+        while hi > 0 {
+            let lo = hi.saturating_sub(self.block_size);
+            let found = self.last_separators(&self.data[lo..hi], remaining);
+            if found.len() >= remaining {
+                return Some(lo + found[found.len() - remaining]);
+            }
+            remaining -= found.len();
+            hi = lo;
+        }
+        None
+    }
+
+    /// Positions of the last `n` separators in `block`, in ascending order
+    fn last_separators(&self, block: &[u8], n: usize) -> Vec<usize> {
+        if self.separator == b'\n' {
+            return self.newlines.find_last_n_newlines(block, n);
+        }
+        let mut found = Vec::new();
+        let mut end = block.len();
+        while found.len() < n {
+            let Some(pos) = self.searcher.rfind_byte(&block[..end], self.separator) else {
+                break;
+            };
+            found.push(pos);
+            end = pos;
+        }
+        found.reverse();
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Start of the last `n` lines, the slow way
+    fn expected_start(data: &[u8], separator: u8, n: usize) -> usize {
+        if n == 0 {
+            return data.len();
+        }
+        let body = data.strip_suffix(&[separator]).unwrap_or(data);
+        let ends: Vec<usize> = body
+            .iter()
+            .enumerate()
+            .filter(|&(_, &byte)| byte == separator)
+            .map(|(pos, _)| pos + 1)
+            .collect();
+        ends.len().checked_sub(n).map_or(0, |i| ends[i])
+    }
+
+    #[test]
+    fn test_last_lines() {
+        let reader = ReverseReader::new(b"one\ntwo\nthree\n", b'\n');
+        assert_eq!(reader.last_lines(1), 8);
+        assert_eq!(reader.last_lines(2), 4);
+        assert_eq!(reader.last_lines(3), 0);
+        assert_eq!(reader.last_lines(10), 0);
+        assert_eq!(reader.last_lines(0), 14);
+
+        // An unterminated last line counts
+        let reader = ReverseReader::new(b"one\ntwo\nthree", b'\n');
+        assert_eq!(reader.last_lines(1), 8);
+        assert_eq!(reader.last_lines(2), 4);
+
+        assert_eq!(ReverseReader::new(b"", b'\n').last_lines(3), 0);
+        assert_eq!(ReverseReader::new(b"\n\n\n", b'\n').last_lines(2), 1);
+    }
+
+    #[test]
+    fn test_last_lines_across_blocks() {
+        let data: Vec<u8> = (0..2000)
+            .flat_map(|i| format!("line {} {}\n", i, "x".repeat(i % 97)).into_bytes())
+            .collect();
+        for separator in [b'\n', b' '] {
+            for block_size in [1, 7, 64, 1000, BLOCK_SIZE] {
+                let reader = ReverseReader::new(&data, separator).with_block_size(block_size);
+                for n in [1, 2, 31, 500, 1999, 2000, 5000] {
+                    assert_eq!(
+                        reader.last_lines(n),
+                        expected_start(&data, separator, n),
+                        "separator {:?}, block {}, n {}",
+                        separator as char,
+                        block_size,
+                        n
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_skip_lines() {
+        let data = b"a\nbb\nccc\n";
+        let reader = ReverseReader::new(data, b'\n');
+        assert_eq!(reader.skip_lines(0), 0);
+        assert_eq!(reader.skip_lines(1), 2);
+        assert_eq!(reader.skip_lines(2), 5);
+        assert_eq!(reader.skip_lines(3), 9);
+        assert_eq!(reader.skip_lines(4), 9);
+
+        let reader = ReverseReader::new(b"a\0b\0c", 0);
+        assert_eq!(reader.skip_lines(2), 4);
+        assert_eq!(reader.last_lines(1), 4);
+    }
+}
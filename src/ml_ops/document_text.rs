@@ -0,0 +1,202 @@
+//! Plain text of PDF and DOCX documents for pattern analysis
+//!
+//! With the `documents` feature, [`extract`] pulls the text out of PDF
+//! files and Word documents so pattern detection and content statistics
+//! see the words instead of compressed streams. Word text comes from the
+//! `w:t` runs of the body, headers, footers and notes, with paragraphs,
+//! breaks and tabs kept as whitespace. Without the feature nothing is
+//! extracted and documents are analyzed as raw bytes.
+
+use crate::error::Result;
+
+/// MIME type of Word documents
+pub const DOCX_MIME_TYPE: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+/// A document format text can be extracted from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    /// Portable Document Format
+    Pdf,
+    /// Office Open XML word processing document
+    Docx,
+}
+
+impl DocumentKind {
+    /// The document kind of a classified MIME type
+    pub fn from_mime_type(mime_type: &str) -> Option<Self> {
+        match mime_type {
+            "application/pdf" => Some(DocumentKind::Pdf),
+            DOCX_MIME_TYPE => Some(DocumentKind::Docx),
+            _ => None,
+        }
+    }
+
+    /// Name used in JSONL output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DocumentKind::Pdf => "pdf",
+            DocumentKind::Docx => "docx",
+        }
+    }
+}
+
+/// Extract the text of a document, or `None` for other file types
+///
+/// Damaged or encrypted documents are an error.
+#[cfg(feature = "documents")]
+pub fn extract(mime_type: &str, content: &[u8]) -> Result<Option<String>> {
+    use crate::error::AiCoreutilsError;
+
+    let Some(kind) = DocumentKind::from_mime_type(mime_type) else {
+        return Ok(None);
+    };
+    let text = match kind {
+        DocumentKind::Pdf => pdf_extract::extract_text_from_mem(content)
+            .map_err(|e| AiCoreutilsError::InvalidInput(format!("Cannot extract PDF text: {}", e)))?,
+        DocumentKind::Docx => docx_text(content)
+            .map_err(|e| AiCoreutilsError::InvalidInput(format!("Cannot extract DOCX text: {}", e)))?,
+    };
+    Ok(Some(text))
+}
+
+/// Extract the text of a document; always `None` without the `documents` feature
+#[cfg(not(feature = "documents"))]
+pub fn extract(_mime_type: &str, _content: &[u8]) -> Result<Option<String>> {
+    Ok(None)
+}
+
+/// Text of the body, headers, footers and notes of a Word document
+#[cfg(feature = "documents")]
+fn docx_text(content: &[u8]) -> std::result::Result<String, Box<dyn std::error::Error>> {
+    use std::io::Read;
+
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(content))?;
+    // The body first, then the other text parts in a stable order
+    let mut parts: Vec<String> = archive
+        .file_names()
+        .filter(|name| {
+            let Some(part) = name.strip_prefix("word/").and_then(|n| n.strip_suffix(".xml")) else {
+                return false;
+            };
+            let base = part.trim_end_matches(|c: char| c.is_ascii_digit());
+            matches!(base, "document" | "header" | "footer" | "footnotes" | "endnotes")
+        })
+        .map(str::to_string)
+        .collect();
+    parts.sort_by_key(|name| (name != "word/document.xml", name.clone()));
+    if parts.first().map(String::as_str) != Some("word/document.xml") {
+        return Err("no word/document.xml".into());
+    }
+
+    let mut text = String::new();
+    for name in parts {
+        let mut xml = String::new();
+        archive.by_name(&name)?.read_to_string(&mut xml)?;
+        wordml_text(&xml, &mut text)?;
+    }
+    Ok(text)
+}
+
+/// Append the text runs of a WordprocessingML part to `out`
+#[cfg(feature = "documents")]
+fn wordml_text(xml: &str, out: &mut String) -> std::result::Result<(), quick_xml::Error> {
+    use quick_xml::escape::resolve_predefined_entity;
+    use quick_xml::events::Event;
+
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut in_text = false;
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) if e.local_name().as_ref() == b"t" => in_text = true,
+            Event::End(e) => match e.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"p" => out.push('\n'),
+                _ => {}
+            },
+            Event::Empty(e) => match e.local_name().as_ref() {
+                b"tab" => out.push('\t'),
+                b"br" | b"cr" => out.push('\n'),
+                _ => {}
+            },
+            Event::Text(e) if in_text => out.push_str(&e.decode()?),
+            Event::GeneralRef(e) if in_text => {
+                if let Some(c) = e.resolve_char_ref()? {
+                    out.push(c);
+                } else if let Some(entity) = resolve_predefined_entity(&e.decode()?) {
+                    out.push_str(entity);
+                }
+            }
+            Event::Eof => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(all(test, feature = "documents"))]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn docx(parts: &[(&str, &str)]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, xml) in parts {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(xml.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    /// A one-page PDF showing `text` in Helvetica, with a correct xref table
+    fn pdf(text: &str) -> Vec<u8> {
+        let stream = format!("BT /F1 12 Tf 72 720 Td ({}) Tj ET", text);
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R \
+             /Resources << /Font << /F1 5 0 R >> >> >>"
+                .to_string(),
+            format!("<< /Length {} >>\nstream\n{}\nendstream", stream.len(), stream),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+        ];
+        let mut out = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+        }
+        let xref = out.len();
+        out.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+        for offset in offsets {
+            out.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        out.extend_from_slice(
+            format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).as_bytes(),
+        );
+        out
+    }
+
+    #[test]
+    fn test_docx_text() {
+        let body = r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>
+            <w:p><w:r><w:t>Contact:</w:t></w:r><w:r><w:tab/><w:t xml:space="preserve">jane@example.com &amp; co</w:t></w:r></w:p>
+            <w:p><w:r><w:instrText>HYPERLINK</w:instrText><w:t>SSN 123-45-6789</w:t><w:br/><w:t>end</w:t></w:r></w:p>
+            </w:body></w:document>"#;
+        let footer = r#"<w:ftr xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:p><w:r><w:t>Confidential</w:t></w:r></w:p></w:ftr>"#;
+        let file = docx(&[("[Content_Types].xml", "<Types/>"), ("word/footer1.xml", footer), ("word/document.xml", body)]);
+
+        let text = extract(DOCX_MIME_TYPE, &file).unwrap().unwrap();
+        assert_eq!(text, "Contact:\tjane@example.com & co\nSSN 123-45-6789\nend\nConfidential\n");
+
+        let not_word = docx(&[("word/other.xml", "<x/>")]);
+        assert!(extract(DOCX_MIME_TYPE, &not_word).is_err());
+        assert!(extract(DOCX_MIME_TYPE, b"not a zip").is_err());
+    }
+
+    #[test]
+    fn test_pdf_text() {
+        let text = extract("application/pdf", &pdf("Invoice for jane@example.com")).unwrap().unwrap();
+        assert!(text.contains("Invoice for jane@example.com"), "{:?}", text);
+        assert!(extract("application/pdf", b"%PDF-1.4 truncated").is_err());
+        assert_eq!(extract("text/plain", b"hello").unwrap(), None);
+    }
+}
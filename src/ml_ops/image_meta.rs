@@ -0,0 +1,221 @@
+//! Image metadata for file classification
+//!
+//! With the `images` feature, [`extract`] reads an image's header for its
+//! format, dimensions and pixel layout without decoding the pixels, and its
+//! EXIF block for the camera, capture time, orientation and GPS position.
+//! Without the feature the types still exist, so classification records
+//! have the same shape, but [`extract`] always returns `None`.
+
+use serde::{Deserialize, Serialize};
+
+/// Header information of an image file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageMetadata {
+    /// Format detected from the content (`png`, `jpeg`, `gif`, `webp`, `bmp`, `tiff`)
+    pub format: String,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Pixel layout (`rgb8`, `rgba8`, `l8`, `rgba16`, ...)
+    pub color_type: String,
+    /// Bits per pixel across all channels
+    pub bits_per_pixel: u16,
+    /// Whether the image has an alpha channel
+    pub has_alpha: bool,
+    /// EXIF basics, for images that carry them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exif: Option<ExifSummary>,
+}
+
+/// The commonly useful EXIF fields
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExifSummary {
+    /// Camera manufacturer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub make: Option<String>,
+    /// Camera model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Software that wrote the image
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub software: Option<String>,
+    /// When the photo was taken, as ISO 8601 (with an offset if recorded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taken: Option<String>,
+    /// EXIF orientation, 1 (upright) to 8
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<u32>,
+    /// GPS latitude in decimal degrees, negative for south
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    /// GPS longitude in decimal degrees, negative for west
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+}
+
+/// Read the metadata of an image, or `None` if it cannot be parsed
+#[cfg(feature = "images")]
+pub fn extract(content: &[u8]) -> Option<ImageMetadata> {
+    use image::{ImageDecoder, ImageReader};
+
+    let reader = ImageReader::new(std::io::Cursor::new(content))
+        .with_guessed_format()
+        .ok()?;
+    let format = reader.format()?;
+    let decoder = reader.into_decoder().ok()?;
+    let (width, height) = decoder.dimensions();
+    let color = decoder.color_type();
+
+    Some(ImageMetadata {
+        format: format!("{:?}", format).to_lowercase(),
+        width,
+        height,
+        color_type: format!("{:?}", color).to_lowercase(),
+        bits_per_pixel: color.bits_per_pixel(),
+        has_alpha: color.has_alpha(),
+        exif: exif_summary(content),
+    })
+}
+
+/// Read the metadata of an image; always `None` without the `images` feature
+#[cfg(not(feature = "images"))]
+pub fn extract(_content: &[u8]) -> Option<ImageMetadata> {
+    None
+}
+
+#[cfg(feature = "images")]
+fn exif_summary(content: &[u8]) -> Option<ExifSummary> {
+    use exif::{In, Tag, Value};
+
+    let exif = exif::Reader::new()
+        .read_from_container(&mut std::io::Cursor::new(content))
+        .ok()?;
+    let ascii = |tag| match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(values) => values.first().cloned(),
+        _ => None,
+    };
+    let text = |tag| {
+        let value = String::from_utf8_lossy(&ascii(tag)?).trim_matches(['\0', ' ']).to_string();
+        (!value.is_empty()).then_some(value)
+    };
+    let coordinate = |tag, reference| {
+        let Value::Rational(dms) = &exif.get_field(tag, In::PRIMARY)?.value else {
+            return None;
+        };
+        let degrees: f64 = dms.iter().zip([1.0, 60.0, 3600.0]).map(|(part, unit)| part.to_f64() / unit).sum();
+        let negative = matches!(text(reference).as_deref(), Some("S" | "W"));
+        Some(if negative { -degrees } else { degrees })
+    };
+
+    // Prefer the capture time over the last-modified time
+    let taken = [(Tag::DateTimeOriginal, Tag::OffsetTimeOriginal), (Tag::DateTime, Tag::OffsetTime)]
+        .into_iter()
+        .find_map(|(time, offset)| {
+            let mut time = exif::DateTime::from_ascii(&ascii(time)?).ok()?;
+            if let Some(offset) = ascii(offset) {
+                time.parse_offset(&offset).ok();
+            }
+            exif_time(&time)
+        });
+
+    let summary = ExifSummary {
+        make: text(Tag::Make),
+        model: text(Tag::Model),
+        software: text(Tag::Software),
+        taken,
+        orientation: exif
+            .get_field(Tag::Orientation, In::PRIMARY)
+            .and_then(|field| field.value.get_uint(0)),
+        latitude: coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef),
+        longitude: coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef),
+    };
+    (summary != ExifSummary::default()).then_some(summary)
+}
+
+/// An EXIF date-time as ISO 8601, keeping it zoneless when no offset was recorded
+#[cfg(feature = "images")]
+fn exif_time(time: &exif::DateTime) -> Option<String> {
+    use chrono::{FixedOffset, NaiveDate, TimeZone};
+
+    let local = NaiveDate::from_ymd_opt(time.year as i32, time.month as u32, time.day as u32)?
+        .and_hms_opt(time.hour as u32, time.minute as u32, time.second as u32)?;
+    match time.offset {
+        Some(minutes) => {
+            let zone = FixedOffset::east_opt(minutes as i32 * 60)?;
+            zone.from_local_datetime(&local).single().map(|t| t.to_rfc3339())
+        }
+        None => Some(local.format("%Y-%m-%dT%H:%M:%S").to_string()),
+    }
+}
+
+#[cfg(all(test, feature = "images"))]
+mod tests {
+    use super::*;
+    use exif::experimental::Writer;
+    use exif::{Field, In, Rational, Tag, Value};
+    use std::io::Cursor;
+
+    fn encode(image: image::DynamicImage, format: image::ImageFormat) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image.write_to(&mut Cursor::new(&mut bytes), format).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_png_dimensions_and_color() {
+        let png = encode(image::RgbaImage::new(3, 2).into(), image::ImageFormat::Png);
+        let meta = extract(&png).unwrap();
+        assert_eq!((meta.format.as_str(), meta.width, meta.height), ("png", 3, 2));
+        assert_eq!((meta.color_type.as_str(), meta.bits_per_pixel, meta.has_alpha), ("rgba8", 32, true));
+        assert_eq!(meta.exif, None);
+        assert_eq!(extract(b"not an image"), None);
+    }
+
+    #[test]
+    fn test_jpeg_exif() {
+        let jpeg = encode(image::RgbImage::new(8, 4).into(), image::ImageFormat::Jpeg);
+
+        let ascii = |tag, text: &str| Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![text.as_bytes().to_vec()]),
+        };
+        let dms = |d, m, s| Value::Rational(vec![Rational::from((d, 1)), Rational::from((m, 1)), Rational::from((s, 1))]);
+        let fields = [
+            ascii(Tag::Make, "Acme"),
+            ascii(Tag::Model, "Shooter 3000"),
+            ascii(Tag::DateTimeOriginal, "2026:03:04 05:06:07"),
+            ascii(Tag::OffsetTimeOriginal, "+09:00"),
+            Field { tag: Tag::Orientation, ifd_num: In::PRIMARY, value: Value::Short(vec![6]) },
+            ascii(Tag::GPSLatitudeRef, "S"),
+            Field { tag: Tag::GPSLatitude, ifd_num: In::PRIMARY, value: dms(33, 52, 36) },
+            ascii(Tag::GPSLongitudeRef, "E"),
+            Field { tag: Tag::GPSLongitude, ifd_num: In::PRIMARY, value: dms(151, 12, 36) },
+        ];
+        let mut writer = Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut tiff = Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+
+        // APP1 segment right after the SOI marker
+        let payload = [b"Exif\0\0".as_slice(), tiff.get_ref()].concat();
+        let mut with_exif = jpeg[..2].to_vec();
+        with_exif.extend_from_slice(&[0xff, 0xe1]);
+        with_exif.extend_from_slice(&(payload.len() as u16 + 2).to_be_bytes());
+        with_exif.extend_from_slice(&payload);
+        with_exif.extend_from_slice(&jpeg[2..]);
+
+        let meta = extract(&with_exif).unwrap();
+        assert_eq!((meta.format.as_str(), meta.width, meta.height, meta.color_type.as_str()), ("jpeg", 8, 4, "rgb8"));
+        let exif = meta.exif.unwrap();
+        assert_eq!(exif.make.as_deref(), Some("Acme"));
+        assert_eq!(exif.model.as_deref(), Some("Shooter 3000"));
+        assert_eq!(exif.taken.as_deref(), Some("2026-03-04T05:06:07+09:00"));
+        assert_eq!(exif.orientation, Some(6));
+        assert!((exif.latitude.unwrap() + 33.87667).abs() < 1e-4);
+        assert!((exif.longitude.unwrap() - 151.21).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,237 @@
+//! Schema and size of SQLite databases
+//!
+//! SQLite files are recognized by their header whatever their extension.
+//! With the `sqlite` feature, [`inspect`] opens one read-only and lists
+//! its tables and views with their columns, indexes, `CREATE` statement
+//! and an estimated row count. Estimates come from `sqlite_stat1` when the
+//! database has been analyzed, otherwise from the largest rowid, so even
+//! very large tables are never scanned. Without the feature nothing is
+//! inspected.
+
+use crate::error::Result;
+use serde::Serialize;
+use std::path::Path;
+
+/// The 16-byte header every SQLite 3 database starts with
+pub const SQLITE_MAGIC: &[u8] = b"SQLite format 3\0";
+
+/// MIME type of SQLite databases
+pub const SQLITE_MIME_TYPE: &str = "application/vnd.sqlite3";
+
+/// One column of a table or view
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ColumnInfo {
+    /// Column name
+    pub name: String,
+    /// Declared type, empty if none
+    #[serde(rename = "type")]
+    pub data_type: String,
+    /// Whether the column is `NOT NULL`
+    pub not_null: bool,
+    /// Whether the column is part of the primary key
+    pub primary_key: bool,
+    /// Default value expression
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+/// A table or view
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TableInfo {
+    /// Table name
+    pub name: String,
+    /// `table` or `view`
+    pub kind: String,
+    /// Columns in declaration order
+    pub columns: Vec<ColumnInfo>,
+    /// Names of the indexes on the table
+    pub indexes: Vec<String>,
+    /// Approximate number of rows
+    pub row_estimate: Option<u64>,
+    /// Where the estimate came from: `stat1` or `rowid`
+    pub row_estimate_source: Option<&'static str>,
+    /// The `CREATE` statement
+    pub sql: Option<String>,
+}
+
+/// Header information and schema of a database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DatabaseInfo {
+    /// Page size in bytes
+    pub page_size: u64,
+    /// Number of pages
+    pub page_count: u64,
+    /// Number of unused pages
+    pub freelist_count: u64,
+    /// Text encoding (`UTF-8`, `UTF-16le`, `UTF-16be`)
+    pub encoding: String,
+    /// Application-defined schema version
+    pub user_version: i64,
+    /// Tables and views, by name
+    pub tables: Vec<TableInfo>,
+}
+
+/// Whether `content` starts with the SQLite header
+pub fn is_sqlite(content: &[u8]) -> bool {
+    content.starts_with(SQLITE_MAGIC)
+}
+
+/// Inspect the database at `path`, opened read-only
+#[cfg(feature = "sqlite")]
+pub fn inspect(path: &Path) -> Result<Option<DatabaseInfo>> {
+    use crate::error::AiCoreutilsError;
+
+    read_schema(path)
+        .map(Some)
+        .map_err(|e| AiCoreutilsError::InvalidInput(format!("Cannot read SQLite database: {}", e)))
+}
+
+/// Inspect the database at `path`; always `None` without the `sqlite` feature
+#[cfg(not(feature = "sqlite"))]
+pub fn inspect(_path: &Path) -> Result<Option<DatabaseInfo>> {
+    Ok(None)
+}
+
+#[cfg(feature = "sqlite")]
+fn read_schema(path: &Path) -> rusqlite::Result<DatabaseInfo> {
+    use rusqlite::{Connection, OpenFlags, OptionalExtension};
+    use std::collections::HashMap;
+
+    let db = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX)?;
+    let pragma = |name: &str| db.query_row(&format!("PRAGMA {}", name), [], |row| row.get::<_, i64>(0));
+
+    // The first number of a stat1 row is the row count of the table or index
+    let has_stat1: bool = db.query_row(
+        "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name = 'sqlite_stat1'",
+        [],
+        |row| row.get(0),
+    )?;
+    let mut stat1: HashMap<String, u64> = HashMap::new();
+    if has_stat1 {
+        let mut rows = db.prepare("SELECT tbl, max(CAST(stat AS INTEGER)) FROM sqlite_stat1 GROUP BY tbl")?;
+        for row in rows.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))? {
+            let (table, rows) = row?;
+            stat1.insert(table, rows.max(0) as u64);
+        }
+    }
+
+    let mut schema = db.prepare(
+        "SELECT name, type, sql FROM sqlite_master \
+         WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%' ORDER BY name",
+    )?;
+    let entries = schema
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get(2)?)))?
+        .collect::<rusqlite::Result<Vec<(String, String, Option<String>)>>>()?;
+
+    let mut columns = db.prepare("SELECT name, type, \"notnull\", dflt_value, pk FROM pragma_table_info(?1)")?;
+    let mut indexes = db.prepare("SELECT name FROM pragma_index_list(?1) ORDER BY name")?;
+    let mut tables = Vec::with_capacity(entries.len());
+    for (name, kind, sql) in entries {
+        let table_columns = columns
+            .query_map([&name], |row| {
+                Ok(ColumnInfo {
+                    name: row.get(0)?,
+                    data_type: row.get(1)?,
+                    not_null: row.get(2)?,
+                    default: row.get(3)?,
+                    primary_key: row.get::<_, i64>(4)? > 0,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        let table_indexes = indexes.query_map([&name], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+
+        let (row_estimate, row_estimate_source) = match stat1.get(&name) {
+            Some(&rows) => (Some(rows), Some("stat1")),
+            // Views and WITHOUT ROWID tables have no rowid to go by
+            None if kind == "table" => {
+                let max_rowid = db
+                    .query_row(&format!("SELECT max(rowid) FROM \"{}\"", name.replace('"', "\"\"")), [], |row| {
+                        row.get::<_, Option<i64>>(0)
+                    })
+                    .optional()
+                    .ok()
+                    .flatten()
+                    .flatten();
+                match max_rowid {
+                    Some(rowid) => (Some(rowid.max(0) as u64), Some("rowid")),
+                    None => (None, None),
+                }
+            }
+            None => (None, None),
+        };
+
+        tables.push(TableInfo {
+            name,
+            kind,
+            columns: table_columns,
+            indexes: table_indexes,
+            row_estimate,
+            row_estimate_source,
+            sql,
+        });
+    }
+
+    Ok(DatabaseInfo {
+        page_size: pragma("page_size")? as u64,
+        page_count: pragma("page_count")? as u64,
+        freelist_count: pragma("freelist_count")? as u64,
+        encoding: db.query_row("PRAGMA encoding", [], |row| row.get(0))?,
+        user_version: pragma("user_version")?,
+        tables,
+    })
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_inspect_schema_and_estimates() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("app.db");
+        let db = Connection::open(&path).unwrap();
+        db.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL, active INTEGER DEFAULT 1);
+             CREATE INDEX users_email ON users(email);
+             CREATE TABLE events (id INTEGER PRIMARY KEY, user_id INTEGER, kind TEXT);
+             CREATE TABLE empty (k TEXT PRIMARY KEY, v TEXT) WITHOUT ROWID;
+             CREATE VIEW active_users AS SELECT id, email FROM users WHERE active;
+             INSERT INTO users (email) VALUES ('a@example.com'), ('b@example.com'), ('c@example.com');
+             WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 50)
+             INSERT INTO events (user_id, kind) SELECT i % 3, 'click' FROM n;
+             ANALYZE users;
+             PRAGMA user_version = 7;",
+        )
+        .unwrap();
+        drop(db);
+
+        let info = inspect(&path).unwrap().unwrap();
+        assert_eq!((info.encoding.as_str(), info.user_version), ("UTF-8", 7));
+        assert!(info.page_count > 0 && info.page_size >= 512);
+
+        let names: Vec<&str> = info.tables.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["active_users", "empty", "events", "users"]);
+
+        let users = &info.tables[3];
+        assert_eq!(users.kind, "table");
+        assert_eq!((users.row_estimate, users.row_estimate_source), (Some(3), Some("stat1")));
+        assert_eq!(users.indexes, ["users_email"]);
+        assert_eq!(users.columns[0], ColumnInfo {
+            name: "id".into(),
+            data_type: "INTEGER".into(),
+            not_null: false,
+            primary_key: true,
+            default: None,
+        });
+        assert!(users.columns[1].not_null);
+        assert_eq!(users.columns[2].default.as_deref(), Some("1"));
+        assert!(users.sql.as_deref().unwrap().starts_with("CREATE TABLE users"));
+
+        assert_eq!((info.tables[2].row_estimate, info.tables[2].row_estimate_source), (Some(50), Some("rowid")));
+        assert_eq!(info.tables[1].row_estimate, None);
+        assert_eq!((info.tables[0].kind.as_str(), info.tables[0].columns.len()), ("view", 2));
+
+        assert!(inspect(&dir.path().join("missing.db")).is_err());
+    }
+}
@@ -0,0 +1,502 @@
+//! Multi-line stack trace detection
+//!
+//! [`StackTraceDetector`] recognizes the stack traces that fill CI logs and
+//! groups each one, however many lines it spans, into a single
+//! [`StackTrace`] with its exception, message and parsed frames:
+//!
+//! - Python tracebacks, including chained ones ("During handling of the
+//!   above exception ...")
+//! - Java stack traces with `Caused by:` sections
+//! - Rust panics, with or without a `stack backtrace:`
+//! - Node.js errors followed by `at` frames
+//!
+//! Detection is line-based: a trace starts at a recognizable first line
+//! (for Java and Node, an exception line immediately followed by a frame)
+//! and extends over every line that belongs to it.
+
+use crate::error::{AiCoreutilsError, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Language whose runtime printed a trace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceLanguage {
+    /// Python traceback
+    Python,
+    /// Java (or other JVM) stack trace
+    Java,
+    /// Rust panic
+    Rust,
+    /// Node.js error stack
+    Node,
+}
+
+impl TraceLanguage {
+    /// Name used in output records
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TraceLanguage::Python => "python",
+            TraceLanguage::Java => "java",
+            TraceLanguage::Rust => "rust",
+            TraceLanguage::Node => "node",
+        }
+    }
+}
+
+/// One frame of a stack trace
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackFrame {
+    /// Function or method name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    /// Source file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// Line number in the file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+    /// Column in the line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<u64>,
+}
+
+/// A complete stack trace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackTrace {
+    /// Runtime that printed the trace
+    pub language: TraceLanguage,
+    /// Exception class (`panic` for Rust panics)
+    pub exception: String,
+    /// Exception or panic message
+    pub message: String,
+    /// Frames in the order printed, including those of causes
+    pub frames: Vec<StackFrame>,
+    /// Java `Caused by:` exceptions and earlier exceptions of a Python chain
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub causes: Vec<String>,
+    /// Thread named by Java and Rust traces
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread: Option<String>,
+    /// Number of lines the trace spans
+    pub line_count: usize,
+}
+
+/// Finds and parses stack traces
+pub struct StackTraceDetector {
+    python_frame: Regex,
+    java_exception: Regex,
+    java_frame: Regex,
+    node_exception: Regex,
+    node_frame: Regex,
+    rust_panic: Regex,
+    rust_frame: Regex,
+    rust_location: Regex,
+}
+
+impl StackTraceDetector {
+    /// Compile the detector's patterns
+    pub fn new() -> Result<Self> {
+        let compile = |pattern: &str| {
+            Regex::new(pattern)
+                .map_err(|e| AiCoreutilsError::InvalidInput(format!("Invalid stack trace regex: {}", e)))
+        };
+        Ok(Self {
+            python_frame: compile(r#"^\s+File "(?P<file>[^"]+)", line (?P<line>\d+)(?:, in (?P<function>.+))?$"#)?,
+            java_exception: compile(
+                r#"^(?:Exception in thread "(?P<thread>[^"]*)" )?(?P<class>[A-Za-z_$][\w$]*(?:\.[A-Za-z_$][\w$]*)+)(?::\s*(?P<msg>.*))?$"#,
+            )?,
+            java_frame: compile(r"^\s+at (?P<function>[\w$.<>/]+)\((?P<location>[^)]*)\)$")?,
+            node_exception: compile(
+                r"^(?:Uncaught )?(?P<class>(?:[A-Z][\w$]*)?(?:Error|Exception))(?: \[[A-Z_]+\])?(?::\s*(?P<msg>.*))?$",
+            )?,
+            node_frame: compile(
+                r"^\s+at (?:(?:async )?(?P<function>[^()]+?) \((?P<location>[^()]+)\)|(?P<bare>[^()\s]+))$",
+            )?,
+            rust_panic: compile(
+                r"^thread '(?P<thread>[^']*)' panicked at (?:'(?P<msg>.*)', )?(?P<file>[^\s:]+):(?P<line>\d+):(?P<column>\d+):?$",
+            )?,
+            rust_frame: compile(r"^\s+\d+: (?P<function>.+)$")?,
+            rust_location: compile(r"^\s+at (?P<file>.+?):(?P<line>\d+)(?::(?P<column>\d+))?$")?,
+        })
+    }
+
+    /// Whether `line` could start a trace, given the line after it
+    ///
+    /// A cheap check to run before [`StackTraceDetector::parse`].
+    pub fn starts_trace(&self, line: &str, next: Option<&str>) -> bool {
+        if line.starts_with("Traceback (most recent call last)") {
+            return true;
+        }
+        if line.starts_with("thread '") {
+            return self.rust_panic.is_match(line);
+        }
+        next.is_some_and(|next| next.trim_start().starts_with("at "))
+            && (self.java_exception.is_match(line) || self.node_exception.is_match(line))
+    }
+
+    /// Parse the trace that starts at `lines[0]`, if there is one
+    pub fn parse(&self, lines: &[&str]) -> Option<StackTrace> {
+        let first = *lines.first()?;
+        if first.starts_with("Traceback (most recent call last)") {
+            return Some(self.parse_python(lines));
+        }
+        if let Some(trace) = self.parse_rust(lines) {
+            return Some(trace);
+        }
+        let next = lines.get(1)?;
+        if self.java_frame.is_match(next) {
+            return self.parse_java(lines);
+        }
+        if self.node_frame.is_match(next) {
+            return self.parse_node(lines);
+        }
+        None
+    }
+
+    /// Every trace in `text`, with its byte range
+    ///
+    /// Ranges cover the trace's lines without the final line terminator.
+    pub fn find_all(&self, text: &str) -> Vec<(Range<usize>, StackTrace)> {
+        let mut starts = Vec::new();
+        let mut lines = Vec::new();
+        let mut offset = 0;
+        for line in text.split_inclusive('\n') {
+            starts.push(offset);
+            offset += line.len();
+            let line = line.strip_suffix('\n').unwrap_or(line);
+            lines.push(line.strip_suffix('\r').unwrap_or(line));
+        }
+
+        let mut traces = Vec::new();
+        let mut i = 0;
+        while i < lines.len() {
+            let trace = self
+                .starts_trace(lines[i], lines.get(i + 1).copied())
+                .then(|| self.parse(&lines[i..]))
+                .flatten();
+            match trace {
+                Some(trace) => {
+                    let last = i + trace.line_count - 1;
+                    traces.push((starts[i]..starts[last] + lines[last].len(), trace));
+                    i = last + 1;
+                }
+                None => i += 1,
+            }
+        }
+        traces
+    }
+
+    fn parse_python(&self, lines: &[&str]) -> StackTrace {
+        let mut frames = Vec::new();
+        let mut causes = Vec::new();
+        let mut exception: Option<&str> = None;
+        let mut line_count = 1;
+
+        let mut i = 1;
+        while let Some(&line) = lines.get(i) {
+            if let Some(previous) = exception {
+                let next_chains = lines.get(i + 1).is_some_and(|next| is_python_chain_message(next));
+                if is_python_chain_message(line) {
+                    causes.push(previous.to_string());
+                    exception = None;
+                } else if !(line.is_empty() && next_chains) {
+                    break;
+                }
+            } else if line.is_empty() {
+                // Blank lines only follow a chain message
+                if causes.is_empty() || line_count != i {
+                    break;
+                }
+            } else if line.starts_with(' ') || line.starts_with('\t') || line.starts_with("Traceback (") {
+                if let Some(caps) = self.python_frame.captures(line) {
+                    frames.push(StackFrame {
+                        function: caps.name("function").map(|f| f.as_str().to_string()),
+                        file: Some(caps["file"].to_string()),
+                        line: caps["line"].parse().ok(),
+                        column: None,
+                    });
+                }
+            } else {
+                // The unindented line after the frames names the exception
+                exception = Some(line.trim());
+            }
+            i += 1;
+            if !line.is_empty() {
+                line_count = i;
+            }
+        }
+
+        let exception = exception.unwrap_or_default();
+        let (class, message) = exception.split_once(':').unwrap_or((exception, ""));
+        StackTrace {
+            language: TraceLanguage::Python,
+            exception: class.trim().to_string(),
+            message: message.trim().to_string(),
+            frames,
+            causes,
+            thread: None,
+            line_count,
+        }
+    }
+
+    fn parse_java(&self, lines: &[&str]) -> Option<StackTrace> {
+        let caps = self.java_exception.captures(lines[0])?;
+        let mut trace = StackTrace {
+            language: TraceLanguage::Java,
+            exception: caps["class"].to_string(),
+            message: caps.name("msg").map_or("", |m| m.as_str()).trim().to_string(),
+            frames: Vec::new(),
+            causes: Vec::new(),
+            thread: caps.name("thread").map(|t| t.as_str().to_string()),
+            line_count: 1,
+        };
+
+        for &line in &lines[1..] {
+            if let Some(frame) = self.java_frame.captures(line) {
+                let location = &frame["location"];
+                let (file, number) = match location.rsplit_once(':') {
+                    Some((file, number)) => (Some(file), number.parse().ok()),
+                    None => (location.contains('.').then_some(location), None),
+                };
+                trace.frames.push(StackFrame {
+                    function: Some(frame["function"].to_string()),
+                    file: file.map(str::to_string),
+                    line: number,
+                    column: None,
+                });
+            } else if let Some(cause) = line.strip_prefix("Caused by: ") {
+                trace.causes.push(cause.trim().to_string());
+            } else {
+                let trimmed = line.trim_start();
+                let nested = line.len() > trimmed.len()
+                    && (trimmed.starts_with("...") || trimmed.starts_with("Suppressed: "));
+                if !nested {
+                    break;
+                }
+            }
+            trace.line_count += 1;
+        }
+        Some(trace)
+    }
+
+    fn parse_node(&self, lines: &[&str]) -> Option<StackTrace> {
+        let caps = self.node_exception.captures(lines[0])?;
+        let mut trace = StackTrace {
+            language: TraceLanguage::Node,
+            exception: caps["class"].to_string(),
+            message: caps.name("msg").map_or("", |m| m.as_str()).trim().to_string(),
+            frames: Vec::new(),
+            causes: Vec::new(),
+            thread: None,
+            line_count: 1,
+        };
+
+        for &line in &lines[1..] {
+            let Some(frame) = self.node_frame.captures(line) else {
+                break;
+            };
+            let location = frame.name("location").or(frame.name("bare")).map_or("", |m| m.as_str());
+            // `file:line:column`, where the file may itself contain colons
+            let mut parts = location.rsplitn(3, ':');
+            let column = parts.next().and_then(|c| c.parse().ok());
+            let number = parts.next().and_then(|l| l.parse().ok());
+            let file = match (number, parts.next()) {
+                (Some(_), Some(file)) => file,
+                _ => location,
+            };
+            trace.frames.push(StackFrame {
+                function: frame.name("function").map(|f| f.as_str().to_string()),
+                file: Some(file.to_string()),
+                line: number,
+                column: column.filter(|_| number.is_some()),
+            });
+            trace.line_count += 1;
+        }
+        Some(trace)
+    }
+
+    fn parse_rust(&self, lines: &[&str]) -> Option<StackTrace> {
+        let caps = self.rust_panic.captures(lines[0])?;
+        let location = StackFrame {
+            function: None,
+            file: Some(caps["file"].to_string()),
+            line: caps["line"].parse().ok(),
+            column: caps["column"].parse().ok(),
+        };
+        let mut trace = StackTrace {
+            language: TraceLanguage::Rust,
+            exception: "panic".to_string(),
+            message: caps.name("msg").map_or("", |m| m.as_str()).to_string(),
+            frames: Vec::new(),
+            causes: Vec::new(),
+            thread: Some(caps["thread"].to_string()),
+            line_count: 1,
+        };
+
+        let mut i = 1;
+        // Since Rust 1.73 the message follows on the next line
+        if caps.name("msg").is_none() {
+            if let Some(&line) = lines.get(1).filter(|l| !is_rust_panic_trailer(l)) {
+                trace.message = line.to_string();
+                i += 1;
+            }
+        }
+
+        let mut in_backtrace = false;
+        while let Some(&line) = lines.get(i) {
+            if line.starts_with("note: ") {
+                // Hints such as "run with `RUST_BACKTRACE=1`"
+            } else if line == "stack backtrace:" {
+                in_backtrace = true;
+            } else if let Some(frame) = in_backtrace.then(|| self.rust_frame.captures(line)).flatten() {
+                trace.frames.push(StackFrame {
+                    function: Some(frame["function"].trim().to_string()),
+                    ..StackFrame::default()
+                });
+            } else if let Some(at) = in_backtrace.then(|| self.rust_location.captures(line)).flatten() {
+                if let Some(frame) = trace.frames.last_mut() {
+                    frame.file = Some(at["file"].to_string());
+                    frame.line = at["line"].parse().ok();
+                    frame.column = at.name("column").and_then(|c| c.as_str().parse().ok());
+                }
+            } else {
+                break;
+            }
+            i += 1;
+        }
+        trace.line_count = i;
+        if trace.frames.is_empty() {
+            trace.frames.push(location);
+        }
+        Some(trace)
+    }
+}
+
+/// Whether `line` joins two tracebacks of a chained Python exception
+fn is_python_chain_message(line: &str) -> bool {
+    line.starts_with("During handling of the above exception")
+        || line.starts_with("The above exception was the direct cause")
+}
+
+/// Whether `line` follows a Rust panic message rather than being one
+fn is_rust_panic_trailer(line: &str) -> bool {
+    line.starts_with("note: ") || line == "stack backtrace:"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(text: &str) -> Vec<(Range<usize>, StackTrace)> {
+        StackTraceDetector::new().unwrap().find_all(text)
+    }
+
+    #[test]
+    fn test_python_chained() {
+        let text = "\
+log line
+Traceback (most recent call last):
+  File \"a.py\", line 2, in <module>
+    d['x']
+KeyError: 'x'
+
+During handling of the above exception, another exception occurred:
+
+Traceback (most recent call last):
+  File \"a.py\", line 4, in <module>
+RuntimeError: lookup failed
+
+after";
+        let traces = find(text);
+        assert_eq!(traces.len(), 1);
+        let (range, trace) = &traces[0];
+        assert!(text[range.clone()].starts_with("Traceback"));
+        assert!(text[range.clone()].ends_with("lookup failed"));
+        assert_eq!(trace.line_count, 10);
+        assert_eq!(trace.exception, "RuntimeError");
+        assert_eq!(trace.message, "lookup failed");
+        assert_eq!(trace.causes, ["KeyError: 'x'"]);
+        assert_eq!(trace.frames.len(), 2);
+        assert_eq!(trace.frames[1].line, Some(4));
+    }
+
+    #[test]
+    fn test_java() {
+        let text = "\
+Exception in thread \"main\" java.lang.IllegalStateException: not ready
+\tat com.example.App.start(App.java:42)
+\tat java.base/jdk.internal.reflect.Native.invoke0(Native Method)
+Caused by: java.io.IOException: closed
+\tat com.example.Io.read(Io.java:7)
+\t... 2 more
+after";
+        let (range, trace) = &find(text)[0];
+        assert_eq!(range.end, text.len() - "\nafter".len());
+        assert_eq!(trace.thread.as_deref(), Some("main"));
+        assert_eq!(trace.exception, "java.lang.IllegalStateException");
+        assert_eq!(trace.frames.len(), 3);
+        assert_eq!(trace.frames[0].file.as_deref(), Some("App.java"));
+        assert_eq!(trace.frames[0].line, Some(42));
+        assert_eq!(trace.frames[1].file, None);
+        assert_eq!(trace.causes, ["java.io.IOException: closed"]);
+    }
+
+    #[test]
+    fn test_rust_panics() {
+        let text = "\
+thread 'main' panicked at src/main.rs:10:5:
+index out of bounds: the len is 3 but the index is 7
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+thread 'worker' panicked at 'boom', src/lib.rs:3:9
+stack backtrace:
+   0: std::panicking::begin_panic
+             at /rustc/abc/library/std/src/panicking.rs:616:12
+   1: app::run
+             at ./src/lib.rs:3:9
+";
+        let traces = find(text);
+        assert_eq!(traces.len(), 2);
+        let first = &traces[0].1;
+        assert_eq!(first.message, "index out of bounds: the len is 3 but the index is 7");
+        assert_eq!(first.line_count, 3);
+        assert_eq!(first.frames[0].file.as_deref(), Some("src/main.rs"));
+        assert_eq!(first.frames[0].column, Some(5));
+
+        let second = &traces[1].1;
+        assert_eq!(second.thread.as_deref(), Some("worker"));
+        assert_eq!(second.message, "boom");
+        assert_eq!(second.line_count, 6);
+        assert_eq!(second.frames[1].function.as_deref(), Some("app::run"));
+        assert_eq!(second.frames[1].line, Some(3));
+    }
+
+    #[test]
+    fn test_node() {
+        let text = "\
+TypeError: Cannot read properties of undefined (reading 'id')
+    at getUser (/app/src/users.js:12:18)
+    at async Promise.all (index 0)
+    at /app/src/index.js:5:3
+    at node:internal/main/run_main_module:28:49
+done";
+        let (_, trace) = &find(text)[0];
+        assert_eq!(trace.language, TraceLanguage::Node);
+        assert_eq!(trace.exception, "TypeError");
+        assert_eq!(trace.line_count, 5);
+        assert_eq!(trace.frames[0].function.as_deref(), Some("getUser"));
+        assert_eq!(trace.frames[0].file.as_deref(), Some("/app/src/users.js"));
+        assert_eq!((trace.frames[0].line, trace.frames[0].column), (Some(12), Some(18)));
+        assert_eq!(trace.frames[1].file.as_deref(), Some("index 0"));
+        assert_eq!(trace.frames[2].function, None);
+        assert_eq!(trace.frames[3].file.as_deref(), Some("node:internal/main/run_main_module"));
+    }
+
+    #[test]
+    fn test_not_traces() {
+        assert!(find("Error: something\nplain text\n").is_empty());
+        assert!(find("java.lang.String is a class\nnothing else").is_empty());
+        assert!(find("").is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+//! Timestamp normalization
+//!
+//! Dates and times show up in many spellings: RFC 3339, the
+//! `YYYY-MM-DD HH:MM:SS,fff` of logging libraries, access log and syslog
+//! times, bare dates and epoch numbers. The functions here turn each into
+//! ISO 8601 so matches and log entries can be sorted and compared as
+//! strings.
+//!
+//! Times written with a zone keep their offset; times written without one
+//! stay without one rather than being guessed. Epoch numbers are UTC.
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+/// Normalize a timestamp with a date and time to ISO 8601
+///
+/// Accepts RFC 3339 and the `YYYY-MM-DD HH:MM:SS[.,fff]` variants loggers
+/// commonly write. Times without a zone stay without one.
+pub fn normalize_timestamp(text: &str) -> Option<String> {
+    let text = text.trim().replace(',', ".");
+    let iso = match text.as_bytes().get(10) {
+        Some(b' ') => format!("{}T{}", &text[..10], &text[11..]),
+        _ => text,
+    };
+    if let Ok(time) = DateTime::parse_from_rfc3339(&iso) {
+        return Some(time.to_rfc3339());
+    }
+    if let Ok(time) = DateTime::parse_from_str(&iso, "%Y-%m-%dT%H:%M:%S%.f%z") {
+        return Some(time.to_rfc3339());
+    }
+    NaiveDateTime::parse_from_str(&iso, "%Y-%m-%dT%H:%M:%S%.f")
+        .ok()
+        .map(|time| time.format("%Y-%m-%dT%H:%M:%S%.f").to_string())
+}
+
+/// Epoch seconds (or milliseconds, for values past the year 5000) as RFC 3339
+pub fn epoch_timestamp(epoch: f64) -> Option<String> {
+    let millis = if epoch.abs() >= 1e11 { epoch } else { epoch * 1000.0 };
+    Utc.timestamp_millis_opt(millis as i64)
+        .single()
+        .map(|t| t.to_rfc3339())
+}
+
+/// An access log `dd/Mmm/yyyy:hh:mm:ss +zzzz` time as RFC 3339
+pub fn common_log_timestamp(text: &str) -> Option<String> {
+    DateTime::parse_from_str(text.trim(), "%d/%b/%Y:%H:%M:%S %z")
+        .ok()
+        .map(|t| t.to_rfc3339())
+}
+
+/// An RFC 3164 `Mmm dd hh:mm:ss` time in the latest year not after `now`
+pub fn syslog_3164_timestamp(text: &str, now: DateTime<Utc>) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    [now.year(), now.year() - 1].into_iter().find_map(|year| {
+        let time = NaiveDateTime::parse_from_str(&format!("{} {}", year, text), "%Y %b %d %H:%M:%S").ok()?;
+        // Allow a day of clock skew between the logging host and this one
+        (time <= now.naive_utc() + chrono::Duration::days(1))
+            .then(|| time.format("%Y-%m-%dT%H:%M:%S").to_string())
+    })
+}
+
+/// A bare `YYYY-MM-DD`, `YYYY/MM/DD` or `MM/DD/YYYY` date as `YYYY-MM-DD`
+///
+/// Year-last dates are read month first unless the first number cannot be
+/// a month, as in `31/12/2025`.
+pub fn date_only(text: &str) -> Option<String> {
+    let parts: Vec<u32> = text
+        .trim()
+        .split(['-', '/'])
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let [a, b, c] = parts[..] else {
+        return None;
+    };
+    let (year, month, day) = match (a > 999, c > 999) {
+        (true, false) => (a, b, c),
+        (false, true) if a > 12 => (c, b, a),
+        (false, true) => (c, a, b),
+        _ => return None,
+    };
+    NaiveDate::from_ymd_opt(year as i32, month, day).map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+/// Normalize any supported timestamp spelling to ISO 8601
+///
+/// Tries, in order: RFC 3339 and logger date-times, access log times,
+/// syslog times (placed in a year relative to `now`), bare dates, and
+/// all-digit epoch seconds or milliseconds.
+pub fn parse_timestamp(text: &str, now: DateTime<Utc>) -> Option<String> {
+    let text = text.trim();
+    normalize_timestamp(text)
+        .or_else(|| common_log_timestamp(text))
+        .or_else(|| syslog_3164_timestamp(text, now))
+        .or_else(|| date_only(text))
+        .or_else(|| {
+            let digits = text.split_once('.').map_or(text, |(whole, _)| whole);
+            let epoch = matches!(digits.len(), 10 | 13) && digits.bytes().all(|b| b.is_ascii_digit());
+            epoch.then(|| text.parse().ok().and_then(epoch_timestamp)).flatten()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syslog_3164_year() {
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(syslog_3164_timestamp("Dec 31 23:59:00", now).as_deref(), Some("2025-12-31T23:59:00"));
+        assert_eq!(syslog_3164_timestamp("Jan  1 00:00:00", now).as_deref(), Some("2026-01-01T00:00:00"));
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        let now = Utc.with_ymd_and_hms(2026, 6, 1, 0, 0, 0).unwrap();
+        let parse = |text| parse_timestamp(text, now);
+        assert_eq!(parse("2026-01-02T03:04:05Z").as_deref(), Some("2026-01-02T03:04:05+00:00"));
+        assert_eq!(parse("2026-01-02 03:04:05,250").as_deref(), Some("2026-01-02T03:04:05.250"));
+        assert_eq!(parse("10/Oct/2025:13:55:36 -0700").as_deref(), Some("2025-10-10T13:55:36-07:00"));
+        assert_eq!(parse("May 31 23:00:00").as_deref(), Some("2026-05-31T23:00:00"));
+        assert_eq!(parse("2026/1/2").as_deref(), Some("2026-01-02"));
+        assert_eq!(parse("01/02/2026").as_deref(), Some("2026-01-02"));
+        assert_eq!(parse("31/12/2025").as_deref(), Some("2025-12-31"));
+        assert_eq!(parse("1767225600").as_deref(), Some("2026-01-01T00:00:00+00:00"));
+        assert_eq!(parse("1767225600500").as_deref(), Some("2026-01-01T00:00:00.500+00:00"));
+        assert_eq!(parse("2026-02-30"), None);
+        assert_eq!(parse("12345"), None);
+    }
+}
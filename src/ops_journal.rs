@@ -0,0 +1,819 @@
+//! Operation journal for undoing file changes
+//!
+//! Mutating utilities (ai-cp, ai-mv, ai-rm, ai-chmod and ai-sd `--write`)
+//! record each invocation as an [`Operation`]: the list of [`Action`]s it
+//! performed, with enough state to reverse them. Files are backed up before
+//! they are overwritten, and removed files are moved into the journal
+//! rather than deleted, unless they are larger than
+//! `journal.max_backup_bytes` or on another filesystem, in which case they
+//! are deleted and recorded as such. [`Journal::undo`] (used by ai-undo)
+//! reverses the most recent operations, newest first.
+//!
+//! Journaling is off unless `journal.enabled` or `AI_COREUTILS_JOURNAL`
+//! turns it on.
+//!
+//! The journal is a directory holding `journal.jsonl`, one line per
+//! operation plus one per undo, and `backups/<operation id>/`. It lives in
+//! `ai-coreutils/journal` under the platform data directory, or in the
+//! [session](crate::session) directory inside a session, unless
+//! `journal.dir` or `AI_COREUTILS_JOURNAL_DIR` says otherwise, and keeps the
+//! last `journal.max_operations` operations. Writers serialize on an
+//! advisory lock on `journal.lock`.
+
+use crate::config;
+use crate::error::{AiCoreutilsError, Result};
+use crate::fs_utils::{FileLock, LockMode};
+use crate::policy::{self, Access};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+const JOURNAL_FILE: &str = "journal.jsonl";
+const LOCK_FILE: &str = "journal.lock";
+const BACKUP_DIR: &str = "backups";
+
+/// One change made by an operation, with what is needed to reverse it
+///
+/// Paths are absolute.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// A file or link was created where nothing existed
+    Created {
+        /// Created path
+        path: PathBuf,
+    },
+    /// A directory was created
+    CreatedDir {
+        /// Created directory
+        path: PathBuf,
+    },
+    /// A file or link was replaced
+    Overwritten {
+        /// Replaced path
+        path: PathBuf,
+        /// Copy of what was there before
+        backup: PathBuf,
+    },
+    /// A file or link was removed
+    Removed {
+        /// Removed path
+        path: PathBuf,
+        /// Where it was moved to instead of being deleted
+        backup: PathBuf,
+    },
+    /// A file or link was deleted without a backup, so it cannot be restored
+    Deleted {
+        /// Deleted path
+        path: PathBuf,
+        /// Size in bytes
+        size: u64,
+    },
+    /// An empty directory was removed
+    RemovedDir {
+        /// Removed directory
+        path: PathBuf,
+        /// Its permission bits
+        #[serde(with = "octal")]
+        mode: u32,
+    },
+    /// A file or directory was moved
+    Moved {
+        /// Original path
+        from: PathBuf,
+        /// New path
+        to: PathBuf,
+    },
+    /// Permission bits were changed
+    ModeChanged {
+        /// Changed path
+        path: PathBuf,
+        /// Bits before the change
+        #[serde(with = "octal")]
+        old_mode: u32,
+        /// Bits after the change
+        #[serde(with = "octal")]
+        new_mode: u32,
+    },
+}
+
+impl Action {
+    /// Path the action changed (the destination, for moves)
+    pub fn path(&self) -> &Path {
+        match self {
+            Action::Created { path }
+            | Action::CreatedDir { path }
+            | Action::Overwritten { path, .. }
+            | Action::Removed { path, .. }
+            | Action::Deleted { path, .. }
+            | Action::RemovedDir { path, .. }
+            | Action::ModeChanged { path, .. } => path,
+            Action::Moved { to, .. } => to,
+        }
+    }
+
+    /// Check that the sandbox policy allows reversing the action
+    fn check_policy(&self) -> Result<()> {
+        if let Action::Moved { from, .. } = self {
+            policy::check(from, Access::Write)?;
+        }
+        policy::check(self.path(), Access::Write)
+    }
+
+    /// Reverse the action
+    ///
+    /// Fails without changing anything if the path has since been taken by
+    /// something else.
+    fn revert(&self) -> Result<()> {
+        match self {
+            Action::Created { path } => match fs::symlink_metadata(path) {
+                Ok(metadata) if metadata.is_dir() => Err(conflict(path, "is now a directory")),
+                Ok(_) => Ok(fs::remove_file(path)?),
+                // Already gone
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(e.into()),
+            },
+            Action::CreatedDir { path } => match fs::remove_dir(path) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                Err(e) if e.kind() == std::io::ErrorKind::DirectoryNotEmpty => {
+                    Err(conflict(path, "is no longer empty"))
+                }
+                result => Ok(result?),
+            },
+            Action::Overwritten { path, backup } => move_entry(backup, path),
+            Action::Removed { path, backup } => {
+                if fs::symlink_metadata(path).is_ok() {
+                    return Err(conflict(path, "exists again"));
+                }
+                move_entry(backup, path)
+            }
+            Action::Deleted { path, .. } => Err(AiCoreutilsError::InvalidInput(format!(
+                "{} was deleted without a backup; not restored",
+                path.display()
+            ))),
+            Action::RemovedDir { path, mode } => {
+                match fs::create_dir(path) {
+                    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && path.is_dir() => {}
+                    result => result?,
+                }
+                set_mode(path, *mode)
+            }
+            Action::Moved { from, to } => {
+                if fs::symlink_metadata(from).is_ok() {
+                    return Err(conflict(from, "exists again"));
+                }
+                move_entry(to, from)
+            }
+            Action::ModeChanged { path, old_mode, .. } => set_mode(path, *old_mode),
+        }
+    }
+}
+
+/// Everything one invocation of a utility changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Operation {
+    /// Unique id, also the name of its backup directory
+    pub id: String,
+    /// Utility that performed it
+    pub tool: String,
+    /// Command-line arguments, without the program name
+    pub args: Vec<String>,
+    /// Working directory of the invocation
+    pub cwd: PathBuf,
+    /// When it started
+    pub time: DateTime<Utc>,
+    /// Changes in the order they were made
+    pub actions: Vec<Action>,
+}
+
+/// An operation with its undo state, as listed by [`Journal::history`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    /// The recorded operation
+    pub operation: Operation,
+    /// When it was undone, if it was
+    pub undone: Option<DateTime<Utc>>,
+}
+
+/// Result of undoing one operation
+#[derive(Debug)]
+pub struct UndoOutcome {
+    /// The operation, now marked as undone
+    pub operation: Operation,
+    /// Actions reversed, newest first
+    pub reverted: Vec<Action>,
+    /// Actions that could not be reversed; their backups are kept
+    pub failed: Vec<(Action, AiCoreutilsError)>,
+}
+
+/// One line of `journal.jsonl`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+enum Line {
+    Operation(Operation),
+    Undone { id: String, time: DateTime<Utc> },
+}
+
+/// A journal directory
+#[derive(Debug, Clone)]
+pub struct Journal {
+    dir: PathBuf,
+    max_operations: usize,
+    max_backup_bytes: u64,
+}
+
+impl Journal {
+    /// Use the journal in `dir`, keeping at most `max_operations` operations
+    ///
+    /// Nothing is created until an operation is recorded. Removed files of
+    /// any size are kept; see [`Journal::with_max_backup_bytes`].
+    pub fn new(dir: impl Into<PathBuf>, max_operations: usize) -> Self {
+        Self {
+            dir: dir.into(),
+            max_operations: max_operations.max(1),
+            max_backup_bytes: u64::MAX,
+        }
+    }
+
+    /// Delete removed files larger than `max_backup_bytes` instead of keeping them
+    pub fn with_max_backup_bytes(mut self, max_backup_bytes: u64) -> Self {
+        self.max_backup_bytes = max_backup_bytes;
+        self
+    }
+
+    /// The journal described by the shared configuration, if enabled
+    ///
+    /// Inside a session, and with no `journal.dir`, this is the session's.
+    pub fn from_config() -> Option<Self> {
+        let journal = &config::get().journal;
+        if !journal.enabled {
+            return None;
+        }
+        let dir = match (&journal.dir, crate::session::Session::current()) {
+            (Some(dir), _) => dir.clone(),
+            (None, Some(session)) => session.journal_dir(),
+            (None, None) => dirs::data_local_dir()?.join("ai-coreutils").join("journal"),
+        };
+        Some(Self::new(dir, journal.max_operations).with_max_backup_bytes(journal.max_backup_bytes))
+    }
+
+    /// Directory holding the journal
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// All recorded operations, oldest first
+    pub fn history(&self) -> Result<Vec<JournalEntry>> {
+        let mut entries: Vec<JournalEntry> = Vec::new();
+        for line in self.read_lines()? {
+            match line {
+                Line::Operation(operation) => entries.push(JournalEntry { operation, undone: None }),
+                Line::Undone { id, time } => {
+                    if let Some(entry) = entries.iter_mut().find(|e| e.operation.id == id) {
+                        entry.undone = Some(time);
+                    }
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Reverse the last `count` operations that have not been undone, newest first
+    ///
+    /// Each operation's actions are reversed newest first. An operation is
+    /// marked as undone once all its actions were attempted; actions that
+    /// failed are reported and their backups kept for manual recovery.
+    ///
+    /// Nothing is reversed if the sandbox policy forbids writing any path
+    /// the operations touched.
+    pub fn undo(&self, count: usize) -> Result<Vec<UndoOutcome>> {
+        let _lock = self.lock()?;
+        let pending: Vec<Operation> = self
+            .history()?
+            .into_iter()
+            .rev()
+            .filter(|e| e.undone.is_none())
+            .take(count)
+            .map(|e| e.operation)
+            .collect();
+        for action in pending.iter().flat_map(|op| &op.actions) {
+            action.check_policy()?;
+        }
+
+        let mut outcomes = Vec::with_capacity(pending.len());
+        for operation in pending {
+            let mut reverted = Vec::new();
+            let mut failed = Vec::new();
+            for action in operation.actions.iter().rev() {
+                match action.revert() {
+                    Ok(()) => reverted.push(action.clone()),
+                    Err(e) => failed.push((action.clone(), e)),
+                }
+            }
+
+            self.append(&Line::Undone {
+                id: operation.id.clone(),
+                time: Utc::now(),
+            })?;
+            if failed.is_empty() {
+                let _ = fs::remove_dir_all(self.backup_dir(&operation.id));
+            }
+            outcomes.push(UndoOutcome { operation, reverted, failed });
+        }
+
+        Ok(outcomes)
+    }
+
+    /// Append an operation, dropping the oldest beyond `max_operations`
+    fn record(&self, operation: Operation) -> Result<()> {
+        let _lock = self.lock()?;
+        self.append(&Line::Operation(operation))?;
+
+        let history = self.history()?;
+        if history.len() <= self.max_operations {
+            return Ok(());
+        }
+
+        let (dropped, kept) = history.split_at(history.len() - self.max_operations);
+        let mut contents = Vec::new();
+        for entry in kept {
+            write_line(&mut contents, &Line::Operation(entry.operation.clone()))?;
+            if let Some(time) = entry.undone {
+                write_line(&mut contents, &Line::Undone { id: entry.operation.id.clone(), time })?;
+            }
+        }
+        // Replaced directly rather than with fs_utils::atomic_write: the
+        // journal is internal state, not subject to the sandbox policy
+        let temp = self.dir.join(format!("{}.tmp", JOURNAL_FILE));
+        fs::write(&temp, &contents)?;
+        fs::rename(&temp, self.dir.join(JOURNAL_FILE))?;
+
+        for entry in dropped {
+            let _ = fs::remove_dir_all(self.backup_dir(&entry.operation.id));
+        }
+        Ok(())
+    }
+
+    fn read_lines(&self) -> Result<Vec<Line>> {
+        let file = match fs::File::open(self.dir.join(JOURNAL_FILE)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut lines = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            // A line cut short by a crash is skipped rather than failing every later undo
+            if let Ok(line) = serde_json::from_str(&line) {
+                lines.push(line);
+            }
+        }
+        Ok(lines)
+    }
+
+    fn append(&self, line: &Line) -> Result<()> {
+        let mut contents = Vec::new();
+        write_line(&mut contents, line)?;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(JOURNAL_FILE))?;
+        file.write_all(&contents)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<FileLock> {
+        fs::create_dir_all(&self.dir)?;
+        FileLock::acquire(&self.dir.join(LOCK_FILE), LockMode::Exclusive, None)
+    }
+
+    fn backup_dir(&self, id: &str) -> PathBuf {
+        self.dir.join(BACKUP_DIR).join(id)
+    }
+}
+
+/// Records the actions of one invocation and appends them to the journal
+///
+/// With the journal disabled, files are still removed by
+/// [`Recorder::remove_file`] but nothing is kept or recorded.
+#[derive(Debug)]
+pub struct Recorder {
+    journal: Option<Journal>,
+    operation: Operation,
+    backups: usize,
+}
+
+impl Recorder {
+    /// Start recording an invocation of `tool` in the configured journal
+    pub fn begin(tool: &str) -> Self {
+        Self::new(Journal::from_config(), tool)
+    }
+
+    /// Start recording an invocation of `tool` in `journal`, if any
+    pub fn new(journal: Option<Journal>, tool: &str) -> Self {
+        Self {
+            journal,
+            operation: Operation {
+                id: uuid::Uuid::new_v4().simple().to_string(),
+                tool: tool.to_string(),
+                args: std::env::args().skip(1).collect(),
+                cwd: std::env::current_dir().unwrap_or_default(),
+                time: Utc::now(),
+                actions: Vec::new(),
+            },
+            backups: 0,
+        }
+    }
+
+    /// Whether actions are being recorded
+    pub fn is_enabled(&self) -> bool {
+        self.journal.is_some()
+    }
+
+    /// Actions recorded so far
+    pub fn actions(&self) -> &[Action] {
+        &self.operation.actions
+    }
+
+    /// Back up `path` before it is replaced
+    ///
+    /// Returns whether something existed there. Directories are left to the
+    /// caller. Fails if the backup cannot be made, in which case the caller
+    /// must not write.
+    pub fn before_write(&mut self, path: &Path) -> Result<bool> {
+        let metadata = match fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        if metadata.is_dir() || self.journal.is_none() {
+            return Ok(true);
+        }
+
+        let backup = self.next_backup()?;
+        copy_entry(path, &backup)?;
+        self.push(Action::Overwritten { path: absolute(path), backup });
+        Ok(true)
+    }
+
+    /// Record that `path` was created
+    pub fn created(&mut self, path: &Path) {
+        self.push(Action::Created { path: absolute(path) });
+    }
+
+    /// Record that the directory `path` was created
+    pub fn created_dir(&mut self, path: &Path) {
+        self.push(Action::CreatedDir { path: absolute(path) });
+    }
+
+    /// Remove a file or symlink, moving it into the journal
+    ///
+    /// Returns whether it was kept. A file larger than the journal's
+    /// `max_backup_bytes`, or on another filesystem, is deleted instead,
+    /// and recorded as [`Action::Deleted`] rather than copied.
+    pub fn remove_file(&mut self, path: &Path) -> Result<bool> {
+        let Some(journal) = &self.journal else {
+            fs::remove_file(path)?;
+            return Ok(false);
+        };
+
+        let size = fs::symlink_metadata(path)?.len();
+        if size <= journal.max_backup_bytes {
+            let backup = self.next_backup()?;
+            match fs::rename(path, &backup) {
+                Ok(()) => {
+                    self.push(Action::Removed { path: absolute(path), backup });
+                    return Ok(true);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => self.backups -= 1,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        fs::remove_file(path)?;
+        self.push(Action::Deleted { path: absolute(path), size });
+        Ok(false)
+    }
+
+    /// Record that the empty directory `path` was removed
+    ///
+    /// `metadata` is the directory's, taken before removal.
+    pub fn removed_dir(&mut self, path: &Path, metadata: &fs::Metadata) {
+        self.push(Action::RemovedDir {
+            path: absolute(path),
+            mode: mode_of(metadata),
+        });
+    }
+
+    /// Record that `from` was moved to `to`
+    pub fn moved(&mut self, from: &Path, to: &Path) {
+        self.push(Action::Moved {
+            from: absolute(from),
+            to: absolute(to),
+        });
+    }
+
+    /// Record that the permission bits of `path` changed
+    pub fn mode_changed(&mut self, path: &Path, old_mode: u32, new_mode: u32) {
+        self.push(Action::ModeChanged {
+            path: absolute(path),
+            old_mode,
+            new_mode,
+        });
+    }
+
+    /// Append the operation to the journal
+    ///
+    /// Returns its id, or `None` if nothing was recorded.
+    pub fn finish(self) -> Result<Option<String>> {
+        match self.journal {
+            Some(journal) if !self.operation.actions.is_empty() => {
+                let id = self.operation.id.clone();
+                journal.record(self.operation)?;
+                Ok(Some(id))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn push(&mut self, action: Action) {
+        if self.journal.is_some() {
+            self.operation.actions.push(action);
+        }
+    }
+
+    fn next_backup(&mut self) -> Result<PathBuf> {
+        let journal = self.journal.as_ref().expect("backups are only made with a journal");
+        let dir = journal.backup_dir(&self.operation.id);
+        fs::create_dir_all(&dir)?;
+        self.backups += 1;
+        Ok(dir.join(self.backups.to_string()))
+    }
+}
+
+/// Modes as octal strings (`"0755"`), as ai-chmod reports them
+mod octal {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(mode: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:04o}", mode))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        u32::from_str_radix(&s, 8).map_err(D::Error::custom)
+    }
+}
+
+fn write_line(out: &mut Vec<u8>, line: &Line) -> Result<()> {
+    serde_json::to_writer(&mut *out, line)?;
+    out.push(b'\n');
+    Ok(())
+}
+
+fn conflict(path: &Path, reason: &str) -> AiCoreutilsError {
+    AiCoreutilsError::InvalidInput(format!("{} {}; not restored", path.display(), reason))
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Move a file, link or directory, copying when it crosses filesystems
+fn move_entry(from: &Path, to: &Path) -> Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            copy_entry(from, to)?;
+            if fs::symlink_metadata(from)?.is_dir() {
+                fs::remove_dir_all(from)?;
+            } else {
+                fs::remove_file(from)?;
+            }
+            Ok(())
+        }
+        result => Ok(result?),
+    }
+}
+
+/// Copy a file, link (as a link) or directory tree
+fn copy_entry(from: &Path, to: &Path) -> Result<()> {
+    let metadata = fs::symlink_metadata(from)?;
+    if metadata.file_type().is_symlink() {
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(fs::read_link(from)?, to)?;
+        #[cfg(not(unix))]
+        fs::copy(from, to)?;
+    } else if metadata.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_entry(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        fs::set_permissions(to, metadata.permissions())?;
+    } else {
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+fn mode_of(metadata: &fs::Metadata) -> u32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.permissions().mode() & crate::fs_utils::mode::MODE_BITS
+    }
+    #[cfg(not(unix))]
+    {
+        if metadata.permissions().readonly() { 0o555 } else { 0o777 }
+    }
+}
+
+fn set_mode(path: &Path, mode: u32) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+    #[cfg(not(unix))]
+    {
+        let mut permissions = fs::metadata(path)?.permissions();
+        permissions.set_readonly(mode & 0o200 == 0);
+        fs::set_permissions(path, permissions)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn journal() -> (TempDir, TempDir, Journal) {
+        let work = TempDir::new().unwrap();
+        let state = TempDir::new().unwrap();
+        let journal = Journal::new(state.path(), 3);
+        (work, state, journal)
+    }
+
+    #[test]
+    fn test_undo_reverses_actions_newest_first() {
+        let (work, _state, journal) = journal();
+        let kept = work.path().join("kept.txt");
+        let removed = work.path().join("removed.txt");
+        let dir = work.path().join("dir");
+        fs::write(&kept, "old").unwrap();
+        fs::write(&removed, "gone").unwrap();
+
+        // A copy that overwrites one file and creates a directory and another file
+        let mut recorder = Recorder::new(Some(journal.clone()), "ai-cp");
+        assert!(recorder.before_write(&kept).unwrap());
+        fs::write(&kept, "new").unwrap();
+        fs::create_dir(&dir).unwrap();
+        recorder.created_dir(&dir);
+        assert!(!recorder.before_write(&dir.join("a.txt")).unwrap());
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        recorder.created(&dir.join("a.txt"));
+        let first = recorder.finish().unwrap().unwrap();
+
+        // A remove followed by a move
+        let mut recorder = Recorder::new(Some(journal.clone()), "ai-rm");
+        recorder.remove_file(&removed).unwrap();
+        assert!(!removed.exists());
+        let moved = work.path().join("moved.txt");
+        fs::rename(&kept, &moved).unwrap();
+        recorder.moved(&kept, &moved);
+        let second = recorder.finish().unwrap().unwrap();
+
+        let history = journal.history().unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].operation.actions.len(), 2);
+        assert!(history.iter().all(|e| e.undone.is_none()));
+
+        let outcomes = journal.undo(1).unwrap();
+        assert_eq!(outcomes[0].operation.id, second);
+        assert!(outcomes[0].failed.is_empty());
+        assert!(matches!(outcomes[0].reverted[0], Action::Moved { .. }));
+        assert_eq!(fs::read_to_string(&removed).unwrap(), "gone");
+        assert_eq!(fs::read_to_string(&kept).unwrap(), "new");
+
+        let outcomes = journal.undo(5).unwrap();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].operation.id, first);
+        assert_eq!(fs::read_to_string(&kept).unwrap(), "old");
+        assert!(!dir.exists());
+        assert!(!journal.backup_dir(&first).exists());
+
+        assert!(journal.history().unwrap().iter().all(|e| e.undone.is_some()));
+        assert!(journal.undo(1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_conflicts_keep_backups() {
+        let (work, _state, journal) = journal();
+        let path = work.path().join("file.txt");
+        fs::write(&path, "original").unwrap();
+
+        let mut recorder = Recorder::new(Some(journal.clone()), "ai-rm");
+        recorder.remove_file(&path).unwrap();
+        let id = recorder.finish().unwrap().unwrap();
+
+        // Something else took the path since
+        fs::write(&path, "replacement").unwrap();
+        let outcomes = journal.undo(1).unwrap();
+        assert_eq!(outcomes[0].failed.len(), 1);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "replacement");
+        assert!(journal.backup_dir(&id).join("1").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_mode_and_removed_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (work, _state, journal) = journal();
+        let dir = work.path().join("dir");
+        fs::create_dir(&dir).unwrap();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o750)).unwrap();
+        let file = dir.join("script.sh");
+        fs::write(&file, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let mut recorder = Recorder::new(Some(journal.clone()), "ai-chmod");
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o755)).unwrap();
+        recorder.mode_changed(&file, 0o644, 0o755);
+        recorder.finish().unwrap();
+
+        let mut recorder = Recorder::new(Some(journal.clone()), "ai-rm");
+        let metadata = fs::metadata(&dir).unwrap();
+        recorder.remove_file(&file).unwrap();
+        fs::remove_dir(&dir).unwrap();
+        recorder.removed_dir(&dir, &metadata);
+        recorder.finish().unwrap();
+
+        journal.undo(2).unwrap();
+        assert_eq!(fs::metadata(&dir).unwrap().permissions().mode() & 0o7777, 0o750);
+        assert_eq!(fs::metadata(&file).unwrap().permissions().mode() & 0o7777, 0o644);
+    }
+
+    #[test]
+    fn test_oldest_operations_are_dropped() {
+        let (work, _state, journal) = journal();
+        let mut ids = Vec::new();
+        for i in 0..5 {
+            let path = work.path().join(format!("{}.txt", i));
+            fs::write(&path, "x").unwrap();
+            let mut recorder = Recorder::new(Some(journal.clone()), "ai-rm");
+            recorder.remove_file(&path).unwrap();
+            ids.push(recorder.finish().unwrap().unwrap());
+        }
+
+        let history = journal.history().unwrap();
+        let kept: Vec<_> = history.iter().map(|e| e.operation.id.clone()).collect();
+        assert_eq!(kept, ids[2..]);
+        assert!(!journal.backup_dir(&ids[0]).exists());
+        assert!(journal.backup_dir(&ids[4]).exists());
+    }
+
+    #[test]
+    fn test_disabled_recorder_records_nothing() {
+        let work = TempDir::new().unwrap();
+        let path = work.path().join("file.txt");
+        fs::write(&path, "x").unwrap();
+
+        let mut recorder = Recorder::new(None, "ai-rm");
+        assert!(recorder.before_write(&path).unwrap());
+        assert!(!recorder.remove_file(&path).unwrap());
+        assert!(!path.exists());
+        assert!(recorder.actions().is_empty());
+        assert_eq!(recorder.finish().unwrap(), None);
+    }
+
+    #[test]
+    fn test_files_over_the_backup_cap_are_deleted() {
+        let (work, state, _) = journal();
+        let journal = Journal::new(state.path(), 3).with_max_backup_bytes(4);
+        let small = work.path().join("small.txt");
+        let large = work.path().join("large.txt");
+        fs::write(&small, "abcd").unwrap();
+        fs::write(&large, "abcde").unwrap();
+
+        let mut recorder = Recorder::new(Some(journal.clone()), "ai-rm");
+        assert!(recorder.remove_file(&small).unwrap());
+        assert!(!recorder.remove_file(&large).unwrap());
+        assert!(!large.exists());
+        assert!(matches!(recorder.actions()[1], Action::Deleted { size: 5, .. }));
+        let id = recorder.finish().unwrap().unwrap();
+
+        // The kept file is restored and the deleted one reported
+        let outcomes = journal.undo(1).unwrap();
+        assert_eq!(outcomes[0].reverted.len(), 1);
+        assert_eq!(outcomes[0].failed.len(), 1);
+        assert_eq!(fs::read_to_string(&small).unwrap(), "abcd");
+        assert!(!large.exists());
+        assert!(journal.backup_dir(&id).exists());
+    }
+}
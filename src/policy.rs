@@ -0,0 +1,376 @@
+//! Sandbox path policy
+//!
+//! Restricts which paths the utilities may read or write, using allowlists
+//! and a denylist of path prefixes from the `[sandbox]` config section or
+//! `AI_COREUTILS_SANDBOX` / `AI_COREUTILS_SANDBOX_WRITE`. Paths are compared
+//! by component after resolving symlinks, so neither `..` nor a link can
+//! lead outside an allowed prefix. A write must be allowed both where the
+//! path lives and where it leads; operations on the directory entry itself
+//! (unlink, rename, `chmod -h`) are judged only by where the entry lives. An
+//! empty policy allows everything and costs nothing to check.
+
+use crate::audit;
+use crate::config::{self, SandboxConfig};
+use crate::error::{AiCoreutilsError, Result};
+use crate::fs_utils::paths::expand_home;
+use crate::jsonl;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+
+static GLOBAL_POLICY: OnceLock<Policy> = OnceLock::new();
+
+/// Kind of access being checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Reading a file or listing a directory
+    Read,
+    /// Creating, modifying, moving or removing a path
+    Write,
+}
+
+impl Access {
+    /// Name used in messages and JSONL records
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Access::Read => "read",
+            Access::Write => "write",
+        }
+    }
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Resolved sandbox rules
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    allow: Vec<PathBuf>,
+    deny: Vec<PathBuf>,
+    allow_write: Vec<PathBuf>,
+}
+
+impl Policy {
+    /// Build a policy from config, resolving each prefix
+    ///
+    /// A leading `~/` is expanded to the home directory; relative prefixes
+    /// are taken from the current directory.
+    pub fn from_config(config: &SandboxConfig) -> Self {
+        let resolve_all = |prefixes: &[PathBuf]| prefixes.iter().map(|p| resolve(&expand_home(p))).collect();
+        Self {
+            allow: resolve_all(&config.allow),
+            deny: resolve_all(&config.deny),
+            allow_write: resolve_all(&config.allow_write),
+        }
+    }
+
+    /// Whether the policy allows every access
+    pub fn is_unrestricted(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty() && self.allow_write.is_empty()
+    }
+
+    /// Check `access` to `path`
+    ///
+    /// Reads need the path under an `allow` prefix, and writes under an
+    /// `allow_write` prefix, or an `allow` prefix if `allow_write` is empty.
+    /// An empty list allows everything. Nothing under a `deny` prefix is
+    /// accessible. A write to a symlink needs both the link and the path it
+    /// resolves to allowed.
+    pub fn check(&self, path: &Path, access: Access) -> Result<()> {
+        if self.is_unrestricted() {
+            return Ok(());
+        }
+
+        let allowed = match access {
+            Access::Read => self.allows(&resolve(path), access),
+            Access::Write => self.allows(&resolve_entry(path), access) && self.allows(&resolve(path), access),
+        };
+        self.verdict(path, access, allowed)
+    }
+
+    /// Check write access to the directory entry at `path`
+    ///
+    /// For operations that do not follow a final symlink, such as unlinking
+    /// or renaming it: only the parent directory is resolved, so a link is
+    /// judged by where it lives rather than where it points.
+    pub fn check_entry(&self, path: &Path) -> Result<()> {
+        if self.is_unrestricted() {
+            return Ok(());
+        }
+        self.verdict(path, Access::Write, self.allows(&resolve_entry(path), Access::Write))
+    }
+
+    fn allows(&self, resolved: &Path, access: Access) -> bool {
+        let under = |prefixes: &[PathBuf]| prefixes.iter().any(|p| resolved.starts_with(p));
+        let allow = match access {
+            Access::Write if !self.allow_write.is_empty() => &self.allow_write,
+            _ => &self.allow,
+        };
+        !under(&self.deny) && (allow.is_empty() || under(allow))
+    }
+
+    fn verdict(&self, path: &Path, access: Access, allowed: bool) -> Result<()> {
+        if allowed {
+            Ok(())
+        } else {
+            Err(AiCoreutilsError::PolicyDenied {
+                path: path.to_path_buf(),
+                access,
+            })
+        }
+    }
+}
+
+/// The policy from the global configuration
+pub fn get() -> &'static Policy {
+    GLOBAL_POLICY.get_or_init(|| Policy::from_config(&config::get().sandbox))
+}
+
+/// Check `access` to `path` against the global policy
+///
+/// Allowed paths are noted in the audit log, if one is being written.
+pub fn check(path: &Path, access: Access) -> Result<()> {
+    // Copies of remote files were fetched on the caller's behalf
+    #[cfg(feature = "remote")]
+    if access == Access::Read && crate::remote::is_cached(path) {
+        return Ok(());
+    }
+    // Archive members are as accessible as the archive holding them
+    #[cfg(feature = "archives")]
+    if let Some(member) = crate::archive::MemberPath::parse(path) {
+        return check(&member.archive, access);
+    }
+    get().check(path, access)?;
+    audit::touched(path, access);
+    Ok(())
+}
+
+/// Check write access to the directory entry at `path` against the global
+/// policy, without following a final symlink
+///
+/// See [`Policy::check_entry`].
+pub fn check_entry(path: &Path) -> Result<()> {
+    #[cfg(feature = "archives")]
+    if let Some(member) = crate::archive::MemberPath::parse(path) {
+        return check(&member.archive, Access::Write);
+    }
+    get().check_entry(path)?;
+    audit::touched(path, Access::Write);
+    Ok(())
+}
+
+/// Check command-line paths before a utility touches any of them
+///
+/// Emits a `POLICY_DENIED` error record for each denied path and returns the
+/// first violation. `-` (standard input or output) is never checked, nor,
+/// with the `remote` feature, are URLs.
+pub fn enforce<I, P>(paths: I, access: Access) -> Result<()>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    enforce_with(paths, |path| check(path, access))
+}
+
+/// Check command-line paths that are unlinked or renamed, like [`enforce`]
+/// with [`check_entry`]
+pub fn enforce_entries<I, P>(paths: I) -> Result<()>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    enforce_with(paths, check_entry)
+}
+
+fn enforce_with<I, P>(paths: I, check: impl Fn(&Path) -> Result<()>) -> Result<()>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    let mut first = None;
+    for path in paths {
+        let path = path.as_ref();
+        if path == Path::new("-") {
+            continue;
+        }
+        #[cfg(feature = "remote")]
+        if crate::remote::is_url(path) {
+            continue;
+        }
+        if let Err(e) = check(path) {
+            report(&e)?;
+            first.get_or_insert(e);
+        }
+    }
+    first.map_or(Ok(()), Err)
+}
+
+/// Emit a `POLICY_DENIED` error record if `error` is a policy violation
+///
+/// Returns whether a record was written, so walks that otherwise skip
+/// unreadable entries quietly can still surface denied ones.
+pub fn report(error: &AiCoreutilsError) -> Result<bool> {
+    let AiCoreutilsError::PolicyDenied { path, .. } = error else {
+        return Ok(false);
+    };
+    jsonl::output_error(&error.to_string(), "POLICY_DENIED", Some(&path.to_string_lossy()))?;
+    Ok(true)
+}
+
+/// Absolute form of `path` with symlinks resolved
+///
+/// The longest existing ancestor is canonicalized, and the components that
+/// do not exist yet are appended with `.` and `..` applied lexically. A
+/// dangling symlink is resolved through its target, so it is judged by
+/// where it would lead.
+fn resolve(path: &Path) -> PathBuf {
+    resolve_within(path, MAX_LINK_DEPTH)
+}
+
+/// Dangling links followed before giving up, as with `SYMLOOP_MAX`
+const MAX_LINK_DEPTH: usize = 40;
+
+fn resolve_within(path: &Path, depth: usize) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+
+    let mut existing = absolute.as_path();
+    let mut missing: Vec<OsString> = Vec::new();
+    let base = loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            break canonical;
+        }
+        if let (Ok(target), Some(parent), Some(remaining)) =
+            (std::fs::read_link(existing), existing.parent(), depth.checked_sub(1))
+        {
+            break resolve_within(&parent.join(target), remaining);
+        }
+        match existing.components().next_back() {
+            Some(Component::Normal(name)) => missing.push(name.to_owned()),
+            Some(Component::ParentDir) => missing.push(OsString::from("..")),
+            Some(Component::CurDir) => {}
+            _ => break existing.to_path_buf(),
+        }
+        match existing.parent() {
+            Some(parent) => existing = parent,
+            None => break existing.to_path_buf(),
+        }
+    };
+
+    let mut resolved = base;
+    for name in missing.iter().rev() {
+        if name == ".." {
+            resolved.pop();
+        } else {
+            resolved.push(name);
+        }
+    }
+    resolved
+}
+
+/// Absolute form of `path` with symlinks resolved in its parent only
+///
+/// A final `.` or `..` is resolved like any other component, since neither
+/// names an entry that can be unlinked on its own.
+fn resolve_entry(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    match (absolute.parent(), absolute.components().next_back()) {
+        (Some(parent), Some(Component::Normal(name))) => resolve(parent).join(name),
+        _ => resolve(&absolute),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn policy(allow: &[&Path], deny: &[&Path], allow_write: &[&Path]) -> Policy {
+        let paths = |list: &[&Path]| list.iter().map(|p| p.to_path_buf()).collect();
+        Policy::from_config(&SandboxConfig {
+            allow: paths(allow),
+            deny: paths(deny),
+            allow_write: paths(allow_write),
+        })
+    }
+
+    #[test]
+    fn test_empty_policy_allows_everything() {
+        let policy = Policy::default();
+        assert!(policy.is_unrestricted());
+        assert!(policy.check(Path::new("/etc/shadow"), Access::Write).is_ok());
+    }
+
+    #[test]
+    fn test_allow_and_deny_prefixes() {
+        let temp = TempDir::new().unwrap();
+        let work = temp.path().join("work");
+        let secrets = work.join("secrets");
+        std::fs::create_dir_all(&secrets).unwrap();
+        let policy = policy(&[&work], &[&secrets], &[]);
+
+        assert!(policy.check(&work.join("notes.txt"), Access::Read).is_ok());
+        assert!(policy.check(&work.join("new/file"), Access::Write).is_ok());
+        assert!(policy.check(&secrets.join("key"), Access::Read).is_err());
+        assert!(policy.check(temp.path(), Access::Read).is_err());
+        // Prefixes match whole components only
+        assert!(policy.check(&temp.path().join("workshop"), Access::Read).is_err());
+        // `..` cannot climb out of an allowed prefix
+        assert!(policy.check(&work.join("../outside"), Access::Read).is_err());
+    }
+
+    #[test]
+    fn test_write_allowlist() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        let out = temp.path().join("out");
+        let policy = policy(&[&src], &[], &[&out]);
+
+        assert!(policy.check(&src.join("main.rs"), Access::Read).is_ok());
+        assert!(policy.check(&src.join("main.rs"), Access::Write).is_err());
+        assert!(policy.check(&out.join("report.json"), Access::Write).is_ok());
+        assert!(policy.check(&out.join("report.json"), Access::Read).is_err());
+
+        let err = policy.check(&src.join("main.rs"), Access::Write).unwrap_err();
+        assert!(err.to_string().contains("denies write access"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_cannot_escape() {
+        let temp = TempDir::new().unwrap();
+        let work = temp.path().join("work");
+        let outside = temp.path().join("outside");
+        std::fs::create_dir_all(&work).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, work.join("link")).unwrap();
+        let policy = policy(&[&work], &[], &[]);
+
+        assert!(policy.check(&work.join("link/file"), Access::Read).is_err());
+        assert!(policy.check(&work.join("link/../file"), Access::Read).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_links_are_judged_where_they_live() {
+        let temp = TempDir::new().unwrap();
+        let work = temp.path().join("work");
+        let outside = temp.path().join("outside");
+        std::fs::create_dir_all(&work).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(work.join("x"), "x").unwrap();
+        std::os::unix::fs::symlink(work.join("x"), outside.join("in")).unwrap();
+        std::os::unix::fs::symlink(outside.join("y"), work.join("out")).unwrap();
+        let policy = policy(&[&work], &[], &[]);
+
+        // A link outside the sandbox cannot be removed or written through
+        assert!(policy.check_entry(&outside.join("in")).is_err());
+        assert!(policy.check(&outside.join("in"), Access::Write).is_err());
+        // A link inside can be removed, but not written through
+        assert!(policy.check_entry(&work.join("out")).is_ok());
+        assert!(policy.check(&work.join("out"), Access::Write).is_err());
+    }
+}
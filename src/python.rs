@@ -2,20 +2,81 @@
 //!
 //! This module provides Python bindings using PyO3, exposing the core
 //! functionality of AI-Coreutils to Python code.
+//!
+//! Calls that scan memory maps, hash, classify or touch the filesystem
+//! release the GIL while they run, so other Python threads keep going.
+//! `read_async`, `grep_async` and `analyze_async` return awaitables that
+//! run on a Tokio runtime (via `pyo3-async-runtimes`), for asyncio-based
+//! agent frameworks whose event loop must not block.
+//!
+//! The file utilities from `async_ops` (`walk_dir`, `wc`, `grep_file` and
+//! `copy_file`) come both as blocking functions, which run on the same
+//! runtime with the GIL released, and as `*_async` awaitables.
+//!
+//! The SIMD primitives (`SimdTextProcessor`, `SimdPatternSearcher`,
+//! `SimdMultiPatternSearcher`, `SimdHasher`, `SimdEntropyCalculator` and
+//! `SimdUtf8Validator`) take any C-contiguous byte buffer — `bytes`,
+//! `bytearray`, `memoryview`, `mmap` or a `uint8` NumPy array — and read it
+//! in place through the buffer protocol, without copying.
 
 #[cfg(feature = "python")]
 use pyo3::prelude::*;
 #[cfg(feature = "python")]
+use pyo3::buffer::PyBuffer;
+#[cfg(feature = "python")]
 use pyo3::types::{PyBytes, PyDict};
 #[cfg(feature = "python")]
-use std::path::PathBuf;
+use std::future::Future;
+#[cfg(feature = "python")]
+use std::path::{Path, PathBuf};
 
+#[cfg(feature = "python")]
+use crate::async_ops::{self, AsyncConfig, GrepMatch, WcCounts};
+#[cfg(feature = "python")]
+use crate::error::AiCoreutilsError;
 #[cfg(feature = "python")]
 use crate::memory::SafeMemoryAccess;
 #[cfg(feature = "python")]
-use crate::simd_ops::{SimdConfig, SimdTextProcessor};
+use crate::simd_ops::{
+    SimdConfig, SimdEntropyCalculator, SimdHasher, SimdMultiPatternSearcher, SimdPatternSearcher,
+    SimdTextProcessor, SimdUtf8Validator,
+};
+#[cfg(feature = "python")]
+use crate::ml_ops::{ContentAnalysis, PatternDetector, PatternMatch, FileClassifier};
+#[cfg(feature = "python")]
+use crate::fs_utils;
+#[cfg(feature = "python")]
+use crate::hash::{self, HashAlgorithm, StreamingHasher};
+
+/// Zero-copy view of a Python object's bytes via the buffer protocol
 #[cfg(feature = "python")]
-use crate::ml_ops::{PatternDetector, FileClassifier};
+struct ByteBuffer(PyBuffer<u8>);
+
+#[cfg(feature = "python")]
+impl ByteBuffer {
+    /// Borrow the buffer of `obj`, which must be C-contiguous bytes
+    fn get(obj: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let buffer = PyBuffer::<u8>::get(obj)?;
+        if !buffer.is_c_contiguous() {
+            return Err(PyErr::new::<pyo3::exceptions::PyBufferError, _>(
+                "buffer must be C-contiguous",
+            ));
+        }
+        Ok(Self(buffer))
+    }
+
+    /// The buffer's bytes
+    fn as_slice(&self) -> &[u8] {
+        if self.0.len_bytes() == 0 {
+            return &[];
+        }
+        // SAFETY: the exporter keeps the memory alive until the buffer is
+        // released on drop, and it is C-contiguous with `len_bytes` bytes.
+        // A writable buffer could be changed by another Python thread while
+        // the GIL is released; callers then see some mix of old and new bytes.
+        unsafe { std::slice::from_raw_parts(self.0.buf_ptr() as *const u8, self.0.len_bytes()) }
+    }
+}
 
 /// Python wrapper for SafeMemoryAccess
 #[cfg(feature = "python")]
@@ -30,12 +91,21 @@ impl PySafeMemoryAccess {
     /// Create a new memory-mapped file access
     #[new]
     #[pyo3(signature = (path))]
-    pub fn new(path: &str) -> PyResult<Self> {
-        let access = SafeMemoryAccess::new(path)
+    pub fn new(py: Python<'_>, path: &str) -> PyResult<Self> {
+        let access = py
+            .allow_threads(|| SafeMemoryAccess::new(path))
             .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
         Ok(Self { inner: access })
     }
 
+    /// Serve a copy of an in-memory buffer through the same API
+    #[staticmethod]
+    pub fn from_bytes(data: &[u8]) -> Self {
+        Self {
+            inner: SafeMemoryAccess::from_bytes(data),
+        }
+    }
+
     /// Get the size of the memory-mapped region
     pub fn size(&self) -> usize {
         self.inner.size()
@@ -50,7 +120,7 @@ impl PySafeMemoryAccess {
     pub fn get(&self, offset: usize, len: usize) -> PyResult<Option<Py<PyBytes>>> {
         Python::with_gil(|py| {
             Ok(self.inner.get(offset, len).map(|data| {
-                PyBytes::new_bound(py, data).into()
+                PyBytes::new(py, data).into()
             }))
         })
     }
@@ -61,18 +131,18 @@ impl PySafeMemoryAccess {
     }
 
     /// Search for a pattern in the memory-mapped region
-    pub fn find_pattern(&self, pattern: &[u8]) -> Vec<usize> {
-        self.inner.find_pattern(pattern)
+    pub fn find_pattern(&self, py: Python<'_>, pattern: &[u8]) -> Vec<usize> {
+        py.allow_threads(|| self.inner.find_pattern(pattern))
     }
 
     /// Count occurrences of a byte in the memory-mapped region
-    pub fn count_byte(&self, byte: u8) -> usize {
-        self.inner.count_byte(byte)
+    pub fn count_byte(&self, py: Python<'_>, byte: u8) -> usize {
+        py.allow_threads(|| self.inner.count_byte(byte))
     }
 
     /// Count lines, words, and bytes in the memory-mapped region
-    pub fn count_text_metrics(&self) -> (usize, usize, usize) {
-        self.inner.count_text_metrics()
+    pub fn count_text_metrics(&self, py: Python<'_>) -> (usize, usize, usize) {
+        py.allow_threads(|| self.inner.count_text_metrics())
     }
 
     /// Get a string representation
@@ -139,6 +209,10 @@ pub struct PyTextMetrics {
     pub words: usize,
     /// Number of bytes
     pub bytes: usize,
+    /// Number of UTF-8 characters
+    pub chars: usize,
+    /// Length in bytes of the longest line
+    pub max_line_length: usize,
 }
 
 #[cfg(feature = "python")]
@@ -146,8 +220,15 @@ pub struct PyTextMetrics {
 impl PyTextMetrics {
     /// Create new TextMetrics
     #[new]
-    pub fn new(lines: usize, words: usize, bytes: usize) -> Self {
-        Self { lines, words, bytes }
+    #[pyo3(signature = (lines, words, bytes, chars=0, max_line_length=0))]
+    pub fn new(lines: usize, words: usize, bytes: usize, chars: usize, max_line_length: usize) -> Self {
+        Self {
+            lines,
+            words,
+            bytes,
+            chars,
+            max_line_length,
+        }
     }
 
     /// Get lines count
@@ -165,20 +246,35 @@ impl PyTextMetrics {
         self.bytes
     }
 
+    /// Get characters count
+    pub fn chars(&self) -> usize {
+        self.chars
+    }
+
+    /// Get the longest line's length in bytes
+    pub fn max_line_length(&self) -> usize {
+        self.max_line_length
+    }
+
     /// Get a dictionary representation
     pub fn to_dict(&self) -> Py<PyDict> {
         Python::with_gil(|py| {
-            let dict = PyDict::new_bound(py);
+            let dict = PyDict::new(py);
             dict.set_item("lines", self.lines).unwrap();
             dict.set_item("words", self.words).unwrap();
             dict.set_item("bytes", self.bytes).unwrap();
+            dict.set_item("chars", self.chars).unwrap();
+            dict.set_item("max_line_length", self.max_line_length).unwrap();
             dict.into()
         })
     }
 
     /// Get a string representation
     pub fn __repr__(&self) -> String {
-        format!("TextMetrics(lines={}, words={}, bytes={})", self.lines, self.words, self.bytes)
+        format!(
+            "TextMetrics(lines={}, words={}, bytes={}, chars={}, max_line_length={})",
+            self.lines, self.words, self.bytes, self.chars, self.max_line_length
+        )
     }
 }
 
@@ -201,23 +297,28 @@ impl PySimdTextProcessor {
     }
 
     /// Analyze text and return metrics
-    pub fn analyze(&self, data: &[u8]) -> PyTextMetrics {
-        let metrics = self.inner.analyze(data);
-        PyTextMetrics {
+    pub fn analyze(&self, py: Python<'_>, data: &Bound<'_, PyAny>) -> PyResult<PyTextMetrics> {
+        let data = ByteBuffer::get(data)?;
+        let metrics = py.allow_threads(|| self.inner.analyze(data.as_slice()));
+        Ok(PyTextMetrics {
             lines: metrics.lines,
             words: metrics.words,
             bytes: metrics.bytes,
-        }
+            chars: metrics.chars,
+            max_line_length: metrics.max_line_length,
+        })
     }
 
     /// Count lines in data
-    pub fn count_lines(&self, data: &[u8]) -> usize {
-        self.inner.analyze(data).lines
+    pub fn count_lines(&self, py: Python<'_>, data: &Bound<'_, PyAny>) -> PyResult<usize> {
+        let data = ByteBuffer::get(data)?;
+        Ok(py.allow_threads(|| self.inner.analyze(data.as_slice()).lines))
     }
 
     /// Count words in data
-    pub fn count_words(&self, data: &[u8]) -> usize {
-        self.inner.analyze(data).words
+    pub fn count_words(&self, py: Python<'_>, data: &Bound<'_, PyAny>) -> PyResult<usize> {
+        let data = ByteBuffer::get(data)?;
+        Ok(py.allow_threads(|| self.inner.analyze(data.as_slice()).words))
     }
 
     /// Get a string representation
@@ -226,6 +327,244 @@ impl PySimdTextProcessor {
     }
 }
 
+/// Python wrapper for SimdPatternSearcher
+#[cfg(feature = "python")]
+#[pyclass(name = "SimdPatternSearcher")]
+pub struct PySimdPatternSearcher {
+    inner: SimdPatternSearcher,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl PySimdPatternSearcher {
+    /// Create a new searcher, optionally with an explicit SIMD config
+    #[new]
+    #[pyo3(signature = (config=None))]
+    pub fn new(config: Option<PySimdConfig>) -> Self {
+        Self {
+            inner: config.map_or_else(SimdPatternSearcher::new, |c| SimdPatternSearcher::with_config(c.inner)),
+        }
+    }
+
+    /// Offset of the first occurrence of `needle`, or None
+    pub fn find_first(&self, py: Python<'_>, haystack: &Bound<'_, PyAny>, needle: &Bound<'_, PyAny>) -> PyResult<Option<usize>> {
+        let (haystack, needle) = (ByteBuffer::get(haystack)?, ByteBuffer::get(needle)?);
+        Ok(py.allow_threads(|| self.inner.find_first(haystack.as_slice(), needle.as_slice())))
+    }
+
+    /// Offset of the last occurrence of `needle`, or None
+    pub fn rfind_first(&self, py: Python<'_>, haystack: &Bound<'_, PyAny>, needle: &Bound<'_, PyAny>) -> PyResult<Option<usize>> {
+        let (haystack, needle) = (ByteBuffer::get(haystack)?, ByteBuffer::get(needle)?);
+        Ok(py.allow_threads(|| self.inner.rfind_first(haystack.as_slice(), needle.as_slice())))
+    }
+
+    /// Offsets of every occurrence of `needle`, not overlapping unless
+    /// `overlapping` is set
+    #[pyo3(signature = (haystack, needle, overlapping=false))]
+    pub fn find_all(
+        &self,
+        py: Python<'_>,
+        haystack: &Bound<'_, PyAny>,
+        needle: &Bound<'_, PyAny>,
+        overlapping: bool,
+    ) -> PyResult<Vec<usize>> {
+        let (haystack, needle) = (ByteBuffer::get(haystack)?, ByteBuffer::get(needle)?);
+        Ok(py.allow_threads(|| match overlapping {
+            true => self.inner.find_all_overlapping(haystack.as_slice(), needle.as_slice()),
+            false => self.inner.find_all(haystack.as_slice(), needle.as_slice()),
+        }))
+    }
+
+    /// Get a string representation
+    pub fn __repr__(&self) -> String {
+        "SimdPatternSearcher()".to_string()
+    }
+}
+
+/// Python wrapper for SimdMultiPatternSearcher
+#[cfg(feature = "python")]
+#[pyclass(name = "SimdMultiPatternSearcher")]
+pub struct PySimdMultiPatternSearcher {
+    inner: SimdMultiPatternSearcher,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl PySimdMultiPatternSearcher {
+    /// Create a searcher for several patterns at once
+    #[new]
+    #[pyo3(signature = (patterns, config=None))]
+    pub fn new(patterns: Vec<Bound<'_, PyAny>>, config: Option<PySimdConfig>) -> PyResult<Self> {
+        let buffers = patterns.iter().map(ByteBuffer::get).collect::<PyResult<Vec<_>>>()?;
+        let patterns: Vec<&[u8]> = buffers.iter().map(ByteBuffer::as_slice).collect();
+        let inner = match config {
+            Some(config) => SimdMultiPatternSearcher::with_config(&patterns, config.inner),
+            None => SimdMultiPatternSearcher::new(&patterns),
+        };
+        Ok(Self { inner })
+    }
+
+    /// `(pattern_index, offset)` for every match of any pattern
+    pub fn find_all(&self, py: Python<'_>, text: &Bound<'_, PyAny>) -> PyResult<Vec<(usize, usize)>> {
+        let text = ByteBuffer::get(text)?;
+        Ok(py.allow_threads(|| self.inner.find_all(text.as_slice())))
+    }
+
+    /// Number of patterns searched for
+    pub fn pattern_count(&self) -> usize {
+        self.inner.pattern_count()
+    }
+
+    /// Number of patterns searched for
+    pub fn __len__(&self) -> usize {
+        self.inner.pattern_count()
+    }
+
+    /// Get a string representation
+    pub fn __repr__(&self) -> String {
+        format!("SimdMultiPatternSearcher(patterns={})", self.inner.pattern_count())
+    }
+}
+
+/// Python wrapper for SimdHasher
+#[cfg(feature = "python")]
+#[pyclass(name = "SimdHasher")]
+#[derive(Default)]
+pub struct PySimdHasher {
+    inner: SimdHasher,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl PySimdHasher {
+    /// Create a new hasher
+    #[new]
+    pub fn new() -> Self {
+        Self { inner: SimdHasher::new() }
+    }
+
+    /// CRC-32/IEEE checksum
+    pub fn crc32(&self, py: Python<'_>, data: &Bound<'_, PyAny>) -> PyResult<u32> {
+        let data = ByteBuffer::get(data)?;
+        Ok(py.allow_threads(|| self.inner.crc32(data.as_slice())))
+    }
+
+    /// Continue a CRC-32 checksum with more data
+    pub fn crc32_update(&self, py: Python<'_>, crc: u32, data: &Bound<'_, PyAny>) -> PyResult<u32> {
+        let data = ByteBuffer::get(data)?;
+        Ok(py.allow_threads(|| self.inner.crc32_update(crc, data.as_slice())))
+    }
+
+    /// XXH3 64-bit hash
+    pub fn xxh3(&self, py: Python<'_>, data: &Bound<'_, PyAny>) -> PyResult<u64> {
+        let data = ByteBuffer::get(data)?;
+        Ok(py.allow_threads(|| self.inner.xxh3(data.as_slice())))
+    }
+
+    /// XXH64 hash with a seed
+    #[pyo3(signature = (data, seed=0))]
+    pub fn xxh64(&self, py: Python<'_>, data: &Bound<'_, PyAny>, seed: u64) -> PyResult<u64> {
+        let data = ByteBuffer::get(data)?;
+        Ok(py.allow_threads(|| self.inner.xxh64(data.as_slice(), seed)))
+    }
+
+    /// djb2 rolling hash; cheap but collision-prone
+    pub fn rolling_hash(&self, py: Python<'_>, data: &Bound<'_, PyAny>) -> PyResult<u64> {
+        let data = ByteBuffer::get(data)?;
+        Ok(py.allow_threads(|| self.inner.rolling_hash(data.as_slice())))
+    }
+
+    /// Get a string representation
+    pub fn __repr__(&self) -> String {
+        "SimdHasher()".to_string()
+    }
+}
+
+/// Python wrapper for SimdEntropyCalculator
+#[cfg(feature = "python")]
+#[pyclass(name = "SimdEntropyCalculator")]
+#[derive(Default)]
+pub struct PySimdEntropyCalculator {
+    inner: SimdEntropyCalculator,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl PySimdEntropyCalculator {
+    /// Create a new entropy calculator
+    #[new]
+    pub fn new() -> Self {
+        Self { inner: SimdEntropyCalculator::new() }
+    }
+
+    /// Shannon entropy in bits per byte (0.0-8.0)
+    pub fn calculate_entropy(&self, py: Python<'_>, data: &Bound<'_, PyAny>) -> PyResult<f64> {
+        let data = ByteBuffer::get(data)?;
+        Ok(py.allow_threads(|| self.inner.calculate_entropy(data.as_slice())))
+    }
+
+    /// `(start, end)` ranges whose sliding `window`-byte entropy reaches `threshold`
+    pub fn high_entropy_windows(
+        &self,
+        py: Python<'_>,
+        data: &Bound<'_, PyAny>,
+        window: usize,
+        threshold: f64,
+    ) -> PyResult<Vec<(usize, usize)>> {
+        let data = ByteBuffer::get(data)?;
+        let ranges = py.allow_threads(|| self.inner.high_entropy_windows(data.as_slice(), window, threshold));
+        Ok(ranges.into_iter().map(|r| (r.start, r.end)).collect())
+    }
+
+    /// Whether the data looks binary
+    pub fn is_binary(&self, py: Python<'_>, data: &Bound<'_, PyAny>) -> PyResult<bool> {
+        let data = ByteBuffer::get(data)?;
+        Ok(py.allow_threads(|| self.inner.is_binary(data.as_slice())))
+    }
+
+    /// Get a string representation
+    pub fn __repr__(&self) -> String {
+        "SimdEntropyCalculator()".to_string()
+    }
+}
+
+/// Python wrapper for SimdUtf8Validator
+#[cfg(feature = "python")]
+#[pyclass(name = "SimdUtf8Validator")]
+pub struct PySimdUtf8Validator {
+    inner: SimdUtf8Validator,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl PySimdUtf8Validator {
+    /// Create a new validator, optionally with an explicit SIMD config
+    #[new]
+    #[pyo3(signature = (config=None))]
+    pub fn new(config: Option<PySimdConfig>) -> Self {
+        Self {
+            inner: config.map_or_else(SimdUtf8Validator::new, |c| SimdUtf8Validator::with_config(c.inner)),
+        }
+    }
+
+    /// `(is_valid, error_offset)`, where `error_offset` is the first invalid byte
+    pub fn validate(&self, py: Python<'_>, data: &Bound<'_, PyAny>) -> PyResult<(bool, Option<usize>)> {
+        let data = ByteBuffer::get(data)?;
+        Ok(py.allow_threads(|| self.inner.validate(data.as_slice())))
+    }
+
+    /// `(char_count, is_valid, error_offset)`, counting code points
+    pub fn count_chars(&self, py: Python<'_>, data: &Bound<'_, PyAny>) -> PyResult<(usize, bool, Option<usize>)> {
+        let data = ByteBuffer::get(data)?;
+        Ok(py.allow_threads(|| self.inner.count_chars(data.as_slice())))
+    }
+
+    /// Get a string representation
+    pub fn __repr__(&self) -> String {
+        "SimdUtf8Validator()".to_string()
+    }
+}
+
 /// Python wrapper for PatternType
 #[cfg(feature = "python")]
 #[pyclass(name = "PatternType")]
@@ -304,6 +643,12 @@ impl PyPatternType {
         Self { name: "FilePath".to_string() }
     }
 
+    /// Stack trace pattern type
+    #[staticmethod]
+    pub fn stack_trace() -> Self {
+        Self { name: "StackTrace".to_string() }
+    }
+
     /// Get a string representation
     pub fn __repr__(&self) -> String {
         format!("PatternType({})", self.name)
@@ -360,7 +705,7 @@ impl PyPatternMatch {
     /// Get a dictionary representation
     pub fn to_dict(&self) -> Py<PyDict> {
         Python::with_gil(|py| {
-            let dict = PyDict::new_bound(py);
+            let dict = PyDict::new(py);
             dict.set_item("pattern", &self.pattern).unwrap();
             dict.set_item("matched_text", &self.matched_text).unwrap();
             dict.set_item("start", self.start).unwrap();
@@ -380,6 +725,20 @@ impl PyPatternMatch {
     }
 }
 
+#[cfg(feature = "python")]
+impl From<PatternMatch> for PyPatternMatch {
+    fn from(m: PatternMatch) -> Self {
+        Self {
+            pattern_type: PyPatternType { name: format!("{:?}", m.pattern_type) },
+            pattern: m.pattern,
+            matched_text: m.matched_text,
+            start: m.start,
+            end: m.end,
+            confidence: m.confidence,
+        }
+    }
+}
+
 /// Python wrapper for TextStatistics
 #[cfg(feature = "python")]
 #[pyclass(name = "TextStatistics")]
@@ -433,7 +792,7 @@ impl PyTextStatistics {
     /// Get a dictionary representation
     pub fn to_dict(&self) -> Py<PyDict> {
         Python::with_gil(|py| {
-            let dict = PyDict::new_bound(py);
+            let dict = PyDict::new(py);
             dict.set_item("characters", self.characters).unwrap();
             dict.set_item("bytes", self.bytes).unwrap();
             dict.set_item("lines", self.lines).unwrap();
@@ -497,7 +856,7 @@ impl PyContentAnalysis {
     /// Get a dictionary representation
     pub fn to_dict(&self) -> Py<PyDict> {
         Python::with_gil(|py| {
-            let dict = PyDict::new_bound(py);
+            let dict = PyDict::new(py);
             dict.set_item("path", &self.path).unwrap();
             dict.set_item("total_patterns", self.total_patterns).unwrap();
             dict.set_item("matches", self.matches.iter().map(|m| m.to_dict()).collect::<Vec<_>>()).unwrap();
@@ -516,6 +875,29 @@ impl PyContentAnalysis {
     }
 }
 
+#[cfg(feature = "python")]
+impl From<ContentAnalysis> for PyContentAnalysis {
+    fn from(analysis: ContentAnalysis) -> Self {
+        let statistics = analysis.statistics;
+        Self {
+            path: analysis.path,
+            total_patterns: analysis.total_patterns,
+            matches: analysis.matches.into_iter().map(PyPatternMatch::from).collect(),
+            statistics: PyTextStatistics {
+                characters: statistics.characters,
+                bytes: statistics.bytes,
+                lines: statistics.lines,
+                words: statistics.words,
+                avg_line_length: statistics.avg_line_length,
+                max_line_length: statistics.max_line_length,
+                whitespace_ratio: statistics.whitespace_ratio,
+                entropy: statistics.entropy,
+            },
+            issues: analysis.issues,
+        }
+    }
+}
+
 /// Python wrapper for FileClassification
 #[cfg(feature = "python")]
 #[pyclass(name = "FileClassification")]
@@ -542,7 +924,7 @@ impl PyFileClassification {
     /// Get a dictionary representation
     pub fn to_dict(&self) -> Py<PyDict> {
         Python::with_gil(|py| {
-            let dict = PyDict::new_bound(py);
+            let dict = PyDict::new(py);
             dict.set_item("path", &self.path).unwrap();
             dict.set_item("file_type", &self.file_type).unwrap();
             dict.set_item("confidence", self.confidence).unwrap();
@@ -582,60 +964,17 @@ impl PyPatternDetector {
     }
 
     /// Detect all patterns in the given text
-    pub fn detect_patterns(&self, text: &str) -> Vec<PyPatternMatch> {
-        let matches = self.inner.detect_patterns(text);
-        matches
-            .into_iter()
-            .map(|m| {
-                let pattern_type_name = format!("{:?}", m.pattern_type);
-                PyPatternMatch {
-                    pattern: m.pattern,
-                    matched_text: m.matched_text,
-                    start: m.start,
-                    end: m.end,
-                    confidence: m.confidence,
-                    pattern_type: PyPatternType { name: pattern_type_name },
-                }
-            })
-            .collect()
+    pub fn detect_patterns(&self, py: Python<'_>, text: &str) -> Vec<PyPatternMatch> {
+        let matches = py.allow_threads(|| self.inner.detect_patterns(text));
+        matches.into_iter().map(PyPatternMatch::from).collect()
     }
 
     /// Analyze content and return detailed results
-    pub fn analyze_content(&self, text: &str, path: &str) -> PyResult<PyContentAnalysis> {
+    pub fn analyze_content(&self, py: Python<'_>, text: &str, path: &str) -> PyResult<PyContentAnalysis> {
         let path = PathBuf::from(path);
-        let analysis = self.inner.analyze_content(text, &path)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
-
-        Ok(PyContentAnalysis {
-            path: analysis.path,
-            total_patterns: analysis.total_patterns,
-            matches: analysis
-                .matches
-                .into_iter()
-                .map(|m| {
-                    let pattern_type_name = format!("{:?}", m.pattern_type);
-                    PyPatternMatch {
-                        pattern: m.pattern,
-                        matched_text: m.matched_text,
-                        start: m.start,
-                        end: m.end,
-                        confidence: m.confidence,
-                        pattern_type: PyPatternType { name: pattern_type_name },
-                    }
-                })
-                .collect(),
-            statistics: PyTextStatistics {
-                characters: analysis.statistics.characters,
-                bytes: analysis.statistics.bytes,
-                lines: analysis.statistics.lines,
-                words: analysis.statistics.words,
-                avg_line_length: analysis.statistics.avg_line_length,
-                max_line_length: analysis.statistics.max_line_length,
-                whitespace_ratio: analysis.statistics.whitespace_ratio,
-                entropy: analysis.statistics.entropy,
-            },
-            issues: analysis.issues,
-        })
+        py.allow_threads(|| self.inner.analyze_content(text, &path))
+            .map(PyContentAnalysis::from)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))
     }
 
     /// Get a string representation
@@ -659,9 +998,10 @@ impl PyFileClassifier {
     }
 
     /// Classify a file based on its extension and content
-    pub fn classify(&self, path: &str, content: &[u8]) -> PyResult<PyFileClassification> {
+    pub fn classify(&self, py: Python<'_>, path: &str, content: &[u8]) -> PyResult<PyFileClassification> {
         let path = PathBuf::from(path);
-        let classification = FileClassifier::classify(&path, content)
+        let classification = py
+            .allow_threads(|| FileClassifier::classify(&path, content))
             .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
 
         Ok(PyFileClassification {
@@ -681,6 +1021,383 @@ impl PyFileClassifier {
     }
 }
 
+/// Python wrapper for StreamingHasher, shaped like a `hashlib` object
+#[cfg(feature = "python")]
+#[pyclass(name = "StreamingHasher")]
+#[derive(Clone)]
+pub struct PyStreamingHasher {
+    inner: StreamingHasher,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl PyStreamingHasher {
+    /// Start a digest (crc32, xxh64, xxh3 or blake3 if built with it)
+    #[new]
+    #[pyo3(signature = (algorithm="xxh3"))]
+    pub fn new(algorithm: &str) -> PyResult<Self> {
+        Ok(Self {
+            inner: StreamingHasher::new(parse_algorithm(algorithm)?),
+        })
+    }
+
+    /// Feed the next chunk of data
+    pub fn update(&mut self, py: Python<'_>, data: &[u8]) {
+        py.allow_threads(|| self.inner.update(data));
+    }
+
+    /// Digest of everything fed so far
+    pub fn digest(&self) -> Py<PyBytes> {
+        Python::with_gil(|py| PyBytes::new(py, &self.inner.finalize()).into())
+    }
+
+    /// Lowercase hex digest of everything fed so far
+    pub fn hexdigest(&self) -> String {
+        self.inner.finalize_hex()
+    }
+
+    /// A copy of the current state
+    pub fn copy(&self) -> Self {
+        self.clone()
+    }
+
+    /// Algorithm name
+    #[getter]
+    pub fn name(&self) -> &'static str {
+        self.inner.algorithm().as_str()
+    }
+
+    /// Number of bytes fed so far
+    #[getter]
+    pub fn bytes_hashed(&self) -> u64 {
+        self.inner.bytes_hashed()
+    }
+
+    /// Get a string representation
+    pub fn __repr__(&self) -> String {
+        format!(
+            "StreamingHasher(algorithm={}, bytes_hashed={})",
+            self.inner.algorithm().as_str(),
+            self.inner.bytes_hashed()
+        )
+    }
+}
+
+/// Python wrapper for a line found by `grep_file` or `grep_async`
+#[cfg(feature = "python")]
+#[pyclass(name = "GrepMatch")]
+#[derive(Clone)]
+pub struct PyGrepMatch {
+    /// Line number (1-indexed)
+    #[pyo3(get)]
+    pub line_number: usize,
+    /// Byte offset of the line in the file
+    #[pyo3(get)]
+    pub byte_offset: u64,
+    /// Matching line content
+    #[pyo3(get)]
+    pub line: String,
+    /// Path to the file containing the match
+    #[pyo3(get)]
+    pub path: String,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl PyGrepMatch {
+    /// Get a dictionary representation
+    pub fn to_dict(&self) -> Py<PyDict> {
+        Python::with_gil(|py| {
+            let dict = PyDict::new(py);
+            dict.set_item("line_number", self.line_number).unwrap();
+            dict.set_item("byte_offset", self.byte_offset).unwrap();
+            dict.set_item("line", &self.line).unwrap();
+            dict.set_item("path", &self.path).unwrap();
+            dict.into()
+        })
+    }
+
+    /// Get a string representation
+    pub fn __repr__(&self) -> String {
+        format!("GrepMatch(path={}, line_number={})", self.path, self.line_number)
+    }
+}
+
+#[cfg(feature = "python")]
+impl From<GrepMatch> for PyGrepMatch {
+    fn from(m: GrepMatch) -> Self {
+        Self {
+            line_number: m.line_number,
+            byte_offset: m.byte_offset,
+            line: m.line,
+            path: m.path.display().to_string(),
+        }
+    }
+}
+
+/// Python wrapper for the counts from `wc` or `wc_async`
+#[cfg(feature = "python")]
+#[pyclass(name = "WcCounts")]
+#[derive(Clone)]
+pub struct PyWcCounts {
+    /// Number of lines
+    #[pyo3(get)]
+    pub lines: u64,
+    /// Number of words
+    #[pyo3(get)]
+    pub words: u64,
+    /// Number of bytes
+    #[pyo3(get)]
+    pub bytes: u64,
+    /// Path to the file counted
+    #[pyo3(get)]
+    pub path: String,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl PyWcCounts {
+    /// Get a dictionary representation
+    pub fn to_dict(&self) -> Py<PyDict> {
+        Python::with_gil(|py| {
+            let dict = PyDict::new(py);
+            dict.set_item("lines", self.lines).unwrap();
+            dict.set_item("words", self.words).unwrap();
+            dict.set_item("bytes", self.bytes).unwrap();
+            dict.set_item("path", &self.path).unwrap();
+            dict.into()
+        })
+    }
+
+    /// Get a string representation
+    pub fn __repr__(&self) -> String {
+        format!("WcCounts(path={}, lines={}, words={}, bytes={})", self.path, self.lines, self.words, self.bytes)
+    }
+}
+
+#[cfg(feature = "python")]
+impl PyWcCounts {
+    fn new(path: &str, counts: WcCounts) -> Self {
+        Self {
+            lines: counts.lines,
+            words: counts.words,
+            bytes: counts.bytes,
+            path: path.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+fn parse_algorithm(algorithm: &str) -> PyResult<HashAlgorithm> {
+    algorithm
+        .parse()
+        .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)
+}
+
+/// Hex digest of a file, read in chunks so memory use stays bounded
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(signature = (path, algorithm="xxh3"))]
+pub fn hash_file(py: Python<'_>, path: &str, algorithm: &str) -> PyResult<String> {
+    let algorithm = parse_algorithm(algorithm)?;
+    py.allow_threads(|| {
+        std::fs::File::open(path)
+            .map_err(AiCoreutilsError::Io)
+            .and_then(|file| hash::hash_reader(algorithm, file))
+            .map(|hasher| hasher.finalize_hex())
+    })
+    .map_err(io_error)
+}
+
+/// Atomically replace a file's contents (temp file + fsync + rename)
+#[cfg(feature = "python")]
+#[pyfunction]
+pub fn atomic_write(py: Python<'_>, path: &str, data: &[u8]) -> PyResult<()> {
+    py.allow_threads(|| fs_utils::atomic_write(&PathBuf::from(path), data))
+        .map_err(io_error)
+}
+
+/// Atomically overwrite a file, returning the backup path if one was made
+#[cfg(feature = "python")]
+#[pyfunction]
+pub fn safe_overwrite(py: Python<'_>, path: &str, data: &[u8]) -> PyResult<Option<String>> {
+    py.allow_threads(|| fs_utils::safe_overwrite(&PathBuf::from(path), data))
+        .map(|backup| backup.map(|p| p.display().to_string()))
+        .map_err(io_error)
+}
+
+/// Collapse `.` and `..` components without touching the filesystem
+#[cfg(feature = "python")]
+#[pyfunction]
+pub fn normalize_path(path: &str) -> String {
+    fs_utils::paths::normalize(Path::new(path)).to_string_lossy().into_owned()
+}
+
+/// Remove a Windows `\\?\` prefix when the path means the same without it
+#[cfg(feature = "python")]
+#[pyfunction]
+pub fn strip_verbatim(path: &str) -> String {
+    fs_utils::paths::strip_verbatim(Path::new(path)).to_string_lossy().into_owned()
+}
+
+/// Expand a leading `~` to the home directory
+#[cfg(feature = "python")]
+#[pyfunction]
+pub fn expand_home(path: &str) -> String {
+    fs_utils::paths::expand_home(Path::new(path)).to_string_lossy().into_owned()
+}
+
+/// `path` relative to `base`, computed lexically; `None` if there is none
+#[cfg(feature = "python")]
+#[pyfunction]
+pub fn relative_path(path: &str, base: &str) -> Option<String> {
+    fs_utils::paths::relative_to(Path::new(path), Path::new(base)).map(|p| p.to_string_lossy().into_owned())
+}
+
+#[cfg(feature = "python")]
+fn io_error(e: AiCoreutilsError) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string())
+}
+
+/// Read a whole file without blocking the event loop
+///
+/// Returns an awaitable resolving to the file's bytes.
+#[cfg(feature = "python")]
+#[pyfunction]
+pub fn read_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    pyo3_async_runtimes::tokio::future_into_py(py, async move {
+        async_ops::async_read_file(Path::new(&path)).await.map_err(io_error)
+    })
+}
+
+/// Search a file for a literal pattern without blocking the event loop
+///
+/// The file is streamed, so memory stays bounded for large logs. Returns
+/// an awaitable resolving to a list of `GrepMatch`.
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(signature = (path, pattern, ignore_case=false, invert=false))]
+pub fn grep_async(py: Python<'_>, path: String, pattern: String, ignore_case: bool, invert: bool) -> PyResult<Bound<'_, PyAny>> {
+    pyo3_async_runtimes::tokio::future_into_py(py, grep(path, pattern, ignore_case, invert))
+}
+
+/// Search a file for a literal pattern, streaming it
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(signature = (path, pattern, ignore_case=false, invert=false))]
+pub fn grep_file(py: Python<'_>, path: String, pattern: String, ignore_case: bool, invert: bool) -> PyResult<Vec<PyGrepMatch>> {
+    block_on(py, grep(path, pattern, ignore_case, invert))
+}
+
+#[cfg(feature = "python")]
+async fn grep(path: String, pattern: String, ignore_case: bool, invert: bool) -> PyResult<Vec<PyGrepMatch>> {
+    let matches = async_ops::async_grep_file(Path::new(&path), &pattern, ignore_case, invert)
+        .await
+        .map_err(io_error)?;
+    Ok(matches.into_iter().map(PyGrepMatch::from).collect())
+}
+
+/// Paths of every file below a directory
+///
+/// Symlinks below `path` are not followed, and subdirectories that cannot
+/// be read are skipped.
+#[cfg(feature = "python")]
+#[pyfunction]
+pub fn walk_dir(py: Python<'_>, path: String) -> PyResult<Vec<String>> {
+    block_on(py, walk(path))
+}
+
+/// Awaitable version of `walk_dir`
+#[cfg(feature = "python")]
+#[pyfunction]
+pub fn walk_dir_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    pyo3_async_runtimes::tokio::future_into_py(py, walk(path))
+}
+
+#[cfg(feature = "python")]
+async fn walk(path: String) -> PyResult<Vec<String>> {
+    let files = async_ops::async_walk_dir(Path::new(&path)).await.map_err(io_error)?;
+    Ok(files.iter().map(|file| file.display().to_string()).collect())
+}
+
+/// Count lines, words and bytes in a file, as `wc` does in the C locale
+#[cfg(feature = "python")]
+#[pyfunction]
+pub fn wc(py: Python<'_>, path: String) -> PyResult<PyWcCounts> {
+    block_on(py, count(path))
+}
+
+/// Awaitable version of `wc`
+#[cfg(feature = "python")]
+#[pyfunction]
+pub fn wc_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    pyo3_async_runtimes::tokio::future_into_py(py, count(path))
+}
+
+#[cfg(feature = "python")]
+async fn count(path: String) -> PyResult<PyWcCounts> {
+    let counts = async_ops::async_wc(Path::new(&path)).await.map_err(io_error)?;
+    Ok(PyWcCounts::new(&path, counts))
+}
+
+/// Copy a file, returning the number of bytes copied
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(signature = (src, dest, buffer_size=8192))]
+pub fn copy_file(py: Python<'_>, src: String, dest: String, buffer_size: usize) -> PyResult<u64> {
+    block_on(py, copy(src, dest, buffer_size))
+}
+
+/// Awaitable version of `copy_file`
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(signature = (src, dest, buffer_size=8192))]
+pub fn copy_file_async(py: Python<'_>, src: String, dest: String, buffer_size: usize) -> PyResult<Bound<'_, PyAny>> {
+    pyo3_async_runtimes::tokio::future_into_py(py, copy(src, dest, buffer_size))
+}
+
+#[cfg(feature = "python")]
+async fn copy(src: String, dest: String, buffer_size: usize) -> PyResult<u64> {
+    let config = AsyncConfig {
+        buffer_size: buffer_size.max(1),
+        ..AsyncConfig::default()
+    };
+    async_ops::async_copy_file(Path::new(&src), Path::new(&dest), &config)
+        .await
+        .map_err(io_error)
+}
+
+/// Run `future` to completion on the shared runtime with the GIL released
+#[cfg(feature = "python")]
+fn block_on<F>(py: Python<'_>, future: F) -> F::Output
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    py.allow_threads(|| pyo3_async_runtimes::tokio::get_runtime().block_on(future))
+}
+
+/// Detect patterns in a file without blocking the event loop
+///
+/// The file is read asynchronously and analyzed, as text with invalid UTF-8
+/// replaced, on the blocking thread pool. Returns an awaitable resolving to
+/// a `ContentAnalysis`.
+#[cfg(feature = "python")]
+#[pyfunction]
+pub fn analyze_async(py: Python<'_>, path: String) -> PyResult<Bound<'_, PyAny>> {
+    pyo3_async_runtimes::tokio::future_into_py(py, async move {
+        let path = PathBuf::from(path);
+        let content = async_ops::async_read_file(&path).await.map_err(io_error)?;
+        let analysis = tokio::task::spawn_blocking(move || {
+            PatternDetector::new()?.analyze_content(&String::from_utf8_lossy(&content), &path)
+        })
+        .await
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?
+        .map_err(io_error)?;
+        Ok(PyContentAnalysis::from(analysis))
+    })
+}
+
 /// Python module definition
 #[cfg(feature = "python")]
 #[pymodule]
@@ -689,6 +1406,11 @@ fn ai_coreutils(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PySimdConfig>()?;
     m.add_class::<PySimdTextProcessor>()?;
     m.add_class::<PyTextMetrics>()?;
+    m.add_class::<PySimdPatternSearcher>()?;
+    m.add_class::<PySimdMultiPatternSearcher>()?;
+    m.add_class::<PySimdHasher>()?;
+    m.add_class::<PySimdEntropyCalculator>()?;
+    m.add_class::<PySimdUtf8Validator>()?;
     m.add_class::<PyPatternType>()?;
     m.add_class::<PyPatternMatch>()?;
     m.add_class::<PyTextStatistics>()?;
@@ -696,5 +1418,25 @@ fn ai_coreutils(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyFileClassification>()?;
     m.add_class::<PyPatternDetector>()?;
     m.add_class::<PyFileClassifier>()?;
+    m.add_class::<PyStreamingHasher>()?;
+    m.add_class::<PyGrepMatch>()?;
+    m.add_class::<PyWcCounts>()?;
+    m.add_function(wrap_pyfunction!(hash_file, m)?)?;
+    m.add_function(wrap_pyfunction!(atomic_write, m)?)?;
+    m.add_function(wrap_pyfunction!(safe_overwrite, m)?)?;
+    m.add_function(wrap_pyfunction!(normalize_path, m)?)?;
+    m.add_function(wrap_pyfunction!(strip_verbatim, m)?)?;
+    m.add_function(wrap_pyfunction!(expand_home, m)?)?;
+    m.add_function(wrap_pyfunction!(relative_path, m)?)?;
+    m.add_function(wrap_pyfunction!(read_async, m)?)?;
+    m.add_function(wrap_pyfunction!(grep_async, m)?)?;
+    m.add_function(wrap_pyfunction!(analyze_async, m)?)?;
+    m.add_function(wrap_pyfunction!(grep_file, m)?)?;
+    m.add_function(wrap_pyfunction!(walk_dir, m)?)?;
+    m.add_function(wrap_pyfunction!(walk_dir_async, m)?)?;
+    m.add_function(wrap_pyfunction!(wc, m)?)?;
+    m.add_function(wrap_pyfunction!(wc_async, m)?)?;
+    m.add_function(wrap_pyfunction!(copy_file, m)?)?;
+    m.add_function(wrap_pyfunction!(copy_file_async, m)?)?;
     Ok(())
 }
@@ -0,0 +1,698 @@
+//! SFTP backend for `sftp://` URLs (`sftp` feature)
+//!
+//! `sftp://[user@]host[:port]/path` names a file on an SSH server; a path
+//! starting with `/~/` is relative to the login directory. Sessions speak
+//! SFTP (russh-sftp) over the OpenSSH client's `sftp` subsystem, so keys,
+//! agents, `~/.ssh/config` and jump hosts work as they do for `ssh`. The
+//! client runs in batch mode: a host that needs a password fails instead of
+//! prompting.
+//!
+//! [`Pool`] keeps one session per user, host and port for the life of the
+//! process, so listing a tree or copying many files logs in once. Host keys
+//! are checked according to `remote.ssh.host_key_policy`, which defaults to
+//! refusing hosts that are not already in `known_hosts`.
+//!
+//! The pool runs its own tokio runtime and exposes blocking calls; they must
+//! not be made from inside another runtime.
+
+use crate::config::{self, SshConfig};
+use crate::error::{AiCoreutilsError, Result};
+use chrono::{DateTime, Utc};
+use russh_sftp::client::error::Error as SftpError;
+use russh_sftp::client::fs::Metadata;
+use russh_sftp::client::SftpSession;
+use russh_sftp::protocol::StatusCode;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Read, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::process::{Child, Command};
+
+/// URL scheme handled by this module
+pub const SCHEME: &str = "sftp://";
+
+/// Bytes per SFTP read or write request
+const CHUNK: usize = 64 * 1024;
+
+static POOL: OnceLock<Pool> = OnceLock::new();
+
+/// Whether `path` is an `sftp://` URL
+pub fn is_sftp(path: &Path) -> bool {
+    path.to_str().is_some_and(|s| s.starts_with(SCHEME))
+}
+
+/// A parsed `sftp://` URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SftpUrl {
+    /// Login name; `None` leaves it to OpenSSH
+    pub user: Option<String>,
+    /// Host name or address
+    pub host: String,
+    /// Port; `None` leaves it to OpenSSH
+    pub port: Option<u16>,
+    /// Path on the server; relative paths start in the login directory
+    pub path: String,
+}
+
+impl SftpUrl {
+    /// Parse `sftp://[user@]host[:port]/path`
+    pub fn parse(url: &str) -> Result<Self> {
+        let invalid = || AiCoreutilsError::InvalidInput(format!("expected sftp://[user@]host[:port]/path: {}", url));
+        let rest = url.strip_prefix(SCHEME).ok_or_else(invalid)?;
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let (user, host_port) = match authority.rsplit_once('@') {
+            Some((user, host_port)) => (Some(user.to_string()), host_port),
+            None => (None, authority),
+        };
+
+        // Bracketed IPv6 addresses contain colons of their own
+        let (host, port) = match host_port.strip_prefix('[') {
+            Some(bracketed) => {
+                let (host, after) = bracketed.split_once(']').ok_or_else(invalid)?;
+                (host, after.strip_prefix(':'))
+            }
+            None => match host_port.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (host_port, None),
+            },
+        };
+        let port = port.map(|p| p.parse::<u16>().map_err(|_| invalid())).transpose()?;
+        if host.is_empty() || user.as_deref() == Some("") {
+            return Err(invalid());
+        }
+
+        let path = match path {
+            "~" => ".".to_string(),
+            path => match path.strip_prefix("~/") {
+                Some(relative) => relative.to_string(),
+                None => format!("/{}", path),
+            },
+        };
+
+        Ok(Self {
+            user,
+            host: host.to_string(),
+            port,
+            path,
+        })
+    }
+
+    /// URL of the entry `name` inside this directory
+    pub fn join(&self, name: &str) -> Self {
+        let path = if self.path.ends_with('/') {
+            format!("{}{}", self.path, name)
+        } else if self.path == "." {
+            name.to_string()
+        } else {
+            format!("{}/{}", self.path, name)
+        };
+        Self { path, ..self.clone() }
+    }
+
+    /// Pool key: sessions are shared by URLs with the same user, host and port
+    fn key(&self) -> String {
+        format!(
+            "{}@{}:{}",
+            self.user.as_deref().unwrap_or(""),
+            self.host,
+            self.port.map(|p| p.to_string()).unwrap_or_default()
+        )
+    }
+}
+
+impl fmt::Display for SftpUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(SCHEME)?;
+        if let Some(user) = &self.user {
+            write!(f, "{}@", user)?;
+        }
+        if self.host.contains(':') {
+            write!(f, "[{}]", self.host)?;
+        } else {
+            f.write_str(&self.host)?;
+        }
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+        match self.path.strip_prefix('/') {
+            Some(absolute) => write!(f, "/{}", absolute),
+            None if self.path == "." => f.write_str("/~"),
+            None => write!(f, "/~/{}", self.path),
+        }
+    }
+}
+
+/// Metadata of a remote file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stat {
+    /// Size in bytes
+    pub size: u64,
+    /// Last modification, if the server reports it
+    pub modified: Option<DateTime<Utc>>,
+    /// Whether it is a directory
+    pub is_dir: bool,
+    /// Whether it is a symbolic link (only from [`Pool::read_dir`])
+    pub is_symlink: bool,
+    /// Permission bits, if the server reports them
+    pub permissions: Option<u32>,
+}
+
+impl From<&Metadata> for Stat {
+    fn from(metadata: &Metadata) -> Self {
+        Self {
+            size: metadata.size.unwrap_or(0),
+            modified: metadata.mtime.and_then(|t| DateTime::from_timestamp(t as i64, 0)),
+            is_dir: metadata.is_dir(),
+            is_symlink: metadata.is_symlink(),
+            permissions: metadata.permissions.map(|mode| mode & 0o7777),
+        }
+    }
+}
+
+/// A session and the ssh process carrying it
+struct Connection {
+    sftp: SftpSession,
+    /// Killed when the connection is dropped; `None` for in-process streams
+    _child: Option<Child>,
+}
+
+/// SFTP sessions, one per user, host and port
+pub struct Pool {
+    runtime: tokio::runtime::Runtime,
+    sessions: Mutex<HashMap<String, Arc<Connection>>>,
+    config: SshConfig,
+}
+
+/// The process-wide pool, configured from `remote.ssh`
+pub fn pool() -> Result<&'static Pool> {
+    if let Some(pool) = POOL.get() {
+        return Ok(pool);
+    }
+    let pool = Pool::new(config::get().remote.ssh.clone())?;
+    Ok(POOL.get_or_init(|| pool))
+}
+
+impl Pool {
+    /// Empty pool connecting with `config`
+    pub fn new(config: SshConfig) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("ai-coreutils-sftp")
+            .enable_all()
+            .build()?;
+        Ok(Self {
+            runtime,
+            sessions: Mutex::new(HashMap::new()),
+            config,
+        })
+    }
+
+    /// Metadata of `url`, following symbolic links
+    pub fn stat(&self, url: &SftpUrl) -> Result<Stat> {
+        let connection = self.session(url)?;
+        self.block_on(connection.sftp.metadata(url.path.as_str()))
+            .map(|metadata| Stat::from(&metadata))
+            .map_err(|e| sftp_error(url, e))
+    }
+
+    /// Whether `url` exists
+    pub fn exists(&self, url: &SftpUrl) -> Result<bool> {
+        match self.stat(url) {
+            Ok(_) => Ok(true),
+            Err(AiCoreutilsError::PathNotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Entries of the directory `url`, without `.` and `..`, sorted by name
+    pub fn read_dir(&self, url: &SftpUrl) -> Result<Vec<(String, Stat)>> {
+        let connection = self.session(url)?;
+        let entries = self
+            .block_on(connection.sftp.read_dir(url.path.as_str()))
+            .map_err(|e| sftp_error(url, e))?;
+        let mut entries: Vec<_> = entries
+            .map(|entry| (entry.file_name(), Stat::from(&entry.metadata())))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(entries)
+    }
+
+    /// Create the directory `url`
+    pub fn create_dir(&self, url: &SftpUrl) -> Result<()> {
+        let connection = self.session(url)?;
+        self.block_on(connection.sftp.create_dir(url.path.as_str()))
+            .map_err(|e| sftp_error(url, e))
+    }
+
+    /// Pass up to `len` bytes of `url` from `start` to `sink`, chunk by chunk
+    ///
+    /// Stops early at the end of the file; returns the bytes passed.
+    pub fn read_range(
+        &self,
+        url: &SftpUrl,
+        start: u64,
+        len: u64,
+        mut sink: impl FnMut(&[u8]) -> Result<()>,
+    ) -> Result<u64> {
+        let connection = self.session(url)?;
+        self.block_on(async move {
+            let mut file = connection.sftp.open(url.path.as_str()).await.map_err(|e| sftp_error(url, e))?;
+            file.seek(SeekFrom::Start(start)).await?;
+            let mut buffer = vec![0u8; CHUNK];
+            let mut read = 0u64;
+            let result = async {
+                while read < len {
+                    let want = (len - read).min(CHUNK as u64) as usize;
+                    let n = file.read(&mut buffer[..want]).await?;
+                    if n == 0 {
+                        break;
+                    }
+                    sink(&buffer[..n])?;
+                    read += n as u64;
+                }
+                Ok::<_, AiCoreutilsError>(read)
+            }
+            .await;
+            file.shutdown().await?;
+            result
+        })
+    }
+
+    /// Write everything from `reader` to `url`, replacing it
+    ///
+    /// `on_chunk` is called with the size of each chunk written.
+    pub fn write(
+        &self,
+        url: &SftpUrl,
+        mut reader: impl Read,
+        mut on_chunk: impl FnMut(u64) -> Result<()>,
+    ) -> Result<u64> {
+        let connection = self.session(url)?;
+        self.block_on(async move {
+            let mut file = connection.sftp.create(url.path.as_str()).await.map_err(|e| sftp_error(url, e))?;
+            let mut buffer = vec![0u8; CHUNK];
+            let mut written = 0u64;
+            let result = async {
+                loop {
+                    let n = reader.read(&mut buffer)?;
+                    if n == 0 {
+                        break;
+                    }
+                    file.write_all(&buffer[..n]).await?;
+                    written += n as u64;
+                    on_chunk(n as u64)?;
+                }
+                Ok::<_, AiCoreutilsError>(written)
+            }
+            .await;
+            file.shutdown().await?;
+            result
+        })
+    }
+
+    /// Run `future` on the pool's runtime
+    fn block_on<F: std::future::Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// The pooled session for `url`, connecting if there is none
+    fn session(&self, url: &SftpUrl) -> Result<Arc<Connection>> {
+        if tokio::runtime::Handle::try_current().is_ok() {
+            return Err(AiCoreutilsError::NotSupported(
+                "SFTP calls cannot be made from inside an async runtime".to_string(),
+            ));
+        }
+        let key = url.key();
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(connection) = sessions.get(&key) {
+            return Ok(connection.clone());
+        }
+        let connection = Arc::new(self.runtime.block_on(self.connect(url))?);
+        sessions.insert(key, connection.clone());
+        Ok(connection)
+    }
+
+    /// Arguments for the ssh client reaching `url`'s host
+    fn ssh_args(&self, url: &SftpUrl) -> Vec<String> {
+        let mut args = vec![
+            "-o".to_string(),
+            "BatchMode=yes".to_string(),
+            "-o".to_string(),
+            format!("StrictHostKeyChecking={}", self.config.host_key_policy.as_ssh_option()),
+            "-o".to_string(),
+            format!("ConnectTimeout={}", self.config.connect_timeout),
+        ];
+        if let Some(known_hosts) = &self.config.known_hosts {
+            args.extend(["-o".to_string(), format!("UserKnownHostsFile={}", known_hosts.display())]);
+        }
+        if let Some(identity) = &self.config.identity_file {
+            args.extend(["-i".to_string(), identity.display().to_string()]);
+        }
+        if let Some(port) = url.port {
+            args.extend(["-p".to_string(), port.to_string()]);
+        }
+        if let Some(user) = &url.user {
+            args.extend(["-l".to_string(), user.clone()]);
+        }
+        // "--" keeps a host name starting with "-" from being read as an option
+        args.extend(["-s".to_string(), "--".to_string(), url.host.clone(), "sftp".to_string()]);
+        args
+    }
+
+    /// Start the ssh client and open an SFTP session over its stdio
+    async fn connect(&self, url: &SftpUrl) -> Result<Connection> {
+        let mut child = Command::new(&self.config.program)
+            .args(self.ssh_args(url))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| remote_error(url, format!("cannot run {}: {}", self.config.program, e)))?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(remote_error(url, "ssh stdio unavailable"));
+        };
+
+        let timeout = Duration::from_secs(self.config.connect_timeout.max(1));
+        let session = tokio::time::timeout(timeout, SftpSession::new(tokio::io::join(stdout, stdin))).await;
+        match session {
+            Ok(Ok(sftp)) => Ok(Connection {
+                sftp,
+                _child: Some(child),
+            }),
+            failed => {
+                // ssh explains login and host key failures on stderr
+                let _ = child.start_kill();
+                let mut stderr = String::new();
+                if let Some(mut pipe) = child.stderr.take() {
+                    let _ = tokio::time::timeout(Duration::from_secs(1), pipe.read_to_string(&mut stderr)).await;
+                }
+                let reason = match (stderr.lines().rfind(|l| !l.trim().is_empty()), failed) {
+                    (Some(line), _) => line.trim().to_string(),
+                    (None, Ok(Err(e))) => e.to_string(),
+                    (None, _) => "timed out".to_string(),
+                };
+                Err(remote_error(url, format!("cannot open SFTP session: {}", reason)))
+            }
+        }
+    }
+
+    /// Serve `root` as `url`'s host from an in-process SFTP server, in
+    /// place of ssh
+    #[cfg(test)]
+    pub(crate) fn serve_dir(&self, url: &SftpUrl, root: &Path) -> Result<()> {
+        let (client, server) = tokio::io::duplex(1 << 20);
+        let handler = tests::DirServer::new(root);
+        let sftp = self.runtime.block_on(async move {
+            russh_sftp::server::run(server, handler).await;
+            SftpSession::new(client).await
+        });
+        let sftp = sftp.map_err(|e| sftp_error(url, e))?;
+        let connection = Arc::new(Connection { sftp, _child: None });
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner()).insert(url.key(), connection);
+        Ok(())
+    }
+}
+
+fn remote_error(url: &SftpUrl, message: impl fmt::Display) -> AiCoreutilsError {
+    AiCoreutilsError::Remote(format!("{}: {}", url, message))
+}
+
+/// Map SFTP status codes onto the crate's errors
+fn sftp_error(url: &SftpUrl, error: SftpError) -> AiCoreutilsError {
+    match &error {
+        SftpError::Status(status) if status.status_code == StatusCode::NoSuchFile => {
+            AiCoreutilsError::PathNotFound(PathBuf::from(url.to_string()))
+        }
+        SftpError::Status(status) if status.status_code == StatusCode::PermissionDenied => {
+            AiCoreutilsError::PermissionDenied(PathBuf::from(url.to_string()))
+        }
+        _ => remote_error(url, error),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use russh_sftp::protocol::{
+        Attrs, Data, File, FileAttributes, Handle, Name, OpenFlags, Status, Version,
+    };
+    use std::io::{Seek, Write};
+    use tempfile::TempDir;
+
+    /// Minimal SFTP server over a local directory, standing in for sshd
+    pub(crate) struct DirServer {
+        root: PathBuf,
+        handles: HashMap<String, Open>,
+        next: u32,
+    }
+
+    enum Open {
+        File(std::fs::File),
+        Dir(Option<Vec<File>>),
+    }
+
+    impl DirServer {
+        pub(crate) fn new(root: &Path) -> Self {
+            Self {
+                root: root.to_path_buf(),
+                handles: HashMap::new(),
+                next: 0,
+            }
+        }
+
+        fn local(&self, path: &str) -> PathBuf {
+            self.root.join(path.trim_start_matches('/'))
+        }
+
+        fn handle(&mut self, id: u32, open: Open) -> Handle {
+            self.next += 1;
+            let handle = self.next.to_string();
+            self.handles.insert(handle.clone(), open);
+            Handle { id, handle }
+        }
+
+        fn attrs(path: &Path) -> std::result::Result<FileAttributes, StatusCode> {
+            std::fs::metadata(path).map(|m| FileAttributes::from(&m)).map_err(status)
+        }
+    }
+
+    fn status(e: std::io::Error) -> StatusCode {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => StatusCode::NoSuchFile,
+            std::io::ErrorKind::PermissionDenied => StatusCode::PermissionDenied,
+            _ => StatusCode::Failure,
+        }
+    }
+
+    fn ok(id: u32) -> Status {
+        Status {
+            id,
+            status_code: StatusCode::Ok,
+            error_message: "Ok".to_string(),
+            language_tag: "en-US".to_string(),
+        }
+    }
+
+    impl russh_sftp::server::Handler for DirServer {
+        type Error = StatusCode;
+
+        fn unimplemented(&self) -> Self::Error {
+            StatusCode::OpUnsupported
+        }
+
+        async fn init(&mut self, _: u32, _: HashMap<String, String>) -> std::result::Result<Version, Self::Error> {
+            Ok(Version::new())
+        }
+
+        async fn open(
+            &mut self,
+            id: u32,
+            filename: String,
+            pflags: OpenFlags,
+            _: FileAttributes,
+        ) -> std::result::Result<Handle, Self::Error> {
+            let file = std::fs::OpenOptions::new()
+                .read(pflags.contains(OpenFlags::READ))
+                .write(pflags.contains(OpenFlags::WRITE))
+                .create(pflags.contains(OpenFlags::CREATE))
+                .truncate(pflags.contains(OpenFlags::TRUNCATE))
+                .open(self.local(&filename))
+                .map_err(status)?;
+            Ok(self.handle(id, Open::File(file)))
+        }
+
+        async fn close(&mut self, id: u32, handle: String) -> std::result::Result<Status, Self::Error> {
+            self.handles.remove(&handle);
+            Ok(ok(id))
+        }
+
+        async fn read(&mut self, id: u32, handle: String, offset: u64, len: u32) -> std::result::Result<Data, Self::Error> {
+            let Some(Open::File(file)) = self.handles.get_mut(&handle) else {
+                return Err(StatusCode::Failure);
+            };
+            file.seek(SeekFrom::Start(offset)).map_err(status)?;
+            let mut data = vec![0u8; len as usize];
+            let n = file.read(&mut data).map_err(status)?;
+            if n == 0 {
+                return Err(StatusCode::Eof);
+            }
+            data.truncate(n);
+            Ok(Data { id, data })
+        }
+
+        async fn write(&mut self, id: u32, handle: String, offset: u64, data: Vec<u8>) -> std::result::Result<Status, Self::Error> {
+            let Some(Open::File(file)) = self.handles.get_mut(&handle) else {
+                return Err(StatusCode::Failure);
+            };
+            file.seek(SeekFrom::Start(offset)).map_err(status)?;
+            file.write_all(&data).map_err(status)?;
+            Ok(ok(id))
+        }
+
+        async fn stat(&mut self, id: u32, path: String) -> std::result::Result<Attrs, Self::Error> {
+            Ok(Attrs { id, attrs: Self::attrs(&self.local(&path))? })
+        }
+
+        async fn lstat(&mut self, id: u32, path: String) -> std::result::Result<Attrs, Self::Error> {
+            Ok(Attrs { id, attrs: Self::attrs(&self.local(&path))? })
+        }
+
+        async fn opendir(&mut self, id: u32, path: String) -> std::result::Result<Handle, Self::Error> {
+            let mut files = Vec::new();
+            for entry in std::fs::read_dir(self.local(&path)).map_err(status)? {
+                let entry = entry.map_err(status)?;
+                let attrs = Self::attrs(&entry.path())?;
+                files.push(File::new(entry.file_name().to_string_lossy(), attrs));
+            }
+            Ok(self.handle(id, Open::Dir(Some(files))))
+        }
+
+        async fn readdir(&mut self, id: u32, handle: String) -> std::result::Result<Name, Self::Error> {
+            match self.handles.get_mut(&handle) {
+                Some(Open::Dir(files)) => match files.take() {
+                    Some(files) => Ok(Name { id, files }),
+                    None => Err(StatusCode::Eof),
+                },
+                _ => Err(StatusCode::Failure),
+            }
+        }
+
+        async fn mkdir(&mut self, id: u32, path: String, _: FileAttributes) -> std::result::Result<Status, Self::Error> {
+            std::fs::create_dir(self.local(&path)).map_err(status)?;
+            Ok(ok(id))
+        }
+
+        async fn realpath(&mut self, id: u32, path: String) -> std::result::Result<Name, Self::Error> {
+            Ok(Name { id, files: vec![File::dummy(path)] })
+        }
+    }
+
+    #[test]
+    fn test_parse_url() {
+        let url = SftpUrl::parse("sftp://deploy@build.example.com:2222/var/log/app.log").unwrap();
+        assert_eq!(url.user.as_deref(), Some("deploy"));
+        assert_eq!(url.host, "build.example.com");
+        assert_eq!(url.port, Some(2222));
+        assert_eq!(url.path, "/var/log/app.log");
+        assert_eq!(url.to_string(), "sftp://deploy@build.example.com:2222/var/log/app.log");
+
+        let home = SftpUrl::parse("sftp://host/~/notes.txt").unwrap();
+        assert_eq!(home.user, None);
+        assert_eq!(home.path, "notes.txt");
+        assert_eq!(home.to_string(), "sftp://host/~/notes.txt");
+        assert_eq!(SftpUrl::parse("sftp://host").unwrap().path, "/");
+
+        let ipv6 = SftpUrl::parse("sftp://[::1]:22/tmp").unwrap();
+        assert_eq!(ipv6.host, "::1");
+        assert_eq!(ipv6.to_string(), "sftp://[::1]:22/tmp");
+
+        assert!(SftpUrl::parse("sftp:///path").is_err());
+        assert!(SftpUrl::parse("sftp://host:port/path").is_err());
+        assert!(SftpUrl::parse("https://host/path").is_err());
+    }
+
+    #[test]
+    fn test_join() {
+        let dir = SftpUrl::parse("sftp://host/srv/data").unwrap();
+        assert_eq!(dir.join("a.txt").to_string(), "sftp://host/srv/data/a.txt");
+        assert_eq!(SftpUrl::parse("sftp://host/").unwrap().join("a").path, "/a");
+        assert_eq!(SftpUrl::parse("sftp://host/~").unwrap().join("a").to_string(), "sftp://host/~/a");
+    }
+
+    #[test]
+    fn test_ssh_args() {
+        let config = SshConfig {
+            host_key_policy: crate::config::HostKeyPolicy::AcceptNew,
+            known_hosts: Some(PathBuf::from("/etc/ai/known_hosts")),
+            ..SshConfig::default()
+        };
+        let pool = Pool::new(config).unwrap();
+        let args = pool.ssh_args(&SftpUrl::parse("sftp://ci@example.com:2200/x").unwrap());
+        let joined = args.join(" ");
+        assert!(joined.starts_with("-o BatchMode=yes -o StrictHostKeyChecking=accept-new"));
+        assert!(joined.contains("-o UserKnownHostsFile=/etc/ai/known_hosts"));
+        assert!(joined.ends_with("-p 2200 -l ci -s -- example.com sftp"));
+    }
+
+    #[test]
+    fn test_connect_failure() {
+        let config = SshConfig {
+            program: "ai-coreutils-no-such-ssh".to_string(),
+            ..SshConfig::default()
+        };
+        let pool = Pool::new(config).unwrap();
+        let err = pool.stat(&SftpUrl::parse("sftp://host/x").unwrap()).unwrap_err();
+        assert!(matches!(err, AiCoreutilsError::Remote(_)));
+        assert!(err.to_string().contains("cannot run"));
+    }
+
+    #[test]
+    fn test_pool_operations() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("data.txt"), b"0123456789").unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        let pool = Pool::new(SshConfig::default()).unwrap();
+        let root = SftpUrl::parse("sftp://test-host/").unwrap();
+        pool.serve_dir(&root, dir.path()).unwrap();
+
+        let stat = pool.stat(&root.join("data.txt")).unwrap();
+        assert_eq!(stat.size, 10);
+        assert!(!stat.is_dir);
+        assert!(pool.stat(&root.join("sub")).unwrap().is_dir);
+        assert!(!pool.exists(&root.join("missing")).unwrap());
+        assert!(matches!(
+            pool.stat(&root.join("missing")),
+            Err(AiCoreutilsError::PathNotFound(_))
+        ));
+
+        let names: Vec<_> = pool.read_dir(&root).unwrap().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["data.txt", "sub"]);
+
+        let mut read = Vec::new();
+        let n = pool
+            .read_range(&root.join("data.txt"), 3, 4, |chunk| {
+                read.extend_from_slice(chunk);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!((n, read.as_slice()), (4, b"3456".as_slice()));
+
+        let mut chunks = 0;
+        let written = pool
+            .write(&root.join("sub/up.txt"), &b"uploaded"[..], |_| {
+                chunks += 1;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!((written, chunks), (8, 1));
+        assert_eq!(std::fs::read(dir.path().join("sub/up.txt")).unwrap(), b"uploaded");
+
+        pool.create_dir(&root.join("made")).unwrap();
+        assert!(dir.path().join("made").is_dir());
+    }
+}
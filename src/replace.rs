@@ -0,0 +1,261 @@
+//! Find-and-replace core for ai-sd
+//!
+//! [`Replacer`] applies a regex (or literal) pattern with a replacement
+//! template to a buffer, line by line, and reports every changed line so a
+//! caller can show a diff before writing anything. Templates use the `regex`
+//! crate syntax: `$1`, `${name}` and `$$` for a literal dollar. References to
+//! groups the pattern does not define are rejected up front rather than
+//! silently expanding to nothing.
+//!
+//! As in ai-grep, matches never span lines, and line terminators (`\n` or
+//! `\r\n`) are preserved exactly.
+
+use crate::async_ops::raw_lines;
+use crate::error::{AiCoreutilsError, Result};
+use regex::bytes::{Regex, RegexBuilder};
+
+/// Bytes examined when deciding whether a file is binary
+const BINARY_SAMPLE: usize = 8 * 1024;
+
+/// A compiled pattern and replacement template
+#[derive(Debug, Clone)]
+pub struct Replacer {
+    regex: Regex,
+    template: Vec<u8>,
+    /// Insert the template as-is instead of expanding `$` references
+    literal: bool,
+}
+
+/// One changed line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineChange {
+    /// Line number (1-indexed)
+    pub line_number: usize,
+    /// Line content before replacement, without its terminator
+    pub before: Vec<u8>,
+    /// Line content after replacement, without its terminator
+    pub after: Vec<u8>,
+    /// Number of replacements made on this line
+    pub replacements: usize,
+}
+
+/// The result of replacing within one buffer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Replacement {
+    /// Full new content
+    pub content: Vec<u8>,
+    /// Changed lines, in order
+    pub changes: Vec<LineChange>,
+    /// Total replacements across all lines
+    pub replacements: usize,
+}
+
+impl Replacement {
+    /// Whether any replacement changed the content
+    ///
+    /// A match replaced with identical text counts as a replacement but not
+    /// as a change.
+    pub fn is_changed(&self) -> bool {
+        !self.changes.is_empty()
+    }
+}
+
+impl Replacer {
+    /// Compile a pattern and replacement template
+    ///
+    /// With `fixed_strings`, both the pattern and the replacement are taken
+    /// literally.
+    pub fn new(pattern: &str, replacement: &str, fixed_strings: bool, ignore_case: bool) -> Result<Self> {
+        let source = if fixed_strings {
+            regex::escape(pattern)
+        } else {
+            pattern.to_string()
+        };
+        let regex = RegexBuilder::new(&source)
+            .case_insensitive(ignore_case)
+            .build()
+            .map_err(|e| AiCoreutilsError::InvalidInput(format!("Invalid pattern: {}", e)))?;
+
+        let replacer = Self {
+            regex,
+            template: replacement.as_bytes().to_vec(),
+            literal: fixed_strings,
+        };
+        if !fixed_strings {
+            replacer.check_template()?;
+        }
+        Ok(replacer)
+    }
+
+    /// Replace every match in `data`
+    pub fn replace(&self, data: &[u8]) -> Replacement {
+        let mut result = Replacement {
+            content: Vec::with_capacity(data.len()),
+            ..Default::default()
+        };
+
+        for line in raw_lines(data) {
+            let (after, replacements) = self.replace_line(line.content);
+            result.replacements += replacements;
+            if after != line.content {
+                result.content.extend_from_slice(&after);
+                result.changes.push(LineChange {
+                    line_number: line.number,
+                    before: line.content.to_vec(),
+                    after,
+                    replacements,
+                });
+            } else {
+                result.content.extend_from_slice(line.content);
+            }
+            result.content.extend_from_slice(line.terminator);
+        }
+
+        result
+    }
+
+    /// Replace every match in one line, returning the new line and the count
+    fn replace_line(&self, line: &[u8]) -> (Vec<u8>, usize) {
+        let mut out = Vec::new();
+        let mut last = 0;
+        let mut count = 0;
+
+        for caps in self.regex.captures_iter(line) {
+            let whole = caps.get(0).expect("group 0 always participates");
+            out.extend_from_slice(&line[last..whole.start()]);
+            if self.literal {
+                out.extend_from_slice(&self.template);
+            } else {
+                caps.expand(&self.template, &mut out);
+            }
+            last = whole.end();
+            count += 1;
+        }
+
+        if count == 0 {
+            return (line.to_vec(), 0);
+        }
+        out.extend_from_slice(&line[last..]);
+        (out, count)
+    }
+
+    /// Reject `$` references to groups the pattern does not define
+    fn check_template(&self) -> Result<()> {
+        for name in template_refs(&self.template) {
+            let known = match name.parse::<usize>() {
+                Ok(index) => index < self.regex.captures_len(),
+                Err(_) => self.regex.capture_names().flatten().any(|n| n == name),
+            };
+            if known {
+                continue;
+            }
+            // `$1x` names a group "1x"; the user almost always meant `${1}x`
+            let digits: String = name.chars().take_while(|c| c.is_ascii_digit()).collect();
+            let hint = if !digits.is_empty() && digits != name {
+                format!(" (write ${{{}}} to follow a group number with text)", digits)
+            } else {
+                String::new()
+            };
+            return Err(AiCoreutilsError::InvalidInput(format!(
+                "Replacement refers to unknown group '{}'{}",
+                name, hint
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Group names referenced by a template, following `regex`'s expansion rules
+fn template_refs(template: &[u8]) -> Vec<String> {
+    let mut refs = Vec::new();
+    let mut rest = template;
+
+    while let Some(pos) = rest.iter().position(|&b| b == b'$') {
+        rest = &rest[pos + 1..];
+        match rest.first() {
+            Some(b'$') => rest = &rest[1..],
+            Some(b'{') => {
+                if let Some(end) = rest.iter().position(|&b| b == b'}') {
+                    refs.push(String::from_utf8_lossy(&rest[1..end]).into_owned());
+                    rest = &rest[end + 1..];
+                }
+            }
+            _ => {
+                let len = rest
+                    .iter()
+                    .take_while(|&&b| b.is_ascii_alphanumeric() || b == b'_')
+                    .count();
+                if len > 0 {
+                    refs.push(String::from_utf8_lossy(&rest[..len]).into_owned());
+                    rest = &rest[len..];
+                }
+            }
+        }
+    }
+
+    refs
+}
+
+/// Whether `data` looks binary (a NUL byte near the start, as GNU grep checks)
+pub fn looks_binary(data: &[u8]) -> bool {
+    data[..data.len().min(BINARY_SAMPLE)].contains(&0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_templates() {
+        let replacer = Replacer::new(r"(\w+)@(?P<host>\w+)", "${host}:$1", false, false).unwrap();
+        let result = replacer.replace(b"mail bob@example now\nnone\nx@y and z@w\n");
+        assert_eq!(result.content, b"mail example:bob now\nnone\ny:x and w:z\n");
+        assert_eq!(result.replacements, 3);
+        let lines: Vec<_> = result.changes.iter().map(|c| (c.line_number, c.replacements)).collect();
+        assert_eq!(lines, [(1, 1), (3, 2)]);
+        assert_eq!(result.changes[0].before, b"mail bob@example now");
+
+        // $$ is a literal dollar
+        let replacer = Replacer::new("cost", "$$5", false, false).unwrap();
+        assert_eq!(replacer.replace(b"cost").content, b"$5");
+    }
+
+    #[test]
+    fn test_unknown_groups_rejected() {
+        assert!(Replacer::new("(a)", "$2", false, false).is_err());
+        assert!(Replacer::new("(a)", "$1x", false, false).is_err());
+        assert!(Replacer::new("(a)", "${1}x", false, false).is_ok());
+        assert!(Replacer::new("(?P<n>a)", "$n $0", false, false).is_ok());
+        assert!(Replacer::new("(", "x", false, false).is_err());
+    }
+
+    #[test]
+    fn test_fixed_strings_and_case() {
+        let replacer = Replacer::new("a.b", "$1", true, false).unwrap();
+        assert_eq!(replacer.replace(b"a.b axb").content, b"$1 axb");
+
+        let replacer = Replacer::new("TODO", "DONE", false, true).unwrap();
+        assert_eq!(replacer.replace(b"todo Todo").content, b"DONE DONE");
+    }
+
+    #[test]
+    fn test_preserves_bytes_outside_matches() {
+        let data = b"keep\r\n\xff old \xfe\r\nold\n\nlast old";
+        let result = Replacer::new("old", "new", false, false).unwrap().replace(data);
+        assert_eq!(result.content, b"keep\r\n\xff new \xfe\r\nnew\n\nlast new");
+        assert_eq!(result.changes.len(), 3);
+
+        // Replacing a match with itself is not a change
+        let result = Replacer::new("keep", "keep", false, false).unwrap().replace(data);
+        assert_eq!(result.replacements, 1);
+        assert!(!result.is_changed());
+        assert_eq!(result.content, data);
+    }
+
+    #[test]
+    fn test_looks_binary() {
+        assert!(looks_binary(b"ELF\0\x01"));
+        assert!(!looks_binary("text é".as_bytes()));
+        assert!(!looks_binary(b""));
+    }
+}
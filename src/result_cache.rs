@@ -0,0 +1,177 @@
+//! Content-addressed cache of expensive analysis results
+//!
+//! Results such as ai-analyze's classification and pattern analysis depend
+//! only on a file's content and on the options used, so they are stored
+//! under a [`CacheKey`] hashed from both, and a repeated run over unchanged
+//! files reads them back instead of analyzing again. Renaming or touching a
+//! file keeps its entry; changing one byte, an option or the library
+//! version misses.
+//!
+//! Entries are JSON files, `<kind>/<2 hex digits>/<key>.json`, in
+//! `ai-coreutils/analysis` under the platform cache directory, in the
+//! [session](crate::session) cache inside a session, or in `cache.dir` /
+//! `AI_COREUTILS_CACHE_DIR`. They are written to a temporary file and
+//! renamed into place, so concurrent runs never read half an entry. Setting
+//! `cache.enabled = false` or `AI_COREUTILS_CACHE=0` turns caching off.
+
+use crate::config;
+use crate::error::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Version and features that change analysis results, so different builds
+/// never share entries
+const BUILD: [&str; 4] = [
+    env!("CARGO_PKG_VERSION"),
+    if cfg!(feature = "images") { "images" } else { "" },
+    if cfg!(feature = "documents") { "documents" } else { "" },
+    if cfg!(feature = "compression") { "compression" } else { "" },
+];
+
+/// Identity of one cached result: what was computed, over what, and how
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    kind: String,
+    hash: String,
+}
+
+impl CacheKey {
+    /// The key for a `kind` of result over `content`, computed with `options`
+    ///
+    /// `options` must hold everything besides the content the result depends
+    /// on, such as thresholds, enabled detectors and, where it matters, the
+    /// file name.
+    pub fn new(kind: &str, content: &[u8], options: &serde_json::Value) -> Self {
+        let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+        let options = options.to_string();
+        let parts = BUILD.iter().map(|part| part.as_bytes()).chain([kind.as_bytes(), options.as_bytes()]);
+        for part in parts {
+            hasher.update(&(part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+        hasher.update(&(content.len() as u64).to_le_bytes());
+        hasher.update(content);
+        Self {
+            kind: kind.to_string(),
+            hash: format!("{:032x}", hasher.digest128()),
+        }
+    }
+
+    /// Kind of result
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// Hex digest of kind, content and options
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+}
+
+/// A cache directory
+#[derive(Debug, Clone)]
+pub struct ResultCache {
+    dir: PathBuf,
+}
+
+impl ResultCache {
+    /// A cache kept in `dir`, which is created on first store
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The cache described by the shared configuration, if enabled
+    pub fn from_config() -> Option<Self> {
+        let cache = &config::get().cache;
+        if !cache.enabled {
+            return None;
+        }
+        let dir = match (&cache.dir, crate::session::Session::current()) {
+            (Some(dir), _) => dir.clone(),
+            (None, Some(session)) => session.cache_dir().join("analysis"),
+            (None, None) => dirs::cache_dir()?.join("ai-coreutils").join("analysis"),
+        };
+        Some(Self::new(dir))
+    }
+
+    /// Directory holding the cache
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The result stored under `key`, if any
+    ///
+    /// An entry that cannot be read or parsed, for example one written by a
+    /// build with a different layout, is a miss.
+    pub fn get<T: DeserializeOwned>(&self, key: &CacheKey) -> Option<T> {
+        let contents = fs::read(self.path(key)).ok()?;
+        serde_json::from_slice(&contents).ok()
+    }
+
+    /// Store `value` under `key`, replacing any previous entry
+    pub fn put<T: Serialize>(&self, key: &CacheKey, value: &T) -> Result<()> {
+        let path = self.path(key);
+        let Some(dir) = path.parent() else {
+            return Ok(());
+        };
+        fs::create_dir_all(dir)?;
+
+        // Each writer has its own temporary file; the last rename wins
+        let temp = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4().simple()));
+        let written = fs::File::create(&temp).map_err(Into::into).and_then(|file| {
+            let mut out = std::io::BufWriter::new(file);
+            serde_json::to_writer(&mut out, value)?;
+            out.flush()?;
+            Ok(())
+        });
+        let renamed = written.and_then(|()| fs::rename(&temp, &path).map_err(Into::into));
+        if renamed.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+        renamed
+    }
+
+    fn path(&self, key: &CacheKey) -> PathBuf {
+        self.dir
+            .join(&key.kind)
+            .join(&key.hash[..2])
+            .join(format!("{}.json", key.hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_key_depends_on_content_options_and_kind() {
+        let options = json!({"min_confidence": 0.5});
+        let key = CacheKey::new("analysis", b"hello", &options);
+        assert_eq!(key, CacheKey::new("analysis", b"hello", &options));
+        assert_eq!(key.hash().len(), 32);
+
+        assert_ne!(key, CacheKey::new("analysis", b"hellp", &options));
+        assert_ne!(key, CacheKey::new("analysis", b"hello", &json!({"min_confidence": 0.6})));
+        assert_ne!(key.hash(), CacheKey::new("classification", b"hello", &options).hash());
+    }
+
+    #[test]
+    fn test_put_and_get() {
+        let dir = TempDir::new().unwrap();
+        let cache = ResultCache::new(dir.path().join("cache"));
+        let key = CacheKey::new("analysis", b"content", &json!({}));
+
+        assert_eq!(cache.get::<Vec<u32>>(&key), None);
+        cache.put(&key, &vec![1u32, 2, 3]).unwrap();
+        assert_eq!(cache.get::<Vec<u32>>(&key), Some(vec![1, 2, 3]));
+        assert_eq!(cache.get::<String>(&key), None);
+
+        cache.put(&key, &vec![4u32]).unwrap();
+        assert_eq!(cache.get::<Vec<u32>>(&key), Some(vec![4]));
+    }
+}
@@ -0,0 +1,201 @@
+//! Seeded shuffling and sampling
+//!
+//! Used by `ai-shuf` and `ai-sample`. [`Rng`] is a SplitMix64 generator
+//! implemented here rather than taken from a crate, so a seed gives the same
+//! permutation or sample on every platform and every release, and a
+//! dataset split can be reproduced from the seed recorded with it.
+//! [`Reservoir`] keeps a uniform sample of a fixed size from a stream of
+//! unknown length.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seeded pseudo-random number generator (SplitMix64)
+///
+/// Not for cryptographic use.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Generator whose output is determined by `seed`
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// A fresh seed from the clock and process id, for runs without `--seed`
+    ///
+    /// Record it with the output so the run can be repeated.
+    pub fn random_seed() -> u64 {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Rng::new(nanos ^ (u64::from(std::process::id()) << 32)).next_u64()
+    }
+
+    /// Next 64 random bits
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform integer in `0..n`, without modulo bias; `n` must not be 0
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "Rng::below(0)");
+        // Lemire's multiply-and-reject
+        let threshold = n.wrapping_neg() % n;
+        loop {
+            let product = u128::from(self.next_u64()) * u128::from(n);
+            if product as u64 >= threshold {
+                return (product >> 64) as u64;
+            }
+        }
+    }
+
+    /// Uniform fraction in `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Shuffle `items` into a uniformly random order (Fisher-Yates)
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        self.partial_shuffle(items, items.len());
+    }
+
+    /// Move `amount` items chosen uniformly at random, in random order, to
+    /// the front of `items`
+    ///
+    /// Costs `amount` swaps however long `items` is; the rest of the slice
+    /// is left in no particular order. With `amount >= items.len()` this is
+    /// [`Rng::shuffle`].
+    pub fn partial_shuffle<T>(&mut self, items: &mut [T], amount: usize) {
+        let len = items.len();
+        for i in 0..amount.min(len.saturating_sub(1)) {
+            let j = i + self.below((len - i) as u64) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+/// Uniform random sample of at most `capacity` items from a stream
+///
+/// Algorithm R: after `n` items have been offered, each of them is held
+/// with probability `capacity / n`. Items are only built when they are
+/// kept, so rejected lines cost no allocation.
+#[derive(Debug, Clone)]
+pub struct Reservoir<T> {
+    capacity: usize,
+    seen: u64,
+    items: Vec<T>,
+}
+
+impl<T> Reservoir<T> {
+    /// Empty reservoir holding up to `capacity` items
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            seen: 0,
+            items: Vec::with_capacity(capacity.min(1 << 16)),
+        }
+    }
+
+    /// Offer the next item of the stream, built by `item` if it is kept
+    ///
+    /// Returns whether it was kept; it may still be replaced later.
+    pub fn offer(&mut self, rng: &mut Rng, item: impl FnOnce() -> T) -> bool {
+        self.seen += 1;
+        if self.items.len() < self.capacity {
+            self.items.push(item());
+            return true;
+        }
+        let slot = rng.below(self.seen);
+        if slot < self.capacity as u64 {
+            self.items[slot as usize] = item();
+            return true;
+        }
+        false
+    }
+
+    /// Items offered so far
+    pub fn seen(&self) -> u64 {
+        self.seen
+    }
+
+    /// The sample, in no particular order
+    pub fn into_items(self) -> Vec<T> {
+        self.items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_is_reproducible() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let first: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        assert_eq!(first, (0..5).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(first, (0..5).map(|_| Rng::new(43).next_u64()).collect::<Vec<_>>());
+
+        // Pinned so a change to the generator, which would silently change
+        // every seeded shuffle, fails here
+        assert_eq!(Rng::new(0).next_u64(), 0xE220_A839_7B1D_CDAF);
+    }
+
+    #[test]
+    fn test_below_is_in_range_and_covers_it() {
+        let mut rng = Rng::new(7);
+        let mut counts = [0u32; 6];
+        for _ in 0..6000 {
+            counts[rng.below(6) as usize] += 1;
+        }
+        assert!(counts.iter().all(|&c| (800..1200).contains(&c)), "{:?}", counts);
+        assert_eq!(rng.below(1), 0);
+        assert!((0..1000).all(|_| (0.0..1.0).contains(&rng.next_f64())));
+    }
+
+    #[test]
+    fn test_shuffle_is_a_permutation() {
+        let mut items: Vec<u32> = (0..100).collect();
+        Rng::new(1).shuffle(&mut items);
+        assert_ne!(items, (0..100).collect::<Vec<_>>());
+        let mut sorted = items.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..100).collect::<Vec<_>>());
+
+        // A partial shuffle picks its prefix the way a full one would
+        let mut partial: Vec<u32> = (0..100).collect();
+        Rng::new(1).partial_shuffle(&mut partial, 10);
+        assert_eq!(partial[..10], items[..10]);
+    }
+
+    #[test]
+    fn test_reservoir_is_uniform() {
+        let mut hits = [0u32; 10];
+        for seed in 0..2000 {
+            let mut rng = Rng::new(seed);
+            let mut reservoir = Reservoir::new(3);
+            for i in 0..10 {
+                reservoir.offer(&mut rng, || i);
+            }
+            assert_eq!(reservoir.seen(), 10);
+            let sample = reservoir.into_items();
+            assert_eq!(sample.len(), 3);
+            for i in sample {
+                hits[i] += 1;
+            }
+        }
+        // Each item is kept 3 times in 10: about 600 of 2000 runs
+        assert!(hits.iter().all(|&h| (500..700).contains(&h)), "{:?}", hits);
+
+        let mut reservoir = Reservoir::new(5);
+        reservoir.offer(&mut Rng::new(0), || "only");
+        assert_eq!(reservoir.into_items(), ["only"]);
+    }
+}
@@ -0,0 +1,469 @@
+//! Scratch space: temporary files and directories, and a registry of them
+//!
+//! ai-mktemp creates uniquely named files and directories from `mktemp`
+//! templates and records each one as an [`Allocation`], with the agent
+//! session that asked for it and an optional expiry. ai-cleanup later
+//! removes everything a session allocated, or everything expired, without
+//! the agent having to remember its own paths.
+//!
+//! The registry is a directory holding `registry.jsonl`, one line per
+//! allocation plus one per release. It lives in `ai-coreutils/scratch`
+//! under the platform data directory unless `scratch.dir` or
+//! `AI_COREUTILS_SCRATCH_DIR` says otherwise. Writers serialize on an
+//! advisory lock on `registry.lock`.
+
+use crate::config;
+use crate::error::{AiCoreutilsError, Result};
+use crate::fs_utils::{FileLock, LockMode};
+use crate::policy::{self, Access};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+const REGISTRY_FILE: &str = "registry.jsonl";
+const LOCK_FILE: &str = "registry.lock";
+
+/// Names tried before giving up, as `mktemp` does
+const MAX_ATTEMPTS: usize = 100;
+
+/// Characters replacing the `X`s of a template
+const NAME_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+/// Template used when none is given
+pub const DEFAULT_TEMPLATE: &str = "tmp.XXXXXXXXXX";
+
+/// What an allocation is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScratchKind {
+    /// A regular file
+    File,
+    /// A directory, removed with its contents
+    Dir,
+}
+
+impl ScratchKind {
+    /// Name used in JSONL output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScratchKind::File => "file",
+            ScratchKind::Dir => "dir",
+        }
+    }
+}
+
+/// A `mktemp` template: a path whose last component has a run of at least
+/// three `X`s, replaced by random characters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    dir: PathBuf,
+    prefix: String,
+    random: usize,
+    suffix: String,
+}
+
+impl Template {
+    /// Parse `template`, appending `suffix` to the generated names
+    ///
+    /// The last run of `X`s in the final component is replaced; anything
+    /// after it is kept, as `mktemp` does.
+    pub fn parse(template: &Path, suffix: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            AiCoreutilsError::InvalidInput(format!("{}: {}", template.display(), reason))
+        };
+        let name = template
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| invalid("the template's last component must be valid UTF-8"))?;
+        let end = name.rfind('X').map(|i| i + 1).unwrap_or(0);
+        let random = name[..end].bytes().rev().take_while(|&b| b == b'X').count();
+        if random < 3 {
+            return Err(invalid("the template must contain at least 3 consecutive 'X's in its last component"));
+        }
+        if suffix.contains(std::path::is_separator) {
+            return Err(invalid("the suffix cannot contain a path separator"));
+        }
+
+        Ok(Self {
+            dir: template.parent().map(Path::to_path_buf).unwrap_or_default(),
+            prefix: name[..end - random].to_string(),
+            random,
+            suffix: format!("{}{}", &name[end..], suffix),
+        })
+    }
+
+    /// A fresh candidate name
+    pub fn generate(&self) -> PathBuf {
+        let mut name = self.prefix.clone();
+        while name.len() < self.prefix.len() + self.random {
+            for byte in uuid::Uuid::new_v4().into_bytes() {
+                // Rejecting the top of the byte range keeps each character equally likely
+                let limit = 256 - 256 % NAME_CHARS.len();
+                if (byte as usize) < limit && name.len() < self.prefix.len() + self.random {
+                    name.push(NAME_CHARS[byte as usize % NAME_CHARS.len()] as char);
+                }
+            }
+        }
+        name.push_str(&self.suffix);
+        self.dir.join(name)
+    }
+
+    /// Create a uniquely named file or directory, readable only by its owner
+    ///
+    /// Fails if [`MAX_ATTEMPTS`] names in a row are taken.
+    pub fn create(&self, kind: ScratchKind) -> Result<PathBuf> {
+        for _ in 0..MAX_ATTEMPTS {
+            let path = self.generate();
+            let created = match kind {
+                ScratchKind::File => create_file(&path),
+                ScratchKind::Dir => create_dir(&path),
+            };
+            match created {
+                Ok(()) => return Ok(path),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let template = self.dir.join(format!("{}{}{}", self.prefix, "X".repeat(self.random), self.suffix));
+        let message = format!("No unused name for {} after {} attempts", template.display(), MAX_ATTEMPTS);
+        Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, message).into())
+    }
+}
+
+#[cfg(unix)]
+fn create_file(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::OpenOptionsExt;
+    fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(path).map(drop)
+}
+
+#[cfg(not(unix))]
+fn create_file(path: &Path) -> std::io::Result<()> {
+    fs::OpenOptions::new().write(true).create_new(true).open(path).map(drop)
+}
+
+#[cfg(unix)]
+fn create_dir(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    fs::DirBuilder::new().mode(0o700).create(path)
+}
+
+#[cfg(not(unix))]
+fn create_dir(path: &Path) -> std::io::Result<()> {
+    fs::create_dir(path)
+}
+
+/// One temporary file or directory in the registry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Allocation {
+    /// Unique id
+    pub id: String,
+    /// Absolute path
+    pub path: PathBuf,
+    /// File or directory
+    pub kind: ScratchKind,
+    /// Session that allocated it
+    pub session: Option<String>,
+    /// When it was created
+    pub created: DateTime<Utc>,
+    /// When ai-cleanup `--expired` may remove it
+    pub expires: Option<DateTime<Utc>>,
+    /// Inode number, so a path since reused by something else is left alone
+    pub inode: Option<u64>,
+}
+
+impl Allocation {
+    /// Describe the file or directory just created at `path`
+    pub fn new(path: &Path, kind: ScratchKind, session: Option<String>, ttl: Option<std::time::Duration>) -> Result<Self> {
+        let created = Utc::now();
+        let expires = match ttl {
+            Some(ttl) => Some(
+                chrono::Duration::from_std(ttl)
+                    .ok()
+                    .and_then(|ttl| created.checked_add_signed(ttl))
+                    .ok_or_else(|| AiCoreutilsError::InvalidInput("Time to live is too long".to_string()))?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            path: std::path::absolute(path)?,
+            kind,
+            session,
+            created,
+            expires,
+            inode: inode(&fs::symlink_metadata(path)?),
+        })
+    }
+
+    /// Whether it has expired by `now`
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    /// Remove the file or directory, unless something else now has its path
+    fn remove(&self) -> Result<ReleaseAction> {
+        let metadata = match fs::symlink_metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(ReleaseAction::Missing),
+            Err(e) => return Err(e.into()),
+        };
+        let same_kind = match self.kind {
+            ScratchKind::File => metadata.is_file(),
+            ScratchKind::Dir => metadata.is_dir(),
+        };
+        if !same_kind || (self.inode.is_some() && inode(&metadata) != self.inode) {
+            return Ok(ReleaseAction::Replaced);
+        }
+        policy::check(&self.path, Access::Write)?;
+        match self.kind {
+            ScratchKind::File => fs::remove_file(&self.path)?,
+            ScratchKind::Dir => fs::remove_dir_all(&self.path)?,
+        }
+        Ok(ReleaseAction::Removed)
+    }
+}
+
+#[cfg(unix)]
+fn inode(metadata: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// What [`Registry::release`] did with an allocation
+#[derive(Debug)]
+pub enum ReleaseAction {
+    /// It was removed
+    Removed,
+    /// It was already gone
+    Missing,
+    /// Something else is at its path now, and was left alone
+    Replaced,
+    /// It would have been removed, with `dry_run`
+    WouldRemove,
+    /// It could not be removed and stays registered
+    Failed(AiCoreutilsError),
+}
+
+impl ReleaseAction {
+    /// Name used in JSONL output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReleaseAction::Removed => "removed",
+            ReleaseAction::Missing => "missing",
+            ReleaseAction::Replaced => "replaced",
+            ReleaseAction::WouldRemove => "would_remove",
+            ReleaseAction::Failed(_) => "failed",
+        }
+    }
+}
+
+/// One line of `registry.jsonl`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+enum Line {
+    Allocated(Allocation),
+    Released { id: String, time: DateTime<Utc> },
+}
+
+/// A registry directory
+#[derive(Debug, Clone)]
+pub struct Registry {
+    dir: PathBuf,
+}
+
+impl Registry {
+    /// Use the registry in `dir`
+    ///
+    /// Nothing is created until an allocation is recorded.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The registry described by the shared configuration
+    ///
+    /// `None` if it is not configured and there is no platform data directory.
+    pub fn from_config() -> Option<Self> {
+        let dir = match &config::get().scratch.dir {
+            Some(dir) => dir.clone(),
+            None => dirs::data_local_dir()?.join("ai-coreutils").join("scratch"),
+        };
+        Some(Self::new(dir))
+    }
+
+    /// Directory holding the registry
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Record a new allocation
+    pub fn record(&self, allocation: &Allocation) -> Result<()> {
+        let _lock = self.lock()?;
+        self.append(&[Line::Allocated(allocation.clone())])
+    }
+
+    /// Allocations not yet released, oldest first
+    pub fn allocations(&self) -> Result<Vec<Allocation>> {
+        let mut allocations: Vec<Allocation> = Vec::new();
+        for line in self.read_lines()? {
+            match line {
+                Line::Allocated(allocation) => allocations.push(allocation),
+                Line::Released { id, .. } => allocations.retain(|a| a.id != id),
+            }
+        }
+        Ok(allocations)
+    }
+
+    /// Remove the allocations `select` picks, and release them
+    ///
+    /// Allocations already gone, or whose path now holds something else,
+    /// are released without removing anything; ones that cannot be removed
+    /// stay registered. With `dry_run` nothing is removed or released.
+    pub fn release<F>(&self, select: F, dry_run: bool) -> Result<Vec<(Allocation, ReleaseAction)>>
+    where
+        F: Fn(&Allocation) -> bool,
+    {
+        let _lock = self.lock()?;
+        let selected: Vec<Allocation> = self.allocations()?.into_iter().filter(|a| select(a)).collect();
+
+        let mut outcomes = Vec::with_capacity(selected.len());
+        let mut released = Vec::new();
+        for allocation in selected {
+            let action = if dry_run {
+                ReleaseAction::WouldRemove
+            } else {
+                allocation.remove().unwrap_or_else(ReleaseAction::Failed)
+            };
+            if !dry_run && !matches!(action, ReleaseAction::Failed(_)) {
+                released.push(Line::Released { id: allocation.id.clone(), time: Utc::now() });
+            }
+            outcomes.push((allocation, action));
+        }
+        if !released.is_empty() {
+            self.append(&released)?;
+            self.compact()?;
+        }
+        Ok(outcomes)
+    }
+
+    /// Rewrite the registry with only the allocations still live
+    fn compact(&self) -> Result<()> {
+        let mut contents = Vec::new();
+        for allocation in self.allocations()? {
+            write_line(&mut contents, &Line::Allocated(allocation))?;
+        }
+        // Replaced directly rather than with fs_utils::atomic_write: the
+        // registry is internal state, not subject to the sandbox policy
+        let temp = self.dir.join(format!("{}.tmp", REGISTRY_FILE));
+        fs::write(&temp, &contents)?;
+        fs::rename(&temp, self.dir.join(REGISTRY_FILE))?;
+        Ok(())
+    }
+
+    fn read_lines(&self) -> Result<Vec<Line>> {
+        let file = match fs::File::open(self.dir.join(REGISTRY_FILE)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut lines = Vec::new();
+        for line in BufReader::new(file).lines() {
+            // A line cut short by a crash is skipped rather than failing every later cleanup
+            if let Ok(line) = serde_json::from_str(&line?) {
+                lines.push(line);
+            }
+        }
+        Ok(lines)
+    }
+
+    fn append(&self, lines: &[Line]) -> Result<()> {
+        let mut contents = Vec::new();
+        for line in lines {
+            write_line(&mut contents, line)?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(REGISTRY_FILE))?;
+        file.write_all(&contents)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<FileLock> {
+        fs::create_dir_all(&self.dir)?;
+        FileLock::acquire(&self.dir.join(LOCK_FILE), LockMode::Exclusive, None)
+    }
+}
+
+fn write_line(out: &mut Vec<u8>, line: &Line) -> Result<()> {
+    serde_json::to_writer(&mut *out, line)?;
+    out.push(b'\n');
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_template() {
+        let template = Template::parse(Path::new("/tmp/build.XXXXXX.d"), ".log").unwrap();
+        let path = template.generate();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert_eq!(path.parent(), Some(Path::new("/tmp")));
+        assert!(name.starts_with("build.") && name.ends_with(".d.log"), "{}", name);
+        assert_eq!(name.len(), "build.XXXXXX.d.log".len());
+        assert!(name[6..12].bytes().all(|b| NAME_CHARS.contains(&b)));
+        assert_ne!(template.generate(), path);
+
+        assert!(Template::parse(Path::new("tmp.XX"), "").is_err());
+        assert!(Template::parse(Path::new("XXXX/name"), "").is_err());
+        assert!(Template::parse(Path::new("tmp.XXX"), "/x").is_err());
+    }
+
+    #[test]
+    fn test_create_and_release() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry = Registry::new(temp_dir.path().join("registry"));
+        let template = Template::parse(&temp_dir.path().join("scratch.XXXXXX"), "").unwrap();
+
+        let dir = template.create(ScratchKind::Dir).unwrap();
+        fs::write(dir.join("inner.txt"), "x").unwrap();
+        let file = template.create(ScratchKind::File).unwrap();
+        let other = template.create(ScratchKind::File).unwrap();
+        let session = Some("agent-1".to_string());
+        let expired = Allocation::new(&dir, ScratchKind::Dir, session.clone(), Some(Duration::ZERO)).unwrap();
+        registry.record(&expired).unwrap();
+        registry.record(&Allocation::new(&file, ScratchKind::File, session, None).unwrap()).unwrap();
+        registry.record(&Allocation::new(&other, ScratchKind::File, None, None).unwrap()).unwrap();
+        assert_eq!(registry.allocations().unwrap().len(), 3);
+
+        let outcomes = registry.release(|a| a.is_expired(Utc::now()), true).unwrap();
+        assert_eq!(outcomes.len(), 1);
+        assert!(matches!(outcomes[0].1, ReleaseAction::WouldRemove));
+        assert!(dir.exists());
+
+        // A path taken over since it was allocated is released but kept
+        fs::remove_file(&file).unwrap();
+        fs::create_dir(&file).unwrap();
+        let outcomes = registry.release(|a| a.session.as_deref() == Some("agent-1"), false).unwrap();
+        let actions: Vec<_> = outcomes.iter().map(|(_, action)| action.as_str()).collect();
+        assert_eq!(actions, ["removed", "replaced"]);
+        assert!(!dir.exists());
+        assert!(file.exists());
+
+        let remaining = registry.allocations().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].path, other);
+    }
+}
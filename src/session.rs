@@ -0,0 +1,267 @@
+//! Agent sessions: one id and one state directory per multi-step run
+//!
+//! Setting `session.id` or `AI_COREUTILS_SESSION` marks every JSONL record
+//! a utility writes, and its audit log line, with `"session": <id>`, so the
+//! output of a run spread over many processes can be correlated. The session
+//! also gets a directory of its own, `<id>` under `ai-coreutils/sessions` in
+//! the platform data directory (or `session.dir` / `AI_COREUTILS_SESSION_DIR`),
+//! holding the state that would otherwise be shared with every other run:
+//!
+//! - `journal/`: the operation journal ai-undo reverts
+//! - `tmp/`: where ai-mktemp creates temporary files by default
+//! - `cache/`: copies of remote files and cached analysis results
+//!
+//! Explicitly configured directories (`journal.dir`, `remote.cache_dir`,
+//! `cache.dir`) still win. Resuming a run is setting the same id again;
+//! ending it with [`Session::end`] renames the directory out of the way
+//! before deleting it, so other processes see either the whole session or
+//! none of it.
+//! Content indexes stay next to the trees they index.
+
+use crate::config;
+use crate::error::Result;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Longest session id accepted
+pub const MAX_ID_LEN: usize = 128;
+
+/// Prefix of a session directory that is being deleted
+const ENDING_PREFIX: &str = ".ending-";
+
+/// Check that `id` can name a session directory
+///
+/// Ids are 1 to 128 ASCII letters, digits, `.`, `_` and `-`, not starting
+/// with `.`, so they can never escape the sessions directory.
+pub fn validate_id(id: &str) -> std::result::Result<(), String> {
+    if id.is_empty() || id.len() > MAX_ID_LEN {
+        return Err(format!("session id must be 1 to {} characters", MAX_ID_LEN));
+    }
+    if id.starts_with('.') {
+        return Err(format!("session id must not start with '.': {}", id));
+    }
+    if let Some(c) = id.chars().find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))) {
+        return Err(format!("session id may only hold letters, digits, '.', '_' and '-', not {:?}: {}", c, id));
+    }
+    Ok(())
+}
+
+/// A fresh session id
+pub fn generate_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// Directory holding the session directories, from the shared configuration
+///
+/// `None` if it is not configured and there is no platform data directory.
+pub fn root() -> Option<PathBuf> {
+    match &config::get().session.dir {
+        Some(dir) => Some(dir.clone()),
+        None => Some(dirs::data_local_dir()?.join("ai-coreutils").join("sessions")),
+    }
+}
+
+/// One session and its state directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    id: String,
+    dir: PathBuf,
+}
+
+/// Disk use of a session directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// Regular files in it
+    pub files: u64,
+    /// Their total size
+    pub bytes: u64,
+    /// Newest modification time of anything in it
+    pub modified: Option<SystemTime>,
+}
+
+impl Session {
+    /// The session `id` under `root`
+    pub fn new(id: &str, root: &Path) -> Result<Self> {
+        validate_id(id).map_err(crate::error::AiCoreutilsError::InvalidInput)?;
+        Ok(Self {
+            id: id.to_string(),
+            dir: root.join(id),
+        })
+    }
+
+    /// The configured session, if any
+    pub fn current() -> Option<Self> {
+        let id = config::get().session.id.as_deref()?;
+        Self::new(id, &root()?).ok()
+    }
+
+    /// The sessions under `root` that have a directory, by id
+    pub fn list(root: &Path) -> Result<Vec<Self>> {
+        let entries = match fs::read_dir(root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut sessions = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            // Directories being ended, and anything else, have invalid ids
+            if let Some(session) = entry.file_name().to_str().and_then(|id| Self::new(id, root).ok()) {
+                sessions.push(session);
+            }
+        }
+        sessions.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(sessions)
+    }
+
+    /// Session id
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// State directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Whether the state directory exists
+    pub fn exists(&self) -> bool {
+        self.dir.is_dir()
+    }
+
+    /// Operation journal directory
+    pub fn journal_dir(&self) -> PathBuf {
+        self.dir.join("journal")
+    }
+
+    /// Temporary file directory
+    pub fn tmp_dir(&self) -> PathBuf {
+        self.dir.join("tmp")
+    }
+
+    /// Cache directory, for remote files and analysis results
+    pub fn cache_dir(&self) -> PathBuf {
+        self.dir.join("cache")
+    }
+
+    /// Create the state directory, readable only by its owner
+    pub fn create(&self) -> Result<()> {
+        create_private_dir(&self.dir)
+    }
+
+    /// Create the temporary file directory if needed and return it
+    pub fn ensure_tmp_dir(&self) -> Result<PathBuf> {
+        self.create()?;
+        let tmp = self.tmp_dir();
+        create_private_dir(&tmp)?;
+        Ok(tmp)
+    }
+
+    /// Files, bytes and last modification under the state directory
+    pub fn usage(&self) -> Usage {
+        let mut usage = Usage::default();
+        for entry in walkdir::WalkDir::new(&self.dir).into_iter().flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_file() {
+                usage.files += 1;
+                usage.bytes += metadata.len();
+            }
+            if let Ok(modified) = metadata.modified() {
+                usage.modified = usage.modified.max(Some(modified));
+            }
+        }
+        usage
+    }
+
+    /// Delete the state directory and everything in it
+    ///
+    /// The directory is first renamed to a hidden name in the same parent,
+    /// which is atomic, and only then removed, so a session is never seen
+    /// half deleted. Leftovers of ends that were interrupted are removed
+    /// too. Returns whether there was a directory to delete.
+    pub fn end(&self) -> Result<bool> {
+        let Some(root) = self.dir.parent() else {
+            return Ok(false);
+        };
+        crate::policy::check(&self.dir, crate::policy::Access::Write)?;
+
+        let ending = root.join(format!("{}{}-{}", ENDING_PREFIX, self.id, generate_id()));
+        let ended = match fs::rename(&self.dir, &ending) {
+            Ok(()) => {
+                fs::remove_dir_all(&ending)?;
+                true
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => false,
+            Err(e) => return Err(e.into()),
+        };
+
+        for entry in fs::read_dir(root)?.flatten() {
+            if entry.file_name().to_string_lossy().starts_with(ENDING_PREFIX) {
+                // Another process may be removing it right now
+                let _ = fs::remove_dir_all(entry.path());
+            }
+        }
+        Ok(ended)
+    }
+}
+
+fn create_private_dir(dir: &Path) -> Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_validate_id() {
+        assert!(validate_id("agent-7").is_ok());
+        assert!(validate_id("run_2026.01.01").is_ok());
+        assert!(validate_id(&generate_id()).is_ok());
+
+        assert!(validate_id("").is_err());
+        assert!(validate_id(".hidden").is_err());
+        assert!(validate_id("..").is_err());
+        assert!(validate_id("a/b").is_err());
+        assert!(validate_id("a b").is_err());
+        assert!(validate_id(&"x".repeat(MAX_ID_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_list_and_end() {
+        let root = TempDir::new().unwrap();
+        let session = Session::new("agent-7", root.path()).unwrap();
+        assert!(Session::list(root.path()).unwrap().is_empty());
+
+        let tmp = session.ensure_tmp_dir().unwrap();
+        fs::write(tmp.join("scratch.txt"), "12345").unwrap();
+        fs::create_dir_all(session.journal_dir()).unwrap();
+        fs::create_dir(root.path().join(format!("{}agent-6-x", ENDING_PREFIX))).unwrap();
+
+        assert_eq!(Session::list(root.path()).unwrap(), vec![session.clone()]);
+        let usage = session.usage();
+        assert_eq!((usage.files, usage.bytes), (1, 5));
+        assert!(usage.modified.is_some());
+
+        assert!(session.end().unwrap());
+        assert!(!session.exists());
+        assert_eq!(fs::read_dir(root.path()).unwrap().count(), 0);
+        assert!(!session.end().unwrap());
+    }
+}
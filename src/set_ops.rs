@@ -0,0 +1,573 @@
+//! Merging two sorted line streams, as `comm` and `join` do
+//!
+//! Both inputs are read a line at a time through [`LineStream`], so memory
+//! stays flat however large they are; only `join` buffers, and only the
+//! lines sharing one key. Lines and keys are ordered by their bytes, as in
+//! the C locale, with [`SimdStringComparer`]. Input that is not sorted is
+//! noticed as it is read: [`OrderCheck`] decides whether that is recorded
+//! or fatal.
+
+use crate::error::{AiCoreutilsError, Result};
+use crate::simd_ops::SimdStringComparer;
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Open an input for a [`LineStream`]; `-` is stdin
+///
+/// A file's size is counted against the read limits before it is streamed.
+pub fn open_input(path: &Path) -> Result<Box<dyn BufRead>> {
+    if path.to_str() == Some("-") {
+        return Ok(Box::new(BufReader::new(std::io::stdin())));
+    }
+    let file = File::open(path)?;
+    crate::limits::read_file(path, file.metadata()?.len())?;
+    Ok(Box::new(BufReader::new(file)))
+}
+
+/// One line of an input
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Line {
+    /// Line number (1-indexed)
+    pub number: usize,
+    /// Byte offset of the first byte of the line
+    pub offset: u64,
+    /// Line content without its terminator
+    pub content: Vec<u8>,
+}
+
+/// Lines of a reader, one at a time
+///
+/// Lines end with the separator; with `\n`, a `\r` before it is dropped
+/// too.
+pub struct LineStream<R> {
+    reader: R,
+    separator: u8,
+    current: Line,
+    previous: Vec<u8>,
+    next_offset: u64,
+    started: bool,
+    at_end: bool,
+}
+
+impl<R: BufRead> LineStream<R> {
+    /// Stream over `reader`; call [`LineStream::advance`] for the first line
+    pub fn new(reader: R, separator: u8) -> Self {
+        Self {
+            reader,
+            separator,
+            current: Line::default(),
+            previous: Vec::new(),
+            next_offset: 0,
+            started: false,
+            at_end: false,
+        }
+    }
+
+    /// Read the next line; `false` at the end of the input
+    pub fn advance(&mut self) -> Result<bool> {
+        std::mem::swap(&mut self.previous, &mut self.current.content);
+        self.current.content.clear();
+        self.started = true;
+        let read = self.reader.read_until(self.separator, &mut self.current.content)?;
+        if read == 0 {
+            self.at_end = true;
+            return Ok(false);
+        }
+        self.current.number += 1;
+        self.current.offset = self.next_offset;
+        self.next_offset += read as u64;
+        if self.current.content.last() == Some(&self.separator) {
+            self.current.content.pop();
+            if self.separator == b'\n' && self.current.content.last() == Some(&b'\r') {
+                self.current.content.pop();
+            }
+        }
+        Ok(true)
+    }
+
+    /// The current line, `None` before the first and after the last
+    pub fn line(&self) -> Option<&Line> {
+        (self.started && !self.at_end).then_some(&self.current)
+    }
+}
+
+/// What to do with input that is not sorted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OrderCheck {
+    /// Do not compare consecutive lines
+    Off,
+    /// Record the first line out of order in the stats
+    #[default]
+    Report,
+    /// Fail at the first line out of order
+    Fail,
+}
+
+/// Which input a line came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// Only in the first input
+    Left,
+    /// Only in the second input
+    Right,
+    /// In both
+    Both,
+}
+
+impl Side {
+    /// Name used in output records
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Side::Left => "file1",
+            Side::Right => "file2",
+            Side::Both => "both",
+        }
+    }
+}
+
+/// Line counts of a [`Merger::comm`] run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommStats {
+    /// Lines only in the first input
+    pub left_only: u64,
+    /// Lines only in the second input
+    pub right_only: u64,
+    /// Lines in both
+    pub both: u64,
+    /// First line of each input found out of order
+    pub unsorted: [Option<usize>; 2],
+}
+
+/// Which fields to join on, and how lines split into fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JoinSpec {
+    /// Join field of each input (1-indexed)
+    pub fields: [usize; 2],
+    /// Field separator; `None` splits at runs of blanks, ignoring leading ones
+    pub separator: Option<u8>,
+    /// The first line of each input is a header, paired without comparing
+    pub header: bool,
+}
+
+impl Default for JoinSpec {
+    fn default() -> Self {
+        Self { fields: [1, 1], separator: None, header: false }
+    }
+}
+
+impl JoinSpec {
+    /// Fields of `line`
+    pub fn split<'a>(&self, line: &'a [u8]) -> Vec<&'a [u8]> {
+        match self.separator {
+            Some(separator) => line.split(|&b| b == separator).collect(),
+            None => line
+                .split(|&b| b == b' ' || b == b'\t')
+                .filter(|field| !field.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Join field of `line` from input `side` (0 or 1); empty if missing
+    pub fn key<'a>(&self, side: usize, line: &'a [u8]) -> &'a [u8] {
+        self.split(line).get(self.fields[side] - 1).copied().unwrap_or_default()
+    }
+
+    /// The line `join` writes: the key, then the other fields of each line
+    /// given, separated by the field separator or a space
+    pub fn output_line(&self, left: Option<&[u8]>, right: Option<&[u8]>) -> Vec<u8> {
+        let separator = self.separator.unwrap_or(b' ');
+        let key = match (left, right) {
+            (Some(line), _) => self.key(0, line),
+            (None, Some(line)) => self.key(1, line),
+            (None, None) => &[],
+        };
+        let mut out = key.to_vec();
+        for (side, line) in [left, right].into_iter().enumerate() {
+            let Some(line) = line else { continue };
+            for (index, field) in self.split(line).into_iter().enumerate() {
+                if index + 1 != self.fields[side] {
+                    out.push(separator);
+                    out.extend_from_slice(field);
+                }
+            }
+        }
+        out
+    }
+}
+
+/// A step of a [`Merger::join`] run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinEvent<'a> {
+    /// The header lines, with `JoinSpec::header`
+    Header(Option<&'a Line>, Option<&'a Line>),
+    /// Two lines with the same key
+    Paired(&'a Line, &'a Line),
+    /// A line whose key is not in the other input
+    Unpaired(Side, &'a Line),
+}
+
+/// Line counts of a [`Merger::join`] run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JoinStats {
+    /// Pairs of lines with the same key
+    pub paired: u64,
+    /// Lines of each input without a match
+    pub unpaired: [u64; 2],
+    /// First line of each input found out of order
+    pub unsorted: [Option<usize>; 2],
+}
+
+/// Compares and merges two sorted inputs
+pub struct Merger {
+    comparer: SimdStringComparer,
+    ignore_case: bool,
+    order: OrderCheck,
+}
+
+impl Merger {
+    /// Merger comparing bytes, reporting unsorted input
+    pub fn new() -> Self {
+        Self {
+            comparer: SimdStringComparer::new(),
+            ignore_case: false,
+            order: OrderCheck::default(),
+        }
+    }
+
+    /// Compare ASCII letters without regard to case
+    pub fn ignore_case(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    /// Set what happens on unsorted input
+    pub fn order_check(mut self, order: OrderCheck) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Order of two lines or keys
+    pub fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        if !self.ignore_case {
+            return self.comparer.compare(a, b);
+        }
+        let fold = |bytes: &[u8]| bytes.iter().map(u8::to_ascii_lowercase).collect::<Vec<_>>();
+        match self.comparer.compare(a, b) {
+            Ordering::Equal => Ordering::Equal,
+            _ => fold(a).cmp(&fold(b)),
+        }
+    }
+
+    /// Walk two sorted inputs together, as `comm` does
+    ///
+    /// `emit` gets every line with the side it is on: [`Side::Both`] lines
+    /// come with the matching line of each input.
+    pub fn comm<A: BufRead, B: BufRead>(
+        &self,
+        left: &mut LineStream<A>,
+        right: &mut LineStream<B>,
+        mut emit: impl FnMut(Side, Option<&Line>, Option<&Line>) -> Result<()>,
+    ) -> Result<CommStats> {
+        let mut stats = CommStats::default();
+        let whole = |_: usize, line: &[u8]| -> Vec<u8> { line.to_vec() };
+        self.step(left, 0, 1, &mut stats.unsorted, &whole)?;
+        self.step(right, 1, 1, &mut stats.unsorted, &whole)?;
+
+        loop {
+            let order = match (left.line(), right.line()) {
+                (None, None) => break,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(l), Some(r)) => self.compare(&l.content, &r.content),
+            };
+            match order {
+                Ordering::Less => {
+                    emit(Side::Left, left.line(), None)?;
+                    stats.left_only += 1;
+                    self.step(left, 0, 1, &mut stats.unsorted, &whole)?;
+                }
+                Ordering::Greater => {
+                    emit(Side::Right, None, right.line())?;
+                    stats.right_only += 1;
+                    self.step(right, 1, 1, &mut stats.unsorted, &whole)?;
+                }
+                Ordering::Equal => {
+                    emit(Side::Both, left.line(), right.line())?;
+                    stats.both += 1;
+                    self.step(left, 0, 1, &mut stats.unsorted, &whole)?;
+                    self.step(right, 1, 1, &mut stats.unsorted, &whole)?;
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Join two inputs sorted on their join fields, as `join` does
+    ///
+    /// Every pair of lines with equal keys is emitted, so a key repeated
+    /// in both inputs gives their cross product; only the lines sharing
+    /// the current key are held in memory. Lines without a match are
+    /// emitted as [`JoinEvent::Unpaired`].
+    pub fn join<A: BufRead, B: BufRead>(
+        &self,
+        spec: &JoinSpec,
+        left: &mut LineStream<A>,
+        right: &mut LineStream<B>,
+        mut emit: impl FnMut(JoinEvent) -> Result<()>,
+    ) -> Result<JoinStats> {
+        let mut stats = JoinStats::default();
+        let key = |side: usize, line: &[u8]| -> Vec<u8> { spec.key(side, line).to_vec() };
+        let first = 1 + usize::from(spec.header);
+        left.advance()?;
+        right.advance()?;
+        if spec.header {
+            if left.line().is_some() || right.line().is_some() {
+                emit(JoinEvent::Header(left.line(), right.line()))?;
+            }
+            left.advance()?;
+            right.advance()?;
+        }
+
+        loop {
+            let (l, r) = match (left.line(), right.line()) {
+                (None, None) => break,
+                (Some(l), None) => {
+                    emit(JoinEvent::Unpaired(Side::Left, l))?;
+                    stats.unpaired[0] += 1;
+                    self.step(left, 0, first, &mut stats.unsorted, &key)?;
+                    continue;
+                }
+                (None, Some(r)) => {
+                    emit(JoinEvent::Unpaired(Side::Right, r))?;
+                    stats.unpaired[1] += 1;
+                    self.step(right, 1, first, &mut stats.unsorted, &key)?;
+                    continue;
+                }
+                (Some(l), Some(r)) => (l, r),
+            };
+            match self.compare(spec.key(0, &l.content), spec.key(1, &r.content)) {
+                Ordering::Less => {
+                    emit(JoinEvent::Unpaired(Side::Left, l))?;
+                    stats.unpaired[0] += 1;
+                    self.step(left, 0, first, &mut stats.unsorted, &key)?;
+                }
+                Ordering::Greater => {
+                    emit(JoinEvent::Unpaired(Side::Right, r))?;
+                    stats.unpaired[1] += 1;
+                    self.step(right, 1, first, &mut stats.unsorted, &key)?;
+                }
+                Ordering::Equal => {
+                    let current = key(0, &l.content);
+                    let lefts = self.group(left, 0, first, &current, &mut stats.unsorted, &key)?;
+                    let rights = self.group(right, 1, first, &current, &mut stats.unsorted, &key)?;
+                    for l in &lefts {
+                        for r in &rights {
+                            emit(JoinEvent::Paired(l, r))?;
+                            stats.paired += 1;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    /// The current line and those after it with the same key, leaving the
+    /// stream on the first line with another key
+    fn group<R: BufRead>(
+        &self,
+        stream: &mut LineStream<R>,
+        side: usize,
+        first: usize,
+        current: &[u8],
+        unsorted: &mut [Option<usize>; 2],
+        key: &impl Fn(usize, &[u8]) -> Vec<u8>,
+    ) -> Result<Vec<Line>> {
+        let mut lines = Vec::new();
+        while let Some(line) = stream.line() {
+            if self.compare(&key(side, &line.content), current) != Ordering::Equal {
+                break;
+            }
+            lines.push(line.clone());
+            self.step(stream, side, first, unsorted, key)?;
+        }
+        Ok(lines)
+    }
+
+    /// Advance `stream`, checking the new line sorts after the previous one
+    ///
+    /// Lines before `first` (a header) are not checked.
+    fn step<R: BufRead>(
+        &self,
+        stream: &mut LineStream<R>,
+        side: usize,
+        first: usize,
+        unsorted: &mut [Option<usize>; 2],
+        key: &impl Fn(usize, &[u8]) -> Vec<u8>,
+    ) -> Result<()> {
+        if !stream.advance()? || self.order == OrderCheck::Off || unsorted[side].is_some() {
+            return Ok(());
+        }
+        let line = &stream.current;
+        if line.number <= first
+            || self.compare(&key(side, &stream.previous), &key(side, &line.content)) != Ordering::Greater
+        {
+            return Ok(());
+        }
+        if self.order == OrderCheck::Fail {
+            return Err(AiCoreutilsError::InvalidInput(format!(
+                "file {} is not in sorted order at line {}",
+                side + 1,
+                line.number
+            )));
+        }
+        unsorted[side] = Some(line.number);
+        Ok(())
+    }
+}
+
+impl Default for Merger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(text: &str) -> LineStream<&[u8]> {
+        LineStream::new(text.as_bytes(), b'\n')
+    }
+
+    fn comm(merger: &Merger, a: &str, b: &str) -> (Vec<(Side, String)>, CommStats) {
+        let mut lines = Vec::new();
+        let stats = merger
+            .comm(&mut stream(a), &mut stream(b), |side, l, r| {
+                let line = l.or(r).unwrap();
+                lines.push((side, String::from_utf8_lossy(&line.content).into_owned()));
+                Ok(())
+            })
+            .unwrap();
+        (lines, stats)
+    }
+
+    #[test]
+    fn test_line_stream() {
+        let mut lines = stream("one\r\ntwo\n\nthree");
+        assert!(lines.line().is_none());
+        let mut seen = Vec::new();
+        while lines.advance().unwrap() {
+            let line = lines.line().unwrap();
+            seen.push((line.number, line.offset, String::from_utf8(line.content.clone()).unwrap()));
+        }
+        assert_eq!(
+            seen,
+            [(1, 0, "one".into()), (2, 5, "two".into()), (3, 9, String::new()), (4, 10, "three".into())]
+        );
+        assert!(lines.line().is_none());
+    }
+
+    #[test]
+    fn test_comm() {
+        let (lines, stats) = comm(&Merger::new(), "apple\nbanana\ncherry\n", "banana\ncherry\ndate\n");
+        assert_eq!(
+            lines,
+            [
+                (Side::Left, "apple".into()),
+                (Side::Both, "banana".into()),
+                (Side::Both, "cherry".into()),
+                (Side::Right, "date".into()),
+            ]
+        );
+        assert_eq!((stats.left_only, stats.right_only, stats.both), (1, 1, 2));
+        assert_eq!(stats.unsorted, [None, None]);
+
+        // Duplicates pair up one for one
+        let (lines, _) = comm(&Merger::new(), "a\na\na\n", "a\n");
+        let sides: Vec<Side> = lines.iter().map(|(side, _)| *side).collect();
+        assert_eq!(sides, [Side::Both, Side::Left, Side::Left]);
+
+        // Long lines compare through the SIMD path
+        let long = "x".repeat(100);
+        let (lines, _) = comm(&Merger::new(), &format!("{}a\n", long), &format!("{}b\n", long));
+        assert_eq!(lines[0].0, Side::Left);
+    }
+
+    #[test]
+    fn test_unsorted_input() {
+        let (_, stats) = comm(&Merger::new(), "b\na\nc\n", "a\n");
+        assert_eq!(stats.unsorted, [Some(2), None]);
+
+        let merger = Merger::new().order_check(OrderCheck::Fail);
+        let err = merger.comm(&mut stream("a\nc\nb\n"), &mut stream(""), |_, _, _| Ok(())).unwrap_err();
+        assert!(err.to_string().contains("file 1 is not in sorted order at line 3"), "{}", err);
+
+        let merger = Merger::new().order_check(OrderCheck::Off);
+        assert!(merger.comm(&mut stream("b\na\n"), &mut stream(""), |_, _, _| Ok(())).is_ok());
+    }
+
+    fn join(merger: &Merger, spec: &JoinSpec, a: &str, b: &str) -> (Vec<String>, JoinStats) {
+        let mut out = Vec::new();
+        let stats = merger
+            .join(spec, &mut stream(a), &mut stream(b), |event| {
+                let line = match event {
+                    JoinEvent::Header(l, r) => {
+                        format!("H {}", String::from_utf8_lossy(&spec.output_line(l.map(|l| &l.content[..]), r.map(|r| &r.content[..]))))
+                    }
+                    JoinEvent::Paired(l, r) => {
+                        format!("{}+{} {}", l.number, r.number, String::from_utf8_lossy(&spec.output_line(Some(&l.content), Some(&r.content))))
+                    }
+                    JoinEvent::Unpaired(side, line) => format!("{} {}", side.as_str(), line.number),
+                };
+                out.push(line);
+                Ok(())
+            })
+            .unwrap();
+        (out, stats)
+    }
+
+    #[test]
+    fn test_join() {
+        let spec = JoinSpec::default();
+        let (out, stats) = join(
+            &Merger::new(),
+            &spec,
+            "1 alice\n2 bob\n2 bobby\n4 dan\n",
+            "  2   red\n2 blue\n3 green\n4 black\n",
+        );
+        assert_eq!(
+            out,
+            [
+                "file1 1",
+                "2+1 2 bob red",
+                "2+2 2 bob blue",
+                "3+1 2 bobby red",
+                "3+2 2 bobby blue",
+                "file2 3",
+                "4+4 4 dan black",
+            ]
+        );
+        assert_eq!((stats.paired, stats.unpaired), (5, [1, 1]));
+    }
+
+    #[test]
+    fn test_join_fields_header_and_case() {
+        let spec = JoinSpec { fields: [2, 1], separator: Some(b','), header: true };
+        let (out, stats) = join(
+            &Merger::new().ignore_case(true),
+            &spec,
+            "name,id\nann,A1\nbo,b2\n",
+            "id,score\na1,90\nB2,75\nc3,\n",
+        );
+        assert_eq!(out, ["H id,name,score", "2+2 A1,ann,90", "3+3 b2,bo,75", "file2 4"]);
+        // The header is not checked against the first data line
+        assert_eq!(stats.unsorted, [None, None]);
+
+        // Without -i the same keys do not match
+        let (_, stats) = join(&Merger::new(), &spec, "name,id\nann,A1\n", "id,score\na1,90\n");
+        assert_eq!((stats.paired, stats.unpaired), (0, [1, 1]));
+    }
+}
@@ -1,11 +1,16 @@
 //! SIMD operations for AI-Coreutils
 //!
 //! This module provides SIMD-accelerated operations for text processing,
-//! pattern matching, and byte counting. Uses portable SIMD via std::simd
+//! pattern matching, and byte counting. Uses AVX2/SSE2 on x86_64, NEON on
+//! aarch64 and SIMD128 on wasm32 (when built with `-C target-feature=+simd128`),
 //! or falls back to optimized scalar implementations.
 
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static SIMD_GLOBALLY_ENABLED: AtomicBool = AtomicBool::new(true);
 
 /// SIMD configuration and capabilities
 #[derive(Debug, Clone)]
@@ -26,8 +31,20 @@ impl Default for SimdConfig {
 }
 
 impl SimdConfig {
+    /// Enable or disable SIMD for all subsequently detected configurations
+    pub fn set_global_enabled(enabled: bool) {
+        SIMD_GLOBALLY_ENABLED.store(enabled, Ordering::Relaxed);
+    }
+
     /// Detect CPU SIMD capabilities and set optimal configuration
     pub fn detect() -> Self {
+        if !SIMD_GLOBALLY_ENABLED.load(Ordering::Relaxed) {
+            return Self {
+                enabled: false,
+                vector_width: 1,
+            };
+        }
+
         #[cfg(target_arch = "x86_64")]
         {
             if is_x86_feature_detected!("avx2") {
@@ -44,19 +61,38 @@ impl SimdConfig {
             }
         }
 
-        #[cfg(target_arch = "aarch64")]
+        // ARM NEON is generally available on aarch64; wasm has no runtime
+        // detection, so SIMD128 is used when it was enabled at compile time
+        #[cfg(any(target_arch = "aarch64", all(target_arch = "wasm32", target_feature = "simd128")))]
         {
-            // ARM NEON is generally available on aarch64
-            return Self {
+            Self {
                 enabled: true,
-                vector_width: 16, // NEON: 128-bit
-            };
+                vector_width: 16, // NEON / SIMD128: 128-bit
+            }
         }
 
         // Fallback to scalar
-        Self {
-            enabled: false,
-            vector_width: 1,
+        #[cfg(not(any(target_arch = "aarch64", all(target_arch = "wasm32", target_feature = "simd128"))))]
+        {
+            Self {
+                enabled: false,
+                vector_width: 1,
+            }
+        }
+    }
+
+    /// Instruction set this configuration selects: `avx2`, `sse2`, `neon`, `simd128` or `scalar`
+    pub fn path(&self) -> &'static str {
+        if !self.enabled {
+            "scalar"
+        } else if cfg!(target_arch = "aarch64") {
+            "neon"
+        } else if cfg!(target_arch = "wasm32") {
+            "simd128"
+        } else if self.vector_width >= 32 {
+            "avx2"
+        } else {
+            "sse2"
         }
     }
 }
@@ -105,8 +141,62 @@ impl SimdPatternSearcher {
         }
     }
 
+    /// Search for a pattern from the end of a byte slice
+    ///
+    /// Returns the offset of the last occurrence, or None if not found; an
+    /// empty pattern is found at the end. Candidates are located with
+    /// [`rfind_byte`](Self::rfind_byte), so only the data after the match
+    /// is scanned.
+    pub fn rfind_first(&self, haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        if needle.is_empty() {
+            return Some(haystack.len());
+        }
+        if haystack.len() < needle.len() {
+            return None;
+        }
+        if needle.len() == 1 {
+            return self.rfind_byte(haystack, needle[0]);
+        }
+        if haystack.len() < 256 {
+            return haystack.windows(needle.len()).rposition(|w| w == needle);
+        }
+
+        // Matches start before `end`
+        let mut end = haystack.len() - needle.len() + 1;
+        while let Some(pos) = self.rfind_byte(&haystack[..end], needle[0]) {
+            if &haystack[pos..pos + needle.len()] == needle {
+                return Some(pos);
+            }
+            end = pos;
+        }
+        None
+    }
+
+    /// Offset of the last occurrence of `byte`, scanning back from the end
+    pub fn rfind_byte(&self, haystack: &[u8], byte: u8) -> Option<usize> {
+        if !self.config.enabled || haystack.len() < 16 {
+            return self.rfind_byte_scalar(haystack, byte);
+        }
+        self.rfind_byte_simd(haystack, byte)
+    }
+
     /// Find all occurrences of a pattern using SIMD-accelerated search
+    ///
+    /// Matches do not overlap: after each one the search resumes past its
+    /// end, as `str::match_indices` and `grep -o` do, so `aa` occurs twice
+    /// in `aaaa`. See [`find_all_overlapping`](Self::find_all_overlapping)
+    /// for every offset the pattern starts at.
     pub fn find_all(&self, haystack: &[u8], needle: &[u8]) -> Vec<usize> {
+        self.find_matches(haystack, needle, false)
+    }
+
+    /// Find every offset a pattern starts at, including matches that
+    /// overlap earlier ones (`aa` occurs three times in `aaaa`)
+    pub fn find_all_overlapping(&self, haystack: &[u8], needle: &[u8]) -> Vec<usize> {
+        self.find_matches(haystack, needle, true)
+    }
+
+    fn find_matches(&self, haystack: &[u8], needle: &[u8], overlapping: bool) -> Vec<usize> {
         if needle.is_empty() {
             return (0..=haystack.len()).collect();
         }
@@ -114,13 +204,14 @@ impl SimdPatternSearcher {
             return Vec::new();
         }
 
+        let step = if overlapping { 1 } else { needle.len() };
         let mut matches = Vec::new();
         let mut start = 0;
 
         while let Some(offset) = self.find_first(&haystack[start..], needle) {
             let absolute_offset = start + offset;
             matches.push(absolute_offset);
-            start = absolute_offset + needle.len();
+            start = absolute_offset + step;
 
             if start >= haystack.len() {
                 break;
@@ -214,6 +305,149 @@ impl SimdPatternSearcher {
         self.find_byte_scalar(&haystack[pos..], needle).map(|offset| pos + offset)
     }
 
+    /// SIMD-accelerated single byte search on other architectures
+    #[cfg(not(target_arch = "x86_64"))]
+    fn find_byte_simd(&self, haystack: &[u8], needle: u8) -> Option<usize> {
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        {
+            unsafe { self.find_byte_simd128(haystack, needle) }
+        }
+        #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+        {
+            self.find_byte_scalar(haystack, needle)
+        }
+    }
+
+    /// SIMD128 implementation of single byte search
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    unsafe fn find_byte_simd128(&self, haystack: &[u8], needle: u8) -> Option<usize> {
+        use std::arch::wasm32::*;
+
+        const VECTOR_SIZE: usize = 16;
+
+        let len = haystack.len();
+        let needle_vec = u8x16_splat(needle);
+        let mut pos = 0;
+
+        // Process 16 bytes at a time
+        while pos + VECTOR_SIZE <= len {
+            let data = v128_load(haystack.as_ptr().add(pos) as *const v128);
+            let mask = u8x16_bitmask(u8x16_eq(data, needle_vec));
+
+            if mask != 0 {
+                return Some(pos + mask.trailing_zeros() as usize);
+            }
+
+            pos += VECTOR_SIZE;
+        }
+
+        // Handle remaining bytes
+        self.find_byte_scalar(&haystack[pos..], needle).map(|offset| pos + offset)
+    }
+
+    /// SIMD-accelerated reverse single byte search
+    #[cfg(target_arch = "x86_64")]
+    fn rfind_byte_simd(&self, haystack: &[u8], byte: u8) -> Option<usize> {
+        if is_x86_feature_detected!("avx2") {
+            unsafe { self.rfind_byte_avx2(haystack, byte) }
+        } else if is_x86_feature_detected!("sse2") {
+            unsafe { self.rfind_byte_sse2(haystack, byte) }
+        } else {
+            self.rfind_byte_scalar(haystack, byte)
+        }
+    }
+
+    /// AVX2 implementation of reverse single byte search
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn rfind_byte_avx2(&self, haystack: &[u8], byte: u8) -> Option<usize> {
+        const VECTOR_SIZE: usize = 32;
+
+        let needle_vec = _mm256_set1_epi8(byte as i8);
+        let mut end = haystack.len();
+
+        // Process 32 bytes at a time, back from the end
+        while end >= VECTOR_SIZE {
+            let data = _mm256_loadu_si256(haystack.as_ptr().add(end - VECTOR_SIZE) as *const __m256i);
+            let mask = _mm256_movemask_epi8(_mm256_cmpeq_epi8(data, needle_vec)) as u32;
+
+            if mask != 0 {
+                // The highest set bit is the last match
+                return Some(end - 1 - mask.leading_zeros() as usize);
+            }
+
+            end -= VECTOR_SIZE;
+        }
+
+        // Handle the bytes left at the start
+        self.rfind_byte_scalar(&haystack[..end], byte)
+    }
+
+    /// SSE2 implementation of reverse single byte search
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "sse2")]
+    unsafe fn rfind_byte_sse2(&self, haystack: &[u8], byte: u8) -> Option<usize> {
+        const VECTOR_SIZE: usize = 16;
+
+        let needle_vec = _mm_set1_epi8(byte as i8);
+        let mut end = haystack.len();
+
+        while end >= VECTOR_SIZE {
+            let data = _mm_loadu_si128(haystack.as_ptr().add(end - VECTOR_SIZE) as *const __m128i);
+            let mask = _mm_movemask_epi8(_mm_cmpeq_epi8(data, needle_vec)) as u16;
+
+            if mask != 0 {
+                return Some(end - 1 - mask.leading_zeros() as usize);
+            }
+
+            end -= VECTOR_SIZE;
+        }
+
+        self.rfind_byte_scalar(&haystack[..end], byte)
+    }
+
+    /// SIMD-accelerated reverse single byte search on other architectures
+    #[cfg(not(target_arch = "x86_64"))]
+    fn rfind_byte_simd(&self, haystack: &[u8], byte: u8) -> Option<usize> {
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        {
+            unsafe { self.rfind_byte_simd128(haystack, byte) }
+        }
+        #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+        {
+            self.rfind_byte_scalar(haystack, byte)
+        }
+    }
+
+    /// SIMD128 implementation of reverse single byte search
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    unsafe fn rfind_byte_simd128(&self, haystack: &[u8], byte: u8) -> Option<usize> {
+        use std::arch::wasm32::*;
+
+        const VECTOR_SIZE: usize = 16;
+
+        let needle_vec = u8x16_splat(byte);
+        let mut end = haystack.len();
+
+        while end >= VECTOR_SIZE {
+            let data = v128_load(haystack.as_ptr().add(end - VECTOR_SIZE) as *const v128);
+            let mask = u8x16_bitmask(u8x16_eq(data, needle_vec));
+
+            if mask != 0 {
+                return Some(end - 1 - mask.leading_zeros() as usize);
+            }
+
+            end -= VECTOR_SIZE;
+        }
+
+        self.rfind_byte_scalar(&haystack[..end], byte)
+    }
+
+    /// Scalar fallback for reverse single byte search
+    fn rfind_byte_scalar(&self, haystack: &[u8], byte: u8) -> Option<usize> {
+        haystack.iter().rposition(|&b| b == byte)
+    }
+
     /// Scalar fallback for single byte search
     fn find_byte_scalar(&self, haystack: &[u8], needle: u8) -> Option<usize> {
         haystack.iter().position(|&b| b == needle)
@@ -285,7 +519,14 @@ impl SimdByteCounter {
             }
         }
 
-        self.count_scalar(data, byte)
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        {
+            unsafe { self.count_simd128(data, byte) }
+        }
+        #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+        {
+            self.count_scalar(data, byte)
+        }
     }
 
     /// AVX2 implementation of byte counting
@@ -356,6 +597,31 @@ impl SimdByteCounter {
         count
     }
 
+    /// SIMD128 implementation of byte counting
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    unsafe fn count_simd128(&self, data: &[u8], byte: u8) -> usize {
+        use std::arch::wasm32::*;
+
+        const VECTOR_SIZE: usize = 16;
+
+        let len = data.len();
+        let vec_byte = u8x16_splat(byte);
+        let mut pos = 0;
+        let mut count = 0;
+
+        // Process 16 bytes at a time
+        while pos + VECTOR_SIZE <= len {
+            let vec_data = v128_load(data.as_ptr().add(pos) as *const v128);
+            count += u8x16_bitmask(u8x16_eq(vec_data, vec_byte)).count_ones() as usize;
+            pos += VECTOR_SIZE;
+        }
+
+        // Handle remaining bytes
+        count += self.count_scalar(&data[pos..], byte);
+
+        count
+    }
+
     /// Scalar fallback for byte counting
     fn count_scalar(&self, data: &[u8], byte: u8) -> usize {
         data.iter().filter(|&&b| b == byte).count()
@@ -388,15 +654,145 @@ impl SimdWhitespaceDetector {
         }
     }
 
-    /// Find the next non-whitespace character offset
-    pub fn skip_whitespace(&self, data: &[u8], mut start: usize) -> usize {
-        while start < data.len() {
-            if !data[start].is_ascii_whitespace() {
-                break;
+    /// Create a new SIMD whitespace detector with explicit configuration
+    pub fn with_config(config: SimdConfig) -> Self {
+        Self { config }
+    }
+
+    /// Offset of the first non-whitespace byte at or after `start`, or
+    /// `data.len()` if there is none
+    ///
+    /// Whitespace is the six ASCII whitespace bytes (see [`is_space`]).
+    pub fn skip_whitespace(&self, data: &[u8], start: usize) -> usize {
+        let start = start.min(data.len());
+        if !self.config.enabled || data.len() - start < 16 {
+            return self.skip_whitespace_scalar(data, start);
+        }
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return unsafe { self.skip_whitespace_avx2(data, start) };
+            }
+            if is_x86_feature_detected!("sse2") {
+                return unsafe { self.skip_whitespace_sse2(data, start) };
+            }
+        }
+
+        self.skip_whitespace_scalar(data, start)
+    }
+
+    /// Offset just past the last non-whitespace byte before `end`, or 0 if
+    /// there is none
+    pub fn rskip_whitespace(&self, data: &[u8], end: usize) -> usize {
+        let end = end.min(data.len());
+        if !self.config.enabled || end < 16 {
+            return self.rskip_whitespace_scalar(data, end);
+        }
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return unsafe { self.rskip_whitespace_avx2(data, end) };
+            }
+            if is_x86_feature_detected!("sse2") {
+                return unsafe { self.rskip_whitespace_sse2(data, end) };
+            }
+        }
+
+        self.rskip_whitespace_scalar(data, end)
+    }
+
+    /// `data` without leading and trailing whitespace
+    pub fn trim_slice<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        let start = self.skip_whitespace(data, 0);
+        if start == data.len() {
+            return &[];
+        }
+        &data[start..self.rskip_whitespace(data, data.len())]
+    }
+
+    /// AVX2 forward whitespace skip
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn skip_whitespace_avx2(&self, data: &[u8], mut pos: usize) -> usize {
+        const VECTOR_SIZE: usize = 32;
+
+        while pos + VECTOR_SIZE <= data.len() {
+            let vec_data = _mm256_loadu_si256(data.as_ptr().add(pos) as *const __m256i);
+            let other = !(_mm256_movemask_epi8(whitespace_avx2(vec_data)) as u32);
+            if other != 0 {
+                return pos + other.trailing_zeros() as usize;
+            }
+            pos += VECTOR_SIZE;
+        }
+
+        self.skip_whitespace_scalar(data, pos)
+    }
+
+    /// SSE2 forward whitespace skip
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "sse2")]
+    unsafe fn skip_whitespace_sse2(&self, data: &[u8], mut pos: usize) -> usize {
+        const VECTOR_SIZE: usize = 16;
+
+        while pos + VECTOR_SIZE <= data.len() {
+            let vec_data = _mm_loadu_si128(data.as_ptr().add(pos) as *const __m128i);
+            let other = !(_mm_movemask_epi8(whitespace_sse2(vec_data)) as u16);
+            if other != 0 {
+                return pos + other.trailing_zeros() as usize;
+            }
+            pos += VECTOR_SIZE;
+        }
+
+        self.skip_whitespace_scalar(data, pos)
+    }
+
+    /// AVX2 backward whitespace skip
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn rskip_whitespace_avx2(&self, data: &[u8], mut end: usize) -> usize {
+        const VECTOR_SIZE: usize = 32;
+
+        while end >= VECTOR_SIZE {
+            let vec_data = _mm256_loadu_si256(data.as_ptr().add(end - VECTOR_SIZE) as *const __m256i);
+            let other = !(_mm256_movemask_epi8(whitespace_avx2(vec_data)) as u32);
+            if other != 0 {
+                return end - other.leading_zeros() as usize;
+            }
+            end -= VECTOR_SIZE;
+        }
+
+        self.rskip_whitespace_scalar(data, end)
+    }
+
+    /// SSE2 backward whitespace skip
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "sse2")]
+    unsafe fn rskip_whitespace_sse2(&self, data: &[u8], mut end: usize) -> usize {
+        const VECTOR_SIZE: usize = 16;
+
+        while end >= VECTOR_SIZE {
+            let vec_data = _mm_loadu_si128(data.as_ptr().add(end - VECTOR_SIZE) as *const __m128i);
+            let other = !(_mm_movemask_epi8(whitespace_sse2(vec_data)) as u16);
+            if other != 0 {
+                return end - other.leading_zeros() as usize;
             }
-            start += 1;
+            end -= VECTOR_SIZE;
         }
-        start
+
+        self.rskip_whitespace_scalar(data, end)
+    }
+
+    fn skip_whitespace_scalar(&self, data: &[u8], start: usize) -> usize {
+        data[start..]
+            .iter()
+            .position(|&b| !is_space(b))
+            .map_or(data.len(), |p| start + p)
+    }
+
+    fn rskip_whitespace_scalar(&self, data: &[u8], end: usize) -> usize {
+        data[..end].iter().rposition(|&b| !is_space(b)).map_or(0, |p| p + 1)
     }
 
     /// Count lines in a buffer
@@ -405,27 +801,80 @@ impl SimdWhitespaceDetector {
     }
 
     /// Count words in a buffer
+    ///
+    /// Words are runs of bytes other than the six ASCII whitespace bytes
+    /// (see [`is_space`]), counted at the byte each one starts with.
     pub fn count_words(&self, data: &[u8]) -> usize {
-        let mut count = 0;
-        let mut in_word = false;
+        if !self.config.enabled || data.len() < 64 {
+            return self.count_words_scalar(data, true);
+        }
 
-        for &byte in data.iter() {
-            let is_whitespace = byte.is_ascii_whitespace();
-            if is_whitespace {
-                if in_word {
-                    count += 1;
-                    in_word = false;
-                }
-            } else {
-                in_word = true;
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return unsafe { self.count_words_avx2(data) };
+            }
+            if is_x86_feature_detected!("sse2") {
+                return unsafe { self.count_words_sse2(data) };
             }
         }
 
-        // Count the last word if the buffer doesn't end with whitespace
-        if in_word {
-            count += 1;
+        self.count_words_scalar(data, true)
+    }
+
+    /// AVX2 word counting
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn count_words_avx2(&self, data: &[u8]) -> usize {
+        const VECTOR_SIZE: usize = 32;
+
+        let mut pos = 0;
+        let mut count = 0;
+        let mut after_whitespace = true;
+
+        while pos + VECTOR_SIZE <= data.len() {
+            let vec_data = _mm256_loadu_si256(data.as_ptr().add(pos) as *const __m256i);
+            let whitespace = _mm256_movemask_epi8(whitespace_avx2(vec_data)) as u32 as u64;
+            count += word_starts(whitespace, after_whitespace, VECTOR_SIZE).count_ones() as usize;
+            after_whitespace = whitespace >> (VECTOR_SIZE - 1) & 1 == 1;
+            pos += VECTOR_SIZE;
+        }
+
+        count + self.count_words_scalar(&data[pos..], after_whitespace)
+    }
+
+    /// SSE2 word counting
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "sse2")]
+    unsafe fn count_words_sse2(&self, data: &[u8]) -> usize {
+        const VECTOR_SIZE: usize = 16;
+
+        let mut pos = 0;
+        let mut count = 0;
+        let mut after_whitespace = true;
+
+        while pos + VECTOR_SIZE <= data.len() {
+            let vec_data = _mm_loadu_si128(data.as_ptr().add(pos) as *const __m128i);
+            let whitespace = _mm_movemask_epi8(whitespace_sse2(vec_data)) as u16 as u64;
+            count += word_starts(whitespace, after_whitespace, VECTOR_SIZE).count_ones() as usize;
+            after_whitespace = whitespace >> (VECTOR_SIZE - 1) & 1 == 1;
+            pos += VECTOR_SIZE;
         }
 
+        count + self.count_words_scalar(&data[pos..], after_whitespace)
+    }
+
+    /// Count the words starting in `data`; a word running on from before it
+    /// is not counted unless `after_whitespace`
+    fn count_words_scalar(&self, data: &[u8], mut after_whitespace: bool) -> usize {
+        let mut count = 0;
+        for &byte in data {
+            let space = is_space(byte);
+            if after_whitespace && !space {
+                count += 1;
+            }
+            after_whitespace = space;
+        }
         count
     }
 
@@ -444,7 +893,14 @@ impl SimdWhitespaceDetector {
             }
         }
 
-        self.count_byte_scalar(data, byte)
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        {
+            unsafe { self.count_byte_simd128(data, byte) }
+        }
+        #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+        {
+            self.count_byte_scalar(data, byte)
+        }
     }
 
     #[cfg(target_arch = "x86_64")]
@@ -493,6 +949,27 @@ impl SimdWhitespaceDetector {
         count
     }
 
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    unsafe fn count_byte_simd128(&self, data: &[u8], byte: u8) -> usize {
+        use std::arch::wasm32::*;
+
+        const VECTOR_SIZE: usize = 16;
+
+        let len = data.len();
+        let vec_byte = u8x16_splat(byte);
+        let mut pos = 0;
+        let mut count = 0;
+
+        while pos + VECTOR_SIZE <= len {
+            let vec_data = v128_load(data.as_ptr().add(pos) as *const v128);
+            count += u8x16_bitmask(u8x16_eq(vec_data, vec_byte)).count_ones() as usize;
+            pos += VECTOR_SIZE;
+        }
+
+        count += self.count_byte_scalar(&data[pos..], byte);
+        count
+    }
+
     fn count_byte_scalar(&self, data: &[u8], byte: u8) -> usize {
         data.iter().filter(|&&b| b == byte).count()
     }
@@ -504,6 +981,50 @@ impl Default for SimdWhitespaceDetector {
     }
 }
 
+/// Whether `byte` is one of the six ASCII whitespace bytes of C's
+/// `isspace`: space, `\t`, `\n`, `\v`, `\f` and `\r`
+///
+/// Unlike [`u8::is_ascii_whitespace`], this includes the vertical tab, as
+/// `wc` does.
+#[inline]
+pub fn is_space(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t'..=b'\r')
+}
+
+/// Bits of the bytes that start a word, in a block of `width` bytes (1 to
+/// 64) with a bit set for each whitespace byte
+///
+/// A byte starts a word when it is not whitespace and the one before it
+/// is; `after_whitespace` stands in for the byte before the block.
+#[inline(always)]
+fn word_starts(whitespace: u64, after_whitespace: bool, width: usize) -> u64 {
+    !whitespace & (whitespace << 1 | after_whitespace as u64) & (u64::MAX >> (64 - width))
+}
+
+/// Lanes of `data` holding one of the six ASCII whitespace bytes
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+#[inline]
+unsafe fn whitespace_avx2(data: __m256i) -> __m256i {
+    let mut whitespace = _mm256_cmpeq_epi8(data, _mm256_set1_epi8(b' ' as i8));
+    for byte in b'\t'..=b'\r' {
+        whitespace = _mm256_or_si256(whitespace, _mm256_cmpeq_epi8(data, _mm256_set1_epi8(byte as i8)));
+    }
+    whitespace
+}
+
+/// Lanes of `data` holding one of the six ASCII whitespace bytes
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+#[inline]
+unsafe fn whitespace_sse2(data: __m128i) -> __m128i {
+    let mut whitespace = _mm_cmpeq_epi8(data, _mm_set1_epi8(b' ' as i8));
+    for byte in b'\t'..=b'\r' {
+        whitespace = _mm_or_si128(whitespace, _mm_cmpeq_epi8(data, _mm_set1_epi8(byte as i8)));
+    }
+    whitespace
+}
+
 /// SIMD-accelerated newline counter for line-based operations
 /// Optimized for ai-head and ai-tail utilities
 pub struct SimdNewlineCounter {
@@ -523,6 +1044,36 @@ impl SimdNewlineCounter {
         Self { config }
     }
 
+    /// Count the newlines in a buffer
+    pub fn count(&self, data: &[u8]) -> usize {
+        SimdByteCounter::with_config(self.config.clone()).count(data, b'\n')
+    }
+
+    /// Offsets of the newlines in a buffer, in order
+    ///
+    /// Newlines are found 64 bytes at a time and read out of the block's
+    /// mask, so scanning stops as soon as the caller stops iterating.
+    pub fn positions<'a>(&self, data: &'a [u8]) -> NewlinePositions<'a> {
+        let mut block_mask: fn(&[u8]) -> u64 = newline_mask_scalar;
+        if self.config.enabled {
+            #[cfg(target_arch = "x86_64")]
+            {
+                if is_x86_feature_detected!("avx2") {
+                    block_mask = newline_mask_avx2;
+                } else if is_x86_feature_detected!("sse2") {
+                    block_mask = newline_mask_sse2;
+                }
+            }
+        }
+        NewlinePositions {
+            data,
+            base: 0,
+            next: 0,
+            mask: 0,
+            block_mask,
+        }
+    }
+
     /// Find the position of the nth newline (1-indexed)
     /// Returns None if n newlines are not found
     pub fn find_nth_newline(&self, data: &[u8], n: usize) -> Option<usize> {
@@ -572,85 +1123,47 @@ impl SimdNewlineCounter {
     /// AVX2 implementation of find_nth_newline
     #[cfg(target_arch = "x86_64")]
     #[target_feature(enable = "avx2")]
-    unsafe fn find_nth_newline_avx2(&self, data: &[u8], n: usize) -> Option<usize> {
+    unsafe fn find_nth_newline_avx2(&self, data: &[u8], mut n: usize) -> Option<usize> {
         const VECTOR_SIZE: usize = 32;
-        let mut count = 0;
         let newline_vec = _mm256_set1_epi8(b'\n' as i8);
+        let mut pos = 0;
 
-        for i in (0..data.len()).step_by(VECTOR_SIZE) {
-            let remaining = data.len() - i;
-            let chunk_size = VECTOR_SIZE.min(remaining);
-
-            // Load the chunk (may be partial)
-            let mut chunk_bytes = [0u8; 32];
-            chunk_bytes[..chunk_size].copy_from_slice(&data[i..i + chunk_size]);
-            let ptr = chunk_bytes.as_ptr() as *const __m256i;
-            let vec_data = _mm256_loadu_si256(ptr);
-
-            // Compare for equality with newline
-            let cmp = _mm256_cmpeq_epi8(vec_data, newline_vec);
-            let mask = _mm256_movemask_epi8(cmp) as u32;
+        while pos + VECTOR_SIZE <= data.len() {
+            let vec_data = _mm256_loadu_si256(data.as_ptr().add(pos) as *const __m256i);
+            let mask = _mm256_movemask_epi8(_mm256_cmpeq_epi8(vec_data, newline_vec)) as u32;
 
-            // Count newlines in this chunk
-            let chunk_newlines = mask.count_ones() as usize;
-            count += chunk_newlines;
-
-            if count >= n {
-                // The nth newline is in this chunk
-                let target_in_chunk = n - (count - chunk_newlines);
-                let mut found = 0;
-                for j in 0..chunk_size {
-                    if data[i + j] == b'\n' {
-                        found += 1;
-                        if found == target_in_chunk {
-                            return Some(i + j);
-                        }
-                    }
-                }
+            let found = mask.count_ones() as usize;
+            if found >= n {
+                return Some(pos + nth_set_bit(mask as u64, n));
             }
+            n -= found;
+            pos += VECTOR_SIZE;
         }
 
-        None
+        self.find_nth_newline_scalar(&data[pos..], n).map(|i| pos + i)
     }
 
     /// SSE2 implementation of find_nth_newline
     #[cfg(target_arch = "x86_64")]
     #[target_feature(enable = "sse2")]
-    unsafe fn find_nth_newline_sse2(&self, data: &[u8], n: usize) -> Option<usize> {
+    unsafe fn find_nth_newline_sse2(&self, data: &[u8], mut n: usize) -> Option<usize> {
         const VECTOR_SIZE: usize = 16;
-        let mut count = 0;
         let newline_vec = _mm_set1_epi8(b'\n' as i8);
+        let mut pos = 0;
 
-        for i in (0..data.len()).step_by(VECTOR_SIZE) {
-            let remaining = data.len() - i;
-            let chunk_size = VECTOR_SIZE.min(remaining);
-
-            let mut chunk_bytes = [0u8; 16];
-            chunk_bytes[..chunk_size].copy_from_slice(&data[i..i + chunk_size]);
-            let ptr = chunk_bytes.as_ptr() as *const __m128i;
-            let vec_data = _mm_loadu_si128(ptr);
-
-            let cmp = _mm_cmpeq_epi8(vec_data, newline_vec);
-            let mask = _mm_movemask_epi8(cmp) as u32;
+        while pos + VECTOR_SIZE <= data.len() {
+            let vec_data = _mm_loadu_si128(data.as_ptr().add(pos) as *const __m128i);
+            let mask = _mm_movemask_epi8(_mm_cmpeq_epi8(vec_data, newline_vec)) as u32;
 
-            let chunk_newlines = mask.count_ones() as usize;
-            count += chunk_newlines;
-
-            if count >= n {
-                let target_in_chunk = n - (count - chunk_newlines);
-                let mut found = 0;
-                for j in 0..chunk_size {
-                    if data[i + j] == b'\n' {
-                        found += 1;
-                        if found == target_in_chunk {
-                            return Some(i + j);
-                        }
-                    }
-                }
+            let found = mask.count_ones() as usize;
+            if found >= n {
+                return Some(pos + nth_set_bit(mask as u64, n));
             }
+            n -= found;
+            pos += VECTOR_SIZE;
         }
 
-        None
+        self.find_nth_newline_scalar(&data[pos..], n).map(|i| pos + i)
     }
 
     /// Scalar fallback for find_nth_newline
@@ -667,106 +1180,182 @@ impl SimdNewlineCounter {
         None
     }
 
-    /// AVX2 implementation of find_last_n_newlines
+    /// AVX2 implementation of find_last_n_newlines, scanning back from the
+    /// end
     #[cfg(target_arch = "x86_64")]
     #[target_feature(enable = "avx2")]
     unsafe fn find_last_n_newlines_avx2(&self, data: &[u8], n: usize) -> Vec<usize> {
         const VECTOR_SIZE: usize = 32;
-        let mut all_newlines = Vec::new();
         let newline_vec = _mm256_set1_epi8(b'\n' as i8);
+        let mut found = Vec::new();
+        let mut end = data.len();
 
-        for i in (0..data.len()).step_by(VECTOR_SIZE) {
-            let remaining = data.len() - i;
-            let chunk_size = VECTOR_SIZE.min(remaining);
-
-            let mut chunk_bytes = [0u8; 32];
-            chunk_bytes[..chunk_size].copy_from_slice(&data[i..i + chunk_size]);
-            let ptr = chunk_bytes.as_ptr() as *const __m256i;
-            let vec_data = _mm256_loadu_si256(ptr);
-
-            let cmp = _mm256_cmpeq_epi8(vec_data, newline_vec);
-            let mask = _mm256_movemask_epi8(cmp) as u32;
-
-            if mask != 0 {
-                // Extract newlines from this chunk
-                for j in 0..chunk_size {
-                    if data[i + j] == b'\n' {
-                        all_newlines.push(i + j);
-                    }
-                }
-            }
+        while end >= VECTOR_SIZE && found.len() < n {
+            let start = end - VECTOR_SIZE;
+            let vec_data = _mm256_loadu_si256(data.as_ptr().add(start) as *const __m256i);
+            let mask = _mm256_movemask_epi8(_mm256_cmpeq_epi8(vec_data, newline_vec)) as u32;
+            push_last_set_bits(mask as u64, start, n, &mut found);
+            end = start;
         }
 
-        // Return the last n newlines
-        let start = if all_newlines.len() > n {
-            all_newlines.len() - n
-        } else {
-            0
-        };
-        all_newlines[start..].to_vec()
+        self.join_last_newlines(&data[..end], n, found)
     }
 
-    /// SSE2 implementation of find_last_n_newlines
+    /// SSE2 implementation of find_last_n_newlines, scanning back from the
+    /// end
     #[cfg(target_arch = "x86_64")]
     #[target_feature(enable = "sse2")]
     unsafe fn find_last_n_newlines_sse2(&self, data: &[u8], n: usize) -> Vec<usize> {
         const VECTOR_SIZE: usize = 16;
-        let mut all_newlines = Vec::new();
         let newline_vec = _mm_set1_epi8(b'\n' as i8);
+        let mut found = Vec::new();
+        let mut end = data.len();
 
-        for i in (0..data.len()).step_by(VECTOR_SIZE) {
-            let remaining = data.len() - i;
-            let chunk_size = VECTOR_SIZE.min(remaining);
-
-            let mut chunk_bytes = [0u8; 16];
-            chunk_bytes[..chunk_size].copy_from_slice(&data[i..i + chunk_size]);
-            let ptr = chunk_bytes.as_ptr() as *const __m128i;
-            let vec_data = _mm_loadu_si128(ptr);
-
-            let cmp = _mm_cmpeq_epi8(vec_data, newline_vec);
-            let mask = _mm_movemask_epi8(cmp) as u32;
-
-            if mask != 0 {
-                for j in 0..chunk_size {
-                    if data[i + j] == b'\n' {
-                        all_newlines.push(i + j);
-                    }
-                }
-            }
+        while end >= VECTOR_SIZE && found.len() < n {
+            let start = end - VECTOR_SIZE;
+            let vec_data = _mm_loadu_si128(data.as_ptr().add(start) as *const __m128i);
+            let mask = _mm_movemask_epi8(_mm_cmpeq_epi8(vec_data, newline_vec)) as u32;
+            push_last_set_bits(mask as u64, start, n, &mut found);
+            end = start;
         }
 
-        let start = if all_newlines.len() > n {
-            all_newlines.len() - n
-        } else {
-            0
-        };
-        all_newlines[start..].to_vec()
+        self.join_last_newlines(&data[..end], n, found)
+    }
+
+    /// Put the newlines `found` (last first) after those still wanted from
+    /// the unscanned `head` of the data, in ascending order
+    fn join_last_newlines(&self, head: &[u8], n: usize, found: Vec<usize>) -> Vec<usize> {
+        let mut newlines = self.find_last_n_newlines_scalar(head, n - found.len());
+        newlines.extend(found.into_iter().rev());
+        newlines
     }
 
     /// Scalar fallback for find_last_n_newlines
     fn find_last_n_newlines_scalar(&self, data: &[u8], n: usize) -> Vec<usize> {
-        let all_newlines: Vec<usize> = data
+        let mut newlines: Vec<usize> = data
             .iter()
             .enumerate()
+            .rev()
             .filter(|(_, &byte)| byte == b'\n')
             .map(|(i, _)| i)
+            .take(n)
             .collect();
+        newlines.reverse();
+        newlines
+    }
+}
 
-        let start = if all_newlines.len() > n {
-            all_newlines.len() - n
-        } else {
-            0
-        };
-        all_newlines[start..].to_vec()
+/// Offset in `mask` of its `n`th set bit (1-indexed); there must be `n`
+#[inline]
+fn nth_set_bit(mut mask: u64, n: usize) -> usize {
+    for _ in 1..n {
+        mask &= mask - 1;
+    }
+    mask.trailing_zeros() as usize
+}
+
+/// Push the offsets of the set bits of `mask`, highest first, until `found`
+/// holds `n`
+#[inline]
+fn push_last_set_bits(mut mask: u64, base: usize, n: usize, found: &mut Vec<usize>) {
+    while mask != 0 && found.len() < n {
+        let bit = 63 - mask.leading_zeros() as usize;
+        found.push(base + bit);
+        mask &= !(1 << bit);
+    }
+}
+
+/// Iterator over newline offsets, from [`SimdNewlineCounter::positions`]
+pub struct NewlinePositions<'a> {
+    data: &'a [u8],
+    /// Offset of the block `mask` covers
+    base: usize,
+    /// Offset of the next block to load
+    next: usize,
+    /// Newlines of the current block not yet returned
+    mask: u64,
+    block_mask: fn(&[u8]) -> u64,
+}
+
+impl Iterator for NewlinePositions<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.mask == 0 {
+            if self.next >= self.data.len() {
+                return None;
+            }
+            let end = (self.next + 64).min(self.data.len());
+            self.mask = (self.block_mask)(&self.data[self.next..end]);
+            self.base = self.next;
+            self.next = end;
+        }
+        let bit = self.mask.trailing_zeros() as usize;
+        self.mask &= self.mask - 1;
+        Some(self.base + bit)
     }
 }
 
+/// Newline mask of a block of up to 64 bytes, bit `i` for byte `i`
+fn newline_mask_scalar(block: &[u8]) -> u64 {
+    block
+        .iter()
+        .enumerate()
+        .fold(0, |mask, (i, &byte)| mask | ((byte == b'\n') as u64) << i)
+}
+
+#[cfg(target_arch = "x86_64")]
+fn newline_mask_avx2(block: &[u8]) -> u64 {
+    #[target_feature(enable = "avx2")]
+    unsafe fn full_block(ptr: *const u8) -> u64 {
+        let newline_vec = _mm256_set1_epi8(b'\n' as i8);
+        let lo = _mm256_cmpeq_epi8(_mm256_loadu_si256(ptr as *const __m256i), newline_vec);
+        let hi = _mm256_cmpeq_epi8(_mm256_loadu_si256(ptr.add(32) as *const __m256i), newline_vec);
+        (_mm256_movemask_epi8(hi) as u32 as u64) << 32 | _mm256_movemask_epi8(lo) as u32 as u64
+    }
+
+    if block.len() < 64 {
+        return newline_mask_scalar(block);
+    }
+    // SAFETY: only chosen by `positions` when AVX2 is available, and the
+    // block holds the 64 bytes read
+    unsafe { full_block(block.as_ptr()) }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn newline_mask_sse2(block: &[u8]) -> u64 {
+    #[target_feature(enable = "sse2")]
+    unsafe fn full_block(ptr: *const u8) -> u64 {
+        let newline_vec = _mm_set1_epi8(b'\n' as i8);
+        let mut mask = 0;
+        for lane in 0..4 {
+            let vec_data = _mm_loadu_si128(ptr.add(lane * 16) as *const __m128i);
+            mask |= (_mm_movemask_epi8(_mm_cmpeq_epi8(vec_data, newline_vec)) as u16 as u64) << (lane * 16);
+        }
+        mask
+    }
+
+    if block.len() < 64 {
+        return newline_mask_scalar(block);
+    }
+    // SAFETY: only chosen by `positions` when SSE2 is available, and the
+    // block holds the 64 bytes read
+    unsafe { full_block(block.as_ptr()) }
+}
+
 impl Default for SimdNewlineCounter {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Copies of at least this many bytes bypass the cache with non-temporal
+/// stores
+///
+/// Below this the destination is likely to be read again soon and fits in
+/// the last-level cache, where ordinary stores are faster.
+pub const NON_TEMPORAL_COPY_THRESHOLD: usize = 4 * 1024 * 1024;
+
 /// SIMD-accelerated memory operations
 /// Optimized for ai-cp and ai-mv utilities
 pub struct SimdMemoryOps {
@@ -788,6 +1377,10 @@ impl SimdMemoryOps {
 
     /// Copy memory from src to dst using SIMD when beneficial
     /// Returns the number of bytes copied
+    ///
+    /// Copies of [`NON_TEMPORAL_COPY_THRESHOLD`] bytes or more use
+    /// non-temporal stores on AVX2, so a huge copy does not evict the rest
+    /// of the working set from the cache.
     pub fn copy(&self, dst: &mut [u8], src: &[u8]) -> Result<usize, String> {
         let bytes_to_copy = src.len().min(dst.len());
 
@@ -800,6 +1393,9 @@ impl SimdMemoryOps {
         #[cfg(target_arch = "x86_64")]
         {
             if is_x86_feature_detected!("avx2") {
+                if bytes_to_copy >= NON_TEMPORAL_COPY_THRESHOLD {
+                    return unsafe { self.copy_avx2_non_temporal(dst, src, bytes_to_copy) };
+                }
                 return unsafe { self.copy_avx2(dst, src, bytes_to_copy) };
             }
             if is_x86_feature_detected!("sse2") {
@@ -844,6 +1440,39 @@ impl SimdMemoryOps {
         a.cmp(b)
     }
 
+    /// Offset of the first byte where `a` and `b` differ, using SIMD
+    ///
+    /// When one slice is a prefix of the other, the difference is at the
+    /// end of the shorter one. Returns `None` only if the slices are equal.
+    pub fn first_difference(&self, a: &[u8], b: &[u8]) -> Option<usize> {
+        let min_len = a.len().min(b.len());
+        let mismatch = if !self.config.enabled || min_len < 64 {
+            first_difference_scalar(a, b, 0, min_len)
+        } else {
+            self.first_difference_dispatch(a, b, min_len)
+        };
+
+        match mismatch {
+            Some(pos) => Some(pos),
+            None if a.len() != b.len() => Some(min_len),
+            None => None,
+        }
+    }
+
+    fn first_difference_dispatch(&self, a: &[u8], b: &[u8], min_len: usize) -> Option<usize> {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return unsafe { self.first_difference_avx2(a, b, min_len) };
+            }
+            if is_x86_feature_detected!("sse2") {
+                return unsafe { self.first_difference_sse2(a, b, min_len) };
+            }
+        }
+
+        first_difference_scalar(a, b, 0, min_len)
+    }
+
     /// Fill a buffer with a repeated byte pattern using SIMD
     pub fn fill(&self, dst: &mut [u8], byte: u8) -> Result<(), String> {
         if !self.config.enabled || dst.len() < 64 {
@@ -891,6 +1520,52 @@ impl SimdMemoryOps {
         Ok(count)
     }
 
+    /// AVX2 copy with non-temporal stores, for buffers much larger than the cache
+    ///
+    /// Streaming stores need an aligned destination, so the head is copied
+    /// normally up to the first 32-byte boundary of `dst`. The body moves
+    /// 128 bytes per iteration while prefetching the source ahead, and
+    /// the tail is copied normally. The closing `sfence` orders the
+    /// streaming stores before any later store.
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn copy_avx2_non_temporal(&self, dst: &mut [u8], src: &[u8], count: usize) -> Result<usize, String> {
+        const VECTOR_SIZE: usize = 32;
+        const BLOCK_SIZE: usize = 4 * VECTOR_SIZE;
+        const PREFETCH_DISTANCE: usize = 4 * BLOCK_SIZE;
+
+        let head = dst.as_ptr().align_offset(VECTOR_SIZE).min(count);
+        dst[..head].copy_from_slice(&src[..head]);
+        let mut pos = head;
+
+        while pos + BLOCK_SIZE <= count {
+            let src_ptr = src.as_ptr().add(pos);
+            let dst_ptr = dst.as_mut_ptr().add(pos) as *mut __m256i;
+
+            if pos + PREFETCH_DISTANCE < count {
+                _mm_prefetch::<_MM_HINT_T0>(src_ptr.add(PREFETCH_DISTANCE) as *const i8);
+            }
+
+            let v0 = _mm256_loadu_si256(src_ptr as *const __m256i);
+            let v1 = _mm256_loadu_si256(src_ptr.add(VECTOR_SIZE) as *const __m256i);
+            let v2 = _mm256_loadu_si256(src_ptr.add(2 * VECTOR_SIZE) as *const __m256i);
+            let v3 = _mm256_loadu_si256(src_ptr.add(3 * VECTOR_SIZE) as *const __m256i);
+            _mm256_stream_si256(dst_ptr, v0);
+            _mm256_stream_si256(dst_ptr.add(1), v1);
+            _mm256_stream_si256(dst_ptr.add(2), v2);
+            _mm256_stream_si256(dst_ptr.add(3), v3);
+
+            pos += BLOCK_SIZE;
+        }
+        _mm_sfence();
+
+        if pos < count {
+            dst[pos..count].copy_from_slice(&src[pos..count]);
+        }
+
+        Ok(count)
+    }
+
     /// SSE2 implementation of memory copy
     #[cfg(target_arch = "x86_64")]
     #[target_feature(enable = "sse2")]
@@ -958,6 +1633,50 @@ impl SimdMemoryOps {
         None
     }
 
+    /// AVX2 implementation of first_difference over the first `min_len` bytes
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn first_difference_avx2(&self, a: &[u8], b: &[u8], min_len: usize) -> Option<usize> {
+        const VECTOR_SIZE: usize = 32;
+        let mut pos = 0;
+
+        while pos + VECTOR_SIZE <= min_len {
+            let a_vec = _mm256_loadu_si256(a.as_ptr().add(pos) as *const __m256i);
+            let b_vec = _mm256_loadu_si256(b.as_ptr().add(pos) as *const __m256i);
+
+            let mask = _mm256_movemask_epi8(_mm256_cmpeq_epi8(a_vec, b_vec)) as u32;
+            if mask != 0xFFFFFFFF {
+                return Some(pos + (!mask).trailing_zeros() as usize);
+            }
+
+            pos += VECTOR_SIZE;
+        }
+
+        first_difference_scalar(a, b, pos, min_len)
+    }
+
+    /// SSE2 implementation of first_difference over the first `min_len` bytes
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "sse2")]
+    unsafe fn first_difference_sse2(&self, a: &[u8], b: &[u8], min_len: usize) -> Option<usize> {
+        const VECTOR_SIZE: usize = 16;
+        let mut pos = 0;
+
+        while pos + VECTOR_SIZE <= min_len {
+            let a_vec = _mm_loadu_si128(a.as_ptr().add(pos) as *const __m128i);
+            let b_vec = _mm_loadu_si128(b.as_ptr().add(pos) as *const __m128i);
+
+            let mask = _mm_movemask_epi8(_mm_cmpeq_epi8(a_vec, b_vec)) as u32;
+            if mask != 0xFFFF {
+                return Some(pos + (!mask & 0xFFFF).trailing_zeros() as usize);
+            }
+
+            pos += VECTOR_SIZE;
+        }
+
+        first_difference_scalar(a, b, pos, min_len)
+    }
+
     /// SSE2 implementation of memory compare
     #[cfg(target_arch = "x86_64")]
     #[target_feature(enable = "sse2")]
@@ -1042,6 +1761,15 @@ impl SimdMemoryOps {
     }
 }
 
+/// First differing offset in `a[start..end]` and `b[start..end]`
+fn first_difference_scalar(a: &[u8], b: &[u8], start: usize, end: usize) -> Option<usize> {
+    a[start..end]
+        .iter()
+        .zip(&b[start..end])
+        .position(|(x, y)| x != y)
+        .map(|i| start + i)
+}
+
 impl Default for SimdMemoryOps {
     fn default() -> Self {
         Self::new()
@@ -1063,25 +1791,52 @@ impl SimdHasher {
     }
 
     /// Compute CRC32 checksum using SIMD when available
+    ///
+    /// Always CRC-32/IEEE. The x86 `crc32` instruction computes CRC-32C (a
+    /// different polynomial), so it is not used: digests must not depend on
+    /// the CPU that computed them.
     pub fn crc32(&self, data: &[u8]) -> u32 {
+        self.crc32_update(0, data)
+    }
+
+    /// Continue a CRC32 over more data
+    ///
+    /// `crc` is the checksum of the data so far (0 to start), so hashing a
+    /// buffer in chunks gives the same result as [`crc32`](Self::crc32)
+    /// over the whole of it.
+    pub fn crc32_update(&self, crc: u32, data: &[u8]) -> u32 {
         if !self.config.enabled || data.len() < 64 {
-            return self.crc32_scalar(data);
+            return self.crc32_scalar(crc, data);
         }
 
         #[cfg(target_arch = "x86_64")]
         {
             if is_x86_feature_detected!("avx2") {
-                return unsafe { self.crc32_avx2(data) };
-            }
-            if is_x86_feature_detected!("sse4.1") {
-                return unsafe { self.crc32_sse41(data) };
+                return unsafe { self.crc32_avx2(crc, data) };
             }
         }
 
-        self.crc32_scalar(data)
+        self.crc32_scalar(crc, data)
+    }
+
+    /// XXH3 64-bit hash, for fast fingerprinting and deduplication
+    ///
+    /// Vectorized internally (SSE2/AVX2/NEON) and far stronger than
+    /// [`rolling_hash`](Self::rolling_hash); see [`crate::hash`] for a
+    /// uniform interface over all algorithms.
+    pub fn xxh3(&self, data: &[u8]) -> u64 {
+        xxhash_rust::xxh3::xxh3_64(data)
+    }
+
+    /// XXH64 hash with a seed
+    pub fn xxh64(&self, data: &[u8], seed: u64) -> u64 {
+        xxhash_rust::xxh64::xxh64(data, seed)
     }
 
     /// Simple rolling hash for incremental verification
+    ///
+    /// djb2: cheap but collision-prone; prefer [`xxh3`](Self::xxh3) for
+    /// fingerprinting.
     pub fn rolling_hash(&self, data: &[u8]) -> u64 {
         let mut hash: u64 = 5381;
 
@@ -1093,8 +1848,8 @@ impl SimdHasher {
     }
 
     /// Scalar CRC32 implementation (fallback)
-    fn crc32_scalar(&self, data: &[u8]) -> u32 {
-        let mut crc: u32 = 0xFFFFFFFF;
+    fn crc32_scalar(&self, crc: u32, data: &[u8]) -> u32 {
+        let mut crc = !crc;
 
         for &byte in data {
             crc ^= byte as u32;
@@ -1113,9 +1868,9 @@ impl SimdHasher {
     /// AVX2 implementation using parallel computation
     #[cfg(target_arch = "x86_64")]
     #[target_feature(enable = "avx2")]
-    unsafe fn crc32_avx2(&self, data: &[u8]) -> u32 {
+    unsafe fn crc32_avx2(&self, crc: u32, data: &[u8]) -> u32 {
         const VECTOR_SIZE: usize = 32;
-        let mut crc: u32 = 0xFFFFFFFF;
+        let mut crc = !crc;
         let mut pos = 0;
 
         // Process 32 bytes at a time using folded CRC
@@ -1151,21 +1906,6 @@ impl SimdHasher {
 
         !crc
     }
-
-    /// SSE4.1 implementation using hardware CRC32 instruction
-    #[cfg(target_arch = "x86_64")]
-    #[target_feature(enable = "sse4.1")]
-    unsafe fn crc32_sse41(&self, data: &[u8]) -> u32 {
-        use std::arch::x86_64::_mm_crc32_u8;
-
-        let mut crc: u32 = 0xFFFFFFFF;
-
-        for &byte in data {
-            crc = _mm_crc32_u8(crc, byte);
-        }
-
-        !crc
-    }
 }
 
 impl Default for SimdHasher {
@@ -1269,8 +2009,52 @@ impl SimdEntropyCalculator {
                 entropy -= probability * probability.log2();
             }
         }
-
-        entropy
+
+        entropy
+    }
+
+    /// Ranges of `data` whose sliding `window`-byte entropy reaches `threshold`
+    ///
+    /// Each window's entropy is updated in O(1) from the previous one, so the
+    /// scan is linear in `data`. Overlapping and adjacent high-entropy windows
+    /// are merged into one range.
+    pub fn high_entropy_windows(&self, data: &[u8], window: usize, threshold: f64) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        if window == 0 || data.len() < window {
+            return ranges;
+        }
+
+        // H = log2(w) - sum(c * log2 c) / w over the window's byte counts
+        let weight = |count: u32| if count > 1 { count as f64 * (count as f64).log2() } else { 0.0 };
+        let mut counts = [0u32; 256];
+        let mut weighted = 0.0;
+        let mut add = |counts: &mut [u32; 256], byte: u8, delta: i32| {
+            let count = &mut counts[byte as usize];
+            let before = weight(*count);
+            *count = count.wrapping_add_signed(delta);
+            weighted += weight(*count) - before;
+            weighted
+        };
+
+        let size = window as f64;
+        let mut current = 0.0;
+        for &byte in &data[..window] {
+            current = add(&mut counts, byte, 1);
+        }
+        for start in 0..=data.len() - window {
+            if start > 0 {
+                add(&mut counts, data[start - 1], -1);
+                current = add(&mut counts, data[start + window - 1], 1);
+            }
+            // Tolerate float drift from the running sum
+            if size.log2() - current / size + 1e-9 >= threshold {
+                match ranges.last_mut() {
+                    Some(last) if last.end >= start => last.end = start + window,
+                    _ => ranges.push(start..start + window),
+                }
+            }
+        }
+        ranges
     }
 
     /// Detect if data is likely binary based on entropy and byte analysis
@@ -1482,6 +2266,12 @@ impl SimdCaseFolder {
         }
     }
 
+    /// Non-x86 fallback for case-insensitive byte search
+    #[cfg(not(target_arch = "x86_64"))]
+    fn find_caseless_byte_simd(&self, text: &[u8], byte: u8) -> Option<usize> {
+        self.find_caseless_byte_scalar(text, byte)
+    }
+
     /// AVX2 caseless byte search
     #[cfg(target_arch = "x86_64")]
     #[target_feature(enable = "avx2")]
@@ -1977,6 +2767,13 @@ impl SimdStringComparer {
         a.cmp(b)
     }
 
+    /// Offset of the first byte where `a` and `b` differ
+    ///
+    /// See [`SimdMemoryOps::first_difference`].
+    pub fn first_difference(&self, a: &[u8], b: &[u8]) -> Option<usize> {
+        SimdMemoryOps::with_config(self.config.clone()).first_difference(a, b)
+    }
+
     /// AVX2 implementation of string comparison
     #[cfg(target_arch = "x86_64")]
     #[target_feature(enable = "avx2")]
@@ -2028,7 +2825,8 @@ impl SimdStringComparer {
             let mask = _mm_movemask_epi8(cmp) as u32;
 
             if mask != 0xFFFF {
-                let diff_pos = mask.trailing_zeros() as usize;
+                // Find the first differing byte
+                let diff_pos = (!mask).trailing_zeros() as usize;
                 let a_byte = *a.get(pos + diff_pos)?;
                 let b_byte = *b.get(pos + diff_pos)?;
                 return Some(a_byte.cmp(&b_byte));
@@ -2105,113 +2903,637 @@ impl SimdMultiPatternSearcher {
         self.find_all_bit_parallel(text, max_len)
     }
 
-    /// Find all patterns using bit-parallel (Shift-Or) algorithm
-    fn find_all_bit_parallel(&self, text: &[u8], _max_len: usize) -> Vec<(usize, usize)> {
-        let mut matches = Vec::new();
-        let mut state = 0xFFFFFFFFFFFFFFFFu64;
-
-        for (pos, &byte) in text.iter().enumerate() {
-            // Shift-Or: update state by shifting left and OR-ing with character mask
-            state = (state << 1) | self.mask[byte as usize];
+    /// Find all patterns using bit-parallel (Shift-Or) algorithm
+    fn find_all_bit_parallel(&self, text: &[u8], _max_len: usize) -> Vec<(usize, usize)> {
+        let mut matches = Vec::new();
+        let mut state = 0xFFFFFFFFFFFFFFFFu64;
+
+        for (pos, &byte) in text.iter().enumerate() {
+            // Shift-Or: update state by shifting left and OR-ing with character mask
+            state = (state << 1) | self.mask[byte as usize];
+
+            // Check for matches (terminal bit set means a pattern ended here)
+            for (pattern_idx, pattern) in self.patterns.iter().enumerate() {
+                let pattern_bit = 1u64 << (pattern.len() - 1);
+                if state & pattern_bit == 0 {
+                    // Make sure we have enough characters for the pattern
+                    if pos + 1 >= pattern.len() {
+                        matches.push((pattern_idx, pos + 1 - pattern.len()));
+                    }
+                }
+            }
+        }
+
+        matches
+    }
+
+    /// SIMD-accelerated single pattern search
+    #[cfg(target_arch = "x86_64")]
+    fn find_single_pattern_simd(&self, text: &[u8], pattern: &[u8]) -> Option<usize> {
+        if !self.config.enabled || text.len() < 256 || pattern.len() < 2 {
+            return text.windows(pattern.len()).position(|w| w == pattern);
+        }
+
+        if pattern.len() == 1 {
+            if is_x86_feature_detected!("avx2") {
+                return unsafe { self.find_byte_avx2(text, pattern[0]) };
+            }
+            if is_x86_feature_detected!("sse2") {
+                return unsafe { self.find_byte_sse2(text, pattern[0]) };
+            }
+        }
+
+        text.windows(pattern.len()).position(|w| w == pattern)
+    }
+
+    /// Non-x86 fallback for single pattern search
+    #[cfg(not(target_arch = "x86_64"))]
+    fn find_single_pattern_simd(&self, text: &[u8], pattern: &[u8]) -> Option<usize> {
+        text.windows(pattern.len()).position(|w| w == pattern)
+    }
+
+    /// AVX2 byte search
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn find_byte_avx2(&self, text: &[u8], byte: u8) -> Option<usize> {
+        const VECTOR_SIZE: usize = 32;
+        let mut pos = 0;
+
+        while pos + VECTOR_SIZE <= text.len() {
+            let ptr = text.as_ptr().add(pos) as *const __m256i;
+            let vec_data = _mm256_loadu_si256(ptr);
+            let needle_vec = _mm256_set1_epi8(byte as i8);
+            let cmp = _mm256_cmpeq_epi8(vec_data, needle_vec);
+            let mask = _mm256_movemask_epi8(cmp) as u32;
+
+            if mask != 0 {
+                let trailing = mask.trailing_zeros() as usize;
+                return Some(pos + trailing);
+            }
+
+            pos += VECTOR_SIZE;
+        }
+
+        text[pos..].iter().position(|&b| b == byte).map(|p| pos + p)
+    }
+
+    /// SSE2 byte search
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "sse2")]
+    unsafe fn find_byte_sse2(&self, text: &[u8], byte: u8) -> Option<usize> {
+        const VECTOR_SIZE: usize = 16;
+        let mut pos = 0;
+
+        while pos + VECTOR_SIZE <= text.len() {
+            let ptr = text.as_ptr().add(pos) as *const __m128i;
+            let vec_data = _mm_loadu_si128(ptr);
+            let needle_vec = _mm_set1_epi8(byte as i8);
+            let cmp = _mm_cmpeq_epi8(vec_data, needle_vec);
+            let mask = _mm_movemask_epi8(cmp) as u32;
+
+            if mask != 0 {
+                let trailing = mask.trailing_zeros() as usize;
+                return Some(pos + trailing);
+            }
+
+            pos += VECTOR_SIZE;
+        }
+
+        text[pos..].iter().position(|&b| b == byte).map(|p| pos + p)
+    }
+
+    /// Get the number of patterns being searched
+    pub fn pattern_count(&self) -> usize {
+        self.patterns.len()
+    }
+}
+
+/// Largest byte class searched with vector compares; bigger classes use a
+/// lookup table one byte at a time
+pub const MAX_SIMD_BYTE_CLASS: usize = 8;
+
+/// SIMD search for the first occurrence of any byte in a small set
+///
+/// Finds delimiters in one pass instead of one pass per byte: `\n` and `\r`
+/// for CRLF handling, `,` `"` `\n` for CSV field splitting, `\0` for binary
+/// detection. Each block is compared against every byte of the class and
+/// the results are OR-ed, so classes of up to [`MAX_SIMD_BYTE_CLASS`] bytes
+/// cost a few instructions per 16 or 32 bytes.
+#[derive(Debug, Clone)]
+pub struct SimdByteClassSearcher {
+    bytes: Vec<u8>,
+    table: [bool; 256],
+    config: SimdConfig,
+}
+
+impl SimdByteClassSearcher {
+    /// Create a searcher for any of `bytes` with auto-detected capabilities
+    pub fn new(bytes: &[u8]) -> Self {
+        Self::with_config(bytes, SimdConfig::detect())
+    }
+
+    /// Create a searcher for any of `bytes` with explicit configuration
+    pub fn with_config(bytes: &[u8], config: SimdConfig) -> Self {
+        let mut table = [false; 256];
+        let mut unique = Vec::new();
+        for &byte in bytes {
+            if !table[byte as usize] {
+                table[byte as usize] = true;
+                unique.push(byte);
+            }
+        }
+        Self {
+            bytes: unique,
+            table,
+            config,
+        }
+    }
+
+    /// The bytes searched for, without duplicates
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Whether `byte` is in the class
+    pub fn contains(&self, byte: u8) -> bool {
+        self.table[byte as usize]
+    }
+
+    /// Offset of the first byte in `haystack` that is in the class
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        let use_simd = self.config.enabled
+            && !self.bytes.is_empty()
+            && self.bytes.len() <= MAX_SIMD_BYTE_CLASS
+            && haystack.len() >= 16;
+        if !use_simd {
+            return self.find_scalar(haystack, 0);
+        }
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return unsafe { self.find_avx2(haystack) };
+            }
+            if is_x86_feature_detected!("sse2") {
+                return unsafe { self.find_sse2(haystack) };
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                return unsafe { self.find_neon(haystack) };
+            }
+        }
+
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        {
+            unsafe { self.find_simd128(haystack) }
+        }
+        #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+        {
+            self.find_scalar(haystack, 0)
+        }
+    }
+
+    /// Offset of the first byte at or after `start` that is in the class
+    pub fn find_from(&self, haystack: &[u8], start: usize) -> Option<usize> {
+        let rest = haystack.get(start..)?;
+        self.find(rest).map(|pos| start + pos)
+    }
+
+    /// Offsets of every byte in `haystack` that is in the class, in order
+    pub fn find_iter<'a>(&'a self, haystack: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        let mut start = 0;
+        std::iter::from_fn(move || {
+            let pos = self.find_from(haystack, start)?;
+            start = pos + 1;
+            Some(pos)
+        })
+    }
+
+    fn find_scalar(&self, haystack: &[u8], start: usize) -> Option<usize> {
+        haystack[start..]
+            .iter()
+            .position(|&b| self.table[b as usize])
+            .map(|p| start + p)
+    }
+
+    /// AVX2 byte class search
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn find_avx2(&self, haystack: &[u8]) -> Option<usize> {
+        const VECTOR_SIZE: usize = 32;
+        let mut needles = [_mm256_setzero_si256(); MAX_SIMD_BYTE_CLASS];
+        for (needle, &byte) in needles.iter_mut().zip(&self.bytes) {
+            *needle = _mm256_set1_epi8(byte as i8);
+        }
+        let needles = &needles[..self.bytes.len()];
+        let mut pos = 0;
+
+        while pos + VECTOR_SIZE <= haystack.len() {
+            let data = _mm256_loadu_si256(haystack.as_ptr().add(pos) as *const __m256i);
+            let mut hits = _mm256_setzero_si256();
+            for &needle in needles {
+                hits = _mm256_or_si256(hits, _mm256_cmpeq_epi8(data, needle));
+            }
+
+            let mask = _mm256_movemask_epi8(hits) as u32;
+            if mask != 0 {
+                return Some(pos + mask.trailing_zeros() as usize);
+            }
+
+            pos += VECTOR_SIZE;
+        }
+
+        self.find_scalar(haystack, pos)
+    }
+
+    /// SSE2 byte class search
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "sse2")]
+    unsafe fn find_sse2(&self, haystack: &[u8]) -> Option<usize> {
+        const VECTOR_SIZE: usize = 16;
+        let mut needles = [_mm_setzero_si128(); MAX_SIMD_BYTE_CLASS];
+        for (needle, &byte) in needles.iter_mut().zip(&self.bytes) {
+            *needle = _mm_set1_epi8(byte as i8);
+        }
+        let needles = &needles[..self.bytes.len()];
+        let mut pos = 0;
+
+        while pos + VECTOR_SIZE <= haystack.len() {
+            let data = _mm_loadu_si128(haystack.as_ptr().add(pos) as *const __m128i);
+            let mut hits = _mm_setzero_si128();
+            for &needle in needles {
+                hits = _mm_or_si128(hits, _mm_cmpeq_epi8(data, needle));
+            }
+
+            let mask = _mm_movemask_epi8(hits) as u32;
+            if mask != 0 {
+                return Some(pos + mask.trailing_zeros() as usize);
+            }
+
+            pos += VECTOR_SIZE;
+        }
+
+        self.find_scalar(haystack, pos)
+    }
+
+    /// NEON byte class search
+    ///
+    /// NEON has no movemask; narrowing each 16-bit lane of the compare
+    /// result by 4 bits gives a 64-bit mask with 4 bits per byte.
+    #[cfg(target_arch = "aarch64")]
+    #[target_feature(enable = "neon")]
+    unsafe fn find_neon(&self, haystack: &[u8]) -> Option<usize> {
+        use std::arch::aarch64::*;
+
+        const VECTOR_SIZE: usize = 16;
+        let mut needles = [vdupq_n_u8(0); MAX_SIMD_BYTE_CLASS];
+        for (needle, &byte) in needles.iter_mut().zip(&self.bytes) {
+            *needle = vdupq_n_u8(byte);
+        }
+        let needles = &needles[..self.bytes.len()];
+        let mut pos = 0;
+
+        while pos + VECTOR_SIZE <= haystack.len() {
+            let data = vld1q_u8(haystack.as_ptr().add(pos));
+            let mut hits = vdupq_n_u8(0);
+            for &needle in needles {
+                hits = vorrq_u8(hits, vceqq_u8(data, needle));
+            }
+
+            if vmaxvq_u8(hits) != 0 {
+                let narrowed = vshrn_n_u16::<4>(vreinterpretq_u16_u8(hits));
+                let mask = vget_lane_u64::<0>(vreinterpret_u64_u8(narrowed));
+                return Some(pos + (mask.trailing_zeros() / 4) as usize);
+            }
+
+            pos += VECTOR_SIZE;
+        }
+
+        self.find_scalar(haystack, pos)
+    }
+
+    /// SIMD128 byte class search
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    unsafe fn find_simd128(&self, haystack: &[u8]) -> Option<usize> {
+        use std::arch::wasm32::*;
+
+        const VECTOR_SIZE: usize = 16;
+        let mut needles = [u8x16_splat(0); MAX_SIMD_BYTE_CLASS];
+        for (needle, &byte) in needles.iter_mut().zip(&self.bytes) {
+            *needle = u8x16_splat(byte);
+        }
+        let needles = &needles[..self.bytes.len()];
+        let mut pos = 0;
+
+        while pos + VECTOR_SIZE <= haystack.len() {
+            let data = v128_load(haystack.as_ptr().add(pos) as *const v128);
+            let mut hits = u8x16_splat(0);
+            for &needle in needles {
+                hits = v128_or(hits, u8x16_eq(data, needle));
+            }
+
+            let mask = u8x16_bitmask(hits);
+            if mask != 0 {
+                return Some(pos + mask.trailing_zeros() as usize);
+            }
+
+            pos += VECTOR_SIZE;
+        }
+
+        self.find_scalar(haystack, pos)
+    }
+}
+
+/// Encoding of a run found by [`SimdPrintableScanner`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrintableEncoding {
+    /// Printable ASCII bytes
+    Ascii,
+    /// Printable ASCII characters each followed by a zero byte, as in
+    /// Windows wide strings
+    Utf16Le,
+}
+
+impl PrintableEncoding {
+    /// Name used in JSONL output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrintableEncoding::Ascii => "ascii",
+            PrintableEncoding::Utf16Le => "utf16le",
+        }
+    }
+}
+
+/// A run of printable characters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrintableRun {
+    /// Byte range of the run in the scanned data
+    pub range: Range<usize>,
+    /// How the characters are encoded
+    pub encoding: PrintableEncoding,
+}
+
+impl PrintableRun {
+    /// Number of characters in the run
+    pub fn chars(&self) -> usize {
+        match self.encoding {
+            PrintableEncoding::Ascii => self.range.len(),
+            PrintableEncoding::Utf16Le => self.range.len() / 2,
+        }
+    }
+
+    /// The run's characters, from the data it was found in
+    pub fn text(&self, data: &[u8]) -> String {
+        let bytes = &data[self.range.clone()];
+        match self.encoding {
+            PrintableEncoding::Ascii => bytes.iter().map(|&b| b as char).collect(),
+            PrintableEncoding::Utf16Le => bytes.iter().step_by(2).map(|&b| b as char).collect(),
+        }
+    }
+}
+
+/// Bytes classified per step of [`SimdPrintableScanner`]
+const PRINTABLE_BLOCK: usize = 64;
+
+/// Bits of a block mask at even offsets
+const EVEN_LANES: u64 = 0x5555_5555_5555_5555;
+
+/// SIMD extraction of printable string runs from binary data
+///
+/// Printable means `0x20..=0x7e` plus tab, as in `strings`. Each 64-byte
+/// block is classified into a printable bitmask and a zero-byte bitmask
+/// with a few vector compares. The positions that cannot be part of a run
+/// ("breaks") are then walked with trailing-zero counts, and a run is
+/// reported wherever two breaks are far enough apart, so fully printable
+/// and fully binary blocks cost a single check. UTF-16LE characters are a
+/// printable byte followed by a zero byte; runs starting at even and odd
+/// offsets are tracked separately.
+#[derive(Debug, Clone)]
+pub struct SimdPrintableScanner {
+    config: SimdConfig,
+}
+
+impl SimdPrintableScanner {
+    /// Create a new scanner with auto-detected capabilities
+    pub fn new() -> Self {
+        Self {
+            config: SimdConfig::detect(),
+        }
+    }
+
+    /// Create a new scanner with explicit configuration
+    pub fn with_config(config: SimdConfig) -> Self {
+        Self { config }
+    }
 
-            // Check for matches (terminal bit set means a pattern ended here)
-            for (pattern_idx, pattern) in self.patterns.iter().enumerate() {
-                let pattern_bit = 1u64 << (pattern.len() - 1);
-                if state & pattern_bit == 0 {
-                    // Make sure we have enough characters for the pattern
-                    if pos + 1 >= pattern.len() {
-                        matches.push((pattern_idx, pos + 1 - pattern.len()));
-                    }
+    /// Byte ranges of ASCII runs of at least `min_len` characters
+    pub fn ascii_runs(&self, data: &[u8], min_len: usize) -> Vec<Range<usize>> {
+        self.find_runs(data, min_len, &[PrintableEncoding::Ascii])
+            .into_iter()
+            .map(|run| run.range)
+            .collect()
+    }
+
+    /// Runs of at least `min_chars` characters in any of `encodings`,
+    /// ordered by offset
+    pub fn find_runs(&self, data: &[u8], min_chars: usize, encodings: &[PrintableEncoding]) -> Vec<PrintableRun> {
+        let min_chars = min_chars.max(1);
+        let ascii = encodings.contains(&PrintableEncoding::Ascii);
+        let wide = encodings.contains(&PrintableEncoding::Utf16Le);
+        let mut runs = Vec::new();
+
+        // Where the run in progress started: ASCII, and UTF-16LE at even
+        // and odd offsets
+        let mut ascii_start = 0;
+        let mut wide_start = [0, 1];
+        // UTF-16LE needs the next block's first byte, so blocks are
+        // finished one behind
+        let mut pending: Option<(usize, u64, u64)> = None;
+
+        for base in (0..data.len()).step_by(PRINTABLE_BLOCK) {
+            let block = &data[base..(base + PRINTABLE_BLOCK).min(data.len())];
+            let (printable, zero) = self.classify(block);
+            if ascii {
+                let scan = BreakScan { base, stride: 1, min_chars, encoding: PrintableEncoding::Ascii };
+                scan.walk(!printable, u64::MAX, &mut ascii_start, &mut runs);
+            }
+            if wide {
+                if let Some(block) = pending {
+                    Self::finish_wide_block(block, zero & 1 != 0, min_chars, &mut wide_start, &mut runs);
                 }
+                pending = Some((base, printable, zero));
             }
         }
 
-        matches
-    }
+        // Close runs that reach the end of the data
+        let len = data.len();
+        if ascii {
+            let scan = BreakScan { base: 0, stride: 1, min_chars, encoding: PrintableEncoding::Ascii };
+            scan.close(len, &mut ascii_start, &mut runs);
+        }
+        if wide {
+            if let Some(block) = pending {
+                Self::finish_wide_block(block, false, min_chars, &mut wide_start, &mut runs);
+            }
+            let scan = BreakScan { base: 0, stride: 2, min_chars, encoding: PrintableEncoding::Utf16Le };
+            for (parity, start) in wide_start.iter_mut().enumerate() {
+                // The first offset of this parity with no room for a character
+                let end = if len % 2 != parity { len.saturating_sub(1) } else { len };
+                scan.close(end, start, &mut runs);
+            }
+        }
 
-    /// SIMD-accelerated single pattern search
-    #[cfg(target_arch = "x86_64")]
-    fn find_single_pattern_simd(&self, text: &[u8], pattern: &[u8]) -> Option<usize> {
-        if !self.config.enabled || text.len() < 256 || pattern.len() < 2 {
-            return text.windows(pattern.len()).position(|w| w == pattern);
+        if ascii && wide {
+            runs.sort_by_key(|run| run.range.start);
         }
+        runs
+    }
 
-        if pattern.len() == 1 {
-            if is_x86_feature_detected!("avx2") {
-                return unsafe { self.find_byte_avx2(text, pattern[0]) };
-            }
-            if is_x86_feature_detected!("sse2") {
-                return unsafe { self.find_byte_sse2(text, pattern[0]) };
+    /// Walk the UTF-16LE breaks of a classified block
+    fn finish_wide_block(
+        (base, printable, zero): (usize, u64, u64),
+        next_zero: bool,
+        min_chars: usize,
+        starts: &mut [usize; 2],
+        runs: &mut Vec<PrintableRun>,
+    ) {
+        // A character at bit i needs bit i printable and bit i + 1 zero
+        let chars = printable & ((zero >> 1) | ((next_zero as u64) << 63));
+        let scan = BreakScan { base, stride: 2, min_chars, encoding: PrintableEncoding::Utf16Le };
+        scan.walk(!chars & EVEN_LANES, EVEN_LANES, &mut starts[0], runs);
+        scan.walk(!chars & !EVEN_LANES, !EVEN_LANES, &mut starts[1], runs);
+    }
+
+    /// Printable and zero-byte bitmasks of a block of up to 64 bytes
+    ///
+    /// Bits past the end of a short block are clear in both masks.
+    fn classify(&self, block: &[u8]) -> (u64, u64) {
+        if self.config.enabled && block.len() == PRINTABLE_BLOCK {
+            #[cfg(target_arch = "x86_64")]
+            {
+                if is_x86_feature_detected!("avx2") {
+                    return unsafe { Self::classify_avx2(block) };
+                }
+                if is_x86_feature_detected!("sse2") {
+                    return unsafe { Self::classify_sse2(block) };
+                }
             }
         }
 
-        text.windows(pattern.len()).position(|w| w == pattern)
+        Self::classify_scalar(block)
     }
 
-    /// Non-x86 fallback for single pattern search
-    #[cfg(not(target_arch = "x86_64"))]
-    fn find_single_pattern_simd(&self, text: &[u8], pattern: &[u8]) -> Option<usize> {
-        text.windows(pattern.len()).position(|w| w == pattern)
+    fn classify_scalar(block: &[u8]) -> (u64, u64) {
+        let mut printable = 0;
+        let mut zero = 0;
+        for (i, &byte) in block.iter().enumerate() {
+            printable |= (((0x20..0x7f).contains(&byte) || byte == b'\t') as u64) << i;
+            zero |= ((byte == 0) as u64) << i;
+        }
+        (printable, zero)
     }
 
-    /// AVX2 byte search
+    /// AVX2 block classification
+    ///
+    /// The compares are signed, so bytes from `0x80` up are negative and
+    /// fail the lower bound.
     #[cfg(target_arch = "x86_64")]
     #[target_feature(enable = "avx2")]
-    unsafe fn find_byte_avx2(&self, text: &[u8], byte: u8) -> Option<usize> {
+    unsafe fn classify_avx2(block: &[u8]) -> (u64, u64) {
         const VECTOR_SIZE: usize = 32;
-        let mut pos = 0;
-
-        while pos + VECTOR_SIZE <= text.len() {
-            let ptr = text.as_ptr().add(pos) as *const __m256i;
-            let vec_data = _mm256_loadu_si256(ptr);
-            let needle_vec = _mm256_set1_epi8(byte as i8);
-            let cmp = _mm256_cmpeq_epi8(vec_data, needle_vec);
-            let mask = _mm256_movemask_epi8(cmp) as u32;
-
-            if mask != 0 {
-                let trailing = mask.trailing_zeros() as usize;
-                return Some(pos + trailing);
-            }
+        let below = _mm256_set1_epi8(0x1f);
+        let above = _mm256_set1_epi8(0x7f);
+        let tab = _mm256_set1_epi8(b'\t' as i8);
+        let zero = _mm256_setzero_si256();
+        let mut printable_mask = 0;
+        let mut zero_mask = 0;
 
-            pos += VECTOR_SIZE;
+        for (i, pos) in (0..PRINTABLE_BLOCK).step_by(VECTOR_SIZE).enumerate() {
+            let data = _mm256_loadu_si256(block.as_ptr().add(pos) as *const __m256i);
+            let in_range = _mm256_and_si256(_mm256_cmpgt_epi8(data, below), _mm256_cmpgt_epi8(above, data));
+            let printable = _mm256_or_si256(in_range, _mm256_cmpeq_epi8(data, tab));
+            printable_mask |= (_mm256_movemask_epi8(printable) as u32 as u64) << (i * VECTOR_SIZE);
+            zero_mask |= (_mm256_movemask_epi8(_mm256_cmpeq_epi8(data, zero)) as u32 as u64) << (i * VECTOR_SIZE);
         }
 
-        text[pos..].iter().position(|&b| b == byte).map(|p| pos + p)
+        (printable_mask, zero_mask)
     }
 
-    /// SSE2 byte search
+    /// SSE2 block classification
     #[cfg(target_arch = "x86_64")]
     #[target_feature(enable = "sse2")]
-    unsafe fn find_byte_sse2(&self, text: &[u8], byte: u8) -> Option<usize> {
+    unsafe fn classify_sse2(block: &[u8]) -> (u64, u64) {
         const VECTOR_SIZE: usize = 16;
-        let mut pos = 0;
+        let below = _mm_set1_epi8(0x1f);
+        let above = _mm_set1_epi8(0x7f);
+        let tab = _mm_set1_epi8(b'\t' as i8);
+        let zero = _mm_setzero_si128();
+        let mut printable_mask = 0;
+        let mut zero_mask = 0;
 
-        while pos + VECTOR_SIZE <= text.len() {
-            let ptr = text.as_ptr().add(pos) as *const __m128i;
-            let vec_data = _mm_loadu_si128(ptr);
-            let needle_vec = _mm_set1_epi8(byte as i8);
-            let cmp = _mm_cmpeq_epi8(vec_data, needle_vec);
-            let mask = _mm_movemask_epi8(cmp) as u32;
+        for (i, pos) in (0..PRINTABLE_BLOCK).step_by(VECTOR_SIZE).enumerate() {
+            let data = _mm_loadu_si128(block.as_ptr().add(pos) as *const __m128i);
+            let in_range = _mm_and_si128(_mm_cmpgt_epi8(data, below), _mm_cmpgt_epi8(above, data));
+            let printable = _mm_or_si128(in_range, _mm_cmpeq_epi8(data, tab));
+            printable_mask |= (_mm_movemask_epi8(printable) as u32 as u64) << (i * VECTOR_SIZE);
+            zero_mask |= (_mm_movemask_epi8(_mm_cmpeq_epi8(data, zero)) as u32 as u64) << (i * VECTOR_SIZE);
+        }
 
-            if mask != 0 {
-                let trailing = mask.trailing_zeros() as usize;
-                return Some(pos + trailing);
-            }
+        (printable_mask, zero_mask)
+    }
+}
 
-            pos += VECTOR_SIZE;
-        }
+impl Default for SimdPrintableScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        text[pos..].iter().position(|&b| b == byte).map(|p| pos + p)
+/// Turns break positions into runs for one encoding and starting parity
+struct BreakScan {
+    base: usize,
+    stride: usize,
+    min_chars: usize,
+    encoding: PrintableEncoding,
+}
+
+impl BreakScan {
+    /// Close runs at the breaks set in a block mask; `lanes` are the bits
+    /// this scan owns
+    fn walk(&self, mut breaks: u64, lanes: u64, start: &mut usize, runs: &mut Vec<PrintableRun>) {
+        if breaks == 0 {
+            return;
+        }
+        if breaks == lanes {
+            // Every lane breaks: only the first can end a run
+            self.close(self.base + breaks.trailing_zeros() as usize, start, runs);
+            *start = self.base + (63 - breaks.leading_zeros()) as usize + self.stride;
+            return;
+        }
+        while breaks != 0 {
+            self.close(self.base + breaks.trailing_zeros() as usize, start, runs);
+            breaks &= breaks - 1;
+        }
     }
 
-    /// Get the number of patterns being searched
-    pub fn pattern_count(&self) -> usize {
-        self.patterns.len()
+    /// Close the run in progress at break offset `at`
+    fn close(&self, at: usize, start: &mut usize, runs: &mut Vec<PrintableRun>) {
+        if at.saturating_sub(*start) / self.stride >= self.min_chars {
+            runs.push(PrintableRun {
+                range: *start..at,
+                encoding: self.encoding,
+            });
+        }
+        *start = at + self.stride;
     }
 }
 
 /// SIMD-optimized text processing utilities
 pub struct SimdTextProcessor {
+    config: SimdConfig,
     pattern_searcher: SimdPatternSearcher,
     byte_counter: SimdByteCounter,
     whitespace_detector: SimdWhitespaceDetector,
@@ -2220,11 +3542,7 @@ pub struct SimdTextProcessor {
 impl SimdTextProcessor {
     /// Create a new SIMD text processor
     pub fn new() -> Self {
-        Self {
-            pattern_searcher: SimdPatternSearcher::new(),
-            byte_counter: SimdByteCounter::new(),
-            whitespace_detector: SimdWhitespaceDetector::new(),
-        }
+        Self::with_config(SimdConfig::detect())
     }
 
     /// Create a new SIMD text processor with explicit configuration
@@ -2232,17 +3550,87 @@ impl SimdTextProcessor {
         Self {
             pattern_searcher: SimdPatternSearcher::with_config(config.clone()),
             byte_counter: SimdByteCounter::with_config(config.clone()),
-            whitespace_detector: SimdWhitespaceDetector::new(),
+            whitespace_detector: SimdWhitespaceDetector::with_config(config.clone()),
+            config,
         }
     }
 
-    /// Count lines, words, and bytes in a single pass
+    /// Count lines, words, bytes and characters, and find the longest line,
+    /// in a single pass
+    ///
+    /// Each block of input is classified once into newline, whitespace and
+    /// UTF-8 continuation byte masks; words are counted at the edges where
+    /// whitespace is followed by anything else.
     pub fn analyze(&self, data: &[u8]) -> TextMetrics {
-        let lines = self.whitespace_detector.count_lines(data);
-        let words = self.whitespace_detector.count_words(data);
-        let bytes = data.len();
+        if !self.config.enabled || data.len() < 64 {
+            return self.analyze_scalar(data);
+        }
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return unsafe { self.analyze_avx2(data) };
+            }
+            if is_x86_feature_detected!("sse2") {
+                return unsafe { self.analyze_sse2(data) };
+            }
+        }
+
+        self.analyze_scalar(data)
+    }
+
+    /// AVX2 text analysis, 64 bytes at a time
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn analyze_avx2(&self, data: &[u8]) -> TextMetrics {
+        const BLOCK_SIZE: usize = 64;
+        let mut scan = TextScan::new();
+        let mut pos = 0;
+
+        while pos + BLOCK_SIZE <= data.len() {
+            let (nl_lo, ws_lo, cont_lo) = text_masks_avx2(data.as_ptr().add(pos));
+            let (nl_hi, ws_hi, cont_hi) = text_masks_avx2(data.as_ptr().add(pos + 32));
+            scan.block(
+                (nl_hi as u64) << 32 | nl_lo as u64,
+                (ws_hi as u64) << 32 | ws_lo as u64,
+                (cont_hi as u64) << 32 | cont_lo as u64,
+                BLOCK_SIZE,
+            );
+            pos += BLOCK_SIZE;
+        }
+
+        scan.scalar(&data[pos..]);
+        scan.finish()
+    }
+
+    /// SSE2 text analysis, 64 bytes at a time
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "sse2")]
+    unsafe fn analyze_sse2(&self, data: &[u8]) -> TextMetrics {
+        const BLOCK_SIZE: usize = 64;
+        let mut scan = TextScan::new();
+        let mut pos = 0;
+
+        while pos + BLOCK_SIZE <= data.len() {
+            let (mut newlines, mut whitespace, mut continuations) = (0u64, 0u64, 0u64);
+            for lane in 0..4 {
+                let (nl, ws, cont) = text_masks_sse2(data.as_ptr().add(pos + lane * 16));
+                newlines |= (nl as u64) << (lane * 16);
+                whitespace |= (ws as u64) << (lane * 16);
+                continuations |= (cont as u64) << (lane * 16);
+            }
+            scan.block(newlines, whitespace, continuations, BLOCK_SIZE);
+            pos += BLOCK_SIZE;
+        }
+
+        scan.scalar(&data[pos..]);
+        scan.finish()
+    }
 
-        TextMetrics { lines, words, bytes }
+    fn analyze_scalar(&self, data: &[u8]) -> TextMetrics {
+        let mut scan = TextScan::new();
+        scan.scalar(data);
+        scan.finish()
     }
 
     /// Get references to internal components
@@ -2267,8 +3655,110 @@ impl Default for SimdTextProcessor {
     }
 }
 
+/// Newline, whitespace and UTF-8 continuation byte masks of the 32
+/// bytes at `ptr`
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+#[inline]
+unsafe fn text_masks_avx2(ptr: *const u8) -> (u32, u32, u32) {
+    let data = _mm256_loadu_si256(ptr as *const __m256i);
+    let newline = _mm256_cmpeq_epi8(data, _mm256_set1_epi8(b'\n' as i8));
+    let whitespace = whitespace_avx2(data);
+    // 0x80..=0xBF are the signed bytes below -64
+    let continuation = _mm256_cmpgt_epi8(_mm256_set1_epi8(-64), data);
+    (
+        _mm256_movemask_epi8(newline) as u32,
+        _mm256_movemask_epi8(whitespace) as u32,
+        _mm256_movemask_epi8(continuation) as u32,
+    )
+}
+
+/// Newline, whitespace and UTF-8 continuation byte masks of the 16
+/// bytes at `ptr`
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+#[inline]
+unsafe fn text_masks_sse2(ptr: *const u8) -> (u16, u16, u16) {
+    let data = _mm_loadu_si128(ptr as *const __m128i);
+    let newline = _mm_cmpeq_epi8(data, _mm_set1_epi8(b'\n' as i8));
+    let whitespace = whitespace_sse2(data);
+    let continuation = _mm_cmplt_epi8(data, _mm_set1_epi8(-64));
+    (
+        _mm_movemask_epi8(newline) as u16,
+        _mm_movemask_epi8(whitespace) as u16,
+        _mm_movemask_epi8(continuation) as u16,
+    )
+}
+
+/// Running state of [`SimdTextProcessor::analyze`], fed blocks of up to 64
+/// bytes as masks with bit `i` for byte `i`
+struct TextScan {
+    pos: usize,
+    lines: usize,
+    words: usize,
+    continuations: usize,
+    line_start: usize,
+    max_line_length: usize,
+    /// Whether the byte before the next block is whitespace; the start of
+    /// the data counts as whitespace
+    after_whitespace: bool,
+}
+
+impl TextScan {
+    fn new() -> Self {
+        Self {
+            pos: 0,
+            lines: 0,
+            words: 0,
+            continuations: 0,
+            line_start: 0,
+            max_line_length: 0,
+            after_whitespace: true,
+        }
+    }
+
+    /// Fold in the next `width` bytes (1 to 64) from their masks
+    #[inline(always)]
+    fn block(&mut self, mut newlines: u64, whitespace: u64, continuations: u64, width: usize) {
+        self.words += word_starts(whitespace, self.after_whitespace, width).count_ones() as usize;
+        self.continuations += continuations.count_ones() as usize;
+        self.lines += newlines.count_ones() as usize;
+        while newlines != 0 {
+            let at = self.pos + newlines.trailing_zeros() as usize;
+            self.max_line_length = self.max_line_length.max(at - self.line_start);
+            self.line_start = at + 1;
+            newlines &= newlines - 1;
+        }
+        self.after_whitespace = whitespace >> (width - 1) & 1 == 1;
+        self.pos += width;
+    }
+
+    /// Classify and fold in bytes without SIMD
+    fn scalar(&mut self, data: &[u8]) {
+        for chunk in data.chunks(64) {
+            let (mut newlines, mut whitespace, mut continuations) = (0u64, 0u64, 0u64);
+            for (i, &byte) in chunk.iter().enumerate() {
+                newlines |= ((byte == b'\n') as u64) << i;
+                whitespace |= (is_space(byte) as u64) << i;
+                continuations |= ((byte & 0xC0 == 0x80) as u64) << i;
+            }
+            self.block(newlines, whitespace, continuations, chunk.len());
+        }
+    }
+
+    fn finish(self) -> TextMetrics {
+        TextMetrics {
+            lines: self.lines,
+            words: self.words,
+            bytes: self.pos,
+            chars: self.pos - self.continuations,
+            max_line_length: self.max_line_length.max(self.pos - self.line_start),
+        }
+    }
+}
+
 /// Text metrics result
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TextMetrics {
     /// Number of lines
     pub lines: usize,
@@ -2276,6 +3766,10 @@ pub struct TextMetrics {
     pub words: usize,
     /// Number of bytes
     pub bytes: usize,
+    /// Number of UTF-8 characters: bytes that are not continuation bytes
+    pub chars: usize,
+    /// Length in bytes of the longest line, not counting its newline
+    pub max_line_length: usize,
 }
 
 #[cfg(test)]
@@ -2291,6 +3785,48 @@ mod tests {
         assert_eq!(searcher.find_first(haystack, needle), Some(6));
     }
 
+    #[test]
+    fn test_pattern_searcher_rfind() {
+        let searcher = SimdPatternSearcher::new();
+        let scalar = SimdPatternSearcher::with_config(SimdConfig {
+            enabled: false,
+            ..SimdConfig::detect()
+        });
+        assert_eq!(searcher.rfind_first(b"Hello World! Hello again!", b"Hello"), Some(13));
+        assert_eq!(searcher.rfind_first(b"aaaa", b"aa"), Some(2));
+        assert_eq!(searcher.rfind_first(b"abc", b""), Some(3));
+        assert_eq!(searcher.rfind_first(b"ab", b"abc"), None);
+
+        let mut haystack = b"/var/log/app/".repeat(40);
+        haystack.extend_from_slice(b"current.log");
+        for len in 0..=haystack.len() {
+            let data = &haystack[..len];
+            let expected = data.iter().rposition(|&b| b == b'/');
+            assert_eq!(searcher.rfind_byte(data, b'/'), expected, "len {}", len);
+            assert_eq!(scalar.rfind_byte(data, b'/'), expected);
+            let expected = data.windows(4).rposition(|w| w == b"log/");
+            assert_eq!(searcher.rfind_first(data, b"log/"), expected, "len {}", len);
+        }
+        assert_eq!(searcher.rfind_byte(&haystack, b'#'), None);
+        assert_eq!(searcher.rfind_first(&haystack, b"log/x"), None);
+    }
+
+    #[test]
+    fn test_pattern_searcher_overlapping() {
+        let searcher = SimdPatternSearcher::new();
+        assert_eq!(searcher.find_all(b"aaaa", b"aa"), vec![0, 2]);
+        assert_eq!(searcher.find_all_overlapping(b"aaaa", b"aa"), vec![0, 1, 2]);
+        assert_eq!(searcher.find_all(b"abababa", b"aba"), vec![0, 4]);
+        assert_eq!(searcher.find_all_overlapping(b"abababa", b"aba"), vec![0, 2, 4]);
+
+        // Long haystacks take the SIMD path
+        let haystack = [b'a'; 1000];
+        assert_eq!(searcher.find_all(&haystack, b"aaa").len(), 333);
+        assert_eq!(searcher.find_all_overlapping(&haystack, b"aaa"), (0..998).collect::<Vec<_>>());
+        assert_eq!(searcher.find_all_overlapping(&haystack, b"a").len(), 1000);
+        assert_eq!(searcher.find_all_overlapping(b"ab", b""), vec![0, 1, 2]);
+    }
+
     #[test]
     fn test_pattern_searcher_find_all() {
         let searcher = SimdPatternSearcher::new();
@@ -2327,6 +3863,57 @@ mod tests {
         assert_eq!(detector.count_words(data), 6);
     }
 
+    #[test]
+    fn test_whitespace_detector_skip_and_trim() {
+        let detector = SimdWhitespaceDetector::new();
+        let scalar = SimdWhitespaceDetector::with_config(SimdConfig {
+            enabled: false,
+            ..SimdConfig::detect()
+        });
+
+        for pad in 0..70 {
+            let mut data = b" \t\n\x0B\x0C\r".repeat(12)[..pad].to_vec();
+            data.extend_from_slice(b"a b");
+            data.extend(b"\r\n \t".repeat(20)[..pad].iter());
+
+            assert_eq!(detector.skip_whitespace(&data, 0), pad);
+            assert_eq!(detector.rskip_whitespace(&data, data.len()), pad + 3);
+            assert_eq!(detector.trim_slice(&data), b"a b");
+            for i in 0..=data.len() {
+                assert_eq!(detector.skip_whitespace(&data, i), scalar.skip_whitespace(&data, i));
+                assert_eq!(detector.rskip_whitespace(&data, i), scalar.rskip_whitespace(&data, i));
+            }
+        }
+
+        let blank = [b' '; 100];
+        assert_eq!(detector.skip_whitespace(&blank, 3), 100);
+        assert_eq!(detector.rskip_whitespace(&blank, 100), 0);
+        assert_eq!(detector.trim_slice(&blank), b"");
+        assert_eq!(detector.skip_whitespace(b"ab", 5), 2);
+    }
+
+    #[test]
+    fn test_whitespace_detector_count_words_matches_scalar() {
+        let simd = SimdWhitespaceDetector::new();
+        let scalar = SimdWhitespaceDetector::with_config(SimdConfig {
+            enabled: false,
+            ..SimdConfig::detect()
+        });
+        let mut data = Vec::new();
+        for i in 0..100 {
+            // Words of every length, so they straddle vector boundaries
+            data.extend(std::iter::repeat_n(b'w', i % 37));
+            data.push(b" \t\n\x0B\x0C\r"[i % 6]);
+        }
+
+        for len in 0..data.len() {
+            assert_eq!(simd.count_words(&data[..len]), scalar.count_words(&data[..len]), "len {}", len);
+            assert_eq!(simd.count_words(&data[len..]), scalar.count_words(&data[len..]), "from {}", len);
+        }
+        assert_eq!(simd.count_words(&data), data.split(|&b| is_space(b)).filter(|w| !w.is_empty()).count());
+        assert_eq!(simd.count_words(b"a\x0Bb"), 2);
+    }
+
     #[test]
     fn test_text_processor_analyze() {
         let processor = SimdTextProcessor::new();
@@ -2336,6 +3923,37 @@ mod tests {
         assert_eq!(metrics.lines, 2);
         assert_eq!(metrics.words, 6);
         assert_eq!(metrics.bytes, 27); // "Hello world\nThis is a test\n" = 27 bytes
+        assert_eq!(metrics.chars, 27);
+        assert_eq!(metrics.max_line_length, 14);
+    }
+
+    #[test]
+    fn test_text_processor_analyze_matches_scalar() {
+        let simd = SimdTextProcessor::new();
+        let scalar = SimdTextProcessor::with_config(SimdConfig {
+            enabled: false,
+            ..SimdConfig::detect()
+        });
+        let mut data = Vec::new();
+        for i in 0..200 {
+            data.extend_from_slice(" word\tcafé ".repeat(i % 7).as_bytes());
+            data.extend_from_slice(if i % 3 == 0 { b"\r\n" } else { b"\n" });
+        }
+        data.extend_from_slice(b"no trailing newline");
+
+        // Every block boundary, and tails of every length
+        for len in (0..data.len()).step_by(13).chain([data.len()]) {
+            for start in 0..3 {
+                let slice = &data[start..len.max(start)];
+                assert_eq!(simd.analyze(slice), scalar.analyze(slice), "{}..{}", start, len);
+            }
+        }
+
+        let metrics = simd.analyze(&data);
+        assert_eq!(metrics.lines, 200);
+        assert_eq!(metrics.words, simd.whitespace_detector().count_words(&data));
+        assert_eq!(metrics.chars, String::from_utf8_lossy(&data).chars().count());
+        assert_eq!(metrics.max_line_length, " word\tcafé ".len() * 6 + 1);
     }
 
     #[test]
@@ -2438,6 +4056,44 @@ mod tests {
         assert_eq!(result.len(), 10);
     }
 
+    #[test]
+    fn test_newline_counter_matches_scalar() {
+        let counter = SimdNewlineCounter::new();
+        let scalar = SimdNewlineCounter::with_config(SimdConfig {
+            enabled: false,
+            ..SimdConfig::detect()
+        });
+        // Lines of every length, with runs of newlines within one vector
+        let mut data = Vec::new();
+        for i in 0..120 {
+            data.extend(std::iter::repeat_n(b'x', i % 41));
+            data.extend(std::iter::repeat_n(b'\n', 1 + i % 3));
+        }
+        let expected: Vec<usize> = (0..data.len()).filter(|&i| data[i] == b'\n').collect();
+
+        for len in (0..=data.len()).step_by(7) {
+            let data = &data[..len];
+            let newlines: Vec<usize> = expected.iter().copied().filter(|&i| i < len).collect();
+            assert_eq!(counter.positions(data).collect::<Vec<_>>(), newlines);
+            assert_eq!(scalar.positions(data).collect::<Vec<_>>(), newlines);
+            assert_eq!(counter.count(data), newlines.len());
+            for n in [1, 2, 3, 17, newlines.len().max(1), newlines.len() + 1] {
+                assert_eq!(counter.find_nth_newline(data, n), newlines.get(n - 1).copied());
+                assert_eq!(
+                    counter.find_last_n_newlines(data, n),
+                    scalar.find_last_n_newlines(data, n),
+                    "len {} n {}",
+                    len,
+                    n
+                );
+                assert_eq!(counter.find_last_n_newlines(data, n), newlines[newlines.len().saturating_sub(n)..]);
+            }
+        }
+
+        // Iteration stops scanning where the caller stops
+        assert_eq!(counter.positions(&data).nth(4), Some(expected[4]));
+    }
+
     #[test]
     fn test_memory_ops_copy() {
         let mem_ops = SimdMemoryOps::new();
@@ -2460,6 +4116,24 @@ mod tests {
         assert_eq!(dst, src);
     }
 
+    #[test]
+    fn test_memory_ops_copy_non_temporal() {
+        let mem_ops = SimdMemoryOps::new();
+        let len = NON_TEMPORAL_COPY_THRESHOLD + 1000;
+        let src: Vec<u8> = (0..251).cycle().take(len + 64).collect();
+        let mut dst = vec![0u8; len + 64];
+
+        // Misaligned source and destination exercise the head and tail
+        for (src_offset, dst_offset) in [(0, 0), (3, 17), (31, 1)] {
+            let n = len - src_offset.max(dst_offset);
+            let copied = mem_ops
+                .copy(&mut dst[dst_offset..dst_offset + n], &src[src_offset..src_offset + n])
+                .unwrap();
+            assert_eq!(copied, n);
+            assert!(dst[dst_offset..dst_offset + n] == src[src_offset..src_offset + n]);
+        }
+    }
+
     #[test]
     fn test_memory_ops_compare_equal() {
         let mem_ops = SimdMemoryOps::new();
@@ -2500,6 +4174,68 @@ mod tests {
         assert_eq!(mem_ops.compare(&a, &b), std::cmp::Ordering::Less);
     }
 
+    #[test]
+    fn test_memory_ops_first_difference() {
+        let mem_ops = SimdMemoryOps::new();
+        let scalar = SimdMemoryOps::with_config(SimdConfig { enabled: false, vector_width: 1 });
+        let a: Vec<u8> = (0..251).cycle().take(1000).collect();
+
+        assert_eq!(mem_ops.first_difference(&a, &a), None);
+        assert_eq!(mem_ops.first_difference(b"", b""), None);
+        // A prefix differs where the shorter slice ends
+        assert_eq!(mem_ops.first_difference(&a, &a[..700]), Some(700));
+        assert_eq!(mem_ops.first_difference(b"", b"x"), Some(0));
+
+        // Every offset, including both ends of vector blocks and the tail
+        for pos in [0, 1, 15, 16, 31, 32, 63, 64, 500, 990, 999] {
+            let mut b = a.clone();
+            b[pos] ^= 0x80;
+            assert_eq!(mem_ops.first_difference(&a, &b), Some(pos));
+            assert_eq!(scalar.first_difference(&a, &b), Some(pos));
+            assert_eq!(SimdStringComparer::new().first_difference(&b, &a), Some(pos));
+        }
+    }
+
+    #[test]
+    fn test_byte_class_searcher() {
+        let scalar = SimdConfig { enabled: false, vector_width: 1 };
+        let mut text = b"field one,field two;".repeat(10);
+        text.extend_from_slice(b"tail\r\n");
+
+        for searcher in [
+            SimdByteClassSearcher::new(b"\r\n\0"),
+            SimdByteClassSearcher::with_config(b"\r\n\0", scalar.clone()),
+        ] {
+            assert_eq!(searcher.find(&text), Some(text.len() - 2));
+            assert_eq!(searcher.find(b"no delimiters here at all, none"), None);
+            assert_eq!(searcher.find(b""), None);
+        }
+
+        // Duplicates are ignored; every match is found in order
+        let csv = SimdByteClassSearcher::new(b",;,");
+        assert_eq!(csv.bytes(), b",;");
+        assert!(csv.contains(b';') && !csv.contains(b'x'));
+        let expected: Vec<usize> = (0..text.len())
+            .filter(|&i| text[i] == b',' || text[i] == b';')
+            .collect();
+        assert_eq!(csv.find_iter(&text).collect::<Vec<_>>(), expected);
+        assert_eq!(csv.find_from(&text, 10), Some(19));
+        assert_eq!(csv.find_from(&text, text.len() + 1), None);
+
+        // Every position within and across vector blocks, and classes too
+        // large for the vector path
+        let class: Vec<u8> = (b'A'..=b'L').collect();
+        let large = SimdByteClassSearcher::new(&class);
+        let small = SimdByteClassSearcher::new(&class[..MAX_SIMD_BYTE_CLASS]);
+        for pos in [0, 1, 15, 16, 31, 32, 33, 95, 99] {
+            let mut data = vec![b'a'; 100];
+            data[pos] = b'C';
+            assert_eq!(large.find(&data), Some(pos));
+            assert_eq!(small.find(&data), Some(pos));
+        }
+        assert_eq!(SimdByteClassSearcher::new(b"").find(&text), None);
+    }
+
     #[test]
     fn test_memory_ops_fill() {
         let mem_ops = SimdMemoryOps::new();
@@ -2540,6 +4276,19 @@ mod tests {
         assert_eq!(crc1, crc2); // Should be deterministic
     }
 
+    #[test]
+    fn test_hasher_known_values() {
+        let hasher = SimdHasher::new();
+        // CRC-32/IEEE check value, on every code path
+        assert_eq!(hasher.crc32(b"123456789"), 0xCBF4_3926);
+        let long = b"123456789".repeat(20);
+        assert_eq!(hasher.crc32(&long), hasher.crc32_scalar(0, &long));
+        let (head, tail) = long.split_at(100);
+        assert_eq!(hasher.crc32_update(hasher.crc32(head), tail), hasher.crc32(&long));
+        assert_eq!(hasher.xxh3(b""), 0x2D06_8005_38D3_94C2);
+        assert_eq!(hasher.xxh64(b"", 0), 0xEF46_DB37_51D8_E999);
+    }
+
     #[test]
     fn test_hasher_rolling_hash() {
         let hasher = SimdHasher::new();
@@ -2598,6 +4347,21 @@ mod tests {
         assert_eq!(entropy, 0.0);
     }
 
+    #[test]
+    fn test_high_entropy_windows() {
+        let calc = SimdEntropyCalculator::new();
+        let data = b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaa0123456789ABCDEFGHIJbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+        let ranges = calc.high_entropy_windows(data, 20, 4.3);
+        // The windows one byte either side still hold 20 distinct bytes
+        assert_eq!(ranges, vec![Range { start: 29, end: 51 }]);
+
+        // Matches a direct computation on every window
+        let expected = calc.calculate_entropy(&data[25..45]);
+        let lower = calc.high_entropy_windows(data, 20, expected);
+        assert!(lower.iter().any(|r| r.start <= 25 && r.end >= 45));
+        assert!(calc.high_entropy_windows(b"short", 20, 0.0).is_empty());
+    }
+
     #[test]
     fn test_entropy_is_binary_text() {
         let calc = SimdEntropyCalculator::new();
@@ -2787,6 +4551,29 @@ mod tests {
 
     // String Comparer Tests
 
+    #[test]
+    fn test_string_comparer_matches_byte_order() {
+        let comparer = SimdStringComparer::new();
+        let base = vec![b'k'; 100];
+        for pos in [0, 1, 15, 16, 17, 31, 32, 70, 99] {
+            for delta in [b'a', b'z'] {
+                let mut other = base.clone();
+                other[pos] = delta;
+                assert_eq!(comparer.compare(&base, &other), base.cmp(&other), "difference at {}", pos);
+                assert_eq!(comparer.compare(&other, &base), other.cmp(&base), "difference at {}", pos);
+                #[cfg(target_arch = "x86_64")]
+                if is_x86_feature_detected!("sse2") {
+                    // The SSE2 path is otherwise only taken without AVX2;
+                    // differences in the scalar tail give `None`
+                    if let Some(ordering) = unsafe { comparer.compare_sse2(&base, &other) } {
+                        assert_eq!(ordering, base.cmp(&other), "sse2, difference at {}", pos);
+                    }
+                }
+            }
+        }
+        assert_eq!(comparer.compare(&base, &base[..99]), std::cmp::Ordering::Greater);
+    }
+
     #[test]
     fn test_string_comparer_equal() {
         let comparer = SimdStringComparer::new();
@@ -2963,4 +4750,84 @@ mod tests {
         let matches = searcher.find_all(&text);
         assert!(matches.len() >= 2);
     }
+
+    // Printable Scanner Tests
+
+    /// Byte-at-a-time reference for the printable scanner
+    fn naive_runs(data: &[u8], min: usize, encoding: PrintableEncoding) -> Vec<Range<usize>> {
+        let printable = |b: u8| (0x20..0x7f).contains(&b) || b == b'\t';
+        let (stride, is_char): (usize, &dyn Fn(usize) -> bool) = match encoding {
+            PrintableEncoding::Ascii => (1, &|i| printable(data[i])),
+            PrintableEncoding::Utf16Le => (2, &|i| i + 1 < data.len() && printable(data[i]) && data[i + 1] == 0),
+        };
+        let mut runs = Vec::new();
+        for parity in 0..stride {
+            let mut start = parity;
+            let mut i = parity;
+            while i < data.len() + stride {
+                if i >= data.len() || !is_char(i) {
+                    if i.saturating_sub(start) / stride >= min {
+                        runs.push(start..i);
+                    }
+                    start = i + stride;
+                }
+                i += stride;
+            }
+        }
+        runs.sort_by_key(|r| r.start);
+        runs
+    }
+
+    #[test]
+    fn test_printable_scanner_runs() {
+        let scanner = SimdPrintableScanner::new();
+        let data = b"\x00\x01hello\x00ab\x7fworld!\tx\xffW\x00I\x00D\x00E\x00\x00end";
+        let runs = scanner.find_runs(data, 3, &[PrintableEncoding::Ascii, PrintableEncoding::Utf16Le]);
+        let found: Vec<_> = runs.iter().map(|r| (r.range.start, r.encoding, r.text(data))).collect();
+        assert_eq!(
+            found,
+            [
+                (2, PrintableEncoding::Ascii, "hello".to_string()),
+                (11, PrintableEncoding::Ascii, "world!\tx".to_string()),
+                (20, PrintableEncoding::Utf16Le, "WIDE".to_string()),
+                (29, PrintableEncoding::Ascii, "end".to_string()),
+            ]
+        );
+        assert_eq!(runs[2].chars(), 4);
+        assert_eq!(scanner.ascii_runs(data, 6), [Range { start: 11, end: 19 }]);
+    }
+
+    #[test]
+    fn test_printable_scanner_matches_naive() {
+        let simd = SimdPrintableScanner::new();
+        let scalar = SimdPrintableScanner::with_config(SimdConfig { enabled: false, vector_width: 1 });
+
+        // Mostly text with binary gaps and wide strings, across block boundaries
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut data = Vec::new();
+        while data.len() < 5000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let len = (state % 90) as usize;
+            match state >> 62 {
+                0 => data.extend((0..len).map(|i| (state >> (i % 56)) as u8)),
+                1 => data.extend((0..len).flat_map(|i| [b'a' + (i % 26) as u8, 0])),
+                _ => data.extend((0..len).map(|i| b' ' + ((state as usize + i) % 95) as u8)),
+            }
+        }
+
+        for len in [0, 1, 63, 64, 65, 127, 128, 129, data.len() - 1, data.len()] {
+            let data = &data[..len];
+            for min in [1, 2, 4, 10] {
+                for encoding in [PrintableEncoding::Ascii, PrintableEncoding::Utf16Le] {
+                    let expected = naive_runs(data, min, encoding);
+                    for scanner in [&simd, &scalar] {
+                        let runs: Vec<_> = scanner.find_runs(data, min, &[encoding]).into_iter().map(|r| r.range).collect();
+                        assert_eq!(runs, expected, "len {} min {} {:?}", len, min, encoding);
+                    }
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,361 @@
+//! Tree snapshots for ai-snapshot
+//!
+//! A [`TreeSnapshot`] records the size, modification time and digest of
+//! every file under a directory, by path relative to it. Two snapshots can
+//! be compared ([`TreeSnapshot::diff`]), or a snapshot can be compared with
+//! the tree as it is now ([`TreeSnapshot::diff_live`]). The live comparison
+//! trusts files whose size and mtime are unchanged, as git does, so only
+//! files that were touched are hashed again. Files modified no earlier than
+//! the snapshot was taken are always hashed, since a later change within
+//! the same mtime tick would otherwise go unnoticed.
+//!
+//! Snapshots are JSON:
+//! `{"root", "algorithm", "created", "files": {path: {size, mtime, digest}}}`.
+
+use crate::checksum::{hash_file, DriftKind};
+use crate::error::{AiCoreutilsError, Result};
+use crate::fs_utils::{self, DirWalk, SymlinkPolicy, WalkEvent};
+use crate::hash::HashAlgorithm;
+use chrono::{DateTime, Utc};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Recorded state of one file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    /// Size in bytes
+    pub size: u64,
+    /// Last modification time
+    pub mtime: DateTime<Utc>,
+    /// Hex digest of the contents
+    pub digest: String,
+}
+
+/// Paths that could not be read, with the reason
+pub type PathErrors = Vec<(PathBuf, AiCoreutilsError)>;
+
+/// A file that differs between two states of a tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// Path relative to the snapshot root
+    pub path: PathBuf,
+    /// What changed
+    pub kind: DriftKind,
+    /// Entry in the base snapshot, if the file was there
+    pub before: Option<SnapshotEntry>,
+    /// Entry now, if the file still exists
+    pub after: Option<SnapshotEntry>,
+}
+
+/// Outcome of comparing a snapshot with the live tree
+#[derive(Debug, Default)]
+pub struct LiveDiff {
+    /// Files added, removed or changed, by path
+    pub changes: Vec<Change>,
+    /// Files whose contents are as recorded
+    pub unchanged: usize,
+    /// Files that were hashed because they are new or their size or mtime changed
+    pub rehashed: usize,
+    /// Files or directories that could not be read
+    pub errors: PathErrors,
+}
+
+/// Size, mtime and digest of every file under a directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeSnapshot {
+    /// Absolute path of the directory the snapshot was taken of
+    pub root: PathBuf,
+    /// Algorithm of every digest
+    pub algorithm: HashAlgorithm,
+    /// When the snapshot was taken
+    pub created: DateTime<Utc>,
+    /// Entries by path relative to `root`
+    pub files: BTreeMap<PathBuf, SnapshotEntry>,
+}
+
+impl TreeSnapshot {
+    /// Record every file under `root`, skipping the files in `exclude`
+    ///
+    /// Files that cannot be read are left out and returned as errors.
+    pub fn create(
+        root: &Path,
+        algorithm: HashAlgorithm,
+        exclude: &[PathBuf],
+    ) -> Result<(Self, PathErrors)> {
+        let created = Utc::now();
+        let (files, mut errors) = walk(root, exclude)?;
+        let hashed: Vec<_> = files
+            .into_par_iter()
+            .map(|file| {
+                let result = hash_file(&root.join(&file.relative), algorithm);
+                (file, result)
+            })
+            .collect();
+
+        let mut snapshot = Self {
+            root: fs::canonicalize(root)?,
+            algorithm,
+            created,
+            files: BTreeMap::new(),
+        };
+        for (file, result) in hashed {
+            match result {
+                Ok(entry) => {
+                    let entry = SnapshotEntry { size: entry.size, mtime: file.mtime, digest: entry.digest };
+                    snapshot.files.insert(file.relative, entry);
+                }
+                Err(e) => errors.push((root.join(file.relative), e)),
+            }
+        }
+        Ok((snapshot, errors))
+    }
+
+    /// Read a snapshot file
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => AiCoreutilsError::PathNotFound(path.to_path_buf()),
+            _ => AiCoreutilsError::Io(e),
+        })?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Atomically write the snapshot to `path`
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut json = serde_json::to_vec_pretty(self)?;
+        json.push(b'\n');
+        fs_utils::atomic_write(path, &json)
+    }
+
+    /// Total size of the recorded files
+    pub fn bytes(&self) -> u64 {
+        self.files.values().map(|e| e.size).sum()
+    }
+
+    /// Changes from this snapshot to a later one
+    ///
+    /// A file counts as changed when its size or digest differs; a new
+    /// mtime alone is not a change. Both snapshots must use the same
+    /// algorithm.
+    pub fn diff(&self, later: &TreeSnapshot) -> Result<Vec<Change>> {
+        if self.algorithm != later.algorithm {
+            return Err(AiCoreutilsError::InvalidInput(format!(
+                "Snapshots use different algorithms ({} and {})",
+                self.algorithm.as_str(),
+                later.algorithm.as_str()
+            )));
+        }
+        let mut changes: Vec<Change> = later
+            .files
+            .iter()
+            .filter_map(|(path, after)| change(path, self.files.get(path), Some(after)))
+            .collect();
+        changes.extend(
+            self.files
+                .iter()
+                .filter(|(path, _)| !later.files.contains_key(*path))
+                .filter_map(|(path, before)| change(path, Some(before), None)),
+        );
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(changes)
+    }
+
+    /// Changes from this snapshot to the files under `root` now
+    ///
+    /// Files whose size and mtime match the snapshot are assumed unchanged
+    /// unless `rehash_all` is set; the rest are hashed and compared.
+    pub fn diff_live(&self, root: &Path, exclude: &[PathBuf], rehash_all: bool) -> Result<LiveDiff> {
+        let (files, errors) = walk(root, exclude)?;
+        let mut diff = LiveDiff { errors, ..LiveDiff::default() };
+
+        let (trusted, suspect): (Vec<_>, Vec<_>) = files.into_iter().partition(|file| {
+            !rehash_all
+                && self
+                    .files
+                    .get(&file.relative)
+                    .is_some_and(|before| {
+                        before.mtime == file.mtime && before.size == file.size && before.mtime < self.created
+                    })
+        });
+        diff.unchanged = trusted.len();
+        diff.rehashed = suspect.len();
+
+        let hashed: Vec<_> = suspect
+            .into_par_iter()
+            .map(|file| {
+                let result = hash_file(&root.join(&file.relative), self.algorithm);
+                (file, result)
+            })
+            .collect();
+        let mut present: std::collections::HashSet<PathBuf> = trusted.into_iter().map(|file| file.relative).collect();
+        for (file, result) in hashed {
+            let relative = file.relative;
+            let entry = match result {
+                Ok(entry) => SnapshotEntry { size: entry.size, mtime: file.mtime, digest: entry.digest },
+                Err(e) => {
+                    diff.errors.push((root.join(&relative), e));
+                    // An unreadable file is not reported as removed
+                    present.insert(relative);
+                    continue;
+                }
+            };
+            match change(&relative, self.files.get(&relative), Some(&entry)) {
+                Some(change) => diff.changes.push(change),
+                None => diff.unchanged += 1,
+            }
+            present.insert(relative);
+        }
+        diff.changes.extend(
+            self.files
+                .iter()
+                .filter(|(path, _)| !present.contains(*path))
+                .filter_map(|(path, before)| change(path, Some(before), None)),
+        );
+
+        diff.changes.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(diff)
+    }
+}
+
+fn change(path: &Path, before: Option<&SnapshotEntry>, after: Option<&SnapshotEntry>) -> Option<Change> {
+    let kind = match (before, after) {
+        (None, Some(_)) => DriftKind::Added,
+        (Some(_), None) => DriftKind::Removed,
+        (Some(b), Some(a)) if b.size != a.size || b.digest != a.digest => DriftKind::Changed,
+        _ => return None,
+    };
+    Some(Change {
+        path: path.to_path_buf(),
+        kind,
+        before: before.cloned(),
+        after: after.cloned(),
+    })
+}
+
+/// A regular file found under a snapshot root
+struct FoundFile {
+    relative: PathBuf,
+    size: u64,
+    mtime: DateTime<Utc>,
+}
+
+/// Regular files under `root`
+///
+/// Configured ignore rules apply, and files in `exclude` are skipped
+/// however their paths are spelled.
+fn walk(root: &Path, exclude: &[PathBuf]) -> Result<(Vec<FoundFile>, PathErrors)> {
+    if !fs::metadata(root)?.is_dir() {
+        return Err(AiCoreutilsError::InvalidInput(format!("{} is not a directory", root.display())));
+    }
+    let excluded: Vec<PathBuf> = exclude.iter().filter_map(|p| fs::canonicalize(p).ok()).collect();
+
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    for event in DirWalk::new(root, SymlinkPolicy::CommandLine) {
+        match event {
+            Ok(WalkEvent::Entry(entry)) if entry.is_file() => {
+                let Some((size, mtime)) = entry.metadata().and_then(|m| Some((m.len(), m.modified().ok()?))) else {
+                    continue;
+                };
+                if !excluded.is_empty() && fs::canonicalize(entry.path()).is_ok_and(|p| excluded.contains(&p)) {
+                    continue;
+                }
+                if let Ok(relative) = entry.path().strip_prefix(root) {
+                    files.push(FoundFile { relative: relative.to_path_buf(), size, mtime: mtime.into() });
+                }
+            }
+            Ok(_) => {}
+            Err(e) => errors.push((root.to_path_buf(), e)),
+        }
+    }
+    Ok((files, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn set_mtime(path: &Path, time: SystemTime) {
+        fs::File::options().write(true).open(path).unwrap().set_modified(time).unwrap();
+    }
+
+    #[test]
+    fn test_create_and_diff_live() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("tree");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("README"), "readme").unwrap();
+        fs::write(root.join("old.txt"), "old").unwrap();
+        let manifest = root.join("snapshot.json");
+        fs::write(&manifest, "{}").unwrap();
+        let exclude = [manifest.clone()];
+
+        let (snapshot, errors) = TreeSnapshot::create(&root, HashAlgorithm::Xxh3, &exclude).unwrap();
+        assert!(errors.is_empty());
+        let paths: Vec<&Path> = snapshot.files.keys().map(PathBuf::as_path).collect();
+        assert_eq!(paths, [Path::new("README"), Path::new("old.txt"), Path::new("src/main.rs")]);
+        assert_eq!(snapshot.bytes(), 21);
+        snapshot.save(&manifest).unwrap();
+        assert_eq!(TreeSnapshot::load(&manifest).unwrap(), snapshot);
+
+        let unchanged = snapshot.diff_live(&root, &exclude, false).unwrap();
+        assert_eq!((unchanged.changes.len(), unchanged.unchanged, unchanged.rehashed), (0, 3, 0));
+
+        // Same size and contents with a new mtime is rehashed but not a change
+        let touched = SystemTime::now() + Duration::from_secs(60);
+        set_mtime(&root.join("README"), touched);
+        fs::write(root.join("src/main.rs"), "fn main() { run() }").unwrap();
+        fs::remove_file(root.join("old.txt")).unwrap();
+        fs::write(root.join("new.txt"), "new").unwrap();
+
+        let diff = snapshot.diff_live(&root, &exclude, false).unwrap();
+        let kinds: Vec<_> = diff.changes.iter().map(|c| (c.path.to_str().unwrap(), c.kind)).collect();
+        assert_eq!(
+            kinds,
+            [("new.txt", DriftKind::Added), ("old.txt", DriftKind::Removed), ("src/main.rs", DriftKind::Changed)]
+        );
+        assert_eq!((diff.unchanged, diff.rehashed), (1, 3));
+        assert_eq!(diff.changes[2].before.as_ref().unwrap().size, 12);
+        assert_eq!(diff.changes[2].after.as_ref().unwrap().size, 19);
+
+        // Same size and mtime hides an edit unless every file is rehashed
+        let mtime = fs::metadata(root.join("src/main.rs")).unwrap().modified().unwrap();
+        let (later, _) = TreeSnapshot::create(&root, HashAlgorithm::Xxh3, &exclude).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() { xxx() }").unwrap();
+        set_mtime(&root.join("src/main.rs"), mtime);
+        assert!(later.diff_live(&root, &exclude, false).unwrap().changes.is_empty());
+        assert_eq!(later.diff_live(&root, &exclude, true).unwrap().changes.len(), 1);
+
+        // A file modified as the snapshot was taken is always rehashed
+        fs::write(root.join("new.txt"), "NEW").unwrap();
+        set_mtime(&root.join("new.txt"), later.created.into());
+        let mut racy = later.clone();
+        racy.files.get_mut(Path::new("new.txt")).unwrap().mtime = later.created;
+        let diff = racy.diff_live(&root, &exclude, false).unwrap();
+        assert_eq!((diff.changes.len(), diff.changes[0].path.as_path()), (1, Path::new("new.txt")));
+    }
+
+    #[test]
+    fn test_diff_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a"), "alpha").unwrap();
+        fs::write(dir.path().join("b"), "beta").unwrap();
+        let (before, _) = TreeSnapshot::create(dir.path(), HashAlgorithm::Crc32, &[]).unwrap();
+        fs::write(dir.path().join("a"), "ALPHA").unwrap();
+        fs::remove_file(dir.path().join("b")).unwrap();
+        let (after, _) = TreeSnapshot::create(dir.path(), HashAlgorithm::Crc32, &[]).unwrap();
+
+        let changes = before.diff(&after).unwrap();
+        let kinds: Vec<_> = changes.iter().map(|c| (c.path.to_str().unwrap(), c.kind)).collect();
+        assert_eq!(kinds, [("a", DriftKind::Changed), ("b", DriftKind::Removed)]);
+        assert!(after.diff(&after).unwrap().is_empty());
+
+        let (xxh3, _) = TreeSnapshot::create(dir.path(), HashAlgorithm::Xxh3, &[]).unwrap();
+        assert!(before.diff(&xxh3).is_err());
+        assert!(TreeSnapshot::create(&dir.path().join("a"), HashAlgorithm::Xxh3, &[]).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+//! Run-time telemetry for summary records
+//!
+//! Every utility's summary record (`copy_summary`, `find_summary` and so on)
+//! carries a `telemetry` field describing the run so far: wall time since
+//! [`crate::config::init`], user and system CPU time and peak resident set
+//! size from `getrusage`, bytes of file contents read and written (counted
+//! by the same library I/O primitives as the audit log), and the SIMD code
+//! path in use. Concurrency and buffer sizes picked by [`crate::tuning`]
+//! are listed under `tuning` with where each came from, so a run can be
+//! reproduced. Set `jsonl.telemetry = false` or
+//! `AI_COREUTILS_JSONL_TELEMETRY=0` to leave it out.
+
+use crate::simd_ops::SimdConfig;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+static STARTED: OnceLock<Instant> = OnceLock::new();
+static BYTES_READ: AtomicU64 = AtomicU64::new(0);
+static BYTES_WRITTEN: AtomicU64 = AtomicU64::new(0);
+static TUNING: Mutex<BTreeMap<&'static str, Tuned>> = Mutex::new(BTreeMap::new());
+
+/// A tuned parameter and where its value came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Tuned {
+    /// Value used
+    pub value: usize,
+    /// `cli`, `config`, `profile`, `measured` or `default`
+    pub source: &'static str,
+}
+
+/// Resource usage of the process so far
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Telemetry {
+    /// Milliseconds since the utility started
+    pub wall_ms: u64,
+    /// Milliseconds of CPU time in user mode; `None` where unavailable
+    pub user_cpu_ms: Option<u64>,
+    /// Milliseconds of CPU time in the kernel; `None` where unavailable
+    pub sys_cpu_ms: Option<u64>,
+    /// Peak resident set size in bytes; `None` where unavailable
+    pub peak_rss_bytes: Option<u64>,
+    /// Bytes of file contents read
+    pub bytes_read: u64,
+    /// Bytes of file contents written
+    pub bytes_written: u64,
+    /// SIMD code path: `avx2`, `sse2`, `neon`, `simd128` or `scalar`
+    pub simd: &'static str,
+    /// Tuned parameters used so far, by name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tuning: BTreeMap<&'static str, Tuned>,
+}
+
+impl Telemetry {
+    /// Measure the process now
+    pub fn capture() -> Self {
+        let (user_cpu_ms, sys_cpu_ms, peak_rss_bytes) = match usage() {
+            Some((user, sys, rss)) => (Some(user), Some(sys), Some(rss)),
+            None => (None, None, None),
+        };
+        Self {
+            wall_ms: STARTED.get_or_init(Instant::now).elapsed().as_millis() as u64,
+            user_cpu_ms,
+            sys_cpu_ms,
+            peak_rss_bytes,
+            bytes_read: BYTES_READ.load(Ordering::Relaxed),
+            bytes_written: BYTES_WRITTEN.load(Ordering::Relaxed),
+            simd: SimdConfig::detect().path(),
+            tuning: tuning(),
+        }
+    }
+}
+
+/// Note the process start; wall time is measured from the first call
+pub fn start() {
+    STARTED.get_or_init(Instant::now);
+}
+
+/// Include telemetry in summary records or not (process-wide)
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether summary records include telemetry
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Add `bytes` of file contents read
+pub fn read(bytes: u64) {
+    BYTES_READ.fetch_add(bytes, Ordering::Relaxed);
+}
+
+/// Add `bytes` of file contents written
+pub fn written(bytes: u64) {
+    BYTES_WRITTEN.fetch_add(bytes, Ordering::Relaxed);
+}
+
+/// Note that parameter `name` was set to `value`, taken from `source`
+pub fn tuned(name: &'static str, value: usize, source: &'static str) {
+    if let Ok(mut tuning) = TUNING.lock() {
+        tuning.insert(name, Tuned { value, source });
+    }
+}
+
+/// Tuned parameters used so far, by name
+pub fn tuning() -> BTreeMap<&'static str, Tuned> {
+    TUNING.lock().map(|tuning| tuning.clone()).unwrap_or_default()
+}
+
+/// `data` with a `telemetry` field added, if enabled and `data` is an object
+pub fn attach(mut data: serde_json::Value) -> serde_json::Value {
+    if !is_enabled() {
+        return data;
+    }
+    if let (Some(fields), Ok(telemetry)) = (data.as_object_mut(), serde_json::to_value(Telemetry::capture())) {
+        fields.insert("telemetry".to_string(), telemetry);
+    }
+    data
+}
+
+/// User and system CPU milliseconds and peak RSS bytes
+#[cfg(unix)]
+fn usage() -> Option<(u64, u64, u64)> {
+    // SAFETY: getrusage only writes into the zeroed struct it is given
+    let usage = unsafe {
+        let mut usage: libc::rusage = std::mem::zeroed();
+        if libc::getrusage(libc::RUSAGE_SELF, &mut usage) != 0 {
+            return None;
+        }
+        usage
+    };
+    let ms = |tv: libc::timeval| tv.tv_sec as u64 * 1000 + tv.tv_usec as u64 / 1000;
+    // ru_maxrss is in kilobytes, except on macOS where it is in bytes
+    let rss_unit = if cfg!(target_os = "macos") { 1 } else { 1024 };
+    Some((ms(usage.ru_utime), ms(usage.ru_stime), usage.ru_maxrss as u64 * rss_unit))
+}
+
+#[cfg(not(unix))]
+fn usage() -> Option<(u64, u64, u64)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture() {
+        start();
+        read(10);
+        written(4);
+        tuned("buffer_size", 65536, "profile");
+        let telemetry = Telemetry::capture();
+
+        // Counters are process-wide, so other tests may have added to them
+        assert!(telemetry.bytes_read >= 10);
+        assert!(telemetry.bytes_written >= 4);
+        assert!(["avx2", "sse2", "neon", "simd128", "scalar"].contains(&telemetry.simd));
+        assert_eq!(telemetry.tuning["buffer_size"], Tuned { value: 65536, source: "profile" });
+        #[cfg(unix)]
+        assert!(telemetry.peak_rss_bytes.unwrap() > 0);
+    }
+
+    #[test]
+    fn test_attach() {
+        let data = attach(serde_json::json!({ "type": "copy_summary", "files": 2 }));
+        assert_eq!(data["files"], 2);
+        assert!(data["telemetry"]["wall_ms"].is_u64());
+
+        // Only objects get a field
+        assert_eq!(attach(serde_json::json!(3)), serde_json::json!(3));
+    }
+}
@@ -0,0 +1,700 @@
+//! Line decoration with GNU `cat`, `nl` and `fold` semantics
+//!
+//! [`LineFormatter`] numbers, squeezes and marks up lines the way `cat -n`,
+//! `-b`, `-s`, `-E`, `-T` and `-v` do. The options compose: `-nET` numbers
+//! every line, shows tabs as `^I` and ends lines with `$`, and
+//! [`LineFormat::show_all`] is `-vET` as in GNU `cat -A`.
+//!
+//! [`LineNumberer`] numbers lines with `nl`'s styles, formats and logical
+//! page sections, and [`Folder`] splits long lines where `fold` would.
+//! Both work on one input line at a time, so callers can keep each output
+//! line's position in the input.
+
+use crate::error::{AiCoreutilsError, Result};
+use regex::Regex;
+use std::ops::Range;
+
+/// Which decorations to apply
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineFormat {
+    /// Number all output lines (`-n`)
+    pub number: bool,
+    /// Number non-blank output lines; overrides `number` (`-b`)
+    pub number_nonblank: bool,
+    /// Collapse runs of blank lines into one (`-s`)
+    pub squeeze_blank: bool,
+    /// End each line with `$` (`-E`)
+    pub show_ends: bool,
+    /// Show tabs as `^I` (`-T`)
+    pub show_tabs: bool,
+    /// Show control characters in `^X` and `M-^X` notation (`-v`)
+    pub show_nonprinting: bool,
+}
+
+impl LineFormat {
+    /// Enable `-vET`, as `cat -A` does
+    pub fn show_all(mut self) -> Self {
+        self.show_nonprinting = true;
+        self.show_ends = true;
+        self.show_tabs = true;
+        self
+    }
+
+    /// Whether any option changes the text of a line
+    pub fn decorates(&self) -> bool {
+        self.show_ends || self.show_tabs || self.show_nonprinting
+    }
+}
+
+/// One decorated output line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormattedLine {
+    /// Line text after decoration
+    pub content: String,
+    /// Output line number with `-n`
+    pub line_number: Option<usize>,
+    /// Non-blank line number with `-b`
+    pub non_blank_number: Option<usize>,
+    /// Whether the source line was empty
+    pub is_blank: bool,
+    /// Number of the line in the input (1-based, squeezed lines included)
+    pub source_line: usize,
+}
+
+/// Stateful line decorator; numbering and squeezing carry across calls
+#[derive(Debug, Clone)]
+pub struct LineFormatter {
+    format: LineFormat,
+    next_source_line: usize,
+    next_number: usize,
+    next_non_blank: usize,
+    last_was_blank: bool,
+}
+
+impl LineFormatter {
+    /// Create a formatter numbering from line 1
+    pub fn new(format: LineFormat) -> Self {
+        Self::starting_at(format, 1)
+    }
+
+    /// Create a formatter whose first line is line `first` of the input
+    ///
+    /// Used when formatting a slice of a file, so `-n` numbers match the
+    /// file. `-b` numbers still start at 1, as the blank lines before the
+    /// slice are unknown.
+    pub fn starting_at(format: LineFormat, first: usize) -> Self {
+        Self {
+            format,
+            next_source_line: first,
+            next_number: first,
+            next_non_blank: 1,
+            last_was_blank: false,
+        }
+    }
+
+    /// Decorate one line (without its terminator); `None` if squeezed out
+    pub fn format_line(&mut self, line: &str) -> Option<FormattedLine> {
+        let source_line = self.next_source_line;
+        self.next_source_line += 1;
+
+        let is_blank = line.is_empty();
+        if self.format.squeeze_blank && is_blank && self.last_was_blank {
+            return None;
+        }
+        self.last_was_blank = is_blank;
+
+        let mut line_number = None;
+        let mut non_blank_number = None;
+        if self.format.number_nonblank {
+            if !is_blank {
+                non_blank_number = Some(self.next_non_blank);
+                self.next_non_blank += 1;
+            }
+        } else if self.format.number {
+            line_number = Some(self.next_number);
+            self.next_number += 1;
+        }
+
+        Some(FormattedLine {
+            content: self.decorate(line),
+            line_number,
+            non_blank_number,
+            is_blank,
+            source_line,
+        })
+    }
+
+    /// Decorate every line of `text`
+    pub fn format_text(&mut self, text: &str) -> Vec<FormattedLine> {
+        text.lines().filter_map(|line| self.format_line(line)).collect()
+    }
+
+    fn decorate(&self, line: &str) -> String {
+        if !self.format.decorates() {
+            return line.to_string();
+        }
+
+        let mut out = String::with_capacity(line.len() + 1);
+        for c in line.chars() {
+            match c {
+                '\t' if self.format.show_tabs => out.push_str("^I"),
+                '\t' => out.push('\t'),
+                c if self.format.show_nonprinting => push_visible(&mut out, c),
+                c => out.push(c),
+            }
+        }
+        if self.format.show_ends {
+            out.push('$');
+        }
+        out
+    }
+}
+
+/// Append `c` in `cat -v` notation: `^X` for controls, `^?` for DEL and
+/// `M-^X` for C1 controls; other characters are kept as they are
+fn push_visible(out: &mut String, c: char) {
+    let code = c as u32;
+    match code {
+        0..=0x1f => {
+            out.push('^');
+            out.push((code as u8 + b'@') as char);
+        }
+        0x7f => out.push_str("^?"),
+        0x80..=0x9f => {
+            out.push_str("M-^");
+            out.push((code as u8 - 0x80 + b'@') as char);
+        }
+        _ => out.push(c),
+    }
+}
+
+/// Which lines of a section `nl` numbers (`-b`, `-h`, `-f`)
+#[derive(Debug, Clone)]
+pub enum NumberingStyle {
+    /// Every line (`a`)
+    All,
+    /// Non-empty lines (`t`)
+    NonEmpty,
+    /// No lines (`n`)
+    None,
+    /// Lines matching the regex (`pREGEX`)
+    Matching(Regex),
+}
+
+impl std::str::FromStr for NumberingStyle {
+    type Err = AiCoreutilsError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "a" => Ok(NumberingStyle::All),
+            "t" => Ok(NumberingStyle::NonEmpty),
+            "n" => Ok(NumberingStyle::None),
+            _ => match s.strip_prefix('p') {
+                Some(pattern) => Regex::new(pattern)
+                    .map(NumberingStyle::Matching)
+                    .map_err(|e| AiCoreutilsError::InvalidInput(format!("invalid regex: {}", e))),
+                None => Err(AiCoreutilsError::InvalidInput(format!(
+                    "invalid numbering style: {} (expected a, t, n or pREGEX)",
+                    s
+                ))),
+            },
+        }
+    }
+}
+
+/// How `nl` writes a line number (`-n`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NumberFormat {
+    /// Left-justified (`ln`)
+    Left,
+    /// Right-justified (`rn`)
+    #[default]
+    Right,
+    /// Right-justified with leading zeros (`rz`)
+    RightZero,
+}
+
+impl std::str::FromStr for NumberFormat {
+    type Err = AiCoreutilsError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ln" => Ok(NumberFormat::Left),
+            "rn" => Ok(NumberFormat::Right),
+            "rz" => Ok(NumberFormat::RightZero),
+            _ => Err(AiCoreutilsError::InvalidInput(format!(
+                "invalid line number format: {} (expected ln, rn or rz)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Section of a logical page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    /// Started by the delimiter three times
+    Header,
+    /// Started by the delimiter twice; input starts in a body
+    Body,
+    /// Started by the delimiter once
+    Footer,
+}
+
+impl Section {
+    /// Name used in output records
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Section::Header => "header",
+            Section::Body => "body",
+            Section::Footer => "footer",
+        }
+    }
+}
+
+/// `nl` options; the defaults are those of GNU `nl`
+#[derive(Debug, Clone)]
+pub struct Numbering {
+    /// Lines numbered in headers (`-h`, default `n`)
+    pub header: NumberingStyle,
+    /// Lines numbered in bodies (`-b`, default `t`)
+    pub body: NumberingStyle,
+    /// Lines numbered in footers (`-f`, default `n`)
+    pub footer: NumberingStyle,
+    /// Section delimiter (`-d`, default `\:`); a line of it three, two or
+    /// one times starts a header, body or footer. Empty disables sections.
+    pub delimiter: Vec<u8>,
+    /// First number of each logical page (`-v`)
+    pub start: i64,
+    /// Step between numbers (`-i`)
+    pub increment: i64,
+    /// Runs of this many empty lines count as one line (`-l`)
+    pub join_blank: usize,
+    /// Number layout (`-n`)
+    pub format: NumberFormat,
+    /// Number width in characters (`-w`)
+    pub width: usize,
+    /// Text between number and line (`-s`)
+    pub separator: String,
+    /// Restart numbering at each section delimiter (turned off by `-p`)
+    pub restart: bool,
+}
+
+impl Default for Numbering {
+    fn default() -> Self {
+        Self {
+            header: NumberingStyle::None,
+            body: NumberingStyle::NonEmpty,
+            footer: NumberingStyle::None,
+            delimiter: b"\\:".to_vec(),
+            start: 1,
+            increment: 1,
+            join_blank: 1,
+            format: NumberFormat::Right,
+            width: 6,
+            separator: "\t".to_string(),
+            restart: true,
+        }
+    }
+}
+
+/// One line of `nl` output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumberedLine {
+    /// Output text: number and separator, or padding, then the line
+    pub content: String,
+    /// Number given to the line, if any
+    pub number: Option<i64>,
+    /// Section the line belongs to
+    pub section: Section,
+    /// Whether the input line was a section delimiter, output as an empty line
+    pub delimiter: bool,
+}
+
+/// Stateful `nl`; numbering and sections carry across calls
+#[derive(Debug, Clone)]
+pub struct LineNumberer {
+    numbering: Numbering,
+    section: Section,
+    /// Next number, `None` once it has overflowed
+    next: Option<i64>,
+    blank_run: usize,
+}
+
+impl LineNumberer {
+    /// Start in the body of the first logical page
+    pub fn new(mut numbering: Numbering) -> Self {
+        // A one-character delimiter is completed with ':', as in GNU nl
+        if numbering.delimiter.len() == 1 {
+            numbering.delimiter.push(b':');
+        }
+        let next = Some(numbering.start);
+        Self {
+            numbering,
+            section: Section::Body,
+            next,
+            blank_run: 0,
+        }
+    }
+
+    /// Number one line (without its terminator)
+    ///
+    /// Fails once numbers no longer fit in an `i64`.
+    pub fn number_line(&mut self, line: &[u8]) -> Result<NumberedLine> {
+        if let Some(section) = self.section_start(line) {
+            self.section = section;
+            if self.numbering.restart {
+                self.next = Some(self.numbering.start);
+            }
+            return Ok(NumberedLine {
+                content: String::new(),
+                number: None,
+                section,
+                delimiter: true,
+            });
+        }
+
+        let text = String::from_utf8_lossy(line);
+        let style = match self.section {
+            Section::Header => &self.numbering.header,
+            Section::Body => &self.numbering.body,
+            Section::Footer => &self.numbering.footer,
+        };
+        let numbered = match style {
+            NumberingStyle::All if self.numbering.join_blank > 1 => {
+                if line.is_empty() {
+                    self.blank_run += 1;
+                }
+                let numbered = !line.is_empty() || self.blank_run == self.numbering.join_blank;
+                if numbered {
+                    self.blank_run = 0;
+                }
+                numbered
+            }
+            NumberingStyle::All => true,
+            NumberingStyle::NonEmpty => !line.is_empty(),
+            NumberingStyle::None => false,
+            NumberingStyle::Matching(regex) => regex.is_match(&text),
+        };
+
+        let (number, prefix) = if numbered {
+            let number = self.next.ok_or_else(|| AiCoreutilsError::InvalidInput("line number overflow".to_string()))?;
+            self.next = number.checked_add(self.numbering.increment);
+            (Some(number), self.format_number(number))
+        } else {
+            (None, " ".repeat(self.numbering.width + self.numbering.separator.chars().count()))
+        };
+        Ok(NumberedLine {
+            content: prefix + &text,
+            number,
+            section: self.section,
+            delimiter: false,
+        })
+    }
+
+    fn format_number(&self, number: i64) -> String {
+        let width = self.numbering.width;
+        let separator = &self.numbering.separator;
+        match self.numbering.format {
+            NumberFormat::Left => format!("{:<width$}{}", number, separator),
+            NumberFormat::Right => format!("{:>width$}{}", number, separator),
+            NumberFormat::RightZero => format!("{:0width$}{}", number, separator),
+        }
+    }
+
+    /// The section `line` starts, if it is a delimiter line
+    fn section_start(&self, line: &[u8]) -> Option<Section> {
+        let delimiter = &self.numbering.delimiter;
+        if delimiter.is_empty() || !line.len().is_multiple_of(delimiter.len()) {
+            return None;
+        }
+        if !line.chunks(delimiter.len()).all(|chunk| chunk == delimiter.as_slice()) {
+            return None;
+        }
+        match line.len() / delimiter.len() {
+            3 => Some(Section::Header),
+            2 => Some(Section::Body),
+            1 => Some(Section::Footer),
+            _ => None,
+        }
+    }
+}
+
+/// Tab stops used when counting columns, as in `fold`
+const TAB_WIDTH: usize = 8;
+
+/// Splits lines longer than a width, as `fold` does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Folder {
+    /// Maximum columns (or bytes) per output line
+    pub width: usize,
+    /// Count bytes rather than columns (`-b`)
+    pub count_bytes: bool,
+    /// Break after the last blank before the width where there is one (`-s`)
+    pub break_spaces: bool,
+}
+
+impl Folder {
+    /// Byte ranges of `line` (without its terminator) that make up its
+    /// output lines
+    ///
+    /// The ranges are in order and cover the whole line, so joining them
+    /// gives the line back; an empty line is one empty range. Columns
+    /// follow `fold`: a tab advances to the next multiple of 8, backspace
+    /// goes back one and carriage return goes to column 0. Unlike GNU
+    /// `fold`, a multi-byte UTF-8 character takes one column rather than one
+    /// per byte. A character wider than the whole width gets a line to
+    /// itself.
+    pub fn fold(&self, line: &[u8]) -> Vec<Range<usize>> {
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        let mut column = 0;
+        let mut pos = 0;
+        while pos < line.len() {
+            let len = if self.count_bytes { 1 } else { char_len(&line[pos..]) };
+            column = self.advance(column, line[pos]);
+            if column <= self.width {
+                pos += len;
+                continue;
+            }
+            if self.break_spaces {
+                if let Some(blank) = line[start..pos].iter().rposition(|&b| b == b' ' || b == b'\t') {
+                    let end = start + blank + 1;
+                    pieces.push(start..end);
+                    start = end;
+                    column = self.columns(&line[start..pos]);
+                    continue;
+                }
+            }
+            if pos == start {
+                // Too wide for an empty line: it goes on one by itself
+                pos += len;
+                continue;
+            }
+            pieces.push(start..pos);
+            start = pos;
+            column = 0;
+        }
+        if start < line.len() || pieces.is_empty() {
+            pieces.push(start..line.len());
+        }
+        pieces
+    }
+
+    /// Column after a character starting with `byte`
+    fn advance(&self, column: usize, byte: u8) -> usize {
+        if self.count_bytes {
+            return column + 1;
+        }
+        match byte {
+            b'\x08' => column.saturating_sub(1),
+            b'\r' => 0,
+            b'\t' => column + TAB_WIDTH - column % TAB_WIDTH,
+            _ => column + 1,
+        }
+    }
+
+    /// Column reached after `text`, from column 0
+    fn columns(&self, text: &[u8]) -> usize {
+        let mut column = 0;
+        let mut pos = 0;
+        while pos < text.len() {
+            column = self.advance(column, text[pos]);
+            pos += if self.count_bytes { 1 } else { char_len(&text[pos..]) };
+        }
+        column
+    }
+}
+
+/// Length of the UTF-8 character starting `bytes`; 1 for an invalid byte
+fn char_len(bytes: &[u8]) -> usize {
+    let len = match bytes[0] {
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => return 1,
+    };
+    match bytes.get(..len).map(std::str::from_utf8) {
+        Some(Ok(_)) => len,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(format: LineFormat, text: &str) -> Vec<String> {
+        LineFormatter::new(format)
+            .format_text(text)
+            .into_iter()
+            .map(|line| line.content)
+            .collect()
+    }
+
+    #[test]
+    fn test_plain_passthrough() {
+        assert_eq!(contents(LineFormat::default(), "a\tb\n\nc"), ["a\tb", "", "c"]);
+    }
+
+    #[test]
+    fn test_decorations_compose() {
+        let format = LineFormat {
+            show_ends: true,
+            show_tabs: true,
+            number: true,
+            ..Default::default()
+        };
+        let lines = LineFormatter::new(format).format_text("a\tb\nc\n");
+        assert_eq!(lines[0].content, "a^Ib$");
+        assert_eq!(lines[1].content, "c$");
+        assert_eq!(lines[1].line_number, Some(2));
+
+        assert_eq!(
+            contents(LineFormat::default().show_all(), "\x01\t\x7f\u{85}é"),
+            ["^A^I^?M-^Eé$"]
+        );
+        // -v alone leaves tabs alone
+        let format = LineFormat { show_nonprinting: true, ..Default::default() };
+        assert_eq!(contents(format, "\t\x1b"), ["\t^["]);
+    }
+
+    #[test]
+    fn test_numbering() {
+        let text = "a\n\nb\n";
+        let format = LineFormat { number: true, ..Default::default() };
+        let numbers: Vec<_> = LineFormatter::new(format)
+            .format_text(text)
+            .iter()
+            .map(|l| l.line_number)
+            .collect();
+        assert_eq!(numbers, [Some(1), Some(2), Some(3)]);
+
+        // -b wins over -n and skips blank lines
+        let format = LineFormat { number: true, number_nonblank: true, ..Default::default() };
+        let lines = LineFormatter::new(format).format_text(text);
+        let numbers: Vec<_> = lines.iter().map(|l| (l.line_number, l.non_blank_number)).collect();
+        assert_eq!(numbers, [(None, Some(1)), (None, None), (None, Some(2))]);
+    }
+
+    #[test]
+    fn test_squeeze_blank_keeps_one() {
+        let format = LineFormat { squeeze_blank: true, number: true, ..Default::default() };
+        let lines = LineFormatter::new(format).format_text("a\n\n\n\nb\n\nc");
+        let kept: Vec<_> = lines.iter().map(|l| (l.content.as_str(), l.source_line)).collect();
+        assert_eq!(kept, [("a", 1), ("", 2), ("b", 5), ("", 6), ("c", 7)]);
+        // Output numbering counts only the lines kept
+        assert_eq!(lines[2].line_number, Some(3));
+    }
+
+    #[test]
+    fn test_state_carries_across_calls() {
+        let format = LineFormat { number: true, squeeze_blank: true, ..Default::default() };
+        let mut formatter = LineFormatter::starting_at(format, 100);
+        assert_eq!(formatter.format_line("x").unwrap().line_number, Some(100));
+        assert!(formatter.format_line("").is_some());
+        assert!(formatter.format_line("").is_none());
+        let line = formatter.format_line("y").unwrap();
+        assert_eq!((line.line_number, line.source_line), (Some(102), 103));
+    }
+
+    fn nl(numbering: Numbering, text: &str) -> Vec<String> {
+        let mut numberer = LineNumberer::new(numbering);
+        text.lines().map(|line| numberer.number_line(line.as_bytes()).unwrap().content).collect()
+    }
+
+    #[test]
+    fn test_nl_sections() {
+        // Output of GNU nl 9.1 for the same input
+        let text = "a\n\nb\n\\:\\:\\:\nh1\n\\:\\:\nb1\n\nb2\n\\:\nf1\n\\:\\:\nb3\n";
+        assert_eq!(
+            nl(Numbering::default(), text),
+            [
+                "     1\ta", "       ", "     2\tb", "", "       h1", "", "     1\tb1", "       ",
+                "     2\tb2", "", "       f1", "", "     1\tb3",
+            ]
+        );
+
+        let mut numberer = LineNumberer::new(Numbering { restart: false, ..Default::default() });
+        let numbers: Vec<_> = text
+            .lines()
+            .map(|line| numberer.number_line(line.as_bytes()).unwrap())
+            .filter_map(|line| line.number.map(|n| (line.section, n)))
+            .collect();
+        assert_eq!(numbers, [(Section::Body, 1), (Section::Body, 2), (Section::Body, 3), (Section::Body, 4), (Section::Body, 5)]);
+    }
+
+    #[test]
+    fn test_nl_styles_and_formats() {
+        let numbering = Numbering {
+            body: NumberingStyle::All,
+            join_blank: 2,
+            format: NumberFormat::RightZero,
+            width: 3,
+            separator: "::".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(nl(numbering, "a\n\nb\n\n\n\nc"), ["001::a", "     ", "002::b", "     ", "003::", "     ", "004::c"]);
+
+        let numbering = Numbering {
+            body: "p^b".parse().unwrap(),
+            format: "ln".parse().unwrap(),
+            start: -3,
+            increment: 5,
+            ..Default::default()
+        };
+        assert_eq!(nl(numbering, "a\nb1\nb2"), ["       a", "-3    \tb1", "2     \tb2"]);
+
+        assert!("x".parse::<NumberingStyle>().is_err());
+        assert!("p(".parse::<NumberingStyle>().is_err());
+        assert!("rr".parse::<NumberFormat>().is_err());
+
+        let mut numberer = LineNumberer::new(Numbering { start: i64::MAX, ..Default::default() });
+        assert_eq!(numberer.number_line(b"x").unwrap().number, Some(i64::MAX));
+        assert!(numberer.number_line(b"y").is_err());
+    }
+
+    #[test]
+    fn test_nl_delimiter() {
+        // One character is completed with ':'; empty turns sections off
+        let numbering = Numbering { delimiter: b"@".to_vec(), ..Default::default() };
+        assert_eq!(nl(numbering.clone(), "a\n@@\nb"), ["     1\ta", "     2\t@@", "     3\tb"]);
+        assert_eq!(nl(numbering, "a\n@:@:\nb"), ["     1\ta", "", "     1\tb"]);
+        let numbering = Numbering { delimiter: Vec::new(), ..Default::default() };
+        assert_eq!(nl(numbering, "\\:\\:"), ["     1\t\\:\\:"]);
+    }
+
+    fn fold(folder: Folder, line: &str) -> Vec<&str> {
+        folder.fold(line.as_bytes()).into_iter().map(|range| &line[range]).collect()
+    }
+
+    #[test]
+    fn test_fold() {
+        let folder = Folder { width: 5, count_bytes: false, break_spaces: false };
+        assert_eq!(fold(folder, "abcdefghijkl"), ["abcde", "fghij", "kl"]);
+        assert_eq!(fold(folder, "abcde"), ["abcde"]);
+        assert_eq!(fold(folder, ""), [""]);
+        assert_eq!(fold(folder, "ab\tcd"), ["ab", "\t", "cd"]);
+        assert_eq!(fold(folder, "éééééé"), ["ééééé", "é"]);
+
+        // -b counts bytes, so tabs and multi-byte characters take less room
+        let bytes = Folder { count_bytes: true, ..folder };
+        assert_eq!(fold(bytes, "ab\tcdef"), ["ab\tcd", "ef"]);
+        assert_eq!(bytes.fold("éééé".as_bytes()), [0..5, 5..8]);
+
+        // A tab wider than the width is put on a line by itself
+        let narrow = Folder { width: 3, ..folder };
+        assert_eq!(fold(narrow, "\tab"), ["\t", "ab"]);
+    }
+
+    #[test]
+    fn test_fold_at_spaces() {
+        let folder = Folder { width: 10, count_bytes: false, break_spaces: true };
+        assert_eq!(fold(folder, "the quick brown fox jumps"), ["the quick ", "brown fox ", "jumps"]);
+        assert_eq!(fold(folder, "abcdefghijklmno pq"), ["abcdefghij", "klmno pq"]);
+        let pieces = folder.fold(b"one two three four five six");
+        assert_eq!(pieces.first().unwrap().start, 0);
+        assert!(pieces.windows(2).all(|w| w[0].end == w[1].start));
+        assert_eq!(pieces.last().unwrap().end, 27);
+    }
+}
@@ -0,0 +1,152 @@
+//! Concurrency and buffer sizes tuned to the machine
+//!
+//! Async file operations run `concurrency` at a time and copy through
+//! buffers of `buffer_size` bytes. Each value is taken from the first of:
+//!
+//! 1. the command line (`-j`)
+//! 2. the configuration (`concurrency`, `tuning.buffer_size`, or their
+//!    `AI_COREUTILS_*` variables)
+//! 3. the machine profile `ai-bench --save` wrote (see [`crate::bench`])
+//! 4. the built-in defaults, 10 and 8 KiB
+//!
+//! With `tuning.mode = "measure"` (`AI_COREUTILS_TUNING=measure`) a missing
+//! profile is measured with ai-bench's quick grep and copy suites on first
+//! use, about a second, and saved for later runs. `off` skips the profile.
+//!
+//! Every value chosen is noted in [`crate::telemetry`], so summary records
+//! show the parameters a run used and where they came from; utilities
+//! without one write a `tuning` metadata record ([`record`]).
+
+use crate::bench::{self, BenchOptions, Profile};
+use crate::config::{self, TuningMode, DEFAULT_CONCURRENCY};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Buffer size when neither configuration nor a machine profile sets one
+pub const DEFAULT_BUFFER_SIZE: usize = 8192;
+
+static PROFILE: OnceLock<Option<(Profile, &'static str)>> = OnceLock::new();
+
+/// Where the machine profile is read from and saved to
+pub fn profile_path() -> Option<PathBuf> {
+    config::get().tuning.profile.clone().or_else(Profile::default_path)
+}
+
+/// The machine profile, and whether it was `profile` (saved) or `measured`
+/// by this process
+///
+/// Loaded, or measured, once per process.
+pub fn profile() -> Option<&'static (Profile, &'static str)> {
+    PROFILE
+        .get_or_init(|| {
+            let mode = config::get().tuning.mode;
+            if mode == TuningMode::Off {
+                return None;
+            }
+            let path = profile_path()?;
+            if let Ok(profile) = Profile::load(&path) {
+                return Some((profile, "profile"));
+            }
+            if mode != TuningMode::Measure {
+                return None;
+            }
+            let profile = measure()?;
+            // A profile that cannot be saved is still good for this run
+            let _ = profile.save(&path);
+            Some((profile, "measured"))
+        })
+        .as_ref()
+}
+
+/// Concurrent file operations to run, preferring `cli`
+pub fn concurrency(cli: Option<usize>) -> usize {
+    let profile = || profile().and_then(|(profile, source)| Some((profile.concurrency?, *source)));
+    resolve("concurrency", cli, config::get().concurrency, profile, DEFAULT_CONCURRENCY)
+}
+
+/// I/O buffer size in bytes, preferring `cli`
+pub fn buffer_size(cli: Option<usize>) -> usize {
+    let profile = || profile().and_then(|(profile, source)| Some((profile.buffer_size?, *source)));
+    resolve("buffer_size", cli, config::get().tuning.buffer_size, profile, DEFAULT_BUFFER_SIZE)
+}
+
+/// The `tuning` metadata record: every parameter chosen so far, with its
+/// value and source
+///
+/// For utilities that write no summary record to carry them in telemetry.
+pub fn record() -> serde_json::Value {
+    let mut record = serde_json::json!({"type": "tuning"});
+    for (name, tuned) in crate::telemetry::tuning() {
+        record[name] = serde_json::json!(tuned);
+    }
+    record
+}
+
+/// The first value set, and where it came from; the profile is only
+/// consulted when nothing is set explicitly
+fn choose(
+    cli: Option<usize>,
+    config: Option<usize>,
+    profile: impl FnOnce() -> Option<(usize, &'static str)>,
+    default: usize,
+) -> (usize, &'static str) {
+    match (cli, config) {
+        (Some(value), _) => (value, "cli"),
+        (None, Some(value)) => (value, "config"),
+        (None, None) => profile().unwrap_or((default, "default")),
+    }
+}
+
+fn resolve(
+    name: &'static str,
+    cli: Option<usize>,
+    config: Option<usize>,
+    profile: impl FnOnce() -> Option<(usize, &'static str)>,
+    default: usize,
+) -> usize {
+    let (value, source) = choose(cli, config, profile, default);
+    let value = value.max(1);
+    crate::telemetry::tuned(name, value, source);
+    value
+}
+
+/// Run the quick grep and copy suites in a directory of their own
+///
+/// On a thread of its own, since callers may already be inside a tokio
+/// runtime.
+fn measure() -> Option<Profile> {
+    std::thread::spawn(|| {
+        let options = BenchOptions::quick();
+        let dir = std::env::temp_dir().join(format!("ai-bench-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).ok()?;
+        let measured = tokio::runtime::Runtime::new().ok().and_then(|rt| {
+            rt.block_on(async {
+                let mut measurements = bench::grep_suite(&options, &dir, bench::available_cpus()).await.ok()?;
+                measurements.extend(bench::copy_suite(&options, &dir).await.ok()?);
+                Some(measurements)
+            })
+        });
+        let _ = std::fs::remove_dir_all(&dir);
+        Some(Profile::from_measurements(measured?))
+    })
+    .join()
+    .ok()
+    .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose() {
+        let profile = || Some((4, "profile"));
+        assert_eq!(choose(Some(2), Some(3), profile, 10), (2, "cli"));
+        assert_eq!(choose(None, Some(3), profile, 10), (3, "config"));
+        assert_eq!(choose(None, None, profile, 10), (4, "profile"));
+        assert_eq!(choose(None, None, || None, 10), (10, "default"));
+
+        // The profile is not loaded when a value is set
+        assert_eq!(choose(Some(2), None, || panic!("profile loaded"), 10), (2, "cli"));
+    }
+}
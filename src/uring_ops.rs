@@ -0,0 +1,377 @@
+//! io_uring file operations (Linux)
+//!
+//! Enabled with the `io-uring` feature. File reads and copies run on
+//! `tokio-uring`, and metadata lookups are submitted to the kernel in
+//! batches of `statx` requests, so agents reading thousands of small files
+//! pay one syscall per batch instead of one per operation.
+//!
+//! This is a standalone API: [`crate::async_ops`] and the utilities keep
+//! using tokio whether or not the feature is enabled. `tokio-uring` needs
+//! its own single-threaded runtime, so the async functions here must run
+//! inside [`tokio_uring::start`]. The batch entry
+//! points ([`read_files`], [`stat_batch`], [`walk_dir`]) manage the ring
+//! themselves and fall back to `std::fs` when io_uring is unavailable (old
+//! kernels, seccomp-restricted containers).
+
+use crate::error::{AiCoreutilsError, Result};
+use futures::stream::{self, StreamExt};
+use io_uring::{opcode, types, IoUring};
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use tokio_uring::buf::IoBuf;
+
+/// Buffer size for io_uring reads and copies
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Number of `statx` requests submitted per batch
+const STAT_BATCH_SIZE: usize = 256;
+
+/// Check whether the kernel allows creating an io_uring instance
+pub fn is_supported() -> bool {
+    IoUring::new(2).is_ok()
+}
+
+/// Read a whole file through io_uring
+///
+/// Must be called inside a `tokio_uring` runtime.
+pub async fn uring_read_file(path: &Path) -> Result<Vec<u8>> {
+    let file = tokio_uring::fs::File::open(path)
+        .await
+        .map_err(AiCoreutilsError::Io)?;
+
+    let mut data = Vec::new();
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut pos = 0u64;
+
+    loop {
+        let (result, buf) = file.read_at(buffer, pos).await;
+        let n = result.map_err(AiCoreutilsError::Io)?;
+        if n == 0 {
+            buffer = buf;
+            break;
+        }
+        data.extend_from_slice(&buf[..n]);
+        pos += n as u64;
+        buffer = buf;
+    }
+    drop(buffer);
+
+    file.close().await.map_err(AiCoreutilsError::Io)?;
+    Ok(data)
+}
+
+/// Copy a file through io_uring, returning the number of bytes copied
+///
+/// Must be called inside a `tokio_uring` runtime.
+pub async fn uring_copy_file(src: &Path, dest: &Path) -> Result<u64> {
+    let source = tokio_uring::fs::File::open(src)
+        .await
+        .map_err(AiCoreutilsError::Io)?;
+    let target = tokio_uring::fs::File::create(dest)
+        .await
+        .map_err(AiCoreutilsError::Io)?;
+
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut pos = 0u64;
+
+    loop {
+        let (result, buf) = source.read_at(buffer, pos).await;
+        let n = result.map_err(AiCoreutilsError::Io)?;
+        if n == 0 {
+            break;
+        }
+
+        // write_at may be partial; keep writing the remainder
+        let mut chunk = buf;
+        chunk.truncate(n);
+        let mut written = 0;
+        while written < n {
+            let (result, slice) = target.write_at(chunk.slice(written..), pos + written as u64).await;
+            written += result.map_err(AiCoreutilsError::Io)?;
+            chunk = slice.into_inner();
+        }
+
+        pos += n as u64;
+        chunk.resize(CHUNK_SIZE, 0);
+        buffer = chunk;
+    }
+
+    target.sync_all().await.map_err(AiCoreutilsError::Io)?;
+    target.close().await.map_err(AiCoreutilsError::Io)?;
+    source.close().await.map_err(AiCoreutilsError::Io)?;
+    Ok(pos)
+}
+
+/// Read many files concurrently on a dedicated io_uring runtime
+///
+/// Results are returned in input order. Falls back to `std::fs::read` if
+/// io_uring is unavailable.
+pub fn read_files(paths: &[PathBuf], max_concurrent: usize) -> Vec<Result<Vec<u8>>> {
+    if !is_supported() {
+        return paths
+            .iter()
+            .map(|p| std::fs::read(p).map_err(AiCoreutilsError::Io))
+            .collect();
+    }
+
+    tokio_uring::start(async {
+        let mut results: Vec<(usize, Result<Vec<u8>>)> = stream::iter(paths.iter().enumerate())
+            .map(|(i, path)| async move { (i, uring_read_file(path).await) })
+            .buffer_unordered(max_concurrent.max(1))
+            .collect()
+            .await;
+        results.sort_by_key(|(i, _)| *i);
+        results.into_iter().map(|(_, r)| r).collect()
+    })
+}
+
+/// File metadata returned by [`stat_batch`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UringStat {
+    /// Size in bytes
+    pub size: u64,
+    /// File type and permission bits (`st_mode`)
+    pub mode: u32,
+    /// Modification time (seconds since the Unix epoch)
+    pub mtime: i64,
+}
+
+impl UringStat {
+    /// Whether this is a directory
+    pub fn is_dir(&self) -> bool {
+        self.mode & libc::S_IFMT == libc::S_IFDIR
+    }
+
+    /// Whether this is a regular file
+    pub fn is_file(&self) -> bool {
+        self.mode & libc::S_IFMT == libc::S_IFREG
+    }
+
+    /// Whether this is a symbolic link
+    pub fn is_symlink(&self) -> bool {
+        self.mode & libc::S_IFMT == libc::S_IFLNK
+    }
+}
+
+/// Stat many paths with batched `statx` submissions (symlinks not followed)
+///
+/// Results are returned in input order. Falls back to
+/// `std::fs::symlink_metadata` if io_uring is unavailable.
+pub fn stat_batch(paths: &[PathBuf]) -> Vec<Result<UringStat>> {
+    match IoUring::new(STAT_BATCH_SIZE as u32) {
+        Ok(mut ring) => paths
+            .chunks(STAT_BATCH_SIZE)
+            .flat_map(|chunk| stat_chunk(&mut ring, chunk))
+            .collect(),
+        Err(_) => paths.iter().map(|p| stat_fallback(p)).collect(),
+    }
+}
+
+fn stat_chunk(ring: &mut IoUring, paths: &[PathBuf]) -> Vec<Result<UringStat>> {
+    let names: Vec<Option<CString>> = paths
+        .iter()
+        .map(|p| CString::new(p.as_os_str().as_bytes()).ok())
+        .collect();
+    // SAFETY: statx is plain old data; the kernel fills it in
+    let mut buffers: Vec<libc::statx> = vec![unsafe { std::mem::zeroed() }; paths.len()];
+    let mut results: Vec<Option<Result<UringStat>>> = (0..paths.len()).map(|_| None).collect();
+
+    let mut submitted = 0;
+    for (i, name) in names.iter().enumerate() {
+        let Some(name) = name else {
+            results[i] = Some(Err(AiCoreutilsError::InvalidInput(format!(
+                "Path contains a NUL byte: {}",
+                paths[i].display()
+            ))));
+            continue;
+        };
+
+        let entry = opcode::Statx::new(
+            types::Fd(libc::AT_FDCWD),
+            name.as_ptr(),
+            &mut buffers[i] as *mut libc::statx as *mut types::statx,
+        )
+        .flags(libc::AT_SYMLINK_NOFOLLOW)
+        .mask(libc::STATX_TYPE | libc::STATX_MODE | libc::STATX_SIZE | libc::STATX_MTIME)
+        .build()
+        .user_data(i as u64);
+
+        // SAFETY: `names` and `buffers` outlive the submission, which is
+        // waited on below before either is dropped
+        if unsafe { ring.submission().push(&entry) }.is_err() {
+            results[i] = Some(stat_fallback(&paths[i]));
+            continue;
+        }
+        submitted += 1;
+    }
+
+    if submitted > 0 {
+        loop {
+            match ring.submit_and_wait(submitted) {
+                Ok(_) => break,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => {
+                    // Requests may still be in flight, so the kernel could
+                    // write into these buffers later: leak them rather than
+                    // free memory it still references
+                    std::mem::forget(buffers);
+                    std::mem::forget(names);
+                    return paths.iter().map(|p| stat_fallback(p)).collect();
+                }
+            }
+        }
+    }
+
+    for cqe in ring.completion() {
+        let i = cqe.user_data() as usize;
+        results[i] = Some(if cqe.result() < 0 {
+            Err(AiCoreutilsError::Io(std::io::Error::from_raw_os_error(-cqe.result())))
+        } else {
+            let st = &buffers[i];
+            Ok(UringStat {
+                size: st.stx_size,
+                mode: st.stx_mode as u32,
+                mtime: st.stx_mtime.tv_sec,
+            })
+        });
+    }
+
+    results
+        .into_iter()
+        .zip(paths)
+        .map(|(r, p)| r.unwrap_or_else(|| stat_fallback(p)))
+        .collect()
+}
+
+fn stat_fallback(path: &Path) -> Result<UringStat> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = std::fs::symlink_metadata(path).map_err(AiCoreutilsError::Io)?;
+    Ok(UringStat {
+        size: metadata.size(),
+        mode: metadata.mode(),
+        mtime: metadata.mtime(),
+    })
+}
+
+/// Recursively list a directory, statting each level in one batch
+///
+/// Returns every entry below `dir` (not `dir` itself) with its metadata.
+/// Symlinks are reported but not followed. Only an unreadable `dir` fails
+/// the walk; directories below it that cannot be read are skipped with an
+/// `UNREADABLE_ENTRY` or `PERMISSION_DENIED` warning, as in
+/// [`crate::fs_utils::DirWalk`].
+pub fn walk_dir(dir: &Path) -> Result<Vec<(PathBuf, UringStat)>> {
+    let mut entries = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        let children: Vec<PathBuf> = match std::fs::read_dir(&current) {
+            Ok(read_dir) => read_dir.filter_map(|e| e.ok().map(|e| e.path())).collect(),
+            Err(e) if current == dir => return Err(AiCoreutilsError::Io(e)),
+            Err(e) => {
+                crate::fs_utils::warn_unreadable(&current, &e)?;
+                continue;
+            }
+        };
+
+        for (path, stat) in children.iter().zip(stat_batch(&children)) {
+            let Ok(stat) = stat else { continue };
+            if stat.is_dir() {
+                pending.push(path.clone());
+            }
+            entries.push((path.clone(), stat));
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_read_and_copy() {
+        let temp_dir = TempDir::new().unwrap();
+        let src = temp_dir.path().join("src.bin");
+        let dest = temp_dir.path().join("dest.bin");
+        let data: Vec<u8> = (0..CHUNK_SIZE * 2 + 123).map(|i| i as u8).collect();
+        std::fs::write(&src, &data).unwrap();
+
+        if !is_supported() {
+            return;
+        }
+
+        tokio_uring::start(async {
+            assert_eq!(uring_read_file(&src).await.unwrap(), data);
+            assert_eq!(uring_copy_file(&src, &dest).await.unwrap(), data.len() as u64);
+        });
+        assert_eq!(std::fs::read(&dest).unwrap(), data);
+    }
+
+    #[test]
+    fn test_read_files_and_stat_batch() {
+        let temp_dir = TempDir::new().unwrap();
+        let paths: Vec<PathBuf> = (0..10)
+            .map(|i| {
+                let path = temp_dir.path().join(format!("f{}.txt", i));
+                std::fs::write(&path, "x".repeat(i)).unwrap();
+                path
+            })
+            .collect();
+
+        let contents = read_files(&paths, 4);
+        for (i, content) in contents.iter().enumerate() {
+            assert_eq!(content.as_ref().unwrap().len(), i);
+        }
+
+        let mut with_missing = paths.clone();
+        with_missing.push(temp_dir.path().join("missing"));
+        let stats = stat_batch(&with_missing);
+        assert_eq!(stats[3].as_ref().unwrap().size, 3);
+        assert!(stats[3].as_ref().unwrap().is_file());
+        assert!(stats[10].is_err());
+    }
+
+    #[test]
+    fn test_walk_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        std::fs::write(temp_dir.path().join("a.txt"), "a").unwrap();
+        std::fs::write(temp_dir.path().join("sub/b.txt"), "bb").unwrap();
+
+        let entries = walk_dir(temp_dir.path()).unwrap();
+        assert_eq!(entries.len(), 3);
+        let b = entries.iter().find(|(p, _)| p.ends_with("sub/b.txt")).unwrap();
+        assert_eq!(b.1.size, 2);
+    }
+
+    #[test]
+    fn test_walk_dir_skips_unreadable_subdirectories() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        assert!(walk_dir(&temp_dir.path().join("missing")).is_err());
+
+        let locked = temp_dir.path().join("locked");
+        std::fs::create_dir(&locked).unwrap();
+        std::fs::write(locked.join("hidden.txt"), "h").unwrap();
+        std::fs::write(temp_dir.path().join("a.txt"), "a").unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+
+        // Permissions do not stop root
+        let readable = std::fs::read_dir(&locked).is_ok();
+        let entries = walk_dir(temp_dir.path());
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+        if readable {
+            return;
+        }
+
+        let entries = entries.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().any(|(p, _)| p == &locked));
+    }
+}
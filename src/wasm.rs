@@ -0,0 +1,293 @@
+//! WebAssembly exports
+//!
+//! With the `wasm` feature, the library exports a small C ABI for
+//! `wasm32-wasip1` hosts, such as browsers and sandboxed agent runtimes, that
+//! cannot load native binaries. The `wasm/` package wraps it for JavaScript.
+//!
+//! The host copies inputs into linear memory with `aic_alloc` and releases
+//! them with `aic_free`. Every operation returns a result buffer: a
+//! little-endian `u32` length followed by that many bytes of one JSONL
+//! record, either `{"type":"result","data":...}` or
+//! `{"type":"error","message":...,"code":...}`. The host reads it and
+//! releases it with `aic_result_free`.
+//!
+//! Build the module with
+//! `cargo rustc --lib --release --target wasm32-wasip1 --features wasm --crate-type cdylib`,
+//! adding `-C target-feature=+simd128` to `RUSTFLAGS` for the SIMD128 code paths.
+
+use crate::error::{AiCoreutilsError, Result};
+use crate::hash::{Digest, HashAlgorithm};
+use crate::jsonl::JsonlRecord;
+use crate::ml_ops::{FileClassifier, PatternDetector};
+use crate::simd_ops::{SimdConfig, SimdEntropyCalculator, SimdTextProcessor};
+use serde_json::json;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Bytes before the record in a result buffer
+const LEN_PREFIX: usize = 4;
+
+/// Pattern detector shared by every call; compiling its regexes is the slow part
+fn detector() -> Result<&'static PatternDetector> {
+    static DETECTOR: OnceLock<std::result::Result<PatternDetector, String>> = OnceLock::new();
+    DETECTOR
+        .get_or_init(|| PatternDetector::new().map_err(|e| e.to_string()))
+        .as_ref()
+        .map_err(|e| AiCoreutilsError::Config(e.clone()))
+}
+
+/// The `len` bytes at `ptr`
+///
+/// # Safety
+///
+/// `ptr` must point to `len` readable bytes, such as a buffer from
+/// `aic_alloc`, that are not changed during the call.
+unsafe fn bytes<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(ptr, len)
+    }
+}
+
+/// The `len` bytes at `ptr` as UTF-8
+///
+/// # Safety
+///
+/// As for [`bytes`].
+unsafe fn text<'a>(ptr: *const u8, len: usize) -> Result<&'a str> {
+    std::str::from_utf8(bytes(ptr, len)).map_err(|e| AiCoreutilsError::InvalidInput(format!("not UTF-8: {}", e)))
+}
+
+/// A result buffer holding the record for `result`
+fn respond(result: Result<serde_json::Value>, code: &str) -> *mut u8 {
+    let record = match result {
+        Ok(data) => JsonlRecord::result(data),
+        Err(e) => JsonlRecord::error(e.to_string(), code),
+    };
+    let line = record
+        .to_jsonl()
+        .unwrap_or_else(|e| format!(r#"{{"type":"error","message":{},"code":"{}"}}"#, json!(e.to_string()), code));
+
+    let mut buffer = Vec::with_capacity(LEN_PREFIX + line.len());
+    buffer.extend_from_slice(&(line.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(line.as_bytes());
+    Box::into_raw(buffer.into_boxed_slice()) as *mut u8
+}
+
+/// Allocate `len` bytes for an input
+#[no_mangle]
+pub extern "C" fn aic_alloc(len: usize) -> *mut u8 {
+    let buffer = vec![0u8; len].into_boxed_slice();
+    Box::into_raw(buffer) as *mut u8
+}
+
+/// Release an input allocated by `aic_alloc`
+///
+/// # Safety
+///
+/// `ptr` and `len` must come from one `aic_alloc` call, and the buffer must
+/// not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn aic_free(ptr: *mut u8, len: usize) {
+    if !ptr.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)));
+    }
+}
+
+/// Release a result buffer
+///
+/// # Safety
+///
+/// `ptr` must come from one of the operations below, and the buffer must not
+/// be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn aic_result_free(ptr: *mut u8) {
+    if ptr.is_null() {
+        return;
+    }
+    let mut len = [0u8; LEN_PREFIX];
+    len.copy_from_slice(bytes(ptr, LEN_PREFIX));
+    let total = LEN_PREFIX + u32::from_le_bytes(len) as usize;
+    drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, total)));
+}
+
+/// Library version and SIMD code path
+#[no_mangle]
+pub extern "C" fn aic_info() -> *mut u8 {
+    respond(
+        Ok(json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "simd": SimdConfig::detect().path(),
+        })),
+        "INFO_ERROR",
+    )
+}
+
+/// Patterns (emails, URLs, secrets and so on) in UTF-8 text
+///
+/// # Safety
+///
+/// `text_ptr` must point to `text_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn aic_detect_patterns(text_ptr: *const u8, text_len: usize) -> *mut u8 {
+    let result = text(text_ptr, text_len)
+        .and_then(|text| Ok(serde_json::to_value(detector()?.detect_patterns(text))?));
+    respond(result, "PATTERN_ERROR")
+}
+
+/// Patterns, statistics and issues in UTF-8 text read from `path`
+///
+/// # Safety
+///
+/// Each pointer must point to its length in readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn aic_analyze_content(
+    text_ptr: *const u8,
+    text_len: usize,
+    path_ptr: *const u8,
+    path_len: usize,
+) -> *mut u8 {
+    let result = text(text_ptr, text_len).and_then(|text| {
+        let path = self::text(path_ptr, path_len)?;
+        Ok(serde_json::to_value(detector()?.analyze_content(text, Path::new(path))?)?)
+    });
+    respond(result, "ANALYZE_ERROR")
+}
+
+/// File type, MIME type and encoding of `content`, named `path`
+///
+/// # Safety
+///
+/// Each pointer must point to its length in readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn aic_classify(
+    path_ptr: *const u8,
+    path_len: usize,
+    content_ptr: *const u8,
+    content_len: usize,
+) -> *mut u8 {
+    let result = text(path_ptr, path_len).and_then(|path| {
+        Ok(serde_json::to_value(FileClassifier::classify(Path::new(path), bytes(content_ptr, content_len))?)?)
+    });
+    respond(result, "CLASSIFY_ERROR")
+}
+
+/// Line, word, byte and character counts, longest line, and Shannon
+/// entropy, of `data`
+///
+/// # Safety
+///
+/// `data_ptr` must point to `data_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn aic_text_metrics(data_ptr: *const u8, data_len: usize) -> *mut u8 {
+    let data = bytes(data_ptr, data_len);
+    let metrics = SimdTextProcessor::new().analyze(data);
+    let result = json!({
+        "lines": metrics.lines,
+        "words": metrics.words,
+        "bytes": metrics.bytes,
+        "chars": metrics.chars,
+        "max_line_length": metrics.max_line_length,
+        "entropy": SimdEntropyCalculator::new().calculate_entropy(data),
+    });
+    respond(Ok(result), "METRICS_ERROR")
+}
+
+/// Hex digest of `data` with the named algorithm (`crc32`, `xxh64`, `xxh3`)
+///
+/// # Safety
+///
+/// Each pointer must point to its length in readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn aic_hash(
+    algorithm_ptr: *const u8,
+    algorithm_len: usize,
+    data_ptr: *const u8,
+    data_len: usize,
+) -> *mut u8 {
+    let result = text(algorithm_ptr, algorithm_len).and_then(|name| {
+        let algorithm: HashAlgorithm = name.parse().map_err(AiCoreutilsError::InvalidInput)?;
+        Ok(json!({
+            "algorithm": algorithm.name(),
+            "hex": algorithm.hex_digest(bytes(data_ptr, data_len)),
+        }))
+    });
+    respond(result, "HASH_ERROR")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    /// Run an operation on the given inputs and parse its record
+    fn call(inputs: &[&[u8]], op: impl FnOnce(&[(*const u8, usize)]) -> *mut u8) -> Value {
+        let buffers: Vec<(*mut u8, usize)> = inputs
+            .iter()
+            .map(|input| {
+                let ptr = aic_alloc(input.len());
+                unsafe { std::ptr::copy_nonoverlapping(input.as_ptr(), ptr, input.len()) };
+                (ptr, input.len())
+            })
+            .collect();
+        let args: Vec<(*const u8, usize)> = buffers.iter().map(|&(ptr, len)| (ptr as *const u8, len)).collect();
+
+        let result = op(&args);
+        let record = unsafe {
+            let len = u32::from_le_bytes(bytes(result, LEN_PREFIX).try_into().unwrap()) as usize;
+            let record: Value = serde_json::from_slice(bytes(result.add(LEN_PREFIX), len)).unwrap();
+            aic_result_free(result);
+            record
+        };
+        for (ptr, len) in buffers {
+            unsafe { aic_free(ptr, len) };
+        }
+        record
+    }
+
+    #[test]
+    fn test_detect_patterns() {
+        let record = call(&[b"Contact admin@example.com"], |a| unsafe { aic_detect_patterns(a[0].0, a[0].1) });
+        assert_eq!(record["type"], "result");
+        assert_eq!(record["data"][0]["pattern_type"], "Email");
+        assert_eq!(record["data"][0]["matched_text"], "admin@example.com");
+
+        let record = call(&[b"\xff\xfe"], |a| unsafe { aic_detect_patterns(a[0].0, a[0].1) });
+        assert_eq!(record["type"], "error");
+        assert_eq!(record["code"], "PATTERN_ERROR");
+    }
+
+    #[test]
+    fn test_classify_and_analyze() {
+        let record = call(&[b"main.rs", b"fn main() {}\n"], |a| unsafe { aic_classify(a[0].0, a[0].1, a[1].0, a[1].1) });
+        assert_eq!(record["data"]["is_binary"], false);
+
+        let record = call(&[b"see https://example.com\n", b"notes.txt"], |a| unsafe {
+            aic_analyze_content(a[0].0, a[0].1, a[1].0, a[1].1)
+        });
+        assert_eq!(record["type"], "result");
+        assert_eq!(record["data"]["patterns_by_type"]["Url"], 1);
+    }
+
+    #[test]
+    fn test_text_metrics_and_hash() {
+        let record = call(&[b"one two\nthree\n"], |a| unsafe { aic_text_metrics(a[0].0, a[0].1) });
+        assert_eq!(record["data"]["lines"], 2);
+        assert_eq!(record["data"]["words"], 3);
+        assert_eq!(record["data"]["bytes"], 14);
+
+        let record = call(&[b"crc32", b""], |a| unsafe { aic_hash(a[0].0, a[0].1, a[1].0, a[1].1) });
+        assert_eq!(record["data"]["hex"], "00000000");
+
+        let record = call(&[b"md5", b""], |a| unsafe { aic_hash(a[0].0, a[0].1, a[1].0, a[1].1) });
+        assert_eq!(record["code"], "HASH_ERROR");
+    }
+
+    #[test]
+    fn test_info() {
+        let record = call(&[], |_| aic_info());
+        assert_eq!(record["data"]["version"], env!("CARGO_PKG_VERSION"));
+        assert!(record["data"]["simd"].is_string());
+    }
+}
@@ -0,0 +1,323 @@
+//! Polling file watcher core for ai-watch
+//!
+//! A [`Snapshot`] records the size, mtime and inode of every file under a
+//! set of paths; diffing two snapshots gives create, modify and delete
+//! events. Polling needs no platform notification API and cannot overflow
+//! an event queue, at the cost of a walk per poll.
+//!
+//! [`Debouncer`] collects events until the tree has been quiet for a delay,
+//! then releases them as one batch with each path's changes folded into a
+//! single event: a file created and then modified is reported as created,
+//! and one created and deleted within a batch is not reported at all.
+
+use crate::error::{AiCoreutilsError, Result};
+use crate::fs_utils::{DirWalk, SymlinkPolicy, WalkEvent};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Kind of change to a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    /// The file appeared
+    Create,
+    /// The file's size, mtime or inode changed
+    Modify,
+    /// The file disappeared
+    Delete,
+}
+
+impl EventKind {
+    /// Name used on the command line and in JSONL output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::Create => "create",
+            EventKind::Modify => "modify",
+            EventKind::Delete => "delete",
+        }
+    }
+
+    /// The net effect of `self` followed by `next` on one path
+    ///
+    /// `None` when the changes cancel out (created, then deleted).
+    fn then(self, next: EventKind) -> Option<EventKind> {
+        match (self, next) {
+            (EventKind::Create, EventKind::Delete) => None,
+            (EventKind::Create, _) => Some(EventKind::Create),
+            (EventKind::Delete, EventKind::Create) => Some(EventKind::Modify),
+            (_, next) => Some(next),
+        }
+    }
+}
+
+impl std::str::FromStr for EventKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "create" => Ok(EventKind::Create),
+            "modify" => Ok(EventKind::Modify),
+            "delete" => Ok(EventKind::Delete),
+            _ => Err(format!(
+                "Invalid event kind: {} (expected create, modify or delete)",
+                s
+            )),
+        }
+    }
+}
+
+/// A change to one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchEvent {
+    /// Path of the file, as reached from the watched path
+    pub path: PathBuf,
+    /// What happened to it
+    pub kind: EventKind,
+    /// When the change was first seen
+    pub detected_at: SystemTime,
+}
+
+/// What identifies a version of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileState {
+    size: u64,
+    mtime: Option<SystemTime>,
+    inode: u64,
+}
+
+impl FileState {
+    fn of(metadata: &fs::Metadata) -> Self {
+        #[cfg(unix)]
+        let inode = std::os::unix::fs::MetadataExt::ino(metadata);
+        #[cfg(not(unix))]
+        let inode = 0;
+        Self {
+            size: metadata.len(),
+            mtime: metadata.modified().ok(),
+            inode,
+        }
+    }
+}
+
+/// The files under a set of watched paths at one moment
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    files: HashMap<PathBuf, FileState>,
+}
+
+impl Snapshot {
+    /// Record every file under `paths`
+    ///
+    /// Watched paths that do not exist (yet) contribute nothing; failures
+    /// to read a directory are returned alongside the partial snapshot.
+    pub fn take(paths: &[PathBuf], policy: SymlinkPolicy) -> (Self, Vec<AiCoreutilsError>) {
+        let mut snapshot = Self::default();
+        let mut errors = Vec::new();
+
+        for root in paths {
+            if fs::symlink_metadata(root).is_err() {
+                continue;
+            }
+            for event in DirWalk::new(root, policy) {
+                match event {
+                    Ok(WalkEvent::Entry(entry)) if entry.is_file() => {
+                        if let Some(metadata) = entry.metadata() {
+                            let state = FileState::of(metadata);
+                            snapshot.files.insert(entry.into_path(), state);
+                        }
+                    }
+                    Ok(_) => {}
+                    // The walk root vanishing between the check and the walk is not an error
+                    Err(_) if !root.exists() => {}
+                    Err(e) => errors.push(e),
+                }
+            }
+        }
+
+        (snapshot, errors)
+    }
+
+    /// Number of files recorded
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether no files were recorded
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Changes from `self` to `newer`, sorted by path
+    pub fn diff(&self, newer: &Snapshot) -> Vec<WatchEvent> {
+        let now = SystemTime::now();
+        let event = |path: &PathBuf, kind| WatchEvent {
+            path: path.clone(),
+            kind,
+            detected_at: now,
+        };
+
+        let mut events: Vec<WatchEvent> = newer
+            .files
+            .iter()
+            .filter_map(|(path, state)| match self.files.get(path) {
+                None => Some(event(path, EventKind::Create)),
+                Some(old) if old != state => Some(event(path, EventKind::Modify)),
+                Some(_) => None,
+            })
+            .chain(
+                self.files
+                    .keys()
+                    .filter(|path| !newer.files.contains_key(*path))
+                    .map(|path| event(path, EventKind::Delete)),
+            )
+            .collect();
+        events.sort_by(|a, b| a.path.cmp(&b.path));
+        events
+    }
+}
+
+/// Holds events until changes stop for a while, folding them per path
+#[derive(Debug)]
+pub struct Debouncer {
+    delay: Duration,
+    pending: BTreeMap<PathBuf, WatchEvent>,
+    last_change: Option<Instant>,
+}
+
+impl Debouncer {
+    /// Release batches once no change has been seen for `delay`
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            pending: BTreeMap::new(),
+            last_change: None,
+        }
+    }
+
+    /// Add an event seen at `now`
+    pub fn push(&mut self, event: WatchEvent, now: Instant) {
+        self.last_change = Some(now);
+        match self.pending.remove(&event.path) {
+            None => {
+                self.pending.insert(event.path.clone(), event);
+            }
+            Some(earlier) => {
+                if let Some(kind) = earlier.kind.then(event.kind) {
+                    let merged = WatchEvent { kind, ..earlier };
+                    self.pending.insert(merged.path.clone(), merged);
+                }
+            }
+        }
+    }
+
+    /// The pending events, sorted by path, if the quiet period has passed
+    ///
+    /// A batch whose changes all cancelled out is released as empty.
+    pub fn ready(&mut self, now: Instant) -> Option<Vec<WatchEvent>> {
+        let last = self.last_change?;
+        if now.duration_since(last) < self.delay {
+            return None;
+        }
+        self.last_change = None;
+        Some(std::mem::take(&mut self.pending).into_values().collect())
+    }
+}
+
+/// Check that every watched path exists
+pub fn validate_paths(paths: &[PathBuf]) -> Result<()> {
+    match paths.iter().find(|p| fs::symlink_metadata(p).is_err()) {
+        Some(missing) => Err(AiCoreutilsError::PathNotFound(missing.clone())),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(path: &str, kind: EventKind) -> WatchEvent {
+        WatchEvent {
+            path: PathBuf::from(path),
+            kind,
+            detected_at: SystemTime::UNIX_EPOCH,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_diff() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = [dir.path().to_path_buf()];
+        fs::write(dir.path().join("kept"), "same").unwrap();
+        fs::write(dir.path().join("changed"), "old").unwrap();
+        fs::write(dir.path().join("deleted"), "x").unwrap();
+        let (before, errors) = Snapshot::take(&paths, SymlinkPolicy::Never);
+        assert!(errors.is_empty());
+        assert_eq!(before.len(), 3);
+
+        fs::write(dir.path().join("changed"), "newer").unwrap();
+        fs::remove_file(dir.path().join("deleted")).unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub/created"), "").unwrap();
+        let (after, _) = Snapshot::take(&paths, SymlinkPolicy::Never);
+
+        let changes: Vec<_> = before
+            .diff(&after)
+            .into_iter()
+            .map(|e| (e.path.strip_prefix(dir.path()).unwrap().to_path_buf(), e.kind))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (PathBuf::from("changed"), EventKind::Modify),
+                (PathBuf::from("deleted"), EventKind::Delete),
+                (PathBuf::from("sub/created"), EventKind::Create),
+            ]
+        );
+        assert!(after.diff(&after).is_empty());
+
+        // A watched file that does not exist yet is picked up once created
+        let file = [dir.path().join("later")];
+        let (empty, _) = Snapshot::take(&file, SymlinkPolicy::Never);
+        fs::write(&file[0], "").unwrap();
+        let (created, _) = Snapshot::take(&file, SymlinkPolicy::Never);
+        assert_eq!(empty.diff(&created)[0].kind, EventKind::Create);
+    }
+
+    #[test]
+    fn test_debouncer_folds_changes() {
+        let start = Instant::now();
+        let delay = Duration::from_millis(100);
+        let mut debouncer = Debouncer::new(delay);
+        assert!(debouncer.ready(start + delay).is_none());
+
+        debouncer.push(event("a", EventKind::Create), start);
+        debouncer.push(event("b", EventKind::Modify), start);
+        debouncer.push(event("c", EventKind::Delete), start);
+        debouncer.push(event("a", EventKind::Modify), start + delay / 2);
+        debouncer.push(event("b", EventKind::Delete), start + delay / 2);
+        debouncer.push(event("c", EventKind::Create), start + delay / 2);
+
+        // Not ready until the delay has passed since the last change
+        assert!(debouncer.ready(start + delay).is_none());
+        let batch = debouncer.ready(start + delay * 2).unwrap();
+        let kinds: Vec<_> = batch.iter().map(|e| (e.path.to_str().unwrap(), e.kind)).collect();
+        assert_eq!(
+            kinds,
+            [("a", EventKind::Create), ("b", EventKind::Delete), ("c", EventKind::Modify)]
+        );
+        assert!(debouncer.ready(start + delay * 3).is_none());
+
+        // Created and deleted within one batch cancels out
+        debouncer.push(event("tmp", EventKind::Create), start);
+        debouncer.push(event("tmp", EventKind::Delete), start);
+        assert_eq!(debouncer.ready(start + delay), Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_event_kind_parsing() {
+        assert_eq!("modify".parse::<EventKind>(), Ok(EventKind::Modify));
+        assert!("rename".parse::<EventKind>().is_err());
+        assert!(validate_paths(&[PathBuf::from("/nonexistent/watch")]).is_err());
+    }
+}
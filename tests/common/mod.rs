@@ -0,0 +1,34 @@
+//! Helpers shared by the integration tests
+
+use serde_json::Value;
+use std::ffi::OsStr;
+use std::process::Command;
+
+/// Run `bin` with `args`, returning its JSONL records and exit code
+pub fn run<S: AsRef<OsStr>>(bin: &str, args: &[S]) -> (Vec<Value>, i32) {
+    run_command(Command::new(bin).args(args))
+}
+
+/// Run a prepared command, such as one with environment variables set,
+/// returning its JSONL records and exit code
+pub fn run_command(command: &mut Command) -> (Vec<Value>, i32) {
+    let output = command.output().unwrap();
+    (records(&output.stdout), output.status.code().unwrap())
+}
+
+/// Parse JSONL output, one record per line
+pub fn records(stdout: &[u8]) -> Vec<Value> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+/// Payloads (`data` or `info`) of the records whose payload type is `kind`
+pub fn of_type<'a>(records: &'a [Value], kind: &str) -> Vec<&'a Value> {
+    records
+        .iter()
+        .filter_map(|r| r.get("data").or_else(|| r.get("info")))
+        .filter(|r| r["type"] == kind)
+        .collect()
+}
@@ -0,0 +1,17 @@
+use std::fmt;
+
+/// Parse errors
+#[derive(Debug)]
+pub enum ParseError {
+    Empty,
+    Invalid(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "empty input"),
+            ParseError::Invalid(s) => write!(f, "invalid input: {}", s),
+        }
+    }
+}
@@ -2,6 +2,8 @@
 //!
 //! These tests verify the basic functionality of the core utilities.
 
+mod common;
+
 use std::fs;
 use tempfile::TempDir;
 
@@ -64,3 +66,2494 @@ fn test_jsonl_output() {
     assert!(jsonl.contains("\"type\":\"error\""));
     assert!(jsonl.contains("Test error"));
 }
+
+/// ai-grep modes checked against GNU grep on `tests/fixtures/grep`
+mod grep_vs_gnu {
+    use super::common::run;
+    use serde_json::Value;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    const FILES: [&str; 4] = ["app.log", "notes.md", "lib.rs", "empty.txt"];
+    const PATTERNS: [&str; 7] = ["error", "ERROR", "database", "école", "INFO", "zzz", ""];
+
+    /// Output lines or records of one run, with its exit code
+    type Run<T> = (Vec<T>, i32);
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/grep").join(name)
+    }
+
+    fn fixtures() -> Vec<String> {
+        FILES.iter().map(|f| fixture(f).display().to_string()).collect()
+    }
+
+    /// Run GNU grep as `grep -F -H <args>`; `None` if it is not available
+    fn gnu_grep(args: &[&str]) -> Option<Run<String>> {
+        let version = Command::new("grep").arg("--version").output().ok()?;
+        if !String::from_utf8_lossy(&version.stdout).contains("GNU") {
+            return None;
+        }
+        let output = Command::new("grep")
+            .env("LC_ALL", "C.UTF-8")
+            .args(["-F", "-H"])
+            .args(args)
+            .output()
+            .ok()?;
+        let lines = String::from_utf8_lossy(&output.stdout).lines().map(String::from).collect();
+        Some((lines, output.status.code()?))
+    }
+
+    /// Run ai-grep, returning its records without the closing `grep_summary`
+    fn ai_grep(args: &[&str]) -> Run<Value> {
+        let (records, _, code) = ai_grep_summary(args);
+        (records, code)
+    }
+
+    fn ai_grep_summary(args: &[&str]) -> (Vec<Value>, Option<Value>, i32) {
+        let (mut records, code) = run(env!("CARGO_BIN_EXE_ai-grep"), args);
+        let summary = records
+            .last()
+            .is_some_and(|r| r["data"]["type"] == "grep_summary")
+            .then(|| records.pop().unwrap()["data"].clone());
+        (records, summary, code)
+    }
+
+    /// Run both with `flags`, a pattern and every fixture
+    fn both(flags: &[&str], pattern: &str) -> Option<(Run<String>, Run<Value>)> {
+        let files = fixtures();
+        let mut args: Vec<&str> = flags.to_vec();
+        args.push("--");
+        args.push(pattern);
+        args.extend(files.iter().map(String::as_str));
+
+        let gnu = gnu_grep(&args)?;
+        // ai-grep takes no `--`; patterns here never start with '-'
+        args.retain(|a| *a != "--");
+        args.insert(0, "-F");
+        Some((gnu, ai_grep(&args)))
+    }
+
+    #[test]
+    fn test_count() {
+        for flags in [&["-c"][..], &["-c", "-v"], &["-c", "-i"], &["-c", "-m", "2"], &["-c", "-i", "-v", "-m", "3"]] {
+            for pattern in PATTERNS {
+                // GNU grep prints no counts at all for `-c -v ''`
+                if pattern.is_empty() && flags.contains(&"-v") {
+                    continue;
+                }
+                let Some(((gnu, gnu_code), (ours, code))) = both(flags, pattern) else {
+                    return;
+                };
+                let ours: Vec<String> = ours
+                    .iter()
+                    .map(|r| format!("{}:{}", r["data"]["file"].as_str().unwrap(), r["data"]["match_count"]))
+                    .collect();
+                assert_eq!(ours, gnu, "{:?} {:?}", flags, pattern);
+                assert_eq!(code, gnu_code, "{:?} {:?}", flags, pattern);
+            }
+        }
+    }
+
+    #[test]
+    fn test_files_with_and_without_match() {
+        for flags in [&["-l"][..], &["-L"], &["-l", "-i"], &["-L", "-v"], &["-l", "-c"], &["-L", "-m", "0"]] {
+            for pattern in PATTERNS {
+                let Some(((gnu, gnu_code), (ours, code))) = both(flags, pattern) else {
+                    return;
+                };
+                let ours: Vec<&str> = ours.iter().map(|r| r["data"]["file"].as_str().unwrap()).collect();
+                assert_eq!(ours, gnu, "{:?} {:?}", flags, pattern);
+                assert_eq!(code, gnu_code, "{:?} {:?}", flags, pattern);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lines_and_max_count() {
+        for flags in [&["-n"][..], &["-n", "-i", "-m", "1"], &["-n", "-v", "-m", "2"], &["-n", "-m", "0"]] {
+            for pattern in PATTERNS {
+                let Some(((gnu, gnu_code), (ours, code))) = both(flags, pattern) else {
+                    return;
+                };
+                // line_content is "N:line" with -n, as GNU prints after the file name
+                let ours: Vec<String> = ours
+                    .iter()
+                    .map(|r| format!("{}:{}", r["file"].as_str().unwrap(), r["line_content"].as_str().unwrap()))
+                    .collect();
+                assert_eq!(ours, gnu, "{:?} {:?}", flags, pattern);
+                assert_eq!(code, gnu_code, "{:?} {:?}", flags, pattern);
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_mmap_reads_the_same() {
+        let files = fixtures();
+        for pattern in PATTERNS {
+            let mut args = vec!["-c", "-F", pattern];
+            args.extend(files.iter().map(String::as_str));
+            let (mapped, code) = ai_grep(&args);
+            args.insert(0, "--no-mmap");
+            let (read, read_code) = ai_grep(&args);
+
+            let counts = |records: &[Value]| -> Vec<Value> { records.iter().map(|r| r["data"].clone()).collect() };
+            assert_eq!(counts(&read), counts(&mapped), "{:?}", pattern);
+            assert_eq!(read_code, code, "{:?}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_context_lines_name_their_match() {
+        let log = fixture("app.log").display().to_string();
+        let (records, code) = ai_grep(&["-F", "-C", "1", "ERROR", &log]);
+        assert_eq!(code, 0);
+
+        let invocation = &records[0]["invocation_id"];
+        assert!(invocation.is_string());
+        assert!(records.iter().all(|r| &r["invocation_id"] == invocation));
+
+        let (matches, context): (Vec<&Value>, Vec<&Value>) = records.iter().partition(|r| r["record_id"].is_u64());
+        assert!(!matches.is_empty() && !context.is_empty());
+        let ids: Vec<&Value> = matches.iter().map(|r| &r["record_id"]).collect();
+        for line in context {
+            assert!(ids.contains(&&line["parent_record_id"]), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_quiet_and_errors() {
+        let missing = fixture("missing.txt").display().to_string();
+        let log = fixture("app.log").display().to_string();
+
+        for (pattern, files) in [
+            ("database", vec![log.as_str()]),
+            ("zzz", vec![log.as_str()]),
+            ("database", vec![missing.as_str(), log.as_str()]),
+            ("zzz", vec![missing.as_str(), log.as_str()]),
+        ] {
+            for quiet in [true, false] {
+                let mut args = if quiet { vec!["-q"] } else { vec![] };
+                args.push(pattern);
+                args.extend(&files);
+                let Some((_, gnu_code)) = gnu_grep(&args) else {
+                    return;
+                };
+                let (records, summary, code) = ai_grep_summary(&args);
+                assert_eq!(code, gnu_code, "{:?}", args);
+                if quiet {
+                    assert!(records.iter().all(|r| r["type"] == "error"), "{:?}", args);
+                    assert!(summary.is_none(), "{:?}", args);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_summary_counts_files() {
+        let mut args = vec!["-F", "-c", "database"];
+        let files = fixtures();
+        let missing = fixture("missing.txt").display().to_string();
+        args.extend(files.iter().map(String::as_str));
+        args.push(&missing);
+
+        let (records, summary, code) = ai_grep_summary(&args);
+        assert_eq!(code, 2);
+        let summary = summary.expect("grep_summary");
+        assert_eq!(summary["files_searched"], files.len());
+        let matched = records.iter().filter(|r| r["data"]["match_count"].as_u64() > Some(0)).count();
+        assert_eq!(summary["files_matched"], matched);
+        assert_eq!(summary["errors"], 1);
+        assert!(summary["telemetry"].is_object());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_recursive_follows_symlinks_unless_asked_not_to() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (tree, outside) = (temp_dir.path().join("tree"), temp_dir.path().join("outside"));
+        std::fs::create_dir_all(&tree).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("linked.txt"), "needle\n").unwrap();
+        std::os::unix::fs::symlink(&outside, tree.join("link")).unwrap();
+        let tree = tree.display().to_string();
+
+        for (policy, searched) in [(None, 1), (Some("command-line"), 0), (Some("never"), 0)] {
+            let mut args = vec!["-r", "needle", &tree];
+            if let Some(policy) = policy {
+                args.extend(["--follow-symlinks", policy]);
+            }
+            let (_, summary, _) = ai_grep_summary(&args);
+            assert_eq!(summary.unwrap()["files_searched"], searched, "{:?}", policy);
+        }
+    }
+}
+
+mod sd_replace {
+    use super::common::{of_type, run};
+    use std::fs;
+    use tempfile::TempDir;
+
+    const AI_SD: &str = env!("CARGO_BIN_EXE_ai-sd");
+
+    #[test]
+    fn test_dry_run_then_write() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("main.rs");
+        fs::write(&file, "fn old_name() {}\r\nold_name();\nother\n").unwrap();
+        fs::write(temp_dir.path().join("data.bin"), b"old_name\0").unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+
+        let (records, code) = run(AI_SD, &["-r", r"old_(\w+)", "new_$1", root]);
+        assert_eq!(code, 0);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "fn old_name() {}\r\nold_name();\nother\n");
+        let diffs = of_type(&records, "replace_diff");
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0]["before"], "fn old_name() {}");
+        assert_eq!(diffs[0]["after"], "fn new_name() {}");
+        let summary = of_type(&records, "replace_summary")[0];
+        assert_eq!(summary["replacements"], 2);
+        assert_eq!(summary["binary_skipped"], 1);
+        assert_eq!(summary["dry_run"], true);
+
+        let (records, code) = run(AI_SD, &["-r", "-w", "--no-diff", r"old_(\w+)", "new_$1", root]);
+        assert_eq!(code, 0);
+        assert!(of_type(&records, "replace_diff").is_empty());
+        assert_eq!(of_type(&records, "replace_file")[0]["written"], true);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "fn new_name() {}\r\nnew_name();\nother\n");
+        assert_eq!(fs::read(temp_dir.path().join("data.bin")).unwrap(), b"old_name\0");
+    }
+
+    #[test]
+    fn test_invalid_template_and_missing_file() {
+        let (records, code) = run(AI_SD, &["(a)", "$2", "Cargo.toml"]);
+        assert_eq!(code, 1);
+        assert_eq!(records[0]["code"], "INVALID_PATTERN");
+
+        let (records, code) = run(AI_SD, &["a", "b", "/nonexistent/file"]);
+        assert_eq!(code, 1);
+        assert_eq!(records[0]["code"], "REPLACE_ERROR");
+        assert_eq!(of_type(&records, "replace_summary")[0]["errors"], 1);
+    }
+}
+
+mod content_index {
+    use super::common::{of_type, run};
+    use std::fs;
+    use tempfile::TempDir;
+
+    const AI_INDEX: &str = env!("CARGO_BIN_EXE_ai-index");
+
+    #[test]
+    fn test_build_query_and_update() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+        fs::create_dir(temp_dir.path().join("src")).unwrap();
+        fs::write(temp_dir.path().join("src/a.rs"), "fn parse_header() {}\nfn other() {}\n").unwrap();
+        fs::write(temp_dir.path().join("src/b.rs"), "fn render() {}\n").unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "nothing here\n").unwrap();
+
+        // Without an index every file is searched
+        let (records, code) = run(AI_INDEX, &["query", r"fn (\w+)_header", root]);
+        assert_eq!(code, 0);
+        assert_eq!(of_type(&records, "search_summary")[0]["index"], false);
+        assert_eq!(of_type(&records, "search_summary")[0]["files_searched"], 3);
+
+        let (records, code) = run(AI_INDEX, &["build", root]);
+        assert_eq!(code, 0);
+        assert_eq!(of_type(&records, "index_built")[0]["added"], 3);
+
+        let (records, code) = run(AI_INDEX, &["query", r"fn (\w+)_header", root]);
+        assert_eq!(code, 0);
+        assert_eq!(records[0]["type"], "match");
+        assert_eq!(records[0]["line_number"], 1);
+        assert_eq!(records[0]["captures"]["1"], "parse");
+        let summary = of_type(&records, "search_summary")[0];
+        assert_eq!(summary["index"], true);
+        assert_eq!((summary["files_searched"].clone(), summary["files_filtered"].clone()), (1.into(), 2.into()));
+
+        // Edits are seen before and after the next incremental build
+        fs::write(temp_dir.path().join("src/b.rs"), "fn render() {}\nfn write_header() {}\n").unwrap();
+        let (records, _) = run(AI_INDEX, &["query", "-l", "-i", "HEADER", root]);
+        assert_eq!(of_type(&records, "search_summary")[0]["files_matched"], 2);
+        assert_eq!(of_type(&records, "search_summary")[0]["files_stale"], 1);
+
+        let (records, _) = run(AI_INDEX, &["build", root]);
+        let built = of_type(&records, "index_built")[0];
+        assert_eq!((built["updated"].clone(), built["unchanged"].clone()), (1.into(), 2.into()));
+
+        let (records, code) = run(AI_INDEX, &["stats", root]);
+        assert_eq!(code, 0);
+        assert_eq!(of_type(&records, "index_stats")[0]["indexed_files"], 3);
+    }
+
+    #[test]
+    fn test_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+
+        let (records, code) = run(AI_INDEX, &["stats", root]);
+        assert_eq!(code, 1);
+        assert_eq!(records[0]["code"], "INDEX_ERROR");
+
+        let (records, code) = run(AI_INDEX, &["query", "(", root]);
+        assert_eq!(code, 2);
+        assert_eq!(records[0]["code"], "INVALID_PATTERN");
+
+        let (_, code) = run(AI_INDEX, &["build", "/nonexistent/dir"]);
+        assert_eq!(code, 1);
+    }
+}
+
+mod watch_events {
+    use serde_json::Value;
+    use std::fs;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+    use tempfile::TempDir;
+
+    /// Start ai-watch, apply `change` once it is watching, and collect its records
+    fn watch(args: &[&str], change: impl FnOnce()) -> (Vec<Value>, i32) {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_ai-watch"))
+            .args(["--interval", "20", "--debounce", "150", "--timeout", "10"])
+            .args(args)
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+        let started: Value = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+        assert_eq!(started["info"]["type"], "watch_started");
+
+        change();
+        let records = lines.map(|line| serde_json::from_str(&line.unwrap()).unwrap()).collect();
+        (records, child.wait().unwrap().code().unwrap())
+    }
+
+    fn payloads<'a>(records: &'a [Value], kind: &str) -> Vec<&'a Value> {
+        records
+            .iter()
+            .filter_map(|r| r.get("data").or_else(|| r.get("info")))
+            .filter(|r| r["type"] == kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_once_reports_debounced_batch() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("old.txt"), "x").unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+
+        let (records, code) = watch(&["--once", root], || {
+            fs::write(temp_dir.path().join("new.txt"), "a").unwrap();
+            fs::write(temp_dir.path().join("new.txt"), "ab").unwrap();
+            fs::remove_file(temp_dir.path().join("old.txt")).unwrap();
+        });
+        assert_eq!(code, 0);
+        let events = payloads(&records, "watch_event");
+        let kinds: Vec<_> = events.iter().map(|e| e["kind"].as_str().unwrap()).collect();
+        assert_eq!(kinds, ["create", "delete"]);
+        assert!(events[0]["path"].as_str().unwrap().ends_with("new.txt"));
+        assert_eq!(payloads(&records, "watch_stopped")[0]["reason"], "once");
+    }
+
+    #[test]
+    fn test_exec_output_and_event_filter() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("watched.txt");
+        fs::write(&file, "1").unwrap();
+
+        let args = ["--once", "--events", "modify", "--exec", "echo changed; echo oops >&2; exit 3"];
+        let (records, code) = watch(&[&args[..], &[file.to_str().unwrap()]].concat(), || {
+            fs::write(&file, "22").unwrap();
+        });
+        assert_eq!(code, 3);
+        assert_eq!(payloads(&records, "watch_event")[0]["kind"], "modify");
+        let mut output: Vec<_> = payloads(&records, "exec_output")
+            .iter()
+            .map(|r| (r["stream"].as_str().unwrap(), r["line"].as_str().unwrap()))
+            .collect();
+        output.sort();
+        assert_eq!(output, [("stderr", "oops"), ("stdout", "changed")]);
+        assert_eq!(payloads(&records, "exec_completed")[0]["exit_code"], 3);
+    }
+
+    #[test]
+    fn test_missing_path() {
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-watch"))
+            .arg("/nonexistent/watched")
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert!(String::from_utf8_lossy(&output.stdout).contains("WATCH_ERROR"));
+    }
+}
+
+mod checksum_manifest {
+    use super::common::{of_type, run};
+    use serde_json::Value;
+    use std::fs;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+    use tempfile::TempDir;
+
+    const AI_CHECKSUM: &str = env!("CARGO_BIN_EXE_ai-checksum");
+
+    #[test]
+    fn test_manifest_then_check() {
+        let temp_dir = TempDir::new().unwrap();
+        let tree = temp_dir.path().join("tree");
+        fs::create_dir(&tree).unwrap();
+        fs::write(tree.join("a.txt"), "123456789").unwrap();
+        fs::write(tree.join("b.txt"), "beta").unwrap();
+        let manifest = temp_dir.path().join("manifest.json");
+        let (tree, manifest) = (tree.to_str().unwrap(), manifest.to_str().unwrap());
+
+        let (records, code) = run(AI_CHECKSUM, &["-r", "-a", "crc32", "-m", manifest, tree]);
+        assert_eq!(code, 0);
+        let sums = of_type(&records, "checksum");
+        assert_eq!(sums.len(), 2);
+        assert_eq!(sums[0]["digest"], "cbf43926");
+
+        let (records, code) = run(AI_CHECKSUM, &["-r", "-c", "-m", manifest, tree]);
+        assert_eq!(code, 0);
+        assert!(of_type(&records, "checksum_drift").is_empty());
+
+        fs::write(temp_dir.path().join("tree/b.txt"), "BETA").unwrap();
+        let (records, code) = run(AI_CHECKSUM, &["-r", "-c", "-q", "-m", manifest, tree]);
+        assert_eq!(code, 1);
+        let drift = of_type(&records, "checksum_drift");
+        assert_eq!(drift.len(), 1);
+        assert_eq!(drift[0]["change"], "changed");
+        assert!(of_type(&records, "checksum").is_empty());
+    }
+
+    #[test]
+    fn test_watch_reports_drift() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("config.toml");
+        fs::write(&file, "a = 1\n").unwrap();
+        let manifest = temp_dir.path().join("manifest.json");
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_ai-checksum"))
+            .args(["-r", "-w", "--interval", "20", "--debounce", "150", "--timeout", "2", "-m"])
+            .args([&manifest, temp_dir.path()])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+        // The first summary is written once the initial manifest is saved
+        let records: Vec<Value> = lines
+            .by_ref()
+            .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+            .take_while(|r: &Value| r["data"]["type"] != "checksum_summary")
+            .collect();
+        assert_eq!(of_type(&records, "checksum").len(), 1);
+
+        // Touching without changing contents is not drift
+        fs::write(&file, "a = 1\n").unwrap();
+        fs::write(&file, "a = 2\n").unwrap();
+        let records: Vec<Value> = lines.map(|line| serde_json::from_str(&line.unwrap()).unwrap()).collect();
+        assert_eq!(child.wait().unwrap().code(), Some(0));
+
+        let drift = of_type(&records, "checksum_drift");
+        assert_eq!(drift.len(), 1);
+        assert_eq!(drift[0]["change"], "changed");
+        assert_eq!(drift[0]["event"], "modify");
+        let saved: Value = serde_json::from_slice(&fs::read(&manifest).unwrap()).unwrap();
+        let digest = saved["files"][file.to_str().unwrap()]["digest"].clone();
+        assert_eq!(digest, drift[0]["actual"]);
+    }
+}
+
+mod csv_tool {
+    use super::common;
+    use serde_json::Value;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn ai_csv(args: &[&str]) -> (String, i32) {
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-csv")).args(args).output().unwrap();
+        (String::from_utf8_lossy(&output.stdout).into_owned(), output.status.code().unwrap())
+    }
+
+    fn records(stdout: &str) -> Vec<Value> {
+        common::records(stdout.as_bytes())
+    }
+
+    #[test]
+    fn test_head_select_and_convert() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("people.csv");
+        fs::write(&file, "id,name,zip\n1,\"Smith, J\",00501\n2,\"multi\nline\",\n3,Bob,12345\n").unwrap();
+        let file = file.to_str().unwrap();
+
+        let (stdout, code) = ai_csv(&["head", "-n", "2", file]);
+        assert_eq!(code, 0);
+        let rows = records(&stdout);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0]["data"]["fields"]["name"], "Smith, J");
+        assert_eq!(rows[1]["data"]["fields"]["name"], "multi\nline");
+        assert_eq!(rows[1]["data"]["line"], 3);
+        assert_eq!(rows[2]["data"]["type"], "csv_summary");
+        assert_eq!(rows[2]["data"]["rows"], 2);
+
+        let (stdout, code) = ai_csv(&["select", "-c", "name,1", "--csv", file]);
+        assert_eq!(code, 0);
+        assert_eq!(stdout, "name,id\n\"Smith, J\",1\n\"multi\nline\",2\nBob,3\n");
+
+        let (stdout, code) = ai_csv(&["to-jsonl", "--typed", file]);
+        assert_eq!(code, 0);
+        let rows = records(&stdout);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], serde_json::json!({"id": 1, "name": "Smith, J", "zip": "00501"}));
+        assert_eq!(rows[1]["zip"], Value::Null);
+        assert_eq!(rows[2]["zip"], 12345);
+
+        let padded = temp_dir.path().join("padded.csv");
+        fs::write(&padded, "id , name\n 1 ,  \"Smith, J \"\n").unwrap();
+        let (stdout, code) = ai_csv(&["to-jsonl", "--trim", "--typed", padded.to_str().unwrap()]);
+        assert_eq!(code, 0);
+        assert_eq!(records(&stdout), [serde_json::json!({"id": 1, "name": "Smith, J "})]);
+    }
+
+    #[test]
+    fn test_stats_and_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("data.tsv");
+        fs::write(&file, "a\tb\n1\t2\n3\t4\t5\n\n6\t\n").unwrap();
+
+        let (stdout, code) = ai_csv(&["stats", file.to_str().unwrap()]);
+        assert_eq!(code, 0);
+        let stats = &records(&stdout)[0]["data"];
+        assert_eq!(stats["delimiter"], "\t");
+        assert_eq!(stats["rows"], 3);
+        assert_eq!(stats["columns"], 2);
+        assert_eq!(stats["ragged_rows"], 1);
+        assert_eq!(stats["first_ragged_line"], 3);
+        assert_eq!(stats["empty_fields"], 1);
+
+        let (stdout, code) = ai_csv(&["select", "-c", "missing", file.to_str().unwrap()]);
+        assert_eq!(code, 1);
+        assert!(stdout.contains("CSV_ERROR"));
+
+        let broken = temp_dir.path().join("broken.csv");
+        fs::write(&broken, "a,b\n1,2\n3,\"open\n").unwrap();
+        let (stdout, code) = ai_csv(&["to-jsonl", broken.to_str().unwrap()]);
+        assert_eq!(code, 1);
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].contains("line 3"));
+    }
+
+    #[test]
+    fn test_profile_columns() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("sales.csv");
+        fs::write(&file, "region,total,paid
+EU,10,true
+US,20.5,false
+EU,,true
+").unwrap();
+        let file = file.to_str().unwrap();
+
+        let (stdout, code) = ai_csv(&["profile", file]);
+        assert_eq!(code, 0);
+        let profile = &records(&stdout)[0]["data"];
+        assert_eq!(profile["type"], "csv_profile");
+        assert_eq!(profile["rows"], 3);
+        let columns = profile["columns"].as_array().unwrap();
+        assert_eq!(columns[0]["type"], "string");
+        assert_eq!(columns[0]["distinct_estimate"], 2);
+        assert_eq!(columns[1]["type"], "float");
+        assert_eq!(columns[1]["nulls"], 1);
+        assert_eq!(columns[1]["mean"], 15.25);
+        assert_eq!(columns[2]["true_count"], 2);
+
+        let (stdout, code) = ai_csv(&["profile", "-c", "total", file]);
+        assert_eq!(code, 0);
+        let columns = records(&stdout)[0]["data"]["columns"].as_array().unwrap().clone();
+        assert_eq!(columns.len(), 1);
+        assert_eq!(columns[0]["name"], "total");
+    }
+}
+
+mod jsonmerge_tool {
+    use super::common::run;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const AI_JSONMERGE: &str = env!("CARGO_BIN_EXE_ai-jsonmerge");
+
+    #[test]
+    fn test_merge_sort_filter_and_count() {
+        let temp_dir = TempDir::new().unwrap();
+        let first = temp_dir.path().join("first.jsonl");
+        let second = temp_dir.path().join("second.jsonl");
+        fs::write(
+            &first,
+            concat!(
+                r#"{"type":"result","timestamp":"2026-01-01T00:00:03Z","data":{"type":"checksum","file":"a"}}"#,
+                "\n",
+                r#"{"type":"error","timestamp":"2026-01-01T00:00:01Z","message":"m","code":"IO_ERROR"}"#,
+                "\n{truncated\n",
+            ),
+        )
+        .unwrap();
+        fs::write(
+            &second,
+            r#"{"type":"result","timestamp":"2026-01-01T00:00:02Z","data":{"type":"checksum","file":"b"}}"#,
+        )
+        .unwrap();
+        let (first, second) = (first.to_str().unwrap(), second.to_str().unwrap());
+
+        let (records, code) = run(AI_JSONMERGE, &["-s", "--source", first, second]);
+        assert_eq!(code, 0);
+        assert_eq!(records[0]["code"], "INVALID_JSONL");
+        let merged: Vec<_> = records[1..].iter().map(|r| r["timestamp"].as_str().unwrap()).collect();
+        assert_eq!(merged, ["2026-01-01T00:00:01Z", "2026-01-01T00:00:02Z", "2026-01-01T00:00:03Z"]);
+        assert_eq!(records[2]["source"]["file"], second);
+        assert_eq!(records[3]["source"]["line"], 1);
+
+        let (records, _) = run(AI_JSONMERGE, &["-t", "checksum", "--since", "2026-01-01T00:00:03Z", first, second]);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1]["data"]["file"], "a");
+
+        let (records, code) = run(AI_JSONMERGE, &["-c", "type", first, second, "missing.jsonl"]);
+        assert_eq!(code, 1);
+        let counts: Vec<_> = records
+            .iter()
+            .filter(|r| r["data"]["type"] == "jsonmerge_count")
+            .map(|r| (r["data"]["value"].as_str().unwrap(), r["data"]["count"].as_u64().unwrap()))
+            .collect();
+        assert_eq!(counts, [("result", 2), ("error", 1)]);
+        let summary = &records.last().unwrap()["data"];
+        assert_eq!(summary["records_read"], 3);
+        assert_eq!(summary["invalid_lines"], 1);
+        assert_eq!(summary["errors"], 1);
+    }
+}
+
+mod logparse_tool {
+    use super::common::run_command;
+    use serde_json::Value;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_mixed_log_with_level_filter() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = temp_dir.path().join("app.log");
+        fs::write(
+            &log,
+            concat!(
+                "2026-01-01 12:00:00,250 INFO started\n",
+                "2026-01-01 12:00:01,000 ERROR job failed\n",
+                "Traceback (most recent call last):\n",
+                "  File \"job.py\", line 3, in run\n",
+                "KeyError: 'id'\n",
+                "10.0.0.1 - - [10/Oct/2025:13:55:36 +0000] \"GET /x HTTP/1.1\" 404 0\n",
+                "noise\n",
+            ),
+        )
+        .unwrap();
+
+        let (records, code) = run_command(Command::new(env!("CARGO_BIN_EXE_ai-logparse")).args(["-l", "warning"]).arg(&log));
+        assert_eq!(code, 0);
+        let entries: Vec<&Value> = records.iter().map(|r| &r["data"]).filter(|d| d["type"] == "log_entry").collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0]["message"], "job failed");
+        assert_eq!(entries[0]["timestamp"], "2026-01-01T12:00:01");
+        assert_eq!(entries[1]["format"], "traceback");
+        assert_eq!(entries[1]["fields"]["exception"], "KeyError");
+        assert_eq!(entries[2]["fields"]["status"], 404);
+
+        let summary = &records.last().unwrap()["data"];
+        assert_eq!(summary["entries"], 5);
+        assert_eq!(summary["unparsed"], 1);
+        assert_eq!(summary["by_level"]["info"], 1);
+    }
+}
+
+mod binfo_tool {
+    use super::common::run;
+    use serde_json::Value;
+
+    const AI_BINFO: &str = env!("CARGO_BIN_EXE_ai-binfo");
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn test_inspects_own_binary() {
+        let (records, code) = run(AI_BINFO, &["--strings", "-n", "8", "--section", ".rodata", AI_BINFO]);
+        assert_eq!(code, 0);
+        let records: Vec<&Value> = records.iter().map(|r| &r["data"]).collect();
+        let info = &records[0];
+        assert_eq!(info["type"], "binary_info");
+        assert_eq!(info["format"], "elf");
+        assert_eq!(info["architecture"], "x86_64");
+        assert_eq!(info["kind"], "executable");
+        assert!(records.iter().any(|r| r["type"] == "binary_section" && r["name"] == ".text"));
+
+        let strings: Vec<&&Value> = records.iter().filter(|r| r["type"] == "binary_string").collect();
+        assert!(strings.iter().all(|r| r["section"] == ".rodata" && r["string"].as_str().unwrap().len() >= 8));
+        assert!(strings.iter().any(|r| r["string"].as_str().unwrap().contains("BINFO_ERROR")));
+    }
+
+    #[test]
+    fn test_non_binary_is_an_error() {
+        let (records, code) = run(AI_BINFO, &[file!()]);
+        assert_ne!(code, 0);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["type"], "error");
+        assert_eq!(records[0]["code"], "BINFO_ERROR");
+        assert_eq!(records[1]["data"]["type"], "binfo_summary");
+        assert_eq!(records[1]["data"]["errors"], 1);
+    }
+}
+
+mod strings_tool {
+    use serde_json::Value;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_ascii_and_wide_strings_with_regex() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("blob.bin");
+        let mut data = vec![0u8, 1, 2];
+        data.extend_from_slice(b"version=1.2.3\0\xff");
+        data.extend("C:\\Temp".bytes().flat_map(|b| [b, 0]));
+        data.extend_from_slice(b"\0\0ab\0https://example.com/x\n");
+        fs::write(&file, &data).unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-strings")).arg(&file).output().unwrap();
+        assert!(output.status.success());
+        let records: Vec<Value> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["data"].clone())
+            .collect();
+        let strings: Vec<(u64, &str, &str)> = records
+            .iter()
+            .filter(|r| r["type"] == "string")
+            .map(|r| (r["offset"].as_u64().unwrap(), r["encoding"].as_str().unwrap(), r["string"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            strings,
+            [(3, "ascii", "version=1.2.3"), (18, "utf16le", "C:\\Temp"), (37, "ascii", "https://example.com/x")]
+        );
+        assert_eq!(records.last().unwrap()["by_encoding"]["utf16le"], 1);
+
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-strings"))
+            .args(["-e", "ascii", "-r", "^https?://"])
+            .arg(&file)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let matched: Vec<&str> = stdout.lines().filter(|l| l.contains("\"type\":\"string\"")).collect();
+        assert_eq!(matched.len(), 1);
+        assert!(matched[0].contains("https://example.com/x"));
+    }
+}
+
+mod wc_tool {
+    use serde_json::Value;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    /// The count line and the `wc` record of one run
+    fn ai_wc(args: &[&str]) -> (String, Value) {
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-wc")).args(args).output().unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let (records, lines): (Vec<&str>, Vec<&str>) = stdout.lines().partition(|line| line.starts_with('{'));
+        let counts = records
+            .into_iter()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .find(|r| r["info"]["operation"] == "wc")
+            .unwrap();
+        (lines.concat(), counts["info"].clone())
+    }
+
+    #[test]
+    fn test_chars_counts_multibyte_characters() {
+        let temp_dir = TempDir::new().unwrap();
+        let short = temp_dir.path().join("short.txt");
+        fs::write(&short, "héllo wörld\nab\n").unwrap();
+        let short = short.to_str().unwrap();
+
+        let (line, counts) = ai_wc(&["-m", short]);
+        assert_eq!(line, format!("     15  {}", short));
+        assert_eq!((counts["chars"].as_u64(), counts["bytes"].as_u64()), (Some(15), Some(17)));
+        assert_eq!((counts["lines"].as_u64(), counts["words"].as_u64()), (Some(2), Some(3)));
+
+        // Long enough for the SIMD blocks
+        let long = temp_dir.path().join("long.txt");
+        fs::write(&long, "héllo wörld\nab\n".repeat(10)).unwrap();
+        let (_, counts) = ai_wc(&["-m", long.to_str().unwrap()]);
+        assert_eq!((counts["chars"].as_u64(), counts["bytes"].as_u64()), (Some(150), Some(170)));
+        assert_eq!(counts["max_line_length"], 13);
+    }
+}
+
+mod shuf_sample_tool {
+    use serde_json::Value;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn records(args: &[&str], file: &std::path::Path) -> Vec<Value> {
+        let output = Command::new(args[0]).args(&args[1..]).arg(file).output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["data"].clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_shuf_is_a_seeded_permutation_with_provenance() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("data.txt");
+        let text: String = (1..=50).map(|i| format!("row {}\n", i)).collect();
+        fs::write(&file, &text).unwrap();
+
+        let shuf = env!("CARGO_BIN_EXE_ai-shuf");
+        let first = records(&[shuf, "--seed", "7"], &file);
+        let lines = &first[..50];
+        assert_eq!(lines, &records(&[shuf, "--seed", "7"], &file)[..50]);
+        assert!(lines.iter().all(|r| r["type"] == "shuffled_line"));
+
+        let mut numbers: Vec<u64> = lines.iter().map(|r| r["line_number"].as_u64().unwrap()).collect();
+        assert_ne!(numbers, (1..=50).collect::<Vec<_>>());
+        for line in lines {
+            let n = line["line_number"].as_u64().unwrap();
+            assert_eq!(line["content"], format!("row {}", n));
+            let offset = line["offset"].as_u64().unwrap() as usize;
+            assert!(text[offset..].starts_with(&format!("row {}\n", n)));
+        }
+        numbers.sort();
+        assert_eq!(numbers, (1..=50).collect::<Vec<_>>());
+        assert_eq!(first.last().unwrap()["seed"], 7);
+
+        let head = records(&[shuf, "--seed", "7", "-n", "5"], &file);
+        assert_eq!(head.len(), 6);
+        assert_eq!(head.last().unwrap()["output"], 5);
+    }
+
+    #[test]
+    fn test_sample_count_and_percent() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("data.txt");
+        fs::write(&file, (1..=1000).map(|i| format!("{}\n", i)).collect::<String>()).unwrap();
+
+        let sample = env!("CARGO_BIN_EXE_ai-sample");
+        let reservoir = records(&[sample, "-n", "10", "--seed", "1"], &file);
+        let lines: Vec<u64> = reservoir
+            .iter()
+            .filter(|r| r["type"] == "sampled_line")
+            .map(|r| r["line_number"].as_u64().unwrap())
+            .collect();
+        assert_eq!(lines.len(), 10);
+        assert!(lines.windows(2).all(|w| w[0] < w[1]), "in file order: {:?}", lines);
+        let summary = reservoir.last().unwrap();
+        assert_eq!((summary["method"].as_str(), summary["lines"].as_u64()), (Some("reservoir"), Some(1000)));
+
+        let again = records(&[sample, "-n", "10", "--seed", "1"], &file);
+        assert_eq!(reservoir[..10], again[..10]);
+
+        let percent = records(&[sample, "-p", "10", "--seed", "1"], &file);
+        let sampled = percent.last().unwrap()["sampled"].as_u64().unwrap();
+        assert_eq!(percent.len() as u64, sampled + 1);
+        assert!((50..150).contains(&sampled), "{}", sampled);
+        for r in &percent[..percent.len() - 1] {
+            assert_eq!(r["content"], r["line_number"].to_string());
+        }
+    }
+}
+
+mod nl_fold_tool {
+    use serde_json::Value;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn lines(bin: &str, args: &[&str], file: &std::path::Path, kind: &str) -> Vec<Value> {
+        let output = Command::new(bin).args(args).arg(file).output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["data"].clone())
+            .filter(|data| data["type"] == kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_nl_maps_numbers_to_source_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("doc.txt");
+        fs::write(&file, "intro\n\n\\:\\:\\:\ntitle\n\\:\\:\nbody\r\n").unwrap();
+
+        let records = lines(env!("CARGO_BIN_EXE_ai-nl"), &["-h", "a", "-w", "3"], &file, "numbered_line");
+        let summary: Vec<(u64, u64, &str, Value, &str)> = records
+            .iter()
+            .map(|r| {
+                (
+                    r["line_number"].as_u64().unwrap(),
+                    r["offset"].as_u64().unwrap(),
+                    r["section"].as_str().unwrap(),
+                    r["number"].clone(),
+                    r["content"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (1, 0, "body", Value::from(1), "  1\tintro"),
+                (2, 6, "body", Value::Null, "    "),
+                (3, 7, "header", Value::Null, ""),
+                (4, 14, "header", Value::from(1), "  1\ttitle"),
+                (5, 20, "body", Value::Null, ""),
+                (6, 25, "body", Value::from(1), "  1\tbody"),
+            ]
+        );
+        assert_eq!(records[2]["delimiter"], true);
+        assert_eq!(records[5]["length"], 4);
+    }
+
+    #[test]
+    fn test_fold_maps_segments_to_byte_ranges() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("prose.txt");
+        let text = "short\nthe quick brown fox jumps over\n";
+        fs::write(&file, text).unwrap();
+
+        let records = lines(env!("CARGO_BIN_EXE_ai-fold"), &["-w", "12", "-s"], &file, "folded_line");
+        let contents: Vec<&str> = records.iter().map(|r| r["content"].as_str().unwrap()).collect();
+        assert_eq!(contents, ["short", "the quick ", "brown fox ", "jumps over"]);
+        for record in &records {
+            let offset = record["offset"].as_u64().unwrap() as usize;
+            let length = record["length"].as_u64().unwrap() as usize;
+            assert_eq!(&text[offset..offset + length], record["content"].as_str().unwrap());
+        }
+        let segments: Vec<(u64, u64, bool)> = records
+            .iter()
+            .map(|r| (r["line_number"].as_u64().unwrap(), r["segment"].as_u64().unwrap(), r["continued"].as_bool().unwrap()))
+            .collect();
+        assert_eq!(segments, [(1, 1, false), (2, 1, true), (2, 2, true), (2, 3, false)]);
+    }
+}
+
+mod comm_join_tool {
+    use serde_json::Value;
+    use std::fs;
+    use std::process::{Command, Output};
+    use tempfile::TempDir;
+
+    fn parse(output: &Output) -> Vec<Value> {
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_comm_sides_and_unsorted_input() {
+        let temp_dir = TempDir::new().unwrap();
+        let a = temp_dir.path().join("a.txt");
+        let b = temp_dir.path().join("b.txt");
+        fs::write(&a, "apple\nbanana\ncherry\n").unwrap();
+        fs::write(&b, "banana\ndate\n").unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-comm")).arg(&a).arg(&b).output().unwrap();
+        assert!(output.status.success());
+        let records = parse(&output);
+        let lines: Vec<(&str, &str)> = records
+            .iter()
+            .map(|r| &r["data"])
+            .filter(|d| d["type"] == "comm_line")
+            .map(|d| (d["side"].as_str().unwrap(), d["content"].as_str().unwrap()))
+            .collect();
+        assert_eq!(lines, [("file1", "apple"), ("both", "banana"), ("file1", "cherry"), ("file2", "date")]);
+        assert_eq!(records[1]["data"]["file1"]["offset"], 6);
+        assert_eq!(records[1]["data"]["file2"]["line_number"], 1);
+        let summary = &records.last().unwrap()["data"];
+        assert_eq!((summary["file1_only"].as_u64(), summary["both"].as_u64()), (Some(2), Some(1)));
+
+        fs::write(&b, "date\nbanana\n").unwrap();
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-comm")).args(["-1", "-2"]).arg(&a).arg(&b).output().unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        let unsorted = parse(&output);
+        assert!(unsorted.iter().any(|r| r["type"] == "warning" && r["code"] == "UNSORTED_INPUT"));
+        assert!(unsorted.iter().all(|r| r["data"]["type"] != "comm_line"));
+    }
+
+    #[test]
+    fn test_join_pairs_and_unpaired_sides() {
+        let temp_dir = TempDir::new().unwrap();
+        let users = temp_dir.path().join("users.csv");
+        let orders = temp_dir.path().join("orders.csv");
+        fs::write(&users, "id,name\n1,ann\n2,bo\n3,cy\n").unwrap();
+        fs::write(&orders, "order,user\no7,2\no8,2\no9,4\n").unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-join"))
+            .args(["-t", ",", "-1", "1", "-2", "2", "--header", "-a", "1", "-a", "2"])
+            .arg(&users)
+            .arg(&orders)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+        let records = parse(&output);
+        let lines: Vec<(&str, &str, Value)> = records
+            .iter()
+            .map(|r| &r["data"])
+            .filter(|d| d["content"].is_string())
+            .map(|d| (d["type"].as_str().unwrap(), d["content"].as_str().unwrap(), d["side"].clone()))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                ("join_header", "id,name,order", Value::Null),
+                ("unpaired", "1,ann", "file1".into()),
+                ("joined", "2,bo,o7", Value::Null),
+                ("joined", "2,bo,o8", Value::Null),
+                ("unpaired", "3,cy", "file1".into()),
+                ("unpaired", "4,o9", "file2".into()),
+            ]
+        );
+        let joined = &records[2]["data"];
+        assert_eq!(joined["file1"]["fields"], serde_json::json!(["2", "bo"]));
+        assert_eq!(joined["file2"]["line_number"], 2);
+        let summary = &records.last().unwrap()["data"];
+        assert_eq!((summary["paired"].as_u64(), summary["unpaired_file2"].as_u64()), (Some(2), Some(1)));
+    }
+}
+
+mod test_tool {
+    use serde_json::Value;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn run(args: &[&str]) -> (Option<i32>, Value) {
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-test")).args(args).output().unwrap();
+        let record: Value = serde_json::from_slice(&output.stdout).unwrap();
+        (output.status.code(), record["data"].clone())
+    }
+
+    #[test]
+    fn test_predicates_and_exit_codes() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("data.txt");
+        fs::write(&file, "0123456789").unwrap();
+        let file = file.to_str().unwrap();
+        let missing = temp_dir.path().join("missing");
+        let missing = missing.to_str().unwrap();
+
+        let (code, data) = run(&[file]);
+        assert_eq!(code, Some(0));
+        assert_eq!(data["type"], "test_result");
+        assert_eq!(data["checks"][0]["predicate"], "exists");
+        assert_eq!(data["file"]["type"], "file");
+        assert_eq!(data["file"]["size"], 10);
+
+        let (code, data) = run(&[missing]);
+        assert_eq!(code, Some(1));
+        assert_eq!(data["result"], false);
+        assert!(data["file"].is_null());
+
+        let (code, data) = run(&["-f", "--size-gt", "5", "--size-lt", "10", file]);
+        assert_eq!(code, Some(1));
+        let results: Vec<bool> = data["checks"].as_array().unwrap().iter().map(|c| c["result"].as_bool().unwrap()).collect();
+        assert_eq!(results, [true, true, false]);
+        assert_eq!(data["checks"][2]["detail"]["size"], 10);
+
+        assert_eq!(run(&["--any", "-d", "-s", file]).0, Some(0));
+        assert_eq!(run(&["--not", "-d", file]).0, Some(0));
+
+        // As with `test -nt`, an existing file is newer than a missing one
+        let (code, data) = run(&["--newer-than", missing, file]);
+        assert_eq!(code, Some(0));
+        assert!(data["checks"][0]["detail"]["reference_modified"].is_null());
+        assert_eq!(run(&["--older-than", file, missing]).0, Some(0));
+    }
+}
+
+#[cfg(unix)]
+mod realpath_tool {
+    use serde_json::Value;
+    use std::fs;
+    use std::os::unix::fs::symlink;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_hops_loops_and_missing_targets() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        fs::create_dir(root.join("real")).unwrap();
+        fs::write(root.join("real/file"), "x").unwrap();
+        symlink("real", root.join("dir")).unwrap();
+        symlink("dir/file", root.join("link")).unwrap();
+        symlink("b", root.join("a")).unwrap();
+        symlink("a", root.join("b")).unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-realpath"))
+            .current_dir(&root)
+            .args(["link", "a", "new", "--relative-to", "real"])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        let records: Vec<Value> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["data"].clone())
+            .collect();
+
+        let link = &records[0];
+        assert_eq!(link["resolved"], root.join("real/file").to_str().unwrap());
+        assert_eq!((link["exists"].as_bool(), link["file_type"].as_str()), (Some(true), Some("file")));
+        assert_eq!(link["relative"], "file");
+        let hops: Vec<&str> = link["hops"].as_array().unwrap().iter().map(|h| h["target"].as_str().unwrap()).collect();
+        assert_eq!(hops, ["dir/file", "real"]);
+
+        assert_eq!(records[1]["loop"], true);
+        assert_eq!(records[1]["error"]["reason"], "loop");
+        assert!(records[1]["resolved"].is_null());
+
+        assert_eq!(records[2]["exists"], false);
+        assert_eq!(records[2]["relative"], "../new");
+        assert_eq!((records[3]["loops"].as_u64(), records[3]["failed"].as_u64()), (Some(1), Some(1)));
+    }
+}
+
+mod scratch_tool {
+    use serde_json::Value;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn run(bin: &str, args: &[&str], registry: &TempDir) -> (Option<i32>, Vec<Value>) {
+        let output = Command::new(bin)
+            .args(args)
+            .env("AI_COREUTILS_SCRATCH_DIR", registry.path())
+            .env_remove("AI_COREUTILS_SESSION")
+            .output()
+            .unwrap();
+        let records = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["data"].clone())
+            .collect();
+        (output.status.code(), records)
+    }
+
+    #[test]
+    fn test_mktemp_and_cleanup_by_session() {
+        let registry = TempDir::new().unwrap();
+        let scratch = TempDir::new().unwrap();
+        let dir = scratch.path().to_str().unwrap();
+        let mktemp = env!("CARGO_BIN_EXE_ai-mktemp");
+        let cleanup = env!("CARGO_BIN_EXE_ai-cleanup");
+
+        let (code, created) = run(mktemp, &["-d", "-p", dir, "--session", "s1", "work.XXXXXX"], &registry);
+        assert_eq!(code, Some(0));
+        let work = created[0]["path"].as_str().unwrap().to_string();
+        assert!(std::path::Path::new(&work).is_dir());
+        let name = std::path::Path::new(&work).file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("work.") && name != "work.XXXXXX");
+        assert_eq!((created[0]["kind"].as_str(), created[0]["registered"].as_bool()), (Some("dir"), Some(true)));
+        std::fs::write(std::path::Path::new(&work).join("notes.txt"), "x").unwrap();
+
+        let (_, created) = run(mktemp, &["-p", dir, "--suffix", ".json", "--session", "s2", "out.XXX"], &registry);
+        let out = created[0]["path"].as_str().unwrap().to_string();
+        assert!(out.ends_with(".json"));
+
+        let (code, _) = run(mktemp, &["-p", dir, "bad.XX"], &registry);
+        assert_eq!(code, Some(1));
+        let (code, _) = run(cleanup, &[], &registry);
+        assert_eq!(code, Some(1));
+
+        let (code, records) = run(cleanup, &["--session", "s1"], &registry);
+        assert_eq!(code, Some(0));
+        assert_eq!(records[0]["action"], "removed");
+        assert_eq!(records[0]["path"], work.as_str());
+        assert!(!std::path::Path::new(&work).exists());
+        assert!(std::path::Path::new(&out).exists());
+
+        let (_, listed) = run(cleanup, &["--list"], &registry);
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0]["session"], "s2");
+    }
+}
+
+mod session_tool {
+    use super::common::run_command;
+    use serde_json::Value;
+    use std::path::Path;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn run(bin: &str, args: &[&str], state: &TempDir, session: Option<&str>) -> (i32, Vec<Value>) {
+        let mut command = Command::new(bin);
+        command
+            .args(args)
+            .env("AI_COREUTILS_SESSION_DIR", state.path().join("sessions"))
+            .env("AI_COREUTILS_SCRATCH_DIR", state.path().join("scratch"))
+            .env_remove("AI_COREUTILS_JOURNAL_DIR")
+            .env_remove("AI_COREUTILS_SESSION");
+        if let Some(session) = session {
+            command.env("AI_COREUTILS_SESSION", session);
+        }
+        let (records, code) = run_command(&mut command);
+        (code, records)
+    }
+
+    #[test]
+    fn test_session_state_and_end() {
+        let state = TempDir::new().unwrap();
+        let session = env!("CARGO_BIN_EXE_ai-session");
+        let mktemp = env!("CARGO_BIN_EXE_ai-mktemp");
+
+        let (code, created) = run(session, &["--new"], &state, None);
+        assert_eq!(code, 0);
+        let id = created[0]["data"]["id"].as_str().unwrap().to_string();
+        let dir = created[0]["data"]["dir"].as_str().unwrap().to_string();
+        assert!(Path::new(&dir).is_dir());
+        assert!(created[0].get("session").is_none());
+
+        // Every record is marked, and temporary files go to the session
+        let (_, made) = run(mktemp, &["-d"], &state, Some(&id));
+        assert_eq!(made[0]["session"], id.as_str());
+        let tmp = made[0]["data"]["path"].as_str().unwrap().to_string();
+        assert!(tmp.starts_with(&dir));
+        let (_, made) = run(mktemp, &["-p", state.path().to_str().unwrap()], &state, Some(&id));
+        let outside = made[0]["data"]["path"].as_str().unwrap().to_string();
+
+        let (_, info) = run(session, &[], &state, Some(&id));
+        assert_eq!(info[0]["data"]["type"], "session_info");
+        assert_eq!(info[0]["data"]["allocations"], 2);
+        let (_, listed) = run(session, &["--list"], &state, None);
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[1]["data"]["sessions"], 1);
+
+        let (code, ended) = run(session, &["--end", &id], &state, None);
+        assert_eq!(code, 0);
+        let summary = &ended.last().unwrap()["data"];
+        assert_eq!(summary["allocations_released"], 2);
+        assert_eq!(summary["removed"], true);
+        assert!(!Path::new(&dir).exists());
+        assert!(!Path::new(&outside).exists());
+
+        let (code, _) = run(session, &[], &state, Some("../escape"));
+        assert_ne!(code, 0);
+        let (code, _) = run(session, &[], &state, None);
+        assert_eq!(code, 1);
+    }
+}
+
+mod result_cache_tool {
+    use serde_json::Value;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    /// `analysis` records of one run, keyed by file name
+    fn analyze(args: &[&str], cache: &TempDir) -> Vec<Value> {
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-analyze"))
+            .args(args)
+            .env("AI_COREUTILS_CACHE_DIR", cache.path())
+            .env_remove("AI_COREUTILS_CACHE")
+            .env_remove("AI_COREUTILS_SESSION")
+            .output()
+            .unwrap();
+        let mut records: Vec<Value> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["data"].clone())
+            .filter(|data| data["type"] == "analysis")
+            .collect();
+        records.sort_by_key(|data| data["file"].as_str().unwrap_or_default().to_string());
+        records
+    }
+
+    #[test]
+    fn test_repeated_analysis_hits_the_cache() {
+        let cache = TempDir::new().unwrap();
+        let src = TempDir::new().unwrap();
+        fs::write(src.path().join("a.txt"), "mail bob@example.com\n").unwrap();
+        fs::write(src.path().join("b.txt"), "see http://example.com\n").unwrap();
+        let dir = src.path().to_str().unwrap();
+
+        let first = analyze(&["-r", dir], &cache);
+        assert_eq!(first.len(), 2);
+        assert!(first.iter().all(|data| data["cache_hit"] == false));
+
+        fs::write(src.path().join("b.txt"), "see http://example.org\n").unwrap();
+        let second = analyze(&["-r", dir], &cache);
+        assert_eq!((second[0]["cache_hit"].as_bool(), second[1]["cache_hit"].as_bool()), (Some(true), Some(false)));
+        let mut cached = second[0].clone();
+        cached["cache_hit"] = false.into();
+        assert_eq!(cached, first[0]);
+
+        let other_options = analyze(&["-r", "-m", "0.9", dir], &cache);
+        assert!(other_options.iter().all(|data| data["cache_hit"] == false));
+        let uncached = analyze(&["-r", "--no-cache", dir], &cache);
+        assert!(uncached.iter().all(|data| data["cache_hit"] == false));
+    }
+}
+
+mod incremental_tool {
+    use serde_json::Value;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    /// Data of every record of one ai-analyze run
+    fn analyze(args: &[&str], cache: &TempDir) -> Vec<Value> {
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-analyze"))
+            .args(args)
+            .env("AI_COREUTILS_CACHE_DIR", cache.path())
+            .env_remove("AI_COREUTILS_CACHE")
+            .env_remove("AI_COREUTILS_SESSION")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["data"].clone())
+            .collect()
+    }
+
+    fn change_of(records: &[Value], file: &str) -> Option<Value> {
+        records
+            .iter()
+            .find(|data| data["type"] == "analysis" && data["file"].as_str().is_some_and(|f| f.ends_with(file)))
+            .map(|data| data["change"].clone())
+    }
+
+    #[test]
+    fn test_changed_since_reuses_unchanged_results() {
+        let cache = TempDir::new().unwrap();
+        let src = TempDir::new().unwrap();
+        let tree = src.path().join("tree");
+        fs::create_dir(&tree).unwrap();
+        fs::write(tree.join("a.txt"), "mail bob@example.com\n").unwrap();
+        fs::write(tree.join("b.txt"), "see http://example.com\n").unwrap();
+        fs::write(tree.join("c.txt"), "nothing here\n").unwrap();
+        let manifest = src.path().join("tree.snap");
+        let manifest = manifest.to_str().unwrap();
+
+        let status = Command::new(env!("CARGO_BIN_EXE_ai-snapshot"))
+            .args(["create", tree.to_str().unwrap(), "-o", manifest])
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success());
+
+        // Nothing analyzed yet: unchanged files without results are analyzed
+        let first = analyze(&["--changed-since", manifest], &cache);
+        assert_eq!(change_of(&first, "a.txt"), Some("unchanged".into()));
+        let summary = first.last().unwrap();
+        assert_eq!(summary["type"], "incremental_summary");
+        assert!(summary["telemetry"].is_object());
+        assert_eq!((summary["unchanged"].as_u64(), summary["analyzed"].as_u64()), (Some(3), Some(3)));
+
+        fs::write(tree.join("b.txt"), "see http://example.org and more\n").unwrap();
+        fs::remove_file(tree.join("c.txt")).unwrap();
+        fs::write(tree.join("d.txt"), "call 555-0100\n").unwrap();
+
+        let second = analyze(&["--changed-since", manifest, "--update-manifest"], &cache);
+        assert_eq!(change_of(&second, "a.txt"), Some("unchanged".into()));
+        assert_eq!(change_of(&second, "b.txt"), Some("changed".into()));
+        assert_eq!(change_of(&second, "d.txt"), Some("added".into()));
+        assert!(second.iter().any(|data| data["type"] == "analysis_removed" && data["change"] == "removed"));
+        let summary = second.last().unwrap();
+        assert_eq!(summary["reused"], 1);
+        assert_eq!(summary["analyzed"], 2);
+        assert_eq!((summary["added"].as_u64(), summary["changed"].as_u64(), summary["removed"].as_u64()), (Some(1), Some(1), Some(1)));
+        assert_eq!(summary["manifest_updated"], true);
+
+        // The manifest now matches the tree
+        let third = analyze(&["--changed-since", manifest], &cache);
+        let summary = third.last().unwrap();
+        assert_eq!((summary["unchanged"].as_u64(), summary["reused"].as_u64()), (Some(3), Some(3)));
+    }
+}
+
+mod bench_tool {
+    use serde_json::Value;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn bench(args: &[&str]) -> Vec<Value> {
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-bench"))
+            .args(args)
+            .env_remove("AI_COREUTILS_SESSION")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["data"].clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_quick_profile_is_saved_and_kept() {
+        let dir = TempDir::new().unwrap();
+        let profile = dir.path().join("profile.json");
+        let (work, profile) = (dir.path().to_str().unwrap(), profile.to_str().unwrap());
+
+        let records = bench(&["--quick", "--dir", work, "-o", profile]);
+        let (summary, results) = records.split_last().unwrap();
+        assert_eq!(summary["type"], "bench_profile");
+        for suite in ["simd", "grep", "copy"] {
+            assert!(results.iter().any(|data| data["type"] == "bench_result" && data["suite"] == suite));
+        }
+        assert!(results.iter().all(|data| data["iterations"].as_u64() >= Some(1)));
+        let concurrency = summary["concurrency"].as_u64().unwrap();
+        assert!(results.iter().any(|data| data["params"]["concurrency"] == concurrency));
+        assert!(summary["buffer_size"].as_u64().is_some());
+        assert_eq!(summary["saved"], profile);
+
+        // The benchmark files are removed; only the profile is left
+        let entries: Vec<_> = fs::read_dir(work).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert_eq!(entries, ["profile.json"]);
+
+        // Rerunning one suite keeps the others' results
+        let records = bench(&["-q", "-s", "simd", "-o", profile]);
+        let summary = records.last().unwrap();
+        assert_eq!(summary["concurrency"].as_u64(), Some(concurrency));
+        let saved: Value = serde_json::from_str(&fs::read_to_string(profile).unwrap()).unwrap();
+        assert_eq!(saved["measurements"].as_array().unwrap().len() as u64, summary["measurements"].as_u64().unwrap());
+    }
+
+    #[test]
+    fn test_saved_profile_tunes_async_cat() {
+        let dir = TempDir::new().unwrap();
+        let profile = dir.path().join("profile.json");
+        let concurrency = bench(&["-q", "-s", "grep", "--dir", dir.path().to_str().unwrap(), "-o", profile.to_str().unwrap()])
+            .last()
+            .unwrap()["concurrency"]
+            .clone();
+        let files = ["a.txt", "b.txt"].map(|name| dir.path().join(name));
+        for file in &files {
+            fs::write(file, "hay\n").unwrap();
+        }
+
+        let tuning = |args: &[&str]| {
+            let output = Command::new(env!("CARGO_BIN_EXE_ai-cat"))
+                .args(args)
+                .args(&files)
+                .env("AI_COREUTILS_PROFILE", &profile)
+                .env_remove("AI_COREUTILS_CONCURRENCY")
+                .env_remove("AI_COREUTILS_TUNING")
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(|line| serde_json::from_str::<Value>(line).unwrap()["info"].clone())
+                .find(|info| info["type"] == "tuning")
+                .unwrap()
+        };
+
+        let tuned = tuning(&["-a"]);
+        assert_eq!(tuned["concurrency"]["value"], concurrency);
+        assert_eq!(tuned["concurrency"]["source"], "profile");
+        let tuned = tuning(&["-a", "-j", "3"]);
+        assert_eq!(tuned["concurrency"]["value"], 3);
+        assert_eq!(tuned["concurrency"]["source"], "cli");
+    }
+}
+
+mod git_status_tool {
+    use super::common::run;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn test_ls_and_find_annotate_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let repo = git2::Repository::init(root).unwrap();
+        fs::write(root.join("tracked.txt"), "v1\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path("tracked.txt".as_ref()).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let author = git2::Signature::new("Jane Doe", "jane@example.com", &git2::Time::new(1_700_000_000, 0)).unwrap();
+        let commit = repo.commit(Some("HEAD"), &author, &author, "Add tracked file", &tree, &[]).unwrap();
+        fs::write(root.join("tracked.txt"), "v2\n").unwrap();
+        fs::write(root.join("new.txt"), "new\n").unwrap();
+
+        let (records, code) = run(env!("CARGO_BIN_EXE_ai-ls"), &["--git-status", root.to_str().unwrap()]);
+        assert_eq!(code, 0);
+        let entry = |name: &str| records.iter().find(|r| r["path"].as_str().unwrap().ends_with(name)).unwrap();
+        assert_eq!(entry("tracked.txt")["git_status"], "modified");
+        assert_eq!(entry("tracked.txt")["last_commit"]["hash"], commit.to_string());
+        assert_eq!(entry("tracked.txt")["last_commit"]["time"], "2023-11-14T22:13:20+00:00");
+        assert_eq!(entry("new.txt")["git_status"], "untracked");
+        assert!(entry("new.txt").get("last_commit").is_none());
+
+        let (records, code) = run(env!("CARGO_BIN_EXE_ai-find"), &[root.to_str().unwrap(), "--name", "tracked.txt", "--git-status"]);
+        assert_eq!(code, 0);
+        assert_eq!(records[0]["data"]["git_status"], "modified");
+        assert_eq!(records[0]["data"]["last_commit"]["author"], "Jane Doe");
+    }
+
+    #[cfg(not(feature = "git"))]
+    #[test]
+    fn test_requires_git_feature() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "a").unwrap();
+        for bin in [env!("CARGO_BIN_EXE_ai-ls"), env!("CARGO_BIN_EXE_ai-find")] {
+            let (records, code) = run(bin, &["--git-status", temp_dir.path().to_str().unwrap()]);
+            assert_ne!(code, 0);
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0]["code"], "GIT_ERROR");
+        }
+    }
+}
+
+mod blame_tool {
+    use super::common::run;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const AI_BLAME: &str = env!("CARGO_BIN_EXE_ai-blame");
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn test_blame_range_with_uncommitted_line() {
+        use serde_json::Value;
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let repo = git2::Repository::init(root).unwrap();
+        fs::write(root.join("notes.txt"), "one\ntwo\nthree\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path("notes.txt".as_ref()).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let author = git2::Signature::new("Jane Doe", "jane@example.com", &git2::Time::new(1_700_000_000, 0)).unwrap();
+        let commit = repo.commit(Some("HEAD"), &author, &author, "Add notes", &tree, &[]).unwrap();
+        fs::write(root.join("notes.txt"), "one\n2\nthree\n").unwrap();
+
+        let file = root.join("notes.txt");
+        let (records, code) = run(AI_BLAME, &[file.to_str().unwrap(), "--lines", "2.."]);
+        assert_eq!(code, 0);
+        let data: Vec<&Value> = records.iter().map(|r| &r["data"]).collect();
+        assert_eq!(data.len(), 3);
+        assert_eq!((&data[0]["line"], &data[0]["content"], &data[0]["commit"]), (&Value::from(2), &Value::from("2"), &Value::Null));
+        assert_eq!(data[1]["commit"], commit.to_string());
+        assert_eq!((&data[1]["author"], &data[1]["time"]), (&Value::from("Jane Doe"), &Value::from("2023-11-14T22:13:20+00:00")));
+        assert_eq!(data[2]["type"], "blame_summary");
+        assert_eq!((&data[2]["lines"], &data[2]["uncommitted"], &data[2]["by_author"]["Jane Doe"]), (&2.into(), &1.into(), &1.into()));
+
+        let (records, code) = run(AI_BLAME, &[file.to_str().unwrap(), "-L", "9"]);
+        assert_ne!(code, 0);
+        assert_eq!(records[0]["code"], "BLAME_ERROR");
+    }
+
+    #[test]
+    fn test_untracked_file_is_an_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("loose.txt");
+        fs::write(&file, "x\n").unwrap();
+        let (records, code) = run(AI_BLAME, &[file.to_str().unwrap()]);
+        assert_ne!(code, 0);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["code"], "BLAME_ERROR");
+    }
+}
+
+mod snapshot_tool {
+    use serde_json::Value;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn ai_snapshot(args: &[&str]) -> (i32, Vec<Value>) {
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-snapshot")).args(args).output().unwrap();
+        let records = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .map(|record| if record["type"] == "result" { record["data"].clone() } else { record })
+            .collect();
+        (output.status.code().unwrap(), records)
+    }
+
+    #[test]
+    fn test_create_then_diff_live_and_snapshots() {
+        let temp_dir = TempDir::new().unwrap();
+        let tree = temp_dir.path().join("tree");
+        fs::create_dir_all(tree.join("src")).unwrap();
+        fs::write(tree.join("src/lib.rs"), "pub fn f() {}\n").unwrap();
+        fs::write(tree.join("Cargo.toml"), "[package]\n").unwrap();
+        let before = temp_dir.path().join("before.json");
+        let (tree, before) = (tree.to_str().unwrap(), before.to_str().unwrap());
+
+        let (code, records) = ai_snapshot(&["create", tree, "-o", before]);
+        assert_eq!(code, 0);
+        assert_eq!((&records[0]["type"], &records[0]["files"]), (&Value::from("snapshot"), &Value::from(2)));
+
+        let (code, records) = ai_snapshot(&["diff", before]);
+        assert_eq!(code, 0);
+        assert_eq!((&records[0]["unchanged"], &records[0]["changed"]), (&Value::from(2), &Value::from(0)));
+
+        fs::write(temp_dir.path().join("tree/src/lib.rs"), "pub fn f() { g() }\n").unwrap();
+        fs::remove_file(temp_dir.path().join("tree/Cargo.toml")).unwrap();
+        fs::write(temp_dir.path().join("tree/build.log"), "ok\n").unwrap();
+
+        let (code, records) = ai_snapshot(&["diff", before]);
+        assert_eq!(code, 1);
+        let changes: Vec<(&str, &str)> = records
+            .iter()
+            .filter(|r| r["type"] == "snapshot_change")
+            .map(|r| (r["path"].as_str().unwrap(), r["change"].as_str().unwrap()))
+            .collect();
+        assert_eq!(changes, [("Cargo.toml", "removed"), ("build.log", "added"), ("src/lib.rs", "changed")]);
+        assert_eq!(records[2]["size_delta"], 5);
+
+        let after = temp_dir.path().join("after.json");
+        ai_snapshot(&["create", tree, "-o", after.to_str().unwrap()]);
+        let (code, records) = ai_snapshot(&["diff", before, after.to_str().unwrap()]);
+        assert_eq!(code, 1);
+        assert_eq!(records.last().unwrap()["against"], after.to_str().unwrap());
+        assert_eq!(records.len(), 4);
+
+        let (code, records) = ai_snapshot(&["diff", temp_dir.path().join("missing.json").to_str().unwrap()]);
+        assert_eq!((code, &records[0]["code"]), (1, &Value::from("SNAPSHOT_ERROR")));
+    }
+}
+
+mod undo_tool {
+    use serde_json::Value;
+    use std::fs;
+    use std::path::Path;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn run(bin: &str, journal: &Path, args: &[&str]) -> (i32, Vec<Value>) {
+        let output = Command::new(bin)
+            .args(args)
+            .env("AI_COREUTILS_JOURNAL", "1")
+            .env("AI_COREUTILS_JOURNAL_DIR", journal)
+            .output()
+            .unwrap();
+        let records = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .map(|record| if record["type"] == "result" { record["data"].clone() } else { record })
+            .collect();
+        (output.status.code().unwrap(), records)
+    }
+
+    #[test]
+    fn test_undo_rm_sd_and_cp() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = temp_dir.path().join("journal");
+        let work = temp_dir.path().join("work");
+        fs::create_dir_all(work.join("dir")).unwrap();
+        fs::write(work.join("dir/a.txt"), "alpha\n").unwrap();
+        fs::write(work.join("b.txt"), "beta\n").unwrap();
+        let (dir, b) = (work.join("dir"), work.join("b.txt"));
+        let copy = work.join("copy.txt");
+
+        let (code, records) = run(env!("CARGO_BIN_EXE_ai-rm"), &journal, &["-r", dir.to_str().unwrap()]);
+        assert_eq!(code, 0);
+        let summary = records.last().unwrap();
+        assert!(summary["journal"].is_string());
+        // The removed bytes moved into the journal rather than being freed
+        assert_eq!(summary["bytes_journaled"], 6);
+        assert_eq!(summary["bytes_freed"], 0);
+        assert!(!dir.exists());
+
+        run(env!("CARGO_BIN_EXE_ai-sd"), &journal, &["-w", "beta", "gamma", b.to_str().unwrap()]);
+        run(env!("CARGO_BIN_EXE_ai-cp"), &journal, &[b.to_str().unwrap(), copy.to_str().unwrap()]);
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "gamma\n");
+
+        let (code, records) = run(env!("CARGO_BIN_EXE_ai-undo"), &journal, &["--list"]);
+        assert_eq!(code, 0);
+        let tools: Vec<&str> = records[..3].iter().map(|r| r["tool"].as_str().unwrap()).collect();
+        assert_eq!(tools, ["ai-cp", "ai-sd", "ai-rm"]);
+
+        let (code, records) = run(env!("CARGO_BIN_EXE_ai-undo"), &journal, &["-n", "2"]);
+        assert_eq!(code, 0);
+        assert_eq!(records.last().unwrap()["operations"], 2);
+        assert!(!copy.exists());
+        assert_eq!(fs::read_to_string(&b).unwrap(), "beta\n");
+        assert!(!dir.exists());
+
+        let (code, _) = run(env!("CARGO_BIN_EXE_ai-undo"), &journal, &[]);
+        assert_eq!(code, 0);
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "alpha\n");
+
+        let (code, records) = run(env!("CARGO_BIN_EXE_ai-undo"), &journal, &[]);
+        assert_eq!((code, &records[0]["code"]), (1, &Value::from("NOTHING_TO_UNDO")));
+    }
+}
+
+/// ai-cp over an existing destination
+#[cfg(unix)]
+mod cp_tool {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn ai_cp(journal: &Path, source: &Path, dest: &Path) {
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-cp"))
+            .args([source, dest])
+            .env("AI_COREUTILS_JOURNAL_DIR", journal)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    }
+
+    #[test]
+    fn test_overwrite_keeps_dest_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        let (source, dest) = (temp_dir.path().join("source"), temp_dir.path().join("dest"));
+        fs::write(&source, "new\n").unwrap();
+        fs::set_permissions(&source, fs::Permissions::from_mode(0o644)).unwrap();
+        fs::write(&dest, "old\n").unwrap();
+        fs::set_permissions(&dest, fs::Permissions::from_mode(0o600)).unwrap();
+
+        ai_cp(&temp_dir.path().join("journal"), &source, &dest);
+
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "new\n");
+        assert_eq!(fs::metadata(&dest).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_overwrite_writes_through_symlink() {
+        let temp_dir = TempDir::new().unwrap();
+        let (source, target) = (temp_dir.path().join("source"), temp_dir.path().join("target"));
+        let link = temp_dir.path().join("link");
+        fs::write(&source, "new\n").unwrap();
+        fs::write(&target, "old\n").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        ai_cp(&temp_dir.path().join("journal"), &source, &link);
+
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new\n");
+    }
+}
+
+mod flock_tool {
+    use serde_json::Value;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_out_of_range_timeout_is_invalid_input() {
+        let temp_dir = TempDir::new().unwrap();
+        let lock = temp_dir.path().join("lock");
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-flock"))
+            .args(["-w", "1e300", lock.to_str().unwrap(), "true"])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        let record: Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(record["code"], "INVALID_INPUT");
+    }
+}
+
+mod dry_run_tool {
+    use serde_json::Value;
+    use std::fs;
+    use std::path::Path;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    /// Records other than progress, without timestamps, telemetry, journal
+    /// or invocation ids
+    fn run(bin: &str, journal: &Path, args: &[&str]) -> Vec<Value> {
+        let output = Command::new(bin)
+            .args(args)
+            .env("AI_COREUTILS_JOURNAL_DIR", journal)
+            .env("AI_COREUTILS_JSONL_TIMESTAMPS", "0")
+            .env("AI_COREUTILS_JSONL_TELEMETRY", "0")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .filter(|record| record["type"] != "progress")
+            .map(|mut record| {
+                if let Some(data) = record.get_mut("data").and_then(Value::as_object_mut) {
+                    data.remove("journal");
+                }
+                if let Some(record) = record.as_object_mut() {
+                    record.remove("invocation_id");
+                }
+                record
+            })
+            .collect()
+    }
+
+    fn assert_same_records(dry: &[Value], real: &[Value]) {
+        assert_eq!(dry.len(), real.len());
+        for (dry, real) in dry.iter().zip(real) {
+            assert_eq!(dry["dry_run"], true);
+            let mut dry = dry.clone();
+            dry.as_object_mut().unwrap().remove("dry_run");
+            assert_eq!(&dry, real);
+        }
+    }
+
+    #[test]
+    fn test_dry_run_matches_real_run_without_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = temp_dir.path().join("journal");
+        let src = temp_dir.path().join("src");
+        fs::create_dir_all(src.join("nested")).unwrap();
+        fs::write(src.join("a.txt"), "alpha\n").unwrap();
+        fs::write(src.join("nested/b.txt"), "beta\n").unwrap();
+        let (src, dest) = (src.to_str().unwrap(), temp_dir.path().join("dest"));
+        let dest = dest.to_str().unwrap();
+
+        let cp = env!("CARGO_BIN_EXE_ai-cp");
+        let dry = run(cp, &journal, &["-R", "-v", "--dry-run", src, dest]);
+        assert!(!Path::new(dest).exists());
+        assert!(!journal.exists());
+        let real = run(cp, &journal, &["-R", "-v", src, dest]);
+        assert_same_records(&dry, &real);
+        assert_eq!(real.last().unwrap()["data"]["files_copied"], 2);
+
+        let rm = env!("CARGO_BIN_EXE_ai-rm");
+        let dry = run(rm, &journal, &["-r", "-v", "--dry-run", dest]);
+        assert_eq!(fs::read_to_string(Path::new(dest).join("nested/b.txt")).unwrap(), "beta\n");
+        let real = run(rm, &journal, &["-r", "-v", dest]);
+        assert_same_records(&dry, &real);
+        assert!(!Path::new(dest).exists());
+    }
+}
+
+mod sandbox_tool {
+    use serde_json::Value;
+    use std::fs;
+    use std::path::Path;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn run(bin: &str, sandbox: &str, sandbox_write: &str, journal: &Path, args: &[&str]) -> (i32, Vec<Value>) {
+        let output = Command::new(bin)
+            .args(args)
+            .env("AI_COREUTILS_SANDBOX", sandbox)
+            .env("AI_COREUTILS_SANDBOX_WRITE", sandbox_write)
+            .env("AI_COREUTILS_JOURNAL_DIR", journal)
+            .output()
+            .unwrap();
+        let records = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect();
+        (output.status.code().unwrap(), records)
+    }
+
+    fn denied(records: &[Value]) -> Vec<&str> {
+        records
+            .iter()
+            .filter(|record| record["code"] == "POLICY_DENIED")
+            .map(|record| record["message"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_sandbox_denies_reads_and_writes() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = temp_dir.path().join("journal");
+        let work = temp_dir.path().join("work");
+        let out = temp_dir.path().join("out");
+        fs::create_dir_all(work.join("secret")).unwrap();
+        fs::create_dir_all(&out).unwrap();
+        fs::write(work.join("notes.txt"), "needle\n").unwrap();
+        fs::write(work.join("secret/key"), "needle\n").unwrap();
+        fs::write(temp_dir.path().join("outside.txt"), "needle\n").unwrap();
+
+        let sandbox = format!("{},!{}", work.display(), work.join("secret").display());
+        let write = out.to_str().unwrap();
+        let notes = work.join("notes.txt");
+        let notes = notes.to_str().unwrap();
+
+        // Reads outside the allowlist stop the command before any output
+        let cat = env!("CARGO_BIN_EXE_ai-cat");
+        let outside = temp_dir.path().join("work/../outside.txt");
+        let (code, records) = run(cat, &sandbox, write, &journal, &[outside.to_str().unwrap()]);
+        assert_eq!(code, 1);
+        assert_eq!(records.len(), 1);
+        assert!(denied(&records)[0].contains("denies read access"));
+
+        // Denied entries are reported and skipped while recursing
+        let grep = env!("CARGO_BIN_EXE_ai-grep");
+        let (_, records) = run(grep, &sandbox, write, &journal, &["-r", "needle", work.to_str().unwrap()]);
+        assert_eq!(denied(&records).len(), 1);
+        let matched: Vec<&Value> = records.iter().filter(|r| r["type"] == "match").collect();
+        assert!(!matched.is_empty());
+        assert!(matched.iter().all(|r| r["file"] == notes));
+
+        // Writes are limited to the write allowlist
+        let cp = env!("CARGO_BIN_EXE_ai-cp");
+        let copy = work.join("copy.txt");
+        let (code, records) = run(cp, &sandbox, write, &journal, &[notes, copy.to_str().unwrap()]);
+        assert_eq!(code, 1);
+        assert!(denied(&records)[0].contains("denies write access"));
+        assert!(!copy.exists());
+
+        let copy = out.join("copy.txt");
+        let (code, _) = run(cp, &sandbox, write, &journal, &[notes, copy.to_str().unwrap()]);
+        assert_eq!(code, 0);
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "needle\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_links_outside_the_sandbox_cannot_be_removed_or_renamed() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = temp_dir.path().join("journal");
+        let allowed = temp_dir.path().join("allowed");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(&allowed).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(allowed.join("x"), "x\n").unwrap();
+        let link = outside.join("link");
+        std::os::unix::fs::symlink(allowed.join("x"), &link).unwrap();
+        let sandbox = allowed.to_str().unwrap();
+
+        let rm = env!("CARGO_BIN_EXE_ai-rm");
+        let (code, records) = run(rm, sandbox, "", &journal, &[link.to_str().unwrap()]);
+        assert_eq!(code, 1);
+        assert!(denied(&records)[0].contains("denies write access"));
+        assert!(link.symlink_metadata().is_ok());
+
+        let mv = env!("CARGO_BIN_EXE_ai-mv");
+        let moved = allowed.join("moved");
+        let (code, records) = run(mv, sandbox, "", &journal, &[link.to_str().unwrap(), moved.to_str().unwrap()]);
+        assert_eq!(code, 1);
+        assert!(denied(&records)[0].contains("denies write access"));
+        assert!(link.symlink_metadata().is_ok());
+        assert!(moved.symlink_metadata().is_err());
+
+        // A link inside the sandbox can be removed wherever it points
+        let inside = allowed.join("out");
+        fs::write(outside.join("elsewhere"), "y\n").unwrap();
+        std::os::unix::fs::symlink(outside.join("elsewhere"), &inside).unwrap();
+        let (code, _) = run(rm, sandbox, "", &journal, &[inside.to_str().unwrap()]);
+        assert_eq!(code, 0);
+        assert!(inside.symlink_metadata().is_err());
+        assert!(outside.join("elsewhere").exists());
+    }
+}
+
+mod audit_tool {
+    use super::common::records;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_every_invocation_is_appended() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = temp_dir.path().join("audit.jsonl");
+        let source = temp_dir.path().join("a.txt");
+        let dest = temp_dir.path().join("b.txt");
+        fs::write(&source, "hello\n").unwrap();
+
+        let run = |bin: &str, args: &[&str]| {
+            Command::new(bin)
+                .args(args)
+                .env("AI_COREUTILS_AUDIT_LOG", &log)
+                .env("AI_COREUTILS_JOURNAL", "0")
+                .output()
+                .unwrap()
+        };
+        let (source, dest) = (source.to_str().unwrap(), dest.to_str().unwrap());
+        assert!(run(env!("CARGO_BIN_EXE_ai-cp"), &[source, dest]).status.success());
+        // Failing runs are recorded too
+        assert!(!run(env!("CARGO_BIN_EXE_ai-undo"), &[]).status.success());
+
+        let records = records(&fs::read(&log).unwrap());
+        assert_eq!(records.len(), 2);
+
+        let cp = &records[0];
+        assert_eq!(cp["type"], "invocation");
+        assert_eq!(cp["tool"], "ai-cp");
+        assert_eq!(cp["argv"][1], source);
+        assert_eq!(cp["bytes_read"], 6);
+        assert_eq!(cp["bytes_written"], 6);
+        let files = cp["files"].as_array().unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().any(|f| f["path"] == dest && f["write"] == true));
+        assert!(cp["start"].as_str().unwrap() <= cp["end"].as_str().unwrap());
+
+        assert_eq!(records[1]["tool"], "ai-undo");
+    }
+}
+
+mod limits_tool {
+    use serde_json::Value;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    /// Records without their invocation id, so runs compare equal
+    fn records(stdout: &[u8]) -> Vec<Value> {
+        String::from_utf8_lossy(stdout)
+            .lines()
+            .map(|line| {
+                let mut record: Value = serde_json::from_str(line).unwrap();
+                record.as_object_mut().unwrap().remove("invocation_id");
+                record
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_max_records_stops_recursive_grep() {
+        let temp_dir = TempDir::new().unwrap();
+        for i in 0..5 {
+            fs::write(temp_dir.path().join(format!("{}.txt", i)), "needle\nneedle\n").unwrap();
+        }
+
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-grep"))
+            .args(["-r", "--max-records", "3", "needle"])
+            .arg(temp_dir.path())
+            .output()
+            .unwrap();
+        let records = records(&output.stdout);
+
+        assert_eq!(records.len(), 4);
+        assert!(records[..3].iter().all(|r| r["type"] == "match"));
+        assert_eq!(records[3]["type"], "limit_exceeded");
+        assert_eq!(records[3]["limit"], "max_records");
+        assert_eq!(records[3]["max"], 3);
+    }
+
+    #[test]
+    fn test_resume_pages_through_grep() {
+        let temp_dir = TempDir::new().unwrap();
+        for i in 0..4 {
+            fs::write(temp_dir.path().join(format!("{}.txt", i)), "needle 1\nneedle 2\nneedle 3\n").unwrap();
+        }
+        let grep = |extra: &[&str]| {
+            let output = Command::new(env!("CARGO_BIN_EXE_ai-grep"))
+                .args(["-r", "-n", "needle"])
+                .arg(temp_dir.path())
+                .args(extra)
+                .env("AI_COREUTILS_JSONL_TIMESTAMPS", "0")
+                .env("AI_COREUTILS_JSONL_TELEMETRY", "0")
+                .output()
+                .unwrap();
+            records(&output.stdout)
+        };
+        let full = grep(&[]);
+        assert_eq!(full.len(), 13);
+
+        let mut paged = Vec::new();
+        let mut token: Option<String> = None;
+        for _ in 0..10 {
+            let mut args = vec!["--max-records", "5"];
+            if let Some(token) = &token {
+                args.extend(["--resume", token]);
+            }
+            let mut page = grep(&args);
+            token = match page.last() {
+                Some(last) if last["type"] == "limit_exceeded" => last["resume"].as_str().map(String::from),
+                _ => None,
+            };
+            if token.is_some() {
+                page.pop();
+            }
+            paged.extend(page);
+            if token.is_none() {
+                break;
+            }
+        }
+        assert_eq!(paged, full);
+
+        // A token only continues the command it came from
+        let first = grep(&["--max-records", "5"]);
+        let token = first.last().unwrap()["resume"].as_str().unwrap().to_string();
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-grep"))
+            .args(["-r", "haystack", "--resume", &token])
+            .arg(temp_dir.path())
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        assert_eq!(records(&output.stdout)[0]["code"], "INVALID_RESUME_TOKEN");
+    }
+
+    #[test]
+    fn test_ordered_async_grep() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["b.txt", "a.txt", "c.txt"] {
+            fs::write(temp_dir.path().join(name), "needle\nhay\nneedle\n").unwrap();
+        }
+        let grep = || {
+            let output = Command::new(env!("CARGO_BIN_EXE_ai-grep"))
+                .args(["-r", "-n", "-a", "--ordered", "needle"])
+                .arg(temp_dir.path())
+                .env("AI_COREUTILS_JSONL_TIMESTAMPS", "0")
+                .env("AI_COREUTILS_JSONL_TELEMETRY", "0")
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            output.stdout
+        };
+
+        let first = grep();
+        let records = records(&first);
+        let keys: Vec<(String, u64)> = records
+            .iter()
+            .filter(|r| r["type"] == "match")
+            .map(|r| (r["file"].as_str().unwrap().to_string(), r["line_number"].as_u64().unwrap()))
+            .collect();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys.len(), 6);
+        assert_eq!(keys, sorted);
+        assert_eq!(self::records(&grep()), records);
+    }
+
+    #[test]
+    fn test_max_bytes_stops_before_reading_in() {
+        // Sparse, so it takes no space; reading it in would take 64 GiB
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("huge.bin");
+        fs::File::create(&path).unwrap().set_len(1 << 36).unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-cat"))
+            .args(["--no-mmap", "--max-bytes", "1M"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        let records = records(&output.stdout);
+        assert_eq!(records.last().unwrap()["limit"], "max_bytes");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_max_bytes_counts_stdin() {
+        use std::io::Write;
+        use std::process::Stdio;
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_ai-nl"))
+            .args(["--max-bytes", "8", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(b"one\ntwo\nthree\n").unwrap();
+        let output = child.wait_with_output().unwrap();
+
+        let records = records(&output.stdout);
+        assert_eq!(records.last().unwrap()["limit"], "max_bytes");
+        assert!(records.iter().all(|r| r["data"]["type"] != "numbered_line"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_max_bytes_counts_piped_wc_and_tail() {
+        use std::io::Write;
+        use std::process::Stdio;
+
+        for bin in [env!("CARGO_BIN_EXE_ai-wc"), env!("CARGO_BIN_EXE_ai-tail")] {
+            let mut child = Command::new(bin)
+                .args(["--max-bytes", "8"])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+            child.stdin.take().unwrap().write_all(b"one\ntwo\nthree\n").unwrap();
+            let output = child.wait_with_output().unwrap();
+
+            assert!(!output.status.success());
+            let records = records(&output.stdout);
+            assert_eq!(records.len(), 1, "{}", bin);
+            assert_eq!(records[0]["type"], "limit_exceeded");
+            assert_eq!(records[0]["limit"], "max_bytes");
+        }
+    }
+
+    #[test]
+    fn test_max_bytes_stops_reading_a_device() {
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-cat"))
+            .args(["--max-bytes", "1M", "/dev/zero"])
+            .output()
+            .unwrap();
+        let records = records(&output.stdout);
+        assert_eq!(records.last().unwrap()["limit"], "max_bytes");
+    }
+
+    #[test]
+    fn test_max_files_and_bytes_stop_cat() {
+        let temp_dir = TempDir::new().unwrap();
+        let files: Vec<_> = (0..3)
+            .map(|i| {
+                let path = temp_dir.path().join(format!("{}.txt", i));
+                fs::write(&path, "0123456789\n").unwrap();
+                path
+            })
+            .collect();
+
+        let cat = |args: &[&str], env: Option<(&str, &str)>| {
+            let mut command = Command::new(env!("CARGO_BIN_EXE_ai-cat"));
+            command.args(args).args(&files);
+            if let Some((key, value)) = env {
+                command.env(key, value);
+            }
+            records(&command.output().unwrap().stdout)
+        };
+
+        let records = cat(&["--max-files", "2"], None);
+        let last = records.last().unwrap();
+        assert_eq!(last["type"], "limit_exceeded");
+        assert_eq!(last["limit"], "max_files");
+        let read = |records: &[Value]| records.iter().filter(|r| r["data"]["type"] == "file_content").count();
+        assert_eq!(read(&records), 2);
+
+        // The environment sets the limit for every utility
+        let records = cat(&[], Some(("AI_COREUTILS_MAX_BYTES", "15")));
+        assert_eq!(records.last().unwrap()["limit"], "max_bytes");
+        assert_eq!(read(&records), 1);
+    }
+}
+
+mod telemetry_tool {
+    use serde_json::Value;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn summary(temp_dir: &TempDir, env: &[(&str, &str)]) -> Value {
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-cp"))
+            .arg(temp_dir.path().join("a.bin"))
+            .arg(temp_dir.path().join("b.bin"))
+            .env("AI_COREUTILS_JOURNAL", "0")
+            .envs(env.iter().copied())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let last = stdout.lines().last().unwrap();
+        serde_json::from_str::<Value>(last).unwrap()["data"].clone()
+    }
+
+    #[test]
+    fn test_summary_carries_telemetry() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.bin"), vec![7u8; 1000]).unwrap();
+
+        let data = summary(&temp_dir, &[("AI_COREUTILS_SIMD", "0")]);
+        assert_eq!(data["type"], "copy_summary");
+        let telemetry = &data["telemetry"];
+        assert_eq!(telemetry["bytes_read"], 1000);
+        assert_eq!(telemetry["bytes_written"], 1000);
+        assert_eq!(telemetry["simd"], "scalar");
+        assert!(telemetry["wall_ms"].is_u64());
+        #[cfg(unix)]
+        assert!(telemetry["peak_rss_bytes"].as_u64().unwrap() > 0);
+
+        let data = summary(&temp_dir, &[("AI_COREUTILS_JSONL_TELEMETRY", "0")]);
+        assert!(data.get("telemetry").is_none());
+    }
+}
+
+mod warnings_tool {
+    use serde_json::Value;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn warnings(bin: &str, args: &[&str], dir: &TempDir) -> Vec<Value> {
+        let output = Command::new(bin).args(args).arg(dir.path()).output().unwrap();
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .filter(|record| record["type"] == "warning")
+            .collect()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_skips_unreadable_entries_with_a_warning() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "needle\n").unwrap();
+        std::os::unix::fs::symlink("/nonexistent/target", temp_dir.path().join("dangling")).unwrap();
+
+        let find = env!("CARGO_BIN_EXE_ai-find");
+        let found = warnings(find, &["--follow-symlinks", "always"], &temp_dir);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0]["code"], "UNREADABLE_ENTRY");
+        assert_eq!(found[0]["level"], "warn");
+        assert!(found[0]["path"].as_str().unwrap().ends_with("dangling"));
+
+        assert!(warnings(find, &["--follow-symlinks", "always", "--log-level", "error"], &temp_dir).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_utf8_and_binary_skips() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("latin1.txt");
+        fs::write(&file, b"caf\xe9\n").unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_ai-cat")).arg(&file).output().unwrap();
+        let first: Value = serde_json::from_slice(output.stdout.split(|&b| b == b'\n').next().unwrap()).unwrap();
+        assert_eq!(first["type"], "warning");
+        assert_eq!(first["code"], "INVALID_UTF8");
+
+        // Binary skips are routine, so they are only written at info level
+        let blob = temp_dir.path().join("blob.bin");
+        fs::write(&blob, b"old\0").unwrap();
+        let sd = |args: &[&str]| {
+            let output = Command::new(env!("CARGO_BIN_EXE_ai-sd")).args(args).args(["old", "new"]).arg(&blob).output().unwrap();
+            String::from_utf8_lossy(&output.stdout).contains("BINARY_SKIPPED")
+        };
+        assert!(!sd(&[]));
+        assert!(sd(&["--log-level", "info"]));
+    }
+}
+
+/// Fixtures shared with the Python (`python/tests/test_parity.py`) and
+/// Node.js (`nodejs/test/test.js`) suites, so every binding is held to the
+/// same results as the library
+mod parity {
+    use ai_coreutils::checksum::Manifest;
+    use ai_coreutils::fs_utils::SymlinkPolicy;
+    use ai_coreutils::hash::{Digest, HashAlgorithm};
+    use ai_coreutils::snapshot::TreeSnapshot;
+    use ai_coreutils::{watch, PatternDetector, SimdTextProcessor};
+    use serde_json::Value;
+    use std::collections::BTreeSet;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    fn cases(category: &str) -> Vec<Value> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/parity/cases.json");
+        let cases: Value = serde_json::from_slice(&fs::read(path).unwrap()).unwrap();
+        cases[category].as_array().unwrap().clone()
+    }
+
+    /// Make the files under `root` exactly `files` (relative path to contents)
+    ///
+    /// Files that already have their contents are left alone, so their mtime
+    /// does not change.
+    fn write_tree(root: &Path, files: &Value) -> Vec<PathBuf> {
+        let files = files.as_object().unwrap();
+        for entry in walkdir::WalkDir::new(root).into_iter().filter_map(Result::ok) {
+            let relative = entry.path().strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/");
+            if entry.file_type().is_file() && !files.contains_key(&relative) {
+                fs::remove_file(entry.path()).unwrap();
+            }
+        }
+        files
+            .iter()
+            .map(|(relative, contents)| {
+                let path = root.join(relative);
+                let contents = contents.as_str().unwrap();
+                if fs::read(&path).ok().as_deref() != Some(contents.as_bytes()) {
+                    fs::create_dir_all(path.parent().unwrap()).unwrap();
+                    fs::write(&path, contents).unwrap();
+                }
+                path
+            })
+            .collect()
+    }
+
+    fn expected_pairs(case: &Value, key: &str) -> Vec<(String, String)> {
+        case[key]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|pair| (pair[0].as_str().unwrap().to_string(), pair[1].as_str().unwrap().to_string()))
+            .collect()
+    }
+
+    fn relative(root: &Path, path: &Path) -> String {
+        path.strip_prefix(root).unwrap_or(path).to_string_lossy().replace('\\', "/")
+    }
+
+    #[test]
+    fn test_hash() {
+        for case in cases("hash") {
+            let algorithm: HashAlgorithm = case["algorithm"].as_str().unwrap().parse().unwrap();
+            let input = case["input"].as_str().unwrap();
+            assert_eq!(algorithm.hex_digest(input.as_bytes()), case["hex"], "{}", case);
+        }
+    }
+
+    #[test]
+    fn test_text_metrics() {
+        for case in cases("text_metrics") {
+            let metrics = SimdTextProcessor::new().analyze(case["input"].as_str().unwrap().as_bytes());
+            assert_eq!(metrics.lines as u64, case["lines"].as_u64().unwrap(), "{}", case);
+            assert_eq!(metrics.words as u64, case["words"].as_u64().unwrap(), "{}", case);
+            assert_eq!(metrics.bytes as u64, case["bytes"].as_u64().unwrap(), "{}", case);
+        }
+    }
+
+    #[test]
+    fn test_patterns() {
+        let detector = PatternDetector::new().unwrap();
+        for case in cases("patterns") {
+            let found: BTreeSet<String> = detector
+                .detect_patterns(case["input"].as_str().unwrap())
+                .iter()
+                .map(|m| format!("{:?}", m.pattern_type))
+                .collect();
+            let expected: BTreeSet<String> =
+                case["types"].as_array().unwrap().iter().map(|t| t.as_str().unwrap().to_string()).collect();
+            assert_eq!(found, expected, "{}", case);
+        }
+    }
+
+    #[test]
+    fn test_trees() {
+        for case in cases("trees") {
+            let temp_dir = TempDir::new().unwrap();
+            let root = temp_dir.path();
+            let before = write_tree(root, &case["before"]);
+
+            let mut manifest = Manifest::new(HashAlgorithm::default());
+            for path in &before {
+                manifest.insert(path).unwrap();
+            }
+            let (snapshot, _) = TreeSnapshot::create(root, HashAlgorithm::default(), &[]).unwrap();
+            let (watched, _) = watch::Snapshot::take(&[root.to_path_buf()], SymlinkPolicy::default());
+
+            let after = write_tree(root, &case["after"]);
+            let (later, _) = TreeSnapshot::create(root, HashAlgorithm::default(), &[]).unwrap();
+            let (rewatched, _) = watch::Snapshot::take(&[root.to_path_buf()], SymlinkPolicy::default());
+
+            let changes = expected_pairs(&case, "changes");
+            let (drifts, errors) = manifest.verify(&after);
+            assert!(errors.is_empty());
+            let drifts: Vec<_> = drifts.iter().map(|d| (relative(root, &d.path), d.kind.as_str().to_string())).collect();
+            assert_eq!(drifts, changes, "manifest: {}", case["name"]);
+
+            let diffs: Vec<_> = snapshot
+                .diff(&later)
+                .unwrap()
+                .iter()
+                .map(|c| (relative(root, &c.path), c.kind.as_str().to_string()))
+                .collect();
+            assert_eq!(diffs, changes, "snapshot: {}", case["name"]);
+
+            let events: Vec<_> = watched
+                .diff(&rewatched)
+                .iter()
+                .map(|e| (relative(root, &e.path), e.kind.as_str().to_string()))
+                .collect();
+            assert_eq!(events, expected_pairs(&case, "events"), "watch: {}", case["name"]);
+        }
+    }
+}
@@ -0,0 +1,31 @@
+/**
+ * AI-Coreutils WebAssembly Example: Scanning Content
+ *
+ * This example demonstrates classifying content and detecting patterns with
+ * no native binaries and no file system access for the module.
+ */
+
+import { load } from 'ai-coreutils-wasm';
+
+const aic = await load();
+console.log(`ai-coreutils ${aic.info.version} (${aic.info.simd})`);
+
+const source = 'fn main() {\n    println!("mail admin@example.com or see https://example.com");\n}\n';
+
+// Classify content by name and bytes
+const classification = aic.classify('main.rs', new TextEncoder().encode(source));
+console.log(`${classification.file_type} (${classification.mime_type}), binary: ${classification.is_binary}`);
+
+// Detect patterns
+for (const m of aic.detectPatterns(source)) {
+  console.log(`${m.pattern_type}: ${m.matched_text} at ${m.start}`);
+}
+
+// Full analysis with statistics
+const analysis = aic.analyzeContent(source, 'main.rs');
+console.log(`${analysis.total_patterns} patterns, entropy ${analysis.statistics.entropy.toFixed(2)}`);
+
+// Metrics and hashing
+const metrics = aic.textMetrics(source);
+console.log(`${metrics.lines} lines, ${metrics.words} words, ${metrics.bytes} bytes`);
+console.log(`xxh3: ${aic.hash(source)}`);
@@ -0,0 +1,108 @@
+/// <reference lib="dom" />
+
+/** Bytes accepted by the module: copied into its memory for each call */
+export type Input = string | ArrayBuffer | ArrayBufferView;
+
+/** Library version and SIMD code path */
+export interface Info {
+  version: string;
+  /** `simd128` when built with `+simd128`, otherwise `scalar` */
+  simd: string;
+}
+
+/** One detected pattern */
+export interface PatternMatch {
+  pattern: string;
+  matched_text: string;
+  /** Byte offsets into the UTF-8 text */
+  start: number;
+  end: number;
+  confidence: number;
+  /** `Email`, `Url`, `IpAddress`, `Date`, `StackTrace` and so on */
+  pattern_type: string;
+  /** ISO 8601 form of a `Date` match */
+  parsed_value?: string;
+  stack_trace?: unknown;
+}
+
+/** Statistics from `analyzeContent` */
+export interface TextStatistics {
+  characters: number;
+  bytes: number;
+  lines: number;
+  words: number;
+  avg_line_length: number;
+  max_line_length: number;
+  whitespace_ratio: number;
+  entropy: number;
+}
+
+/** Result of `analyzeContent` */
+export interface ContentAnalysis {
+  path: string;
+  total_patterns: number;
+  patterns_by_type: Record<string, number>;
+  matches: PatternMatch[];
+  statistics: TextStatistics;
+  issues: string[];
+}
+
+/** Result of `classify` */
+export interface FileClassification {
+  path: string;
+  file_type: string;
+  confidence: number;
+  encoding: string;
+  mime_type: string;
+  is_binary: boolean;
+  language: string | null;
+}
+
+/** Result of `textMetrics` */
+export interface TextMetrics {
+  lines: number;
+  words: number;
+  bytes: number;
+  /** UTF-8 characters */
+  chars: number;
+  /** Length in bytes of the longest line, not counting its newline */
+  max_line_length: number;
+  /** Shannon entropy in bits per byte */
+  entropy: number;
+}
+
+/** Thrown for error records; `code` is the record's code, such as `PATTERN_ERROR` */
+export interface AiCoreutilsError extends Error {
+  code: string;
+}
+
+export interface LoadOptions {
+  /**
+   * WASI implementation, such as a `node:wasi` `WASI` instance or an object of
+   * `wasi_snapshot_preview1` functions. The default gives the module clocks,
+   * randomness and console output only.
+   */
+  wasi?: { wasiImport?: WebAssembly.ModuleImports; initialize?(instance: WebAssembly.Instance): void } | WebAssembly.ModuleImports;
+}
+
+/** A loaded module */
+export declare class AiCoreutils {
+  constructor(instance: WebAssembly.Instance);
+  get info(): Info;
+  detectPatterns(text: string): PatternMatch[];
+  analyzeContent(text: string, path: string): ContentAnalysis;
+  classify(path: string, content: Input): FileClassification;
+  textMetrics(data: Input): TextMetrics;
+  /** Hex digest with `crc32`, `xxh64` or `xxh3` (default) */
+  hash(data: Input, algorithm?: string): string;
+}
+
+/**
+ * Load the module from a URL (relative ones resolve against the package), a
+ * `Response`, its bytes or a compiled module; defaults to the bundled
+ * `ai_coreutils.wasm`
+ */
+export declare function load(
+  source?: string | URL | Response | BufferSource | WebAssembly.Module,
+  options?: LoadOptions
+): Promise<AiCoreutils>;
@@ -0,0 +1,198 @@
+/**
+ * AI-Coreutils for WebAssembly
+ *
+ * Loads the wasm32-wasip1 build of the library (`src/wasm.rs`) and wraps its
+ * C ABI. Inputs are copied into the module's memory. Each call returns one
+ * JSONL record: its `data` is returned, or its `message` is thrown as an
+ * Error whose `code` is the record's code.
+ */
+
+const encoder = new TextEncoder();
+const decoder = new TextDecoder();
+
+/** WASI errno returned by imports the library never needs */
+const ENOSYS = 52;
+
+/** Bytes before the record in a result buffer */
+const LEN_PREFIX = 4;
+
+/**
+ * Just enough of WASI preview 1 for the library: clocks, randomness, and
+ * stdout/stderr for panic messages. Anything else reports ENOSYS.
+ */
+function minimalWasi(memory) {
+  const view = () => new DataView(memory().buffer);
+  const empty = (countPtr, sizePtr) => {
+    view().setUint32(countPtr, 0, true);
+    view().setUint32(sizePtr, 0, true);
+    return 0;
+  };
+
+  const imports = {
+    args_sizes_get: empty,
+    args_get: () => 0,
+    environ_sizes_get: empty,
+    environ_get: () => 0,
+    clock_time_get(id, _precision, out) {
+      // Clock 0 is real time; the others are monotonic
+      const ns = id === 0
+        ? BigInt(Date.now()) * 1000000n
+        : BigInt(Math.round(performance.now() * 1e6));
+      view().setBigUint64(out, ns, true);
+      return 0;
+    },
+    random_get(ptr, len) {
+      // getRandomValues fills at most 64 KiB per call
+      for (let offset = 0; offset < len; offset += 65536) {
+        crypto.getRandomValues(new Uint8Array(memory().buffer, ptr + offset, Math.min(65536, len - offset)));
+      }
+      return 0;
+    },
+    fd_write(fd, iovs, iovsLen, nwritten) {
+      let text = '';
+      let written = 0;
+      for (let i = 0; i < iovsLen; i++) {
+        const ptr = view().getUint32(iovs + i * 8, true);
+        const len = view().getUint32(iovs + i * 8 + 4, true);
+        text += decoder.decode(new Uint8Array(memory().buffer, ptr, len));
+        written += len;
+      }
+      (fd === 2 ? console.error : console.log)(text.replace(/\n$/, ''));
+      view().setUint32(nwritten, written, true);
+      return 0;
+    },
+    proc_exit(code) {
+      throw new Error(`ai-coreutils exited with code ${code}`);
+    },
+  };
+
+  return new Proxy(imports, { get: (target, name) => target[name] ?? (() => ENOSYS) });
+}
+
+/** UTF-8 of a string, or the bytes of an ArrayBuffer or view */
+function toBytes(input) {
+  if (typeof input === 'string') {
+    return encoder.encode(input);
+  }
+  if (input instanceof ArrayBuffer) {
+    return new Uint8Array(input);
+  }
+  return new Uint8Array(input.buffer, input.byteOffset, input.byteLength);
+}
+
+/** Compile the module from a URL, Response, bytes or compiled module */
+async function compile(source) {
+  if (source instanceof WebAssembly.Module) {
+    return source;
+  }
+  if (source instanceof ArrayBuffer || ArrayBuffer.isView(source)) {
+    return WebAssembly.compile(source);
+  }
+  if (typeof Response !== 'undefined' && source instanceof Response) {
+    return WebAssembly.compileStreaming(source);
+  }
+
+  const url = new URL(source, import.meta.url);
+  if (url.protocol === 'file:') {
+    const { readFile } = await import('node:fs/promises');
+    return WebAssembly.compile(await readFile(url));
+  }
+  return WebAssembly.compileStreaming(fetch(url));
+}
+
+/**
+ * Load the module
+ *
+ * `source` is a URL (relative ones resolve against this file), a `Response`,
+ * the module's bytes or a compiled `WebAssembly.Module`; it defaults to
+ * `ai_coreutils.wasm` next to this file. Pass
+ * `options.wasi` to use another WASI implementation, such as a `node:wasi`
+ * `WASI` instance; by default a minimal one is provided, so no file system
+ * or environment is visible to the module.
+ */
+export async function load(source = './ai_coreutils.wasm', options = {}) {
+  const module = await compile(source);
+  let instance;
+  const wasi = options.wasi;
+  const imports = wasi ? (wasi.wasiImport ?? wasi) : minimalWasi(() => instance.exports.memory);
+
+  instance = await WebAssembly.instantiate(module, { wasi_snapshot_preview1: imports });
+  if (typeof wasi?.initialize === 'function') {
+    wasi.initialize(instance);
+  } else {
+    instance.exports._initialize?.();
+  }
+  return new AiCoreutils(instance);
+}
+
+/** A loaded module */
+export class AiCoreutils {
+  #exports;
+
+  constructor(instance) {
+    this.#exports = instance.exports;
+  }
+
+  /** Library version and SIMD code path (`simd128` or `scalar`) */
+  get info() {
+    return this.#call('aic_info');
+  }
+
+  /** Patterns (emails, URLs, secrets and so on) in text */
+  detectPatterns(text) {
+    return this.#call('aic_detect_patterns', text);
+  }
+
+  /** Patterns, statistics and issues in text read from `path` */
+  analyzeContent(text, path) {
+    return this.#call('aic_analyze_content', text, path);
+  }
+
+  /** File type, MIME type and encoding of `content`, named `path` */
+  classify(path, content) {
+    return this.#call('aic_classify', path, content);
+  }
+
+  /** Line, word and byte counts, and Shannon entropy */
+  textMetrics(data) {
+    return this.#call('aic_text_metrics', data);
+  }
+
+  /** Hex digest with `crc32`, `xxh64` or `xxh3` (default) */
+  hash(data, algorithm = 'xxh3') {
+    return this.#call('aic_hash', algorithm, data).hex;
+  }
+
+  #call(name, ...inputs) {
+    const exports = this.#exports;
+    const buffers = [];
+    try {
+      for (const input of inputs) {
+        const bytes = toBytes(input);
+        const ptr = exports.aic_alloc(bytes.length) >>> 0;
+        buffers.push([ptr, bytes.length]);
+        new Uint8Array(exports.memory.buffer, ptr, bytes.length).set(bytes);
+      }
+
+      const result = exports[name](...buffers.flat()) >>> 0;
+      let record;
+      try {
+        const len = new DataView(exports.memory.buffer).getUint32(result, true);
+        record = JSON.parse(decoder.decode(new Uint8Array(exports.memory.buffer, result + LEN_PREFIX, len)));
+      } finally {
+        exports.aic_result_free(result);
+      }
+
+      if (record.type === 'error') {
+        const error = new Error(record.message);
+        error.code = record.code;
+        throw error;
+      }
+      return record.data;
+    } finally {
+      for (const [ptr, len] of buffers) {
+        exports.aic_free(ptr, len);
+      }
+    }
+  }
+}
@@ -0,0 +1,63 @@
+/**
+ * Parity tests: the fixtures in tests/fixtures/parity/cases.json run through
+ * the WebAssembly module, as they do through the Rust library
+ * (tests/integration_tests.rs, mod parity) and the Python and Node.js
+ * bindings. The module has no file system access, so the tree cases are
+ * skipped.
+ *
+ * Run with `npm test` after `npm run build`.
+ */
+
+import assert from 'node:assert/strict';
+import { readFile } from 'node:fs/promises';
+import { load } from '../index.js';
+
+const cases = JSON.parse(
+  await readFile(new URL('../../tests/fixtures/parity/cases.json', import.meta.url), 'utf8')
+);
+const aic = await load();
+
+const tests = {
+  hash() {
+    for (const c of cases.hash) {
+      assert.equal(aic.hash(c.input, c.algorithm), c.hex, JSON.stringify(c));
+    }
+  },
+
+  textMetrics() {
+    for (const c of cases.text_metrics) {
+      const metrics = aic.textMetrics(c.input);
+      assert.deepEqual(
+        [metrics.lines, metrics.words, metrics.bytes],
+        [c.lines, c.words, c.bytes],
+        JSON.stringify(c)
+      );
+    }
+  },
+
+  patterns() {
+    for (const c of cases.patterns) {
+      const found = [...new Set(aic.detectPatterns(c.input).map((m) => m.pattern_type))].sort();
+      assert.deepEqual(found, c.types, JSON.stringify(c));
+    }
+  },
+
+  errors() {
+    assert.throws(() => aic.hash('', 'md5'), { code: 'HASH_ERROR' });
+    assert.throws(() => aic.detectPatterns(new Uint8Array([0xff])), { code: 'PATTERN_ERROR' });
+  },
+};
+
+let failed = 0;
+for (const [name, test] of Object.entries(tests)) {
+  try {
+    test();
+    console.log(`ok - ${name}`);
+  } catch (e) {
+    failed += 1;
+    console.log(`not ok - ${name}`);
+    console.error(e);
+  }
+}
+console.log(`# simd: ${aic.info.simd}`);
+process.exitCode = failed ? 1 : 0;